@@ -0,0 +1,119 @@
+use chrono::{Duration, Utc};
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use ratatui::{buffer::Buffer, layout::Rect, widgets::StatefulWidget};
+use std::sync::Arc;
+use upquack::configuration::Settings;
+use upquack::store::{domains_from_json, domains_to_json};
+use upquack::ui::domain_table::{DomainTable, DomainTableState};
+use upquack::ui::domains::{
+    CheckStatus, DomainStatus, HistoryRetention, HttpCode, MonitoredDomain,
+};
+use upquack::ui::history_table::{HistoryTable, HistoryTableState};
+use upquack::ui::latency::LatencySettings;
+use upquack::ui::locale::Locale;
+
+// A full screen on a large terminal.
+const AREA: Rect = Rect::new(0, 0, 200, 60);
+
+// Mostly Up with a short outage every 50 checks, a minute apart.
+fn history(checks: usize) -> Vec<CheckStatus> {
+    let start = Utc::now() - Duration::minutes(checks as i64);
+    let outage: Arc<str> = "connection refused".into();
+    (0..checks)
+        .map(|i| {
+            let down = i % 50 < 3;
+            CheckStatus {
+                http_code: Some(if down {
+                    HttpCode::NetworkError
+                } else {
+                    HttpCode::Ok
+                }),
+                response_time_ms: (!down).then_some(80 + (i % 40) as u64),
+                error_message: down.then(|| Arc::clone(&outage)),
+                ..CheckStatus::new(
+                    start + Duration::minutes(i as i64),
+                    if down {
+                        DomainStatus::Error(Arc::clone(&outage))
+                    } else {
+                        DomainStatus::Up
+                    },
+                )
+            }
+        })
+        .collect()
+}
+
+fn domains(count: usize, checks_each: usize) -> Vec<MonitoredDomain> {
+    (0..count)
+        .map(|i| {
+            let mut domain = MonitoredDomain::new(&format!("https://service-{}.example.com", i));
+            domain.check_history = history(checks_each);
+            domain
+        })
+        .collect()
+}
+
+fn render_domain_table(c: &mut Criterion) {
+    let settings = Settings::default();
+    let domains = domains(1_000, 100);
+
+    c.bench_function("domain table, 1k domains", |b| {
+        b.iter(|| {
+            let mut buf = Buffer::empty(AREA);
+            let mut state = DomainTableState::default();
+            DomainTable::new(black_box(&domains), &settings).render(AREA, &mut buf, &mut state);
+            buf
+        })
+    });
+}
+
+fn render_history_table(c: &mut Criterion) {
+    let mut domain = MonitoredDomain::new("https://example.com");
+    domain.check_history = history(100_000);
+    let table = |collapsed| {
+        HistoryTable::new(
+            &domain,
+            HistoryRetention::default(),
+            LatencySettings::default(),
+            Locale::default(),
+        )
+        .with_collapsed(collapsed)
+    };
+
+    let mut group = c.benchmark_group("history table, 100k checks");
+    group.sample_size(20);
+    for (name, collapsed) in [("every check", false), ("collapsed", true)] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut buf = Buffer::empty(AREA);
+                let mut state = HistoryTableState::default();
+                table(collapsed).render(AREA, &mut buf, &mut state);
+                buf
+            })
+        });
+    }
+    group.finish();
+}
+
+fn store_serialization(c: &mut Criterion) {
+    let domains = domains(1_000, 100);
+    let json = domains_to_json(&domains).expect("Domains serialize");
+
+    let mut group = c.benchmark_group("store, 1k domains with 100 checks");
+    group.sample_size(20);
+    group.bench_function("serialize", |b| {
+        b.iter(|| domains_to_json(black_box(&domains)).expect("Domains serialize"))
+    });
+    group.bench_function("deserialize", |b| {
+        b.iter(|| domains_from_json(black_box(&json)).expect("Domains deserialize"))
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    render_domain_table,
+    render_history_table,
+    store_serialization
+);
+criterion_main!(benches);
@@ -0,0 +1,157 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use utoipa::ToSchema;
+
+// Writes go through here so two requests adding at once don't lose one.
+static ANNOTATIONS_LOCK: Mutex<()> = Mutex::new(());
+
+// A note at a point in time, like a deploy, drawn as a marker on the charts
+// so a change in response times can be matched with what caused it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Annotation {
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+    // Only shown on this domain's charts, on every chart when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl Annotation {
+    pub fn new(text: &str, url: Option<&str>, timestamp: DateTime<Utc>) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("An annotation needs some text".to_string());
+        }
+
+        Ok(Annotation {
+            timestamp,
+            text: text.to_string(),
+            url: url.map(|url| url.trim().to_string()),
+        })
+    }
+
+    // Whether it's shown on the chart of the domain with url, None for any.
+    pub fn applies_to(&self, url: Option<&str>) -> bool {
+        match (&self.url, url) {
+            (Some(own), Some(url)) => own == url,
+            _ => true,
+        }
+    }
+}
+
+// Kept next to the store, so every profile has its own.
+pub fn annotations_path(store_path: &str) -> PathBuf {
+    Path::new(store_path).with_file_name("annotations.json")
+}
+
+// Oldest first. No file yet means no annotations.
+pub fn load_annotations(path: &Path) -> io::Result<Vec<Annotation>> {
+    match fs::read_to_string(path) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn add_annotation(path: &Path, annotation: Annotation) -> io::Result<()> {
+    let _guard = ANNOTATIONS_LOCK.lock().unwrap();
+    let mut annotations = load_annotations(path)?;
+    // Ones given a timestamp can be older than the last.
+    let index = annotations.partition_point(|a| a.timestamp <= annotation.timestamp);
+    annotations.insert(index, annotation);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&annotations)?)
+}
+
+// The annotations in [from, to] shown on the chart of the domain with url,
+// all of them without one.
+pub fn annotations_between(
+    annotations: &[Annotation],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    url: Option<&str>,
+) -> Vec<Annotation> {
+    annotations
+        .iter()
+        .filter(|a| a.timestamp >= from && a.timestamp <= to && a.applies_to(url))
+        .cloned()
+        .collect()
+}
+
+pub fn run_annotate(store_path: &str, text: &str, url: Option<&str>) -> io::Result<()> {
+    let annotation = Annotation::new(text, url, Utc::now())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let path = annotations_path(store_path);
+    add_annotation(&path, annotation.clone())?;
+
+    match &annotation.url {
+        Some(url) => println!(
+            "Annotated {} at {}: {}",
+            url,
+            annotation.timestamp.to_rfc3339(),
+            annotation.text
+        ),
+        None => println!(
+            "Annotated all charts at {}: {}",
+            annotation.timestamp.to_rfc3339(),
+            annotation.text
+        ),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn stores_annotations_in_order_and_filters_them() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let store_path = temp_dir.path().join("domains.json");
+        let path = annotations_path(store_path.to_str().unwrap());
+        assert!(load_annotations(&path).unwrap().is_empty());
+
+        let now = Utc::now();
+        let deploy = Annotation::new(" deployed v1.2 ", None, now).unwrap();
+        let migration = Annotation::new(
+            "migration",
+            Some("https://api.example.com"),
+            now - Duration::hours(1),
+        )
+        .unwrap();
+        add_annotation(&path, deploy.clone()).unwrap();
+        add_annotation(&path, migration.clone()).unwrap();
+        assert!(Annotation::new("  ", None, now).is_err());
+
+        let annotations = load_annotations(&path).unwrap();
+        assert_eq!(annotations, vec![migration.clone(), deploy.clone()]);
+        assert_eq!(deploy.text, "deployed v1.2");
+
+        let earlier = now - Duration::hours(2);
+        assert_eq!(
+            annotations_between(&annotations, earlier, now, Some("https://api.example.com")),
+            vec![migration, deploy.clone()]
+        );
+        assert_eq!(
+            annotations_between(&annotations, earlier, now, Some("https://www.example.com")),
+            vec![deploy.clone()]
+        );
+        assert_eq!(
+            annotations_between(&annotations, earlier, now, None),
+            annotations
+        );
+        assert_eq!(
+            annotations_between(&annotations, now - Duration::minutes(30), now, None),
+            vec![deploy]
+        );
+    }
+}
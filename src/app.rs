@@ -1,4 +1,5 @@
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use chrono::Utc;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::prelude::*;
 use ratatui::{
     DefaultTerminal, Frame,
@@ -7,23 +8,112 @@ use ratatui::{
     style::Stylize,
     symbols::border,
     text::{Line, Text},
-    widgets::{Block, Paragraph, Widget},
+    widgets::{Block, List, ListItem, Paragraph, StatefulWidget, Widget},
 };
 use std::io;
+use std::time::{Duration, Instant};
 
-use crate::ui::domains::DomainScreen;
-use tokio::sync::mpsc;
+use crate::blind_spot;
+use crate::configuration::{Settings, get_configuration};
+use crate::logging::LogHandle;
+use crate::monitor::{MonitorEvent, MonitorHandle, start_monitoring};
+use crate::profile::Profile;
+#[cfg(feature = "server")]
+use crate::server;
+use crate::ui::dashboard::DashboardScreen;
+use crate::ui::domains::{CheckStatus, DomainScreen, DomainStatus, MonitoredDomain, status_label};
+use crate::ui::help::HelpScreen;
+use crate::ui::history::HistoryScreen;
+use crate::ui::incident_overview::IncidentOverviewScreen;
+use crate::ui::incidents::IncidentsScreen;
+use crate::ui::logs::LogScreen;
+use crate::ui::main_menu::{MainMenu, MenuEntry};
+use crate::ui::profiles::ProfileScreen;
+use crate::ui::progress::{InitialChecks, Progress, ProgressPopup};
+use crate::ui::settings::SettingsScreen;
+use crate::ui::soak::SoakScreen;
+use crate::ui::table_view::{in_environment, load_ui_state, ui_state_path};
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+// How long notifications and errors stay at the bottom of the screen.
+const NOTICE_DURATION: Duration = Duration::from_secs(5);
+// How long to wait for input before redrawing anyway, for new check results
+// and the like. Input is handled as soon as it arrives either way.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(16);
+const LOW_RESOURCE_REDRAW_INTERVAL: Duration = Duration::from_millis(250);
 
 #[derive(Debug)]
 pub enum AppEvent {
     SwitchToDomainsScreen,
+    SwitchToProfilesScreen,
+    SwitchToDashboardScreen,
+    SwitchToIncidentOverviewScreen,
+    SwitchToLogsScreen,
+    SwitchToSettingsScreen,
+    SwitchToHelpScreen,
+    SwitchToHistoryScreen(MonitoredDomain),
+    SwitchToIncidentsScreen(MonitoredDomain),
+    StartSoakTest(MonitoredDomain),
+    ProfileSelected(Profile),
+    // Forwarded from the monitor, so screens can keep their own copy of the
+    // domains up to date instead of reading the shared list every frame.
+    CheckCompleted {
+        domain_id: Uuid,
+        check: CheckStatus,
+    },
+    DomainAdded(MonitoredDomain),
+    DomainUpdated(MonitoredDomain),
+    DomainRemoved(Uuid),
+    // Sent when monitor events were missed, replacing the whole list.
+    DomainsReloaded(Vec<MonitoredDomain>),
+    // A check of a running soak test, not recorded in the history.
+    SoakCheckCompleted {
+        domain_id: Uuid,
+        check: CheckStatus,
+    },
+    // Shown to the user for a few seconds, e.g. a domain going down.
+    NotificationSent(String),
+    // A domain changed status, shown like NotificationSent when its
+    // environment is the selected one.
+    StatusNotice {
+        environment: Option<String>,
+        message: String,
+    },
+    // The domain table's environment switcher moved on.
+    EnvironmentSelected(Option<String>),
+    Error(String),
+    // A slow operation moved along, shown in a popup until it's finished.
+    Progress(Progress),
+}
+
+#[derive(Debug)]
+struct Notice {
+    message: String,
+    is_error: bool,
+    shown_at: Instant,
 }
 
 #[derive(Debug)]
 pub struct App {
-    current_screen: Menu,
+    // Screens opened on top of each other, the main menu at the bottom. Esc
+    // goes back to the one below.
+    screens: Vec<Menu>,
+    main_menu: MainMenu,
     exit: bool,
     event_sender: mpsc::UnboundedSender<AppEvent>,
+    settings: Settings,
+    monitor: MonitorHandle,
+    server_task: Option<JoinHandle<()>>,
+    forwarder_task: JoinHandle<()>,
+    notice: Option<Notice>,
+    // The operation in progress and when it started, for the spinner.
+    progress: Option<(Progress, Instant)>,
+    logs: LogHandle,
+    // The domain table's environment pick, which scopes the dashboard, the
+    // incident overview and status notices too.
+    environment: Option<String>,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -32,17 +122,156 @@ enum Menu {
     #[default]
     Main,
     Domains(DomainScreen),
+    History(HistoryScreen),
+    Incidents(IncidentsScreen),
+    Soak(SoakScreen),
+    Profiles(ProfileScreen),
+    Dashboard(DashboardScreen),
+    IncidentOverview(IncidentOverviewScreen),
+    Logs(LogScreen),
+    Settings(SettingsScreen),
+    Help(HelpScreen),
+}
+
+impl Menu {
+    // This screen's part of the breadcrumbs shown in the header.
+    fn breadcrumbs(&self) -> Vec<String> {
+        match self {
+            Menu::Main => Vec::new(),
+            Menu::Domains(_) => vec!["Domains".to_string()],
+            Menu::History(history_screen) => {
+                vec![history_screen.url().to_string(), "History".to_string()]
+            }
+            Menu::Incidents(incidents_screen) => {
+                vec![incidents_screen.url().to_string(), "Incidents".to_string()]
+            }
+            Menu::Soak(soak_screen) => {
+                vec![soak_screen.url().to_string(), "Soak test".to_string()]
+            }
+            Menu::Profiles(_) => vec!["Profiles".to_string()],
+            Menu::Dashboard(_) => vec!["Dashboard".to_string()],
+            Menu::IncidentOverview(_) => vec!["Incidents".to_string()],
+            Menu::Logs(_) => vec!["Logs".to_string()],
+            Menu::Settings(_) => vec!["Settings".to_string()],
+            Menu::Help(_) => vec!["Help".to_string()],
+        }
+    }
 }
 
 impl App {
-    pub fn new(event_sender: mpsc::UnboundedSender<AppEvent>) -> Self {
-        App {
-            current_screen: Menu::Main,
+    pub fn new(
+        event_sender: mpsc::UnboundedSender<AppEvent>,
+        settings: Settings,
+        monitor: MonitorHandle,
+    ) -> Self {
+        let mut app = App {
+            screens: vec![Menu::Main],
+            main_menu: MainMenu::default(),
             exit: false,
+            forwarder_task: forward_monitor_events(&monitor, event_sender.clone()),
             event_sender,
+            settings,
+            monitor,
+            server_task: None,
+            notice: None,
+            progress: None,
+            logs: LogHandle::default(),
+            environment: None,
+        };
+        app.environment = app.saved_environment();
+        app.start_server();
+        app.announce_blind_spots();
+        app
+    }
+
+    // The environment the domain table was left on in this profile.
+    fn saved_environment(&self) -> Option<String> {
+        let path = ui_state_path(&self.settings.profile.store_path());
+        match load_ui_state(&path) {
+            Ok(ui_state) => ui_state.current.environment,
+            Err(e) => {
+                tracing::error!("Failed to load the UI state: {}", e);
+                None
+            }
         }
     }
 
+    // Tells about the time upquack wasn't running, the log has the details.
+    fn announce_blind_spots(&mut self) {
+        if let Some(summary) = blind_spot::summary(&self.monitor.blind_spots) {
+            self.show_notice(summary, false);
+        }
+    }
+
+    // Where the log viewer and the settings screen find the logs.
+    pub fn with_logs(mut self, logs: LogHandle) -> Self {
+        self.logs = logs;
+        self
+    }
+
+    // Serves the API and dashboard alongside the TUI when enabled in the
+    // active profile's settings.
+    #[cfg(feature = "server")]
+    fn start_server(&mut self) {
+        if !self.settings.server.enabled {
+            return;
+        }
+
+        let (settings, monitor) = (self.settings.clone(), self.monitor.clone());
+        self.server_task = Some(tokio::spawn(async move {
+            if let Err(e) = server::serve(settings, monitor).await {
+                tracing::error!("API server stopped: {}", e);
+            }
+        }));
+    }
+
+    #[cfg(not(feature = "server"))]
+    fn start_server(&mut self) {
+        if self.settings.server.enabled {
+            tracing::error!("[server] is enabled, but this build has no API server");
+            self.send(AppEvent::Error(
+                "[server] is enabled, but this build has no API server".to_string(),
+            ));
+        }
+    }
+
+    // Stops monitoring the current profile's domains and starts on the new
+    // profile's, restarting the API server so it serves the new profile too.
+    async fn switch_profile(&mut self, profile: Profile) {
+        let settings = match get_configuration(&profile) {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::error!("Failed to load profile {}: {}", profile.name(), e);
+                self.send(AppEvent::Error(format!(
+                    "Failed to load profile {}: {}",
+                    profile.name(),
+                    e
+                )));
+                return;
+            }
+        };
+
+        self.monitor.stop();
+        self.forwarder_task.abort();
+        if let Some(server_task) = self.server_task.take() {
+            server_task.abort();
+            // Wait for the listener to close so the new server can bind.
+            let _ = server_task.await;
+        }
+
+        // Pausing is for the whole app, not per profile.
+        let paused = self.monitor.is_paused();
+        self.monitor = start_monitoring(&settings, paused).await;
+        self.forwarder_task = forward_monitor_events(&self.monitor, self.event_sender.clone());
+        self.settings = settings;
+        self.environment = self.saved_environment();
+        self.start_server();
+        self.announce_blind_spots();
+        self.screens.truncate(1);
+        self.progress = None;
+        tracing::debug!("Switched to profile {}", profile.name());
+    }
+
     pub async fn run(
         &mut self,
         terminal: &mut DefaultTerminal,
@@ -58,8 +287,81 @@ impl App {
             while let Ok(event) = event_receiver.try_recv() {
                 match event {
                     AppEvent::SwitchToDomainsScreen => {
-                        self.current_screen = Menu::Domains(DomainScreen::init().await);
+                        self.screens.push(Menu::Domains(DomainScreen::new(
+                            self.settings.clone(),
+                            &self.monitor,
+                            self.event_sender.clone(),
+                        )));
+                    }
+                    AppEvent::SwitchToProfilesScreen => {
+                        self.screens.push(Menu::Profiles(ProfileScreen::new(
+                            &self.settings.profile,
+                            self.event_sender.clone(),
+                        )));
+                    }
+                    AppEvent::SwitchToDashboardScreen => {
+                        self.screens.push(Menu::Dashboard(DashboardScreen::new(
+                            self.settings.clone(),
+                            &self.monitor,
+                            self.environment.clone(),
+                        )));
+                    }
+                    AppEvent::SwitchToIncidentOverviewScreen => {
+                        self.screens
+                            .push(Menu::IncidentOverview(IncidentOverviewScreen::new(
+                                self.settings.clone(),
+                                &self.monitor,
+                                self.environment.clone(),
+                                self.event_sender.clone(),
+                            )));
+                    }
+                    AppEvent::SwitchToHelpScreen => {
+                        self.screens.push(Menu::Help(HelpScreen::default()));
+                    }
+                    AppEvent::SwitchToLogsScreen => {
+                        self.screens
+                            .push(Menu::Logs(LogScreen::new(self.logs.clone())));
+                    }
+                    AppEvent::SwitchToSettingsScreen => {
+                        self.screens
+                            .push(Menu::Settings(SettingsScreen::new(self.logs.clone())));
+                    }
+                    AppEvent::SwitchToHistoryScreen(domain) => {
+                        self.screens.push(Menu::History(HistoryScreen::new(
+                            domain,
+                            self.settings.clone(),
+                        )));
+                    }
+                    AppEvent::SwitchToIncidentsScreen(domain) => {
+                        self.screens.push(Menu::Incidents(IncidentsScreen::new(
+                            domain,
+                            self.settings.clone(),
+                            &self.monitor,
+                            self.event_sender.clone(),
+                        )));
+                    }
+                    AppEvent::StartSoakTest(domain) => {
+                        self.screens.push(Menu::Soak(SoakScreen::start(
+                            domain,
+                            self.settings.clone(),
+                            &self.monitor,
+                            self.event_sender.clone(),
+                        )));
+                    }
+                    AppEvent::ProfileSelected(profile) => self.switch_profile(profile).await,
+                    AppEvent::NotificationSent(message) => self.show_notice(message, false),
+                    AppEvent::StatusNotice {
+                        environment,
+                        message,
+                    } => {
+                        if in_environment(self.environment.as_deref(), environment.as_deref()) {
+                            self.show_notice(message, false);
+                        }
                     }
+                    AppEvent::EnvironmentSelected(environment) => self.environment = environment,
+                    AppEvent::Error(message) => self.show_notice(message, true),
+                    AppEvent::Progress(progress) => self.update_progress(progress),
+                    event => self.dispatch_domain_event(&event),
                 }
             }
 
@@ -70,20 +372,163 @@ impl App {
         Ok(())
     }
 
+    // Every open screen keeps its copy of the domains current, not just the
+    // one on top. History screens of removed domains close.
+    fn dispatch_domain_event(&mut self, event: &AppEvent) {
+        self.screens.retain_mut(|screen| match screen {
+            Menu::Domains(domain_screen) => {
+                domain_screen.handle_app_event(event);
+                true
+            }
+            Menu::History(history_screen) => history_screen.handle_app_event(event),
+            Menu::Incidents(incidents_screen) => incidents_screen.handle_app_event(event),
+            Menu::Soak(soak_screen) => soak_screen.handle_app_event(event),
+            Menu::IncidentOverview(overview_screen) => {
+                overview_screen.handle_app_event(event);
+                true
+            }
+            // The dashboard follows the monitor's snapshot instead.
+            Menu::Main
+            | Menu::Profiles(_)
+            | Menu::Dashboard(_)
+            | Menu::Logs(_)
+            | Menu::Settings(_)
+            | Menu::Help(_) => true,
+        });
+    }
+
+    fn current_screen(&mut self) -> &mut Menu {
+        self.screens
+            .last_mut()
+            .expect("The main menu is never popped")
+    }
+
+    fn on_main_menu(&self) -> bool {
+        self.screens.len() == 1
+    }
+
+    fn send(&self, event: AppEvent) {
+        if let Err(e) = self.event_sender.send(event) {
+            tracing::error!("Error sending event: {}", e);
+        }
+    }
+
+    fn show_notice(&mut self, message: String, is_error: bool) {
+        self.notice = Some(Notice {
+            message,
+            is_error,
+            shown_at: Instant::now(),
+        });
+    }
+
+    fn update_progress(&mut self, progress: Progress) {
+        if progress.is_finished() {
+            self.progress = None;
+            return;
+        }
+        let started = match &self.progress {
+            Some((current, started)) if current.operation == progress.operation => *started,
+            _ => Instant::now(),
+        };
+        self.progress = Some((progress, started));
+    }
+
     fn draw(&mut self, frame: &mut Frame) {
-        match &mut self.current_screen {
-            Menu::Main => frame.render_widget(self, frame.area()),
-            Menu::Domains(domain_screen) => frame.render_widget(domain_screen, frame.area()),
+        let breadcrumbs: Vec<String> = self.screens.iter().flat_map(Menu::breadcrumbs).collect();
+        let mut area = frame.area();
+        let mut banners = Vec::new();
+        if self.monitor.is_paused() {
+            banners.push(" MONITORING PAUSED - Ctrl+P to resume ".to_string());
+        }
+        if let Some(unsaved) = self.monitor.watchdog.unsaved_while_failing() {
+            banners.push(format!(
+                " SAVING FAILED - {} results not on disk yet, check disk space and permissions ",
+                unsaved
+            ));
+        }
+        for banner in banners {
+            let [banner_area, screen_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+            let banner = Line::from(banner).centered().white().on_red().bold();
+            frame.render_widget(banner, banner_area);
+            area = screen_area;
+        }
+        if !breadcrumbs.is_empty() {
+            let [header_area, screen_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+            frame.render_widget(breadcrumb_line(&breadcrumbs), header_area);
+            area = screen_area;
+        }
+
+        match self.current_screen() {
+            Menu::Main => frame.render_widget(&mut *self, area),
+            Menu::Domains(domain_screen) => frame.render_widget(domain_screen, area),
+            Menu::History(history_screen) => frame.render_widget(history_screen, area),
+            Menu::Incidents(incidents_screen) => frame.render_widget(incidents_screen, area),
+            Menu::Soak(soak_screen) => frame.render_widget(soak_screen, area),
+            Menu::Profiles(profile_screen) => frame.render_widget(profile_screen, area),
+            Menu::Dashboard(dashboard_screen) => frame.render_widget(dashboard_screen, area),
+            Menu::IncidentOverview(overview_screen) => frame.render_widget(overview_screen, area),
+            Menu::Logs(log_screen) => frame.render_widget(log_screen, area),
+            Menu::Settings(settings_screen) => frame.render_widget(settings_screen, area),
+            Menu::Help(help_screen) => frame.render_widget(help_screen, area),
+        }
+
+        if let Some((progress, started)) = &self.progress {
+            frame.render_widget(ProgressPopup::new(progress, started.elapsed()), area);
+        }
+
+        if self
+            .notice
+            .as_ref()
+            .is_some_and(|notice| notice.shown_at.elapsed() > NOTICE_DURATION)
+        {
+            self.notice = None;
+        }
+        if let Some(notice) = &self.notice {
+            let area = frame.area();
+            let notice_area = Rect::new(area.x, area.bottom().saturating_sub(2), area.width, 1);
+            let line = Line::from(format!(" {} ", notice.message)).centered();
+            let line = if notice.is_error {
+                line.white().on_red()
+            } else {
+                line.black().on_yellow()
+            };
+            frame.render_widget(line, notice_area);
         }
     }
 
     fn handle_input_events(&mut self) -> io::Result<()> {
-        if event::poll(tokio::time::Duration::from_millis(0))? {
+        let redraw_interval = if self.settings.performance.low_resource {
+            LOW_RESOURCE_REDRAW_INTERVAL
+        } else {
+            REDRAW_INTERVAL
+        };
+        if event::poll(redraw_interval)? {
             match event::read()? {
                 Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                    let consumed = match &mut self.current_screen {
-                        Menu::Main => self.handle_global_key_event(key_event),
+                    let consumed = match self.current_screen() {
+                        Menu::Main => self.handle_main_menu_key_event(key_event),
                         Menu::Domains(domain_screen) => domain_screen.handle_key_event(key_event),
+                        Menu::History(history_screen) => history_screen.handle_key_event(key_event),
+                        Menu::Incidents(incidents_screen) => {
+                            incidents_screen.handle_key_event(key_event)
+                        }
+                        Menu::Soak(soak_screen) => soak_screen.handle_key_event(key_event),
+                        Menu::Profiles(profile_screen) => {
+                            profile_screen.handle_key_event(key_event)
+                        }
+                        Menu::Dashboard(dashboard_screen) => {
+                            dashboard_screen.handle_key_event(key_event)
+                        }
+                        Menu::IncidentOverview(overview_screen) => {
+                            overview_screen.handle_key_event(key_event)
+                        }
+                        Menu::Logs(log_screen) => log_screen.handle_key_event(key_event),
+                        Menu::Settings(settings_screen) => {
+                            settings_screen.handle_key_event(key_event)
+                        }
+                        Menu::Help(help_screen) => help_screen.handle_key_event(key_event),
                     };
 
                     if !consumed {
@@ -96,6 +541,21 @@ impl App {
         Ok(())
     }
 
+    fn handle_main_menu_key_event(&mut self, key_event: KeyEvent) -> bool {
+        let Some(entry) = self.main_menu.handle_key_event(key_event) else {
+            // Up/Down moved the selection.
+            return matches!(
+                key_event.code,
+                KeyCode::Up | KeyCode::Down | KeyCode::Char('k') | KeyCode::Char('j')
+            );
+        };
+        match entry.event() {
+            Some(event) => self.send(event),
+            None => self.exit = true,
+        }
+        true
+    }
+
     fn handle_global_key_event(&mut self, key_event: KeyEvent) -> bool {
         match key_event.code {
             KeyCode::Char('q') | KeyCode::Char('Q') => {
@@ -103,29 +563,177 @@ impl App {
                 true
             }
             KeyCode::Esc => {
-                if let Menu::Domains(_) = self.current_screen {
-                    self.current_screen = Menu::Main;
-                    true
-                } else {
+                if self.on_main_menu() {
                     false
+                } else {
+                    self.screens.pop();
+                    true
                 }
             }
-            KeyCode::Char('e') | KeyCode::Char('E') => {
-                // Send an event to the main async loop to switch screens
-                if !matches!(self.current_screen, Menu::Domains(_)) {
-                    if let Err(e) = self.event_sender.send(AppEvent::SwitchToDomainsScreen) {
-                        eprintln!("Error sending event: {}", e);
-                    }
-                    true
+            KeyCode::Char('p') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                let paused = !self.monitor.is_paused();
+                self.monitor.set_paused(paused);
+                tracing::debug!("Monitoring {}", if paused { "paused" } else { "resumed" });
+                let message = if paused {
+                    "Monitoring paused, Ctrl+P resumes it"
                 } else {
-                    false // Already on Domains screen, no action needed
-                }
+                    "Monitoring resumed"
+                };
+                self.show_notice(message.to_string(), false);
+                true
             }
             _ => false,
         }
     }
 }
 
+// "Domains > example.com > History", the screen on top highlighted.
+fn breadcrumb_line(breadcrumbs: &[String]) -> Line<'static> {
+    let mut spans = vec![" ".into()];
+    for (i, crumb) in breadcrumbs.iter().enumerate() {
+        if i + 1 == breadcrumbs.len() {
+            spans.push(crumb.clone().yellow().bold());
+        } else {
+            spans.push(crumb.clone().dark_gray());
+            spans.push(" > ".dark_gray());
+        }
+    }
+    Line::from(spans)
+}
+
+// Turns the monitor's events into AppEvents until the monitor is replaced
+// (the task is aborted then) or the app quits.
+fn forward_monitor_events(
+    monitor: &MonitorHandle,
+    event_sender: mpsc::UnboundedSender<AppEvent>,
+) -> JoinHandle<()> {
+    let mut events = monitor.events.subscribe();
+    let domains = monitor.domains.clone();
+    let started_at = monitor.started_at;
+    let monitor = monitor.clone();
+
+    tokio::spawn(async move {
+        // Started paused nothing is checked, so there's no progress to show.
+        let mut initial_checks = (!monitor.is_paused())
+            .then(|| InitialChecks::new(&domains.lock().unwrap(), started_at));
+        let progress = initial_checks.as_ref().map(InitialChecks::progress);
+        if let Some(progress) = progress.filter(|p| !p.is_finished())
+            && event_sender.send(AppEvent::Progress(progress)).is_err()
+        {
+            return;
+        }
+
+        loop {
+            let app_event = match events.recv().await {
+                Ok(event) => {
+                    if let Some(progress) = initial_checks.as_mut().and_then(|i| i.update(&event)) {
+                        if progress.is_finished() {
+                            initial_checks = None;
+                        }
+                        if event_sender.send(AppEvent::Progress(progress)).is_err() {
+                            break;
+                        }
+                    }
+                    match app_event(event, &domains.lock().unwrap(), monitor.is_paused()) {
+                        Some(app_event) => app_event,
+                        None => continue,
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::debug!("UI missed {} monitor events, reloading domains", missed);
+                    let reloaded = domains.lock().unwrap().clone();
+                    if let Some(checks) = &mut initial_checks {
+                        *checks = InitialChecks::new(&reloaded, started_at);
+                        let progress = checks.progress();
+                        if progress.is_finished() {
+                            initial_checks = None;
+                        }
+                        if event_sender.send(AppEvent::Progress(progress)).is_err() {
+                            break;
+                        }
+                    }
+                    AppEvent::DomainsReloaded(reloaded)
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if event_sender.send(app_event).is_err() {
+                break;
+            }
+        }
+    })
+}
+
+fn app_event(event: MonitorEvent, domains: &[MonitoredDomain], paused: bool) -> Option<AppEvent> {
+    Some(match event {
+        MonitorEvent::CheckCompleted {
+            domain_id, check, ..
+        } => AppEvent::CheckCompleted { domain_id, check },
+        // The first check of a domain isn't news.
+        MonitorEvent::StatusChanged { from: None, .. } => return None,
+        // A check that was under way when monitoring was paused.
+        MonitorEvent::StatusChanged { .. } if paused => return None,
+        // Muted domains change status quietly.
+        MonitorEvent::StatusChanged { domain_id, .. }
+            if domains
+                .iter()
+                .any(|d| d.id == domain_id && d.mute_remaining(Utc::now()).is_some()) =>
+        {
+            return None;
+        }
+        MonitorEvent::StatusChanged {
+            domain_id,
+            url,
+            to,
+            owner,
+            runbook,
+            ..
+        } => AppEvent::StatusNotice {
+            environment: domains
+                .iter()
+                .find(|d| d.id == domain_id)
+                .and_then(|d| d.environment.clone()),
+            message: status_notice(&url, &to, owner.as_deref(), runbook.as_deref()),
+        },
+        MonitorEvent::DomainAdded { domain } => AppEvent::DomainAdded(domain),
+        MonitorEvent::DomainUpdated { domain } => AppEvent::DomainUpdated(domain),
+        MonitorEvent::DomainRemoved { domain_id, .. } => AppEvent::DomainRemoved(domain_id),
+        MonitorEvent::PersistenceFailing { failures, error } => AppEvent::Error(format!(
+            "Saving results failed {} times in a row: {}",
+            failures, error
+        )),
+        MonitorEvent::PersistenceRecovered { saved, dropped, .. } => {
+            AppEvent::NotificationSent(if dropped == 0 {
+                format!("Saving works again, {} held back results saved", saved)
+            } else {
+                format!(
+                    "Saving works again, {} held back results saved, {} dropped by the history's retention",
+                    saved, dropped
+                )
+            })
+        }
+    })
+}
+
+// "<url> is Down (owner: ops) - runbook: <link>", the runbook only for
+// failures since that's when someone has to act on it.
+fn status_notice(
+    url: &str,
+    to: &DomainStatus,
+    owner: Option<&str>,
+    runbook: Option<&str>,
+) -> String {
+    let mut message = format!("{} is {}", url, status_label(to));
+    if let Some(owner) = owner {
+        message.push_str(&format!(" (owner: {})", owner));
+    }
+    if let Some(runbook) = runbook
+        && matches!(to, DomainStatus::Down | DomainStatus::Error(_))
+    {
+        message.push_str(&format!(" - runbook: {}", runbook));
+    }
+    message
+}
+
 impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let upquack_title = "
@@ -137,11 +745,17 @@ impl Widget for &mut App {
  ╚═════╝ ╚═╝     ╚══▀▀═╝  ╚═════╝ ╚═╝  ╚═╝ ╚═════╝╚═╝  ╚═╝
 ";
         let instructions = Line::from(vec![
-            " Quit ".into(),
+            " Navigate ".into(),
+            "<Up/Down> ".blue().bold(),
+            " - ".into(),
+            "Open ".into(),
+            "<Enter> ".blue().bold(),
+            " - ".into(),
+            "Quit ".into(),
             "<Q> ".blue().bold(),
             " - ".into(),
-            "Manage URLs ".into(),
-            "<E> ".blue().bold(),
+            "Pause ".into(),
+            "<Ctrl+P> ".blue().bold(),
         ]);
 
         let block = Block::bordered()
@@ -163,14 +777,32 @@ impl Widget for &mut App {
 
         let text = Text::from(banner_lines);
 
-        let menu_options = Text::from(vec![Line::from("Monitored URLs               E")])
+        // Each entry with its shortcut, e.g. "Monitored URLs      E".
+        let items: Vec<ListItem> = MenuEntry::ALL
+            .iter()
+            .map(|entry| ListItem::new(format!("{:<28}{}", entry.label(), entry.shortcut())))
+            .collect();
+        let menu = List::new(items)
             .style(Color::LightBlue)
-            .centered();
+            .highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White))
+            .highlight_symbol("> ");
+        let [menu_area, profile_area] = Layout::vertical([
+            Constraint::Length(MenuEntry::ALL.len() as u16 + 1),
+            Constraint::Length(1),
+        ])
+        .areas(box_layout[1]);
+        let [menu_area] = Layout::horizontal([Constraint::Length(31)])
+            .flex(layout::Flex::Center)
+            .areas(menu_area);
 
         let header = Paragraph::new(text).centered();
 
         block.render(area, buf);
         header.render(box_layout[0], buf);
-        menu_options.render(box_layout[1], buf);
+        StatefulWidget::render(menu, menu_area, buf, &mut self.main_menu.list_state);
+        Line::from(format!("Profile: {}", self.settings.profile.name()))
+            .dark_gray()
+            .centered()
+            .render(profile_area, buf);
     }
 }
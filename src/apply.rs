@@ -0,0 +1,578 @@
+use crate::configuration::{DISCOVERY_GROUP_PREFIX, MonitorTemplate, Settings};
+use crate::monitor::{CheckTarget, Criterion, HttpChallenge};
+use crate::store::{load_domains, save_domains};
+use crate::ui::domains::{
+    DomainAuth, FieldChange, HttpMethod, MonitoredDomain, StatusRange, describe_timeout,
+};
+use crate::wildcard::WildcardUrl;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{BufRead, IsTerminal, Write};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// A monitors file lists every domain that should be monitored, e.g.
+//
+// monitors:
+//   - url: https://example.com
+//     interval_seconds: 30
+//   - url: https://api.example.com/health?token=5f2b9c
+//     display_name: Payments API
+//     owner: payments
+//     environment: prod
+//     tags: [prod, eu]
+//     metadata:
+//       runbook: https://wiki.example.com/payments-api
+//     template: Standard API health check
+//   - url: https://sla.example.com
+//     history_max_checks: 10000
+//     history_max_age_days: 90
+//     latency_warn_below_ms: 500
+//     success_criteria:
+//       all:
+//         - status_in: { min: 200, max: 299 }
+//         - body_contains: "ok"
+//   - url: https://www.example.com
+//     challenge:
+//       path: /.well-known/health-token
+//       token: deploy-42
+//   - url: http://grafana.internal:3000/api/health
+//     ssh_jump_host: ops@bastion.example.com
+//   - url: docker://postgres
+//   - url: k8s://shop/app=web
+//     kubeconfig: /etc/upquack/kubeconfig
+//   - url: ping://10.0.0.1
+//   - url: dns://example.com/MX
+//   - url: redfish://10.0.0.50
+//   - url: snmp://switch.lan?min=600
+//   - url: sftp://backup@nas.lan/backups/nightly.done?max_age_minutes=1500
+//   - url: ntp://10.0.0.1?max_offset_ms=50
+//   - url: statuspage://www.githubstatus.com?component=Actions
+//   - url: feed://example.com/podcast.xml?max_age_hours=168
+//   - url: file:///var/backups/nightly.done?max_age_minutes=1500
+//   - url: grpc://10.0.0.5:50051/shop.Checkout
+//   - url: smtp://mx.example.com?starttls=true
+//   - url: ssh://bastion.example.com
+//   - url: host://?max_disk_percent=85
+//   - url: https://*.example.com/health
+//     hosts: [api, www, shop]
+//     template: Standard API health check
+//   - url: https://*.example.org/
+//     zone_file: example.org.zone
+//
+// A wildcard URL becomes one monitor per host, managed as a group: hosts
+// that are no longer listed (or in the zone file) are removed.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MonitorsFile {
+    #[serde(default)]
+    monitors: Vec<MonitorDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MonitorDefinition {
+    url: String,
+    template: Option<String>,
+    interval_seconds: Option<u64>,
+    timeout_seconds: Option<u64>,
+    history_max_checks: Option<usize>,
+    history_max_age_days: Option<u64>,
+    latency_good_below_ms: Option<u64>,
+    latency_warn_below_ms: Option<u64>,
+    // serde_yaml wants !tags for enums otherwise, see the example above.
+    #[serde(default, with = "serde_yaml::with::singleton_map_recursive")]
+    success_criteria: Option<Criterion>,
+    #[serde(default)]
+    expected_status_codes: Vec<StatusRange>,
+    expected_redirect: Option<String>,
+    max_redirects: Option<usize>,
+    keyword: Option<String>,
+    body_regex: Option<String>,
+    http_method: Option<HttpMethod>,
+    request_body: Option<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    auth: Option<DomainAuth>,
+    challenge: Option<HttpChallenge>,
+    ssh_jump_host: Option<String>,
+    kubeconfig: Option<String>,
+    owner: Option<String>,
+    environment: Option<String>,
+    display_name: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+    // The names filling in a wildcard URL's "*".
+    hosts: Option<Vec<String>>,
+    // A zone file export to take them from instead, relative to the
+    // monitors file.
+    zone_file: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+enum Change {
+    Create(MonitoredDomain),
+    Update {
+        desired: MonitoredDomain,
+        changes: Vec<FieldChange>,
+    },
+    Delete(MonitoredDomain),
+}
+
+pub fn run_apply(
+    path: &Path,
+    dry_run: bool,
+    assume_yes: bool,
+    settings: &Settings,
+) -> io::Result<()> {
+    let store_path = settings.profile.store_path();
+    let definitions = read_definitions(path)?;
+    let current = match load_domains(&store_path) {
+        Ok(domains) => domains,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+
+    let plan = plan_changes(&current, &definitions, &settings.templates)?;
+    print_plan(&plan);
+
+    if plan.is_empty() {
+        println!("Monitors already match {}.", path.display());
+        return Ok(());
+    }
+    if dry_run {
+        println!("Dry run, nothing was saved.");
+        return Ok(());
+    }
+    if !assume_yes && !confirm("Apply these changes?")? {
+        println!("Nothing was saved.");
+        return Ok(());
+    }
+
+    save_domains(&apply_plan(current, plan), &store_path)?;
+    println!("Saved to {}.", store_path);
+    Ok(())
+}
+
+fn confirm(question: &str) -> io::Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Not running in a terminal, pass --yes to apply without confirmation",
+        ));
+    }
+
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn read_definitions(path: &Path) -> io::Result<Vec<MonitorDefinition>> {
+    let raw = fs::read_to_string(path)?;
+    let invalid_data = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+
+    let file: MonitorsFile = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml") | Some("yml") => {
+            serde_yaml::from_str(&raw).map_err(|e| invalid_data(e.to_string()))?
+        }
+        Some("toml") => toml::from_str(&raw).map_err(|e| invalid_data(e.to_string()))?,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Monitors file must be .yaml, .yml or .toml",
+            ));
+        }
+    };
+
+    let mut monitors = file.monitors;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    for definition in &mut monitors {
+        if let Some(zone_file) = &mut definition.zone_file {
+            *zone_file = base_dir.join(&*zone_file);
+        }
+    }
+    Ok(monitors)
+}
+
+// The URLs a definition stands for, with the group they're managed as.
+fn definition_urls(definition: &MonitorDefinition) -> io::Result<(Vec<String>, Option<String>)> {
+    let url = definition.url.trim();
+    let invalid_data = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+    let Some(wildcard) = WildcardUrl::parse(url) else {
+        if definition.hosts.is_some() || definition.zone_file.is_some() {
+            return Err(invalid_data(format!(
+                "{}: hosts and zone_file only go with a wildcard URL like https://*.example.com",
+                url
+            )));
+        }
+        return Ok((vec![url.to_string()], None));
+    };
+    // Only their URLs would tell the monitors apart.
+    if definition.display_name.is_some() {
+        return Err(invalid_data(format!(
+            "{}: a wildcard URL's monitors can't share a display_name",
+            url
+        )));
+    }
+
+    let mut names = definition.hosts.clone().unwrap_or_default();
+    if let Some(zone_file) = &definition.zone_file {
+        names.extend(
+            wildcard
+                .zone_file_names(zone_file)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", zone_file.display(), e)))?,
+        );
+    }
+    let urls = wildcard
+        .expand(&names)
+        .map_err(|e| invalid_data(format!("{}: {}", url, e)))?;
+    Ok((urls, Some(wildcard.pattern())))
+}
+
+// Works out what has to change for the store to match the definitions. Domains
+// are matched by URL so existing ones keep their id and check history.
+fn plan_changes(
+    current: &[MonitoredDomain],
+    definitions: &[MonitorDefinition],
+    templates: &[MonitorTemplate],
+) -> io::Result<Vec<Change>> {
+    let mut seen_urls = HashSet::new();
+    let mut desired_domains = Vec::new();
+
+    for definition in definitions {
+        let (urls, group) = definition_urls(definition)?;
+        for url in &urls {
+            let url = url.as_str();
+            if CheckTarget::parse(url).is_err() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid URL: {}", url),
+                ));
+            }
+            if !seen_urls.insert(url.to_string()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Duplicate URL: {}", url),
+                ));
+            }
+
+            let mut desired = MonitoredDomain::new(url);
+            if let Some(name) = &definition.template {
+                let template = templates.iter().find(|t| &t.name == name).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Unknown template \"{}\" for {}", name, url),
+                    )
+                })?;
+                desired.apply_template(template);
+            }
+            if let Some(interval_seconds) = definition.interval_seconds {
+                desired.interval_seconds = interval_seconds;
+            }
+            if definition.timeout_seconds.is_some() {
+                desired.timeout_seconds = definition.timeout_seconds;
+            }
+            desired.history_max_checks = definition.history_max_checks;
+            desired.history_max_age_days = definition.history_max_age_days;
+            desired.latency_good_below_ms = definition.latency_good_below_ms;
+            desired.latency_warn_below_ms = definition.latency_warn_below_ms;
+            desired.success_criteria = definition.success_criteria.clone();
+            desired.expected_status_codes = definition.expected_status_codes.clone();
+            desired.expected_redirect = definition.expected_redirect.clone();
+            desired.max_redirects = definition.max_redirects;
+            desired.keyword = definition.keyword.clone();
+            desired.body_regex = definition.body_regex.clone();
+            desired.http_method = definition.http_method;
+            desired.request_body = definition.request_body.clone();
+            desired.headers = definition.headers.clone();
+            desired.auth = definition.auth.clone();
+            desired.challenge = definition.challenge.clone();
+            desired.ssh_jump_host = definition.ssh_jump_host.clone();
+            desired.kubeconfig = definition.kubeconfig.clone();
+            desired.owner = definition.owner.clone();
+            desired.environment = definition.environment.clone();
+            desired.display_name = definition.display_name.clone();
+            desired.tags = definition.tags.clone();
+            desired.metadata = definition.metadata.clone();
+            desired.group = group.clone();
+            desired.validate().map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", url, e))
+            })?;
+            desired_domains.push(desired);
+        }
+    }
+
+    let mut plan = Vec::new();
+    let mut matched_urls = HashSet::new();
+
+    for desired in desired_domains {
+        match current.iter().find(|d| d.url == desired.url) {
+            Some(existing) => {
+                matched_urls.insert(existing.url.clone());
+                let changes = existing.field_changes(&desired);
+                if !changes.is_empty() {
+                    let desired = MonitoredDomain {
+                        id: existing.id,
+                        check_history: existing.check_history.clone(),
+                        incident_notes: existing.incident_notes.clone(),
+                        muted_until: existing.muted_until,
+                        muted_from: existing.muted_from,
+                        certificate: existing.certificate.clone(),
+                        daily_rollups: existing.daily_rollups.clone(),
+                        incident_log: existing.incident_log.clone(),
+                        ..desired
+                    };
+                    plan.push(Change::Update { desired, changes });
+                }
+            }
+            None => plan.push(Change::Create(desired)),
+        }
+    }
+
+    for existing in current {
+        // Discovery sources keep their own monitors in step.
+        let discovered = existing
+            .group
+            .as_deref()
+            .is_some_and(|group| group.starts_with(DISCOVERY_GROUP_PREFIX));
+        if !matched_urls.contains(&existing.url) && !discovered {
+            plan.push(Change::Delete(existing.clone()));
+        }
+    }
+
+    Ok(plan)
+}
+
+fn apply_plan(mut domains: Vec<MonitoredDomain>, plan: Vec<Change>) -> Vec<MonitoredDomain> {
+    for change in plan {
+        match change {
+            Change::Create(domain) => domains.push(domain),
+            Change::Update { desired, .. } => {
+                if let Some(d) = domains.iter_mut().find(|d| d.id == desired.id) {
+                    *d = desired;
+                }
+            }
+            Change::Delete(domain) => domains.retain(|d| d.id != domain.id),
+        }
+    }
+    domains
+}
+
+fn print_plan(plan: &[Change]) {
+    let (mut created, mut updated, mut deleted) = (0, 0, 0);
+
+    for change in plan {
+        match change {
+            Change::Create(domain) => {
+                created += 1;
+                println!(
+                    "+ {} (interval {}s, timeout {})",
+                    domain.url,
+                    domain.interval_seconds,
+                    describe_timeout(domain.timeout_seconds)
+                );
+            }
+            Change::Update { desired, changes } => {
+                updated += 1;
+                println!("~ {}", desired.url);
+                for change in changes {
+                    println!("    {}: {} -> {}", change.field, change.old, change.new);
+                }
+            }
+            Change::Delete(domain) => {
+                deleted += 1;
+                println!("- {}", domain.url);
+            }
+        }
+    }
+
+    println!(
+        "Plan: {} to create, {} to update, {} to delete.",
+        created, updated, deleted
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(url: &str, interval_seconds: Option<u64>) -> MonitorDefinition {
+        MonitorDefinition {
+            url: url.to_string(),
+            template: None,
+            interval_seconds,
+            timeout_seconds: None,
+            history_max_checks: None,
+            history_max_age_days: None,
+            latency_good_below_ms: None,
+            latency_warn_below_ms: None,
+            success_criteria: None,
+            expected_status_codes: Vec::new(),
+            expected_redirect: None,
+            max_redirects: None,
+            keyword: None,
+            body_regex: None,
+            http_method: None,
+            request_body: None,
+            headers: BTreeMap::new(),
+            auth: None,
+            challenge: None,
+            ssh_jump_host: None,
+            kubeconfig: None,
+            owner: None,
+            environment: None,
+            display_name: None,
+            tags: Vec::new(),
+            metadata: BTreeMap::new(),
+            hosts: None,
+            zone_file: None,
+        }
+    }
+
+    #[test]
+    fn plan_creates_updates_and_deletes() {
+        let kept = MonitoredDomain::new("https://kept.com");
+        let retuned = MonitoredDomain::new("https://retuned.com");
+        let removed = MonitoredDomain::new("https://removed.com");
+        let mut discovered = MonitoredDomain::new("https://10.0.0.1:8080/health");
+        discovered.group = Some("discovery:web".to_string());
+        let current = vec![kept.clone(), retuned.clone(), removed.clone(), discovered];
+
+        let definitions = vec![
+            definition("https://kept.com", None),
+            definition("https://retuned.com", Some(30)),
+            definition("https://new.com", None),
+        ];
+
+        let plan = plan_changes(&current, &definitions, &[]).expect("Plan should succeed");
+        assert_eq!(plan.len(), 3);
+
+        let domains = apply_plan(current, plan);
+        let urls: Vec<&str> = domains.iter().map(|d| &*d.url).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://kept.com",
+                "https://retuned.com",
+                "https://10.0.0.1:8080/health",
+                "https://new.com"
+            ]
+        );
+
+        let retuned_after = &domains[1];
+        assert_eq!(retuned_after.id, retuned.id);
+        assert_eq!(retuned_after.interval_seconds, 30);
+    }
+
+    #[test]
+    fn plan_applies_templates() {
+        let templates = vec![MonitorTemplate {
+            name: "fast".to_string(),
+            interval_seconds: Some(10),
+            timeout_seconds: Some(2),
+            ..Default::default()
+        }];
+        let mut with_template = definition("https://example.com", None);
+        with_template.template = Some("fast".to_string());
+
+        let plan = plan_changes(&[], &[with_template], &templates).expect("Plan should succeed");
+        match &plan[..] {
+            [Change::Create(domain)] => {
+                assert_eq!(domain.interval_seconds, 10);
+                assert_eq!(domain.timeout_seconds, Some(2));
+            }
+            other => panic!("Unexpected plan: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn plan_rejects_unknown_template_and_duplicates() {
+        let mut with_template = definition("https://example.com", None);
+        with_template.template = Some("missing".to_string());
+        assert!(plan_changes(&[], &[with_template], &[]).is_err());
+
+        let duplicates = vec![
+            definition("https://example.com", None),
+            definition("https://example.com", Some(5)),
+        ];
+        assert!(plan_changes(&[], &duplicates, &[]).is_err());
+    }
+
+    #[test]
+    fn plan_expands_wildcards_into_a_group() {
+        let mut wildcard = definition("https://*.example.com/health", Some(30));
+        wildcard.hosts = Some(vec!["api".to_string(), "www".to_string()]);
+
+        let plan =
+            plan_changes(&[], std::slice::from_ref(&wildcard), &[]).expect("Plan should succeed");
+        let domains = apply_plan(Vec::new(), plan);
+        let urls: Vec<&str> = domains.iter().map(|d| &*d.url).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://api.example.com/health",
+                "https://www.example.com/health"
+            ]
+        );
+        assert!(domains.iter().all(|d| d.interval_seconds == 30
+            && d.group.as_deref() == Some("https://*.example.com/health")));
+
+        wildcard.hosts = None;
+        assert!(plan_changes(&[], &[wildcard], &[]).is_err());
+        let mut not_wildcard = definition("https://example.com", None);
+        not_wildcard.hosts = Some(vec!["api".to_string()]);
+        assert!(plan_changes(&[], &[not_wildcard], &[]).is_err());
+    }
+
+    #[test]
+    fn monitors_file_formats() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+
+        let yaml_path = temp_dir.path().join("monitors.yaml");
+        fs::write(
+            &yaml_path,
+            "monitors:\n  - url: https://example.com\n    interval_seconds: 30\n",
+        )
+        .expect("Failed to write yaml");
+        let from_yaml = read_definitions(&yaml_path).expect("Failed to read yaml");
+        assert_eq!(from_yaml[0].interval_seconds, Some(30));
+
+        fs::write(
+            &yaml_path,
+            "monitors:\n  - url: https://example.com\n    success_criteria:\n      all:\n        - status_in: { min: 200, max: 299 }\n        - not:\n            body_contains: maintenance\n",
+        )
+        .expect("Failed to write yaml");
+        let with_criteria = read_definitions(&yaml_path).expect("Failed to read criteria");
+        assert_eq!(
+            with_criteria[0].success_criteria,
+            Some(Criterion::All(vec![
+                Criterion::StatusIn { min: 200, max: 299 },
+                Criterion::Not(Box::new(Criterion::BodyContains("maintenance".to_string()))),
+            ]))
+        );
+
+        let toml_path = temp_dir.path().join("monitors.toml");
+        fs::write(
+            &toml_path,
+            "[[monitors]]\nurl = \"https://example.com\"\nsuccess_criteria = { latency_below_ms = 500 }\n",
+        )
+            .expect("Failed to write toml");
+        let from_toml = read_definitions(&toml_path).expect("Failed to read toml");
+        assert_eq!(from_toml[0].url, "https://example.com");
+        assert_eq!(
+            from_toml[0].success_criteria,
+            Some(Criterion::LatencyBelowMs(500))
+        );
+
+        let json_path = temp_dir.path().join("monitors.json");
+        fs::write(&json_path, "{}").expect("Failed to write json");
+        assert!(read_definitions(&json_path).is_err());
+    }
+}
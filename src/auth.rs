@@ -0,0 +1,200 @@
+use crate::configuration::ServerSettings;
+use axum::http::{HeaderMap, header::AUTHORIZATION};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use subtle::ConstantTimeEq;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Read,
+    Admin,
+    // The deploy webhook, which takes the deploy or the admin token.
+    Deploy,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AuthError {
+    // No credentials, or ones matching neither token.
+    Unauthorized,
+    // A valid read token used on an endpoint that needs the admin token, or
+    // an admin endpoint while no admin token is configured.
+    Forbidden,
+}
+
+// Reads are open until a read token is configured, after which either token
+// is accepted. Admin endpoints always need the admin token.
+pub fn authorize(
+    settings: &ServerSettings,
+    headers: &HeaderMap,
+    access: Access,
+) -> Result<(), AuthError> {
+    let presented = presented_token(headers);
+    let matches = |token: &Option<String>| match (token, &presented) {
+        (Some(token), Some(presented)) => tokens_match(token, presented),
+        _ => false,
+    };
+
+    match access {
+        Access::Read if settings.read_token.is_none() => Ok(()),
+        Access::Read if matches(&settings.read_token) || matches(&settings.admin_token) => Ok(()),
+        Access::Admin if settings.admin_token.is_none() => Err(AuthError::Forbidden),
+        Access::Admin if matches(&settings.admin_token) => Ok(()),
+        Access::Admin if matches(&settings.read_token) => Err(AuthError::Forbidden),
+        Access::Deploy if settings.deploy_token.is_none() && settings.admin_token.is_none() => {
+            Err(AuthError::Forbidden)
+        }
+        Access::Deploy if matches(&settings.deploy_token) || matches(&settings.admin_token) => {
+            Ok(())
+        }
+        Access::Deploy if matches(&settings.read_token) => Err(AuthError::Forbidden),
+        _ => Err(AuthError::Unauthorized),
+    }
+}
+
+// Accepts "Bearer <token>" and basic auth with the token as password, since
+// basic auth is what browsers prompt for when opening the dashboard.
+fn presented_token(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, credentials) = value.split_once(' ')?;
+
+    if scheme.eq_ignore_ascii_case("bearer") {
+        return Some(credentials.trim().to_string());
+    }
+    if scheme.eq_ignore_ascii_case("basic") {
+        let decoded = STANDARD.decode(credentials.trim()).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (_username, password) = decoded.split_once(':')?;
+        return Some(password.to_string());
+    }
+    None
+}
+
+// Constant time so response timing doesn't reveal how much of a guess was right.
+fn tokens_match(expected: &str, presented: &str) -> bool {
+    expected.as_bytes().ct_eq(presented.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn server_settings(read_token: Option<&str>, admin_token: Option<&str>) -> ServerSettings {
+        ServerSettings {
+            read_token: read_token.map(str::to_string),
+            admin_token: admin_token.map(str::to_string),
+            ..ServerSettings::default()
+        }
+    }
+
+    fn headers(authorization: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(authorization).unwrap());
+        headers
+    }
+
+    #[test]
+    fn reads_are_open_without_read_token() {
+        let settings = server_settings(None, Some("admin"));
+        assert_eq!(
+            authorize(&settings, &HeaderMap::new(), Access::Read),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn read_token_accepts_either_token() {
+        let settings = server_settings(Some("reader"), Some("admin"));
+
+        assert_eq!(
+            authorize(&settings, &HeaderMap::new(), Access::Read),
+            Err(AuthError::Unauthorized)
+        );
+        assert_eq!(
+            authorize(&settings, &headers("Bearer reader"), Access::Read),
+            Ok(())
+        );
+        assert_eq!(
+            authorize(&settings, &headers("Bearer admin"), Access::Read),
+            Ok(())
+        );
+        assert_eq!(
+            authorize(&settings, &headers("Bearer guess"), Access::Read),
+            Err(AuthError::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn admin_access_needs_admin_token() {
+        let settings = server_settings(Some("reader"), Some("admin"));
+
+        assert_eq!(
+            authorize(&settings, &headers("Bearer admin"), Access::Admin),
+            Ok(())
+        );
+        assert_eq!(
+            authorize(&settings, &headers("Bearer reader"), Access::Admin),
+            Err(AuthError::Forbidden)
+        );
+        assert_eq!(
+            authorize(&settings, &HeaderMap::new(), Access::Admin),
+            Err(AuthError::Unauthorized)
+        );
+
+        let without_admin = server_settings(Some("reader"), None);
+        assert_eq!(
+            authorize(&without_admin, &headers("Bearer reader"), Access::Admin),
+            Err(AuthError::Forbidden)
+        );
+    }
+
+    #[test]
+    fn deploy_hook_takes_deploy_or_admin_token() {
+        let settings = ServerSettings {
+            deploy_token: Some("ci".to_string()),
+            ..server_settings(Some("reader"), Some("admin"))
+        };
+
+        for token in ["Bearer ci", "Bearer admin"] {
+            assert_eq!(
+                authorize(&settings, &headers(token), Access::Deploy),
+                Ok(())
+            );
+        }
+        assert_eq!(
+            authorize(&settings, &headers("Bearer reader"), Access::Deploy),
+            Err(AuthError::Forbidden)
+        );
+        // The deploy token opens nothing else.
+        assert_eq!(
+            authorize(&settings, &headers("Bearer ci"), Access::Admin),
+            Err(AuthError::Unauthorized)
+        );
+        assert_eq!(
+            authorize(
+                &server_settings(None, None),
+                &HeaderMap::new(),
+                Access::Deploy
+            ),
+            Err(AuthError::Forbidden)
+        );
+    }
+
+    #[test]
+    fn basic_auth_uses_password_as_token() {
+        let settings = server_settings(Some("reader"), None);
+        let credentials = STANDARD.encode("anyone:reader");
+
+        assert_eq!(
+            authorize(
+                &settings,
+                &headers(&format!("Basic {}", credentials)),
+                Access::Read
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            authorize(&settings, &headers("Basic not-base64!"), Access::Read),
+            Err(AuthError::Unauthorized)
+        );
+    }
+}
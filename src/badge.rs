@@ -0,0 +1,112 @@
+use crate::ui::domains::{DomainStatus, MonitoredDomain, status_label};
+use chrono::{DateTime, Duration, Utc};
+
+// UTC days (today included) the uptime on a badge covers.
+pub const BADGE_UPTIME_DAYS: i64 = 30;
+
+// Roughly how wide a character of 11px Verdana is, which is all the badge
+// layout needs.
+const CHAR_WIDTH: f64 = 6.5;
+const PADDING: f64 = 10.0;
+
+// A shields.io style "label | message" badge of the domain's current status
+// and uptime over the last 30 days, e.g. "uptime | up 99.98%".
+pub fn uptime_badge(
+    domain: &MonitoredDomain,
+    label: &str,
+    now: DateTime<Utc>,
+    count_rate_limited_as_down: bool,
+) -> String {
+    let Some(last_check) = domain.check_history.last() else {
+        return render(label, "no data", "#9f9f9f");
+    };
+    let color = match last_check.status {
+        DomainStatus::Up => "#4c1",
+        DomainStatus::Down | DomainStatus::Error(_) => "#e05d44",
+        DomainStatus::Degraded | DomainStatus::Unknown | DomainStatus::RateLimited => "#dfb317",
+    };
+    let today = now.date_naive();
+    let uptime = domain
+        .daily_stats(
+            today - Duration::days(BADGE_UPTIME_DAYS - 1),
+            today,
+            count_rate_limited_as_down,
+        )
+        .uptime_percentage;
+    let status = status_label(&last_check.status).to_lowercase();
+    let message = match uptime {
+        Some(uptime) => format!("{} {:.2}%", status, uptime),
+        None => status,
+    };
+    render(label, &message, color)
+}
+
+fn render(label: &str, message: &str, color: &str) -> String {
+    let label_width = text_width(label);
+    let message_width = text_width(message);
+    let width = label_width + message_width;
+    let (label, message) = (escape(label), escape(message));
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}"><title>{label}: {message}</title><linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient><clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath><g clip-path="url(#r)"><rect width="{label_width}" height="20" fill="#555"/><rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g><g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11"><text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text><text x="{label_x}" y="14">{label}</text><text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text><text x="{message_x}" y="14">{message}</text></g></svg>"##,
+        label_x = label_width / 2,
+        message_x = label_width + message_width / 2,
+    )
+}
+
+fn text_width(text: &str) -> u32 {
+    (text.chars().count() as f64 * CHAR_WIDTH + PADDING).round() as u32
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::domains::CheckStatus;
+    use chrono::TimeZone;
+
+    fn check(status: DomainStatus, at: DateTime<Utc>) -> CheckStatus {
+        CheckStatus::new(at, status)
+    }
+
+    #[test]
+    fn badge_shows_status_and_recent_uptime() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 20, 12, 0, 0).unwrap();
+        let mut domain = MonitoredDomain::new("https://example.com");
+        assert!(uptime_badge(&domain, "uptime", now, false).contains("no data"));
+
+        for check in [
+            // Older than the badge looks back.
+            check(DomainStatus::Down, now - Duration::days(40)),
+            check(DomainStatus::Down, now - Duration::days(20)),
+            check(DomainStatus::Up, now - Duration::days(1)),
+            check(DomainStatus::Up, now - Duration::hours(1)),
+            check(DomainStatus::Up, now),
+        ] {
+            domain.push_check(check);
+        }
+        // The retention only keeps the last checks, the rollups the rest.
+        domain.check_history.drain(0..2);
+        let badge = uptime_badge(&domain, "uptime", now, false);
+        assert!(badge.contains(">up 75.00%<"), "{}", badge);
+        assert!(badge.contains("#4c1"));
+
+        domain.push_check(check(DomainStatus::Error("timeout".into()), now));
+        let badge = uptime_badge(&domain, "api", now, false);
+        assert!(badge.contains(">error 60.00%<"), "{}", badge);
+        assert!(badge.contains("#e05d44"));
+    }
+
+    #[test]
+    fn labels_are_escaped() {
+        let badge = render("<a&b>", "up", "#4c1");
+        assert!(badge.contains("&lt;a&amp;b&gt;"));
+        assert!(!badge.contains("<a&b>"));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::incident::format_duration;
+use crate::signing::CheckSigner;
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+// How the Unknown checks marking a blind spot start their error message.
+const NOT_MONITORED: &str = "Not monitored, upquack wasn't running";
+
+// A stretch of time a domain went unchecked because upquack itself wasn't
+// running, found at startup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlindSpot {
+    pub url: Arc<str>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+// Checks run up to an interval late anyway, so only time without checks of
+// more than two intervals counts as a gap.
+fn is_gap(without_checks: Duration, interval: Duration) -> bool {
+    without_checks > interval * 2
+}
+
+// How old a domain's last result is next to its interval, shown by the color
+// of its Last Check cell so a scheduler falling behind stands out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckAge {
+    Fresh,
+    // Overdue by up to an interval.
+    Late,
+    // Overdue by more, what a gap in the history starts as.
+    Stale,
+}
+
+// A rate limited result stays fresh, the next check waits as long as the
+// server asked.
+pub fn check_age(check: &CheckStatus, interval_seconds: u64, now: DateTime<Utc>) -> CheckAge {
+    let interval = Duration::seconds(interval_seconds as i64);
+    let age = now - check.timestamp;
+    if check.status == DomainStatus::RateLimited || age <= interval {
+        CheckAge::Fresh
+    } else if is_gap(age, interval) {
+        CheckAge::Stale
+    } else {
+        CheckAge::Late
+    }
+}
+
+// Time without checks inside a domain's history, e.g. while its monitoring
+// task had died, shown in the history instead of the previous result
+// silently carrying over. Gaps never count towards uptime, which only counts
+// the checks made.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistoryGap {
+    // When the first missed check was due.
+    pub from: DateTime<Utc>,
+    // The next check, or now when there was none since.
+    pub to: DateTime<Utc>,
+    // Index of the check right after the gap, the history's length if none.
+    pub before: usize,
+}
+
+// The gaps in the history, oldest first. Those after an Unknown check aren't
+// repeated, that check (a blind spot or clock jump marker) explains them, and
+// neither are those after a rate limited one, the server asked for the wait.
+pub fn history_gaps(
+    checks: &[CheckStatus],
+    interval_seconds: u64,
+    now: DateTime<Utc>,
+) -> Vec<HistoryGap> {
+    let interval = Duration::seconds(interval_seconds as i64);
+    let next_timestamps = checks
+        .iter()
+        .skip(1)
+        .map(|c| c.timestamp)
+        .chain(std::iter::once(now));
+
+    checks
+        .iter()
+        .zip(next_timestamps)
+        .enumerate()
+        .filter(|(_, (check, _))| {
+            !matches!(
+                check.status,
+                DomainStatus::Unknown | DomainStatus::RateLimited
+            )
+        })
+        .filter(|(_, (check, next))| is_gap(*next - check.timestamp, interval))
+        .map(|(i, (check, next))| HistoryGap {
+            from: check.timestamp + interval,
+            to: next,
+            before: i + 1,
+        })
+        .collect()
+}
+
+// A gap since a domain's last check at startup is recorded as an Unknown
+// check at the first missed one, so it stays in the history (and is signed
+// with it) even once newer checks follow.
+pub fn mark_blind_spots(
+    domains: &mut [MonitoredDomain],
+    now: DateTime<Utc>,
+    signer: Option<&CheckSigner>,
+) -> Vec<BlindSpot> {
+    let mut blind_spots = Vec::new();
+
+    for domain in domains.iter_mut() {
+        let Some(last_check) = domain.check_history.last() else {
+            continue;
+        };
+        let interval = Duration::seconds(domain.interval_seconds as i64);
+        let missed_from = last_check.timestamp + interval;
+        if !is_gap(now - last_check.timestamp, interval) {
+            continue;
+        }
+
+        let mut marker = CheckStatus {
+            error_message: Some(format!("{} until {}", NOT_MONITORED, now.to_rfc3339()).into()),
+            detail: Some(
+                format!("not monitored for {}", format_duration(now - missed_from)).into(),
+            ),
+            ..CheckStatus::new(missed_from, DomainStatus::Unknown)
+        };
+        if let Some(signer) = signer {
+            signer.sign(domain.id, domain.check_history.last(), &mut marker);
+        }
+        domain.check_history.push(marker);
+        blind_spots.push(BlindSpot {
+            url: domain.url.clone(),
+            from: missed_from,
+            to: now,
+        });
+    }
+
+    blind_spots
+}
+
+// Whether the check marks a blind spot rather than being a real check.
+pub fn is_marker(check: &CheckStatus) -> bool {
+    check.status == DomainStatus::Unknown
+        && check
+            .error_message
+            .as_deref()
+            .is_some_and(|message| message.starts_with(NOT_MONITORED))
+}
+
+// One line for the notice, the longest blind spot gives the downtime.
+pub fn summary(blind_spots: &[BlindSpot]) -> Option<String> {
+    let longest = blind_spots.iter().map(|b| b.to - b.from).max()?;
+    Some(format!(
+        "upquack wasn't running for {}, {} domain(s) went unchecked (marked UNKNOWN in their history)",
+        format_duration(longest),
+        blind_spots.len()
+    ))
+}
+
+// Logs every blind spot, for the record the notice points at.
+pub fn log_blind_spots(blind_spots: &[BlindSpot]) {
+    if let Some(summary) = summary(blind_spots) {
+        tracing::warn!("{}", summary);
+    }
+    for blind_spot in blind_spots {
+        tracing::warn!(
+            "Not monitored: {} from {} to {} ({})",
+            blind_spot.url,
+            blind_spot.from.to_rfc3339(),
+            blind_spot.to.to_rfc3339(),
+            format_duration(blind_spot.to - blind_spot.from)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checked_at(url: &str, interval_seconds: u64, at: DateTime<Utc>) -> MonitoredDomain {
+        let mut domain = MonitoredDomain::new(url);
+        domain.interval_seconds = interval_seconds;
+        domain.check_history.push(CheckStatus {
+            response_time_ms: Some(40),
+            ..CheckStatus::new(at, DomainStatus::Up)
+        });
+        domain
+    }
+
+    #[test]
+    fn gaps_longer_than_two_intervals_are_marked() {
+        let now = Utc::now();
+        let mut domains = vec![
+            checked_at("https://fresh.com", 60, now - Duration::seconds(90)),
+            checked_at("https://stale.com", 60, now - Duration::hours(3)),
+            MonitoredDomain::new("https://new.com"),
+        ];
+        let age = |domain: &MonitoredDomain| check_age(&domain.check_history[0], 60, now);
+        assert_eq!(age(&domains[0]), CheckAge::Late);
+        assert_eq!(age(&domains[1]), CheckAge::Stale);
+        assert_eq!(
+            check_age(&domains[0].check_history[0], 120, now),
+            CheckAge::Fresh
+        );
+
+        let blind_spots = mark_blind_spots(&mut domains, now, None);
+
+        assert_eq!(
+            blind_spots,
+            vec![BlindSpot {
+                url: "https://stale.com".into(),
+                from: now - Duration::hours(3) + Duration::seconds(60),
+                to: now,
+            }]
+        );
+        assert_eq!(domains[0].check_history.len(), 1);
+        let marker = domains[1].check_history.last().unwrap();
+        assert!(is_marker(marker));
+        assert!(!is_marker(&domains[0].check_history[0]));
+        assert_eq!(marker.timestamp, blind_spots[0].from);
+        assert_eq!(marker.detail.as_deref(), Some("not monitored for 2h 59m"));
+        assert!(domains[2].check_history.is_empty());
+
+        assert!(
+            summary(&blind_spots)
+                .unwrap()
+                .starts_with("upquack wasn't running for 2h 59m")
+        );
+        assert_eq!(summary(&[]), None);
+    }
+
+    #[test]
+    fn gaps_are_found_between_checks_and_up_to_now() {
+        let start = Utc::now() - Duration::hours(2);
+        let mut domain = checked_at("https://example.com", 60, start);
+        let mut check_at = |minutes: i64, status: DomainStatus| {
+            let mut check = domain.check_history[0].clone();
+            check.timestamp = start + Duration::minutes(minutes);
+            check.status = status;
+            domain.check_history.push(check);
+        };
+        check_at(1, DomainStatus::Up);
+        check_at(2, DomainStatus::Up);
+        // The task died for 10 minutes.
+        check_at(12, DomainStatus::Down);
+        // The server asked for a long wait.
+        check_at(13, DomainStatus::RateLimited);
+        check_at(30, DomainStatus::Up);
+        let now = start + Duration::minutes(40);
+
+        let gaps = history_gaps(&domain.check_history, 60, now);
+
+        assert_eq!(
+            gaps,
+            vec![
+                HistoryGap {
+                    from: start + Duration::minutes(3),
+                    to: start + Duration::minutes(12),
+                    before: 3,
+                },
+                HistoryGap {
+                    from: start + Duration::minutes(31),
+                    to: now,
+                    before: 6,
+                },
+            ]
+        );
+        // Gaps don't count, the checks made are all Up apart from one.
+        assert_eq!(domain.uptime_percentage(false), Some(80.0));
+
+        // A blind spot marker already shows the gap after it.
+        let mut domains = vec![domain];
+        mark_blind_spots(&mut domains, now, None);
+        assert_eq!(history_gaps(&domains[0].check_history, 60, now).len(), 1);
+    }
+}
@@ -0,0 +1,312 @@
+use crate::configuration::BudgetSettings;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+use tokio::time::{Duration, Instant, sleep};
+use url::Url;
+
+const BUDGET_WINDOW: Duration = Duration::from_secs(60);
+
+// Caps how many requests the monitoring tasks may send per minute, in total and
+// per host. Checks over budget wait in `acquire` until a slot frees up.
+#[derive(Debug, Default)]
+pub struct RequestBudget {
+    max_total: Option<usize>,
+    max_per_host: Option<usize>,
+    sent: Mutex<SentRequests>,
+    queued: AtomicUsize,
+}
+
+#[derive(Debug, Default)]
+struct SentRequests {
+    total: VecDeque<Instant>,
+    per_host: HashMap<String, VecDeque<Instant>>,
+}
+
+// Keeps the queued counter right even if a waiting task gets cancelled.
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl RequestBudget {
+    pub fn new(settings: &BudgetSettings) -> Self {
+        RequestBudget {
+            max_total: settings.max_requests_per_minute,
+            max_per_host: settings.max_requests_per_host_per_minute,
+            ..Default::default()
+        }
+    }
+
+    pub async fn acquire(&self, host: &str) {
+        let mut queued: Option<QueuedGuard> = None;
+
+        loop {
+            let wait = self.sent.lock().unwrap().try_reserve(
+                host,
+                Instant::now(),
+                self.max_total,
+                self.max_per_host,
+            );
+
+            match wait {
+                Ok(()) => return,
+                Err(wait) => {
+                    if queued.is_none() {
+                        self.queued.fetch_add(1, Ordering::Relaxed);
+                        queued = Some(QueuedGuard(&self.queued));
+                        tracing::debug!(
+                            "Request budget reached, delaying check of {host} by {wait:?}"
+                        );
+                    }
+                    sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    // Number of checks currently held back by the budget.
+    pub fn queued_checks(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+}
+
+// The request budget is kept per host.
+pub fn budget_host(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string())
+}
+
+// Monitors whose intervals add up to more checks per minute than the budget
+// allows, with the interval that would fit them all in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetWarning {
+    Total {
+        checks_per_minute: f64,
+        budget: usize,
+        suggested_interval_seconds: u64,
+    },
+    Host {
+        host: String,
+        monitors: usize,
+        checks_per_minute: f64,
+        budget: usize,
+        suggested_interval_seconds: u64,
+    },
+}
+
+impl fmt::Display for BudgetWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetWarning::Total {
+                checks_per_minute,
+                budget,
+                suggested_interval_seconds,
+            } => write!(
+                f,
+                "{:.0} checks/min scheduled, budget is {}: try intervals of {}s or more",
+                checks_per_minute, budget, suggested_interval_seconds
+            ),
+            BudgetWarning::Host {
+                host,
+                monitors,
+                checks_per_minute,
+                budget,
+                suggested_interval_seconds,
+            } => write!(
+                f,
+                "{} monitors probe {} {:.0} times/min, budget is {}: try intervals of {}s or more",
+                monitors, host, checks_per_minute, budget, suggested_interval_seconds
+            ),
+        }
+    }
+}
+
+// Adds up the checks per minute the monitors' (URL, interval) pairs
+// schedule, in total and per host, against the warning budgets.
+pub fn scheduled_load<'a>(
+    monitors: impl IntoIterator<Item = (&'a str, u64)>,
+    settings: &BudgetSettings,
+) -> Vec<BudgetWarning> {
+    let per_minute = |interval: u64| 60.0 / interval.max(1) as f64;
+    // The interval that fits this many monitors into the budget.
+    let suggested =
+        |monitors: usize, budget: usize| (monitors as u64 * 60).div_ceil(budget.max(1) as u64);
+
+    let mut total = 0.0;
+    let mut count = 0;
+    let mut hosts: BTreeMap<String, (usize, f64)> = BTreeMap::new();
+    for (url, interval) in monitors {
+        total += per_minute(interval);
+        count += 1;
+        let host = hosts.entry(budget_host(url)).or_default();
+        host.0 += 1;
+        host.1 += per_minute(interval);
+    }
+
+    let mut warnings = Vec::new();
+    if let Some(budget) = settings.warn_total()
+        && total > budget as f64
+    {
+        warnings.push(BudgetWarning::Total {
+            checks_per_minute: total,
+            budget,
+            suggested_interval_seconds: suggested(count, budget),
+        });
+    }
+    if let Some(budget) = settings.warn_per_host() {
+        // One monitor probing its own host often is its owner's call.
+        for (host, (monitors, checks_per_minute)) in hosts {
+            if monitors > 1 && checks_per_minute > budget as f64 {
+                warnings.push(BudgetWarning::Host {
+                    host,
+                    monitors,
+                    checks_per_minute,
+                    budget,
+                    suggested_interval_seconds: suggested(monitors, budget),
+                });
+            }
+        }
+    }
+    warnings
+}
+
+impl SentRequests {
+    // Records a request if both budgets allow it, otherwise returns how long
+    // until the oldest request blocking it leaves the window.
+    fn try_reserve(
+        &mut self,
+        host: &str,
+        now: Instant,
+        max_total: Option<usize>,
+        max_per_host: Option<usize>,
+    ) -> Result<(), Duration> {
+        prune(&mut self.total, now);
+        let host_sent = self.per_host.entry(host.to_string()).or_default();
+        prune(host_sent, now);
+
+        let wait = [
+            wait_for_slot(&self.total, max_total, now),
+            wait_for_slot(host_sent, max_per_host, now),
+        ]
+        .into_iter()
+        .flatten()
+        .max();
+
+        match wait {
+            Some(wait) => Err(wait),
+            None => {
+                self.total.push_back(now);
+                host_sent.push_back(now);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn prune(sent: &mut VecDeque<Instant>, now: Instant) {
+    while sent
+        .front()
+        .is_some_and(|sent_at| now.duration_since(*sent_at) >= BUDGET_WINDOW)
+    {
+        sent.pop_front();
+    }
+}
+
+fn wait_for_slot(sent: &VecDeque<Instant>, limit: Option<usize>, now: Instant) -> Option<Duration> {
+    // A limit of 0 would block forever, treat it like 1.
+    let limit = limit?.max(1);
+    if sent.len() < limit {
+        return None;
+    }
+
+    let oldest_blocking = sent[sent.len() - limit];
+    Some((oldest_blocking + BUDGET_WINDOW).saturating_duration_since(now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlimited_budget_never_waits() {
+        let mut sent = SentRequests::default();
+        let now = Instant::now();
+
+        for _ in 0..1000 {
+            assert!(sent.try_reserve("example.com", now, None, None).is_ok());
+        }
+    }
+
+    #[test]
+    fn per_host_budget_delays_until_window_frees() {
+        let mut sent = SentRequests::default();
+        let start = Instant::now();
+
+        assert!(sent.try_reserve("a.com", start, None, Some(2)).is_ok());
+        assert!(
+            sent.try_reserve("a.com", start + Duration::from_secs(10), None, Some(2))
+                .is_ok()
+        );
+
+        let wait = sent
+            .try_reserve("a.com", start + Duration::from_secs(20), None, Some(2))
+            .unwrap_err();
+        assert_eq!(wait, Duration::from_secs(40));
+
+        // Other hosts are not affected by a.com's budget.
+        assert!(
+            sent.try_reserve("b.com", start + Duration::from_secs(20), None, Some(2))
+                .is_ok()
+        );
+
+        assert!(
+            sent.try_reserve("a.com", start + Duration::from_secs(60), None, Some(2))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn total_budget_applies_across_hosts() {
+        let mut sent = SentRequests::default();
+        let now = Instant::now();
+
+        assert!(sent.try_reserve("a.com", now, Some(2), None).is_ok());
+        assert!(sent.try_reserve("b.com", now, Some(2), None).is_ok());
+        assert!(sent.try_reserve("c.com", now, Some(2), None).is_err());
+    }
+
+    #[test]
+    fn warns_when_intervals_exceed_the_budget() {
+        let settings = BudgetSettings {
+            max_requests_per_minute: Some(20),
+            warn_checks_per_host_per_minute: Some(6),
+            ..Default::default()
+        };
+        let monitors = [
+            ("https://api.example.com/health", 10),
+            ("https://api.example.com/login", 20),
+            ("https://www.example.com/", 5),
+            ("ping://10.0.0.1", 60),
+        ];
+        let warnings = scheduled_load(monitors, &settings);
+        assert_eq!(
+            warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+            [
+                "22 checks/min scheduled, budget is 20: try intervals of 12s or more",
+                "2 monitors probe api.example.com 9 times/min, budget is 6: try intervals of 20s or more",
+            ]
+        );
+        assert!(scheduled_load(monitors, &BudgetSettings::default()).is_empty());
+    }
+}
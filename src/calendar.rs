@@ -0,0 +1,173 @@
+use crate::incident::format_duration;
+use crate::store::load_domains;
+use crate::ui::domains::MonitoredDomain;
+use chrono::{DateTime, Utc};
+use std::{fs, io, path::Path};
+
+// Writes the incident calendar to `output` or stdout.
+pub fn run_incident_calendar(store_path: &str, output: Option<&Path>) -> io::Result<()> {
+    let domains = load_domains(store_path)?;
+    let calendar = incident_calendar(&domains, Utc::now());
+
+    match output {
+        Some(path) => {
+            fs::write(path, calendar)?;
+            println!("Wrote the incident calendar to {}", path.display());
+        }
+        None => print!("{}", calendar),
+    }
+    Ok(())
+}
+
+// Every domain's incidents as an iCalendar (RFC 5545) feed, one event per
+// incident, so calendar apps can show when things were down. Ongoing
+// incidents end at `now` until they're resolved. A domain's last mute goes
+// in as a maintenance window.
+pub fn incident_calendar(domains: &[MonitoredDomain], now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//upquack//incidents//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:upquack incidents".to_string(),
+    ];
+
+    for domain in domains {
+        if let (Some(from), Some(until)) = (domain.muted_from, domain.muted_until) {
+            lines.extend([
+                "BEGIN:VEVENT".to_string(),
+                format!("UID:{}-maintenance-{}@upquack", domain.id, from.timestamp()),
+                format!("DTSTAMP:{}", timestamp(now)),
+                format!("DTSTART:{}", timestamp(from)),
+                format!("DTEND:{}", timestamp(until)),
+                format!("SUMMARY:{}", escape(&format!("{} maintenance", domain.url))),
+                "DESCRIPTION:Status change notifications muted.".to_string(),
+                format!("URL:{}", escape(&domain.url)),
+                "END:VEVENT".to_string(),
+            ]);
+        }
+        for incident in domain.incidents() {
+            let state = if incident.is_closed() {
+                "resolved"
+            } else {
+                "ongoing"
+            };
+            let mut description = format!(
+                "{} for {}, {} failed checks.",
+                state,
+                format_duration(incident.duration(now)),
+                incident.failed_checks
+            );
+            if let Some(error) = &incident.first_error {
+                description.push_str(&format!("\nFirst error: {}", error));
+            }
+            if let Some(note) = domain.incident_note(incident.started_at) {
+                description.push_str(&format!("\n\n{}", note.note.trim_end()));
+            }
+
+            lines.extend([
+                "BEGIN:VEVENT".to_string(),
+                // Stable across exports, so subscribed calendars update the
+                // event instead of adding another.
+                format!(
+                    "UID:{}-{}@upquack",
+                    domain.id,
+                    incident.started_at.timestamp()
+                ),
+                format!("DTSTAMP:{}", timestamp(now)),
+                format!("DTSTART:{}", timestamp(incident.started_at)),
+                format!("DTEND:{}", timestamp(incident.ended_at.unwrap_or(now))),
+                format!(
+                    "SUMMARY:{}",
+                    escape(&format!("{} down ({})", domain.url, state))
+                ),
+                format!("DESCRIPTION:{}", escape(&description)),
+                format!("URL:{}", escape(&domain.url)),
+                "END:VEVENT".to_string(),
+            ]);
+        }
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Lines end in CRLF and longer ones are folded at 75 bytes, continuing on
+// lines that start with a space. Characters are never split.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut line_len = 0;
+    for c in line.chars() {
+        if line_len + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            line_len = 1;
+        }
+        folded.push(c);
+        line_len += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::domains::{CheckStatus, DomainStatus};
+    use chrono::Duration;
+
+    fn check(at: DateTime<Utc>, status: DomainStatus) -> CheckStatus {
+        CheckStatus {
+            error_message: matches!(status, DomainStatus::Down).then(|| "503, retrying".into()),
+            ..CheckStatus::new(at, status)
+        }
+    }
+
+    #[test]
+    fn incidents_become_events() {
+        let start: DateTime<Utc> = "2025-03-01T12:00:00Z".parse().unwrap();
+        let now = start + Duration::hours(2);
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.check_history = vec![
+            check(start, DomainStatus::Down),
+            check(start + Duration::minutes(5), DomainStatus::Up),
+            check(start + Duration::hours(1), DomainStatus::Down),
+        ];
+        domain.set_incident_note(start, "Database failover", now);
+        domain.muted_from = Some(start + Duration::hours(3));
+        domain.muted_until = Some(start + Duration::hours(4));
+
+        let calendar = incident_calendar(&[domain.clone()], now);
+
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 3);
+        assert!(calendar.contains("DTSTART:20250301T150000Z\r\nDTEND:20250301T160000Z\r\n"));
+        assert!(calendar.contains("SUMMARY:https://example.com maintenance"));
+        assert!(calendar.contains("DTSTART:20250301T120000Z\r\nDTEND:20250301T120500Z\r\n"));
+        // The ongoing one lasts until now.
+        assert!(calendar.contains("DTSTART:20250301T130000Z\r\nDTEND:20250301T140000Z\r\n"));
+        assert!(calendar.contains("SUMMARY:https://example.com down (ongoing)"));
+        let unfolded = calendar.replace("\r\n ", "");
+        assert!(unfolded.contains("First error: 503\\, retrying\\n\\nDatabase failover"));
+        assert!(unfolded.contains(&format!("UID:{}-{}@upquack", domain.id, start.timestamp())));
+    }
+
+    #[test]
+    fn long_lines_are_folded() {
+        let line = format!("DESCRIPTION:{}", "é".repeat(100));
+        let folded = fold(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), format!("{}\r\n", line));
+    }
+}
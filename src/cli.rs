@@ -0,0 +1,90 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "A terminal URL monitoring application")]
+pub struct Cli {
+    /// Use a named profile, with its own monitors and configuration
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+    /// Start with all checks and notifications paused, e.g. during planned maintenance
+    #[arg(long, global = true)]
+    pub paused: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Monitor without the TUI, serving the JSON API and web dashboard
+    #[cfg(feature = "server")]
+    Daemon,
+    /// Make the monitored domains match a YAML or TOML monitors file
+    Apply {
+        /// Path to the monitors file (.yaml, .yml or .toml)
+        file: PathBuf,
+        /// Print the changes without saving them
+        #[arg(long)]
+        dry_run: bool,
+        /// Save without asking for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Export all monitors as an Uptime Kuma backup file
+    Export {
+        /// Where to write the backup JSON
+        file: PathBuf,
+    },
+    /// Add the HTTP monitors from an Uptime Kuma backup file
+    Import {
+        /// Path to the backup JSON
+        file: PathBuf,
+    },
+    /// Report every domain's incidents with their postmortem notes as Markdown
+    Incidents {
+        /// Where to write the report, defaults to stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Write an iCalendar (.ics) file for calendar apps instead
+        #[arg(long)]
+        ics: bool,
+    },
+    /// Mark a point in time, like a deploy, on the dashboard's charts
+    Annotate {
+        /// What happened, e.g. "deployed v1.2"
+        text: String,
+        /// Only show it on the chart of the monitor with this URL
+        #[arg(long)]
+        url: Option<String>,
+    },
+    /// Manage encrypted secrets, referenced from configs as "secret:<name>"
+    Secret {
+        #[command(subcommand)]
+        action: SecretAction,
+    },
+    /// Bundle logs, redacted config and store statistics for a bug report
+    Diagnose {
+        /// Where to write the .tar.gz, defaults to upquack-diagnostics-<time>.tar.gz
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Check that signed check histories haven't been modified
+    VerifyHistory {
+        /// History file to verify, defaults to the profile's store
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// Base64 public key to verify with, defaults to the configured key's .pub file
+        #[arg(long)]
+        public_key: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SecretAction {
+    /// Store a secret, reading the value from a prompt or stdin
+    Set { name: String },
+    /// List the names of stored secrets
+    List,
+    /// Delete a secret
+    Remove { name: String },
+}
@@ -1 +1,655 @@
+use crate::logging::LogLevel;
+use crate::profile::Profile;
+use crate::secrets::SecretResolver;
+use crate::ui::domains::{HistoryRetention, HttpMethod, MonitoredDomain, StatusRange};
+use crate::ui::latency::LatencySettings;
+use crate::ui::locale::Locale;
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, io, path::Path};
 
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub monitor: MonitorSettings,
+    pub budget: BudgetSettings,
+    pub latency: LatencySettings,
+    pub display: DisplaySettings,
+    pub server: ServerSettings,
+    pub signing: SigningSettings,
+    pub performance: PerformanceSettings,
+    pub logging: LoggingSettings,
+    pub telemetry: TelemetrySettings,
+    pub templates: Vec<MonitorTemplate>,
+    pub discovery: Vec<DiscoverySource>,
+    // Credentials for the BMCs redfish:// monitors query.
+    pub bmc: Vec<BmcSettings>,
+    // Credentials for the agents snmp:// monitors query.
+    pub snmp: Vec<SnmpSettings>,
+    // Logins for the servers ftp:// monitors connect to.
+    pub ftp: Vec<FtpSettings>,
+    // Which profile these settings (and the store they go with) belong to.
+    #[serde(skip)]
+    pub profile: Profile,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MonitorSettings {
+    // Whether checks answered with 429/503 + Retry-After count against uptime.
+    pub count_rate_limited_as_down: bool,
+    // History kept per monitor unless the monitor overrides it.
+    pub history_max_checks: usize,
+    pub history_max_age_days: Option<u64>,
+    // The burst of checks the domains screen runs on S.
+    pub soak_checks: usize,
+    pub soak_spacing_seconds: u64,
+    // Saves after checks failing this many times in a row raise an alert.
+    pub save_failure_threshold: usize,
+}
+
+impl Default for MonitorSettings {
+    fn default() -> Self {
+        let retention = HistoryRetention::default();
+        MonitorSettings {
+            count_rate_limited_as_down: false,
+            history_max_checks: retention.max_checks,
+            history_max_age_days: retention.max_age_days,
+            soak_checks: 20,
+            soak_spacing_seconds: 1,
+            save_failure_threshold: 3,
+        }
+    }
+}
+
+impl MonitorSettings {
+    // Keeping no history at all would leave nothing to show or compare.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.history_max_checks == 0 || self.history_max_age_days == Some(0) {
+            return Err("History retention under [monitor] must be a positive number".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn history_retention(&self) -> HistoryRetention {
+        HistoryRetention {
+            max_checks: self.history_max_checks,
+            max_age_days: self.history_max_age_days,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BudgetSettings {
+    // Unset means no limit.
+    pub max_requests_per_minute: Option<usize>,
+    pub max_requests_per_host_per_minute: Option<usize>,
+    // Scheduled checks per minute past which the domain list warns, the
+    // limits above when unset.
+    pub warn_checks_per_minute: Option<usize>,
+    pub warn_checks_per_host_per_minute: Option<usize>,
+}
+
+impl BudgetSettings {
+    pub fn warn_total(&self) -> Option<usize> {
+        self.warn_checks_per_minute.or(self.max_requests_per_minute)
+    }
+
+    pub fn warn_per_host(&self) -> Option<usize> {
+        self.warn_checks_per_host_per_minute
+            .or(self.max_requests_per_host_per_minute)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    // e.g. "de-DE" for "1.234,5ms" and "01.03.2025 12:00:00", or "system"
+    // to follow LC_ALL/LC_NUMERIC/LANG. ISO-style formats when unset.
+    pub locale: Locale,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerSettings {
+    // Also serve the API and dashboard while the TUI is running.
+    pub enabled: bool,
+    pub address: String,
+    // Unset tokens leave the matching endpoints open, see auth.rs.
+    pub read_token: Option<String>,
+    pub admin_token: Option<String>,
+    // Only lets CI/CD pipelines create and update monitors through
+    // POST /hooks/deploy, so they don't need the admin token.
+    pub deploy_token: Option<String>,
+    // Serve /badge/{id}.svg without a token, so READMEs can embed them.
+    pub public_badges: bool,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        ServerSettings {
+            enabled: false,
+            address: "127.0.0.1:8090".to_string(),
+            read_token: None,
+            admin_token: None,
+            deploy_token: None,
+            public_badges: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LoggingSettings {
+    // What the log viewer and log/debug.log get at startup, changeable while
+    // running from the settings screen.
+    pub level: LogLevel,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    // Where to send check and storage spans over OTLP/HTTP, e.g.
+    // "http://localhost:4318/v1/traces". Needs the otel feature.
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        TelemetrySettings {
+            otlp_endpoint: None,
+            service_name: "upquack".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SigningSettings {
+    // Sign every check record so `upquack verify-history` can prove the
+    // history wasn't edited.
+    pub enabled: bool,
+    pub key_path: String,
+}
+
+impl Default for SigningSettings {
+    fn default() -> Self {
+        SigningSettings {
+            enabled: false,
+            key_path: "db/signing.key".to_string(),
+        }
+    }
+}
+
+// History kept per monitor in low resource mode, unless a monitor sets its own.
+pub const LOW_RESOURCE_HISTORY_MAX_CHECKS: usize = 20;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PerformanceSettings {
+    // For small machines like a Raspberry Pi Zero: less history in memory,
+    // fewer redraws, no charts and smaller event buffers.
+    pub low_resource: bool,
+}
+
+// Preset monitor settings the add form can start from.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MonitorTemplate {
+    pub name: String,
+    pub interval_seconds: Option<u64>,
+    pub timeout_seconds: Option<u64>,
+    #[serde(default, alias = "http_method")]
+    pub method: Option<HttpMethod>,
+    #[serde(default, alias = "expected_status_codes")]
+    pub expected_status: Vec<StatusRange>,
+    #[serde(default)]
+    pub keyword: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+// The group of a discovery source's monitors starts with this, apply leaves
+// them to the source.
+pub const DISCOVERY_GROUP_PREFIX: &str = "discovery:";
+
+// A service discovery endpoint whose targets are kept in step with a group of
+// monitors: new ones are added from the template, vanished ones removed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscoverySource {
+    pub name: String,
+    pub kind: DiscoveryKind,
+    // The http_sd endpoint, or the Consul agent, e.g. http://consul:8500.
+    pub url: String,
+    // The Consul service to list.
+    pub service: Option<String>,
+    // How a target (host:port) becomes a URL to check.
+    #[serde(default = "default_discovery_scheme")]
+    pub scheme: String,
+    #[serde(default)]
+    pub path: String,
+    pub template: Option<String>,
+    #[serde(default = "default_discovery_refresh_seconds")]
+    pub refresh_seconds: u64,
+    // Sent as a bearer token, or as X-Consul-Token to Consul.
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscoveryKind {
+    Prometheus,
+    Consul,
+}
+
+fn default_discovery_scheme() -> String {
+    "http".to_string()
+}
+
+fn default_discovery_refresh_seconds() -> u64 {
+    60
+}
+
+impl DiscoverySource {
+    // The group the discovered monitors are managed as.
+    pub fn group(&self) -> String {
+        format!("{}{}", DISCOVERY_GROUP_PREFIX, self.name)
+    }
+}
+
+// The login for a BMC's Redfish API, matched to redfish:// monitors by host.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BmcSettings {
+    // As written in the monitor's URL, with the port if it has one.
+    pub host: String,
+    pub username: String,
+    pub password: String,
+    // BMCs mostly come with self-signed certificates.
+    #[serde(default)]
+    pub accept_invalid_certificates: bool,
+}
+
+// The credentials for an snmp:// monitor's agent, matched by host. A
+// community means SNMPv2c, a username SNMPv3: authenticated when there's an
+// auth_password, and encrypted with AES-128 when there's a privacy_password too.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SnmpSettings {
+    // As written in the monitor's URL, with the port if it has one.
+    pub host: String,
+    pub community: Option<String>,
+    pub username: Option<String>,
+    #[serde(default)]
+    pub auth_protocol: SnmpAuthProtocol,
+    pub auth_password: Option<String>,
+    pub privacy_password: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnmpAuthProtocol {
+    #[default]
+    Sha,
+    Sha256,
+}
+
+// The login for an ftp:// monitor's server, matched by host. Servers without
+// one are logged in to anonymously.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FtpSettings {
+    // As written in the monitor's URL, with the port if it has one.
+    pub host: String,
+    pub username: String,
+    pub password: String,
+}
+
+pub fn get_configuration(profile: &Profile) -> io::Result<Settings> {
+    let mut settings = load_configuration(&profile.config_path())?;
+    settings.profile = profile.clone();
+    apply_low_resource(&mut settings);
+    resolve_secrets(&mut settings)?;
+    Ok(settings)
+}
+
+fn apply_low_resource(settings: &mut Settings) {
+    if settings.performance.low_resource {
+        let monitor = &mut settings.monitor;
+        monitor.history_max_checks = monitor
+            .history_max_checks
+            .min(LOW_RESOURCE_HISTORY_MAX_CHECKS);
+    }
+}
+
+// Swaps "secret:<name>" values for the secret itself.
+fn resolve_secrets(settings: &mut Settings) -> io::Result<()> {
+    let mut resolver = SecretResolver::new(&settings.profile);
+    let server = &mut settings.server;
+    let discovery_tokens = settings.discovery.iter_mut().map(|s| &mut s.token);
+    let bmc_passwords = settings.bmc.iter_mut().map(|b| &mut b.password);
+    let ftp_passwords = settings.ftp.iter_mut().map(|f| &mut f.password);
+    let snmp_secrets = settings.snmp.iter_mut().flat_map(|s| {
+        [
+            &mut s.community,
+            &mut s.auth_password,
+            &mut s.privacy_password,
+        ]
+    });
+
+    for value in [
+        &mut server.read_token,
+        &mut server.admin_token,
+        &mut server.deploy_token,
+    ]
+    .into_iter()
+    .chain(discovery_tokens)
+    .chain(snmp_secrets)
+    .flatten()
+    .chain(bmc_passwords)
+    .chain(ftp_passwords)
+    {
+        *value = resolver.resolve(value)?;
+    }
+    Ok(())
+}
+
+fn load_configuration(path: &Path) -> io::Result<Settings> {
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let invalid_data = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+    let raw_config = fs::read_to_string(path)?;
+    let mut config: toml::Value =
+        toml::from_str(&raw_config).map_err(|e| invalid_data(e.to_string()))?;
+
+    expand_env_vars(&mut config, &|name| std::env::var(name).ok()).map_err(invalid_data)?;
+    let settings: Settings = config
+        .try_into()
+        .map_err(|e: toml::de::Error| invalid_data(e.to_string()))?;
+    settings.latency.validate().map_err(invalid_data)?;
+    settings.monitor.validate().map_err(invalid_data)?;
+    // A template is checked the way a monitor made from it would be, so a bad
+    // one fails here rather than on each add, deploy or discovery.
+    for template in &settings.templates {
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.apply_template(template);
+        domain
+            .validate()
+            .map_err(|e| invalid_data(format!("Template {}: {}", template.name, e)))?;
+    }
+    Ok(settings)
+}
+
+// Expands ${NAME} (or ${NAME:-fallback}) in every string value, so secrets and
+// per-deployment addresses can come from the environment. Expanding after
+// parsing keeps quotes or newlines in a variable from breaking the TOML.
+fn expand_env_vars(
+    value: &mut toml::Value,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    match value {
+        toml::Value::String(s) => *s = expand_env_string(s, lookup)?,
+        toml::Value::Array(values) => {
+            for value in values {
+                expand_env_vars(value, lookup)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, value) in table.iter_mut() {
+                expand_env_vars(value, lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand_env_string(s: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut expanded = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let placeholder = &rest[start + 2..];
+        let end = placeholder
+            .find('}')
+            .ok_or_else(|| format!("Unclosed \"${{\" in config value \"{}\"", s))?;
+
+        let (name, fallback) = match placeholder[..end].split_once(":-") {
+            Some((name, fallback)) => (name, Some(fallback)),
+            None => (&placeholder[..end], None),
+        };
+        let value = lookup(name)
+            .or_else(|| fallback.map(str::to_string))
+            .ok_or_else(|| {
+                format!(
+                    "Environment variable {} used in the config is not set",
+                    name
+                )
+            })?;
+
+        expanded.push_str(&value);
+        rest = &placeholder[end + 1..];
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::latency::LatencyLevel;
+
+    #[test]
+    fn empty_config_uses_defaults() {
+        let settings: Settings = toml::from_str("").expect("Failed to parse empty config");
+        assert!(!settings.monitor.count_rate_limited_as_down);
+        assert!(settings.budget.max_requests_per_minute.is_none());
+        assert!(settings.templates.is_empty());
+        assert!(settings.discovery.is_empty());
+        assert!(!settings.server.enabled);
+        assert_eq!(settings.server.address, "127.0.0.1:8090");
+        assert!(settings.server.read_token.is_none());
+        assert!(settings.server.admin_token.is_none());
+        assert!(!settings.signing.enabled);
+        assert!(!settings.performance.low_resource);
+    }
+
+    #[test]
+    fn low_resource_mode_keeps_less_history() {
+        let mut settings: Settings = toml::from_str(
+            r#"
+            [monitor]
+            history_max_checks = 500
+
+            [performance]
+            low_resource = true
+            "#,
+        )
+        .expect("Failed to parse performance settings");
+
+        apply_low_resource(&mut settings);
+        assert_eq!(
+            settings.monitor.history_max_checks,
+            LOW_RESOURCE_HISTORY_MAX_CHECKS
+        );
+    }
+
+    #[test]
+    fn latency_thresholds_and_overrides() {
+        let settings: Settings = toml::from_str(
+            r#"
+            [latency]
+            unit = "s"
+            warn_below_ms = 2000
+            "#,
+        )
+        .expect("Failed to parse latency settings");
+
+        let latency = settings.latency;
+        assert_eq!(latency.level(150), LatencyLevel::Good);
+        assert_eq!(latency.level(1500), LatencyLevel::Warn);
+        assert_eq!(latency.level(2000), LatencyLevel::Slow);
+        assert_eq!(latency.format(1500, &Locale::default()), "1.50s");
+        assert_eq!(latency.format(1500, &Locale::parse("de-DE")), "1,50s");
+
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.latency_good_below_ms = Some(50);
+        assert_eq!(
+            domain.latency_settings(latency).level(150),
+            LatencyLevel::Warn
+        );
+    }
+
+    #[test]
+    fn templates_are_parsed() {
+        let settings: Settings = toml::from_str(
+            r#"
+            [[templates]]
+            name = "API health"
+            interval_seconds = 30
+            timeout_seconds = 5
+
+            [[templates]]
+            name = "Slow page"
+            "#,
+        )
+        .expect("Failed to parse templates");
+
+        assert_eq!(settings.templates.len(), 2);
+        assert_eq!(settings.templates[0].name, "API health");
+        assert_eq!(settings.templates[0].timeout_seconds, Some(5));
+        assert_eq!(settings.templates[1].interval_seconds, None);
+    }
+
+    #[test]
+    fn env_vars_are_expanded() {
+        let lookup = |name: &str| (name == "UPQUACK_HOST").then(|| "0.0.0.0".to_string());
+
+        assert_eq!(
+            expand_env_string("${UPQUACK_HOST}:8090", &lookup).unwrap(),
+            "0.0.0.0:8090"
+        );
+        assert_eq!(
+            expand_env_string("${UPQUACK_PORT:-8090}", &lookup).unwrap(),
+            "8090"
+        );
+        assert_eq!(
+            expand_env_string("no placeholders", &lookup).unwrap(),
+            "no placeholders"
+        );
+        assert!(expand_env_string("${UPQUACK_TOKEN}", &lookup).is_err());
+        assert!(expand_env_string("${UPQUACK_HOST", &lookup).is_err());
+    }
+
+    #[test]
+    fn env_vars_expand_inside_tables_and_arrays() {
+        let mut config: toml::Value = toml::from_str(
+            r#"
+            [server]
+            admin_token = "${ADMIN_TOKEN}"
+
+            [[templates]]
+            name = "${TEAM} checks"
+            "#,
+        )
+        .unwrap();
+        let lookup = |name: &str| match name {
+            "ADMIN_TOKEN" => Some("s3cret \"quoted\"".to_string()),
+            "TEAM" => Some("Payments".to_string()),
+            _ => None,
+        };
+
+        expand_env_vars(&mut config, &lookup).expect("Expansion should succeed");
+        let settings: Settings = config.try_into().expect("Config should still parse");
+        assert_eq!(
+            settings.server.admin_token.as_deref(),
+            Some("s3cret \"quoted\"")
+        );
+        assert_eq!(settings.templates[0].name, "Payments checks");
+    }
+
+    #[test]
+    fn templates_set_the_check() {
+        let settings: Settings = toml::from_str(
+            r#"
+            [[templates]]
+            name = "api"
+            timeout_seconds = 5
+            method = "POST"
+            expected_status = ["200", "201-204"]
+            keyword = "ok"
+            headers = { X-Api-Key = "secret:api" }
+            "#,
+        )
+        .expect("Failed to parse template");
+
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain
+            .headers
+            .insert("Accept".to_string(), "text/plain".to_string());
+        domain.apply_template(&settings.templates[0]);
+        assert_eq!(domain.interval_seconds, 60);
+        assert_eq!(domain.timeout_seconds, Some(5));
+        assert_eq!(domain.http_method, Some(HttpMethod::Post));
+        assert_eq!(
+            domain.expected_status_codes,
+            vec![
+                StatusRange { min: 200, max: 200 },
+                StatusRange { min: 201, max: 204 }
+            ]
+        );
+        assert_eq!(domain.keyword.as_deref(), Some("ok"));
+        assert_eq!(domain.headers.len(), 2);
+        assert_eq!(domain.headers["X-Api-Key"], "secret:api");
+
+        // Fields the template leaves out aren't touched.
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.keyword = Some("healthy".to_string());
+        domain.apply_template(&MonitorTemplate::default());
+        assert_eq!(domain.keyword.as_deref(), Some("healthy"));
+    }
+
+    #[test]
+    fn missing_config_file_uses_defaults() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let settings = load_configuration(&temp_dir.path().join("missing.toml"))
+            .expect("Missing config should not be an error");
+        assert!(!settings.monitor.count_rate_limited_as_down);
+    }
+
+    #[test]
+    fn invalid_config_is_rejected() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let config_path = temp_dir.path().join("production.toml");
+        fs::write(
+            &config_path,
+            "[monitor]\ncount_rate_limited_as_down = \"yes\"",
+        )
+        .expect("Failed to write config");
+
+        let err = load_configuration(&config_path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        fs::write(
+            &config_path,
+            "[latency]\ngood_below_ms = 1000\nwarn_below_ms = 500",
+        )
+        .expect("Failed to write config");
+        let err = load_configuration(&config_path).unwrap_err();
+        assert!(err.to_string().contains("must be below the warn threshold"));
+
+        fs::write(&config_path, "[monitor]\nhistory_max_checks = 0")
+            .expect("Failed to write config");
+        let err = load_configuration(&config_path).unwrap_err();
+        assert!(err.to_string().contains("History retention"));
+
+        fs::write(
+            &config_path,
+            "[[templates]]\nname = \"api\"\nkeyword = \"\"",
+        )
+        .expect("Failed to write config");
+        let err = load_configuration(&config_path).unwrap_err();
+        assert_eq!(err.to_string(), "Template api: Keyword is empty");
+    }
+}
@@ -0,0 +1,273 @@
+use crate::profile::Profile;
+use crate::store::load_domains;
+use crate::ui::domains::DomainStatus;
+use chrono::Utc;
+use flate2::{Compression, write::GzEncoder};
+use std::{
+    fs::{self, File},
+    io,
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+};
+
+// Only the end of each log goes in, that's where the interesting part is.
+const LOG_TAIL_LINES: usize = 2000;
+
+static REDACTED: &str = "[redacted]";
+
+// Config keys made of any of these words (split on "_" and "-") never leave
+// the machine.
+static SENSITIVE_WORDS: [&str; 8] = [
+    "token",
+    "secret",
+    "password",
+    "passphrase",
+    "webhook",
+    "auth",
+    "authorization",
+    "credentials",
+];
+
+pub fn run_diagnose(profile: &Profile, output: Option<&Path>) -> io::Result<()> {
+    let path = write_bundle(profile, output, None)?;
+    println!(
+        "Wrote {}, attach it to your bug report. Secrets in the config are redacted, \
+         but have a look at the logs inside before sharing it publicly.",
+        path.display()
+    );
+    Ok(())
+}
+
+// Writes a bundle into the profile's data directory whenever upquack panics,
+// after the previous hook (Rust's default, which prints the panic) has run.
+// This is installed before ratatui::init, whose own hook restores the
+// terminal first and then calls this one.
+pub fn install_panic_hook(profile: &Profile) {
+    let profile = profile.clone();
+    let previous_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info: &PanicHookInfo| {
+        previous_hook(info);
+        tracing::error!("upquack panicked: {}", info);
+
+        let path = profile.data_dir().join(bundle_name());
+        let written = fs::create_dir_all(profile.data_dir())
+            .and_then(|_| write_bundle(&profile, Some(&path), Some(&info.to_string())));
+        match written {
+            Ok(path) => eprintln!(
+                "upquack crashed. A diagnostics bundle was written to {}, please attach it to a bug report.",
+                path.display()
+            ),
+            Err(e) => eprintln!(
+                "upquack crashed and writing a diagnostics bundle failed: {}",
+                e
+            ),
+        }
+    }));
+}
+
+fn write_bundle(
+    profile: &Profile,
+    output: Option<&Path>,
+    panic_message: Option<&str>,
+) -> io::Result<PathBuf> {
+    let path = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(bundle_name()));
+
+    let mut bundle =
+        tar::Builder::new(GzEncoder::new(File::create(&path)?, Compression::default()));
+    let mut add = |name: &str, contents: String| -> io::Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(Utc::now().timestamp().max(0) as u64);
+        header.set_cksum();
+        bundle.append_data(&mut header, name, contents.as_bytes())
+    };
+
+    add("version.txt", version_info(profile))?;
+    add("config.toml", redacted_config(&profile.config_path()))?;
+    add("store-stats.txt", store_stats(&profile.store_path()))?;
+    for log_file in [profile.debug_log_path(), profile.error_log_path()] {
+        let name = format!(
+            "logs/{}",
+            log_file.file_name().unwrap_or_default().to_string_lossy()
+        );
+        add(&name, log_tail(&log_file))?;
+    }
+    if let Some(panic_message) = panic_message {
+        add("panic.txt", panic_message.to_string())?;
+    }
+
+    bundle.into_inner()?.finish()?;
+    Ok(path)
+}
+
+fn bundle_name() -> String {
+    format!(
+        "upquack-diagnostics-{}.tar.gz",
+        Utc::now().format("%Y%m%d-%H%M%S")
+    )
+}
+
+fn version_info(profile: &Profile) -> String {
+    format!(
+        "upquack {}\nos: {} ({})\nprofile: {}\ncreated: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        profile.name(),
+        Utc::now().to_rfc3339()
+    )
+}
+
+fn redacted_config(path: &Path) -> String {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) => return format!("# No config at {}: {}\n", path.display(), e),
+    };
+
+    match toml::from_str::<toml::Value>(&raw) {
+        Ok(mut config) => {
+            redact(&mut config);
+            toml::to_string_pretty(&config).unwrap_or_default()
+        }
+        // Can't tell secrets apart in a broken file, so leave it all out.
+        Err(e) => format!("# Config at {} does not parse: {}\n", path.display(), e),
+    }
+}
+
+fn redact(value: &mut toml::Value) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                let sensitive = key
+                    .to_lowercase()
+                    .split(['_', '-'])
+                    .any(|word| SENSITIVE_WORDS.contains(&word));
+                if sensitive && !value.is_table() {
+                    *value = toml::Value::String(REDACTED.to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        toml::Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+// Counts only; monitored URLs can be private so they stay out.
+fn store_stats(store_path: &str) -> String {
+    let domains = match load_domains(store_path) {
+        Ok(domains) => domains,
+        Err(e) => return format!("Could not load {}: {}\n", store_path, e),
+    };
+    let size = fs::metadata(store_path).map(|m| m.len()).unwrap_or(0);
+    let checks: Vec<_> = domains.iter().flat_map(|d| &d.check_history).collect();
+
+    let count =
+        |matches: fn(&DomainStatus) -> bool| checks.iter().filter(|c| matches(&c.status)).count();
+    let oldest = checks.iter().map(|c| c.timestamp).min();
+    let newest = checks.iter().map(|c| c.timestamp).max();
+
+    format!(
+        "store: {} ({} bytes)\ndomains: {}\nchecks: {} (up {}, down {}, error {}, rate limited {}, unknown {})\noldest check: {}\nnewest check: {}\n",
+        store_path,
+        size,
+        domains.len(),
+        checks.len(),
+        count(|s| matches!(s, DomainStatus::Up)),
+        count(|s| matches!(s, DomainStatus::Down)),
+        count(|s| matches!(s, DomainStatus::Error(_))),
+        count(|s| matches!(s, DomainStatus::RateLimited)),
+        count(|s| matches!(s, DomainStatus::Unknown)),
+        oldest.map_or("-".to_string(), |t| t.to_rfc3339()),
+        newest.map_or("-".to_string(), |t| t.to_rfc3339()),
+    )
+}
+
+fn log_tail(path: &Path) -> String {
+    match fs::read_to_string(path) {
+        Ok(log) => {
+            let lines: Vec<&str> = log.lines().collect();
+            let start = lines.len().saturating_sub(LOG_TAIL_LINES);
+            lines[start..].join("\n")
+        }
+        Err(e) => format!("Could not read {}: {}\n", path.display(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_sensitive_config_values() {
+        let mut config: toml::Value = toml::from_str(
+            r#"
+            [server]
+            address = "127.0.0.1:8090"
+            read_token = "abc"
+            admin_token = "secret:api-admin"
+
+            [notifications]
+            webhook_url = "https://hooks.example.com/T000/B000"
+            smtp_password = "hunter2"
+
+            [templates.headers]
+            Authorization = "Bearer eyJhbGciOi"
+            "#,
+        )
+        .unwrap();
+
+        redact(&mut config);
+        let redacted = toml::to_string(&config).unwrap();
+
+        assert!(redacted.contains("127.0.0.1:8090"));
+        for leaked in [
+            "abc",
+            "api-admin",
+            "hooks.example.com",
+            "hunter2",
+            "eyJhbGciOi",
+        ] {
+            assert!(!redacted.contains(leaked), "{} leaked", leaked);
+        }
+    }
+
+    #[test]
+    fn bundle_contains_expected_files() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let output = temp_dir.path().join("bundle.tar.gz");
+
+        write_bundle(&Profile::default(), Some(&output), Some("boom"))
+            .expect("Failed to write bundle");
+
+        let archive = File::open(&output).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+        let names: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                entry
+                    .unwrap()
+                    .path()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+
+        for expected in [
+            "version.txt",
+            "config.toml",
+            "store-stats.txt",
+            "logs/debug.log",
+            "panic.txt",
+        ] {
+            assert!(names.iter().any(|n| n == expected), "{} missing", expected);
+        }
+    }
+}
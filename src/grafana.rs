@@ -0,0 +1,232 @@
+use crate::series::{SeriesPoint, latency_series};
+use crate::ui::domains::MonitoredDomain;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Points per series when Grafana doesn't say how many fit the panel.
+const DEFAULT_DATA_POINTS: usize = 200;
+
+// What a series can chart, the metric picked in Grafana's query editor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    ResponseTime,
+    MaxResponseTime,
+    Uptime,
+    Checks,
+}
+
+impl Metric {
+    const ALL: [Metric; 4] = [
+        Metric::ResponseTime,
+        Metric::MaxResponseTime,
+        Metric::Uptime,
+        Metric::Checks,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Metric::ResponseTime => "Average response time (ms)",
+            Metric::MaxResponseTime => "Max response time (ms)",
+            Metric::Uptime => "Uptime (%)",
+            Metric::Checks => "Checks",
+        }
+    }
+
+    fn value(self, point: &SeriesPoint) -> Option<f64> {
+        match self {
+            Metric::ResponseTime => point.average_response_time_ms,
+            Metric::MaxResponseTime => point.max_response_time_ms.map(|ms| ms as f64),
+            Metric::Uptime => point.uptime_percentage,
+            Metric::Checks => Some(point.checks as f64),
+        }
+    }
+}
+
+// An entry of the metric picker (POST /grafana/metrics).
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MetricOption {
+    label: &'static str,
+    value: Metric,
+    payloads: Vec<PayloadOption>,
+}
+
+// The domain picker shown under each metric.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PayloadOption {
+    label: &'static str,
+    name: &'static str,
+    r#type: &'static str,
+    placeholder: &'static str,
+    options: Vec<SelectOption>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SelectOption {
+    label: String,
+    value: String,
+}
+
+// Body of POST /grafana/query, the parts of it we use.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryRequest {
+    pub range: QueryRange,
+    pub targets: Vec<QueryTarget>,
+    pub max_data_points: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct QueryRange {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct QueryTarget {
+    // Unset while the query is still being put together in the editor.
+    pub target: Option<Metric>,
+    #[serde(default)]
+    pub payload: TargetPayload,
+    #[serde(default)]
+    pub hide: bool,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct TargetPayload {
+    // A domain id or URL, every domain when unset.
+    pub domain: Option<String>,
+}
+
+// One series of the answer, [value, unix milliseconds] per point with
+// null values for buckets without checks, so gaps show.
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+pub struct TimeSeries {
+    pub target: String,
+    #[schema(value_type = Vec<Vec<f64>>)]
+    pub datapoints: Vec<(Option<f64>, i64)>,
+}
+
+pub fn metric_options(domains: &[MonitoredDomain]) -> Vec<MetricOption> {
+    Metric::ALL
+        .iter()
+        .map(|&metric| MetricOption {
+            label: metric.label(),
+            value: metric,
+            payloads: vec![PayloadOption {
+                label: "Domain",
+                name: "domain",
+                r#type: "select",
+                placeholder: "All domains",
+                options: domains
+                    .iter()
+                    .map(|d| SelectOption {
+                        label: d.name().to_string(),
+                        value: d.id.to_string(),
+                    })
+                    .collect(),
+            }],
+        })
+        .collect()
+}
+
+// A series per target and domain, bucketed like the dashboard's chart.
+pub fn query(
+    domains: &[MonitoredDomain],
+    request: &QueryRequest,
+    count_rate_limited_as_down: bool,
+) -> Result<Vec<TimeSeries>, String> {
+    let (from, to) = (request.range.from, request.range.to);
+    if from >= to {
+        return Err("range.from has to be before range.to".to_string());
+    }
+    let max_points = request.max_data_points.unwrap_or(DEFAULT_DATA_POINTS);
+
+    let mut series = Vec::new();
+    for target in request.targets.iter().filter(|t| !t.hide) {
+        let Some(metric) = target.target else {
+            continue;
+        };
+        let selected: Vec<&MonitoredDomain> = match target.payload.domain.as_deref() {
+            None | Some("") => domains.iter().collect(),
+            Some(wanted) => {
+                let domain = domains
+                    .iter()
+                    .find(|d| d.id.to_string() == wanted || &*d.url == wanted)
+                    .ok_or_else(|| format!("No domain {}", wanted))?;
+                vec![domain]
+            }
+        };
+        for domain in selected {
+            let points =
+                latency_series(domain, from, to, max_points, count_rate_limited_as_down).points;
+            series.push(TimeSeries {
+                target: domain.name().to_string(),
+                datapoints: points
+                    .iter()
+                    .map(|point| (metric.value(point), point.at.timestamp_millis()))
+                    .collect(),
+            });
+        }
+    }
+    Ok(series)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::domains::{CheckStatus, DomainStatus};
+    use chrono::Duration;
+
+    #[test]
+    fn answers_grafana_queries() {
+        let from = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut api = MonitoredDomain::new("https://api.example.com");
+        api.display_name = Some("API".to_string());
+        for (minute, status) in [(0, DomainStatus::Up), (1, DomainStatus::Down)] {
+            api.check_history.push(CheckStatus {
+                response_time_ms: Some(100 + minute as u64 * 100),
+                ..CheckStatus::new(from + Duration::minutes(minute), status)
+            });
+        }
+        let web = MonitoredDomain::new("https://www.example.com");
+        let domains = vec![api, web];
+
+        let options = metric_options(&domains);
+        assert_eq!(options.len(), Metric::ALL.len());
+        assert_eq!(options[0].payloads[0].options[0].label, "API");
+
+        let request: QueryRequest = serde_json::from_value(serde_json::json!({
+            "range": {"from": "2023-11-14T22:13:20Z", "to": "2023-11-14T22:16:20Z", "raw": {}},
+            "maxDataPoints": 3,
+            "targets": [
+                {"refId": "A", "target": "uptime", "payload": {"domain": "https://api.example.com"}},
+                {"refId": "B", "target": "response_time"},
+                {"refId": "C"},
+                {"refId": "D", "target": "checks", "hide": true}
+            ]
+        }))
+        .unwrap();
+        let series = query(&domains, &request, false).unwrap();
+        assert_eq!(series.len(), 3);
+        assert_eq!(series[0].target, "API");
+        assert_eq!(
+            series[0].datapoints,
+            vec![
+                (Some(100.0), from.timestamp_millis()),
+                (Some(0.0), (from + Duration::minutes(1)).timestamp_millis()),
+                (None, (from + Duration::minutes(2)).timestamp_millis()),
+            ]
+        );
+        assert_eq!(series[1].datapoints[1].0, Some(200.0));
+        assert_eq!(series[2].target, "https://www.example.com");
+
+        let unknown: QueryRequest = serde_json::from_value(serde_json::json!({
+            "range": {"from": "2023-11-14T22:13:20Z", "to": "2023-11-14T22:16:20Z"},
+            "targets": [{"target": "uptime", "payload": {"domain": "https://nope.example.com"}}]
+        }))
+        .unwrap();
+        assert!(query(&domains, &unknown, false).is_err());
+    }
+}
@@ -0,0 +1,452 @@
+use crate::incident::{self, incidents};
+use crate::monitor::MonitorHandle;
+use crate::ui::domains::{CheckStatus, DomainStatus, HttpCode, MonitoredDomain};
+use async_graphql::{
+    ComplexObject, Context, EmptyMutation, EmptySubscription, Enum, InputObject, Object, Schema,
+    SimpleObject,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+pub type UpquackSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+// The most items any list returns at once.
+const MAX_PAGE_SIZE: usize = 500;
+// Deep enough for domain -> incidents -> checks with room to spare.
+const MAX_QUERY_DEPTH: usize = 8;
+
+// The same data as the REST API, read-only, for dashboards that want nested
+// queries in one request.
+pub fn schema(monitor: MonitorHandle, count_rate_limited_as_down: bool) -> UpquackSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(monitor)
+        .data(UptimeRules {
+            count_rate_limited_as_down,
+        })
+        .limit_depth(MAX_QUERY_DEPTH)
+        .finish()
+}
+
+struct UptimeRules {
+    count_rate_limited_as_down: bool,
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// Monitored domains matching the filter, in the order they were added.
+    async fn domains(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<DomainFilter>,
+        #[graphql(default)] offset: usize,
+        #[graphql(default = 50)] limit: usize,
+    ) -> DomainPage {
+        let monitor = ctx.data_unchecked::<MonitorHandle>();
+        let domains_guard = monitor.domains.lock().unwrap();
+        let matching: Vec<&MonitoredDomain> = domains_guard
+            .iter()
+            .filter(|d| filter.as_ref().is_none_or(|f| f.matches(d)))
+            .collect();
+
+        DomainPage {
+            total: matching.len(),
+            items: page(matching, offset, limit)
+                .map(|d| Domain(d.clone()))
+                .collect(),
+        }
+    }
+
+    async fn domain(&self, ctx: &Context<'_>, id: Uuid) -> Option<Domain> {
+        let monitor = ctx.data_unchecked::<MonitorHandle>();
+        let domains_guard = monitor.domains.lock().unwrap();
+        domains_guard
+            .iter()
+            .find(|d| d.id == id)
+            .map(|d| Domain(d.clone()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+enum Status {
+    Up,
+    Degraded,
+    Down,
+    Unknown,
+    RateLimited,
+    Error,
+}
+
+impl From<&DomainStatus> for Status {
+    fn from(status: &DomainStatus) -> Self {
+        match status {
+            DomainStatus::Up => Status::Up,
+            DomainStatus::Degraded => Status::Degraded,
+            DomainStatus::Down => Status::Down,
+            DomainStatus::Unknown => Status::Unknown,
+            DomainStatus::RateLimited => Status::RateLimited,
+            DomainStatus::Error(_) => Status::Error,
+        }
+    }
+}
+
+#[derive(Debug, Default, InputObject)]
+struct DomainFilter {
+    url_contains: Option<String>,
+    owner: Option<String>,
+    /// The status of the latest check.
+    status: Option<Status>,
+}
+
+impl DomainFilter {
+    fn matches(&self, domain: &MonitoredDomain) -> bool {
+        self.url_contains
+            .as_ref()
+            .is_none_or(|part| domain.url.contains(part.as_str()))
+            && self
+                .owner
+                .as_ref()
+                .is_none_or(|owner| domain.owner.as_ref() == Some(owner))
+            && self.status.is_none_or(|status| {
+                domain
+                    .check_history
+                    .last()
+                    .is_some_and(|c| Status::from(&c.status) == status)
+            })
+    }
+}
+
+#[derive(SimpleObject)]
+struct DomainPage {
+    /// Matching domains, on this page or not.
+    total: usize,
+    items: Vec<Domain>,
+}
+
+struct Domain(MonitoredDomain);
+
+#[Object]
+impl Domain {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn url(&self) -> &str {
+        &self.0.url
+    }
+
+    async fn display_name(&self) -> Option<&str> {
+        self.0.display_name.as_deref()
+    }
+
+    async fn owner(&self) -> Option<&str> {
+        self.0.owner.as_deref()
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.tags
+    }
+
+    /// Free-form key/value pairs, e.g. a runbook or dashboard link.
+    async fn metadata(&self) -> Vec<MetadataEntry> {
+        self.0
+            .metadata
+            .iter()
+            .map(|(key, value)| MetadataEntry {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect()
+    }
+
+    async fn interval_seconds(&self) -> u64 {
+        self.0.interval_seconds
+    }
+
+    async fn timeout_seconds(&self) -> Option<u64> {
+        self.0.timeout_seconds
+    }
+
+    async fn uptime_percentage(&self, ctx: &Context<'_>) -> Option<f64> {
+        let rules = ctx.data_unchecked::<UptimeRules>();
+        self.0.uptime_percentage(rules.count_rate_limited_as_down)
+    }
+
+    async fn last_check(&self) -> Option<Check> {
+        self.0.check_history.last().map(Check::from)
+    }
+
+    /// Checks in [since, until), newest first.
+    async fn checks(
+        &self,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        status: Option<Status>,
+        #[graphql(default)] offset: usize,
+        #[graphql(default = 100)] limit: usize,
+    ) -> CheckPage {
+        let matching: Vec<&CheckStatus> = self
+            .0
+            .check_history
+            .iter()
+            .rev()
+            .filter(|c| in_period(c.timestamp, since, until))
+            .filter(|c| status.is_none_or(|status| Status::from(&c.status) == status))
+            .collect();
+
+        CheckPage {
+            total: matching.len(),
+            items: page(matching, offset, limit).map(Check::from).collect(),
+        }
+    }
+
+    /// Runs of failed checks until the next Up one, newest first.
+    async fn incidents(
+        &self,
+        #[graphql(default)] ongoing_only: bool,
+        #[graphql(default)] offset: usize,
+        #[graphql(default = 20)] limit: usize,
+    ) -> Vec<Incident> {
+        let found: Vec<incident::Incident> = self
+            .0
+            .incidents()
+            .into_iter()
+            .rev()
+            .filter(|i| !ongoing_only || !i.is_closed())
+            .collect();
+
+        page(found, offset, limit)
+            .map(|found| Incident {
+                started_at: found.started_at,
+                ended_at: found.ended_at,
+                failed_checks: found.failed_checks,
+                first_error: found.first_error.as_deref().map(str::to_string),
+                note: self
+                    .0
+                    .incident_note(found.started_at)
+                    .map(|note| note.note.clone()),
+                checks: self
+                    .0
+                    .check_history
+                    .iter()
+                    .filter(|c| in_period(c.timestamp, Some(found.started_at), found.ended_at))
+                    .map(Check::from)
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Aggregates over the checks in [since, until).
+    async fn stats(
+        &self,
+        ctx: &Context<'_>,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Stats {
+        let rules = ctx.data_unchecked::<UptimeRules>();
+        let from = since.unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let to = until.unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let period = self
+            .0
+            .period_stats(from, to, rules.count_rate_limited_as_down);
+        let checks: Vec<CheckStatus> = self
+            .0
+            .check_history
+            .iter()
+            .filter(|c| in_period(c.timestamp, since, until))
+            .cloned()
+            .collect();
+
+        Stats {
+            check_count: checks.len(),
+            incident_count: incidents(&checks).len(),
+            uptime_percentage: period.uptime_percentage,
+            average_response_time_ms: period.average_response_time_ms,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+struct MetadataEntry {
+    key: String,
+    value: String,
+}
+
+#[derive(SimpleObject)]
+struct CheckPage {
+    total: usize,
+    items: Vec<Check>,
+}
+
+#[derive(SimpleObject)]
+struct Check {
+    timestamp: DateTime<Utc>,
+    status: Status,
+    /// Unset for timeouts, network errors and checks other than HTTP.
+    http_code: Option<u16>,
+    response_time_ms: Option<u64>,
+    error_message: Option<String>,
+    detail: Option<String>,
+}
+
+impl From<&CheckStatus> for Check {
+    fn from(check: &CheckStatus) -> Self {
+        Check {
+            timestamp: check.timestamp,
+            status: Status::from(&check.status),
+            http_code: match check.http_code {
+                Some(HttpCode::Ok) => Some(200),
+                Some(HttpCode::Err) => Some(500),
+                Some(HttpCode::Other(code)) => Some(code),
+                Some(HttpCode::Timeout | HttpCode::NetworkError) | None => None,
+            },
+            response_time_ms: check.response_time_ms,
+            error_message: check.error_message.as_deref().map(str::to_string),
+            detail: check.detail.as_deref().map(str::to_string),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+struct Incident {
+    started_at: DateTime<Utc>,
+    /// The first Up check after it, unset while it's ongoing.
+    ended_at: Option<DateTime<Utc>>,
+    failed_checks: usize,
+    first_error: Option<String>,
+    /// The postmortem note, if one was written.
+    note: Option<String>,
+    #[graphql(skip)]
+    checks: Vec<Check>,
+}
+
+#[ComplexObject]
+impl Incident {
+    /// The checks from its start until it ended, oldest first.
+    async fn checks(&self) -> &[Check] {
+        &self.checks
+    }
+}
+
+#[derive(SimpleObject)]
+struct Stats {
+    check_count: usize,
+    incident_count: usize,
+    uptime_percentage: Option<f64>,
+    average_response_time_ms: Option<f64>,
+}
+
+fn page<T>(items: Vec<T>, offset: usize, limit: usize) -> impl Iterator<Item = T> {
+    items
+        .into_iter()
+        .skip(offset)
+        .take(limit.min(MAX_PAGE_SIZE))
+}
+
+fn in_period(
+    timestamp: DateTime<Utc>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> bool {
+    since.is_none_or(|since| timestamp >= since) && until.is_none_or(|until| timestamp < until)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::RequestBudget;
+    use chrono::Duration;
+    use std::sync::{Arc, Mutex};
+
+    fn check(at: DateTime<Utc>, status: DomainStatus) -> CheckStatus {
+        CheckStatus {
+            http_code: Some(HttpCode::Ok),
+            response_time_ms: Some(40),
+            ..CheckStatus::new(at, status)
+        }
+    }
+
+    fn test_schema(domains: Vec<MonitoredDomain>) -> UpquackSchema {
+        let monitor = MonitorHandle::new(
+            Arc::new(Mutex::new(domains)),
+            "db/domains.json",
+            Arc::new(|_: &MonitoredDomain, _: &[CheckStatus]| Ok(())),
+            Arc::new(RequestBudget::default()),
+            tokio::sync::broadcast::channel(16).0,
+        );
+        schema(monitor, false)
+    }
+
+    #[tokio::test]
+    async fn nested_queries_reach_incident_checks() {
+        let start: DateTime<Utc> = "2025-03-01T12:00:00Z".parse().unwrap();
+        let mut api = MonitoredDomain::new("https://api.example.com");
+        api.owner = Some("payments".to_string());
+        api.check_history = vec![
+            check(start, DomainStatus::Up),
+            check(start + Duration::minutes(1), DomainStatus::Down),
+            check(start + Duration::minutes(2), DomainStatus::Down),
+            check(start + Duration::minutes(3), DomainStatus::Up),
+        ];
+        let www = MonitoredDomain::new("https://www.example.com");
+        let schema = test_schema(vec![api, www]);
+
+        let response = schema
+            .execute(
+                r#"{
+                    domains(filter: { owner: "payments" }) {
+                        total
+                        items {
+                            url
+                            incidents { failedChecks endedAt checks { status } }
+                            stats { checkCount incidentCount uptimePercentage }
+                            checks(limit: 1) { total items { status httpCode } }
+                        }
+                    }
+                }"#,
+            )
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        let domain = &data["domains"]["items"][0];
+
+        assert_eq!(data["domains"]["total"], 1);
+        assert_eq!(domain["url"], "https://api.example.com");
+        assert_eq!(domain["incidents"][0]["failedChecks"], 2);
+        assert_eq!(
+            domain["incidents"][0]["checks"],
+            serde_json::json!([{ "status": "DOWN" }, { "status": "DOWN" }])
+        );
+        assert_eq!(domain["stats"]["checkCount"], 4);
+        assert_eq!(domain["stats"]["incidentCount"], 1);
+        assert_eq!(domain["stats"]["uptimePercentage"], 50.0);
+        // Newest first, a page at a time.
+        assert_eq!(domain["checks"]["total"], 4);
+        assert_eq!(
+            domain["checks"]["items"],
+            serde_json::json!([{ "status": "UP", "httpCode": 200 }])
+        );
+    }
+
+    #[tokio::test]
+    async fn pages_and_lookups() {
+        let domains: Vec<MonitoredDomain> = (0..3)
+            .map(|i| MonitoredDomain::new(&format!("https://{}.example.com", i)))
+            .collect();
+        let id = domains[2].id;
+        let schema = test_schema(domains);
+
+        let response = schema
+            .execute(format!(
+                r#"{{ domains(offset: 1, limit: 1) {{ total items {{ url }} }} domain(id: "{}") {{ url }} }}"#,
+                id
+            ))
+            .await;
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["domains"]["total"], 3);
+        assert_eq!(data["domains"]["items"][0]["url"], "https://1.example.com");
+        assert_eq!(data["domain"]["url"], "https://2.example.com");
+    }
+}
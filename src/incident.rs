@@ -0,0 +1,259 @@
+use crate::store::load_domains;
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use crate::ui::locale::Locale;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path, sync::Arc};
+use utoipa::ToSchema;
+
+// A run of checks that weren't Up, from the first failing check until the
+// next Up one. Rate limited and unknown results neither start nor end one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Incident {
+    pub started_at: DateTime<Utc>,
+    // The first Up check after it, unset while it's still going on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<DateTime<Utc>>,
+    pub failed_checks: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub first_error: Option<Arc<str>>,
+}
+
+impl Incident {
+    pub fn is_closed(&self) -> bool {
+        self.ended_at.is_some()
+    }
+
+    pub fn duration(&self, now: DateTime<Utc>) -> chrono::Duration {
+        self.ended_at.unwrap_or(now) - self.started_at
+    }
+}
+
+// A postmortem note on a closed incident, tied to the time the incident
+// started.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct IncidentNote {
+    pub incident_started_at: DateTime<Utc>,
+    pub note: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+// Incidents in the history, oldest first.
+pub fn incidents(check_history: &[CheckStatus]) -> Vec<Incident> {
+    let mut incidents = Vec::new();
+    for check in check_history {
+        log_check(&mut incidents, check);
+    }
+    incidents
+}
+
+// Starts, extends or ends the last incident of the log with a new check.
+pub fn log_check(incidents: &mut Vec<Incident>, check: &CheckStatus) {
+    let open = incidents
+        .last_mut()
+        .filter(|incident| !incident.is_closed());
+    match (&check.status, open) {
+        (DomainStatus::Up | DomainStatus::Degraded, Some(incident)) => {
+            incident.ended_at = Some(check.timestamp);
+        }
+        (DomainStatus::Down | DomainStatus::Error(_), Some(incident)) => {
+            incident.failed_checks += 1;
+        }
+        (DomainStatus::Down | DomainStatus::Error(_), None) => incidents.push(Incident {
+            started_at: check.timestamp,
+            ended_at: None,
+            failed_checks: 1,
+            first_error: check.error_message.clone(),
+        }),
+        _ => {}
+    }
+}
+
+pub fn format_duration(duration: chrono::Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+// Writes every domain's incidents with their postmortem notes as Markdown, to
+// `output` or stdout, its times and counts written for `locale`.
+pub fn run_incident_report(
+    store_path: &str,
+    output: Option<&Path>,
+    locale: Locale,
+) -> io::Result<()> {
+    let domains = load_domains(store_path)?;
+    let report = incident_report(&domains, Utc::now(), locale);
+
+    match output {
+        Some(path) => {
+            fs::write(path, report)?;
+            println!("Wrote the incident report to {}", path.display());
+        }
+        None => print!("{}", report),
+    }
+    Ok(())
+}
+
+fn incident_report(domains: &[MonitoredDomain], now: DateTime<Utc>, locale: Locale) -> String {
+    // RFC 3339 as always, unless a locale was picked.
+    let timestamp = |at: DateTime<Utc>| {
+        if locale == Locale::default() {
+            at.to_rfc3339()
+        } else {
+            locale.timestamp(at)
+        }
+    };
+    let mut report = format!("# Incident report\n\nGenerated {}\n", timestamp(now));
+
+    // Not incidents yet, but they will be once browsers refuse the chain.
+    let weak_certificates: Vec<(&MonitoredDomain, &[String])> = domains
+        .iter()
+        .filter_map(|d| Some((d, d.certificate.as_ref()?.warnings.as_slice())))
+        .filter(|(_, warnings)| !warnings.is_empty())
+        .collect();
+    if !weak_certificates.is_empty() {
+        report.push_str("\n## Weak certificates\n\n");
+        for (domain, warnings) in weak_certificates {
+            for warning in warnings {
+                report.push_str(&format!("- {}: {}\n", domain.url, warning));
+            }
+        }
+    }
+
+    for domain in domains {
+        let domain_incidents = domain.incidents();
+        if domain_incidents.is_empty() {
+            continue;
+        }
+
+        report.push_str(&format!("\n## {}\n", domain.url));
+        if !domain.metadata.is_empty() {
+            report.push('\n');
+            for (key, value) in &domain.metadata {
+                report.push_str(&format!("- {}: {}\n", key, value));
+            }
+        }
+        for incident in domain_incidents.iter().rev() {
+            report.push_str(&format!(
+                "\n### {} ({}, {})\n\n",
+                timestamp(incident.started_at),
+                format_duration(incident.duration(now)),
+                if incident.is_closed() {
+                    "resolved"
+                } else {
+                    "ongoing"
+                }
+            ));
+            report.push_str(&format!(
+                "- Failed checks: {}\n",
+                locale.integer(incident.failed_checks as u64)
+            ));
+            if let Some(error) = &incident.first_error {
+                report.push_str(&format!("- First error: {}\n", error));
+            }
+            if let Some(note) = domain.incident_note(incident.started_at) {
+                report.push_str(&format!("\n{}\n", note.note.trim_end()));
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::CertificateInspection;
+    use crate::ui::domains::HistoryRetention;
+    use chrono::Duration;
+
+    fn check(minute: i64, status: DomainStatus) -> CheckStatus {
+        let start: DateTime<Utc> = "2025-03-01T12:00:00Z".parse().expect("Valid timestamp");
+        CheckStatus {
+            error_message: matches!(status, DomainStatus::Down).then(|| "503".into()),
+            ..CheckStatus::new(start + Duration::minutes(minute), status)
+        }
+    }
+
+    #[test]
+    fn incidents_span_failed_checks() {
+        let history = vec![
+            check(0, DomainStatus::Up),
+            check(1, DomainStatus::Down),
+            check(2, DomainStatus::RateLimited),
+            check(3, DomainStatus::Down),
+            check(4, DomainStatus::Up),
+            check(5, DomainStatus::Down),
+        ];
+        let found = incidents(&history);
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].started_at, history[1].timestamp);
+        assert_eq!(found[0].ended_at, Some(history[4].timestamp));
+        assert_eq!(found[0].failed_checks, 2);
+        assert!(!found[1].is_closed());
+    }
+
+    #[test]
+    fn logged_incidents_outlive_the_history() {
+        let mut domain = MonitoredDomain::new("https://example.com");
+        for minute in 0..3 {
+            domain.push_check(check(minute, DomainStatus::Down));
+        }
+        for minute in 3..10 {
+            domain.push_check(check(minute, DomainStatus::Up));
+        }
+        let retention = HistoryRetention {
+            max_checks: 2,
+            max_age_days: None,
+        };
+        domain.compact_history(retention, domain.check_history[9].timestamp);
+
+        assert!(incidents(&domain.check_history).is_empty());
+        let logged = domain.incidents();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(logged[0].failed_checks, 3);
+        assert_eq!(
+            logged[0].ended_at,
+            Some(check(3, DomainStatus::Up).timestamp)
+        );
+    }
+
+    #[test]
+    fn report_includes_notes() {
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.check_history = vec![check(1, DomainStatus::Down), check(4, DomainStatus::Up)];
+        domain.set_incident_note(
+            domain.check_history[0].timestamp,
+            "Expired certificate, renewed by hand.",
+            Utc::now(),
+        );
+
+        domain.certificate = Some(CertificateInspection {
+            inspected_at: Utc::now(),
+            warnings: vec!["1024-bit RSA key on \"example.com\"".to_string()],
+        });
+        domain.metadata.insert(
+            "runbook".to_string(),
+            "https://wiki.example.com/site".to_string(),
+        );
+
+        let report = incident_report(&[domain.clone()], Utc::now(), Locale::default());
+        assert!(report.contains("## Weak certificates"));
+        assert!(report.contains("- https://example.com: 1024-bit RSA key on \"example.com\""));
+        assert!(
+            report.contains("## https://example.com\n\n- runbook: https://wiki.example.com/site\n")
+        );
+        assert!(report.contains("(3m 0s, resolved)"));
+        assert!(report.contains("- First error: 503"));
+        assert!(report.contains("Expired certificate, renewed by hand."));
+        assert!(report.contains("### 2025-03-01T12:01:00+00:00 "));
+
+        let localized = incident_report(&[domain], Utc::now(), Locale::parse("de-DE"));
+        assert!(localized.contains("### 01.03.2025 "));
+    }
+}
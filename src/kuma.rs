@@ -0,0 +1,300 @@
+use crate::monitor::CheckTarget;
+use crate::store::{load_domains, save_domains};
+use crate::ui::domains::{DomainAuth, HttpMethod, MonitoredDomain, StatusRange};
+use crate::utils::is_valid_url;
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+
+// Uptime Kuma release whose backup format we write. Its importer accepts
+// backups from 1.x versions.
+const KUMA_BACKUP_VERSION: &str = "1.23.16";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct KumaBackup {
+    version: String,
+    #[serde(rename = "notificationList", default)]
+    notification_list: Vec<serde_json::Value>,
+    #[serde(rename = "monitorList", default)]
+    monitor_list: Vec<KumaMonitor>,
+}
+
+// Only the fields upquack can map; Kuma fills in defaults for the rest.
+#[derive(Debug, Serialize, Deserialize)]
+struct KumaMonitor {
+    id: u64,
+    name: String,
+    #[serde(rename = "type")]
+    monitor_type: String,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    method: Option<String>,
+    // What POST and PUT monitors send.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    // "basic" when the basic_auth fields are used. Kuma has no bearer auth
+    // method, so bearer tokens aren't exported.
+    #[serde(
+        rename = "authMethod",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    auth_method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    basic_auth_user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    basic_auth_pass: Option<String>,
+    // Shown under the monitor's name. upquack writes the metadata into it,
+    // one "key: value" per line, and doesn't read it back.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    // What "keyword" monitors look for in the body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keyword: Option<String>,
+    interval: u64,
+    #[serde(rename = "retryInterval", default)]
+    retry_interval: u64,
+    #[serde(default)]
+    timeout: Option<f64>,
+    // Older Kuma versions write this as 0/1, we don't need it when importing.
+    #[serde(skip_deserializing)]
+    active: bool,
+    #[serde(default)]
+    maxretries: u64,
+    #[serde(default)]
+    accepted_statuscodes: Vec<String>,
+    #[serde(rename = "notificationIDList", default)]
+    notification_id_list: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    tags: Vec<serde_json::Value>,
+}
+
+pub fn run_export(path: &Path, store_path: &str) -> io::Result<()> {
+    let domains = load_domains(store_path)?;
+    let backup = to_kuma_backup(&domains);
+
+    fs::write(path, serde_json::to_string_pretty(&backup)?)?;
+    println!(
+        "Exported {} monitors to {} (Uptime Kuma backup format).",
+        backup.monitor_list.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+pub fn run_import(path: &Path, store_path: &str) -> io::Result<()> {
+    let backup: KumaBackup = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let mut domains = match load_domains(store_path) {
+        Ok(domains) => domains,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+
+    let (mut imported, mut skipped) = (0, 0);
+    for monitor in &backup.monitor_list {
+        match from_kuma_monitor(monitor) {
+            Some(domain) if !domains.iter().any(|d| d.url == domain.url) => {
+                println!("+ {}", domain.url);
+                domains.push(domain);
+                imported += 1;
+            }
+            Some(domain) => {
+                println!("  {} is already monitored, skipping", domain.url);
+                skipped += 1;
+            }
+            None => {
+                println!(
+                    "  \"{}\" is a {} monitor, which upquack can't import",
+                    monitor.name, monitor.monitor_type
+                );
+                skipped += 1;
+            }
+        }
+    }
+
+    save_domains(&domains, store_path)?;
+    println!("Imported {} monitors, skipped {}.", imported, skipped);
+    Ok(())
+}
+
+fn to_kuma_backup(domains: &[MonitoredDomain]) -> KumaBackup {
+    // Only HTTP(S) monitors are exported, Kuma wants its own settings for the rest.
+    let monitor_list = domains
+        .iter()
+        .filter(|domain| CheckTarget::parse(&domain.url).is_ok_and(|t| t.is_http()))
+        .enumerate()
+        .map(|(i, domain)| {
+            let (auth_method, basic_auth_user, basic_auth_pass) = match &domain.auth {
+                Some(DomainAuth::Basic { username, password }) => (
+                    Some("basic".to_string()),
+                    Some(username.clone()),
+                    Some(password.clone()),
+                ),
+                _ => (None, None, None),
+            };
+            KumaMonitor {
+                id: i as u64 + 1,
+                name: domain.name().to_string(),
+                monitor_type: match domain.keyword {
+                    Some(_) => "keyword".to_string(),
+                    None => "http".to_string(),
+                },
+                url: Some(domain.url.to_string()),
+                method: Some(domain.method().label().to_string()),
+                body: domain.request_body.clone(),
+                description: (!domain.metadata.is_empty()).then(|| {
+                    domain
+                        .metadata
+                        .iter()
+                        .map(|(key, value)| format!("{}: {}", key, value))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }),
+                auth_method,
+                basic_auth_user,
+                basic_auth_pass,
+                keyword: domain.keyword.clone(),
+                interval: domain.interval_seconds,
+                retry_interval: domain.interval_seconds,
+                // Kuma has no "use the default" timeout, so write out what reqwest is given.
+                timeout: Some(domain.timeout().as_secs_f64()),
+                active: true,
+                maxretries: 0,
+                accepted_statuscodes: if domain.expected_status_codes.is_empty() {
+                    vec!["200-299".to_string()]
+                } else {
+                    domain
+                        .expected_status_codes
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect()
+                },
+                notification_id_list: serde_json::Map::new(),
+                tags: Vec::new(),
+            }
+        })
+        .collect();
+
+    KumaBackup {
+        version: KUMA_BACKUP_VERSION.to_string(),
+        notification_list: Vec::new(),
+        monitor_list,
+    }
+}
+
+// Only HTTP(S) style monitors have an upquack equivalent.
+fn from_kuma_monitor(monitor: &KumaMonitor) -> Option<MonitoredDomain> {
+    if !matches!(
+        monitor.monitor_type.as_str(),
+        "http" | "keyword" | "json-query"
+    ) {
+        return None;
+    }
+    let url = monitor.url.as_deref().filter(|url| is_valid_url(url))?;
+
+    let mut domain = MonitoredDomain::new(url);
+    let name = monitor.name.trim();
+    if !name.is_empty() && name != domain.url.as_ref() {
+        domain.display_name = Some(name.to_string());
+    }
+    domain.interval_seconds = monitor.interval.max(1);
+    domain.timeout_seconds = monitor
+        .timeout
+        .filter(|timeout| *timeout > 0.0)
+        .map(|timeout| timeout.ceil() as u64);
+    if monitor.monitor_type == "keyword" {
+        domain.keyword = monitor
+            .keyword
+            .clone()
+            .filter(|keyword| !keyword.is_empty());
+    }
+    // GET and HEAD are what checks pick on their own.
+    let method = monitor.method.as_deref().and_then(HttpMethod::parse);
+    if let Some(method @ (HttpMethod::Post | HttpMethod::Put)) = method {
+        domain.http_method = Some(method);
+        domain.request_body = monitor.body.clone().filter(|body| !body.is_empty());
+    }
+    // Kuma's default is what checks expect without any.
+    if monitor.accepted_statuscodes != ["200-299"] {
+        domain.expected_status_codes = monitor
+            .accepted_statuscodes
+            .iter()
+            .filter_map(|codes| StatusRange::try_from(codes.clone()).ok())
+            .collect();
+    }
+    if monitor.auth_method.as_deref() == Some("basic")
+        && let (Some(username), Some(password)) =
+            (&monitor.basic_auth_user, &monitor.basic_auth_pass)
+    {
+        domain.auth = Some(DomainAuth::Basic {
+            username: username.clone(),
+            password: password.clone(),
+        });
+    }
+    Some(domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_then_import_keeps_settings() {
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.interval_seconds = 30;
+        domain.timeout_seconds = Some(5);
+        domain.display_name = Some("Example".to_string());
+        domain.keyword = Some("Welcome".to_string());
+        domain.http_method = Some(HttpMethod::Post);
+        domain.request_body = Some(r#"{"probe": true}"#.to_string());
+        domain.auth = DomainAuth::parse("basic ops:hunter2").unwrap();
+        domain.expected_status_codes =
+            crate::ui::domains::parse_status_codes("200-299, 401").unwrap();
+        domain.metadata = crate::ui::domains::parse_metadata("team=web; tier=1").unwrap();
+
+        let backup = to_kuma_backup(&[domain]);
+        assert_eq!(
+            backup.monitor_list[0].description.as_deref(),
+            Some("team: web\ntier: 1")
+        );
+        let json = serde_json::to_string(&backup).expect("Failed to serialize backup");
+        let parsed: KumaBackup = serde_json::from_str(&json).expect("Failed to parse backup");
+
+        let imported = from_kuma_monitor(&parsed.monitor_list[0]).expect("Monitor should import");
+        assert_eq!(&*imported.url, "https://example.com");
+        assert_eq!(imported.interval_seconds, 30);
+        assert_eq!(imported.timeout_seconds, Some(5));
+        assert_eq!(imported.display_name.as_deref(), Some("Example"));
+        assert_eq!(imported.keyword.as_deref(), Some("Welcome"));
+        assert_eq!(imported.http_method, Some(HttpMethod::Post));
+        assert_eq!(imported.request_body.as_deref(), Some(r#"{"probe": true}"#));
+        assert_eq!(
+            crate::ui::domains::format_status_codes(&imported.expected_status_codes),
+            "200-299, 401"
+        );
+        assert_eq!(
+            imported.auth,
+            DomainAuth::parse("basic ops:hunter2").unwrap()
+        );
+    }
+
+    #[test]
+    fn import_skips_monitors_without_http_equivalent() {
+        let backup: KumaBackup = serde_json::from_str(
+            r#"{
+                "version": "1.23.0",
+                "monitorList": [
+                    {"id": 1, "name": "db", "type": "port", "hostname": "db.local", "interval": 60},
+                    {"id": 2, "name": "site", "type": "http", "url": "https://example.org", "interval": 120, "timeout": 47.5}
+                ]
+            }"#,
+        )
+        .expect("Failed to parse backup");
+
+        assert!(from_kuma_monitor(&backup.monitor_list[0]).is_none());
+
+        let site = from_kuma_monitor(&backup.monitor_list[1]).expect("Monitor should import");
+        assert_eq!(site.interval_seconds, 120);
+        assert_eq!(site.timeout_seconds, Some(48));
+    }
+}
@@ -0,0 +1,36 @@
+// Everything but the command line entry point, so the benchmarks in benches/
+// can reach the render and storage paths.
+pub mod annotation;
+pub mod app;
+pub mod apply;
+#[cfg(feature = "server")]
+pub mod auth;
+pub mod badge;
+pub mod blind_spot;
+pub mod budget;
+pub mod calendar;
+pub mod cli;
+pub mod configuration;
+pub mod diagnose;
+pub mod grafana;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod incident;
+pub mod kuma;
+pub mod logging;
+pub mod monitor;
+pub mod profile;
+pub mod rollup;
+pub mod secrets;
+pub mod series;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod signing;
+pub mod store;
+pub mod telemetry;
+pub mod ui;
+pub mod utils;
+pub mod wildcard;
+
+#[cfg(not(any(feature = "rustls", feature = "native-tls")))]
+compile_error!("Enable a TLS backend for checks, the \"rustls\" or \"native-tls\" feature");
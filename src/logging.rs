@@ -0,0 +1,326 @@
+use crate::configuration::Settings;
+use crate::profile::Profile;
+use crate::telemetry::{Telemetry, init_telemetry};
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write},
+    fs::{self, File},
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tracing::{Event, Level, Subscriber, field::Field};
+use tracing_subscriber::{
+    Layer, Registry,
+    filter::LevelFilter,
+    fmt::layer as fmt_layer,
+    layer::{Context, SubscriberExt},
+    reload,
+};
+
+// Log lines kept in memory for the log viewer.
+pub const LOG_RING_CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    #[default]
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 5] = [
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+        LogLevel::Trace,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+
+    // One step more (or less) verbose, stopping at either end.
+    pub fn step(self, more_verbose: bool) -> LogLevel {
+        let index = LogLevel::ALL.iter().position(|l| *l == self).unwrap_or(0);
+        let index = if more_verbose {
+            (index + 1).min(LogLevel::ALL.len() - 1)
+        } else {
+            index.saturating_sub(1)
+        };
+        LogLevel::ALL[index]
+    }
+
+    fn filter(self) -> LevelFilter {
+        match self {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+    pub timestamp: DateTime<Local>,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+// The recent log lines and the level they're logged at, shared by the log
+// viewer and the settings screen.
+#[derive(Debug, Clone)]
+pub struct LogHandle {
+    lines: Arc<Mutex<VecDeque<LogLine>>>,
+    capacity: usize,
+    level: Arc<Mutex<LogLevel>>,
+    reload: Option<reload::Handle<LevelFilter, Registry>>,
+}
+
+impl Default for LogHandle {
+    fn default() -> Self {
+        LogHandle::new(LOG_RING_CAPACITY, LogLevel::default())
+    }
+}
+
+impl LogHandle {
+    pub fn new(capacity: usize, level: LogLevel) -> Self {
+        LogHandle {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+            level: Arc::new(Mutex::new(level)),
+            reload: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Up to `count` lines, oldest first, ending `skip_newest` lines before the
+    // newest one.
+    pub fn tail(&self, count: usize, skip_newest: usize) -> Vec<LogLine> {
+        let lines = self.lines.lock().unwrap();
+        let end = lines.len().saturating_sub(skip_newest);
+        lines
+            .range(end.saturating_sub(count)..end)
+            .cloned()
+            .collect()
+    }
+
+    pub fn level(&self) -> LogLevel {
+        *self.level.lock().unwrap()
+    }
+
+    // Takes effect right away for the viewer and the debug log, until
+    // upquack is restarted. The error log always gets errors.
+    pub fn set_level(&self, level: LogLevel) {
+        *self.level.lock().unwrap() = level;
+        if let Some(reload) = &self.reload
+            && let Err(e) = reload.reload(level.filter())
+        {
+            tracing::error!("Failed to change the log level: {}", e);
+        }
+        tracing::info!("Log level set to {}", level.label());
+    }
+
+    // Keeps every event it sees, filtering is up to the subscriber.
+    pub fn layer(&self) -> LogRingLayer {
+        LogRingLayer {
+            lines: Arc::clone(&self.lines),
+            capacity: self.capacity,
+        }
+    }
+
+    fn push(lines: &Mutex<VecDeque<LogLine>>, capacity: usize, line: LogLine) {
+        let mut lines = lines.lock().unwrap();
+        if lines.len() == capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+}
+
+pub struct LogRingLayer {
+    lines: Arc<Mutex<VecDeque<LogLine>>>,
+    capacity: usize,
+}
+
+impl<S: Subscriber> Layer<S> for LogRingLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+        let (message, log_target) = visitor.finish();
+        let metadata = event.metadata();
+        LogHandle::push(
+            &self.lines,
+            self.capacity,
+            LogLine {
+                timestamp: Local::now(),
+                level: *metadata.level(),
+                // Records from the log crate all come from the "log" target.
+                target: log_target.unwrap_or_else(|| metadata.target().to_string()),
+                message,
+            },
+        );
+    }
+}
+
+// The message first, then the other fields as key=value.
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+    log_target: Option<String>,
+}
+
+impl tracing::field::Visit for LineVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "log.target" => self.log_target = Some(value.to_string()),
+            "message" => self.message.push_str(value),
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.fields, " {}={}", name, value);
+            }
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.message, "{:?}", value);
+            }
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.fields, " {}={:?}", name, value);
+            }
+        }
+    }
+}
+
+impl LineVisitor {
+    fn finish(mut self) -> (String, Option<String>) {
+        self.message.push_str(&self.fields);
+        (self.message, self.log_target)
+    }
+}
+
+// Kept until upquack exits, dropping it flushes the last spans.
+pub struct Logging {
+    pub logs: LogHandle,
+    pub telemetry: Option<Telemetry>,
+}
+
+// Sends everything logged at the configured level (and records from crates
+// that use the log crate) to the log viewer and the profile's debug.log, errors
+// also to its error.log, and spans to OpenTelemetry when that's set up. Both
+// files start over on every run.
+pub fn init_logging(settings: &Settings, profile: &Profile) -> io::Result<Logging> {
+    let open = |path: &Path| -> io::Result<Mutex<File>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        Ok(Mutex::new(File::create(path)?))
+    };
+    let debug_log = open(&profile.debug_log_path())?;
+    let error_log = open(&profile.error_log_path())?;
+
+    let level = settings.logging.level;
+    let mut logs = LogHandle::new(LOG_RING_CAPACITY, level);
+    let (level_filter, reload) = reload::Layer::new(level.filter());
+    logs.reload = Some(reload);
+
+    let telemetry = init_telemetry(&settings.telemetry);
+    let (telemetry, otel_layer, telemetry_error) = match telemetry {
+        Ok(Some((telemetry, layer))) => (Some(telemetry), Some(layer), None),
+        Ok(None) => (None, None, None),
+        Err(e) => (None, None, Some(e)),
+    };
+    let subscriber = tracing_subscriber::registry()
+        .with(
+            logs.layer()
+                .and_then(fmt_layer().with_writer(debug_log))
+                .with_filter(level_filter),
+        )
+        .with(
+            fmt_layer()
+                .with_writer(error_log)
+                .with_filter(LevelFilter::ERROR),
+        )
+        .with(otel_layer);
+
+    tracing::subscriber::set_global_default(subscriber).map_err(io::Error::other)?;
+    // Filtered by the subscriber like everything else, so the log level can
+    // be raised at runtime for these too.
+    tracing_log::LogTracer::builder()
+        .with_max_level(tracing_log::log::LevelFilter::Trace)
+        .init()
+        .map_err(io::Error::other)?;
+
+    if let Some(e) = telemetry_error {
+        tracing::error!("{}", e);
+    } else if let Some(endpoint) = settings.telemetry.otlp_endpoint.as_ref() {
+        tracing::debug!("Exporting traces to {}", endpoint);
+    }
+    Ok(Logging { logs, telemetry })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_keeps_the_most_recent_lines() {
+        let logs = LogHandle::new(3, LogLevel::Debug);
+        let subscriber = tracing_subscriber::registry().with(logs.layer());
+
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..5 {
+                tracing::warn!(check = i, "Slow check of {}", "https://example.com");
+            }
+        });
+
+        assert_eq!(logs.len(), 3);
+        let lines = logs.tail(2, 1);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0].message,
+            "Slow check of https://example.com check=2"
+        );
+        assert_eq!(
+            lines[1].message,
+            "Slow check of https://example.com check=3"
+        );
+        assert_eq!(lines[0].level, Level::WARN);
+        assert!(lines[0].target.starts_with("upquack"));
+    }
+
+    #[test]
+    fn levels_step_within_bounds() {
+        assert_eq!(LogLevel::Debug.step(true), LogLevel::Trace);
+        assert_eq!(LogLevel::Trace.step(true), LogLevel::Trace);
+        assert_eq!(LogLevel::Warn.step(false), LogLevel::Error);
+        assert_eq!(LogLevel::Error.step(false), LogLevel::Error);
+    }
+}
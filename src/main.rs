@@ -1,28 +1,95 @@
-mod app;
-mod monitor;
-mod ui;
-mod utils;
-
-use crate::app::App;
-use ftail::Ftail;
-use log::LevelFilter;
+use clap::Parser;
 use std::{io, path::Path};
 use tokio::sync::mpsc;
+use upquack::annotation::run_annotate;
+use upquack::app::App;
+use upquack::apply::run_apply;
+use upquack::calendar::run_incident_calendar;
+use upquack::cli::{Cli, Command, SecretAction};
+use upquack::configuration::{Settings, get_configuration};
+use upquack::diagnose::{install_panic_hook, run_diagnose};
+use upquack::incident::run_incident_report;
+use upquack::kuma::{run_export, run_import};
+use upquack::logging::init_logging;
+use upquack::monitor::start_monitoring;
+use upquack::profile::Profile;
+use upquack::secrets::{run_list_secrets, run_remove_secret, run_set_secret};
+#[cfg(feature = "server")]
+use upquack::server;
+use upquack::signing::run_verify_history;
 
 #[tokio::main]
 async fn main() -> io::Result<()> {
-    let error_log_file = Path::new("log/error.log");
-    let debug_log_file = Path::new("log/debug.log");
+    let cli = Cli::parse();
+
+    let profile = match &cli.profile {
+        Some(name) => Profile::named(name)?,
+        None => Profile::default(),
+    };
+
+    // Diagnosing has to work with a broken config, and secrets have to be
+    // manageable even when the config refers to ones
+    // that don't exist yet, so those log with the defaults.
+    let settings = match &cli.command {
+        Some(Command::Diagnose { .. }) | Some(Command::Secret { .. }) => Settings::default(),
+        _ => get_configuration(&profile)?,
+    };
+    // Kept until main returns, dropping it flushes the last spans.
+    let logging = init_logging(&settings, &profile)?;
+    install_panic_hook(&profile);
+
+    match &cli.command {
+        Some(Command::Diagnose { output }) => return run_diagnose(&profile, output.as_deref()),
+        Some(Command::Secret { action }) => {
+            return match action {
+                SecretAction::Set { name } => run_set_secret(&profile, name),
+                SecretAction::List => run_list_secrets(&profile),
+                SecretAction::Remove { name } => run_remove_secret(&profile, name),
+            };
+        }
+        _ => {}
+    }
+
+    match cli.command {
+        #[cfg(feature = "server")]
+        Some(Command::Daemon) => {
+            let monitor = start_monitoring(&settings, cli.paused).await;
+            return server::serve(settings, monitor).await;
+        }
+        Some(Command::Apply { file, dry_run, yes }) => {
+            return run_apply(&file, dry_run, yes, &settings);
+        }
+        Some(Command::Annotate { text, url }) => {
+            return run_annotate(&profile.store_path(), &text, url.as_deref());
+        }
+        Some(Command::Export { file }) => return run_export(&file, &profile.store_path()),
+        Some(Command::Import { file }) => return run_import(&file, &profile.store_path()),
+        Some(Command::Incidents { output, ics: false }) => {
+            return run_incident_report(
+                &profile.store_path(),
+                output.as_deref(),
+                settings.display.locale,
+            );
+        }
+        Some(Command::Incidents { output, ics: true }) => {
+            return run_incident_calendar(&profile.store_path(), output.as_deref());
+        }
+        Some(Command::VerifyHistory { file, public_key }) => {
+            let file = file.unwrap_or_else(|| profile.store_path().into());
+            return run_verify_history(
+                &file,
+                Path::new(&settings.signing.key_path),
+                public_key.as_deref(),
+            );
+        }
+        Some(Command::Diagnose { .. }) | Some(Command::Secret { .. }) | None => {}
+    }
 
-    Ftail::new()
-        .single_file(debug_log_file, false, LevelFilter::Debug)
-        .single_file(error_log_file, false, LevelFilter::Error)
-        .init()
-        .unwrap();
+    let monitor = start_monitoring(&settings, cli.paused).await;
 
     let mut terminal = ratatui::init();
     let (event_sender, event_receiver) = mpsc::unbounded_channel();
-    let mut app_init = App::new(event_sender);
+    let mut app_init = App::new(event_sender, settings, monitor).with_logs(logging.logs.clone());
     let run_upquack = app_init.run(&mut terminal, event_receiver).await;
     ratatui::restore();
     run_upquack
@@ -1,123 +1,1691 @@
-use crate::ui::domains::{CheckStatus, DomainStatus, HttpCode, MonitoredDomain};
-use chrono::Utc;
-use reqwest::{Client, StatusCode};
+mod certificate;
+mod challenge;
+mod criteria;
+mod discovery;
+mod dns;
+mod docker;
+mod feed;
+mod file_transfer;
+mod grpc;
+mod host;
+mod json_path;
+mod kubernetes;
+mod local_file;
+mod ntp;
+mod ping;
+mod redfish;
+mod smtp;
+mod snapshot;
+mod snmp;
+mod ssh;
+mod status_page;
+mod target;
+mod timing;
+mod traceroute;
+mod tunnel;
+mod watchdog;
+
+pub use certificate::CertificateInspection;
+pub use challenge::HttpChallenge;
+pub use criteria::Criterion;
+pub use discovery::{discovered_urls, reconcile};
+pub use snapshot::{DomainSnapshot, MonitorSnapshot};
+pub use target::CheckTarget;
+pub use timing::CheckTimings;
+pub use traceroute::TraceHop;
+pub use tunnel::{SshTunnels, validate_jump_host};
+pub use watchdog::PersistenceWatchdog;
+
+use crate::blind_spot::{self, BlindSpot, log_blind_spots, mark_blind_spots};
+use crate::budget::{RequestBudget, budget_host};
+use crate::configuration::{BmcSettings, FtpSettings, Settings, SnmpSettings};
+use crate::secrets::SecretResolver;
+use crate::signing::CheckSigner;
+use crate::store::{load_domains, save_domains};
+use crate::ui::domains::{
+    CheckStatus, DomainAuth, DomainStatus, HistoryRetention, HttpCode, HttpMethod, MonitoredDomain,
+    format_status_codes, status_label,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{DateTime, Utc};
+use criteria::CheckResponse;
+use rand::Rng;
+use regex::Regex;
+use reqwest::header::{self, RETRY_AFTER};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode, redirect};
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, io, mem,
     ops::Deref,
-    sync::{Arc, Mutex},
-    time,
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+    time::{self, Instant},
 };
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
 use tokio::time::sleep;
+use tracing::{Instrument, field};
+use utoipa::ToSchema;
+use uuid::Uuid;
 
 type DomainCallbackType =
     dyn Fn(&MonitoredDomain, &[CheckStatus]) -> Result<(), std::io::Error> + Send + Sync + 'static;
 
-pub async fn start_monitoring_task(
-    domains: Arc<Mutex<Vec<MonitoredDomain>>>,
+// How far the wall clock may drift from the monotonic clock during a single
+// sleep before we treat it as a suspend/resume or a clock change.
+const CLOCK_JUMP_THRESHOLD_SECS: i64 = 30;
+
+// How many events a slow subscriber may fall behind before it starts missing some.
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+const LOW_RESOURCE_EVENT_CHANNEL_CAPACITY: usize = 32;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MonitorEvent {
+    CheckCompleted {
+        domain_id: Uuid,
+        #[schema(value_type = String)]
+        url: Arc<str>,
+        check: CheckStatus,
+    },
+    StatusChanged {
+        domain_id: Uuid,
+        #[schema(value_type = String)]
+        url: Arc<str>,
+        from: Option<DomainStatus>,
+        to: DomainStatus,
+        // So whoever consumes the events can route them to the right team.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        owner: Option<String>,
+        // The runbook link from the domain's metadata, for the responder.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        runbook: Option<String>,
+    },
+    // Changes to the list of domains, from the TUI or the API.
+    DomainAdded {
+        domain: MonitoredDomain,
+    },
+    DomainUpdated {
+        domain: MonitoredDomain,
+    },
+    DomainRemoved {
+        domain_id: Uuid,
+        #[schema(value_type = String)]
+        url: Arc<str>,
+    },
+    // Saving check results failed this many times in a row, raised once per
+    // run of failures. The results are kept in memory until a save works.
+    PersistenceFailing {
+        failures: usize,
+        error: String,
+    },
+    PersistenceRecovered {
+        failures: usize,
+        // Results made while saving failed that are on disk now, and those
+        // the history's retention dropped before they could be saved.
+        saved: usize,
+        dropped: usize,
+    },
+}
+
+impl MonitorEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MonitorEvent::CheckCompleted { .. } => "check_completed",
+            MonitorEvent::StatusChanged { .. } => "status_changed",
+            MonitorEvent::DomainAdded { .. } => "domain_added",
+            MonitorEvent::DomainUpdated { .. } => "domain_updated",
+            MonitorEvent::DomainRemoved { .. } => "domain_removed",
+            MonitorEvent::PersistenceFailing { .. } => "persistence_failing",
+            MonitorEvent::PersistenceRecovered { .. } => "persistence_recovered",
+        }
+    }
+}
+
+// The logins of what checks other than HTTP(S) ones talk to, from the
+// configuration's [[bmc]], [[snmp]] and [[ftp]] entries, and the secrets
+// store for the "secret:<name>" references in domains' auth and headers.
+#[derive(Clone, Default)]
+pub struct Credentials {
+    pub bmc: Vec<BmcSettings>,
+    pub snmp: Vec<SnmpSettings>,
+    pub ftp: Vec<FtpSettings>,
+    secrets: Arc<Mutex<SecretResolver>>,
+}
+
+impl Credentials {
+    // Unlocks the secrets store now if the domains refer to it, while a
+    // passphrase can still be prompted for. References added later, once the
+    // TUI owns the terminal, never prompt.
+    pub fn from_settings(settings: &Settings, domains: &[MonitoredDomain]) -> Credentials {
+        let mut secrets = SecretResolver::new(&settings.profile);
+        let references = domains.iter().flat_map(|domain| {
+            let auth = domain.auth.iter().map(|auth| match auth {
+                DomainAuth::Basic { password, .. } => password,
+                DomainAuth::Bearer { token } => token,
+            });
+            domain.headers.values().chain(auth)
+        });
+        for value in references.filter(|value| SecretResolver::is_secret(value)) {
+            if let Err(e) = secrets.resolve(value) {
+                tracing::error!("Failed to resolve {}: {}", value, e);
+            }
+        }
+        Credentials {
+            bmc: settings.bmc.clone(),
+            snmp: settings.snmp.clone(),
+            ftp: settings.ftp.clone(),
+            secrets: Arc::new(Mutex::new(secrets.with_prompt(false))),
+        }
+    }
+
+    // The value, or the secret it refers to for "secret:<name>".
+    pub fn resolve(&self, value: &str) -> Result<String, String> {
+        if !SecretResolver::is_secret(value) {
+            return Ok(value.to_string());
+        }
+        let mut secrets = self.secrets.lock().unwrap();
+        secrets.resolve(value).map_err(|e| e.to_string())
+    }
+
+    // The Authorization header value for the auth, with its secrets resolved.
+    pub fn authorization(&self, auth: &DomainAuth) -> Result<String, String> {
+        let resolve = |value: &str| self.resolve(value);
+        Ok(match auth {
+            DomainAuth::Basic { username, password } => format!(
+                "Basic {}",
+                STANDARD.encode(format!("{}:{}", username, resolve(password)?))
+            ),
+            DomainAuth::Bearer { token } => format!("Bearer {}", resolve(token)?),
+        })
+    }
+
+    // The domain's headers and Authorization, with their secrets resolved.
+    pub fn request_headers(
+        &self,
+        headers: &BTreeMap<String, String>,
+        auth: Option<&DomainAuth>,
+    ) -> Result<BTreeMap<String, String>, String> {
+        let mut resolved = BTreeMap::new();
+        for (name, value) in headers {
+            let value = self
+                .resolve(value)
+                .map_err(|e| format!("{} header: {}", name, e))?;
+            resolved.insert(name.clone(), value);
+        }
+        if let Some(auth) = auth {
+            let value = self
+                .authorization(auth)
+                .map_err(|e| format!("Auth: {}", e))?;
+            resolved.insert(header::AUTHORIZATION.to_string(), value);
+        }
+        Ok(resolved)
+    }
+}
+
+// State shared between the monitoring tasks and whatever shows or changes
+// their results (the TUI and the API server).
+#[derive(Clone)]
+pub struct MonitorHandle {
+    pub domains: Arc<Mutex<Vec<MonitoredDomain>>>,
+    pub budget: Arc<RequestBudget>,
+    pub events: broadcast::Sender<MonitorEvent>,
+    // Checks before this were made by an earlier run.
+    pub started_at: DateTime<Utc>,
+    store_path: String,
+    client: Client,
     update_domains_callback: Arc<DomainCallbackType>,
-) {
-    let client = Client::builder()
-        .timeout(time::Duration::from_secs(10))
-        .build()
-        .expect("Failed to create client");
+    signer: Option<Arc<CheckSigner>>,
+    retention: HistoryRetention,
+    // What the screens draw from, including when each running task checks
+    // its domain next. See snapshot.rs.
+    snapshot: Arc<watch::Sender<Arc<MonitorSnapshot>>>,
+    // Raises repeated failures to save check results, see watchdog.rs.
+    pub watchdog: Arc<PersistenceWatchdog>,
+    stop: Arc<watch::Sender<bool>>,
+    // While set no checks run, see set_paused.
+    paused: Arc<watch::Sender<bool>>,
+    // For monitors checked through an SSH jump host.
+    tunnels: Arc<SshTunnels>,
+    // For the BMCs, SNMP agents and FTP servers checks log in to.
+    credentials: Arc<Credentials>,
+    // Found when this run started, see blind_spot.rs.
+    pub blind_spots: Arc<Vec<BlindSpot>>,
+}
 
-    let domains_to_monitor = {
-        let domains_guard = domains.lock().unwrap();
-        domains_guard.clone()
+impl fmt::Debug for MonitorHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MonitorHandle")
+            .field("domains", &self.domains)
+            .field("budget", &self.budget)
+            .field("signer", &self.signer)
+            .field("retention", &self.retention)
+            .field("paused", &self.is_paused())
+            .finish_non_exhaustive()
+    }
+}
+
+// Loads the stored domains and starts checking them, saving every result back
+// to the store.
+pub async fn start_monitoring(settings: &Settings, paused: bool) -> MonitorHandle {
+    let store_path = settings.profile.store_path();
+    let retention = settings.monitor.history_retention();
+    let mut domains = load_domains(&store_path).unwrap_or_default();
+
+    let signer = if settings.signing.enabled {
+        match CheckSigner::load_or_create(Path::new(&settings.signing.key_path)) {
+            Ok(signer) => Some(signer),
+            Err(e) => {
+                tracing::error!(
+                    "Failed to load check signing key, checks won't be signed: {}",
+                    e
+                );
+                None
+            }
+        }
+    } else {
+        None
     };
 
-    log::debug!(
-        "Starting monitoring task for {} domains",
-        domains_to_monitor.len()
-    );
+    // Retention may have been lowered since the history was written, so the
+    // whole store is compacted once before checks resume.
+    let now = Utc::now();
+    let dropped: usize = domains
+        .iter_mut()
+        .map(|d| d.compact_history(d.history_retention(retention), now))
+        .sum();
+    if dropped > 0 {
+        tracing::debug!("Dropped {} checks past their retention", dropped);
+        if let Err(e) = save_domains(&domains, &store_path) {
+            tracing::error!("Failed to save domains after compaction: {}", e);
+        }
+    }
 
-    for domain in domains_to_monitor {
-        let client = client.clone();
-        let domains_arc_clone = Arc::clone(&domains);
-        let update_domains_callback_clone = Arc::clone(&update_domains_callback);
+    // Time upquack itself wasn't running shows up as a gap in the history.
+    let blind_spots = mark_blind_spots(&mut domains, now, signer.as_ref());
+    if !blind_spots.is_empty() {
+        log_blind_spots(&blind_spots);
+        if let Err(e) = save_domains(&domains, &store_path) {
+            tracing::error!("Failed to save domains after marking blind spots: {}", e);
+        }
+    }
+    domains
+        .iter_mut()
+        .for_each(MonitoredDomain::share_history_strings);
+    let credentials = Credentials::from_settings(settings, &domains);
+    let domains_arc = Arc::new(Mutex::new(domains));
 
-        tokio::spawn(async move {
-            let domain_id = domain.id;
-            let interval = time::Duration::from_secs(domain.interval_seconds);
-            log::debug!(
-                "Monitoring task started for URL: {} (ID: {}) with interval: {:?}",
-                domain.url,
-                domain_id,
-                interval
-            );
+    let update_domains_callback = {
+        let domains_arc_for_callback = Arc::clone(&domains_arc);
+        let store_path = store_path.clone();
+        Arc::new(
+            move |updated_domain: &MonitoredDomain, check_history: &[CheckStatus]| {
+                let mut domains_guard = domains_arc_for_callback.lock().unwrap();
+                if let Some(d) = domains_guard.iter_mut().find(|d| d.id == updated_domain.id) {
+                    d.check_history = check_history.to_vec();
+                    d.daily_rollups = updated_domain.daily_rollups.clone();
+                    d.incident_log = updated_domain.incident_log.clone();
 
-            loop {
-                let start_time = Utc::now();
-                let head_req_result = domain_head_request(&client, &domain.url).await;
-                let end_time = Utc::now();
-                let response_time = (end_time - start_time).num_milliseconds() as u64;
-
-                let head_status = match head_req_result {
-                    Ok(status_code) => {
-                        let http_code = HttpCode::from_status_code(status_code);
-                        let domain_status = if status_code.is_success() {
-                            DomainStatus::Up
-                        } else {
-                            DomainStatus::Down
-                        };
-                        CheckStatus {
-                            timestamp: end_time,
-                            status: domain_status,
-                            http_code: Some(http_code),
-                            error_message: None,
-                            response_time_ms: Some(response_time),
-                        }
+                    if let Err(e) = save_domains(&domains_guard, &store_path) {
+                        tracing::error!("Failed to save domains after check: {}", e);
+                        return Err(e); // Propagate the error
                     }
-                    Err(e) => {
-                        let err_msg = e.to_string();
-                        log::error!("Error checking {}: {}", domain.url, err_msg);
-                        CheckStatus {
-                            timestamp: end_time,
-                            status: DomainStatus::Error(err_msg.clone()),
-                            http_code: if e.is_timeout() {
-                                Some(HttpCode::Timeout)
-                            } else {
-                                Some(HttpCode::NetworkError)
-                            },
-                            response_time_ms: None,
-                            error_message: Some(err_msg),
+                }
+                Ok(())
+            },
+        )
+    };
+
+    let capacity = if settings.performance.low_resource {
+        LOW_RESOURCE_EVENT_CHANNEL_CAPACITY
+    } else {
+        EVENT_CHANNEL_CAPACITY
+    };
+    let (events, _) = broadcast::channel(capacity);
+    let monitor = MonitorHandle::new(
+        domains_arc,
+        &store_path,
+        update_domains_callback,
+        Arc::new(RequestBudget::new(&settings.budget)),
+        events,
+    )
+    .with_signer(signer)
+    .with_retention(retention)
+    .with_watchdog(PersistenceWatchdog::new(
+        settings.monitor.save_failure_threshold,
+    ))
+    .with_blind_spots(blind_spots)
+    .with_credentials(credentials);
+    monitor.set_paused(paused);
+    monitor.start();
+    monitor.start_discovery(&settings.discovery, &settings.templates);
+    monitor
+}
+
+impl MonitorHandle {
+    pub fn new(
+        domains: Arc<Mutex<Vec<MonitoredDomain>>>,
+        store_path: &str,
+        update_domains_callback: Arc<DomainCallbackType>,
+        budget: Arc<RequestBudget>,
+        events: broadcast::Sender<MonitorEvent>,
+    ) -> Self {
+        let client = Client::builder()
+            .timeout(time::Duration::from_secs(10))
+            // Followed by hand instead, see follow_redirects.
+            .redirect(redirect::Policy::none())
+            // HTTP/2 is for gRPC checks, see grpc.rs.
+            .http1_only()
+            .dns_resolver(Arc::new(timing::TracedResolver))
+            .build()
+            .expect("Failed to create client");
+        let snapshot = MonitorSnapshot::from_domains(&domains.lock().unwrap());
+
+        MonitorHandle {
+            domains,
+            budget,
+            events,
+            started_at: Utc::now(),
+            store_path: store_path.to_string(),
+            client,
+            update_domains_callback,
+            signer: None,
+            retention: HistoryRetention::default(),
+            snapshot: Arc::new(watch::channel(Arc::new(snapshot)).0),
+            watchdog: Arc::new(PersistenceWatchdog::default()),
+            stop: Arc::new(watch::channel(false).0),
+            paused: Arc::new(watch::channel(false).0),
+            tunnels: Arc::new(SshTunnels::default()),
+            credentials: Arc::new(Credentials::default()),
+            blind_spots: Arc::new(Vec::new()),
+        }
+    }
+
+    pub fn with_credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Arc::new(credentials);
+        self
+    }
+
+    pub fn with_signer(mut self, signer: Option<CheckSigner>) -> Self {
+        self.signer = signer.map(Arc::new);
+        self
+    }
+
+    pub fn store_path(&self) -> &str {
+        &self.store_path
+    }
+
+    pub fn with_watchdog(mut self, watchdog: PersistenceWatchdog) -> Self {
+        self.watchdog = Arc::new(watchdog);
+        self
+    }
+
+    pub fn with_blind_spots(mut self, blind_spots: Vec<BlindSpot>) -> Self {
+        self.blind_spots = Arc::new(blind_spots);
+        self
+    }
+
+    // The default retention for monitors without their own.
+    pub fn with_retention(mut self, retention: HistoryRetention) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    // Starts a monitoring task for every domain currently in the list.
+    pub fn start(&self) {
+        let domain_ids: Vec<Uuid> = {
+            let domains_guard = self.domains.lock().unwrap();
+            domains_guard.iter().map(|d| d.id).collect()
+        };
+
+        tracing::debug!("Starting monitoring task for {} domains", domain_ids.len());
+
+        self.spawn_snapshot_task();
+        for domain_id in domain_ids {
+            self.spawn_domain_task(domain_id);
+        }
+    }
+
+    // The latest state of every domain, cheap enough to call on every frame.
+    pub fn snapshot(&self) -> Arc<MonitorSnapshot> {
+        Arc::clone(&self.snapshot.borrow())
+    }
+
+    fn update_snapshot(
+        snapshot: &watch::Sender<Arc<MonitorSnapshot>>,
+        update: impl FnOnce(&mut MonitorSnapshot),
+    ) {
+        snapshot.send_modify(|snapshot| update(Arc::make_mut(snapshot)));
+    }
+
+    // Keeps the snapshot in step with the monitor's events, so nothing that
+    // draws it has to lock the domains.
+    fn spawn_snapshot_task(&self) {
+        let domains = Arc::clone(&self.domains);
+        let snapshot = Arc::clone(&self.snapshot);
+        let mut events = self.events.subscribe();
+        let mut stop_receiver = self.stop.subscribe();
+
+        // Anything changed since the handle was created. The domains are
+        // always locked before the snapshot, never the other way round.
+        let domains_guard = domains.lock().unwrap();
+        Self::update_snapshot(&snapshot, |s| s.replace_domains(&domains_guard));
+        drop(domains_guard);
+
+        tokio::spawn(async move {
+            let updates = async {
+                loop {
+                    match events.recv().await {
+                        Ok(MonitorEvent::CheckCompleted { domain_id, .. }) => {
+                            let domains_guard = domains.lock().unwrap();
+                            if let Some(d) = domains_guard.iter().find(|d| d.id == domain_id) {
+                                Self::update_snapshot(&snapshot, |s| s.upsert(d));
+                            }
+                        }
+                        Ok(
+                            MonitorEvent::DomainAdded { domain }
+                            | MonitorEvent::DomainUpdated { domain },
+                        ) => Self::update_snapshot(&snapshot, |s| s.upsert(&domain)),
+                        Ok(MonitorEvent::DomainRemoved { domain_id, .. }) => {
+                            Self::update_snapshot(&snapshot, |s| s.remove(domain_id))
+                        }
+                        Ok(
+                            MonitorEvent::StatusChanged { .. }
+                            | MonitorEvent::PersistenceFailing { .. }
+                            | MonitorEvent::PersistenceRecovered { .. },
+                        ) => {}
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            tracing::debug!("Snapshot missed {} events, rebuilding it", missed);
+                            let domains_guard = domains.lock().unwrap();
+                            Self::update_snapshot(&snapshot, |s| s.replace_domains(&domains_guard));
                         }
+                        Err(broadcast::error::RecvError::Closed) => break,
                     }
-                };
+                }
+            };
+
+            tokio::select! {
+                _ = updates => {}
+                _ = wait_until_stopped(&mut stop_receiver) => {}
+            }
+        });
+    }
+
+    // Stops every monitoring task started from this handle, e.g. when
+    // switching to another profile.
+    pub fn stop(&self) {
+        self.stop.send_replace(true);
+    }
+
+    // Holds every check until resumed, e.g. during planned maintenance. Checks
+    // already under way still finish.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.send_replace(paused);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    // Adding, updating and removing domains goes through here so the store and
+    // the running tasks stay in step, whether the change comes from the TUI or
+    // the API. Running tasks re-read their domain every round, so only new
+    // domains need a task of their own.
+    pub fn add_domain(&self, domain: MonitoredDomain) -> io::Result<()> {
+        let domain_id = domain.id;
+        {
+            let mut domains_guard = self.domains.lock().unwrap();
+            domains_guard.push(domain.clone());
+            save_domains(&domains_guard, &self.store_path)?;
+        }
+        self.spawn_domain_task(domain_id);
+        let _ = self.events.send(MonitorEvent::DomainAdded { domain });
+        Ok(())
+    }
+
+    // Copies the settings of `edited` over, keeping the stored check history
+    // since it may have grown in the meantime. Returns the updated domain.
+    pub fn update_domain(&self, edited: &MonitoredDomain) -> io::Result<Option<MonitoredDomain>> {
+        let mut domains_guard = self.domains.lock().unwrap();
+        let Some(d) = domains_guard.iter_mut().find(|d| d.id == edited.id) else {
+            return Ok(None);
+        };
+        // What was found is about the old URL's certificate.
+        if d.url != edited.url {
+            d.certificate = None;
+        }
+        d.url = edited.url.clone();
+        d.display_name = edited.display_name.clone();
+        d.tags = edited.tags.clone();
+        d.copy_settings_from(edited);
+        let updated = d.clone();
+
+        save_domains(&domains_guard, &self.store_path)?;
+        let _ = self.events.send(MonitorEvent::DomainUpdated {
+            domain: updated.clone(),
+        });
+        Ok(Some(updated))
+    }
+
+    // Gives every other monitor of the edited one's group its settings.
+    pub fn update_group(&self, edited: &MonitoredDomain) -> io::Result<()> {
+        let Some(group) = &edited.group else {
+            return Ok(());
+        };
+        let mut domains_guard = self.domains.lock().unwrap();
+        let mut updated = Vec::new();
+        for d in domains_guard
+            .iter_mut()
+            .filter(|d| d.group.as_ref() == Some(group) && d.id != edited.id)
+        {
+            d.copy_settings_from(edited);
+            updated.push(d.clone());
+        }
+        if updated.is_empty() {
+            return Ok(());
+        }
+
+        save_domains(&domains_guard, &self.store_path)?;
+        for domain in updated {
+            let _ = self.events.send(MonitorEvent::DomainUpdated { domain });
+        }
+        Ok(())
+    }
 
-                let mut domains_clone = {
-                    let domain_guard = domains_arc_clone.lock().unwrap();
-                    domain_guard.clone()
+    pub fn next_check_at(&self, domain_id: Uuid) -> Option<DateTime<Utc>> {
+        self.snapshot.borrow().next_checks.get(&domain_id).copied()
+    }
+
+    pub fn upcoming_checks(&self) -> Vec<DateTime<Utc>> {
+        self.snapshot.borrow().upcoming_checks()
+    }
+
+    // Edits aren't allowed to touch the notes (the edit form and monitors
+    // files don't have them), so they're saved through here.
+    pub fn set_incident_note(
+        &self,
+        domain_id: Uuid,
+        incident_started_at: DateTime<Utc>,
+        note: &str,
+    ) -> io::Result<Option<MonitoredDomain>> {
+        let mut domains_guard = self.domains.lock().unwrap();
+        let Some(d) = domains_guard.iter_mut().find(|d| d.id == domain_id) else {
+            return Ok(None);
+        };
+        d.set_incident_note(incident_started_at, note, Utc::now());
+        let updated = d.clone();
+
+        save_domains(&domains_guard, &self.store_path)?;
+        let _ = self.events.send(MonitorEvent::DomainUpdated {
+            domain: updated.clone(),
+        });
+        Ok(Some(updated))
+    }
+
+    // Holds back the domain's status change notifications until `until`, None
+    // unmutes it.
+    pub fn mute_domain(
+        &self,
+        domain_id: Uuid,
+        until: Option<DateTime<Utc>>,
+    ) -> io::Result<Option<MonitoredDomain>> {
+        let mut domains_guard = self.domains.lock().unwrap();
+        let Some(d) = domains_guard.iter_mut().find(|d| d.id == domain_id) else {
+            return Ok(None);
+        };
+        d.muted_until = until;
+        d.muted_from = until.map(|_| Utc::now());
+        let updated = d.clone();
+
+        save_domains(&domains_guard, &self.store_path)?;
+        let _ = self.events.send(MonitorEvent::DomainUpdated {
+            domain: updated.clone(),
+        });
+        Ok(Some(updated))
+    }
+
+    pub fn set_tags(
+        &self,
+        domain_id: Uuid,
+        tags: Vec<String>,
+    ) -> io::Result<Option<MonitoredDomain>> {
+        let mut domains_guard = self.domains.lock().unwrap();
+        let Some(d) = domains_guard.iter_mut().find(|d| d.id == domain_id) else {
+            return Ok(None);
+        };
+        d.tags = tags;
+        let updated = d.clone();
+
+        save_domains(&domains_guard, &self.store_path)?;
+        let _ = self.events.send(MonitorEvent::DomainUpdated {
+            domain: updated.clone(),
+        });
+        Ok(Some(updated))
+    }
+
+    pub fn remove_domain(&self, domain_id: Uuid) -> io::Result<Option<MonitoredDomain>> {
+        let mut domains_guard = self.domains.lock().unwrap();
+        let Some(index) = domains_guard.iter().position(|d| d.id == domain_id) else {
+            return Ok(None);
+        };
+        let removed = domains_guard.remove(index);
+        body_regexes().lock().unwrap().remove(&domain_id);
+
+        save_domains(&domains_guard, &self.store_path)?;
+        let _ = self.events.send(MonitorEvent::DomainRemoved {
+            domain_id,
+            url: removed.url.clone(),
+        });
+        Ok(Some(removed))
+    }
+
+    // One check outside the schedule, e.g. for a soak test. It counts against
+    // the request budget but isn't recorded in the history.
+    pub async fn check_now(&self, domain: &MonitoredDomain) -> CheckStatus {
+        let span = tracing::info_span!("check now", url = %domain.url);
+        async {
+            acquire_budget(&self.budget, &domain.url).await;
+            check_domain(
+                &self.client,
+                &self.tunnels,
+                &self.credentials,
+                &self.budget,
+                domain,
+            )
+            .await
+            .0
+        }
+        .instrument(span)
+        .await
+    }
+
+    fn spawn_domain_task(&self, domain_id: Uuid) {
+        let client = self.client.clone();
+        let tunnels = Arc::clone(&self.tunnels);
+        let credentials = Arc::clone(&self.credentials);
+        let domains_arc_clone = Arc::clone(&self.domains);
+        let update_domains_callback_clone = Arc::clone(&self.update_domains_callback);
+        let budget = Arc::clone(&self.budget);
+        let events = self.events.clone();
+        let signer = self.signer.clone();
+        let retention = self.retention;
+        let snapshot = Arc::clone(&self.snapshot);
+        let watchdog = Arc::clone(&self.watchdog);
+        let mut stop_receiver = self.stop.subscribe();
+        let mut pause_receiver = self.paused.subscribe();
+
+        tokio::spawn(async move {
+            tracing::debug!("Monitoring task started for domain {}", domain_id);
+
+            let task_snapshot = Arc::clone(&snapshot);
+            let schedule_next = move |delay: time::Duration| {
+                let next_check = Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+                Self::update_snapshot(&task_snapshot, |s| {
+                    s.set_next_check(domain_id, Some(next_check))
+                });
+            };
+            schedule_next(time::Duration::ZERO);
+            let paused_snapshot = Arc::clone(&snapshot);
+
+            let checks = async move {
+                let record = |check: CheckStatus| {
+                    let recorded = record_check(
+                        &domains_arc_clone,
+                        domain_id,
+                        check,
+                        &update_domains_callback_clone,
+                        &events,
+                        signer.as_deref(),
+                        retention,
+                    );
+                    let Some((check, saved)) = recorded else {
+                        return;
+                    };
+                    // What the save that worked put on disk from while it failed.
+                    let held = |domain_id: Uuid, since: DateTime<Utc>| {
+                        let domains_guard = domains_arc_clone.lock().unwrap();
+                        let domain = domains_guard.iter().find(|d| d.id == domain_id)?;
+                        let held = domain
+                            .check_history
+                            .iter()
+                            .filter(|c| c.timestamp >= since && c.timestamp != check.timestamp);
+                        Some(held.count())
+                    };
+                    if let Some(alert) =
+                        watchdog.record_save(domain_id, check.timestamp, &saved, held)
+                    {
+                        let _ = events.send(alert);
+                    }
                 };
+                // The route is traced in a task of its own so the next check
+                // isn't held up, and recorded between checks like the rest.
+                let mut pending_trace: Option<PendingTrace> = None;
+                // Failed certificate inspections back off rather than retry
+                // every check.
+                let mut certificate_failures = 0;
+                let mut certificate_retry_at: Option<DateTime<Utc>> = None;
+                loop {
+                    // Re-read the domain every round so edits take effect on the next check.
+                    let domain = {
+                        let domains_guard = domains_arc_clone.lock().unwrap();
+                        domains_guard
+                            .iter()
+                            .find(|d| d.id == domain_id)
+                            .map(|d| d.with_recent_history(timing::OUTLIER_WINDOW * 2))
+                    };
+                    let Some(domain) = domain else {
+                        tracing::debug!(
+                            "Domain {} was removed, stopping its monitoring task",
+                            domain_id
+                        );
+                        break;
+                    };
+                    if *pause_receiver.borrow_and_update() {
+                        // Nothing is due while paused, the check runs on resume.
+                        Self::update_snapshot(&paused_snapshot, |s| {
+                            s.set_next_check(domain_id, None)
+                        });
+                        wait_until_resumed(&mut pause_receiver).await;
+                        schedule_next(time::Duration::ZERO);
+                        continue;
+                    }
+                    let interval = time::Duration::from_secs(domain.interval_seconds);
+                    let span = tracing::info_span!(
+                        "check",
+                        url = %domain.url,
+                        domain_id = %domain_id,
+                        status = field::Empty,
+                        next_check_in_s = field::Empty,
+                    );
+                    let (head_status, retry_after) = async {
+                        acquire_budget(&budget, &domain.url).await;
+                        check_domain(&client, &tunnels, &credentials, &budget, &domain).await
+                    }
+                    .instrument(span.clone())
+                    .await;
+                    span.record("status", status_label(&head_status.status));
+                    let up = matches!(head_status.status, DomainStatus::Up);
+                    // Up to Down or Error, when the route at the time tells
+                    // whose network the problem is on.
+                    let went_down = matches!(
+                        head_status.status,
+                        DomainStatus::Down | DomainStatus::Error(_)
+                    ) && domain
+                        .check_history
+                        .iter()
+                        .rev()
+                        .find(|c| !blind_spot::is_marker(c))
+                        .is_some_and(|c| {
+                            matches!(c.status, DomainStatus::Up | DomainStatus::Degraded)
+                        });
+                    let checked_at = head_status.timestamp;
+
+                    span.in_scope(|| record(head_status));
+
+                    // Traced from here, so not for checks through a jump host.
+                    if went_down
+                        && pending_trace.is_none()
+                        && domain.ssh_jump_host.is_none()
+                        && let Some(host) = traceroute::trace_host(&domain.url)
+                    {
+                        let trace_span = tracing::info_span!(parent: &span, "traceroute");
+                        let trace = tokio::spawn(async move {
+                            match traceroute::trace(&host).instrument(trace_span).await {
+                                Ok(hops) => Some(hops),
+                                Err(e) => {
+                                    tracing::debug!("Tracing the route to {} failed: {}", host, e);
+                                    None
+                                }
+                            }
+                        });
+                        pending_trace = Some((checked_at, trace));
+                    }
+
+                    // The certificate chain is looked at once a day, on a
+                    // direct connection so not through a jump host.
+                    if up
+                        && domain.url.starts_with("https://")
+                        && domain.ssh_jump_host.is_none()
+                        && CertificateInspection::is_due(domain.certificate.as_ref(), Utc::now())
+                        && certificate_retry_at.is_none_or(|at| Utc::now() >= at)
+                    {
+                        let limit = domain.timeout();
+                        let inspection = async {
+                            acquire_budget(&budget, &domain.url).await;
+                            certificate::inspect(&domain.url, limit).await
+                        };
+                        match inspection
+                            .instrument(tracing::info_span!(parent: &span, "certificate"))
+                            .await
+                        {
+                            Ok(inspection) => {
+                                certificate_failures = 0;
+                                certificate_retry_at = None;
+                                record_certificate(
+                                    &domains_arc_clone,
+                                    domain_id,
+                                    inspection,
+                                    &events,
+                                );
+                            }
+                            Err(e) => {
+                                certificate_failures += 1;
+                                let delay =
+                                    CertificateInspection::retry_delay(certificate_failures);
+                                certificate_retry_at = Some(Utc::now() + delay);
+                                tracing::debug!(
+                                    "Inspecting the certificate of {} failed, trying again in {}m: {}",
+                                    domain.url,
+                                    delay.num_minutes(),
+                                    e
+                                );
+                            }
+                        }
+                    }
 
-                if let Some(d) = domains_clone.iter_mut().find(|d| d.id == domain_id) {
-                    d.check_history.push(head_status);
+                    // Never come back sooner than the server asked us to.
+                    let next_check_in = retry_after.map_or(interval, |delay| delay.max(interval));
+                    span.record("next_check_in_s", next_check_in.as_secs());
 
-                    if d.check_history.len() > 100 {
-                        d.check_history.drain(0..d.check_history.len() - 100); // Only keep the last 100
+                    let sleep_started_wall = Utc::now();
+                    let sleep_started_mono = Instant::now();
+                    schedule_next(next_check_in);
+                    let wake = sleep(next_check_in);
+                    tokio::pin!(wake);
+                    loop {
+                        let Some((traced_at, trace)) = pending_trace.as_mut() else {
+                            wake.as_mut().await;
+                            break;
+                        };
+                        let traced_at = *traced_at;
+                        tokio::select! {
+                            _ = wake.as_mut() => break,
+                            hops = trace => {
+                                pending_trace = None;
+                                if let Ok(Some(hops)) = hops {
+                                    record_traceroute(
+                                        &domains_arc_clone,
+                                        domain_id,
+                                        traced_at,
+                                        hops,
+                                        &update_domains_callback_clone,
+                                        &events,
+                                        signer.as_deref(),
+                                    );
+                                }
+                            }
+                        }
                     }
 
-                    let update_callback_deref = update_domains_callback_clone.deref();
-                    if let Err(e) = update_callback_deref(d, &d.check_history) {
-                        log::error!("Failed to save domain {} after check: {}", d.url, e);
+                    // Tokio sleeps on the monotonic clock, which stops while the machine is
+                    // suspended. Comparing it against the wall clock tells us when we woke up
+                    // from a sleep (or the clock was changed) so the gap can be recorded and
+                    // the checks of all domains don't fire in the same instant.
+                    let wall_elapsed = Utc::now() - sleep_started_wall;
+                    if let Some(jump) =
+                        detect_clock_jump(sleep_started_mono.elapsed(), wall_elapsed)
+                    {
+                        tracing::debug!(
+                            "Clock jump of {}s detected for {}, re-staggering checks",
+                            jump.num_seconds(),
+                            domain.url
+                        );
+                        tracing::info!(
+                            parent: &span,
+                            jump_s = jump.num_seconds(),
+                            "clock jump, re-staggering checks"
+                        );
+                        record(clock_jump_annotation(sleep_started_wall, jump));
+                        let jitter = wake_jitter(interval);
+                        schedule_next(jitter);
+                        sleep(jitter).await;
                     }
                 }
+            };
 
-                sleep(interval).await;
+            tokio::select! {
+                _ = checks => {}
+                _ = wait_until_stopped(&mut stop_receiver) => {
+                    tracing::debug!("Monitoring of domain {} stopped", domain_id);
+                }
             }
+            Self::update_snapshot(&snapshot, |s| s.set_next_check(domain_id, None));
         });
     }
 }
 
-async fn domain_head_request(client: &Client, url: &str) -> Result<StatusCode, reqwest::Error> {
-    let res = client.head(url).send().await?;
-    Ok(res.status())
+// Waits for the request budget in a span of its own, so time spent queued
+// behind other checks of the host shows apart from the check.
+async fn acquire_budget(budget: &RequestBudget, url: &str) {
+    let host = budget_host(url);
+    budget
+        .acquire(&host)
+        .instrument(tracing::info_span!("budget wait", host = %host))
+        .await;
+}
+
+// Checks the domain the way its URL's scheme asks for, HTTP(S) ones through
+// their SSH jump host when they have one.
+async fn check_domain(
+    client: &Client,
+    tunnels: &SshTunnels,
+    credentials: &Credentials,
+    budget: &RequestBudget,
+    domain: &MonitoredDomain,
+) -> (CheckStatus, Option<time::Duration>) {
+    match CheckTarget::parse(&domain.url) {
+        Ok(CheckTarget::Docker { container }) => {
+            return (docker::check(&container, domain).await, None);
+        }
+        Ok(CheckTarget::Kubernetes {
+            namespace,
+            selector,
+        }) => return (kubernetes::check(&namespace, &selector, domain).await, None),
+        Ok(CheckTarget::Ping { host }) => return (ping::check(&host, domain).await, None),
+        Ok(CheckTarget::Dns(query)) => return (dns::check(&query, domain).await, None),
+        Ok(CheckTarget::Redfish { host, system }) => {
+            return (
+                redfish::check(&host, system.as_deref(), &credentials.bmc, domain).await,
+                None,
+            );
+        }
+        Ok(CheckTarget::Snmp(query)) => {
+            return (snmp::check(&query, &credentials.snmp, domain).await, None);
+        }
+        Ok(CheckTarget::Ntp(query)) => return (ntp::check(&query, domain).await, None),
+        Ok(CheckTarget::LocalFile(query)) => {
+            return (local_file::check(&query, domain).await, None);
+        }
+        Ok(CheckTarget::Ssh(query)) => return (ssh::check(&query, domain).await, None),
+        Ok(CheckTarget::Smtp(query)) => return (smtp::check(&query, domain).await, None),
+        Ok(CheckTarget::Host(query)) => return (host::check(&query, domain).await, None),
+        Ok(CheckTarget::Grpc(query)) => return (grpc::check(&query, domain).await, None),
+        Ok(CheckTarget::Feed(query)) => return (feed::check(client, &query, domain).await, None),
+        Ok(CheckTarget::StatusPage(query)) => {
+            return (status_page::check(client, &query, domain).await, None);
+        }
+        Ok(CheckTarget::FileTransfer(query)) => {
+            return (
+                file_transfer::check(&query, &credentials.ftp, domain).await,
+                None,
+            );
+        }
+        _ => {}
+    }
+    let headers = match resolve_headers(credentials, domain).await {
+        Ok(headers) => headers,
+        Err(e) => return (failed_check(e, &domain.url), None),
+    };
+    let Some(jump_host) = &domain.ssh_jump_host else {
+        return run_check(client, budget, domain, &headers).await;
+    };
+    match tunnels.client(jump_host).await {
+        Ok(tunnel_client) => run_check(&tunnel_client, budget, domain, &headers).await,
+        Err(e) => (
+            failed_check(format!("SSH tunnel via {}: {}", jump_host, e), &domain.url),
+            None,
+        ),
+    }
+}
+
+// A secret added while running opens the secrets store on first use, which
+// reads a file and derives a key, so that's kept off the runtime's threads.
+async fn resolve_headers(
+    credentials: &Credentials,
+    domain: &MonitoredDomain,
+) -> Result<BTreeMap<String, String>, String> {
+    if domain.auth.is_none()
+        && !domain
+            .headers
+            .values()
+            .any(|v| SecretResolver::is_secret(v))
+    {
+        return credentials.request_headers(&domain.headers, None);
+    }
+    let headers = domain.headers.clone();
+    let auth = domain.auth.clone();
+    let credentials = credentials.clone();
+    tokio::task::spawn_blocking(move || credentials.request_headers(&headers, auth.as_ref()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Like HTTP checks, one that got no answer has no response time.
+fn response_time(answered: bool, start: DateTime<Utc>, end: DateTime<Utc>) -> Option<u64> {
+    answered.then(|| (end - start).num_milliseconds() as u64)
+}
+
+// An Error check for what went wrong before the request could be sent.
+fn failed_check(message: String, url: &str) -> CheckStatus {
+    let err_msg: Arc<str> = message.into();
+    tracing::error!("Error checking {}: {}", url, err_msg);
+    CheckStatus {
+        http_code: Some(HttpCode::NetworkError),
+        ..CheckStatus::failed(Utc::now(), err_msg)
+    }
+}
+
+// Requests the domain once and judges the response, without recording it.
+// Also returns how long the server asked us to wait when it rate limited us.
+// The headers are the domain's plus its Authorization.
+async fn run_check(
+    client: &Client,
+    budget: &RequestBudget,
+    domain: &MonitoredDomain,
+    headers: &BTreeMap<String, String>,
+) -> (CheckStatus, Option<time::Duration>) {
+    let start_time = Utc::now();
+    let head_req_result = domain_request(client, domain, headers).await;
+    let end_time = Utc::now();
+    let response_time = (end_time - start_time).num_milliseconds() as u64;
+
+    let mut retry_after = None;
+
+    let check = match head_req_result {
+        Ok((response, redirects)) => {
+            let status_code = response.status();
+            let http_code = HttpCode::from_status_code(status_code);
+            // What the server says it sent, the bytes read when the body was.
+            let mut response_bytes = response
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|length| length.to_str().ok())
+                .and_then(|length| length.parse().ok());
+            let mut error_message = None;
+            let mut domain_status = if let Some(delay) = rate_limit_delay(&response, end_time) {
+                tracing::debug!(
+                    "{} is rate limited ({}), retrying after {:?}",
+                    domain.url,
+                    status_code,
+                    delay
+                );
+                retry_after = Some(delay);
+                DomainStatus::RateLimited
+            } else {
+                let landed_on = redirect_target(&response);
+                let body = if domain.needs_body() {
+                    response.text().await.ok()
+                } else {
+                    None
+                };
+                if let Some(body) = &body {
+                    response_bytes = Some(body.len() as u64);
+                }
+                let check_response = CheckResponse {
+                    status: status_code.as_u16(),
+                    response_time_ms: response_time,
+                    body: body.as_deref(),
+                };
+                let (status, failure) = judge_response(domain, &check_response);
+                error_message = failure.or_else(|| {
+                    (status != DomainStatus::Up && status_code.is_redirection())
+                        .then(|| unfollowed_redirect(domain, &redirects))
+                        .flatten()
+                });
+                if status == DomainStatus::Up && !domain.expects_redirect_to(&landed_on) {
+                    error_message = Some(format!(
+                        "Landed on {} instead of {}",
+                        landed_on,
+                        domain.expected_redirect.as_deref().unwrap_or_default()
+                    ));
+                    DomainStatus::Down
+                } else {
+                    status
+                }
+            };
+            // The challenge is a request of its own, so it waits its turn too.
+            if matches!(domain_status, DomainStatus::Up)
+                && let Some(challenge) = &domain.challenge
+                && let Err(failed) = async {
+                    acquire_budget(budget, &domain.url).await;
+                    challenge
+                        .check(
+                            client,
+                            &domain.url,
+                            headers,
+                            domain.timeout_seconds.map(time::Duration::from_secs),
+                        )
+                        .await
+                }
+                .await
+            {
+                error_message = Some(format!("Failed challenge: {}", failed));
+                domain_status = DomainStatus::Down;
+            }
+            // Break an unusually slow response down by phase to hint at why.
+            // The probe connects directly, so not through a jump host.
+            let timings = if domain.ssh_jump_host.is_none()
+                && timing::is_latency_outlier(&domain.check_history, response_time)
+            {
+                let limit = domain.timeout();
+                acquire_budget(budget, &domain.url).await;
+                timing::measure(&domain.url, limit)
+                    .await
+                    .inspect_err(|e| tracing::debug!("Timing {} failed: {}", domain.url, e))
+                    .ok()
+            } else {
+                None
+            };
+            CheckStatus {
+                http_code: Some(http_code),
+                error_message: error_message.map(Arc::from),
+                response_time_ms: Some(response_time),
+                timings,
+                redirects,
+                response_bytes,
+                ..CheckStatus::new(end_time, domain_status)
+            }
+        }
+        Err(e) => {
+            // Shared by the status and the message rather than copied.
+            let err_msg: Arc<str> = e.to_string().into();
+            tracing::error!("Error checking {}: {}", domain.url, err_msg);
+            CheckStatus {
+                http_code: if e.is_timeout() {
+                    Some(HttpCode::Timeout)
+                } else {
+                    Some(HttpCode::NetworkError)
+                },
+                ..CheckStatus::failed(end_time, err_msg)
+            }
+        }
+    };
+    (check, retry_after)
+}
+
+// Up for a 2xx status, or what the success criteria ask for instead, as
+// long as the body has the keyword and matches the body regex. Otherwise
+// Down with what failed, plus what the regex captured when it did match.
+fn judge_response(
+    domain: &MonitoredDomain,
+    response: &CheckResponse,
+) -> (DomainStatus, Option<String>) {
+    let (status, error_message) = judge_status_and_keyword(domain, response);
+    let Some(pattern) = &domain.body_regex else {
+        return (status, error_message);
+    };
+    match (status, match_body(domain.id, pattern, response.body)) {
+        (DomainStatus::Up, Err(failure)) => (DomainStatus::Down, Some(failure)),
+        (status, Ok(Some(captured))) if status != DomainStatus::Up => {
+            let captured = format!("body regex captured {:?}", captured);
+            let message = match error_message {
+                Some(message) => format!("{}, {}", message, captured),
+                None => captured,
+            };
+            (status, Some(message))
+        }
+        (status, _) => (status, error_message),
+    }
+}
+
+// The regex's first capture group in the body, None when it has no groups
+// or the group didn't take part in the match. Err when there's no match.
+fn match_body(
+    domain_id: Uuid,
+    pattern: &str,
+    body: Option<&str>,
+) -> Result<Option<String>, String> {
+    let regex = body_regex(domain_id, pattern)?;
+    let captures = body
+        .and_then(|body| regex.captures(body))
+        .ok_or_else(|| format!("Body doesn't match the regex {:?}", pattern))?;
+    Ok(captures.get(1).map(|group| group.as_str().to_string()))
+}
+
+// Compiled once per monitor rather than on every check, and again when its
+// pattern is edited. remove_domain drops the monitor's entry.
+fn body_regexes() -> &'static Mutex<HashMap<Uuid, Regex>> {
+    static COMPILED: OnceLock<Mutex<HashMap<Uuid, Regex>>> = OnceLock::new();
+    COMPILED.get_or_init(Default::default)
+}
+
+fn body_regex(domain_id: Uuid, pattern: &str) -> Result<Regex, String> {
+    if let Some(regex) = body_regexes().lock().unwrap().get(&domain_id)
+        && regex.as_str() == pattern
+    {
+        return Ok(regex.clone());
+    }
+    let regex = Regex::new(pattern).map_err(|e| format!("Invalid body regex: {}", e))?;
+    body_regexes()
+        .lock()
+        .unwrap()
+        .insert(domain_id, regex.clone());
+    Ok(regex)
+}
+
+fn judge_status_and_keyword(
+    domain: &MonitoredDomain,
+    response: &CheckResponse,
+) -> (DomainStatus, Option<String>) {
+    if !domain.expected_status_codes.is_empty() && !domain.expects_status(response.status) {
+        return (
+            DomainStatus::Down,
+            Some(format!(
+                "Status {} isn't one of the expected {}",
+                response.status,
+                format_status_codes(&domain.expected_status_codes)
+            )),
+        );
+    }
+    if let Some(criteria) = &domain.success_criteria {
+        if let Err(failed) = criteria.evaluate(response) {
+            return (
+                DomainStatus::Down,
+                Some(format!("Failed criterion: {}", failed)),
+            );
+        }
+    } else if !domain.expects_status(response.status) {
+        return (DomainStatus::Down, None);
+    }
+    // The keyword is a body_contains criterion ANDed onto the others, kept
+    // as its own field for the edit form and Uptime Kuma.
+    if let Some(keyword) = &domain.keyword
+        && Criterion::BodyContains(keyword.clone())
+            .evaluate(response)
+            .is_err()
+    {
+        return (
+            DomainStatus::Down,
+            Some(format!("Keyword {:?} not found in the response", keyword)),
+        );
+    }
+    (DomainStatus::Up, None)
+}
+
+// Resolves once `MonitorHandle::stop` is called. Dropping every handle
+// without stopping leaves the tasks running.
+async fn wait_until_stopped(stop_receiver: &mut watch::Receiver<bool>) {
+    if stop_receiver.wait_for(|stopped| *stopped).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+async fn wait_until_resumed(pause_receiver: &mut watch::Receiver<bool>) {
+    if pause_receiver.wait_for(|paused| !*paused).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+// Kept with the domain and saved along with its next check.
+fn record_certificate(
+    domains: &Arc<Mutex<Vec<MonitoredDomain>>>,
+    domain_id: Uuid,
+    inspection: CertificateInspection,
+    events: &broadcast::Sender<MonitorEvent>,
+) {
+    let updated = {
+        let mut domains_guard = domains.lock().unwrap();
+        domains_guard
+            .iter_mut()
+            .find(|d| d.id == domain_id)
+            .map(|d| {
+                let known = d.certificate.as_ref().map(|c| &c.warnings);
+                if !inspection.warnings.is_empty() && known != Some(&inspection.warnings) {
+                    tracing::warn!(
+                        "Weak certificate chain for {}: {}",
+                        d.url,
+                        inspection.warnings.join("; ")
+                    );
+                }
+                d.certificate = Some(inspection);
+                d.clone()
+            })
+    };
+    if let Some(domain) = updated {
+        let _ = events.send(MonitorEvent::DomainUpdated { domain });
+    }
+}
+
+// A traceroute still running, with when the check it's for was taken.
+type PendingTrace = (DateTime<Utc>, JoinHandle<Option<Vec<TraceHop>>>);
+
+// Attaches the route to the check that went down. It's re-signed, being the
+// newest record nothing chains to it yet.
+fn record_traceroute(
+    domains: &Arc<Mutex<Vec<MonitoredDomain>>>,
+    domain_id: Uuid,
+    checked_at: DateTime<Utc>,
+    hops: Vec<TraceHop>,
+    update_domains_callback: &Arc<DomainCallbackType>,
+    events: &broadcast::Sender<MonitorEvent>,
+    signer: Option<&CheckSigner>,
+) {
+    let domain = {
+        let domain_guard = domains.lock().unwrap();
+        domain_guard.iter().find(|d| d.id == domain_id).cloned()
+    };
+    let Some(mut d) = domain else {
+        return;
+    };
+    let Some(index) = d
+        .check_history
+        .iter()
+        .position(|c| c.timestamp == checked_at)
+    else {
+        return;
+    };
+    d.check_history[index].traceroute = hops;
+    // Checks recorded since chain on this one's hash, so they're signed again too.
+    if let Some(signer) = signer {
+        for i in index..d.check_history.len() {
+            let (before, rest) = d.check_history.split_at_mut(i);
+            signer.sign(domain_id, before.last(), &mut rest[0]);
+        }
+    }
+
+    let update_callback_deref = update_domains_callback.deref();
+    if let Err(e) = update_callback_deref(&d, &d.check_history) {
+        tracing::error!("Failed to save domain {} after traceroute: {}", d.url, e);
+    }
+    let _ = events.send(MonitorEvent::DomainUpdated { domain: d });
+}
+
+#[tracing::instrument(skip_all)]
+fn record_check(
+    domains: &Arc<Mutex<Vec<MonitoredDomain>>>,
+    domain_id: Uuid,
+    mut check: CheckStatus,
+    update_domains_callback: &Arc<DomainCallbackType>,
+    events: &broadcast::Sender<MonitorEvent>,
+    signer: Option<&CheckSigner>,
+    default_retention: HistoryRetention,
+) -> Option<(CheckStatus, io::Result<()>)> {
+    // Only this domain is copied, the callback writes it back.
+    let domain = {
+        let domain_guard = domains.lock().unwrap();
+        domain_guard.iter().find(|d| d.id == domain_id).cloned()
+    };
+
+    if let Some(mut d) = domain {
+        if let Some(previous) = d.check_history.last() {
+            check.share_strings_with(previous);
+        }
+        // A blind spot isn't a status of its own, so coming back Up after one
+        // is no news.
+        let previous_status = d
+            .check_history
+            .iter()
+            .rev()
+            .find(|c| !blind_spot::is_marker(c))
+            .map(|c| c.status.clone());
+        let status_changed = previous_status
+            .as_ref()
+            .is_none_or(|previous| mem::discriminant(previous) != mem::discriminant(&check.status));
+
+        // Sending only fails when nobody is subscribed, which is fine.
+        if status_changed {
+            let _ = events.send(MonitorEvent::StatusChanged {
+                domain_id,
+                url: d.url.clone(),
+                from: previous_status,
+                to: check.status.clone(),
+                owner: d.owner.clone(),
+                runbook: d.runbook().map(String::from),
+            });
+        }
+
+        if let Some(signer) = signer {
+            signer.sign(domain_id, d.check_history.last(), &mut check);
+        }
+        let _ = events.send(MonitorEvent::CheckCompleted {
+            domain_id,
+            url: d.url.clone(),
+            check: check.clone(),
+        });
+        d.push_check(check.clone());
+        d.compact_history(d.history_retention(default_retention), Utc::now());
+
+        let update_callback_deref = update_domains_callback.deref();
+        let saved = update_callback_deref(&d, &d.check_history);
+        if let Err(e) = &saved {
+            tracing::error!("Failed to save domain {} after check: {}", d.url, e);
+        }
+        return Some((check, saved));
+    }
+    None
+}
+
+// Returns how far the wall clock moved past the monotonic clock, if that is
+// more than we can explain by scheduling noise.
+fn detect_clock_jump(
+    mono_elapsed: time::Duration,
+    wall_elapsed: chrono::Duration,
+) -> Option<chrono::Duration> {
+    let mono_elapsed = chrono::Duration::from_std(mono_elapsed).ok()?;
+    let skew = wall_elapsed - mono_elapsed;
+
+    if skew.num_seconds().abs() > CLOCK_JUMP_THRESHOLD_SECS {
+        Some(skew)
+    } else {
+        None
+    }
+}
+
+fn clock_jump_annotation(gap_start: DateTime<Utc>, jump: chrono::Duration) -> CheckStatus {
+    let message = if jump > chrono::Duration::zero() {
+        format!(
+            "No checks for {}s (system sleep or clock change)",
+            jump.num_seconds()
+        )
+    } else {
+        format!("System clock moved back by {}s", jump.num_seconds().abs())
+    };
+
+    CheckStatus {
+        error_message: Some(message.into()),
+        ..CheckStatus::new(gap_start, DomainStatus::Unknown)
+    }
+}
+
+// A random delay within one interval so domains don't all get checked at the
+// same moment after waking up.
+fn wake_jitter(interval: time::Duration) -> time::Duration {
+    let max_ms = interval.as_millis().max(1) as u64;
+    time::Duration::from_millis(rand::rng().random_range(0..max_ms))
+}
+
+// A 429 is always a rate limit; a 503 only when the server tells us when to come
+// back, otherwise it is a regular outage. Returns how long to wait before the
+// next check.
+fn rate_limit_delay(response: &Response, now: DateTime<Utc>) -> Option<time::Duration> {
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| parse_retry_after(value, now));
+
+    match response.status() {
+        StatusCode::TOO_MANY_REQUESTS => Some(retry_after.unwrap_or_default()),
+        StatusCode::SERVICE_UNAVAILABLE => retry_after,
+        _ => None,
+    }
+}
+
+// Retry-After is either a number of seconds or an HTTP date.
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<time::Duration> {
+    let value = value.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(time::Duration::from_secs(seconds));
+    }
+
+    let retry_at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (retry_at.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+// A HEAD request, unless the keyword or success criteria need to look at
+// the body or the domain asks for another method.
+#[tracing::instrument(
+    name = "request",
+    skip_all,
+    fields(http.method = field::Empty, http.status_code = field::Empty)
+)]
+async fn domain_request(
+    client: &Client,
+    domain: &MonitoredDomain,
+    headers: &BTreeMap<String, String>,
+) -> Result<(Response, Vec<RedirectHop>), reqwest::Error> {
+    let method = domain.method();
+    let mut request = client.request(
+        match method {
+            HttpMethod::Head => Method::HEAD,
+            HttpMethod::Get => Method::GET,
+            HttpMethod::Post => Method::POST,
+            HttpMethod::Put => Method::PUT,
+        },
+        &*domain.url,
+    );
+    if let Some(body) = &domain.request_body {
+        let content_type = if serde_json::from_str::<serde_json::Value>(body).is_ok() {
+            "application/json"
+        } else {
+            "text/plain"
+        };
+        request = request
+            .header(header::CONTENT_TYPE, content_type)
+            .body(body.clone());
+    }
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    if let Some(timeout_seconds) = domain.timeout_seconds {
+        request = request.timeout(time::Duration::from_secs(timeout_seconds));
+    }
+    let span = tracing::Span::current();
+    span.record("http.method", method.label());
+    // A redirect that's expected is the answer, not a hop.
+    let response = follow_redirect_chain(client, request, domain.max_redirects(), |status| {
+        domain.expects_status(status.as_u16())
+    })
+    .await;
+    if let Ok((response, _)) = &response {
+        span.record("http.status_code", response.status().as_u16());
+    }
+    response
+}
+
+// Why a check that ended on a redirect is Down: the domain doesn't follow
+// redirects, or it ran out of hops.
+fn unfollowed_redirect(domain: &MonitoredDomain, redirects: &[RedirectHop]) -> Option<String> {
+    let last = redirects.last()?;
+    Some(match domain.max_redirects() {
+        0 => format!(
+            "Status {} redirects to {}, redirects aren't followed",
+            last.status, last.location
+        ),
+        max => format!(
+            "Still redirecting after {} hops, next to {}",
+            max, last.location
+        ),
+    })
+}
+
+// Where the redirects led: the URL of the final response, or where it points
+// when its redirect was the expected answer.
+fn redirect_target(response: &Response) -> String {
+    let location = response
+        .status()
+        .is_redirection()
+        .then(|| response.headers().get(header::LOCATION))
+        .flatten()
+        .and_then(|location| location.to_str().ok())
+        .and_then(|location| response.url().join(location).ok());
+    location
+        .unwrap_or_else(|| response.url().clone())
+        .to_string()
+}
+
+// Most hops followed unless a domain says otherwise, like reqwest's default
+// policy.
+pub const MAX_REDIRECTS: usize = 10;
+
+// One redirect on the way to the final response, kept with the check.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RedirectHop {
+    pub status: u16,
+    // Where the redirect pointed, resolved against the URL that sent it.
+    pub location: String,
+}
+
+// Sends the request and follows redirects by hand, as the clients don't,
+// until a response isn't one, stop_at takes the redirect as the answer, or
+// MAX_REDIRECTS hops were followed.
+pub async fn follow_redirects(
+    client: &Client,
+    request: RequestBuilder,
+    stop_at: impl Fn(StatusCode) -> bool,
+) -> Result<Response, reqwest::Error> {
+    follow_redirect_chain(client, request, MAX_REDIRECTS, stop_at)
+        .await
+        .map(|(response, _)| response)
+}
+
+// Like follow_redirects with a limit of its own, also returning every
+// redirect answered along the way, the last one too when it wasn't followed.
+pub async fn follow_redirect_chain(
+    client: &Client,
+    request: RequestBuilder,
+    max_redirects: usize,
+    stop_at: impl Fn(StatusCode) -> bool,
+) -> Result<(Response, Vec<RedirectHop>), reqwest::Error> {
+    let mut request = request.build()?;
+    let mut hops = Vec::new();
+    loop {
+        let url = request.url().clone();
+        let next = request.try_clone();
+        let response = client.execute(request).await?;
+        let status = response.status();
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| url.join(location).ok());
+        let Some(location) = location.filter(|_| status.is_redirection()) else {
+            return Ok((response, hops));
+        };
+        hops.push(RedirectHop {
+            status: status.as_u16(),
+            location: location.to_string(),
+        });
+        if stop_at(status) || hops.len() > max_redirects {
+            return Ok((response, hops));
+        }
+        let Some(mut next) = next else {
+            return Ok((response, hops));
+        };
+
+        // 303, and 301/302 after a POST like browsers do, carry on as a GET
+        // without the body.
+        if status == StatusCode::SEE_OTHER
+            || matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND)
+                && next.method() == Method::POST
+        {
+            if next.method() != Method::HEAD {
+                *next.method_mut() = Method::GET;
+            }
+            *next.body_mut() = None;
+            next.headers_mut().remove(header::CONTENT_TYPE);
+        }
+        // Credentials stay with the site they were meant for.
+        if location.origin() != url.origin() {
+            next.headers_mut().remove(header::AUTHORIZATION);
+        }
+        *next.url_mut() = location;
+        request = next;
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ui::domains::CheckStatus;
+    use crate::ui::domains::{CheckStatus, parse_status_codes};
     use std::{
+        collections::BTreeMap,
         fs, io,
         path::Path,
         sync::{Arc, Mutex},
@@ -141,6 +1709,714 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn clock_jump_detects_suspend() {
+        let jump = detect_clock_jump(Duration::from_secs(60), chrono::Duration::seconds(3660));
+        assert_eq!(jump.map(|j| j.num_seconds()), Some(3600));
+    }
+
+    #[test]
+    fn clock_jump_ignores_scheduling_noise() {
+        let jump = detect_clock_jump(
+            Duration::from_secs(60),
+            chrono::Duration::milliseconds(60_250),
+        );
+        assert!(jump.is_none());
+    }
+
+    #[test]
+    fn clock_jump_detects_clock_moving_back() {
+        let jump = detect_clock_jump(Duration::from_secs(60), chrono::Duration::seconds(-600));
+        assert_eq!(jump.map(|j| j.num_seconds()), Some(-660));
+    }
+
+    #[test]
+    fn wake_jitter_stays_within_interval() {
+        for _ in 0..100 {
+            assert!(wake_jitter(Duration::from_secs(5)) < Duration::from_secs(5));
+        }
+    }
+
+    #[test]
+    fn retry_after_in_seconds() {
+        assert_eq!(
+            parse_retry_after("120", Utc::now()),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn retry_after_as_http_date() {
+        let now = DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:27:00 GMT")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+            Some(Duration::from_secs(60))
+        );
+        // A date in the past means we may retry right away.
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn retry_after_garbage_is_ignored() {
+        assert_eq!(parse_retry_after("soon", Utc::now()), None);
+    }
+
+    #[test]
+    fn keyword_has_to_be_in_the_body() {
+        let mut domain = MonitoredDomain::new("https://example.com");
+        let response = |status, body| CheckResponse {
+            status,
+            response_time_ms: 50,
+            body,
+        };
+        assert!(!domain.needs_body());
+        assert_eq!(
+            judge_response(&domain, &response(200, None)),
+            (DomainStatus::Up, None)
+        );
+
+        domain.keyword = Some("Welcome".to_string());
+        assert!(domain.needs_body());
+        assert_eq!(
+            judge_response(&domain, &response(200, Some("<h1>Welcome</h1>"))).0,
+            DomainStatus::Up
+        );
+        let (status, message) = judge_response(&domain, &response(200, Some("Maintenance")));
+        assert_eq!(status, DomainStatus::Down);
+        assert_eq!(
+            message.as_deref(),
+            Some("Keyword \"Welcome\" not found in the response")
+        );
+        assert_eq!(
+            judge_response(&domain, &response(503, Some("Welcome"))).0,
+            DomainStatus::Down
+        );
+    }
+
+    #[test]
+    fn body_regex_has_to_match() {
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.body_regex = Some(r"Version: (\d+\.\d+\.\d+)".to_string());
+        assert!(domain.needs_body());
+        let response = |status, body| CheckResponse {
+            status,
+            response_time_ms: 50,
+            body: Some(body),
+        };
+
+        assert_eq!(
+            judge_response(&domain, &response(200, "Version: 2.1.0")),
+            (DomainStatus::Up, None)
+        );
+        assert_eq!(
+            judge_response(&domain, &response(200, "Version: unknown")),
+            (
+                DomainStatus::Down,
+                Some(r#"Body doesn't match the regex "Version: (\\d+\\.\\d+\\.\\d+)""#.to_string())
+            )
+        );
+        assert_eq!(
+            judge_response(&domain, &response(503, "Version: 2.0.9")),
+            (
+                DomainStatus::Down,
+                Some(r#"body regex captured "2.0.9""#.to_string())
+            )
+        );
+
+        domain.keyword = Some("healthy".to_string());
+        assert_eq!(
+            judge_response(&domain, &response(200, "Version: 2.0.9")).1,
+            Some(
+                r#"Keyword "healthy" not found in the response, body regex captured "2.0.9""#
+                    .to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn body_regexes_follow_edits_and_removals() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let store_path = temp_dir.path().join("domains.json");
+        let monitor = MonitorHandle::new(
+            Arc::new(Mutex::new(Vec::new())),
+            store_path.to_str().unwrap(),
+            Arc::new(|_: &MonitoredDomain, _: &[CheckStatus]| Ok(())),
+            Arc::new(RequestBudget::default()),
+            broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        );
+        monitor.set_paused(true);
+        let response = CheckResponse {
+            status: 200,
+            response_time_ms: 50,
+            body: Some("Version: 2.1.0"),
+        };
+        let cached = |id| {
+            body_regexes()
+                .lock()
+                .unwrap()
+                .get(&id)
+                .map(|r| r.to_string())
+        };
+
+        let mut domain = MonitoredDomain::new("http://127.0.0.1:9");
+        domain.body_regex = Some(r"Version: 1\.".to_string());
+        monitor
+            .add_domain(domain.clone())
+            .expect("Failed to add domain");
+        assert_eq!(judge_response(&domain, &response).0, DomainStatus::Down);
+
+        domain.body_regex = Some(r"Version: 2\.".to_string());
+        assert_eq!(judge_response(&domain, &response).0, DomainStatus::Up);
+        assert_eq!(cached(domain.id).as_deref(), Some(r"Version: 2\."));
+
+        monitor
+            .remove_domain(domain.id)
+            .expect("Failed to remove domain");
+        assert_eq!(cached(domain.id), None);
+        monitor.stop();
+    }
+
+    #[test]
+    fn method_follows_the_domain() {
+        let mut domain = MonitoredDomain::new("https://example.com/health");
+        assert_eq!(domain.method(), HttpMethod::Head);
+        domain.keyword = Some("ok".to_string());
+        assert_eq!(domain.method(), HttpMethod::Get);
+
+        domain.http_method = Some(HttpMethod::Head);
+        assert!(domain.validate().is_err());
+        domain.http_method = Some(HttpMethod::Post);
+        domain.request_body = Some(r#"{"deep": true}"#.to_string());
+        assert_eq!(domain.method(), HttpMethod::Post);
+        assert!(domain.validate().is_ok());
+
+        domain.http_method = Some(HttpMethod::Get);
+        assert!(domain.validate().is_err());
+        assert_eq!(HttpMethod::parse("put"), Some(HttpMethod::Put));
+        assert_eq!(HttpMethod::parse("DELETE"), None);
+
+        let mut ping = MonitoredDomain::new("ping://10.0.0.1");
+        ping.http_method = Some(HttpMethod::Post);
+        assert!(ping.validate().is_err());
+    }
+
+    fn check_at(timestamp: DateTime<Utc>) -> CheckStatus {
+        CheckStatus {
+            http_code: Some(HttpCode::Ok),
+            response_time_ms: Some(20),
+            ..CheckStatus::new(timestamp, DomainStatus::Up)
+        }
+    }
+
+    #[test]
+    fn record_check_applies_retention_overrides() {
+        let mut long_lived = MonitoredDomain::new("https://sla.example.com");
+        long_lived.history_max_checks = Some(150);
+        let default = MonitoredDomain::new("https://dev.example.com");
+        let ids = [long_lived.id, default.id];
+
+        let domains = Arc::new(Mutex::new(vec![long_lived, default]));
+        let callback: Arc<DomainCallbackType> = {
+            let domains = Arc::clone(&domains);
+            Arc::new(move |updated: &MonitoredDomain, history: &[CheckStatus]| {
+                let mut domains_guard = domains.lock().unwrap();
+                if let Some(d) = domains_guard.iter_mut().find(|d| d.id == updated.id) {
+                    d.check_history = history.to_vec();
+                }
+                Ok(())
+            })
+        };
+        let events = broadcast::channel(EVENT_CHANNEL_CAPACITY).0;
+
+        for _ in 0..200 {
+            for id in ids {
+                let check = check_at(Utc::now());
+                let retention = HistoryRetention::default();
+                record_check(&domains, id, check, &callback, &events, None, retention);
+            }
+        }
+
+        let domains_guard = domains.lock().unwrap();
+        assert_eq!(domains_guard[0].check_history.len(), 150);
+        assert_eq!(domains_guard[1].check_history.len(), 100);
+    }
+
+    #[test]
+    fn repeated_errors_share_one_string() {
+        let domain = MonitoredDomain::new("https://example.com");
+        let id = domain.id;
+        let domains = Arc::new(Mutex::new(vec![domain]));
+        let callback: Arc<DomainCallbackType> = {
+            let domains = Arc::clone(&domains);
+            Arc::new(move |updated: &MonitoredDomain, history: &[CheckStatus]| {
+                domains.lock().unwrap()[0].check_history = history.to_vec();
+                assert_eq!(updated.id, id);
+                Ok(())
+            })
+        };
+        let events = broadcast::channel(EVENT_CHANNEL_CAPACITY).0;
+
+        for _ in 0..3 {
+            let message: Arc<str> = "connection refused".into();
+            let check = CheckStatus {
+                status: DomainStatus::Error(Arc::clone(&message)),
+                error_message: Some(message),
+                ..check_at(Utc::now())
+            };
+            let retention = HistoryRetention::default();
+            record_check(&domains, id, check, &callback, &events, None, retention);
+        }
+
+        let domains_guard = domains.lock().unwrap();
+        let history = &domains_guard[0].check_history;
+        let first = history[0].error_message.as_ref().unwrap();
+        let last = history[2].error_message.as_ref().unwrap();
+        assert!(Arc::ptr_eq(first, last));
+    }
+
+    #[test]
+    fn traceroute_goes_with_the_failed_check() {
+        let domain = MonitoredDomain::new("https://example.com");
+        let id = domain.id;
+        let domains = Arc::new(Mutex::new(vec![domain]));
+        let callback: Arc<DomainCallbackType> = {
+            let domains = Arc::clone(&domains);
+            Arc::new(move |_: &MonitoredDomain, history: &[CheckStatus]| {
+                domains.lock().unwrap()[0].check_history = history.to_vec();
+                Ok(())
+            })
+        };
+        let events = broadcast::channel(EVENT_CHANNEL_CAPACITY).0;
+        let failed = CheckStatus {
+            status: DomainStatus::Down,
+            ..check_at(Utc::now())
+        };
+        let failed_at = failed.timestamp;
+        let retention = HistoryRetention::default();
+        record_check(&domains, id, failed, &callback, &events, None, retention);
+
+        let hops = vec![TraceHop {
+            ttl: 1,
+            address: Some("192.168.1.1".to_string()),
+            rtt_ms: Some(0.5),
+        }];
+        let later = failed_at + chrono::Duration::seconds(1);
+        record_traceroute(&domains, id, later, hops.clone(), &callback, &events, None);
+        // The trace can finish after the next check was recorded.
+        record_check(
+            &domains,
+            id,
+            check_at(later),
+            &callback,
+            &events,
+            None,
+            retention,
+        );
+        assert!(
+            domains.lock().unwrap()[0].check_history[0]
+                .traceroute
+                .is_empty()
+        );
+        record_traceroute(
+            &domains,
+            id,
+            failed_at,
+            hops.clone(),
+            &callback,
+            &events,
+            None,
+        );
+        assert_eq!(domains.lock().unwrap()[0].check_history[0].traceroute, hops);
+    }
+
+    #[test]
+    fn compaction_drops_checks_past_max_age() {
+        let now = Utc::now();
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.history_max_age_days = Some(7);
+        domain.check_history = vec![
+            check_at(now - chrono::Duration::days(10)),
+            check_at(now - chrono::Duration::days(8)),
+            check_at(now - chrono::Duration::days(1)),
+        ];
+
+        let retention = domain.history_retention(HistoryRetention::default());
+        assert_eq!(domain.compact_history(retention, now), 2);
+        assert_eq!(domain.check_history.len(), 1);
+    }
+
+    // A local server where /old redirects to /new, which answers 200.
+    async fn redirecting_server() -> std::net::SocketAddr {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 4096];
+                let mut len = 0;
+                while !String::from_utf8_lossy(&request[..len]).contains("\r\n\r\n") {
+                    len += stream.read(&mut request[len..]).await.unwrap();
+                }
+                let answer = if request.starts_with(b"HEAD /old ") {
+                    "HTTP/1.1 301 Moved Permanently\r\nlocation: /new\r\n"
+                } else {
+                    "HTTP/1.1 200 OK\r\n"
+                };
+                let answer = format!("{}content-length: 0\r\nconnection: close\r\n\r\n", answer);
+                stream.write_all(answer.as_bytes()).await.unwrap();
+            }
+        });
+        address
+    }
+
+    // An HTTP check following redirects by hand, as the monitor's client does.
+    async fn http_check(domain: &MonitoredDomain) -> CheckStatus {
+        let client = Client::builder()
+            .redirect(redirect::Policy::none())
+            .build()
+            .unwrap();
+        run_check(&client, &RequestBudget::default(), domain, &BTreeMap::new())
+            .await
+            .0
+    }
+
+    #[tokio::test]
+    async fn expected_status_codes_decide() {
+        let address = redirecting_server().await;
+        let mut domain = MonitoredDomain::new(&format!("http://{}/old", address));
+
+        domain.expected_status_codes = parse_status_codes("301").unwrap();
+        let redirector = http_check(&domain).await;
+        assert_eq!(redirector.status, DomainStatus::Up);
+        assert_eq!(
+            redirector.http_code,
+            Some(HttpCode::from_status_code(StatusCode::MOVED_PERMANENTLY))
+        );
+
+        domain.expected_status_codes = parse_status_codes("401, 403").unwrap();
+        let login_wall = http_check(&domain).await;
+        assert_eq!(login_wall.status, DomainStatus::Down);
+        assert_eq!(
+            login_wall.error_message.as_deref(),
+            Some("Status 200 isn't one of the expected 401, 403")
+        );
+    }
+
+    #[tokio::test]
+    async fn redirects_are_followed() {
+        let address = redirecting_server().await;
+        let mut domain = MonitoredDomain::new(&format!("http://{}/old", address));
+        let followed = http_check(&domain).await;
+        assert_eq!(followed.status, DomainStatus::Up);
+        assert_eq!(followed.http_code, Some(HttpCode::Ok));
+        assert_eq!(
+            followed.redirects,
+            [RedirectHop {
+                status: 301,
+                location: format!("http://{}/new", address),
+            }]
+        );
+
+        domain.max_redirects = Some(0);
+        let unfollowed = http_check(&domain).await;
+        assert_eq!(unfollowed.status, DomainStatus::Down);
+        assert_eq!(
+            unfollowed.error_message.as_deref(),
+            Some(
+                format!(
+                    "Status 301 redirects to http://{}/new, redirects aren't followed",
+                    address
+                )
+                .as_str()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn response_sizes_are_recorded() {
+        let address = redirecting_server().await;
+        let domain = MonitoredDomain::new(&format!("http://{}/new", address));
+        assert_eq!(http_check(&domain).await.response_bytes, Some(0));
+        let unanswered = MonitoredDomain::new("http://127.0.0.1:9/new");
+        assert_eq!(http_check(&unanswered).await.response_bytes, None);
+    }
+
+    #[tokio::test]
+    async fn redirects_have_to_land_where_expected() {
+        let address = redirecting_server().await;
+        let mut domain = MonitoredDomain::new(&format!("http://{}/old", address));
+
+        // Following the redirect lands on /new, the expected 301 points there.
+        domain.expected_redirect = Some(format!("http://{}/new", address));
+        assert_eq!(http_check(&domain).await.status, DomainStatus::Up);
+        domain.expected_status_codes = parse_status_codes("301").unwrap();
+        assert_eq!(http_check(&domain).await.status, DomainStatus::Up);
+        domain.expected_redirect = Some("https://*/new".to_string());
+        let insecure = http_check(&domain).await;
+        assert_eq!(insecure.status, DomainStatus::Down);
+        assert_eq!(
+            insecure.error_message.as_deref(),
+            Some(format!("Landed on http://{}/new instead of https://*/new", address).as_str())
+        );
+        domain.expected_redirect = Some("http://127.0.0.1:*/*".to_string());
+        assert_eq!(http_check(&domain).await.status, DomainStatus::Up);
+    }
+
+    #[test]
+    fn runbook_comes_from_metadata_links() {
+        let mut domain = MonitoredDomain::new("https://example.com");
+        assert_eq!(domain.runbook(), None);
+        domain.metadata = crate::ui::domains::parse_metadata(
+            "Runbook=wiki page; dashboard=https://grafana.example.com",
+        )
+        .unwrap();
+        assert_eq!(domain.runbook(), None);
+        domain.metadata =
+            crate::ui::domains::parse_metadata("Runbook=https://wiki.example.com/web; team=web")
+                .unwrap();
+        assert_eq!(domain.runbook(), Some("https://wiki.example.com/web"));
+    }
+
+    // What check_domain sends for the domain, at /health on a local server,
+    // lowercased.
+    async fn sent_request(configure: impl FnOnce(&mut MonitoredDomain)) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            // The headers, then as much body as they announce.
+            let request = loop {
+                let len = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..len]);
+                let text = String::from_utf8_lossy(&request).to_lowercase();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .map_or(0, |length| length.parse().unwrap());
+                    if body.len() >= length {
+                        break text;
+                    }
+                }
+            };
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            request
+        });
+
+        let mut domain = MonitoredDomain::new(&format!("http://{}/health", address));
+        configure(&mut domain);
+        let (client, tunnels) = (Client::new(), SshTunnels::default());
+        let (credentials, budget) = (Credentials::default(), RequestBudget::default());
+        let check = check_domain(&client, &tunnels, &credentials, &budget, &domain);
+        let (request, (check, _)) = tokio::join!(server, check);
+        assert_eq!(check.status, DomainStatus::Up);
+        request.unwrap()
+    }
+
+    #[tokio::test]
+    async fn requests_carry_the_method_and_body() {
+        let request = sent_request(|domain| {
+            domain.http_method = Some(HttpMethod::Post);
+            domain.request_body = Some(r#"{"probe": true}"#.to_string());
+        })
+        .await;
+        assert!(request.starts_with("post /health http/1.1"));
+        assert!(request.contains("content-type: application/json"));
+        assert!(request.ends_with(r#"{"probe": true}"#));
+    }
+
+    #[tokio::test]
+    async fn requests_carry_the_headers() {
+        let request = sent_request(|domain| {
+            domain.headers = BTreeMap::from([
+                ("X-Api-Key".to_string(), "abc".to_string()),
+                ("Host".to_string(), "api.internal".to_string()),
+            ]);
+        })
+        .await;
+        assert!(request.contains("x-api-key: abc"));
+        assert!(request.contains("host: api.internal"));
+    }
+
+    #[tokio::test]
+    async fn requests_carry_the_auth() {
+        let request = sent_request(|domain| {
+            domain.auth = DomainAuth::parse("basic ops:hunter2").unwrap();
+        })
+        .await;
+        assert!(request.contains(&"authorization: Basic b3BzOmh1bnRlcjI=".to_lowercase()));
+    }
+
+    #[tokio::test]
+    async fn domain_changes_are_broadcast() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let store_path = temp_dir.path().join("domains.json");
+        let monitor = MonitorHandle::new(
+            Arc::new(Mutex::new(Vec::new())),
+            store_path.to_str().unwrap(),
+            Arc::new(|_: &MonitoredDomain, _: &[CheckStatus]| Ok(())),
+            Arc::new(RequestBudget::default()),
+            broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        );
+        let mut events = monitor.events.subscribe();
+
+        let mut domain = MonitoredDomain::new("https://example.com");
+        monitor
+            .add_domain(domain.clone())
+            .expect("Failed to add domain");
+        domain.interval_seconds = 30;
+        monitor
+            .update_domain(&domain)
+            .expect("Failed to update domain");
+        monitor
+            .remove_domain(domain.id)
+            .expect("Failed to remove domain");
+        monitor.stop();
+
+        let mut names = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            names.push(event.name());
+        }
+        assert_eq!(names, ["domain_added", "domain_updated", "domain_removed"]);
+    }
+
+    #[tokio::test]
+    async fn snapshot_follows_domain_changes() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let store_path = temp_dir.path().join("domains.json");
+        let monitor = MonitorHandle::new(
+            Arc::new(Mutex::new(Vec::new())),
+            store_path.to_str().unwrap(),
+            Arc::new(|_: &MonitoredDomain, _: &[CheckStatus]| Ok(())),
+            Arc::new(RequestBudget::default()),
+            broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        );
+        monitor.set_paused(true);
+        monitor.start();
+
+        let domain = MonitoredDomain::new("http://127.0.0.1:9");
+        monitor
+            .add_domain(domain.clone())
+            .expect("Failed to add domain");
+        let until = Utc::now() + chrono::Duration::hours(1);
+        monitor
+            .mute_domain(domain.id, Some(until))
+            .expect("Failed to mute domain");
+        sleep(Duration::from_millis(50)).await;
+        let snapshot = monitor.snapshot();
+        assert_eq!(snapshot.domains.len(), 1);
+        assert_eq!(snapshot.domains[0].muted_until, Some(until));
+
+        monitor
+            .remove_domain(domain.id)
+            .expect("Failed to remove domain");
+        sleep(Duration::from_millis(50)).await;
+        assert!(monitor.snapshot().domains.is_empty());
+        // Earlier snapshots stay as they were.
+        assert_eq!(snapshot.domains.len(), 1);
+        monitor.stop();
+    }
+
+    #[tokio::test]
+    async fn paused_monitor_runs_no_checks() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let store_path = temp_dir.path().join("domains.json");
+        let monitor = MonitorHandle::new(
+            Arc::new(Mutex::new(Vec::new())),
+            store_path.to_str().unwrap(),
+            Arc::new(|_: &MonitoredDomain, _: &[CheckStatus]| Ok(())),
+            Arc::new(RequestBudget::default()),
+            broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        );
+        let mut events = monitor.events.subscribe();
+        monitor.set_paused(true);
+        monitor
+            .add_domain(MonitoredDomain::new("http://127.0.0.1:9"))
+            .expect("Failed to add domain");
+
+        // A check would be due right away, so none finishing in a while means
+        // the pause held it.
+        let completed = async {
+            loop {
+                if let Ok(MonitorEvent::CheckCompleted { check, .. }) = events.recv().await {
+                    break check;
+                }
+            }
+        };
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), completed)
+                .await
+                .is_err()
+        );
+        assert!(monitor.upcoming_checks().is_empty());
+
+        let resumed_at = Utc::now();
+        monitor.set_paused(false);
+        let check = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Ok(MonitorEvent::CheckCompleted { check, .. }) = events.recv().await {
+                    break check;
+                }
+            }
+        })
+        .await
+        .expect("Checked once resumed");
+        assert!(check.timestamp > resumed_at);
+        monitor.stop();
+    }
+
+    #[tokio::test]
+    async fn mute_is_saved_with_the_domain() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let store_path = temp_dir.path().join("domains.json");
+        let monitor = MonitorHandle::new(
+            Arc::new(Mutex::new(Vec::new())),
+            store_path.to_str().unwrap(),
+            Arc::new(|_: &MonitoredDomain, _: &[CheckStatus]| Ok(())),
+            Arc::new(RequestBudget::default()),
+            broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        );
+        let domain = MonitoredDomain::new("https://example.com");
+        monitor
+            .add_domain(domain.clone())
+            .expect("Failed to add domain");
+
+        let now = Utc::now();
+        monitor
+            .mute_domain(domain.id, Some(now + chrono::Duration::hours(4)))
+            .expect("Failed to mute domain");
+        let saved = crate::store::load_domains(store_path.to_str().unwrap())
+            .expect("Failed to load domains");
+        assert!(saved[0].mute_remaining(now).is_some());
+        // The mute lapses on its own.
+        assert!(
+            saved[0]
+                .mute_remaining(now + chrono::Duration::hours(5))
+                .is_none()
+        );
+
+        let unmuted = monitor
+            .mute_domain(domain.id, None)
+            .expect("Failed to unmute domain")
+            .expect("Domain exists");
+        assert!(unmuted.mute_remaining(now).is_none());
+        monitor.stop();
+    }
+
     #[tokio::test]
     async fn monitoring_task_processes_domains() {
         let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
@@ -148,8 +2424,38 @@ mod tests {
 
         let test_domains = vec![MonitoredDomain {
             id: Uuid::new_v4(),
-            url: "http://google.com".to_string(),
+            url: "http://google.com".into(),
+            display_name: None,
             interval_seconds: 1,
+            timeout_seconds: None,
+            history_max_checks: None,
+            history_max_age_days: None,
+            latency_good_below_ms: None,
+            latency_warn_below_ms: None,
+            success_criteria: None,
+            expected_status_codes: Vec::new(),
+            expected_redirect: None,
+            max_redirects: None,
+            keyword: None,
+            body_regex: None,
+            http_method: None,
+            request_body: None,
+            headers: BTreeMap::new(),
+            auth: None,
+            challenge: None,
+            ssh_jump_host: None,
+            kubeconfig: None,
+            owner: None,
+            environment: None,
+            tags: Vec::new(),
+            metadata: BTreeMap::new(),
+            group: None,
+            incident_notes: Vec::new(),
+            muted_until: None,
+            muted_from: None,
+            certificate: None,
+            daily_rollups: Vec::new(),
+            incident_log: Vec::new(),
             check_history: Vec::new(),
         }];
 
@@ -164,7 +2470,14 @@ mod tests {
         );
 
         // Start the monitoring task
-        start_monitoring_task(test_domains_arc.clone(), update_domains_closure).await;
+        MonitorHandle::new(
+            test_domains_arc.clone(),
+            temp_dir_path.join("domains.json").to_str().unwrap(),
+            update_domains_closure,
+            Arc::new(RequestBudget::default()),
+            broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        )
+        .start();
 
         sleep(Duration::from_secs(60)).await;
 
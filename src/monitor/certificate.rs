@@ -0,0 +1,435 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::{
+    ClientConfig, DigitallySignedStruct, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{CryptoProvider, ring, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+};
+use url::Url;
+use utoipa::ToSchema;
+use x509_parser::prelude::{FromDer, X509Certificate, X509Name};
+use x509_parser::public_key::PublicKey;
+
+// Certificates change rarely, so the chain is looked at once a day.
+const INSPECTION_INTERVAL_HOURS: i64 = 24;
+// How long to wait after the first failed look, doubled after each further
+// one up to the inspection interval.
+const FIRST_RETRY_MINUTES: i64 = 5;
+// RSA keys shorter than this are considered breakable.
+const MIN_RSA_KEY_BITS: usize = 2048;
+
+// Signature algorithms broken by collisions, by the DER of their OID.
+const WEAK_SIGNATURES: [(&[u8], &str); 4] = [
+    (
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x05],
+        "SHA-1",
+    ),
+    (&[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x01], "SHA-1"),
+    (&[0x2a, 0x86, 0x48, 0xce, 0x38, 0x04, 0x03], "SHA-1"),
+    (
+        &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x04],
+        "MD5",
+    ),
+];
+
+// Root CAs browsers announced they stop trusting, by the organization in
+// their name, with what was announced. Certificates chaining to them need
+// replacing before then. Extend as distrusts are announced.
+const DISTRUSTED_ROOTS: [(&str, &str); 4] = [
+    (
+        "Entrust",
+        "Chrome distrusts certificates issued after 2024-11-11",
+    ),
+    (
+        "AffirmTrust",
+        "Chrome distrusts certificates issued after 2024-11-11",
+    ),
+    (
+        "Chunghwa Telecom",
+        "Chrome distrusts certificates issued after 2025-07-31",
+    ),
+    (
+        "NetLock",
+        "Chrome distrusts certificates issued after 2025-07-31",
+    ),
+];
+
+// What the last look at a domain's certificate chain found, shown as a
+// warning on the monitor and listed in the incident report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CertificateInspection {
+    pub inspected_at: DateTime<Utc>,
+    // Empty when nothing is weak.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl CertificateInspection {
+    // Whether an https URL is due for another look.
+    pub fn is_due(inspection: Option<&CertificateInspection>, now: DateTime<Utc>) -> bool {
+        inspection
+            .is_none_or(|i| now - i.inspected_at >= Duration::hours(INSPECTION_INTERVAL_HOURS))
+    }
+
+    // How long to leave a chain alone after `failures` looks in a row failed.
+    pub fn retry_delay(failures: u32) -> Duration {
+        let doublings = failures.saturating_sub(1).min(16);
+        Duration::minutes(FIRST_RETRY_MINUTES << doublings)
+            .min(Duration::hours(INSPECTION_INTERVAL_HOURS))
+    }
+}
+
+// Looks at the chain `url`'s server presents. The check itself already
+// verified it, here it's only read, so chains with the SHA-1 signatures
+// webpki refuses can still be reported.
+pub async fn inspect(url: &str, limit: time::Duration) -> Result<CertificateInspection, String> {
+    let chain = timeout(limit, peer_certificates(url))
+        .await
+        .map_err(|_| "timed out".to_string())??;
+    let chain: Vec<&[u8]> = chain.iter().map(|c| c.as_ref()).collect();
+    Ok(CertificateInspection {
+        inspected_at: Utc::now(),
+        warnings: weaknesses(&chain),
+    })
+}
+
+async fn peer_certificates(url: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let url = Url::parse(url).map_err(|e| e.to_string())?;
+    let host = url.host_str().ok_or("URL has no host")?.to_string();
+    let port = url.port_or_known_default().ok_or("URL has no port")?;
+    let stream = TcpStream::connect((host.as_str(), port))
+        .await
+        .map_err(|e| format!("connect: {}", e))?;
+
+    let provider = Arc::new(ring::default_provider());
+    let config = ClientConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(ReadOnlyVerifier(provider)))
+        .with_no_client_auth();
+    let server_name = ServerName::try_from(host).map_err(|e| e.to_string())?;
+    let stream = TlsConnector::from(Arc::new(config))
+        .connect(server_name, stream)
+        .await
+        .map_err(|e| format!("TLS: {}", e))?;
+
+    let (_, connection) = stream.get_ref();
+    Ok(connection
+        .peer_certificates()
+        .map(|chain| chain.iter().map(|c| c.clone().into_owned()).collect())
+        .unwrap_or_default())
+}
+
+// Takes any chain, the handshake's own signature is still checked. Nothing
+// is sent over the connection.
+#[derive(Debug)]
+struct ReadOnlyVerifier(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for ReadOnlyVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+// The weak spots of a chain, leaf first as servers send it. A self-signed
+// root's own signature doesn't matter, it's trusted as is.
+fn weaknesses(chain: &[&[u8]]) -> Vec<String> {
+    let certificates: Vec<Certificate> = chain
+        .iter()
+        .filter_map(|der| Certificate::parse(der))
+        .collect();
+    let mut warnings = Vec::new();
+
+    for certificate in &certificates {
+        let self_signed = certificate.subject == certificate.issuer;
+        if let Some((_, name)) = WEAK_SIGNATURES
+            .iter()
+            .find(|(oid, _)| *oid == certificate.signature_algorithm.as_slice())
+            && !self_signed
+        {
+            warnings.push(format!("{} signature on {}", name, certificate.subject));
+        }
+        if let Some(bits) = certificate.rsa_key_bits
+            && bits < MIN_RSA_KEY_BITS
+        {
+            warnings.push(format!("{}-bit RSA key on {}", bits, certificate.subject));
+        }
+    }
+
+    if let Some(root) = certificates.last().map(|c| &c.issuer)
+        && let Some((_, announced)) = DISTRUSTED_ROOTS
+            .iter()
+            .find(|(organization, _)| root.organization_contains(organization))
+    {
+        warnings.push(format!("Chains to {}, {}", root, announced));
+    }
+    warnings
+}
+
+// The parts of an X.509 certificate the checks need.
+#[derive(Debug)]
+struct Certificate {
+    subject: Name,
+    issuer: Name,
+    // The DER of the signature algorithm's OID.
+    signature_algorithm: Vec<u8>,
+    rsa_key_bits: Option<usize>,
+}
+
+impl Certificate {
+    fn parse(der: &[u8]) -> Option<Self> {
+        let (_, certificate) = X509Certificate::from_der(der).ok()?;
+        let rsa_key_bits = match certificate.public_key().parsed() {
+            Ok(PublicKey::RSA(key)) => Some(integer_bits(key.modulus)),
+            _ => None,
+        };
+        Some(Certificate {
+            subject: Name::from(certificate.subject()),
+            issuer: Name::from(certificate.issuer()),
+            signature_algorithm: certificate
+                .signature_algorithm
+                .algorithm
+                .as_bytes()
+                .to_vec(),
+            rsa_key_bits,
+        })
+    }
+}
+
+// A certificate's subject or issuer, by the attributes worth showing.
+#[derive(Debug, PartialEq)]
+struct Name {
+    common_name: Option<String>,
+    organization: Option<String>,
+}
+
+impl From<&X509Name<'_>> for Name {
+    fn from(name: &X509Name) -> Self {
+        Name {
+            common_name: name
+                .iter_common_name()
+                .find_map(|a| a.as_str().ok())
+                .map(String::from),
+            organization: name
+                .iter_organization()
+                .find_map(|a| a.as_str().ok())
+                .map(String::from),
+        }
+    }
+}
+
+impl Name {
+    fn organization_contains(&self, fragment: &str) -> bool {
+        [&self.organization, &self.common_name]
+            .into_iter()
+            .flatten()
+            .any(|value| value.to_lowercase().contains(&fragment.to_lowercase()))
+    }
+}
+
+impl std::fmt::Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.common_name, &self.organization) {
+            (Some(common_name), _) => write!(f, "\"{}\"", common_name),
+            (None, Some(organization)) => write!(f, "\"{}\"", organization),
+            (None, None) => write!(f, "an unnamed certificate"),
+        }
+    }
+}
+
+// The size of a positive integer in bits, leading zeros left out.
+fn integer_bits(integer: &[u8]) -> usize {
+    let Some(first) = integer.iter().position(|&b| b != 0) else {
+        return 0;
+    };
+    (integer.len() - first) * 8 - integer[first].leading_zeros() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTEGER: u8 = 0x02;
+    const BIT_STRING: u8 = 0x03;
+    const OBJECT_IDENTIFIER: u8 = 0x06;
+    const UTC_TIME: u8 = 0x17;
+    const SEQUENCE: u8 = 0x30;
+    const SET: u8 = 0x31;
+    const VERSION: u8 = 0xa0;
+    const RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+    const COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+    const ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0a];
+
+    fn element(tag: u8, parts: &[&[u8]]) -> Vec<u8> {
+        let contents = parts.concat();
+        let mut der = vec![tag];
+        if contents.len() < 0x80 {
+            der.push(contents.len() as u8);
+        } else {
+            der.extend([0x82, (contents.len() >> 8) as u8, contents.len() as u8]);
+        }
+        der.extend(contents);
+        der
+    }
+
+    fn name(organization: &str, common_name: &str) -> Vec<u8> {
+        let attribute = |oid: &[u8], value: &str| {
+            element(
+                SET,
+                &[&element(
+                    SEQUENCE,
+                    &[
+                        &element(OBJECT_IDENTIFIER, &[oid]),
+                        &element(0x0c, &[value.as_bytes()]),
+                    ],
+                )],
+            )
+        };
+        element(
+            SEQUENCE,
+            &[
+                &attribute(ORGANIZATION, organization),
+                &attribute(COMMON_NAME, common_name),
+            ],
+        )
+    }
+
+    fn certificate(
+        signature: &[u8],
+        modulus_bytes: usize,
+        subject: &[u8],
+        issuer: &[u8],
+    ) -> Vec<u8> {
+        let algorithm = element(SEQUENCE, &[&element(OBJECT_IDENTIFIER, &[signature])]);
+        // A leading zero keeps the modulus positive, it doesn't count.
+        let mut modulus = vec![0x00, 0xc1];
+        modulus.resize(modulus_bytes + 1, 0xff);
+        let rsa_key = element(
+            SEQUENCE,
+            &[
+                &element(INTEGER, &[&modulus]),
+                &element(INTEGER, &[&[0x01, 0x00, 0x01]]),
+            ],
+        );
+        let public_key_info = element(
+            SEQUENCE,
+            &[
+                &element(
+                    SEQUENCE,
+                    &[
+                        &element(OBJECT_IDENTIFIER, &[RSA_ENCRYPTION]),
+                        &[0x05, 0x00],
+                    ],
+                ),
+                &element(BIT_STRING, &[&[0x00], &rsa_key]),
+            ],
+        );
+        let tbs = element(
+            SEQUENCE,
+            &[
+                &element(VERSION, &[&element(INTEGER, &[&[0x02]])]),
+                &element(INTEGER, &[&[0x10]]),
+                &algorithm,
+                issuer,
+                &element(
+                    SEQUENCE,
+                    &[
+                        &element(UTC_TIME, &[b"250101000000Z"]),
+                        &element(UTC_TIME, &[b"350101000000Z"]),
+                    ],
+                ),
+                subject,
+                &public_key_info,
+            ],
+        );
+        element(
+            SEQUENCE,
+            &[&tbs, &algorithm, &element(BIT_STRING, &[&[0x00]])],
+        )
+    }
+
+    #[test]
+    fn failed_inspections_back_off() {
+        assert_eq!(CertificateInspection::retry_delay(1), Duration::minutes(5));
+        assert_eq!(CertificateInspection::retry_delay(3), Duration::minutes(20));
+        assert_eq!(CertificateInspection::retry_delay(40), Duration::hours(24));
+    }
+
+    #[test]
+    fn flags_weak_signatures_short_keys_and_distrusted_roots() {
+        let sha256 = [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+        let sha1 = WEAK_SIGNATURES[0].0;
+        let leaf = name("Example", "www.example.com");
+        let intermediate = name("Entrust, Inc.", "Entrust Certification Authority - L1K");
+        let root = name("Entrust, Inc.", "Entrust Root Certification Authority - G2");
+
+        let weak_leaf = certificate(sha1, 128, &leaf, &intermediate);
+        let parsed = Certificate::parse(&weak_leaf).expect("Certificate parses");
+        assert_eq!(parsed.rsa_key_bits, Some(1024));
+        assert_eq!(parsed.subject.to_string(), "\"www.example.com\"");
+
+        let chain = [weak_leaf, certificate(&sha256, 256, &intermediate, &root)];
+        let chain: Vec<&[u8]> = chain.iter().map(Vec::as_slice).collect();
+        assert_eq!(
+            weaknesses(&chain),
+            vec![
+                "SHA-1 signature on \"www.example.com\"".to_string(),
+                "1024-bit RSA key on \"www.example.com\"".to_string(),
+                "Chains to \"Entrust Root Certification Authority - G2\", Chrome distrusts certificates issued after 2024-11-11".to_string(),
+            ]
+        );
+
+        // A sound chain, and a self-signed SHA-1 root, are fine.
+        let other_root = name("ISRG", "ISRG Root X1");
+        let sound = certificate(&sha256, 256, &leaf, &other_root);
+        let old_root = certificate(sha1, 512, &other_root, &other_root);
+        assert!(weaknesses(&[&sound, &old_root]).is_empty());
+        assert!(weaknesses(&[b"not a certificate"]).is_empty());
+    }
+}
@@ -0,0 +1,127 @@
+use super::follow_redirects;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+use url::Url;
+use utoipa::ToSchema;
+
+// How much of an unexpected response is quoted in the error.
+const QUOTED_BODY_CHARS: usize = 40;
+
+// A path on the monitored site that has to serve a known token, e.g. one a
+// deploy writes to /.well-known/health-token. Seeing the new token confirms
+// the deploy reached every server behind the URL. Rotate the token together
+// with the deploy, from the edit form or a monitors file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct HttpChallenge {
+    pub path: String,
+    pub token: String,
+}
+
+impl HttpChallenge {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.path.starts_with('/') {
+            return Err("Challenge path must start with /".to_string());
+        }
+        if self.token.trim().is_empty() {
+            return Err("Challenge token is empty".to_string());
+        }
+        Ok(())
+    }
+
+    // The challenge path on the monitored site, replacing the URL's own path.
+    pub fn url(&self, base_url: &str) -> Result<Url, String> {
+        Url::parse(base_url)
+            .and_then(|base| base.join(&self.path))
+            .map_err(|e| format!("Invalid challenge URL: {}", e))
+    }
+
+    // Surrounding whitespace is ignored, files usually end with a newline.
+    pub fn verify(&self, body: &str) -> Result<(), String> {
+        let body = body.trim();
+        if body == self.token.trim() {
+            return Ok(());
+        }
+
+        let mut quoted: String = body.chars().take(QUOTED_BODY_CHARS).collect();
+        if body.chars().count() > QUOTED_BODY_CHARS {
+            quoted.push('…');
+        }
+        Err(format!("{} returned {:?}", self, quoted))
+    }
+
+    pub async fn check(
+        &self,
+        client: &Client,
+        base_url: &str,
+        headers: &BTreeMap<String, String>,
+        timeout: Option<Duration>,
+    ) -> Result<(), String> {
+        let mut request = client.get(self.url(base_url)?);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        let response = follow_redirects(client, request, |_| false)
+            .await
+            .map_err(|e| format!("{} failed: {}", self, e))?;
+        if !response.status().is_success() {
+            return Err(format!("{} returned {}", self, response.status()));
+        }
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("{} failed: {}", self, e))?;
+        self.verify(&body)
+    }
+}
+
+impl fmt::Display for HttpChallenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (expecting {:?})", self.path, self.token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge() -> HttpChallenge {
+        HttpChallenge {
+            path: "/.well-known/health-token".to_string(),
+            token: "deploy-42".to_string(),
+        }
+    }
+
+    #[test]
+    fn challenge_replaces_the_path() {
+        let url = challenge()
+            .url("https://example.com/app/status?verbose=1")
+            .expect("Valid URL");
+        assert_eq!(url.as_str(), "https://example.com/.well-known/health-token");
+    }
+
+    #[test]
+    fn token_must_match_exactly() {
+        assert!(challenge().verify("deploy-42\n").is_ok());
+        assert_eq!(
+            challenge().verify("deploy-41"),
+            Err(
+                "/.well-known/health-token (expecting \"deploy-42\") returned \"deploy-41\""
+                    .to_string()
+            )
+        );
+        assert!(
+            HttpChallenge {
+                path: "health-token".to_string(),
+                ..challenge()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use super::json_path::JsonPath;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use utoipa::ToSchema;
+
+// When a check counts as Up, for monitors that need more than a 2xx status.
+// Criteria combine with all/any/not, e.g. in a monitors file:
+//
+// success_criteria:
+//   all:
+//     - status_in: { min: 200, max: 299 }
+//     - body_contains: "\"healthy\":true"
+//     - latency_below_ms: 500
+//     - json_path: { path: "$.status", equals: "ok" }
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(no_recursion)]
+pub enum Criterion {
+    StatusIn {
+        min: u16,
+        max: u16,
+    },
+    BodyContains(String),
+    LatencyBelowMs(u64),
+    // The body parsed as JSON has a value at the path, equal to equals when
+    // set. Paths leading to several values (with [*]) need all of them equal.
+    JsonPath {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        equals: Option<Value>,
+    },
+    All(Vec<Criterion>),
+    Any(Vec<Criterion>),
+    Not(Box<Criterion>),
+}
+
+// What a criterion is checked against.
+#[derive(Debug)]
+pub struct CheckResponse<'a> {
+    pub status: u16,
+    pub response_time_ms: u64,
+    // Only fetched when a criterion looks at it, see Criterion::needs_body.
+    pub body: Option<&'a str>,
+}
+
+impl Criterion {
+    // Checks that fail on the body need a GET instead of the usual HEAD.
+    pub fn needs_body(&self) -> bool {
+        match self {
+            Criterion::BodyContains(_) | Criterion::JsonPath { .. } => true,
+            Criterion::StatusIn { .. } | Criterion::LatencyBelowMs(_) => false,
+            Criterion::All(criteria) | Criterion::Any(criteria) => {
+                criteria.iter().any(Criterion::needs_body)
+            }
+            Criterion::Not(criterion) => criterion.needs_body(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Criterion::StatusIn { min, max } if min > max => Err(format!(
+                "Status range {}-{} is empty in the success criteria",
+                min, max
+            )),
+            Criterion::BodyContains(keyword) if keyword.is_empty() => {
+                Err("Keyword in the success criteria is empty".to_string())
+            }
+            Criterion::All(criteria) | Criterion::Any(criteria) if criteria.is_empty() => {
+                Err("all/any in the success criteria needs at least one criterion".to_string())
+            }
+            Criterion::All(criteria) | Criterion::Any(criteria) => {
+                criteria.iter().try_for_each(Criterion::validate)
+            }
+            Criterion::Not(criterion) => criterion.validate(),
+            Criterion::JsonPath { path, .. } => JsonPath::parse(path).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    // Ok when the response passes, otherwise which criterion failed and why.
+    pub fn evaluate(&self, response: &CheckResponse) -> Result<(), String> {
+        match self {
+            Criterion::StatusIn { min, max } => {
+                if (*min..=*max).contains(&response.status) {
+                    Ok(())
+                } else {
+                    Err(format!("{} (got {})", self, response.status))
+                }
+            }
+            Criterion::BodyContains(keyword) => {
+                if response
+                    .body
+                    .is_some_and(|body| body.contains(keyword.as_str()))
+                {
+                    Ok(())
+                } else {
+                    Err(format!("{} (not found)", self))
+                }
+            }
+            Criterion::JsonPath { path, equals } => {
+                let Some(body) = response
+                    .body
+                    .and_then(|body| serde_json::from_str::<Value>(body).ok())
+                else {
+                    return Err(format!("{} (body isn't JSON)", self));
+                };
+                let found = JsonPath::parse(path)?.select(&body);
+                if found.is_empty() {
+                    return Err(format!("{} (not found)", self));
+                }
+                match equals
+                    .as_ref()
+                    .and_then(|expected| found.iter().find(|v| **v != expected))
+                {
+                    Some(got) => Err(format!("{} (got {})", self, got)),
+                    None => Ok(()),
+                }
+            }
+            Criterion::LatencyBelowMs(max_ms) => {
+                if response.response_time_ms < *max_ms {
+                    Ok(())
+                } else {
+                    Err(format!("{} (took {}ms)", self, response.response_time_ms))
+                }
+            }
+            // Reports the first criterion that failed.
+            Criterion::All(criteria) => criteria.iter().try_for_each(|c| c.evaluate(response)),
+            Criterion::Any(criteria) => {
+                let mut failures = Vec::new();
+                for criterion in criteria {
+                    match criterion.evaluate(response) {
+                        Ok(()) => return Ok(()),
+                        Err(failure) => failures.push(failure),
+                    }
+                }
+                Err(format!("none of: {}", failures.join("; ")))
+            }
+            Criterion::Not(criterion) => match criterion.evaluate(response) {
+                Ok(()) => Err(self.to_string()),
+                Err(_) => Ok(()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Criterion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |criteria: &[Criterion], separator: &str| {
+            criteria
+                .iter()
+                .map(Criterion::to_string)
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+
+        match self {
+            Criterion::StatusIn { min, max } if min == max => write!(f, "status {}", min),
+            Criterion::StatusIn { min, max } => write!(f, "status {}-{}", min, max),
+            Criterion::BodyContains(keyword) => write!(f, "body contains {:?}", keyword),
+            Criterion::LatencyBelowMs(max_ms) => write!(f, "latency < {}ms", max_ms),
+            Criterion::JsonPath {
+                path,
+                equals: Some(expected),
+            } => write!(f, "{} == {}", path, expected),
+            Criterion::JsonPath { path, equals: None } => write!(f, "{} exists", path),
+            Criterion::All(criteria) => write!(f, "({})", join(criteria, " AND ")),
+            Criterion::Any(criteria) => write!(f, "({})", join(criteria, " OR ")),
+            Criterion::Not(criterion) => write!(f, "NOT {}", criterion),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, response_time_ms: u64, body: Option<&str>) -> CheckResponse<'_> {
+        CheckResponse {
+            status,
+            response_time_ms,
+            body,
+        }
+    }
+
+    fn health_check() -> Criterion {
+        serde_json::from_str(
+            r#"{"all": [
+                {"status_in": {"min": 200, "max": 299}},
+                {"body_contains": "healthy"},
+                {"latency_below_ms": 500}
+            ]}"#,
+        )
+        .expect("Failed to parse criteria")
+    }
+
+    #[test]
+    fn all_criteria_must_pass() {
+        let criteria = health_check();
+        assert!(criteria.needs_body());
+        assert!(criteria.validate().is_ok());
+        assert!(
+            criteria
+                .evaluate(&response(200, 120, Some("healthy")))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn failing_criterion_is_named() {
+        let criteria = health_check();
+        assert_eq!(
+            criteria.evaluate(&response(200, 120, Some("degraded"))),
+            Err("body contains \"healthy\" (not found)".to_string())
+        );
+        assert_eq!(
+            criteria.evaluate(&response(200, 900, Some("healthy"))),
+            Err("latency < 500ms (took 900ms)".to_string())
+        );
+    }
+
+    #[test]
+    fn any_and_not_combine() {
+        let criteria = Criterion::Any(vec![
+            Criterion::StatusIn { min: 200, max: 200 },
+            Criterion::Not(Box::new(Criterion::StatusIn { min: 500, max: 599 })),
+        ]);
+        assert!(!criteria.needs_body());
+        assert!(criteria.evaluate(&response(404, 10, None)).is_ok());
+        assert_eq!(
+            criteria.evaluate(&response(503, 10, None)),
+            Err("none of: status 200 (got 503); NOT status 500-599".to_string())
+        );
+    }
+
+    #[test]
+    fn json_path_looks_into_the_body() {
+        let criteria: Criterion =
+            serde_json::from_str(r#"{"json_path": {"path": "$.status", "equals": "ok"}}"#)
+                .expect("Failed to parse criteria");
+        assert!(criteria.needs_body());
+        assert!(
+            criteria
+                .evaluate(&response(200, 10, Some(r#"{"status": "ok"}"#)))
+                .is_ok()
+        );
+        assert_eq!(
+            criteria.evaluate(&response(200, 10, Some(r#"{"status": "degraded"}"#))),
+            Err(r#"$.status == "ok" (got "degraded")"#.to_string())
+        );
+        assert_eq!(
+            criteria.evaluate(&response(200, 10, Some("<html>"))),
+            Err(r#"$.status == "ok" (body isn't JSON)"#.to_string())
+        );
+
+        let all_up = Criterion::JsonPath {
+            path: "$.checks[*].up".to_string(),
+            equals: Some(Value::Bool(true)),
+        };
+        assert_eq!(
+            all_up.evaluate(&response(
+                200,
+                10,
+                Some(r#"{"checks": [{"up": true}, {"up": false}]}"#)
+            )),
+            Err("$.checks[*].up == true (got false)".to_string())
+        );
+        let exists = Criterion::JsonPath {
+            path: "$.version".to_string(),
+            equals: None,
+        };
+        assert_eq!(
+            exists.evaluate(&response(200, 10, Some("{}"))),
+            Err("$.version exists (not found)".to_string())
+        );
+        assert!(
+            Criterion::JsonPath {
+                path: "status".to_string(),
+                equals: None
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn empty_combinators_are_rejected() {
+        assert!(Criterion::All(Vec::new()).validate().is_err());
+        assert!(
+            Criterion::StatusIn { min: 300, max: 200 }
+                .validate()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use super::{MonitorHandle, follow_redirects, wait_until_stopped};
+use crate::configuration::{DiscoveryKind, DiscoverySource, MonitorTemplate};
+use crate::ui::domains::MonitoredDomain;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use uuid::Uuid;
+
+// One entry of a Prometheus http_sd response.
+#[derive(Debug, Deserialize)]
+struct TargetGroup {
+    targets: Vec<String>,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+}
+
+// One instance in Consul's catalog.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CatalogService {
+    address: String,
+    #[serde(default)]
+    service_address: String,
+    service_port: u16,
+}
+
+// The URLs to check for a discovery response, in the order listed.
+pub fn discovered_urls(source: &DiscoverySource, body: &str) -> Result<Vec<String>, String> {
+    let mut urls: Vec<String> = Vec::new();
+    match source.kind {
+        DiscoveryKind::Prometheus => {
+            let groups: Vec<TargetGroup> = serde_json::from_str(body)
+                .map_err(|e| format!("Invalid http_sd response: {}", e))?;
+            for group in groups {
+                // Prometheus' own labels for how to scrape, honoured the same way.
+                let scheme = group.labels.get("__scheme__").unwrap_or(&source.scheme);
+                if !matches!(scheme.as_str(), "http" | "https") {
+                    tracing::error!(
+                        "Skipping targets of {} with scheme {:?}, only http and https are checked",
+                        source.name,
+                        scheme
+                    );
+                    continue;
+                }
+                for target in group.targets {
+                    urls.push(format!("{}://{}{}", scheme, target, source.path));
+                }
+            }
+        }
+        DiscoveryKind::Consul => {
+            let services: Vec<CatalogService> = serde_json::from_str(body)
+                .map_err(|e| format!("Invalid Consul catalog response: {}", e))?;
+            for service in services {
+                // Instances without their own address use their node's.
+                let host = match service.service_address.as_str() {
+                    "" => service.address,
+                    address => address.to_string(),
+                };
+                let host = match host.contains(':') {
+                    true => format!("[{}]", host),
+                    false => host,
+                };
+                urls.push(format!(
+                    "{}://{}:{}{}",
+                    source.scheme, host, service.service_port, source.path
+                ));
+            }
+        }
+    }
+    // A target listed twice, e.g. in two groups, is still one monitor.
+    let mut unique: Vec<String> = Vec::new();
+    for url in urls {
+        if !unique.contains(&url) {
+            unique.push(url);
+        }
+    }
+    Ok(unique)
+}
+
+// The monitors to add for targets that are new, and those of the source's
+// group whose target vanished. URLs already monitored outside the group are
+// left alone, and targets that wouldn't make a valid monitor are skipped.
+pub fn reconcile(
+    domains: &[MonitoredDomain],
+    source: &DiscoverySource,
+    urls: &[String],
+    template: Option<&MonitorTemplate>,
+) -> (Vec<MonitoredDomain>, Vec<Uuid>) {
+    let group = source.group();
+    let added = urls
+        .iter()
+        .filter(|url| !domains.iter().any(|d| &*d.url == url.as_str()))
+        .map(|url| {
+            let mut domain = MonitoredDomain::new(url);
+            if let Some(template) = template {
+                domain.apply_template(template);
+            }
+            domain.group = Some(group.clone());
+            domain
+        })
+        .filter(|domain| match domain.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Skipping {} from {}: {}", domain.url, source.name, e);
+                false
+            }
+        })
+        .collect();
+    let removed = domains
+        .iter()
+        .filter(|d| d.group.as_ref() == Some(&group) && !urls.iter().any(|url| **url == *d.url))
+        .map(|d| d.id)
+        .collect();
+    (added, removed)
+}
+
+async fn fetch(client: &Client, source: &DiscoverySource) -> Result<Vec<String>, String> {
+    let request = match source.kind {
+        DiscoveryKind::Prometheus => {
+            let request = client.get(&source.url);
+            match &source.token {
+                Some(token) => request.bearer_auth(token),
+                None => request,
+            }
+        }
+        DiscoveryKind::Consul => {
+            let service = source
+                .service
+                .as_deref()
+                .ok_or("A Consul source needs a service")?;
+            let url = format!(
+                "{}/v1/catalog/service/{}",
+                source.url.trim_end_matches('/'),
+                service
+            );
+            let request = client.get(url);
+            match &source.token {
+                Some(token) => request.header("X-Consul-Token", token),
+                None => request,
+            }
+        }
+    };
+
+    let response = follow_redirects(client, request, |_| false)
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let body = response.text().await.map_err(|e| e.to_string())?;
+    discovered_urls(source, &body)
+}
+
+impl MonitorHandle {
+    // Keeps each source's group of monitors in step with its targets until
+    // the handle is stopped.
+    pub fn start_discovery(&self, sources: &[DiscoverySource], templates: &[MonitorTemplate]) {
+        for source in sources {
+            let template = match &source.template {
+                Some(name) => match templates.iter().find(|t| &t.name == name) {
+                    Some(template) => Some(template.clone()),
+                    None => {
+                        tracing::error!(
+                            "Unknown template \"{}\" for discovery source {}, not starting it",
+                            name,
+                            source.name
+                        );
+                        continue;
+                    }
+                },
+                None => None,
+            };
+
+            let monitor = self.clone();
+            let source = source.clone();
+            let mut stop_receiver = self.stop.subscribe();
+            tokio::spawn(async move {
+                let refresh = Duration::from_secs(source.refresh_seconds.max(1));
+                let rounds = async {
+                    loop {
+                        monitor.discover(&source, template.as_ref()).await;
+                        tokio::time::sleep(refresh).await;
+                    }
+                };
+                tokio::select! {
+                    _ = rounds => {}
+                    _ = wait_until_stopped(&mut stop_receiver) => {}
+                }
+            });
+        }
+    }
+
+    async fn discover(&self, source: &DiscoverySource, template: Option<&MonitorTemplate>) {
+        let urls = match fetch(&self.client, source).await {
+            Ok(urls) => urls,
+            Err(e) => {
+                // Nothing is removed on a failed fetch, only on an answer.
+                tracing::error!("Discovery from {} failed: {}", source.name, e);
+                return;
+            }
+        };
+        if urls.is_empty() {
+            // More likely a broken source than a fleet scaled to nothing, and
+            // removing the monitors would lose their history.
+            tracing::warn!(
+                "Discovery from {} found no targets, keeping its monitors",
+                source.name
+            );
+            return;
+        }
+
+        let (added, removed) = {
+            let domains_guard = self.domains.lock().unwrap();
+            reconcile(&domains_guard, source, &urls, template)
+        };
+        for domain in added {
+            tracing::info!("Discovered {} from {}", domain.url, source.name);
+            if let Err(e) = self.add_domain(domain) {
+                tracing::error!("Failed to save discovered domain: {}", e);
+            }
+        }
+        for domain_id in removed {
+            match self.remove_domain(domain_id) {
+                Ok(Some(domain)) => {
+                    tracing::info!("{} vanished from {}, removed it", domain.url, source.name)
+                }
+                Ok(None) => {}
+                Err(e) => tracing::error!("Failed to save domains after discovery: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(kind: DiscoveryKind) -> DiscoverySource {
+        DiscoverySource {
+            name: "web".to_string(),
+            kind,
+            url: "http://sd.internal".to_string(),
+            service: Some("web".to_string()),
+            scheme: "https".to_string(),
+            path: "/health".to_string(),
+            template: None,
+            refresh_seconds: 60,
+            token: None,
+        }
+    }
+
+    #[test]
+    fn discovers_targets_and_reconciles_the_group() {
+        let prometheus = source(DiscoveryKind::Prometheus);
+        let http_sd = r#"[
+            {"targets": ["10.0.0.1:8080", "10.0.0.2:8080"], "labels": {"env": "prod"}},
+            {"targets": ["10.0.0.3:9090"], "labels": {"__scheme__": "http"}},
+            {"targets": ["web"], "labels": {"__scheme__": "docker"}}
+        ]"#;
+        assert_eq!(
+            discovered_urls(&prometheus, http_sd).unwrap(),
+            vec![
+                "https://10.0.0.1:8080/health",
+                "https://10.0.0.2:8080/health",
+                "http://10.0.0.3:9090/health",
+            ]
+        );
+        assert!(discovered_urls(&prometheus, "{}").is_err());
+
+        let consul = source(DiscoveryKind::Consul);
+        let catalog = r#"[
+            {"Address": "10.0.1.1", "ServiceAddress": "", "ServicePort": 80},
+            {"Address": "10.0.1.2", "ServiceAddress": "fd00::2", "ServicePort": 8080}
+        ]"#;
+        assert_eq!(
+            discovered_urls(&consul, catalog).unwrap(),
+            vec![
+                "https://10.0.1.1:80/health",
+                "https://[fd00::2]:8080/health"
+            ]
+        );
+
+        let mut vanished = MonitoredDomain::new("https://10.0.0.9:8080/health");
+        vanished.group = Some(prometheus.group());
+        let kept = MonitoredDomain::new("https://10.0.0.1:8080/health");
+        let mut kept_in_group = MonitoredDomain::new("https://10.0.0.2:8080/health");
+        kept_in_group.group = Some(prometheus.group());
+        let manual = MonitoredDomain::new("https://10.0.0.8:8080/health");
+        let domains = vec![vanished.clone(), kept, kept_in_group, manual];
+
+        let template = MonitorTemplate {
+            name: "fast".to_string(),
+            interval_seconds: Some(10),
+            ..Default::default()
+        };
+        let mut urls = discovered_urls(&prometheus, http_sd).unwrap();
+        urls.push("https://bad host:8080/health".to_string());
+        let (added, removed) = reconcile(&domains, &prometheus, &urls, Some(&template));
+        assert_eq!(added.len(), 1);
+        assert_eq!(&*added[0].url, "http://10.0.0.3:9090/health");
+        assert_eq!(added[0].interval_seconds, 10);
+        assert_eq!(added[0].group.as_deref(), Some("discovery:web"));
+        assert_eq!(removed, vec![vanished.id]);
+    }
+}
@@ -0,0 +1,801 @@
+use super::response_time;
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::Utc;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::task::JoinSet;
+
+// Used when /etc/resolv.conf names no nameserver, like the C library does.
+const FALLBACK_RESOLVER: &str = "127.0.0.1:53";
+
+// What resolvers=public asks: Google, Cloudflare and Quad9.
+const PUBLIC_RESOLVERS: [Ipv4Addr; 3] = [
+    Ipv4Addr::new(8, 8, 8, 8),
+    Ipv4Addr::new(1, 1, 1, 1),
+    Ipv4Addr::new(9, 9, 9, 9),
+];
+
+// Nothing the checks look up comes near this, bigger answers come over TCP.
+const MAX_UDP_RESPONSE: usize = 4096;
+
+// A query lost on the way is sent again after this, twice at most.
+const RESEND_AFTER: Duration = Duration::from_secs(1);
+const RESENDS: usize = 2;
+
+// The record types a DNS check can ask for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordType {
+    A,
+    Aaaa,
+    Cname,
+    Mx,
+    Ns,
+    Txt,
+}
+
+impl RecordType {
+    pub fn parse(name: &str) -> Option<RecordType> {
+        match name.to_uppercase().as_str() {
+            "A" => Some(RecordType::A),
+            "AAAA" => Some(RecordType::Aaaa),
+            "CNAME" => Some(RecordType::Cname),
+            "MX" => Some(RecordType::Mx),
+            "NS" => Some(RecordType::Ns),
+            "TXT" => Some(RecordType::Txt),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> u16 {
+        match self {
+            RecordType::A => 1,
+            RecordType::Ns => 2,
+            RecordType::Cname => 5,
+            RecordType::Mx => 15,
+            RecordType::Txt => 16,
+            RecordType::Aaaa => 28,
+        }
+    }
+
+    fn from_code(code: u16) -> Option<RecordType> {
+        [
+            RecordType::A,
+            RecordType::Aaaa,
+            RecordType::Cname,
+            RecordType::Mx,
+            RecordType::Ns,
+            RecordType::Txt,
+        ]
+        .into_iter()
+        .find(|t| t.code() == code)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RecordType::A => "A",
+            RecordType::Aaaa => "AAAA",
+            RecordType::Cname => "CNAME",
+            RecordType::Mx => "MX",
+            RecordType::Ns => "NS",
+            RecordType::Txt => "TXT",
+        }
+    }
+}
+
+// dns://<name>[/<type>][?resolver=<ip>[:port]...][&resolvers=public]
+// [&expect=<value>...], e.g. dns://example.com, dns://example.com/MX,
+// dns://example.com/AAAA?resolver=1.1.1.1,
+// dns://example.com?resolvers=public&resolver=10.0.0.53 or
+// dns://example.com?expect=93.184.216.34&expect=93.184.216.35.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnsQuery {
+    pub name: String,
+    pub record_type: RecordType,
+    // The system's resolver when empty, all of them asked when there are
+    // several.
+    pub resolvers: Vec<SocketAddr>,
+    // The only values the A, AAAA or CNAME records may have, any when empty.
+    pub expected: Vec<String>,
+}
+
+impl DnsQuery {
+    pub fn parse(rest: &str) -> Option<DnsQuery> {
+        let (target, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (name, record_type) = match target.split_once('/') {
+            Some((name, record_type)) => (name, RecordType::parse(record_type)?),
+            None => (target, RecordType::A),
+        };
+        let name = name.trim_end_matches('.').to_lowercase();
+        if !is_dns_name(&name) {
+            return None;
+        }
+
+        let mut resolvers = Vec::new();
+        let mut expected = Vec::new();
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some(("resolver", address)) => resolvers.push(parse_resolver(address)?),
+                Some(("resolvers", "public")) => {
+                    resolvers.extend(PUBLIC_RESOLVERS.map(|ip| SocketAddr::from((ip, 53))))
+                }
+                Some(("expect", value)) => expected.push(expected_value(record_type, value)?),
+                _ => return None,
+            }
+        }
+        // One named as well as in resolvers=public is asked once.
+        let mut unique = Vec::new();
+        for resolver in resolvers {
+            if !unique.contains(&resolver) {
+                unique.push(resolver);
+            }
+        }
+        Some(DnsQuery {
+            name,
+            record_type,
+            resolvers: unique,
+            expected,
+        })
+    }
+}
+
+// The value written the way answers are parsed, so 2001:DB8::0001 matches
+// 2001:db8::1. Only addresses and CNAMEs can be expected.
+fn expected_value(record_type: RecordType, value: &str) -> Option<String> {
+    match record_type {
+        RecordType::A => value.parse::<Ipv4Addr>().ok().map(|ip| ip.to_string()),
+        RecordType::Aaaa => value.parse::<Ipv6Addr>().ok().map(|ip| ip.to_string()),
+        RecordType::Cname => {
+            let name = value.trim_end_matches('.').to_lowercase();
+            is_dns_name(&name).then_some(name)
+        }
+        _ => None,
+    }
+}
+
+// Letters, digits, '-' and '_' (for SRV-style names) in labels of at most 63.
+fn is_dns_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        })
+}
+
+// An IP address, with a port when it isn't 53. IPv6 ones with a port go in
+// brackets, e.g. [2606:4700:4700::1111]:53.
+pub fn parse_resolver(address: &str) -> Option<SocketAddr> {
+    address.parse::<SocketAddr>().ok().or_else(|| {
+        address
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .ok()
+            .map(|ip| SocketAddr::new(ip, 53))
+    })
+}
+
+// The first nameserver in /etc/resolv.conf.
+fn system_resolver() -> SocketAddr {
+    fs::read_to_string("/etc/resolv.conf")
+        .ok()
+        .and_then(|conf| {
+            conf.lines().find_map(|line| {
+                let mut tokens = line.split_whitespace();
+                match tokens.next() {
+                    Some("nameserver") => tokens.next().and_then(parse_resolver),
+                    _ => None,
+                }
+            })
+        })
+        .unwrap_or_else(|| FALLBACK_RESOLVER.parse().unwrap())
+}
+
+// One record of an answer, e.g. type A with value 93.184.216.34.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnsRecord {
+    pub record_type: RecordType,
+    pub value: String,
+}
+
+impl std::fmt::Display for DnsRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.record_type.label(), self.value)
+    }
+}
+
+// The parts of a response a check looks at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnsAnswer {
+    // 0 is NOERROR, 3 NXDOMAIN.
+    pub rcode: u8,
+    pub truncated: bool,
+    // Records of types we don't know are left out.
+    pub records: Vec<DnsRecord>,
+}
+
+// Resolves the name and records what came back. Up when the resolver
+// answers with records of the type asked for, Down when the name doesn't
+// exist or has none, and Error when the resolver can't be reached or a
+// record has a value the URL doesn't expect. Asking several resolvers, the
+// check is Degraded while they answer differently.
+#[tracing::instrument(name = "dns check", skip(domain))]
+pub async fn check(query: &DnsQuery, domain: &MonitoredDomain) -> CheckStatus {
+    let limit = domain.timeout();
+    let resolvers = if query.resolvers.is_empty() {
+        vec![system_resolver()]
+    } else {
+        query.resolvers.clone()
+    };
+    let start_time = Utc::now();
+    let mut lookups = JoinSet::new();
+    for (i, resolver) in resolvers.iter().copied().enumerate() {
+        let query = query.clone();
+        lookups.spawn(async move {
+            let answer = tokio::time::timeout(limit, resolve(resolver, &query))
+                .await
+                .unwrap_or_else(|_| Err(format!("{} didn't answer in time", resolver)));
+            (i, answer)
+        });
+    }
+    let mut answers: Vec<Result<DnsAnswer, String>> =
+        vec![Err("Lookup failed".to_string()); resolvers.len()];
+    while let Some(Ok((i, answer))) = lookups.join_next().await {
+        answers[i] = answer;
+    }
+    let end_time = Utc::now();
+
+    let (status, error_message, detail) = if let [answer] = answers.as_slice() {
+        match answer {
+            Ok(answer) => {
+                let (status, error_message) = judge(query, answer);
+                let detail = answer
+                    .records
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                (
+                    status,
+                    error_message,
+                    (!detail.is_empty()).then_some(detail),
+                )
+            }
+            Err(e) => (DomainStatus::Error(e.clone().into()), Some(e.clone()), None),
+        }
+    } else {
+        compare(query, &resolvers, &answers)
+    };
+    if let DomainStatus::Error(e) = &status {
+        tracing::error!("Error checking {}: {}", domain.url, e);
+    }
+    let response_time_ms = response_time(answers.iter().any(Result::is_ok), start_time, end_time);
+    CheckStatus {
+        response_time_ms,
+        error_message: error_message.map(Arc::from),
+        detail: detail.map(Arc::from),
+        ..CheckStatus::new(end_time, status)
+    }
+}
+
+// The answers of several resolvers, grouped by what they said, e.g.
+// "A 93.184.216.34 from 8.8.8.8, 1.1.1.1; NXDOMAIN from 9.9.9.9". Resolvers
+// that didn't answer are listed after them and left out of the comparison.
+fn compare(
+    query: &DnsQuery,
+    resolvers: &[SocketAddr],
+    answers: &[Result<DnsAnswer, String>],
+) -> (DomainStatus, Option<String>, Option<String>) {
+    let reached: Vec<(&SocketAddr, &DnsAnswer)> = resolvers
+        .iter()
+        .zip(answers)
+        .filter_map(|(resolver, answer)| answer.as_ref().ok().map(|a| (resolver, a)))
+        .collect();
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for (resolver, answer) in &reached {
+        let said = summary(query, answer);
+        let name = resolver_name(**resolver);
+        match groups.iter_mut().find(|(other, _)| *other == said) {
+            Some((_, names)) => names.push(name),
+            None => groups.push((said, vec![name])),
+        }
+    }
+    let unreachable = resolvers
+        .iter()
+        .zip(answers)
+        .filter(|(_, answer)| answer.is_err())
+        .map(|(resolver, _)| format!("{} did not answer", resolver_name(*resolver)));
+    let detail = groups
+        .iter()
+        .map(|(said, names)| format!("{} from {}", said, names.join(", ")))
+        .chain(unreachable)
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    let Some((_, first)) = reached.first() else {
+        let errors = answers
+            .iter()
+            .filter_map(|a| a.as_ref().err().cloned())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return (
+            DomainStatus::Error(errors.clone().into()),
+            Some(errors),
+            None,
+        );
+    };
+    for (resolver, answer) in &reached {
+        if let (DomainStatus::Error(_), Some(message)) = judge(query, answer) {
+            let message = format!("{}: {}", resolver_name(**resolver), message);
+            return (
+                DomainStatus::Error(message.clone().into()),
+                Some(message),
+                Some(detail),
+            );
+        }
+    }
+    if groups.len() > 1 {
+        return (
+            DomainStatus::Degraded,
+            Some(format!("Resolvers disagree about {}", query.name)),
+            Some(detail),
+        );
+    }
+    let (status, error_message) = judge(query, first);
+    (status, error_message, Some(detail))
+}
+
+// What a resolver said about the type asked for, compared between them.
+fn summary(query: &DnsQuery, answer: &DnsAnswer) -> String {
+    match answer.rcode {
+        0 => {}
+        3 => return "NXDOMAIN".to_string(),
+        rcode => return rcode_name(rcode),
+    }
+    let mut records: Vec<String> = answer
+        .records
+        .iter()
+        .filter(|r| r.record_type == query.record_type)
+        .map(ToString::to_string)
+        .collect();
+    if records.is_empty() {
+        return format!("no {} records", query.record_type.label());
+    }
+    // Round robin answers come in any order.
+    records.sort();
+    records.join(", ")
+}
+
+// 8.8.8.8, with the port only when it isn't 53.
+fn resolver_name(resolver: SocketAddr) -> String {
+    if resolver.port() == 53 {
+        resolver.ip().to_string()
+    } else {
+        resolver.to_string()
+    }
+}
+
+fn judge(query: &DnsQuery, answer: &DnsAnswer) -> (DomainStatus, Option<String>) {
+    match answer.rcode {
+        0 => {}
+        3 => {
+            return (
+                DomainStatus::Down,
+                Some(format!("{} doesn't exist (NXDOMAIN)", query.name)),
+            );
+        }
+        rcode => {
+            return (
+                DomainStatus::Down,
+                Some(format!("Resolver answered {}", rcode_name(rcode))),
+            );
+        }
+    }
+    let records: Vec<&DnsRecord> = answer
+        .records
+        .iter()
+        .filter(|r| r.record_type == query.record_type)
+        .collect();
+    if records.is_empty() {
+        return (
+            DomainStatus::Down,
+            Some(format!(
+                "No {} records for {}",
+                query.record_type.label(),
+                query.name
+            )),
+        );
+    }
+    // A hijacked or botched zone answers with values nobody expected.
+    if !query.expected.is_empty() && records.iter().any(|r| !query.expected.contains(&r.value)) {
+        let observed = records
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let message = format!(
+            "{} resolves to {}, expected {}",
+            query.name,
+            observed,
+            query.expected.join(" or ")
+        );
+        return (DomainStatus::Error(message.clone().into()), Some(message));
+    }
+    (DomainStatus::Up, None)
+}
+
+fn rcode_name(rcode: u8) -> String {
+    match rcode {
+        1 => "FORMERR".to_string(),
+        2 => "SERVFAIL".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        rcode => format!("error code {}", rcode),
+    }
+}
+
+// Asks over UDP, and again over TCP when the answer didn't fit.
+pub async fn resolve(resolver: SocketAddr, query: &DnsQuery) -> Result<DnsAnswer, String> {
+    let id: u16 = rand::random();
+    let message = encode_query(id, &query.name, query.record_type);
+
+    let local: SocketAddr = match resolver {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(local).await.map_err(|e| e.to_string())?;
+    socket
+        .connect(resolver)
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", resolver, e))?;
+    let mut buffer = vec![0; MAX_UDP_RESPONSE];
+    let mut resends = RESENDS;
+    let answer = loop {
+        socket.send(&message).await.map_err(|e| e.to_string())?;
+        let receive = receive_answer(&socket, &mut buffer, id);
+        if resends == 0 {
+            break receive.await?;
+        }
+        resends -= 1;
+        if let Ok(answer) = tokio::time::timeout(RESEND_AFTER, receive).await {
+            break answer?;
+        }
+    };
+    if !answer.truncated {
+        return Ok(answer);
+    }
+
+    let mut stream = TcpStream::connect(resolver)
+        .await
+        .map_err(|e| format!("Failed to reach {} over TCP: {}", resolver, e))?;
+    let mut framed = (message.len() as u16).to_be_bytes().to_vec();
+    framed.extend_from_slice(&message);
+    stream.write_all(&framed).await.map_err(|e| e.to_string())?;
+    let len = stream.read_u16().await.map_err(|e| e.to_string())?;
+    let mut response = vec![0; len as usize];
+    stream
+        .read_exact(&mut response)
+        .await
+        .map_err(|e| e.to_string())?;
+    parse_response(id, &response)?.ok_or_else(|| "Answer to another query over TCP".to_string())
+}
+
+// Answers to someone else's query (a late one of ours, or spoofed) are
+// skipped, the timeout around the check ends the wait.
+async fn receive_answer(
+    socket: &UdpSocket,
+    buffer: &mut [u8],
+    id: u16,
+) -> Result<DnsAnswer, String> {
+    loop {
+        let len = socket.recv(buffer).await.map_err(|e| e.to_string())?;
+        if let Some(answer) = parse_response(id, &buffer[..len])? {
+            return Ok(answer);
+        }
+    }
+}
+
+// A standard query for one question, asking for recursion.
+pub fn encode_query(id: u16, name: &str, record_type: RecordType) -> Vec<u8> {
+    let mut message = Vec::with_capacity(18 + name.len());
+    message.extend_from_slice(&id.to_be_bytes());
+    // RD set, one question, no other sections.
+    message.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.split('.') {
+        message.push(label.len() as u8);
+        message.extend_from_slice(label.as_bytes());
+    }
+    message.push(0);
+    message.extend_from_slice(&record_type.code().to_be_bytes());
+    // Class IN.
+    message.extend_from_slice(&[0, 1]);
+    message
+}
+
+// None for a response to another query id.
+pub fn parse_response(id: u16, message: &[u8]) -> Result<Option<DnsAnswer>, String> {
+    let malformed = || "Malformed DNS response".to_string();
+    let header = message.get(..12).ok_or_else(malformed)?;
+    if u16::from_be_bytes([header[0], header[1]]) != id || header[2] & 0x80 == 0 {
+        return Ok(None);
+    }
+    let truncated = header[2] & 0x02 != 0;
+    let rcode = header[3] & 0x0f;
+    let questions = u16::from_be_bytes([header[4], header[5]]);
+    let answers = u16::from_be_bytes([header[6], header[7]]);
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = read_name(message, pos).ok_or_else(malformed)?.1 + 4;
+    }
+    let mut records = Vec::new();
+    for _ in 0..answers {
+        let (_, next) = read_name(message, pos).ok_or_else(malformed)?;
+        let fixed = message.get(next..next + 10).ok_or_else(malformed)?;
+        let code = u16::from_be_bytes([fixed[0], fixed[1]]);
+        let len = u16::from_be_bytes([fixed[8], fixed[9]]) as usize;
+        let data_start = next + 10;
+        let data = message
+            .get(data_start..data_start + len)
+            .ok_or_else(malformed)?;
+        pos = data_start + len;
+
+        let Some(record_type) = RecordType::from_code(code) else {
+            continue;
+        };
+        let value = match record_type {
+            RecordType::A => <[u8; 4]>::try_from(data)
+                .map(|octets| Ipv4Addr::from(octets).to_string())
+                .map_err(|_| malformed())?,
+            RecordType::Aaaa => <[u8; 16]>::try_from(data)
+                .map(|octets| Ipv6Addr::from(octets).to_string())
+                .map_err(|_| malformed())?,
+            RecordType::Cname | RecordType::Ns => {
+                read_name(message, data_start).ok_or_else(malformed)?.0
+            }
+            RecordType::Mx => {
+                let preference = u16::from_be_bytes(
+                    data.get(..2)
+                        .ok_or_else(malformed)?
+                        .try_into()
+                        .map_err(|_| malformed())?,
+                );
+                let exchange = read_name(message, data_start + 2).ok_or_else(malformed)?.0;
+                format!("{} {}", preference, exchange)
+            }
+            RecordType::Txt => {
+                // Length-prefixed strings, shown joined as one.
+                let mut text = String::new();
+                let mut rest = data;
+                while let Some((&len, tail)) = rest.split_first() {
+                    let chunk = tail.get(..len as usize).ok_or_else(malformed)?;
+                    text.push_str(&String::from_utf8_lossy(chunk));
+                    rest = &tail[len as usize..];
+                }
+                format!("\"{}\"", text)
+            }
+        };
+        records.push(DnsRecord { record_type, value });
+    }
+
+    Ok(Some(DnsAnswer {
+        rcode,
+        truncated,
+        records,
+    }))
+}
+
+// The name starting at pos, following compression pointers, and where the
+// data after it starts.
+fn read_name(message: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut end = None;
+    // Pointers only ever point back, this keeps a loop of them from hanging.
+    for _ in 0..128 {
+        let len = *message.get(pos)?;
+        match len {
+            0 => {
+                return Some((labels.join("."), end.unwrap_or(pos + 1)));
+            }
+            len if len & 0xc0 == 0xc0 => {
+                let offset = u16::from_be_bytes([len & 0x3f, *message.get(pos + 1)?]) as usize;
+                end.get_or_insert(pos + 2);
+                pos = offset;
+            }
+            len => {
+                let label = message.get(pos + 1..pos + 1 + len as usize)?;
+                labels.push(String::from_utf8_lossy(label).to_lowercase());
+                pos += 1 + len as usize;
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_and_parses_answers() {
+        assert_eq!(
+            DnsQuery::parse("Example.com./aaaa?resolver=1.1.1.1"),
+            Some(DnsQuery {
+                name: "example.com".to_string(),
+                record_type: RecordType::Aaaa,
+                resolvers: vec!["1.1.1.1:53".parse().unwrap()],
+                expected: Vec::new(),
+            })
+        );
+        assert_eq!(
+            DnsQuery::parse("example.com?resolver=[::1]:5353")
+                .unwrap()
+                .resolvers,
+            ["[::1]:5353".parse().unwrap()]
+        );
+        assert!(DnsQuery::parse("example.com/SRV").is_none());
+        assert!(DnsQuery::parse("-example.com").is_none());
+        assert!(DnsQuery::parse("example.com?resolver=dns.google").is_none());
+
+        let query = encode_query(0x1234, "www.example.com", RecordType::A);
+        // The response repeats the question and answers with a CNAME to
+        // example.com and an A record for it, both names compressed.
+        let mut response = query.clone();
+        response[2] = 0x81;
+        response[3] = 0x80;
+        response[7] = 2;
+        response.extend_from_slice(&[0xc0, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 2, 0xc0, 16]);
+        response.extend_from_slice(&[0xc0, 16, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 93, 184, 216, 34]);
+
+        let answer = parse_response(0x1234, &response).unwrap().unwrap();
+        assert_eq!(
+            answer
+                .records
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["CNAME example.com", "A 93.184.216.34"]
+        );
+        let query = DnsQuery::parse("www.example.com").unwrap();
+        assert_eq!(judge(&query, &answer), (DomainStatus::Up, None));
+        let mx = DnsQuery::parse("www.example.com/MX").unwrap();
+        assert_eq!(judge(&mx, &answer).0, DomainStatus::Down);
+        let pinned = DnsQuery::parse("www.example.com?expect=93.184.216.34").unwrap();
+        assert_eq!(judge(&pinned, &answer), (DomainStatus::Up, None));
+        let moved = DnsQuery::parse("www.example.com?expect=93.184.215.14").unwrap();
+        let message = "www.example.com resolves to A 93.184.216.34, expected 93.184.215.14";
+        assert_eq!(
+            judge(&moved, &answer),
+            (
+                DomainStatus::Error(message.into()),
+                Some(message.to_string())
+            )
+        );
+        let cname = DnsQuery::parse("www.example.com/CNAME?expect=Example.com.").unwrap();
+        assert_eq!(judge(&cname, &answer).0, DomainStatus::Up);
+        assert!(DnsQuery::parse("example.com?expect=example.net").is_none());
+        assert!(DnsQuery::parse("example.com/MX?expect=mail.example.com").is_none());
+
+        assert_eq!(parse_response(0x4321, &response), Ok(None));
+        assert!(parse_response(0x1234, &response[..response.len() - 2]).is_err());
+        response[3] = 0x83;
+        assert_eq!(
+            judge(&query, &parse_response(0x1234, &response).unwrap().unwrap()).0,
+            DomainStatus::Down
+        );
+    }
+
+    #[tokio::test]
+    async fn asks_again_when_a_query_is_lost() {
+        let resolver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = resolver.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buffer = [0; 512];
+            // The first query is dropped, the resend gets an empty answer.
+            resolver.recv_from(&mut buffer).await.unwrap();
+            let (len, from) = resolver.recv_from(&mut buffer).await.unwrap();
+            let mut response = buffer[..len].to_vec();
+            response[2] = 0x81;
+            response[3] = 0x80;
+            resolver.send_to(&response, from).await.unwrap();
+        });
+
+        let query = DnsQuery::parse("example.com").unwrap();
+        let answer = tokio::time::timeout(Duration::from_secs(5), resolve(address, &query))
+            .await
+            .expect("Answered after the resend")
+            .unwrap();
+        assert_eq!(answer.rcode, 0);
+        assert!(answer.records.is_empty());
+    }
+
+    #[test]
+    fn compares_resolvers() {
+        let query = DnsQuery::parse("example.com?resolvers=public&resolver=1.1.1.1").unwrap();
+        assert_eq!(query.resolvers.len(), 3);
+        let a = |values: &[&str]| {
+            Ok(DnsAnswer {
+                rcode: 0,
+                truncated: false,
+                records: values
+                    .iter()
+                    .map(|value| DnsRecord {
+                        record_type: RecordType::A,
+                        value: value.to_string(),
+                    })
+                    .collect(),
+            })
+        };
+        let resolvers = &query.resolvers;
+
+        let agreed = [
+            a(&["93.184.216.34", "93.184.216.35"]),
+            a(&["93.184.216.35", "93.184.216.34"]),
+            a(&["93.184.216.34", "93.184.216.35"]),
+        ];
+        assert_eq!(
+            compare(&query, resolvers, &agreed),
+            (
+                DomainStatus::Up,
+                None,
+                Some("A 93.184.216.34, A 93.184.216.35 from 8.8.8.8, 1.1.1.1, 9.9.9.9".to_string())
+            )
+        );
+
+        let propagating = [
+            a(&["203.0.113.9"]),
+            a(&["93.184.216.34"]),
+            Err("9.9.9.9:53 didn't answer in time".to_string()),
+        ];
+        assert_eq!(
+            compare(&query, resolvers, &propagating),
+            (
+                DomainStatus::Degraded,
+                Some("Resolvers disagree about example.com".to_string()),
+                Some(
+                    "A 203.0.113.9 from 8.8.8.8; A 93.184.216.34 from 1.1.1.1; 9.9.9.9 did not answer"
+                        .to_string()
+                )
+            )
+        );
+
+        let one_unreachable = [
+            a(&["93.184.216.34"]),
+            a(&["93.184.216.34"]),
+            Err("9.9.9.9:53 didn't answer in time".to_string()),
+        ];
+        assert_eq!(
+            compare(&query, resolvers, &one_unreachable),
+            (
+                DomainStatus::Up,
+                None,
+                Some("A 93.184.216.34 from 8.8.8.8, 1.1.1.1; 9.9.9.9 did not answer".to_string())
+            )
+        );
+
+        let pinned = DnsQuery {
+            expected: vec!["93.184.216.34".to_string()],
+            ..query.clone()
+        };
+        assert_eq!(
+            compare(&pinned, resolvers, &propagating).1.as_deref(),
+            Some("8.8.8.8: example.com resolves to A 203.0.113.9, expected 93.184.216.34")
+        );
+        let unreachable = [
+            Err("a".to_string()),
+            Err("b".to_string()),
+            Err("c".to_string()),
+        ];
+        assert_eq!(
+            compare(&query, resolvers, &unreachable).0,
+            DomainStatus::Error("a, b, c".into())
+        );
+    }
+}
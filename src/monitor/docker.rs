@@ -0,0 +1,227 @@
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+
+// Where Docker listens unless DOCKER_HOST points at another unix socket.
+const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+
+// The parts of `docker inspect` a check looks at.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContainerInspect {
+    state: ContainerState,
+    restart_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContainerState {
+    // "running", "exited", "restarting", ...
+    status: String,
+    // Only containers with a HEALTHCHECK have one.
+    health: Option<ContainerHealth>,
+    started_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ContainerHealth {
+    // "starting", "healthy" or "unhealthy".
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DockerError {
+    message: String,
+}
+
+// Asks the local Docker daemon about the container. A container is Up while
+// it runs and its health check (if it has one) passes, and Down after
+// restarting since the previous check, so a crash loop doesn't hide between
+// checks.
+#[tracing::instrument(name = "docker check", skip(domain))]
+pub async fn check(container: &str, domain: &MonitoredDomain) -> CheckStatus {
+    let limit = domain.timeout();
+    let start_time = Utc::now();
+    let inspected = tokio::time::timeout(limit, inspect(&socket_path(), container))
+        .await
+        .unwrap_or_else(|_| Err("Docker didn't answer in time".to_string()));
+    let end_time = Utc::now();
+
+    match inspected {
+        Ok(inspected) => {
+            let previous_check = domain.check_history.last().map(|c| c.timestamp);
+            let (status, error_message) = judge(&inspected, previous_check);
+            CheckStatus {
+                response_time_ms: Some((end_time - start_time).num_milliseconds() as u64),
+                error_message: error_message.map(Arc::from),
+                detail: Some(describe(&inspected).into()),
+                ..CheckStatus::new(end_time, status)
+            }
+        }
+        Err(e) => {
+            let err_msg: Arc<str> = e.into();
+            tracing::error!("Error checking {}: {}", domain.url, err_msg);
+            CheckStatus::failed(end_time, err_msg)
+        }
+    }
+}
+
+fn socket_path() -> String {
+    std::env::var("DOCKER_HOST")
+        .ok()
+        .and_then(|host| host.strip_prefix("unix://").map(str::to_string))
+        .unwrap_or_else(|| DEFAULT_SOCKET.to_string())
+}
+
+fn judge(
+    inspected: &ContainerInspect,
+    previous_check: Option<DateTime<Utc>>,
+) -> (DomainStatus, Option<String>) {
+    let state = &inspected.state;
+    if state.status != "running" {
+        return (
+            DomainStatus::Down,
+            Some(format!("Container is {}", state.status)),
+        );
+    }
+    match state.health.as_ref().map(|h| h.status.as_str()) {
+        Some("unhealthy") => {
+            return (
+                DomainStatus::Down,
+                Some("Container is unhealthy".to_string()),
+            );
+        }
+        // Nothing to judge until the first health check ran.
+        Some("starting") => return (DomainStatus::Unknown, None),
+        _ => {}
+    }
+    // Recreated containers start over at 0, so this only catches restarts.
+    if inspected.restart_count > 0 && previous_check.is_some_and(|at| state.started_at > at) {
+        return (
+            DomainStatus::Down,
+            Some("Container restarted since the last check".to_string()),
+        );
+    }
+    (DomainStatus::Up, None)
+}
+
+// e.g. "healthy, 2 restarts" or "running, 0 restarts".
+fn describe(inspected: &ContainerInspect) -> String {
+    let state = &inspected.state;
+    let health = state
+        .health
+        .as_ref()
+        .map_or(state.status.as_str(), |h| h.status.as_str());
+    let restarts = match inspected.restart_count {
+        1 => "1 restart".to_string(),
+        n => format!("{} restarts", n),
+    };
+    format!("{}, {}", health, restarts)
+}
+
+#[cfg(unix)]
+async fn inspect(socket: &str, container: &str) -> Result<ContainerInspect, String> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket)
+        .await
+        .map_err(|e| format!("Docker socket {}: {}", socket, e))?;
+    // HTTP/1.0 so the body comes back in one piece instead of chunked.
+    let request = format!(
+        "GET /containers/{}/json HTTP/1.0\r\nHost: docker\r\n\r\n",
+        container
+    );
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| format!("Docker request: {}", e))?;
+    let mut response = Vec::new();
+    stream
+        .read_to_end(&mut response)
+        .await
+        .map_err(|e| format!("Docker response: {}", e))?;
+    parse_response(&String::from_utf8_lossy(&response), container)
+}
+
+#[cfg(not(unix))]
+async fn inspect(_socket: &str, _container: &str) -> Result<ContainerInspect, String> {
+    Err("Docker checks need a unix socket".to_string())
+}
+
+fn parse_response(response: &str, container: &str) -> Result<ContainerInspect, String> {
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or("Docker sent an incomplete response")?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or("Docker sent an invalid response")?;
+
+    match status {
+        200 => serde_json::from_str(body).map_err(|e| format!("Unexpected inspect output: {}", e)),
+        404 => Err(format!("No such container: {}", container)),
+        _ => {
+            let message = serde_json::from_str::<DockerError>(body)
+                .map(|e| e.message)
+                .unwrap_or_else(|_| body.trim().to_string());
+            Err(format!("Docker answered {}: {}", status, message))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inspected(status: &str, health: Option<&str>, restarts: u32) -> ContainerInspect {
+        let health = health.map_or("null".to_string(), |h| format!(r#"{{"Status": "{}"}}"#, h));
+        let body = format!(
+            r#"{{"Id": "4f2a", "RestartCount": {}, "State": {{"Status": "{}", "Health": {}, "StartedAt": "2025-03-01T12:00:00.123456789Z"}}}}"#,
+            restarts, status, health
+        );
+        parse_response(&format!("HTTP/1.0 200 OK\r\n\r\n{}", body), "web")
+            .expect("Inspect output parses")
+    }
+
+    #[test]
+    fn health_and_restarts_decide_the_status() {
+        let before_start = Some("2025-03-01T11:59:00Z".parse().unwrap());
+        let after_start = Some("2025-03-01T12:01:00Z".parse().unwrap());
+
+        let healthy = inspected("running", Some("healthy"), 0);
+        assert_eq!(judge(&healthy, before_start).0, DomainStatus::Up);
+        assert_eq!(describe(&healthy), "healthy, 0 restarts");
+
+        let unhealthy = inspected("running", Some("unhealthy"), 0);
+        assert_eq!(judge(&unhealthy, before_start).0, DomainStatus::Down);
+        let exited = inspected("exited", None, 0);
+        assert_eq!(
+            judge(&exited, before_start),
+            (DomainStatus::Down, Some("Container is exited".to_string()))
+        );
+
+        // Restarted after the previous check, or long before it.
+        let restarted = inspected("running", None, 2);
+        assert_eq!(judge(&restarted, before_start).0, DomainStatus::Down);
+        assert_eq!(judge(&restarted, after_start).0, DomainStatus::Up);
+        assert_eq!(describe(&restarted), "running, 2 restarts");
+    }
+
+    #[test]
+    fn docker_errors_are_passed_on() {
+        assert_eq!(
+            parse_response("HTTP/1.0 404 Not Found\r\n\r\n{}", "web").unwrap_err(),
+            "No such container: web"
+        );
+        let error = parse_response(
+            "HTTP/1.0 500 Internal Server Error\r\n\r\n{\"message\": \"daemon is shutting down\"}",
+            "web",
+        )
+        .unwrap_err();
+        assert_eq!(error, "Docker answered 500: daemon is shutting down");
+    }
+}
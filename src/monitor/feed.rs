@@ -0,0 +1,246 @@
+use super::{follow_redirects, response_time};
+use crate::incident::format_duration;
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use regex::Regex;
+use reqwest::Client;
+use std::sync::Arc;
+use std::time::Duration;
+
+// How old the newest entry may get before the check turns Down, when the
+// URL doesn't say.
+const DEFAULT_MAX_AGE_HOURS: u64 = 24;
+
+// The dates an RSS item or Atom entry is stamped with.
+const ENTRY_DATE: &str = r"(?s)<(?:pubDate|published|updated|dc:date)(?:\s[^>]*)?>\s*(?:<!\[CDATA\[)?(.*?)(?:\]\]>)?\s*</(?:pubDate|published|updated|dc:date)>";
+
+// feed://<host>/<path>[?max_age_hours=<n>&warn_age_hours=<n>] for a feed
+// served over https, feed+http:// for plain http. Other query parameters
+// are the feed URL's own and sent along.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedQuery {
+    // What's fetched, e.g. https://example.com/podcast.xml.
+    pub url: String,
+    pub max_age_hours: u64,
+    // Degraded once the newest entry is older, Down only past max_age_hours.
+    pub warn_age_hours: Option<u64>,
+}
+
+impl FeedQuery {
+    pub fn parse(scheme: &str, rest: &str) -> Option<FeedQuery> {
+        let (target, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let host = target.split('/').next().unwrap_or_default();
+        if host.is_empty() || host.contains(['@', ' ']) {
+            return None;
+        }
+
+        let hours = |value: &str| value.parse::<u64>().ok().filter(|h| *h > 0);
+        let mut max_age_hours = DEFAULT_MAX_AGE_HOURS;
+        let mut warn_age_hours = None;
+        let mut passed_on = Vec::new();
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some(("max_age_hours", value)) => max_age_hours = hours(value)?,
+                Some(("warn_age_hours", value)) => warn_age_hours = Some(hours(value)?),
+                _ => passed_on.push(pair),
+            }
+        }
+        if warn_age_hours.is_some_and(|warn| warn >= max_age_hours) {
+            return None;
+        }
+        let mut url = format!("{}://{}", scheme, target);
+        if !passed_on.is_empty() {
+            url = format!("{}?{}", url, passed_on.join("&"));
+        }
+        Some(FeedQuery {
+            url,
+            max_age_hours,
+            warn_age_hours,
+        })
+    }
+}
+
+// Fetches the feed and looks at its newest entry. Up while it's newer than
+// max_age_hours (and warn_age_hours when set), Degraded past warn_age_hours,
+// Down past max_age_hours or when no entry has a date, and Error when the
+// feed can't be fetched.
+#[tracing::instrument(name = "feed check", skip(client, domain), fields(url = %query.url))]
+pub async fn check(client: &Client, query: &FeedQuery, domain: &MonitoredDomain) -> CheckStatus {
+    let limit = domain.timeout();
+    let start_time = Utc::now();
+    let feed = fetch(client, &query.url, limit).await;
+    let end_time = Utc::now();
+
+    let (status, error_message, detail) = match feed {
+        Ok(feed) => judge(query, &feed, end_time),
+        Err(e) => {
+            tracing::error!("Error checking {}: {}", domain.url, e);
+            (DomainStatus::Error(e.clone().into()), Some(e), None)
+        }
+    };
+    let response_time_ms = response_time(
+        !matches!(status, DomainStatus::Error(_)),
+        start_time,
+        end_time,
+    );
+    CheckStatus {
+        response_time_ms,
+        error_message: error_message.map(Arc::from),
+        detail: detail.map(Arc::from),
+        ..CheckStatus::new(end_time, status)
+    }
+}
+
+async fn fetch(client: &Client, url: &str, limit: Duration) -> Result<String, String> {
+    let response = follow_redirects(client, client.get(url).timeout(limit), |_| false)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+    response.text().await.map_err(|e| e.to_string())
+}
+
+// The newest date of the feed's entries and how many entries it has. Dates
+// before the first entry are the feed's own and left out.
+fn newest_entry(feed: &str) -> (Option<DateTime<Utc>>, usize) {
+    let first_entry = ["<item", "<entry"]
+        .iter()
+        .filter_map(|tag| feed.find(tag))
+        .min();
+    let Some(first_entry) = first_entry else {
+        return (None, 0);
+    };
+    let entries = &feed[first_entry..];
+    let count = entries.matches("<item").count() + entries.matches("<entry").count();
+    let entry_date = Regex::new(ENTRY_DATE).expect("Valid entry date regex");
+    let newest = entry_date
+        .captures_iter(entries)
+        .filter_map(|captures| parse_date(captures[1].trim()))
+        .max();
+    (newest, count)
+}
+
+// RSS dates are RFC 2822, Atom ones RFC 3339.
+fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(date)
+        .or_else(|_| DateTime::parse_from_rfc3339(date))
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+fn judge(
+    query: &FeedQuery,
+    feed: &str,
+    now: DateTime<Utc>,
+) -> (DomainStatus, Option<String>, Option<String>) {
+    if !feed.contains("<rss") && !feed.contains("<feed") && !feed.contains("<rdf:RDF") {
+        let message = "Not an RSS or Atom feed".to_string();
+        return (
+            DomainStatus::Error(message.clone().into()),
+            Some(message),
+            None,
+        );
+    }
+    let (newest, count) = newest_entry(feed);
+    let Some(newest) = newest else {
+        return (
+            DomainStatus::Down,
+            Some("The feed has no dated entries".to_string()),
+            Some(format!("{} entries", count)),
+        );
+    };
+    let age = now - newest;
+    let detail = format!("{} entries, newest {} ago", count, format_duration(age));
+    let older_than = |hours: u64| age > ChronoDuration::hours(hours as i64);
+    if older_than(query.max_age_hours) {
+        let message = format!(
+            "Newest entry is {} old (more than {}h)",
+            format_duration(age),
+            query.max_age_hours
+        );
+        return (DomainStatus::Down, Some(message), Some(detail));
+    }
+    if let Some(warn_age_hours) = query.warn_age_hours
+        && older_than(warn_age_hours)
+    {
+        let message = format!(
+            "Newest entry is {} old (more than {}h)",
+            format_duration(age),
+            warn_age_hours
+        );
+        return (DomainStatus::Degraded, Some(message), Some(detail));
+    }
+    (DomainStatus::Up, None, Some(detail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Serves the feed to every request.
+    async fn feed_server(feed: String) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 4096];
+                let _ = stream.read(&mut request).await.unwrap();
+                let answer = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/rss+xml\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    feed.len(),
+                    feed
+                );
+                stream.write_all(answer.as_bytes()).await.unwrap();
+            }
+        });
+        address
+    }
+
+    #[tokio::test]
+    async fn judges_the_newest_entry() {
+        let now = Utc::now();
+        let rss = format!(
+            "<rss><channel><lastBuildDate>{}</lastBuildDate>\
+             <item><title>Episode 2</title><pubDate>{}</pubDate></item>\
+             <item><title>Episode 1</title><pubDate>{}</pubDate></item></channel></rss>",
+            now.to_rfc2822(),
+            (now - ChronoDuration::hours(30)).to_rfc2822(),
+            (now - ChronoDuration::hours(200)).to_rfc2822(),
+        );
+        let address = feed_server(rss).await;
+        let query = FeedQuery::parse(
+            "http",
+            &format!("{}/podcast.xml?warn_age_hours=12", address),
+        )
+        .unwrap();
+        assert_eq!(query.url, format!("http://{}/podcast.xml", address));
+        assert_eq!(query.max_age_hours, 24);
+        let domain = MonitoredDomain::new(&format!("feed+http://{}/podcast.xml", address));
+        let status = check(&Client::new(), &query, &domain).await;
+        // The channel's build date doesn't count, the newest episode does.
+        assert_eq!(status.status, DomainStatus::Down);
+        assert_eq!(
+            status.detail.as_deref(),
+            Some("2 entries, newest 30h 0m ago")
+        );
+
+        let atom = format!(
+            "<feed><updated>{}</updated><entry><updated><![CDATA[{}]]></updated></entry></feed>",
+            now.to_rfc3339(),
+            (now - ChronoDuration::hours(13)).to_rfc3339()
+        );
+        assert_eq!(judge(&query, &atom, now).0, DomainStatus::Degraded);
+        let fresh = FeedQuery::parse("https", "example.com/atom?lang=en&max_age_hours=48").unwrap();
+        assert_eq!(fresh.url, "https://example.com/atom?lang=en");
+        assert_eq!(judge(&fresh, &atom, now).0, DomainStatus::Up);
+        assert_eq!(judge(&fresh, "<feed></feed>", now).0, DomainStatus::Down);
+        assert!(matches!(
+            judge(&fresh, "<html></html>", now).0,
+            DomainStatus::Error(_)
+        ));
+
+        assert!(FeedQuery::parse("https", "example.com/rss?warn_age_hours=48").is_none());
+        assert!(FeedQuery::parse("https", "/rss").is_none());
+    }
+}
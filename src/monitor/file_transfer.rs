@@ -0,0 +1,627 @@
+use super::target::host_and_port;
+use crate::configuration::FtpSettings;
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::net::SocketAddr;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol {
+    Ftp,
+    Sftp,
+}
+
+impl Protocol {
+    fn default_port(self) -> u16 {
+        match self {
+            Protocol::Ftp => 21,
+            Protocol::Sftp => 22,
+        }
+    }
+}
+
+// ftp://<host>[:port][/<path>] or sftp://[<user>@]<host>[:port][/<path>],
+// with ?max_age_minutes=<n> for files. Without a path the check only logs
+// in, a path ending in / is listed and any other path is a file whose
+// modification time is read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileTransferQuery {
+    pub protocol: Protocol,
+    // Only sftp:// takes one, FTP logins go in an [[ftp]] entry.
+    pub user: Option<String>,
+    // As written, with the port if it has one.
+    pub host: String,
+    pub path: Option<String>,
+    pub max_age_minutes: Option<u64>,
+}
+
+impl FileTransferQuery {
+    pub fn parse(protocol: Protocol, rest: &str) -> Option<FileTransferQuery> {
+        let (target, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (authority, path) = match target.find('/') {
+            Some(slash) => (&target[..slash], Some(&target[slash..])),
+            None => (target, None),
+        };
+        let path = path.filter(|path| *path != "/");
+        let (user, host) = match authority.rsplit_once('@') {
+            Some((user, host)) => (Some(user), host),
+            None => (None, authority),
+        };
+        if user.is_some_and(|user| protocol == Protocol::Ftp || !is_user(user))
+            || host_and_port(host, protocol.default_port()).is_none()
+            || path.is_some_and(|path| path.contains(['"', '\n', '\r']))
+        {
+            return None;
+        }
+
+        let mut max_age_minutes = None;
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some(("max_age_minutes", minutes)) => {
+                    max_age_minutes = Some(minutes.parse().ok().filter(|m| *m > 0)?)
+                }
+                _ => return None,
+            }
+        }
+        // A file's age is all there is to bound.
+        if max_age_minutes.is_some() && path.is_none_or(|path| path.ends_with('/')) {
+            return None;
+        }
+        Some(FileTransferQuery {
+            protocol,
+            user: user.map(str::to_string),
+            host: host.to_string(),
+            path: path.map(str::to_string),
+            max_age_minutes,
+        })
+    }
+
+    // The host and port to connect to, IPv6 addresses without brackets.
+    fn host_and_port(&self) -> (String, u16) {
+        let default = self.protocol.default_port();
+        let (host, port) =
+            host_and_port(&self.host, default).unwrap_or_else(|| (self.host.clone(), default));
+        (host.trim_matches(['[', ']']).to_string(), port)
+    }
+}
+
+fn is_user(user: &str) -> bool {
+    !user.is_empty()
+        && !user.starts_with('-')
+        && user
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+// What a session found out about the server.
+#[derive(Debug, Clone, PartialEq)]
+enum Found {
+    // Logged in, with the server's greeting for FTP.
+    LoggedIn(Option<String>),
+    Listed(usize),
+    Modified(DateTime<Utc>),
+    Missing,
+    // Wrong credentials, or for SFTP an unknown host key.
+    LoginRefused(String),
+}
+
+// Logs in and looks at the path. Up when that works (and the file isn't
+// older than allowed), Down when the server can't be reached, the file is
+// missing or too old, and Error when the login is refused.
+#[tracing::instrument(name = "file transfer check", skip(logins, domain), fields(host = %query.host))]
+pub async fn check(
+    query: &FileTransferQuery,
+    logins: &[FtpSettings],
+    domain: &MonitoredDomain,
+) -> CheckStatus {
+    let limit = domain.timeout();
+    let start_time = Utc::now();
+    let session = async {
+        match query.protocol {
+            Protocol::Ftp => {
+                let login = logins.iter().find(|l| l.host == query.host);
+                ftp_session(query, login).await
+            }
+            Protocol::Sftp => sftp_session(query, limit).await,
+        }
+    };
+    let found = tokio::time::timeout(limit, session)
+        .await
+        .unwrap_or_else(|_| Err(format!("{} didn't answer in time", query.host)));
+    let end_time = Utc::now();
+
+    let answered = found.is_ok();
+    let (status, error_message, detail) = match found {
+        Ok(found) => judge(query, &found, end_time),
+        Err(e) => (DomainStatus::Down, Some(e), None),
+    };
+    if let DomainStatus::Error(e) = &status {
+        tracing::error!("Error checking {}: {}", domain.url, e);
+    }
+    CheckStatus {
+        response_time_ms: answered.then(|| (end_time - start_time).num_milliseconds() as u64),
+        error_message: error_message.map(Arc::from),
+        detail: detail.map(Arc::from),
+        ..CheckStatus::new(end_time, status)
+    }
+}
+
+fn judge(
+    query: &FileTransferQuery,
+    found: &Found,
+    now: DateTime<Utc>,
+) -> (DomainStatus, Option<String>, Option<String>) {
+    let path = query.path.as_deref().unwrap_or("/");
+    match found {
+        Found::LoggedIn(greeting) => (DomainStatus::Up, None, greeting.clone()),
+        Found::Listed(entries) => (DomainStatus::Up, None, Some(format!("{} entries", entries))),
+        Found::Modified(modified) => {
+            let detail = Some(format!(
+                "modified {}",
+                modified.format("%Y-%m-%d %H:%M UTC")
+            ));
+            let age_minutes = (now - *modified).num_minutes();
+            match query.max_age_minutes {
+                Some(max) if age_minutes > max as i64 => (
+                    DomainStatus::Down,
+                    Some(format!(
+                        "{} was last modified {} minutes ago, more than {}",
+                        path, age_minutes, max
+                    )),
+                    detail,
+                ),
+                _ => (DomainStatus::Up, None, detail),
+            }
+        }
+        Found::Missing => (
+            DomainStatus::Down,
+            Some(format!("{} doesn't exist", path)),
+            None,
+        ),
+        Found::LoginRefused(reason) => {
+            let message = format!("Login refused: {}", reason);
+            (
+                DomainStatus::Error(message.clone().into()),
+                Some(message),
+                None,
+            )
+        }
+    }
+}
+
+// The control connection of an FTP session.
+struct FtpControl {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+}
+
+impl FtpControl {
+    // The reply code and the text of its first line. Multi-line replies
+    // ("220-...") run until a line with the code and a space.
+    async fn reply(&mut self) -> Result<(u16, String), String> {
+        let first = self.line().await?;
+        let code: u16 = first
+            .get(..3)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| format!("Unexpected reply {:?}", first))?;
+        if first.as_bytes().get(3) == Some(&b'-') {
+            let last = format!("{} ", code);
+            while !self.line().await?.starts_with(&last) {}
+        }
+        Ok((code, first.get(4..).unwrap_or_default().trim().to_string()))
+    }
+
+    async fn line(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line).await {
+            Ok(0) => Err("The server closed the connection".to_string()),
+            Ok(_) => Ok(line.trim_end().to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    async fn command(&mut self, command: &str) -> Result<(u16, String), String> {
+        self.writer
+            .write_all(format!("{}\r\n", command).as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+        self.reply().await
+    }
+}
+
+fn unexpected(command: &str, (code, text): (u16, String)) -> String {
+    format!("{} failed: {} {}", command, code, text)
+}
+
+async fn ftp_session(
+    query: &FileTransferQuery,
+    login: Option<&FtpSettings>,
+) -> Result<Found, String> {
+    let (host, port) = query.host_and_port();
+    let stream = TcpStream::connect((host.as_str(), port))
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", query.host, e))?;
+    let peer = stream.peer_addr().map_err(|e| e.to_string())?;
+    let (reader, writer) = stream.into_split();
+    let mut control = FtpControl {
+        reader: BufReader::new(reader),
+        writer,
+    };
+
+    let greeting = match control.reply().await? {
+        (220, greeting) => greeting,
+        reply => return Err(unexpected("Connecting", reply)),
+    };
+    let (username, password) = match login {
+        Some(login) => (login.username.as_str(), login.password.as_str()),
+        None => ("anonymous", "anonymous@"),
+    };
+    match control.command(&format!("USER {}", username)).await? {
+        (230, _) => {}
+        (331 | 332, _) => match control.command(&format!("PASS {}", password)).await? {
+            (230 | 202, _) => {}
+            (530, text) => return Ok(Found::LoginRefused(text)),
+            reply => return Err(unexpected("PASS", reply)),
+        },
+        (530, text) => return Ok(Found::LoginRefused(text)),
+        reply => return Err(unexpected("USER", reply)),
+    }
+
+    let found = match query.path.as_deref() {
+        None => Found::LoggedIn(Some(greeting)),
+        Some(directory) if directory.ends_with('/') => {
+            let data = passive_data(&mut control, peer).await?;
+            let (reader, _) = data.into_split();
+            match control.command(&format!("LIST {}", directory)).await? {
+                (125 | 150, _) => {}
+                (450 | 550, _) => return Ok(Found::Missing),
+                reply => return Err(unexpected("LIST", reply)),
+            }
+            let mut listing = String::new();
+            BufReader::new(reader)
+                .read_to_string(&mut listing)
+                .await
+                .map_err(|e| e.to_string())?;
+            match control.reply().await? {
+                (226 | 250, _) => {}
+                reply => return Err(unexpected("LIST", reply)),
+            }
+            Found::Listed(listing.lines().filter(|l| !l.trim().is_empty()).count())
+        }
+        Some(file) => match control.command(&format!("MDTM {}", file)).await? {
+            (213, timestamp) => Found::Modified(parse_mdtm(&timestamp)?),
+            (550, _) => Found::Missing,
+            reply => return Err(unexpected("MDTM", reply)),
+        },
+    };
+    // The check is done either way, a server slow to say goodbye doesn't matter.
+    let _ = control.writer.write_all(b"QUIT\r\n").await;
+    Ok(found)
+}
+
+// Opens a passive data connection, with EPSV and then PASV. The address in
+// a PASV reply is ignored in favour of the control connection's, it's often
+// a private one behind NAT.
+async fn passive_data(control: &mut FtpControl, peer: SocketAddr) -> Result<TcpStream, String> {
+    let port = match control.command("EPSV").await? {
+        // "Entering Extended Passive Mode (|||6446|)"
+        (229, text) => text
+            .split('|')
+            .nth(3)
+            .and_then(|port| port.parse::<u16>().ok())
+            .ok_or_else(|| format!("Unexpected EPSV reply {:?}", text))?,
+        _ => match control.command("PASV").await? {
+            // "Entering Passive Mode (192,168,1,2,25,46)"
+            (227, text) => {
+                pasv_port(&text).ok_or_else(|| format!("Unexpected PASV reply {:?}", text))?
+            }
+            reply => return Err(unexpected("PASV", reply)),
+        },
+    };
+    TcpStream::connect(SocketAddr::new(peer.ip(), port))
+        .await
+        .map_err(|e| format!("Failed to open the data connection: {}", e))
+}
+
+// The port of a PASV reply, its last two numbers, each a byte.
+fn pasv_port(text: &str) -> Option<u16> {
+    let numbers: Vec<u8> = text
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    match numbers[..] {
+        [.., high, low] if numbers.len() >= 6 => Some(u16::from_be_bytes([high, low])),
+        _ => None,
+    }
+}
+
+// "20261016030012" or with fractions of a second, always UTC.
+fn parse_mdtm(timestamp: &str) -> Result<DateTime<Utc>, String> {
+    timestamp
+        .get(..14)
+        .and_then(|t| NaiveDateTime::parse_from_str(t, "%Y%m%d%H%M%S").ok())
+        .map(|t| t.and_utc())
+        .ok_or_else(|| format!("Unexpected MDTM reply {:?}", timestamp))
+}
+
+// Runs sftp in batch mode, which logs in like ssh: keys, agent, ~/.ssh/config
+// and known_hosts of the user running upquack, never prompting.
+async fn sftp_session(query: &FileTransferQuery, limit: Duration) -> Result<Found, String> {
+    let (host, port) = query.host_and_port();
+    // sftp would take what follows a colon for a path.
+    let host = if host.contains(':') {
+        format!("[{}]", host)
+    } else {
+        host
+    };
+    let destination = match &query.user {
+        Some(user) => format!("{}@{}", user, host),
+        None => host,
+    };
+    let script = match query.path.as_deref() {
+        None => "pwd\n".to_string(),
+        Some(directory) if directory.ends_with('/') => format!("ls -1 \"{}\"\n", directory),
+        Some(file) => format!("ls -l \"{}\"\n", file),
+    };
+
+    let mut process = Command::new("sftp")
+        .args(["-b", "-"])
+        .args(["-o", "BatchMode=yes"])
+        .args(["-o", &format!("ConnectTimeout={}", limit.as_secs().max(1))])
+        .args(["-P", &port.to_string()])
+        .arg("--")
+        .arg(&destination)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("starting sftp: {}", e))?;
+    if let Some(mut stdin) = process.stdin.take() {
+        stdin
+            .write_all(script.as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let output = process
+        .wait_with_output()
+        .await
+        .map_err(|e| e.to_string())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // Batch mode echoes each command, prefixed with the prompt.
+    let lines: Vec<&str> = stdout
+        .lines()
+        .filter(|line| !line.starts_with("sftp>") && !line.trim().is_empty())
+        .collect();
+
+    if !output.status.success() {
+        let reason = stderr
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("sftp failed")
+            .to_string();
+        return if stderr.contains("No such file") || stderr.contains("not found") {
+            Ok(Found::Missing)
+        } else if stderr.contains("Permission denied") || stderr.contains("Host key verification") {
+            Ok(Found::LoginRefused(reason))
+        } else {
+            Err(reason)
+        };
+    }
+    match query.path.as_deref() {
+        None => Ok(Found::LoggedIn(None)),
+        Some(directory) if directory.ends_with('/') => Ok(Found::Listed(lines.len())),
+        Some(_) => lines
+            .first()
+            .and_then(|line| parse_long_listing(line, Local::now()))
+            .map(Found::Modified)
+            .ok_or_else(|| format!("Unexpected listing {:?}", stdout.trim())),
+    }
+}
+
+// The modification time from an `ls -l` line, in sftp's local time:
+// "-rw-r--r-- 1 backup backup 123 Oct 16 03:00 done" for the last half year,
+// "... Oct 16  2025 done" before that.
+fn parse_long_listing(line: &str, now: DateTime<Local>) -> Option<DateTime<Utc>> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let (month, day, time_or_year) = (fields.get(5)?, fields.get(6)?, fields.get(7)?);
+    let month = month.parse::<chrono::Month>().ok()?.number_from_month();
+    let day: u32 = day.parse().ok()?;
+    let modified = match time_or_year.split_once(':') {
+        Some((hour, minute)) => {
+            let at = |year| {
+                NaiveDate::from_ymd_opt(year, month, day)?.and_hms_opt(
+                    hour.parse().ok()?,
+                    minute.parse().ok()?,
+                    0,
+                )
+            };
+            // Without a year it's within the last half year, so last year's
+            // when this year's would be in the future.
+            let this_year = at(now.year())?;
+            if this_year > now.naive_local() + chrono::Duration::days(1) {
+                at(now.year() - 1)?
+            } else {
+                this_year
+            }
+        }
+        None => {
+            NaiveDate::from_ymd_opt(time_or_year.parse().ok()?, month, day)?.and_hms_opt(0, 0, 0)?
+        }
+    };
+    Local
+        .from_local_datetime(&modified)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn checks_ftp_servers() {
+        let query = FileTransferQuery::parse(
+            Protocol::Sftp,
+            "backup@files.lan:2222/backups/done?max_age_minutes=1440",
+        )
+        .unwrap();
+        assert_eq!(query.user.as_deref(), Some("backup"));
+        assert_eq!(query.host_and_port(), ("files.lan".to_string(), 2222));
+        assert_eq!(
+            FileTransferQuery::parse(Protocol::Ftp, "[::1]")
+                .unwrap()
+                .host_and_port(),
+            ("::1".to_string(), 21)
+        );
+        assert_eq!(
+            FileTransferQuery::parse(Protocol::Sftp, "-oProxyCommand=x"),
+            None
+        );
+        assert_eq!(
+            FileTransferQuery::parse(Protocol::Ftp, "user@files.lan"),
+            None
+        );
+        assert_eq!(
+            FileTransferQuery::parse(Protocol::Ftp, "files.lan/incoming/?max_age_minutes=5"),
+            None
+        );
+
+        // A server with a file but no directory listing.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            writer
+                .write_all(b"220-Welcome\r\n220 Files ready\r\n")
+                .await
+                .unwrap();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let reply: &[u8] = match line.as_str() {
+                    "USER monitor" => b"331 Password required\r\n",
+                    "PASS hunter22" => b"230 Logged in\r\n",
+                    "MDTM /backups/done" => b"213 20261016030012\r\n",
+                    "QUIT" => break,
+                    _ => b"500 Unknown command\r\n",
+                };
+                writer.write_all(reply).await.unwrap();
+            }
+        });
+        let login = FtpSettings {
+            host: address.to_string(),
+            username: "monitor".to_string(),
+            password: "hunter22".to_string(),
+        };
+        let query = FileTransferQuery::parse(
+            Protocol::Ftp,
+            &format!("{}/backups/done?max_age_minutes=60", address),
+        )
+        .unwrap();
+        let found = ftp_session(&query, Some(&login)).await.unwrap();
+        let modified = DateTime::parse_from_rfc3339("2026-10-16T03:00:12Z").unwrap();
+        assert_eq!(found, Found::Modified(modified.to_utc()));
+
+        let (status, error, detail) = judge(&query, &found, modified.to_utc());
+        assert_eq!(status, DomainStatus::Up);
+        assert_eq!(error, None);
+        assert_eq!(detail.as_deref(), Some("modified 2026-10-16 03:00 UTC"));
+        let (status, error, _) = judge(
+            &query,
+            &found,
+            modified.to_utc() + chrono::Duration::hours(2),
+        );
+        assert_eq!(status, DomainStatus::Down);
+        assert_eq!(
+            error.as_deref(),
+            Some("/backups/done was last modified 120 minutes ago, more than 60")
+        );
+
+        assert_eq!(
+            pasv_port("Entering Passive Mode (192,168,1,2,25,46)"),
+            Some(6446)
+        );
+        assert_eq!(
+            pasv_port("Entering Passive Mode (192,168,1,2,999,46)"),
+            None
+        );
+        assert_eq!(pasv_port("Entering Passive Mode (25,46)"), None);
+
+        let now = Local.with_ymd_and_hms(2026, 1, 5, 12, 0, 0).unwrap();
+        let listed = |line| parse_long_listing(line, now).map(|t| t.with_timezone(&Local));
+        assert_eq!(
+            listed("-rw-r--r--    1 backup   backup        123 Dec 31 23:15 done"),
+            Local.with_ymd_and_hms(2025, 12, 31, 23, 15, 0).single()
+        );
+        assert_eq!(
+            listed("-rw-r--r--    1 backup   backup        123 Jan  5 09:30 done"),
+            Local.with_ymd_and_hms(2026, 1, 5, 9, 30, 0).single()
+        );
+        assert_eq!(
+            listed("-rw-r--r--    1 backup   backup        123 Mar  2  2024 done"),
+            Local.with_ymd_and_hms(2024, 3, 2, 0, 0, 0).single()
+        );
+    }
+
+    // Sends what a server would, then hangs up.
+    async fn server_saying(what: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_all(what).await.unwrap();
+        });
+        address.to_string()
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_replies() {
+        let session = |address: String| async move {
+            let query = FileTransferQuery::parse(Protocol::Ftp, &address).unwrap();
+            ftp_session(&query, None).await
+        };
+        assert_eq!(
+            session(server_saying(b"SSH-2.0-OpenSSH_9.6\r\n").await).await,
+            Err("Unexpected reply \"SSH-2.0-OpenSSH_9.6\"".to_string())
+        );
+        // Cut off in the middle of a multi-line greeting.
+        assert_eq!(
+            session(server_saying(b"220-Welcome\r\n").await).await,
+            Err("The server closed the connection".to_string())
+        );
+        assert_eq!(
+            session(server_saying(b"421 Too many users\r\n").await).await,
+            Err("Connecting failed: 421 Too many users".to_string())
+        );
+
+        assert_eq!(
+            parse_mdtm("20261016030012.345"),
+            Ok(Utc.with_ymd_and_hms(2026, 10, 16, 3, 0, 12).unwrap())
+        );
+        assert!(parse_mdtm("2026101603").is_err());
+        assert!(parse_mdtm("20261316030012").is_err());
+        assert_eq!(pasv_port("Entering Passive Mode (192,168,1,2,25)"), None);
+        assert_eq!(pasv_port("Entering Passive Mode (192,168,1,2,256,1)"), None);
+        assert_eq!(pasv_port(""), None);
+
+        let now = Local.with_ymd_and_hms(2026, 1, 5, 12, 0, 0).unwrap();
+        for line in [
+            "total 8",
+            "-rw-r--r--    1 backup   backup        123 Foo 31 23:15 done",
+            "-rw-r--r--    1 backup   backup        123 Feb 30  2024 done",
+            "-rw-r--r--    1 backup   backup        123 Dec 31 25:15 done",
+        ] {
+            assert_eq!(parse_long_listing(line, now), None, "{}", line);
+        }
+    }
+}
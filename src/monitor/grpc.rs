@@ -0,0 +1,346 @@
+use super::response_time;
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::Utc;
+use reqwest::Client;
+use reqwest::header::{CONTENT_TYPE, HeaderMap, TE};
+use std::sync::Arc;
+use std::time::Duration;
+
+// The health service every gRPC server can register.
+const HEALTH_CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+
+// gRPC's NOT_FOUND, what servers answer for a service they don't know.
+const NOT_FOUND: &str = "5";
+
+// grpc://<host>:<port>[/<service>] for plaintext HTTP/2, grpcs:// for TLS,
+// e.g. grpcs://api.example.com:443/shop.Checkout. Without a service the
+// server's overall health counts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrpcQuery {
+    pub host: String,
+    pub service: Option<String>,
+    pub tls: bool,
+}
+
+impl GrpcQuery {
+    pub fn parse(rest: &str, tls: bool) -> Option<GrpcQuery> {
+        let (host, service) = match rest.split_once('/') {
+            Some((host, "")) => (host, None),
+            Some((host, service)) => (host, Some(service)),
+            None => (rest, None),
+        };
+        let valid_host = !host.is_empty()
+            && host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'));
+        let valid_service = service.is_none_or(|service| {
+            service
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        });
+        if !valid_host || !valid_service {
+            return None;
+        }
+        Some(GrpcQuery {
+            host: host.to_string(),
+            service: service.map(str::to_string),
+            tls,
+        })
+    }
+
+    fn check_url(&self) -> String {
+        let scheme = if self.tls { "https" } else { "http" };
+        format!("{}://{}{}", scheme, self.host, HEALTH_CHECK_PATH)
+    }
+}
+
+// What a server answers, grpc.health.v1.HealthCheckResponse.ServingStatus.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ServingStatus {
+    Unknown,
+    Serving,
+    NotServing,
+    ServiceUnknown,
+}
+
+impl ServingStatus {
+    fn from_number(number: u64) -> ServingStatus {
+        match number {
+            1 => ServingStatus::Serving,
+            2 => ServingStatus::NotServing,
+            3 => ServingStatus::ServiceUnknown,
+            _ => ServingStatus::Unknown,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ServingStatus::Unknown => "UNKNOWN",
+            ServingStatus::Serving => "SERVING",
+            ServingStatus::NotServing => "NOT_SERVING",
+            ServingStatus::ServiceUnknown => "SERVICE_UNKNOWN",
+        }
+    }
+}
+
+// Calls Health/Check over HTTP/2. Up when the server answers SERVING, Down
+// for any other serving status or a service it doesn't know, and Error when
+// it can't be reached or fails the call.
+#[tracing::instrument(name = "grpc check", skip(domain), fields(host = %query.host))]
+pub async fn check(query: &GrpcQuery, domain: &MonitoredDomain) -> CheckStatus {
+    let limit = domain.timeout();
+    let start_time = Utc::now();
+    let answer = call(query, limit).await;
+    let end_time = Utc::now();
+
+    let (status, error_message, detail) = match answer {
+        Ok(serving) => judge(query, serving),
+        Err(e) => {
+            tracing::error!("Error checking {}: {}", domain.url, e);
+            (DomainStatus::Error(e.clone().into()), Some(e), None)
+        }
+    };
+    let response_time_ms = response_time(
+        !matches!(status, DomainStatus::Error(_)),
+        start_time,
+        end_time,
+    );
+    CheckStatus {
+        response_time_ms,
+        error_message: error_message.map(Arc::from),
+        detail: detail.map(Arc::from),
+        ..CheckStatus::new(end_time, status)
+    }
+}
+
+async fn call(query: &GrpcQuery, limit: Duration) -> Result<ServingStatus, String> {
+    // gRPC servers speak HTTP/2 only, plaintext ones without an upgrade.
+    let client = Client::builder()
+        .http2_prior_knowledge()
+        .timeout(limit)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .post(query.check_url())
+        .header(CONTENT_TYPE, "application/grpc")
+        .header(TE, "trailers")
+        .body(request(query.service.as_deref()))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", query.host, e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "{} answered with HTTP status {}",
+            query.host,
+            response.status()
+        ));
+    }
+    // A failed call usually comes without a body, its status in the headers.
+    if let Some(answer) = failed_call(response.headers()) {
+        return answer;
+    }
+    let body = response.bytes().await.map_err(|e| e.to_string())?;
+    parse_answer(&body)
+}
+
+// Ok(ServiceUnknown) for NOT_FOUND, which the health service answers for
+// services it doesn't know, and Err for any other failed call.
+fn failed_call(headers: &HeaderMap) -> Option<Result<ServingStatus, String>> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    match header("grpc-status")? {
+        "0" => None,
+        NOT_FOUND => Some(Ok(ServingStatus::ServiceUnknown)),
+        code => Some(Err(match header("grpc-message") {
+            Some(message) => format!("Health check failed with gRPC status {}: {}", code, message),
+            None => format!("Health check failed with gRPC status {}", code),
+        })),
+    }
+}
+
+// A HealthCheckRequest in a gRPC frame: not compressed, the message length
+// and the message, field 1 being the service name.
+fn request(service: Option<&str>) -> Vec<u8> {
+    let mut message = Vec::new();
+    if let Some(service) = service {
+        message.push(0x0a);
+        put_varint(&mut message, service.len() as u64);
+        message.extend_from_slice(service.as_bytes());
+    }
+    let mut frame = vec![0];
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&message);
+    frame
+}
+
+fn put_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn varint(bytes: &[u8], at: &mut usize) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*at)?;
+        *at += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte < 0x80 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+// The status field of the HealthCheckResponse in the body's frame, UNKNOWN
+// when the server leaves it out.
+fn parse_answer(body: &[u8]) -> Result<ServingStatus, String> {
+    let invalid = || "Invalid gRPC health check response".to_string();
+    if body.len() < 5 || body[0] != 0 {
+        return Err(invalid());
+    }
+    let len = u32::from_be_bytes(body[1..5].try_into().unwrap()) as usize;
+    let message = body.get(5..5 + len).ok_or_else(invalid)?;
+
+    let mut status = ServingStatus::Unknown;
+    let mut at = 0;
+    while at < message.len() {
+        let key = varint(message, &mut at).ok_or_else(invalid)?;
+        // Fields a later version adds are skipped by their wire type.
+        match (key >> 3, key & 0x07) {
+            (1, 0) => {
+                status = ServingStatus::from_number(varint(message, &mut at).ok_or_else(invalid)?)
+            }
+            (_, 0) => {
+                varint(message, &mut at).ok_or_else(invalid)?;
+            }
+            (_, 1) => at += 8,
+            (_, 2) => {
+                let len = varint(message, &mut at).ok_or_else(invalid)?;
+                at = at.saturating_add(len as usize);
+            }
+            (_, 5) => at += 4,
+            _ => return Err(invalid()),
+        }
+    }
+    if at > message.len() {
+        return Err(invalid());
+    }
+    Ok(status)
+}
+
+fn judge(
+    query: &GrpcQuery,
+    serving: ServingStatus,
+) -> (DomainStatus, Option<String>, Option<String>) {
+    let detail = Some(serving.label().to_string());
+    let target = query.service.as_deref().unwrap_or(&query.host);
+    match serving {
+        ServingStatus::Serving => (DomainStatus::Up, None, detail),
+        ServingStatus::ServiceUnknown => (
+            DomainStatus::Down,
+            Some(format!(
+                "{} doesn't know the service {}",
+                query.host, target
+            )),
+            detail,
+        ),
+        _ => (
+            DomainStatus::Down,
+            Some(format!("{} is {}", target, serving.label())),
+            detail,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn speaks_the_health_checking_protocol() {
+        let query = GrpcQuery::parse("api.example.com:443/shop.Checkout", true).unwrap();
+        assert_eq!(
+            query.check_url(),
+            "https://api.example.com:443/grpc.health.v1.Health/Check"
+        );
+        assert_eq!(
+            request(query.service.as_deref()),
+            b"\0\0\0\0\x0f\x0a\x0dshop.Checkout"
+        );
+        assert_eq!(request(None), b"\0\0\0\0\0");
+        assert!(GrpcQuery::parse("", false).is_none());
+        assert!(GrpcQuery::parse("api.lan:50051/shop Checkout", false).is_none());
+
+        assert_eq!(
+            parse_answer(b"\0\0\0\0\x02\x08\x01"),
+            Ok(ServingStatus::Serving)
+        );
+        // An unknown string field before the status is skipped.
+        assert_eq!(
+            parse_answer(b"\0\0\0\0\x05\x12\x01x\x08\x02"),
+            Ok(ServingStatus::NotServing)
+        );
+        assert_eq!(parse_answer(b"\0\0\0\0\0"), Ok(ServingStatus::Unknown));
+        assert!(parse_answer(b"\0\0\0\0\x09\x08").is_err());
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(failed_call(&headers), None);
+        headers.insert("grpc-status", HeaderValue::from_static("5"));
+        assert_eq!(
+            failed_call(&headers),
+            Some(Ok(ServingStatus::ServiceUnknown))
+        );
+        headers.insert("grpc-status", HeaderValue::from_static("12"));
+        headers.insert("grpc-message", HeaderValue::from_static("unimplemented"));
+        assert_eq!(
+            failed_call(&headers),
+            Some(Err(
+                "Health check failed with gRPC status 12: unimplemented".to_string()
+            ))
+        );
+
+        assert_eq!(judge(&query, ServingStatus::Serving).0, DomainStatus::Up);
+        assert_eq!(
+            judge(&query, ServingStatus::NotServing),
+            (
+                DomainStatus::Down,
+                Some("shop.Checkout is NOT_SERVING".to_string()),
+                Some("NOT_SERVING".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_answers() {
+        let mut encoded = Vec::new();
+        put_varint(&mut encoded, 300);
+        assert_eq!(encoded, [0xac, 0x02]);
+        assert_eq!(varint(&encoded, &mut 0), Some(300));
+
+        for body in [
+            &b""[..],
+            // Compressed, or shorter than its frame header.
+            b"\x01\0\0\0\x02\x08\x01",
+            b"\0\0\0\x02",
+            // A varint cut off, or longer than 64 bits.
+            b"\0\0\0\0\x02\x08\x81",
+            b"\0\0\0\0\x0c\x08\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01",
+            // A string, a fixed64 and a fixed32 running past the message.
+            b"\0\0\0\0\x03\x12\x05x",
+            b"\0\0\0\0\x02\x09\0",
+            b"\0\0\0\0\x02\x0d\0",
+            // Groups (wire type 3) are long deprecated.
+            b"\0\0\0\0\x01\x0b",
+        ] {
+            assert_eq!(
+                parse_answer(body),
+                Err("Invalid gRPC health check response".to_string()),
+                "{:02x?}",
+                body
+            );
+        }
+    }
+}
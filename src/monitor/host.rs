@@ -0,0 +1,215 @@
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use sysinfo::{Disks, System};
+
+// How full the disk and memory may get before the check turns Degraded, when
+// the URL doesn't say.
+const DEFAULT_MAX_DISK_PERCENT: u8 = 90;
+const DEFAULT_MAX_MEMORY_PERCENT: u8 = 90;
+
+// host://[<path>][?max_disk_percent=<n>&max_memory_percent=<n>&max_load=<n>],
+// the machine upquack runs on. The path picks the disk, / by default, e.g.
+// host:///var/lib/upquack?max_disk_percent=80.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostQuery {
+    pub path: PathBuf,
+    pub max_disk_percent: u8,
+    pub max_memory_percent: u8,
+    // The one minute load average, one per CPU when the URL doesn't say.
+    pub max_load: Option<f64>,
+}
+
+impl HostQuery {
+    pub fn parse(rest: &str) -> Option<HostQuery> {
+        let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let path = match path {
+            // The system drive on Windows.
+            "" if cfg!(windows) => "C:\\",
+            "" => "/",
+            path if path.starts_with('/') => path,
+            _ => return None,
+        };
+
+        let percent = |value: &str| value.parse::<u8>().ok().filter(|p| (1..=100).contains(p));
+        let mut max_disk_percent = DEFAULT_MAX_DISK_PERCENT;
+        let mut max_memory_percent = DEFAULT_MAX_MEMORY_PERCENT;
+        let mut max_load = None;
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some(("max_disk_percent", value)) => max_disk_percent = percent(value)?,
+                Some(("max_memory_percent", value)) => max_memory_percent = percent(value)?,
+                Some(("max_load", value)) => {
+                    max_load = Some(value.parse::<f64>().ok().filter(|l| *l > 0.0)?)
+                }
+                _ => return None,
+            }
+        }
+        Some(HostQuery {
+            path: PathBuf::from(path),
+            max_disk_percent,
+            max_memory_percent,
+            max_load,
+        })
+    }
+}
+
+// What the machine reports, read in one go.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostMetrics {
+    pub mount_point: PathBuf,
+    pub disk_percent: u8,
+    pub memory_percent: u8,
+    pub load: f64,
+    pub cpus: usize,
+}
+
+// Reads disk space, memory and load. Up while all are within the URL's
+// thresholds, Degraded when any is past its threshold, and Error when the
+// path isn't on any disk the system lists.
+#[tracing::instrument(name = "host check", skip(domain), fields(path = %query.path.display()))]
+pub async fn check(query: &HostQuery, domain: &MonitoredDomain) -> CheckStatus {
+    let start_time = Utc::now();
+    let path = query.path.clone();
+    // sysinfo reads /proc and friends synchronously.
+    let metrics = tokio::task::spawn_blocking(move || read_metrics(&path))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|metrics| metrics);
+    let end_time = Utc::now();
+
+    let (status, error_message, detail) = match metrics {
+        Ok(metrics) => judge(query, &metrics),
+        Err(e) => {
+            tracing::error!("Error checking {}: {}", domain.url, e);
+            (DomainStatus::Error(e.clone().into()), Some(e), None)
+        }
+    };
+    CheckStatus {
+        response_time_ms: Some((end_time - start_time).num_milliseconds() as u64),
+        error_message: error_message.map(Arc::from),
+        detail: detail.map(Arc::from),
+        ..CheckStatus::new(end_time, status)
+    }
+}
+
+fn read_metrics(path: &Path) -> Result<HostMetrics, String> {
+    let disks = Disks::new_with_refreshed_list();
+    // The disk mounted closest to the path, / for most paths.
+    let disk = disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .filter(|disk| disk.total_space() > 0)
+        .ok_or_else(|| format!("No disk holds {}", path.display()))?;
+    let mut system = System::new();
+    system.refresh_memory();
+
+    Ok(HostMetrics {
+        mount_point: disk.mount_point().to_path_buf(),
+        disk_percent: percent_used(
+            disk.total_space().saturating_sub(disk.available_space()),
+            disk.total_space(),
+        ),
+        memory_percent: percent_used(
+            system
+                .total_memory()
+                .saturating_sub(system.available_memory()),
+            system.total_memory(),
+        ),
+        // Always 0 on Windows, which has no load average.
+        load: System::load_average().one,
+        cpus: std::thread::available_parallelism().map_or(1, |cpus| cpus.get()),
+    })
+}
+
+fn percent_used(used: u64, total: u64) -> u8 {
+    if total == 0 {
+        return 0;
+    }
+    (used as f64 * 100.0 / total as f64).round() as u8
+}
+
+fn judge(
+    query: &HostQuery,
+    metrics: &HostMetrics,
+) -> (DomainStatus, Option<String>, Option<String>) {
+    let detail = format!(
+        "Disk {}% ({}), memory {}%, load {:.2}",
+        metrics.disk_percent,
+        metrics.mount_point.display(),
+        metrics.memory_percent,
+        metrics.load
+    );
+    let max_load = query.max_load.unwrap_or(metrics.cpus as f64);
+    let mut warnings = Vec::new();
+    if metrics.disk_percent > query.max_disk_percent {
+        warnings.push(format!(
+            "{} is {}% full (more than {}%)",
+            metrics.mount_point.display(),
+            metrics.disk_percent,
+            query.max_disk_percent
+        ));
+    }
+    if metrics.memory_percent > query.max_memory_percent {
+        warnings.push(format!(
+            "Memory is {}% used (more than {}%)",
+            metrics.memory_percent, query.max_memory_percent
+        ));
+    }
+    if metrics.load > max_load {
+        warnings.push(format!(
+            "Load is {:.2} (more than {})",
+            metrics.load, max_load
+        ));
+    }
+    if warnings.is_empty() {
+        (DomainStatus::Up, None, Some(detail))
+    } else {
+        (
+            DomainStatus::Degraded,
+            Some(warnings.join(", ")),
+            Some(detail),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn warns_past_thresholds() {
+        let query =
+            HostQuery::parse("?max_disk_percent=100&max_memory_percent=100&max_load=100000")
+                .unwrap();
+        let status = check(&query, &MonitoredDomain::new("host://")).await;
+        assert_eq!(status.status, DomainStatus::Up);
+        assert!(status.detail.unwrap().starts_with("Disk "));
+
+        let busy = HostMetrics {
+            mount_point: PathBuf::from("/var"),
+            disk_percent: 95,
+            memory_percent: 40,
+            load: 6.5,
+            cpus: 4,
+        };
+        let defaults = HostQuery::parse("/var/lib/upquack").unwrap();
+        assert_eq!(
+            judge(&defaults, &busy),
+            (
+                DomainStatus::Degraded,
+                Some("/var is 95% full (more than 90%), Load is 6.50 (more than 4)".to_string()),
+                Some("Disk 95% (/var), memory 40%, load 6.50".to_string())
+            )
+        );
+        let relaxed = HostQuery::parse("?max_disk_percent=97&max_load=8").unwrap();
+        assert_eq!(judge(&relaxed, &busy).0, DomainStatus::Up);
+
+        assert!(HostQuery::parse("var").is_none());
+        assert!(HostQuery::parse("?max_disk_percent=120").is_none());
+        assert!(HostQuery::parse("?max_load=-1").is_none());
+    }
+}
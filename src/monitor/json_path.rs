@@ -0,0 +1,57 @@
+use serde_json::Value;
+
+// A JSONPath as RFC 9535 has it, e.g. $.checks[*].status, or
+// $..status with recursive descent and $.checks[?@.status != 'up'] with
+// filters.
+#[derive(Debug, Clone)]
+pub struct JsonPath(serde_json_path::JsonPath);
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<JsonPath, String> {
+        serde_json_path::JsonPath::parse(path.trim())
+            .map(JsonPath)
+            .map_err(|e| format!("Invalid JSONPath {:?}: {}", path, e))
+    }
+
+    // Every value the path leads to, none when it leads nowhere.
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        self.0.query(root).all()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn selects_what_the_path_leads_to() {
+        let health = json!({
+            "status": "degraded",
+            "checks": [
+                {"name": "db", "status": "up"},
+                {"name": "cache", "status": "down"}
+            ],
+            "build.info": {"version": "2.1.0"}
+        });
+        let select = |path: &str| JsonPath::parse(path).unwrap().select(&health);
+
+        assert_eq!(select("$.status"), [&json!("degraded")]);
+        assert_eq!(select("$.checks[0].status"), [&json!("up")]);
+        assert_eq!(select("$.checks[-1].name"), [&json!("cache")]);
+        assert_eq!(select("$.checks[*].status"), [&json!("up"), &json!("down")]);
+        assert_eq!(select("$['build.info'].version"), [&json!("2.1.0")]);
+        assert_eq!(select("$"), [&health]);
+        assert!(select("$.checks[5]").is_empty());
+        assert!(select("$.status.nested").is_empty());
+
+        assert!(JsonPath::parse("status").is_err());
+        assert!(JsonPath::parse("$.checks[").is_err());
+        assert!(JsonPath::parse("$.checks[first]").is_err());
+        assert_eq!(select("$..version"), [&json!("2.1.0")]);
+        assert_eq!(
+            select("$.checks[?@.status != 'up'].name"),
+            [&json!("cache")]
+        );
+    }
+}
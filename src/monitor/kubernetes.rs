@@ -0,0 +1,443 @@
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::Utc;
+use reqwest::{Certificate, Client, Identity};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{env, fs};
+use url::Url;
+
+// The parts of a kubeconfig needed to reach the API server of its current
+// context. Credential plugins (exec, auth-provider) aren't supported.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Kubeconfig {
+    current_context: Option<String>,
+    #[serde(default)]
+    contexts: Vec<NamedContext>,
+    #[serde(default)]
+    clusters: Vec<NamedCluster>,
+    #[serde(default)]
+    users: Vec<NamedUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedContext {
+    name: String,
+    context: Context,
+}
+
+#[derive(Debug, Deserialize)]
+struct Context {
+    cluster: String,
+    user: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedCluster {
+    name: String,
+    cluster: Cluster,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Cluster {
+    server: String,
+    certificate_authority: Option<PathBuf>,
+    certificate_authority_data: Option<String>,
+    #[serde(default)]
+    insecure_skip_tls_verify: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedUser {
+    name: String,
+    user: User,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct User {
+    token: Option<String>,
+    #[serde(rename = "tokenFile")]
+    token_file: Option<PathBuf>,
+    client_certificate: Option<PathBuf>,
+    client_certificate_data: Option<String>,
+    client_key: Option<PathBuf>,
+    client_key_data: Option<String>,
+    exec: Option<serde_yaml::Value>,
+    auth_provider: Option<serde_yaml::Value>,
+}
+
+// An API server and how to authenticate with it.
+#[derive(Debug)]
+struct ApiServer {
+    url: String,
+    client: Client,
+    token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PodList {
+    items: Vec<Pod>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pod {
+    metadata: PodMetadata,
+    #[serde(default)]
+    status: PodStatus,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PodMetadata {
+    name: String,
+    // Set once the pod is shutting down.
+    deletion_timestamp: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PodStatus {
+    #[serde(default)]
+    conditions: Vec<PodCondition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PodCondition {
+    #[serde(rename = "type")]
+    kind: String,
+    status: String,
+}
+
+impl Pod {
+    fn is_ready(&self) -> bool {
+        self.status
+            .conditions
+            .iter()
+            .any(|c| c.kind == "Ready" && c.status == "True")
+    }
+}
+
+// Counts the ready pods matching the selector. Up when every one of them is
+// ready, Down when any isn't or none match.
+#[tracing::instrument(name = "kubernetes check", skip(domain))]
+pub async fn check(namespace: &str, selector: &str, domain: &MonitoredDomain) -> CheckStatus {
+    let limit = domain.timeout();
+    let start_time = Utc::now();
+    let pods = match kubeconfig_path(domain.kubeconfig.as_deref()) {
+        Some(path) => list_pods(&path, namespace, selector, limit).await,
+        None => Err("No kubeconfig, set the monitor's or KUBECONFIG".to_string()),
+    };
+    let end_time = Utc::now();
+
+    match pods {
+        Ok(pods) => {
+            let (status, error_message, detail) = judge(&pods);
+            CheckStatus {
+                response_time_ms: Some((end_time - start_time).num_milliseconds() as u64),
+                error_message: error_message.map(Arc::from),
+                detail: Some(detail.into()),
+                ..CheckStatus::new(end_time, status)
+            }
+        }
+        Err(e) => {
+            let err_msg: Arc<str> = e.into();
+            tracing::error!("Error checking {}: {}", domain.url, err_msg);
+            CheckStatus::failed(end_time, err_msg)
+        }
+    }
+}
+
+// The monitor's own kubeconfig, else the first one in KUBECONFIG, else
+// ~/.kube/config, like kubectl.
+fn kubeconfig_path(configured: Option<&str>) -> Option<PathBuf> {
+    if let Some(path) = configured {
+        return Some(PathBuf::from(path));
+    }
+    if let Some(paths) = env::var_os("KUBECONFIG")
+        && let Some(first) = env::split_paths(&paths).next()
+    {
+        return Some(first);
+    }
+    env::home_dir().map(|home| home.join(".kube").join("config"))
+}
+
+async fn list_pods(
+    kubeconfig: &Path,
+    namespace: &str,
+    selector: &str,
+    limit: Duration,
+) -> Result<Vec<Pod>, String> {
+    let raw = fs::read_to_string(kubeconfig)
+        .map_err(|e| format!("Reading {}: {}", kubeconfig.display(), e))?;
+    let config: Kubeconfig = serde_yaml::from_str(&raw)
+        .map_err(|e| format!("Invalid kubeconfig {}: {}", kubeconfig.display(), e))?;
+    let base_dir = kubeconfig.parent().unwrap_or(Path::new("."));
+    let server = api_server(&config, base_dir, limit)?;
+
+    let mut url = Url::parse(&server.url).map_err(|e| format!("Invalid server URL: {}", e))?;
+    url.path_segments_mut()
+        .map_err(|_| "Invalid server URL".to_string())?
+        .pop_if_empty()
+        .extend(["api", "v1", "namespaces", namespace, "pods"]);
+    url.query_pairs_mut().append_pair("labelSelector", selector);
+
+    let mut request = server.client.get(url);
+    if let Some(token) = &server.token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Kubernetes API: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Kubernetes API answered {}", status));
+    }
+    let pods: PodList = response
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected pod list: {}", e))?;
+    Ok(pods.items)
+}
+
+fn api_server(config: &Kubeconfig, base_dir: &Path, limit: Duration) -> Result<ApiServer, String> {
+    let context_name = config
+        .current_context
+        .as_deref()
+        .ok_or("The kubeconfig has no current-context")?;
+    let context = &config
+        .contexts
+        .iter()
+        .find(|c| c.name == context_name)
+        .ok_or_else(|| format!("No context \"{}\" in the kubeconfig", context_name))?
+        .context;
+    let cluster = &config
+        .clusters
+        .iter()
+        .find(|c| c.name == context.cluster)
+        .ok_or_else(|| format!("No cluster \"{}\" in the kubeconfig", context.cluster))?
+        .cluster;
+    let user = &config
+        .users
+        .iter()
+        .find(|u| u.name == context.user)
+        .ok_or_else(|| format!("No user \"{}\" in the kubeconfig", context.user))?
+        .user;
+    if user.exec.is_some() || user.auth_provider.is_some() {
+        return Err(
+            "Credential plugins aren't supported, use a token or client certificate".into(),
+        );
+    }
+
+    let mut builder = Client::builder().timeout(limit);
+    if cluster.insecure_skip_tls_verify {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    let ca = read_data_or_file(
+        &cluster.certificate_authority_data,
+        &cluster.certificate_authority,
+        base_dir,
+    )?;
+    if let Some(ca) = ca {
+        let certificate =
+            Certificate::from_pem(&ca).map_err(|e| format!("Invalid cluster CA: {}", e))?;
+        builder = builder.add_root_certificate(certificate);
+    }
+    let certificate = read_data_or_file(
+        &user.client_certificate_data,
+        &user.client_certificate,
+        base_dir,
+    )?;
+    let key = read_data_or_file(&user.client_key_data, &user.client_key, base_dir)?;
+    if let (Some(certificate), Some(key)) = (certificate, key) {
+        builder = with_identity(builder, &certificate, &key)?;
+    }
+    let token = match (&user.token, &user.token_file) {
+        (Some(token), _) => Some(token.clone()),
+        (None, Some(path)) => Some(
+            fs::read_to_string(base_dir.join(path))
+                .map_err(|e| format!("Reading {}: {}", path.display(), e))?
+                .trim()
+                .to_string(),
+        ),
+        (None, None) => None,
+    };
+
+    Ok(ApiServer {
+        url: cluster.server.clone(),
+        client: builder.build().map_err(|e| e.to_string())?,
+        token,
+    })
+}
+
+#[cfg(feature = "rustls")]
+fn with_identity(
+    builder: reqwest::ClientBuilder,
+    certificate: &[u8],
+    key: &[u8],
+) -> Result<reqwest::ClientBuilder, String> {
+    let identity = Identity::from_pem(&[certificate, b"\n", key].concat())
+        .map_err(|e| format!("Invalid client certificate: {}", e))?;
+    Ok(builder.use_rustls_tls().identity(identity))
+}
+
+#[cfg(not(feature = "rustls"))]
+fn with_identity(
+    builder: reqwest::ClientBuilder,
+    certificate: &[u8],
+    key: &[u8],
+) -> Result<reqwest::ClientBuilder, String> {
+    let identity = Identity::from_pkcs8_pem(certificate, key)
+        .map_err(|e| format!("Invalid client certificate: {}", e))?;
+    Ok(builder.identity(identity))
+}
+
+// Kubeconfigs carry certificates either inline as base64 or as a path,
+// relative to the kubeconfig.
+fn read_data_or_file(
+    data: &Option<String>,
+    file: &Option<PathBuf>,
+    base_dir: &Path,
+) -> Result<Option<Vec<u8>>, String> {
+    match (data, file) {
+        (Some(data), _) => STANDARD
+            .decode(data.trim())
+            .map(Some)
+            .map_err(|e| format!("Invalid base64 in the kubeconfig: {}", e)),
+        (None, Some(file)) => fs::read(base_dir.join(file))
+            .map(Some)
+            .map_err(|e| format!("Reading {}: {}", file.display(), e)),
+        (None, None) => Ok(None),
+    }
+}
+
+// Pods on their way out don't count, a rollout replaces them.
+fn judge(pods: &[Pod]) -> (DomainStatus, Option<String>, String) {
+    let current: Vec<&Pod> = pods
+        .iter()
+        .filter(|pod| pod.metadata.deletion_timestamp.is_none())
+        .collect();
+    let not_ready: Vec<&str> = current
+        .iter()
+        .filter(|pod| !pod.is_ready())
+        .map(|pod| pod.metadata.name.as_str())
+        .collect();
+    let ready = current.len() - not_ready.len();
+    let detail = format!("{}/{} ready", ready, current.len());
+
+    if current.is_empty() {
+        (
+            DomainStatus::Down,
+            Some("No pods match the selector".to_string()),
+            detail,
+        )
+    } else if !not_ready.is_empty() {
+        (
+            DomainStatus::Down,
+            Some(format!("Not ready: {}", not_ready.join(", "))),
+            detail,
+        )
+    } else {
+        (DomainStatus::Up, None, detail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pod(name: &str, ready: bool, terminating: bool) -> Pod {
+        Pod {
+            metadata: PodMetadata {
+                name: name.to_string(),
+                deletion_timestamp: terminating.then(|| "2025-03-01T12:00:00Z".to_string()),
+            },
+            status: PodStatus {
+                conditions: vec![PodCondition {
+                    kind: "Ready".to_string(),
+                    status: if ready { "True" } else { "False" }.to_string(),
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn every_pod_has_to_be_ready() {
+        let all_ready = [pod("web-1", true, false), pod("web-2", true, false)];
+        assert_eq!(
+            judge(&all_ready),
+            (DomainStatus::Up, None, "2/2 ready".to_string())
+        );
+
+        let one_down = [pod("web-1", true, false), pod("web-2", false, false)];
+        assert_eq!(
+            judge(&one_down),
+            (
+                DomainStatus::Down,
+                Some("Not ready: web-2".to_string()),
+                "1/2 ready".to_string()
+            )
+        );
+
+        // The old pod of a rollout is left out.
+        let rolling = [pod("web-1", true, false), pod("web-0", false, true)];
+        assert_eq!(judge(&rolling).0, DomainStatus::Up);
+        assert_eq!(judge(&[]).0, DomainStatus::Down);
+    }
+
+    #[test]
+    fn current_context_picks_server_and_token() {
+        let config: Kubeconfig = serde_yaml::from_str(
+            r#"
+            apiVersion: v1
+            kind: Config
+            current-context: prod
+            contexts:
+              - name: staging
+                context: { cluster: staging, user: ci }
+              - name: prod
+                context: { cluster: prod, user: monitor, namespace: shop }
+            clusters:
+              - name: staging
+                cluster: { server: "https://staging.example.com:6443" }
+              - name: prod
+                cluster:
+                  server: "https://k8s.example.com:6443"
+                  insecure-skip-tls-verify: true
+            users:
+              - name: ci
+                user: { token: ci-token }
+              - name: monitor
+                user: { token: monitor-token }
+            "#,
+        )
+        .expect("Kubeconfig parses");
+
+        let server = api_server(&config, Path::new("."), Duration::from_secs(5))
+            .expect("Current context resolves");
+        assert_eq!(server.url, "https://k8s.example.com:6443");
+        assert_eq!(server.token.as_deref(), Some("monitor-token"));
+    }
+
+    #[test]
+    fn credential_plugins_are_refused() {
+        let config: Kubeconfig = serde_yaml::from_str(
+            r#"
+            current-context: eks
+            contexts: [{ name: eks, context: { cluster: eks, user: eks } }]
+            clusters: [{ name: eks, cluster: { server: "https://eks.example.com" } }]
+            users:
+              - name: eks
+                user:
+                  exec: { command: aws, args: [eks, get-token] }
+            "#,
+        )
+        .expect("Kubeconfig parses");
+
+        assert!(api_server(&config, Path::new("."), Duration::from_secs(5)).is_err());
+    }
+}
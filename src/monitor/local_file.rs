@@ -0,0 +1,149 @@
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::{DateTime, Utc};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// file://<absolute path>[?max_age_minutes=<n>], a file on the machine
+// upquack runs on, e.g. file:///var/backups/nightly.done?max_age_minutes=1500.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalFileQuery {
+    pub path: PathBuf,
+    pub max_age_minutes: Option<u64>,
+}
+
+impl LocalFileQuery {
+    pub fn parse(rest: &str) -> Option<LocalFileQuery> {
+        let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+        // file:///C:/backups/done on Windows.
+        let has_drive = |path: &str| path.get(1..3) == Some(":/");
+        let path = match path.strip_prefix('/') {
+            Some(windows) if has_drive(windows) => windows,
+            _ => path,
+        };
+        if !(path.starts_with('/') || has_drive(path)) || path.ends_with('/') {
+            return None;
+        }
+
+        let mut max_age_minutes = None;
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some(("max_age_minutes", minutes)) => {
+                    max_age_minutes = Some(minutes.parse().ok().filter(|m| *m > 0)?)
+                }
+                _ => return None,
+            }
+        }
+        Some(LocalFileQuery {
+            path: PathBuf::from(path),
+            max_age_minutes,
+        })
+    }
+}
+
+// Looks the file up. Up when it exists (and isn't older than allowed), Down
+// when it's missing or too old, and Error when it can't be looked at, e.g.
+// for lack of permissions.
+#[tracing::instrument(name = "local file check", skip(domain), fields(path = %query.path.display()))]
+pub async fn check(query: &LocalFileQuery, domain: &MonitoredDomain) -> CheckStatus {
+    let start_time = Utc::now();
+    let modified = tokio::fs::metadata(&query.path)
+        .await
+        .and_then(|metadata| metadata.modified());
+    let end_time = Utc::now();
+
+    let (status, error_message, detail) = judge(query, modified.map(DateTime::from), end_time);
+    if let DomainStatus::Error(e) = &status {
+        tracing::error!("Error checking {}: {}", domain.url, e);
+    }
+    CheckStatus {
+        response_time_ms: Some((end_time - start_time).num_milliseconds() as u64),
+        error_message: error_message.map(Arc::from),
+        detail: detail.map(Arc::from),
+        ..CheckStatus::new(end_time, status)
+    }
+}
+
+fn judge(
+    query: &LocalFileQuery,
+    modified: std::io::Result<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> (DomainStatus, Option<String>, Option<String>) {
+    let path = query.path.display();
+    let modified = match modified {
+        Ok(modified) => modified,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return (
+                DomainStatus::Down,
+                Some(format!("{} doesn't exist", path)),
+                None,
+            );
+        }
+        Err(e) => {
+            let message = format!("Can't look at {}: {}", path, e);
+            return (
+                DomainStatus::Error(message.clone().into()),
+                Some(message),
+                None,
+            );
+        }
+    };
+    let detail = Some(format!(
+        "modified {}",
+        modified.format("%Y-%m-%d %H:%M UTC")
+    ));
+    let age_minutes = (now - modified).num_minutes();
+    match query.max_age_minutes {
+        Some(max) if age_minutes > max as i64 => (
+            DomainStatus::Down,
+            Some(format!(
+                "{} was last modified {} minutes ago, more than {}",
+                path, age_minutes, max
+            )),
+            detail,
+        ),
+        _ => (DomainStatus::Up, None, detail),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[tokio::test]
+    async fn judges_existence_and_age() {
+        let marker = tempfile::NamedTempFile::new().unwrap();
+        let url = format!("file://{}?max_age_minutes=60", marker.path().display());
+        let query = LocalFileQuery::parse(url.strip_prefix("file://").unwrap()).unwrap();
+        assert_eq!(query.max_age_minutes, Some(60));
+        let status = check(&query, &MonitoredDomain::new(&url)).await;
+        assert_eq!(status.status, DomainStatus::Up);
+        assert!(status.detail.unwrap().starts_with("modified "));
+
+        let now = Utc::now();
+        let (status, message, _) = judge(&query, Ok(now - Duration::minutes(90)), now);
+        assert_eq!(status, DomainStatus::Down);
+        assert!(
+            message
+                .unwrap()
+                .ends_with("last modified 90 minutes ago, more than 60")
+        );
+
+        let missing = LocalFileQuery::parse("/var/backups/missing.done").unwrap();
+        let status = check(
+            &missing,
+            &MonitoredDomain::new("file:///var/backups/missing.done"),
+        )
+        .await;
+        assert_eq!(status.status, DomainStatus::Down);
+
+        assert_eq!(
+            LocalFileQuery::parse("/C:/backups/done").map(|q| q.path),
+            Some(PathBuf::from("C:/backups/done"))
+        );
+        assert!(LocalFileQuery::parse("backups/done").is_none());
+        assert!(LocalFileQuery::parse("/var/backups/").is_none());
+        assert!(LocalFileQuery::parse("/var/backups/done?max_age_minutes=0").is_none());
+    }
+}
@@ -0,0 +1,355 @@
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+
+// What the clock may be off by before the check turns Degraded, when the URL
+// doesn't say.
+const DEFAULT_MAX_OFFSET_MS: u64 = 100;
+
+// Seconds from the NTP epoch (1900) to the Unix one.
+const NTP_UNIX_OFFSET: i64 = 2_208_988_800;
+
+// The NTPv4 header, all a client request or server answer needs.
+const PACKET_LEN: usize = 48;
+
+// ntp://<host>[:port][?max_offset_ms=<n>], e.g. ntp://pool.ntp.org or
+// ntp://10.0.0.1?max_offset_ms=50.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NtpQuery {
+    pub host: String,
+    pub max_offset_ms: u64,
+}
+
+impl NtpQuery {
+    pub fn parse(rest: &str) -> Option<NtpQuery> {
+        let (host, query) = rest.split_once('?').unwrap_or((rest, ""));
+        if !is_ntp_host(host) {
+            return None;
+        }
+
+        let mut max_offset_ms = DEFAULT_MAX_OFFSET_MS;
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some(("max_offset_ms", value)) => max_offset_ms = value.parse().ok()?,
+                _ => return None,
+            }
+        }
+        Some(NtpQuery {
+            host: host.to_string(),
+            max_offset_ms,
+        })
+    }
+
+    // host:port for the socket, on port 123 unless the URL names one.
+    fn address(&self) -> String {
+        let host = self.host.as_str();
+        let has_port = if host.starts_with('[') {
+            !host.ends_with(']')
+        } else {
+            // One colon is a port, more make an IPv6 address.
+            host.matches(':').count() == 1
+        };
+        if has_port {
+            host.to_string()
+        } else if host.contains(':') && !host.starts_with('[') {
+            format!("[{}]:123", host)
+        } else {
+            format!("{}:123", host)
+        }
+    }
+}
+
+// Like ping:// hosts, plus a port and IPv6 addresses in brackets.
+fn is_ntp_host(host: &str) -> bool {
+    !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'))
+}
+
+// The parts of a server's answer a check looks at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NtpAnswer {
+    // 3 means the server's clock isn't synchronized.
+    pub leap_indicator: u8,
+    // 1 for servers with a reference clock, 0 for a kiss-o'-death.
+    pub stratum: u8,
+    // The kiss code when stratum is 0, e.g. DENY or RATE.
+    pub reference_id: [u8; 4],
+    // How far the local clock is behind the server's, negative when ahead.
+    pub offset: ChronoDuration,
+    pub round_trip: ChronoDuration,
+}
+
+// Asks the server for the time and records the offset and stratum. Up while
+// the offset is within the URL's max_offset_ms, Degraded when the clock
+// drifted past it, Down when the server doesn't answer or isn't
+// synchronized, and Error when it answers with something unusable.
+#[tracing::instrument(name = "ntp check", skip(domain), fields(host = %query.host))]
+pub async fn check(query: &NtpQuery, domain: &MonitoredDomain) -> CheckStatus {
+    let limit = domain.timeout();
+    // None when the server didn't answer in time.
+    let answer = tokio::time::timeout(limit, ask(query)).await.ok();
+    let end_time = Utc::now();
+
+    let (status, error_message, answer) = match answer {
+        Some(Ok(answer)) => {
+            let (status, error_message) = judge(query, &answer);
+            (status, error_message, Some(answer))
+        }
+        None => (
+            DomainStatus::Down,
+            Some(format!(
+                "{} didn't answer within {}s",
+                query.host,
+                limit.as_secs()
+            )),
+            None,
+        ),
+        Some(Err(e)) => {
+            tracing::error!("Error checking {}: {}", domain.url, e);
+            (DomainStatus::Error(e.clone().into()), Some(e), None)
+        }
+    };
+    CheckStatus {
+        response_time_ms: answer
+            .as_ref()
+            .map(|a| a.round_trip.num_milliseconds().max(0) as u64),
+        error_message: error_message.map(Arc::from),
+        detail: answer
+            .map(|a| format!("Offset {}, stratum {}", format_offset(a.offset), a.stratum).into()),
+        ..CheckStatus::new(end_time, status)
+    }
+}
+
+fn judge(query: &NtpQuery, answer: &NtpAnswer) -> (DomainStatus, Option<String>) {
+    if answer.stratum == 0 {
+        // A kiss-o'-death, the code says why.
+        let code = String::from_utf8_lossy(&answer.reference_id);
+        let message = format!(
+            "{} refused the request ({})",
+            query.host,
+            code.trim_end_matches('\0')
+        );
+        return (DomainStatus::Error(message.clone().into()), Some(message));
+    }
+    if answer.leap_indicator == 3 || answer.stratum >= 16 {
+        return (
+            DomainStatus::Down,
+            Some(format!("{} isn't synchronized", query.host)),
+        );
+    }
+    let max_offset = ChronoDuration::milliseconds(query.max_offset_ms as i64);
+    if answer.offset.abs() > max_offset {
+        return (
+            DomainStatus::Degraded,
+            Some(format!(
+                "Clock is off by {} (more than {} ms)",
+                format_offset(answer.offset),
+                query.max_offset_ms
+            )),
+        );
+    }
+    (DomainStatus::Up, None)
+}
+
+// Signed milliseconds with one decimal, e.g. +12.3 ms.
+fn format_offset(offset: ChronoDuration) -> String {
+    let micros = offset.num_microseconds().unwrap_or(i64::MAX);
+    format!("{:+.1} ms", micros as f64 / 1000.0)
+}
+
+// One client request, the usual SNTP exchange.
+async fn ask(query: &NtpQuery) -> Result<NtpAnswer, String> {
+    let address = tokio::net::lookup_host(query.address())
+        .await
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .ok_or_else(|| format!("Failed to resolve {}", query.host))?;
+    let local = if address.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(local).await.map_err(|e| e.to_string())?;
+    socket
+        .connect(address)
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", query.host, e))?;
+
+    let sent_at = Utc::now();
+    let transmit = to_ntp(sent_at);
+    socket
+        .send(&request(transmit))
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut buffer = [0; 512];
+    loop {
+        let len = socket.recv(&mut buffer).await.map_err(|e| e.to_string())?;
+        let received_at = Utc::now();
+        // Late answers to earlier requests don't echo our timestamp.
+        if let Some(answer) = parse_answer(&buffer[..len], transmit, sent_at, received_at)? {
+            return Ok(answer);
+        }
+    }
+}
+
+fn request(transmit: u64) -> [u8; PACKET_LEN] {
+    let mut packet = [0; PACKET_LEN];
+    // No leap warning, version 4, client mode.
+    packet[0] = 4 << 3 | 3;
+    packet[40..48].copy_from_slice(&transmit.to_be_bytes());
+    packet
+}
+
+fn parse_answer(
+    packet: &[u8],
+    transmit: u64,
+    sent_at: DateTime<Utc>,
+    received_at: DateTime<Utc>,
+) -> Result<Option<NtpAnswer>, String> {
+    if packet.len() < PACKET_LEN || packet[0] & 0x07 != 4 {
+        return Err("Invalid NTP response".to_string());
+    }
+    let timestamp = |at: usize| u64::from_be_bytes(packet[at..at + 8].try_into().unwrap());
+    if timestamp(24) != transmit {
+        return Ok(None);
+    }
+    let server_received = from_ntp(timestamp(32));
+    let server_sent = from_ntp(timestamp(40));
+    Ok(Some(NtpAnswer {
+        leap_indicator: packet[0] >> 6,
+        stratum: packet[1],
+        reference_id: packet[12..16].try_into().unwrap(),
+        offset: ((server_received - sent_at) + (server_sent - received_at)) / 2,
+        round_trip: (received_at - sent_at) - (server_sent - server_received),
+    }))
+}
+
+// 32 bits of seconds since 1900 and 32 of fraction.
+fn to_ntp(at: DateTime<Utc>) -> u64 {
+    let seconds = (at.timestamp() + NTP_UNIX_OFFSET) as u64 & 0xffff_ffff;
+    let fraction = ((at.timestamp_subsec_nanos() as u64) << 32) / 1_000_000_000;
+    seconds << 32 | fraction
+}
+
+fn from_ntp(timestamp: u64) -> DateTime<Utc> {
+    let mut seconds = (timestamp >> 32) as i64;
+    // Timestamps wrap in 2036, small ones are from the era after.
+    if seconds < 0x8000_0000 {
+        seconds += 1 << 32;
+    }
+    let nanos = ((timestamp & 0xffff_ffff) * 1_000_000_000) >> 32;
+    DateTime::from_timestamp(seconds - NTP_UNIX_OFFSET, nanos as u32).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A server whose clock is 250 ms ahead of ours.
+    async fn fast_server() -> String {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = socket.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let mut request = [0; PACKET_LEN];
+            let (_, client) = socket.recv_from(&mut request).await.unwrap();
+            let now = to_ntp(Utc::now() + ChronoDuration::milliseconds(250));
+            let mut answer = [0; PACKET_LEN];
+            answer[0] = 4 << 3 | 4;
+            answer[1] = 2;
+            answer[24..32].copy_from_slice(&request[40..48]);
+            answer[32..40].copy_from_slice(&now.to_be_bytes());
+            answer[40..48].copy_from_slice(&now.to_be_bytes());
+            socket.send_to(&answer, client).await.unwrap();
+        });
+        address
+    }
+
+    #[tokio::test]
+    async fn reports_offset_and_stratum() {
+        let at = DateTime::from_timestamp(1_700_000_000, 500_000_000).unwrap();
+        assert_eq!(from_ntp(to_ntp(at)), at);
+
+        let host = fast_server().await;
+        let query = NtpQuery::parse(&format!("{}?max_offset_ms=100", host)).unwrap();
+        let domain = MonitoredDomain::new(&format!("ntp://{}", host));
+        let status = check(&query, &domain).await;
+        assert_eq!(status.status, DomainStatus::Degraded);
+        assert!(status.detail.unwrap().ends_with("stratum 2"));
+        assert!(
+            status
+                .error_message
+                .unwrap()
+                .starts_with("Clock is off by +2")
+        );
+
+        let synchronized = NtpAnswer {
+            leap_indicator: 0,
+            stratum: 1,
+            reference_id: *b"GPS\0",
+            offset: ChronoDuration::milliseconds(-3),
+            round_trip: ChronoDuration::milliseconds(20),
+        };
+        let pool = NtpQuery::parse("pool.ntp.org").unwrap();
+        assert_eq!(pool.address(), "pool.ntp.org:123");
+        assert_eq!(judge(&pool, &synchronized), (DomainStatus::Up, None));
+        let alarm = NtpAnswer {
+            leap_indicator: 3,
+            ..synchronized.clone()
+        };
+        assert_eq!(judge(&pool, &alarm).0, DomainStatus::Down);
+        let kiss = NtpAnswer {
+            stratum: 0,
+            reference_id: *b"RATE",
+            ..synchronized
+        };
+        assert_eq!(
+            judge(&pool, &kiss).1.as_deref(),
+            Some("pool.ntp.org refused the request (RATE)")
+        );
+
+        assert!(NtpQuery::parse("").is_none());
+        assert!(NtpQuery::parse("time.lan?max_offset_ms=soon").is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_answers() {
+        let sent_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let transmit = to_ntp(sent_at);
+        let mut answer = [0; PACKET_LEN];
+        answer[0] = 4 << 3 | 4;
+        answer[24..32].copy_from_slice(&transmit.to_be_bytes());
+        let parse = |packet: &[u8]| parse_answer(packet, transmit, sent_at, sent_at);
+
+        assert!(parse(&answer).unwrap().is_some());
+        assert_eq!(
+            parse(&answer[..PACKET_LEN - 1]),
+            Err("Invalid NTP response".to_string())
+        );
+        assert!(parse(&[]).is_err());
+        // Our own request reflected back is in client mode.
+        assert!(parse(&request(transmit)).is_err());
+        // An answer to someone else's request is skipped.
+        answer[24..32].copy_from_slice(&(transmit + 1).to_be_bytes());
+        assert_eq!(parse(&answer), Ok(None));
+    }
+
+    #[tokio::test]
+    async fn a_silent_server_is_down() {
+        // Bound, so the request isn't refused, but never answered.
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let host = socket.local_addr().unwrap().to_string();
+        let query = NtpQuery::parse(&host).unwrap();
+        let mut domain = MonitoredDomain::new(&format!("ntp://{}", host));
+        domain.timeout_seconds = Some(1);
+        let status = check(&query, &domain).await;
+        assert_eq!(status.status, DomainStatus::Down);
+        assert_eq!(
+            status.error_message.as_deref(),
+            Some(format!("{} didn't answer within 1s", query.host).as_str())
+        );
+        drop(socket);
+    }
+}
@@ -0,0 +1,165 @@
+use super::target::host_and_port;
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::Utc;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::process::Command;
+
+// Echo requests sent per check, ping's usual second apart.
+const PING_COUNT: u32 = 3;
+
+// What ping reported about the replies.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PingSummary {
+    pub transmitted: u32,
+    pub received: u32,
+    pub loss_percent: f64,
+    // The average round trip, unset without replies.
+    pub average_rtt_ms: Option<f64>,
+}
+
+// Host names and IPv4/IPv6 addresses, without a port or brackets, which
+// ping doesn't take.
+pub fn is_ping_host(host: &str) -> bool {
+    host_and_port(host, 0).is_some_and(|(name, port)| port == 0 && !name.starts_with('['))
+}
+
+// Pings the host with the system's ping, which doesn't need the raw socket
+// privileges sending ICMP ourselves would. Up while any request is answered,
+// with the loss and average round trip recorded; Down when none are.
+#[tracing::instrument(name = "ping check", skip(domain))]
+pub async fn check(host: &str, domain: &MonitoredDomain) -> CheckStatus {
+    let limit = domain.timeout();
+    let output = Command::new("ping")
+        .args(["-n", "-c", &PING_COUNT.to_string(), host])
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let result = match tokio::time::timeout(limit, output).await {
+        Ok(Ok(output)) => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            parse_summary(&stdout).ok_or_else(|| {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                match stderr.trim() {
+                    "" => "ping gave no summary".to_string(),
+                    stderr => stderr.to_string(),
+                }
+            })
+        }
+        Ok(Err(e)) => Err(format!("Failed to run ping: {}", e)),
+        Err(_) => Err("ping didn't finish in time".to_string()),
+    };
+    let timestamp = Utc::now();
+
+    match result {
+        Ok(summary) => {
+            let error_message: Option<Arc<str>> =
+                (summary.received == 0).then(|| "No echo replies".into());
+            CheckStatus {
+                response_time_ms: summary.average_rtt_ms.map(|ms| ms.round() as u64),
+                error_message,
+                detail: Some(
+                    format!("{}/{} replies", summary.received, summary.transmitted).into(),
+                ),
+                packet_loss_percent: Some(summary.loss_percent),
+                ..CheckStatus::new(
+                    timestamp,
+                    if summary.received > 0 {
+                        DomainStatus::Up
+                    } else {
+                        DomainStatus::Down
+                    },
+                )
+            }
+        }
+        Err(e) => {
+            let err_msg: Arc<str> = e.into();
+            tracing::error!("Error checking {}: {}", domain.url, err_msg);
+            CheckStatus::failed(timestamp, err_msg)
+        }
+    }
+}
+
+// The statistics at the end of ping's output, in the wording of iputils,
+// BSD/macOS and busybox alike:
+//   4 packets transmitted, 3 received, 25% packet loss, time 3004ms
+//   rtt min/avg/max/mdev = 0.031/0.041/0.054/0.008 ms
+pub fn parse_summary(output: &str) -> Option<PingSummary> {
+    let counts = output.lines().find(|l| l.contains("packets transmitted"))?;
+    let mut numbers = counts
+        .split(',')
+        .map(|part| part.split_whitespace().next().unwrap_or_default());
+    let transmitted = numbers.next()?.parse().ok()?;
+    let received = numbers.next()?.parse().ok()?;
+    let loss_percent = counts
+        .split(',')
+        .find(|part| part.contains("packet loss"))?
+        .split_whitespace()
+        .next()?
+        .trim_end_matches('%')
+        .parse()
+        .ok()?;
+
+    // min/avg/max(/mdev) = a/b/c(/d) ms
+    let average_rtt_ms = output
+        .lines()
+        .find(|l| l.contains("min/avg/max"))
+        .and_then(|l| l.split('=').nth(1))
+        .and_then(|values| values.trim().split('/').nth(1))
+        .and_then(|avg| avg.parse().ok());
+
+    Some(PingSummary {
+        transmitted,
+        received,
+        loss_percent,
+        average_rtt_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ping_statistics() {
+        let iputils = "\
+PING example.com (93.184.216.34) 56(84) bytes of data.
+64 bytes from 93.184.216.34: icmp_seq=1 ttl=56 time=11.2 ms
+
+--- example.com ping statistics ---
+4 packets transmitted, 3 received, 25% packet loss, time 603ms
+rtt min/avg/max/mdev = 11.032/11.461/12.054/0.408 ms
+";
+        assert_eq!(
+            parse_summary(iputils),
+            Some(PingSummary {
+                transmitted: 4,
+                received: 3,
+                loss_percent: 25.0,
+                average_rtt_ms: Some(11.461),
+            })
+        );
+
+        let macos = "\
+--- 10.0.0.1 ping statistics ---
+4 packets transmitted, 0 packets received, 100.0% packet loss
+";
+        assert_eq!(
+            parse_summary(macos),
+            Some(PingSummary {
+                transmitted: 4,
+                received: 0,
+                loss_percent: 100.0,
+                average_rtt_ms: None,
+            })
+        );
+        assert_eq!(parse_summary("ping: unknown host nowhere"), None);
+
+        assert!(is_ping_host("10.0.0.1"));
+        assert!(is_ping_host("fd00::1"));
+        assert!(is_ping_host("router.lan"));
+        assert!(!is_ping_host("-f"));
+        assert!(!is_ping_host("a b"));
+        assert!(!is_ping_host("router.lan:80"));
+    }
+}
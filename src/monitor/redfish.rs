@@ -0,0 +1,191 @@
+use super::target::host_and_port;
+use crate::configuration::BmcSettings;
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::Utc;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+// The collection of computer systems a BMC manages, usually just one.
+#[derive(Debug, Deserialize)]
+struct SystemCollection {
+    #[serde(rename = "Members", default)]
+    members: Vec<Link>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Link {
+    #[serde(rename = "@odata.id")]
+    id: String,
+}
+
+// The parts of a ComputerSystem resource a check looks at.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ComputerSystem {
+    // "On", "Off", "PoweringOn" or "PoweringOff".
+    power_state: Option<String>,
+    #[serde(default)]
+    status: ResourceStatus,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ResourceStatus {
+    // "OK", "Warning" or "Critical".
+    health: Option<String>,
+}
+
+// A BMC host, optionally with a port.
+pub fn is_bmc_host(host: &str) -> bool {
+    host_and_port(host, 443).is_some()
+}
+
+// Asks the BMC about the system's power and health over Redfish. Up while
+// the system is on and its health isn't critical, Down otherwise. Without
+// a system id the BMC's first system is checked.
+#[tracing::instrument(name = "redfish check", skip(bmcs, domain))]
+pub async fn check(
+    host: &str,
+    system: Option<&str>,
+    bmcs: &[BmcSettings],
+    domain: &MonitoredDomain,
+) -> CheckStatus {
+    let limit = domain.timeout();
+    let bmc = bmcs.iter().find(|b| b.host == host);
+    let start_time = Utc::now();
+    let system = query_system(host, system, bmc, limit).await;
+    let end_time = Utc::now();
+
+    match system {
+        Ok(system) => {
+            let (status, error_message, detail) = judge(&system);
+            CheckStatus {
+                response_time_ms: Some((end_time - start_time).num_milliseconds() as u64),
+                error_message: error_message.map(Arc::from),
+                detail: Some(detail.into()),
+                ..CheckStatus::new(end_time, status)
+            }
+        }
+        Err(e) => {
+            let err_msg: Arc<str> = e.into();
+            tracing::error!("Error checking {}: {}", domain.url, err_msg);
+            CheckStatus::failed(end_time, err_msg)
+        }
+    }
+}
+
+async fn query_system(
+    host: &str,
+    system: Option<&str>,
+    bmc: Option<&BmcSettings>,
+    limit: Duration,
+) -> Result<ComputerSystem, String> {
+    let client = Client::builder()
+        .timeout(limit)
+        .danger_accept_invalid_certs(bmc.is_some_and(|b| b.accept_invalid_certificates))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let base = format!("https://{}", host);
+    let get = |path: String| {
+        let request = client.get(format!("{}{}", base, path));
+        match bmc {
+            Some(bmc) => request.basic_auth(&bmc.username, Some(&bmc.password)),
+            None => request,
+        }
+    };
+
+    let path = match system {
+        Some(system) => format!("/redfish/v1/Systems/{}", system),
+        None => {
+            let systems: SystemCollection = fetch(get("/redfish/v1/Systems".to_string()), host)
+                .await?
+                .json()
+                .await
+                .map_err(|e| format!("Invalid systems collection: {}", e))?;
+            systems
+                .members
+                .into_iter()
+                .next()
+                .map(|member| member.id)
+                .ok_or("The BMC lists no systems")?
+        }
+    };
+    fetch(get(path), host)
+        .await?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid system resource: {}", e))
+}
+
+async fn fetch(request: reqwest::RequestBuilder, host: &str) -> Result<reqwest::Response, String> {
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    match response.status() {
+        StatusCode::UNAUTHORIZED => Err(format!(
+            "The BMC refused the login, check the [[bmc]] entry for {}",
+            host
+        )),
+        status if !status.is_success() => Err(format!("The BMC answered {}", status)),
+        _ => Ok(response),
+    }
+}
+
+fn judge(system: &ComputerSystem) -> (DomainStatus, Option<String>, String) {
+    let power = system.power_state.as_deref().unwrap_or("Unknown");
+    let health = system.status.health.as_deref().unwrap_or("Unknown");
+    let detail = format!("Power {}, health {}", power, health);
+    if power != "On" {
+        return (
+            DomainStatus::Down,
+            Some(format!("System is powered {}", power.to_lowercase())),
+            detail,
+        );
+    }
+    if health == "Critical" {
+        return (
+            DomainStatus::Down,
+            Some("System health is critical".to_string()),
+            detail,
+        );
+    }
+    (DomainStatus::Up, None, detail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn judges_power_and_health() {
+        let system = |json: &str| -> ComputerSystem { serde_json::from_str(json).unwrap() };
+
+        let (status, error, detail) = judge(&system(
+            r#"{"@odata.id": "/redfish/v1/Systems/1", "PowerState": "On", "Status": {"State": "Enabled", "Health": "Warning"}}"#,
+        ));
+        assert_eq!(status, DomainStatus::Up);
+        assert_eq!(error, None);
+        assert_eq!(detail, "Power On, health Warning");
+
+        let (status, error, _) = judge(&system(r#"{"PowerState": "Off"}"#));
+        assert_eq!(status, DomainStatus::Down);
+        assert_eq!(error.as_deref(), Some("System is powered off"));
+
+        let (status, _, _) = judge(&system(
+            r#"{"PowerState": "On", "Status": {"Health": "Critical"}}"#,
+        ));
+        assert_eq!(status, DomainStatus::Down);
+
+        let systems: SystemCollection = serde_json::from_str(
+            r#"{"Members": [{"@odata.id": "/redfish/v1/Systems/System.Embedded.1"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            systems.members[0].id,
+            "/redfish/v1/Systems/System.Embedded.1"
+        );
+
+        assert!(is_bmc_host("10.0.0.50:8443"));
+        assert!(!is_bmc_host("bmc/../x"));
+    }
+}
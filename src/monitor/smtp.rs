@@ -0,0 +1,377 @@
+use super::response_time;
+use super::target::host_and_port;
+use super::timing::tls_connector;
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::Utc;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+
+// What the check announces itself as when the URL doesn't say.
+const DEFAULT_EHLO: &str = "localhost";
+
+// smtp://<host>[:port][?ehlo=<name>&starttls=true] on port 25 unless the URL
+// names one (587 for submission), smtps://<host>[:port] for implicit TLS on
+// 465, e.g. smtp://mx.example.com?starttls=true.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmtpQuery {
+    pub host: String,
+    pub port: u16,
+    // Implicit TLS, smtps://.
+    pub tls: bool,
+    // Greets the server with EHLO and this name after the banner.
+    pub ehlo: Option<String>,
+    // Upgrades the connection with STARTTLS, greeting the server first.
+    pub starttls: bool,
+}
+
+impl SmtpQuery {
+    pub fn parse(rest: &str, tls: bool) -> Option<SmtpQuery> {
+        let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (host, port) = host_and_port(address, if tls { 465 } else { 25 })?;
+
+        let mut ehlo = None;
+        let mut starttls = false;
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some(("ehlo", name)) if !name.is_empty() && !name.contains(' ') => {
+                    ehlo = Some(name.to_string())
+                }
+                // STARTTLS on a connection that's TLS already makes no sense.
+                Some(("starttls", value)) if !tls => starttls = value.parse().ok()?,
+                _ => return None,
+            }
+        }
+        Some(SmtpQuery {
+            host,
+            port,
+            tls,
+            ehlo,
+            starttls,
+        })
+    }
+
+    // What EHLO announces, when the check greets at all.
+    fn greeting(&self) -> Option<&str> {
+        match &self.ehlo {
+            Some(name) => Some(name),
+            None if self.starttls => Some(DEFAULT_EHLO),
+            None => None,
+        }
+    }
+}
+
+// Why a session ended early, with the status it leaves the monitor in.
+type Failure = (DomainStatus, String);
+
+// Connects, reads the banner and, as the URL asks, greets the server and
+// upgrades to TLS, then says QUIT. Up when the server accepts all of it,
+// Down when it can't be reached, doesn't answer in time or refuses (a 4xx or
+// 5xx banner, no STARTTLS on offer, a failed handshake), and Error when what
+// it says isn't SMTP.
+#[tracing::instrument(name = "smtp check", skip(domain), fields(host = %query.host, port = query.port))]
+pub async fn check(query: &SmtpQuery, domain: &MonitoredDomain) -> CheckStatus {
+    let limit = domain.timeout();
+    let start_time = Utc::now();
+    let session = tokio::time::timeout(limit, session(query))
+        .await
+        .unwrap_or_else(|_| {
+            Err((
+                DomainStatus::Down,
+                format!(
+                    "{}:{} didn't finish the session within {}s",
+                    query.host,
+                    query.port,
+                    limit.as_secs()
+                ),
+            ))
+        });
+    let end_time = Utc::now();
+
+    let (status, error_message, detail) = match session {
+        Ok(detail) => (DomainStatus::Up, None, Some(detail)),
+        Err((status, message)) => {
+            if let DomainStatus::Error(_) = status {
+                tracing::error!("Error checking {}: {}", domain.url, message);
+            }
+            (status, Some(message), None)
+        }
+    };
+    let response_time_ms = response_time(status == DomainStatus::Up, start_time, end_time);
+    CheckStatus {
+        response_time_ms,
+        error_message: error_message.map(Arc::from),
+        detail: detail.map(Arc::from),
+        ..CheckStatus::new(end_time, status)
+    }
+}
+
+// The Result column text, the banner and how far the session went, e.g.
+// "mx.example.com ESMTP Postfix (STARTTLS)".
+async fn session(query: &SmtpQuery) -> Result<String, Failure> {
+    let down = |message: String| (DomainStatus::Down, message);
+    let stream = TcpStream::connect((query.host.trim_matches(['[', ']']), query.port))
+        .await
+        .map_err(|e| {
+            down(format!(
+                "Failed to connect to {}:{}: {}",
+                query.host, query.port, e
+            ))
+        })?;
+    if query.tls {
+        let stream = handshake(query, stream).await?;
+        return implicit_tls(query, BufReader::new(stream)).await;
+    }
+    let mut stream = BufReader::new(stream);
+    let banner = expect(&mut stream, "banner", 220).await?;
+    let Some(name) = query.greeting() else {
+        quit(&mut stream).await;
+        return Ok(banner);
+    };
+    let extensions = ehlo(&mut stream, name).await?;
+    if !query.starttls {
+        quit(&mut stream).await;
+        return Ok(banner);
+    }
+    if !extensions
+        .iter()
+        .any(|e| e.eq_ignore_ascii_case("STARTTLS"))
+    {
+        return Err(down(format!("{} doesn't offer STARTTLS", query.host)));
+    }
+    command(&mut stream, "STARTTLS").await?;
+    expect(&mut stream, "STARTTLS", 220).await?;
+    let stream = handshake(query, stream.into_inner()).await?;
+    // The server forgets the greeting with the upgrade.
+    let mut stream = BufReader::new(stream);
+    ehlo(&mut stream, name).await?;
+    quit(&mut stream).await;
+    Ok(format!("{} (STARTTLS)", banner))
+}
+
+// The session over implicit TLS, banner first like over plaintext.
+async fn implicit_tls<S: AsyncRead + AsyncWrite + Unpin>(
+    query: &SmtpQuery,
+    mut stream: BufReader<S>,
+) -> Result<String, Failure> {
+    let banner = expect(&mut stream, "banner", 220).await?;
+    if let Some(name) = query.greeting() {
+        ehlo(&mut stream, name).await?;
+    }
+    quit(&mut stream).await;
+    Ok(format!("{} (TLS)", banner))
+}
+
+async fn handshake(
+    query: &SmtpQuery,
+    stream: TcpStream,
+) -> Result<tokio_rustls::client::TlsStream<TcpStream>, Failure> {
+    let error = |message: String| (DomainStatus::Error(message.clone().into()), message);
+    let server_name = ServerName::try_from(query.host.trim_matches(['[', ']']).to_string())
+        .map_err(|e| error(e.to_string()))?;
+    tls_connector()
+        .map_err(error)?
+        .connect(server_name, stream)
+        .await
+        .map_err(|e| {
+            (
+                DomainStatus::Down,
+                format!("TLS with {} failed: {}", query.host, e),
+            )
+        })
+}
+
+// EHLO, returning the extensions the server lists, e.g. STARTTLS or SIZE.
+async fn ehlo<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut BufReader<S>,
+    name: &str,
+) -> Result<Vec<String>, Failure> {
+    command(stream, &format!("EHLO {}", name)).await?;
+    let (code, lines) = reply(stream).await?;
+    if code != 250 {
+        return Err(refused("EHLO", code, &lines));
+    }
+    // The first line is the server's greeting, the rest one extension each.
+    Ok(lines
+        .iter()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect())
+}
+
+async fn command<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut BufReader<S>,
+    command: &str,
+) -> Result<(), Failure> {
+    stream
+        .get_mut()
+        .write_all(format!("{}\r\n", command).as_bytes())
+        .await
+        .map_err(|e| (DomainStatus::Down, format!("Connection lost: {}", e)))
+}
+
+// Leaves politely, a server that hangs up first is fine too.
+async fn quit<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut BufReader<S>) {
+    if command(stream, "QUIT").await.is_ok() {
+        let _ = reply(stream).await;
+    }
+}
+
+// The reply's text when it has the expected code, what was refused otherwise.
+async fn expect<S: AsyncRead + Unpin>(
+    stream: &mut BufReader<S>,
+    what: &str,
+    expected: u16,
+) -> Result<String, Failure> {
+    let (code, lines) = reply(stream).await?;
+    if code != expected {
+        return Err(refused(what, code, &lines));
+    }
+    Ok(lines.join(" "))
+}
+
+fn refused(what: &str, code: u16, lines: &[String]) -> Failure {
+    (
+        DomainStatus::Down,
+        format!("{} refused with {} {}", what, code, lines.join(" ")),
+    )
+}
+
+// One reply, e.g. "250-mx.example.com" and "250 STARTTLS", as its code and
+// the text of its lines.
+async fn reply<S: AsyncRead + Unpin>(
+    stream: &mut BufReader<S>,
+) -> Result<(u16, Vec<String>), Failure> {
+    let not_smtp = |line: &str| {
+        let message = format!("Not an SMTP reply: {:?}", line);
+        (DomainStatus::Error(message.clone().into()), message)
+    };
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        let read = stream
+            .read_line(&mut line)
+            .await
+            .map_err(|e| (DomainStatus::Down, format!("Connection lost: {}", e)))?;
+        if read == 0 {
+            return Err((DomainStatus::Down, "Connection closed".to_string()));
+        }
+        let line = line.trim_end();
+        let code = line
+            .get(..3)
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or_else(|| not_smtp(line))?;
+        let text = line.get(4..).unwrap_or_default().to_string();
+        lines.push(text);
+        match line.as_bytes().get(3) {
+            Some(b'-') => continue,
+            None | Some(b' ') => return Ok((code, lines)),
+            _ => return Err(not_smtp(line)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    // Answers with the banner, then 250 to EHLO with the extensions and 221
+    // to QUIT.
+    async fn mail_server(banner: &'static str, extensions: &'static str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                stream.write_all(banner.as_bytes()).await.unwrap();
+                let mut command = vec![0; 512];
+                while let Ok(read) = stream.read(&mut command).await {
+                    let answer = match &command[..read] {
+                        [] => break,
+                        [b'E', b'H', b'L', b'O', ..] => extensions,
+                        _ => "221 Bye\r\n",
+                    };
+                    if stream.write_all(answer.as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        address
+    }
+
+    #[tokio::test]
+    async fn talks_smtp() {
+        let address = mail_server(
+            "220 mx.example.com ESMTP\r\n",
+            "250-mx.example.com\r\n250-SIZE 10240000\r\n250 8BITMIME\r\n",
+        )
+        .await;
+        let query = SmtpQuery::parse(&format!("{}?ehlo=monitor.lan", address), false).unwrap();
+        let domain = MonitoredDomain::new(&format!("smtp://{}", address));
+        let status = check(&query, &domain).await;
+        assert_eq!(status.status, DomainStatus::Up);
+        assert_eq!(status.detail.as_deref(), Some("mx.example.com ESMTP"));
+
+        let query = SmtpQuery::parse(&format!("{}?starttls=true", address), false).unwrap();
+        let status = check(&query, &domain).await;
+        assert_eq!(status.status, DomainStatus::Down);
+        assert_eq!(
+            status.error_message.as_deref(),
+            Some("127.0.0.1 doesn't offer STARTTLS")
+        );
+
+        let busy = mail_server("554 5.3.2 Too busy\r\n", "").await;
+        let query = SmtpQuery::parse(&busy, false).unwrap();
+        let status = check(&query, &domain).await;
+        assert_eq!(status.status, DomainStatus::Down);
+        assert_eq!(
+            status.error_message.as_deref(),
+            Some("banner refused with 554 5.3.2 Too busy")
+        );
+
+        let submission = SmtpQuery::parse("mail.example.com:587?starttls=true", false).unwrap();
+        assert_eq!(submission.port, 587);
+        assert_eq!(submission.greeting(), Some("localhost"));
+        assert_eq!(
+            SmtpQuery::parse("mail.example.com", true).unwrap().port,
+            465
+        );
+        assert!(SmtpQuery::parse("mail.example.com?starttls=true", true).is_none());
+        assert!(SmtpQuery::parse("mail.example.com:0", false).is_none());
+        assert!(SmtpQuery::parse("", false).is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_replies() {
+        async fn read(input: &str) -> Result<(u16, Vec<String>), Failure> {
+            reply(&mut BufReader::new(input.as_bytes())).await
+        }
+        let not_smtp = |line: &str| {
+            let message = format!("Not an SMTP reply: {:?}", line);
+            Err((DomainStatus::Error(message.clone().into()), message))
+        };
+
+        assert_eq!(
+            read("250-mx.example.com\r\n250 8BITMIME\r\n").await,
+            Ok((
+                250,
+                vec!["mx.example.com".to_string(), "8BITMIME".to_string()]
+            ))
+        );
+        assert_eq!(read("220\r\n").await, Ok((220, vec![String::new()])));
+        assert_eq!(
+            read("HTTP/1.1 400 Bad Request\r\n").await,
+            not_smtp("HTTP/1.1 400 Bad Request")
+        );
+        assert_eq!(read("22\r\n").await, not_smtp("22"));
+        assert_eq!(read("250*mx\r\n").await, not_smtp("250*mx"));
+        // Cut off in the middle of a multiline reply.
+        let closed = Err((DomainStatus::Down, "Connection closed".to_string()));
+        assert_eq!(read("250-mx.example.com\r\n").await, closed);
+        assert_eq!(read("").await, closed);
+    }
+}
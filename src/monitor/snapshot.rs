@@ -0,0 +1,184 @@
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::{DateTime, Utc};
+use std::{collections::HashMap, sync::Arc};
+use uuid::Uuid;
+
+// What screens draw from: every domain's latest check and stats and when
+// each is checked next. The monitor publishes a new one on every change, so
+// rendering never locks (or copies out of) the shared domain list.
+#[derive(Debug, Clone, Default)]
+pub struct MonitorSnapshot {
+    // Bumped on every change, so readers know when to redo what they derive.
+    pub version: u64,
+    // In the monitor's order.
+    pub domains: Vec<Arc<DomainSnapshot>>,
+    // When each running task checks its domain next.
+    pub next_checks: HashMap<Uuid, DateTime<Utc>>,
+}
+
+impl MonitorSnapshot {
+    pub fn from_domains(domains: &[MonitoredDomain]) -> Self {
+        MonitorSnapshot {
+            version: 0,
+            domains: domains
+                .iter()
+                .map(|d| Arc::new(DomainSnapshot::new(d)))
+                .collect(),
+            next_checks: HashMap::new(),
+        }
+    }
+
+    // Next check times of every monitored domain, soonest first. Checks that
+    // are overdue (e.g. queued by the request budget) come first.
+    pub fn upcoming_checks(&self) -> Vec<DateTime<Utc>> {
+        let mut upcoming: Vec<_> = self.next_checks.values().copied().collect();
+        upcoming.sort();
+        upcoming
+    }
+
+    pub fn upsert(&mut self, domain: &MonitoredDomain) {
+        let updated = Arc::new(DomainSnapshot::new(domain));
+        match self.domains.iter_mut().find(|d| d.id == domain.id) {
+            Some(d) => *d = updated,
+            None => self.domains.push(updated),
+        }
+        self.version += 1;
+    }
+
+    pub fn remove(&mut self, domain_id: Uuid) {
+        self.domains.retain(|d| d.id != domain_id);
+        self.next_checks.remove(&domain_id);
+        self.version += 1;
+    }
+
+    // For when changes were missed, keeps the schedule.
+    pub fn replace_domains(&mut self, domains: &[MonitoredDomain]) {
+        let next_checks = std::mem::take(&mut self.next_checks);
+        *self = MonitorSnapshot {
+            version: self.version + 1,
+            next_checks,
+            ..MonitorSnapshot::from_domains(domains)
+        };
+    }
+
+    pub fn set_next_check(&mut self, domain_id: Uuid, at: Option<DateTime<Utc>>) {
+        match at {
+            Some(at) => self.next_checks.insert(domain_id, at),
+            None => self.next_checks.remove(&domain_id),
+        };
+        self.version += 1;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DomainSnapshot {
+    pub id: Uuid,
+    pub url: Arc<str>,
+    pub display_name: Option<String>,
+    pub owner: Option<String>,
+    pub environment: Option<String>,
+    pub interval_seconds: u64,
+    pub muted_until: Option<DateTime<Utc>>,
+    pub last_check: Option<CheckStatus>,
+    // When the incident still going on started.
+    pub failing_since: Option<DateTime<Utc>>,
+    // The stored checks by how they count towards uptime.
+    up_checks: usize,
+    failed_checks: usize,
+    rate_limited_checks: usize,
+}
+
+impl DomainSnapshot {
+    pub fn new(domain: &MonitoredDomain) -> Self {
+        let (mut up_checks, mut failed_checks, mut rate_limited_checks) = (0, 0, 0);
+        for check in &domain.check_history {
+            match check.status {
+                DomainStatus::Up | DomainStatus::Degraded => up_checks += 1,
+                DomainStatus::Down | DomainStatus::Error(_) => failed_checks += 1,
+                DomainStatus::RateLimited => rate_limited_checks += 1,
+                DomainStatus::Unknown => {}
+            }
+        }
+
+        DomainSnapshot {
+            id: domain.id,
+            url: domain.url.clone(),
+            display_name: domain.display_name.clone(),
+            owner: domain.owner.clone(),
+            environment: domain.environment.clone(),
+            interval_seconds: domain.interval_seconds,
+            muted_until: domain.muted_until,
+            last_check: domain.check_history.last().cloned(),
+            failing_since: domain
+                .incidents()
+                .last()
+                .filter(|i| !i.is_closed())
+                .map(|i| i.started_at),
+            up_checks,
+            failed_checks,
+            rate_limited_checks,
+        }
+    }
+
+    // The same as MonitoredDomain::uptime_percentage.
+    pub fn uptime_percentage(&self, count_rate_limited_as_down: bool) -> Option<f64> {
+        let mut total = self.up_checks + self.failed_checks;
+        if count_rate_limited_as_down {
+            total += self.rate_limited_checks;
+        }
+        (total > 0).then(|| self.up_checks as f64 / total as f64 * 100.0)
+    }
+
+    // The same as MonitoredDomain::name.
+    pub fn name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.url)
+    }
+
+    pub fn is_muted(&self, now: DateTime<Utc>) -> bool {
+        self.muted_until.is_some_and(|until| until > now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn domain_snapshots_match_the_domain() {
+        let now = Utc::now();
+        let mut domain = MonitoredDomain::new("https://example.com");
+        let statuses = [
+            DomainStatus::Up,
+            DomainStatus::RateLimited,
+            DomainStatus::Unknown,
+            DomainStatus::Up,
+            DomainStatus::Down,
+            DomainStatus::Error("timeout".into()),
+        ];
+        domain.check_history = statuses
+            .iter()
+            .enumerate()
+            .map(|(i, status)| {
+                CheckStatus::new(now - Duration::minutes(10 - i as i64), status.clone())
+            })
+            .collect();
+
+        let snapshot = DomainSnapshot::new(&domain);
+
+        for count_rate_limited_as_down in [false, true] {
+            assert_eq!(
+                snapshot.uptime_percentage(count_rate_limited_as_down),
+                domain.uptime_percentage(count_rate_limited_as_down)
+            );
+        }
+        assert_eq!(
+            snapshot.failing_since,
+            Some(domain.check_history[4].timestamp)
+        );
+        assert_eq!(
+            snapshot.last_check.map(|c| c.timestamp),
+            domain.check_history.last().map(|c| c.timestamp)
+        );
+    }
+}
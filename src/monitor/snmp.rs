@@ -0,0 +1,1174 @@
+use super::target::host_and_port;
+use crate::configuration::{SnmpAuthProtocol, SnmpSettings};
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use aes::Aes128;
+use aes::cipher::{BlockEncrypt, KeyInit, generic_array::GenericArray};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use tokio::net::UdpSocket;
+
+// SNMPv2-MIB::sysUpTime.0, what snmp:// monitors ask for without an OID.
+const SYS_UP_TIME: &str = "1.3.6.1.2.1.1.3.0";
+
+const AGENT_PORT: u16 = 161;
+
+// Asked with when the agent has no [[snmp]] entry.
+const DEFAULT_COMMUNITY: &str = "public";
+
+// A single varbind answer fits easily, even with SNMPv3's headers.
+const MAX_RESPONSE: usize = 4096;
+
+// A request lost on the way is sent again after this, twice at most.
+const RESEND_AFTER: Duration = Duration::from_secs(1);
+const RESENDS: usize = 2;
+
+// The usmStats counters a Report names when a v3 request was refused.
+const USM_STATS: &str = "1.3.6.1.6.3.15.1.1.";
+
+// snmp://<host>[:port][/<oid>][?min=<n>&max=<n>], e.g. snmp://switch.lan or
+// snmp://10.0.0.1/1.3.6.1.4.1.2021.10.1.5.1?max=400. The OID is sysUpTime.0
+// when left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnmpQuery {
+    pub host: String,
+    pub oid: String,
+    // Bounds the value has to stay within, TimeTicks in seconds.
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+}
+
+impl SnmpQuery {
+    pub fn parse(rest: &str) -> Option<SnmpQuery> {
+        let (target, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (host, oid) = match target.split_once('/') {
+            Some((host, oid)) => (host, oid.trim_start_matches('.')),
+            None => (target, SYS_UP_TIME),
+        };
+        if host_and_port(host, AGENT_PORT).is_none() || encode_oid(oid).is_none() {
+            return None;
+        }
+
+        let (mut min, mut max) = (None, None);
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some(("min", value)) => min = Some(value.parse().ok()?),
+                Some(("max", value)) => max = Some(value.parse().ok()?),
+                _ => return None,
+            }
+        }
+        if let (Some(min), Some(max)) = (min, max)
+            && min > max
+        {
+            return None;
+        }
+        Some(SnmpQuery {
+            host: host.to_string(),
+            oid: oid.to_string(),
+            min,
+            max,
+        })
+    }
+
+    // host:port for the socket, on port 161 unless the URL names one.
+    fn address(&self) -> String {
+        let (host, port) = host_and_port(&self.host, AGENT_PORT)
+            .unwrap_or_else(|| (self.host.clone(), AGENT_PORT));
+        let host = host.trim_matches(['[', ']']);
+        if host.contains(':') {
+            format!("[{}]:{}", host, port)
+        } else {
+            format!("{}:{}", host, port)
+        }
+    }
+}
+
+// A value of the varbind the agent answered with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnmpValue {
+    Integer(i64),
+    // Counter32, Gauge32 and Counter64.
+    Unsigned(u64),
+    // Hundredths of a second.
+    TimeTicks(u64),
+    Text(String),
+    Oid(String),
+    IpAddress(Ipv4Addr),
+}
+
+impl SnmpValue {
+    // What thresholds compare against.
+    fn number(&self) -> Option<i128> {
+        match self {
+            SnmpValue::Integer(value) => Some(*value as i128),
+            SnmpValue::Unsigned(value) => Some(*value as i128),
+            SnmpValue::TimeTicks(ticks) => Some(*ticks as i128 / 100),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for SnmpValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnmpValue::Integer(value) => write!(f, "{}", value),
+            SnmpValue::Unsigned(value) => write!(f, "{}", value),
+            SnmpValue::TimeTicks(ticks) => {
+                let minutes = ticks / 6000;
+                let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+                match days {
+                    0 => write!(f, "up {}h {}m", hours, minutes),
+                    days => write!(f, "up {}d {}h {}m", days, hours, minutes),
+                }
+            }
+            SnmpValue::Text(text) => write!(f, "{:?}", text),
+            SnmpValue::Oid(oid) => write!(f, "{}", oid),
+            SnmpValue::IpAddress(address) => write!(f, "{}", address),
+        }
+    }
+}
+
+// How the agent is asked, from its [[snmp]] entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Credentials<'a> {
+    Community(&'a str),
+    User {
+        name: &'a str,
+        auth: Option<(SnmpAuthProtocol, &'a str)>,
+        privacy: Option<&'a str>,
+    },
+}
+
+fn credentials<'a>(host: &str, entries: &'a [SnmpSettings]) -> Result<Credentials<'a>, String> {
+    let Some(entry) = entries.iter().find(|e| e.host == host) else {
+        return Ok(Credentials::Community(DEFAULT_COMMUNITY));
+    };
+    match (&entry.community, &entry.username) {
+        (Some(community), None) => Ok(Credentials::Community(community)),
+        (None, Some(name)) => {
+            let auth = entry
+                .auth_password
+                .as_deref()
+                .map(|password| (entry.auth_protocol, password));
+            if entry.privacy_password.is_some() && auth.is_none() {
+                return Err(format!(
+                    "The [[snmp]] entry for {} has a privacy_password without an auth_password",
+                    host
+                ));
+            }
+            Ok(Credentials::User {
+                name,
+                auth,
+                privacy: entry.privacy_password.as_deref(),
+            })
+        }
+        _ => Err(format!(
+            "The [[snmp]] entry for {} needs either a community or a username",
+            host
+        )),
+    }
+}
+
+// Reads the OID from the agent and records its value. Up when it answers and
+// the value is within the URL's bounds, Down when it's out of them or the
+// agent doesn't answer, and Error when it answers with something unusable.
+#[tracing::instrument(name = "snmp check", skip(entries, domain), fields(host = %query.host))]
+pub async fn check(
+    query: &SnmpQuery,
+    entries: &[SnmpSettings],
+    domain: &MonitoredDomain,
+) -> CheckStatus {
+    let limit = domain.timeout();
+    let start_time = Utc::now();
+    // None when the agent didn't answer in time.
+    let answer = match credentials(&query.host, entries) {
+        Ok(credentials) => tokio::time::timeout(limit, get(query, credentials))
+            .await
+            .ok(),
+        Err(e) => Some(Err(e)),
+    };
+    let end_time = Utc::now();
+
+    let (status, error_message, detail) = match answer {
+        Some(Ok(value)) => {
+            let (status, error_message) = judge(query, &value);
+            (status, error_message, Some(value.to_string()))
+        }
+        None => (
+            DomainStatus::Down,
+            Some(format!(
+                "{} didn't answer within {}s",
+                query.host,
+                limit.as_secs()
+            )),
+            None,
+        ),
+        Some(Err(e)) => {
+            tracing::error!("Error checking {}: {}", domain.url, e);
+            (DomainStatus::Error(e.clone().into()), Some(e), None)
+        }
+    };
+    CheckStatus {
+        response_time_ms: detail
+            .is_some()
+            .then(|| (end_time - start_time).num_milliseconds() as u64),
+        error_message: error_message.map(Arc::from),
+        detail: detail.map(Arc::from),
+        ..CheckStatus::new(end_time, status)
+    }
+}
+
+fn judge(query: &SnmpQuery, value: &SnmpValue) -> (DomainStatus, Option<String>) {
+    if query.min.is_none() && query.max.is_none() {
+        return (DomainStatus::Up, None);
+    }
+    let Some(number) = value.number() else {
+        let message = format!("{} isn't a number to compare, it's {}", query.oid, value);
+        return (DomainStatus::Error(message.clone().into()), Some(message));
+    };
+    if let Some(min) = query.min
+        && number < min as i128
+    {
+        return (
+            DomainStatus::Down,
+            Some(format!("{} is below the minimum {}", number, min)),
+        );
+    }
+    if let Some(max) = query.max
+        && number > max as i128
+    {
+        return (
+            DomainStatus::Down,
+            Some(format!("{} is above the maximum {}", number, max)),
+        );
+    }
+    (DomainStatus::Up, None)
+}
+
+// One GET for the query's OID, with SNMPv3 engine discovery first.
+async fn get(query: &SnmpQuery, credentials: Credentials<'_>) -> Result<SnmpValue, String> {
+    let address = tokio::net::lookup_host(query.address())
+        .await
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .ok_or_else(|| format!("Failed to resolve {}", query.host))?;
+    let local = if address.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(local).await.map_err(|e| e.to_string())?;
+    socket
+        .connect(address)
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", query.host, e))?;
+    let oid = encode_oid(&query.oid).ok_or("Invalid OID")?;
+
+    match credentials {
+        Credentials::Community(community) => {
+            let request_id = rand::random::<u32>() as i64 >> 1;
+            let message = encode_v2c(community, request_id, &oid);
+            exchange(&socket, &message, |response| {
+                parse_v2c(request_id, response)
+            })
+            .await
+        }
+        Credentials::User {
+            name,
+            auth,
+            privacy,
+        } => {
+            let discovery_id = rand::random::<u32>() as i64 >> 1;
+            let discovery = encode_v3(&V3Request {
+                message_id: discovery_id,
+                engine: &EngineParameters::default(),
+                user: "",
+                auth: None,
+                privacy: None,
+                pdu: &encode_get(discovery_id, None),
+            });
+            let engine = exchange(&socket, &discovery, |response| {
+                Ok(parse_v3(discovery_id, response)?.map(|message| message.engine))
+            })
+            .await?;
+
+            let auth = match auth {
+                Some((protocol, password)) => {
+                    Some((protocol, cached_key(protocol, password, &engine.id).await?))
+                }
+                None => None,
+            };
+            let privacy = match (privacy, &auth) {
+                (Some(password), Some((protocol, _))) => {
+                    let mut key = cached_key(*protocol, password, &engine.id).await?;
+                    key.truncate(16);
+                    Some(key)
+                }
+                _ => None,
+            };
+            let keys = auth
+                .as_ref()
+                .map(|(protocol, key)| (*protocol, key.as_slice()));
+
+            let request_id = rand::random::<u32>() as i64 >> 1;
+            let message = encode_v3(&V3Request {
+                message_id: request_id,
+                engine: &engine,
+                user: name,
+                auth: keys,
+                privacy: privacy.as_deref(),
+                pdu: &encode_get(request_id, Some(&oid)),
+            });
+            exchange(&socket, &message, |response| {
+                let Some(message) = parse_v3(request_id, response)? else {
+                    return Ok(None);
+                };
+                if let Some((protocol, key)) = keys {
+                    message.verify(protocol, key)?;
+                }
+                let pdu = message.scoped_pdu(privacy.as_deref())?;
+                parse_pdu(request_id, &pdu).map(Some)
+            })
+            .await
+        }
+    }
+}
+
+// Sends the request, again when no answer came in time, and waits for the
+// answer the parser recognizes as its own.
+async fn exchange<T>(
+    socket: &UdpSocket,
+    message: &[u8],
+    parse: impl Fn(&[u8]) -> Result<Option<T>, String>,
+) -> Result<T, String> {
+    let mut buffer = vec![0; MAX_RESPONSE];
+    let mut resends = RESENDS;
+    loop {
+        socket.send(message).await.map_err(|e| e.to_string())?;
+        let receive = receive(socket, &mut buffer, &parse);
+        if resends == 0 {
+            return receive.await;
+        }
+        resends -= 1;
+        if let Ok(answer) = tokio::time::timeout(RESEND_AFTER, receive).await {
+            return answer;
+        }
+    }
+}
+
+// Late answers to earlier requests are skipped.
+async fn receive<T>(
+    socket: &UdpSocket,
+    buffer: &mut [u8],
+    parse: impl Fn(&[u8]) -> Result<Option<T>, String>,
+) -> Result<T, String> {
+    loop {
+        let len = socket.recv(buffer).await.map_err(|e| e.to_string())?;
+        if let Some(answer) = parse(&buffer[..len])? {
+            return Ok(answer);
+        }
+    }
+}
+
+// BER, the subset SNMP uses.
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    match content.len() {
+        len @ 0..=127 => encoded.push(len as u8),
+        len => {
+            let bytes = (len as u32).to_be_bytes();
+            let skip = bytes.iter().take_while(|b| **b == 0).count();
+            encoded.push(0x80 | (4 - skip) as u8);
+            encoded.extend_from_slice(&bytes[skip..]);
+        }
+    }
+    encoded.extend_from_slice(content);
+    encoded
+}
+
+fn sequence(parts: &[&[u8]]) -> Vec<u8> {
+    tlv(0x30, &parts.concat())
+}
+
+fn integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    // The shortest two's complement: drop leading bytes that only repeat the sign.
+    let mut start = 0;
+    while start < 7
+        && ((bytes[start] == 0 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    tlv(0x02, &bytes[start..])
+}
+
+fn octets(bytes: &[u8]) -> Vec<u8> {
+    tlv(0x04, bytes)
+}
+
+// Dotted decimal to its BER content, None unless it's a valid OID.
+fn encode_oid(oid: &str) -> Option<Vec<u8>> {
+    let arcs = oid
+        .split('.')
+        .map(|arc| arc.parse::<u32>().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    let (first, second) = match arcs[..] {
+        [first @ 0..=1, second @ 0..=39, ..] | [first @ 2, second, ..] => (first, second),
+        _ => return None,
+    };
+    let mut encoded = Vec::new();
+    for arc in std::iter::once(first * 40 + second).chain(arcs[2..].iter().copied()) {
+        let mut chunk = vec![(arc & 0x7f) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            chunk.push((rest & 0x7f) as u8 | 0x80);
+            rest >>= 7;
+        }
+        encoded.extend(chunk.iter().rev());
+    }
+    Some(encoded)
+}
+
+fn decode_oid(content: &[u8]) -> Result<String, String> {
+    let mut arcs = Vec::new();
+    let mut arc: u64 = 0;
+    for byte in content {
+        if arc > u64::MAX >> 7 {
+            return Err(malformed());
+        }
+        arc = (arc << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (arc / 40).min(2);
+                arcs.push(first);
+                arcs.push(arc - first * 40);
+            } else {
+                arcs.push(arc);
+            }
+            arc = 0;
+        }
+    }
+    // The last byte has to end an arc.
+    if arcs.is_empty() || content.last().is_some_and(|byte| byte & 0x80 != 0) {
+        return Err(malformed());
+    }
+    Ok(arcs
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join("."))
+}
+
+fn decode_integer(content: &[u8]) -> Result<i64, String> {
+    if content.is_empty() || content.len() > 8 {
+        return Err(malformed());
+    }
+    let fill = if content[0] & 0x80 != 0 { 0xff } else { 0 };
+    let mut bytes = [fill; 8];
+    bytes[8 - content.len()..].copy_from_slice(content);
+    Ok(i64::from_be_bytes(bytes))
+}
+
+// Counters and gauges are unsigned, but encoded as integers, so 2^32 - 1
+// takes five bytes.
+fn decode_unsigned(content: &[u8]) -> Result<u64, String> {
+    let content = match content {
+        [0, rest @ ..] => rest,
+        content => content,
+    };
+    if content.len() > 8 {
+        return Err(malformed());
+    }
+    Ok(content
+        .iter()
+        .fold(0, |value, byte| (value << 8) | *byte as u64))
+}
+
+fn malformed() -> String {
+    "Malformed SNMP response".to_string()
+}
+
+// Walks the elements of a BER encoding, knowing where each one sits in the
+// whole message, so the v3 digest can be checked in place.
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader {
+            data,
+            position: 0,
+            offset: 0,
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    // The next element's tag and content, with the content's offset.
+    fn next_at(&mut self) -> Result<(u8, &'a [u8], usize), String> {
+        let tag = *self.data.get(self.position).ok_or_else(malformed)?;
+        let first = *self.data.get(self.position + 1).ok_or_else(malformed)?;
+        let mut start = self.position + 2;
+        let len = if first & 0x80 == 0 {
+            first as usize
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count > 4 {
+                return Err(malformed());
+            }
+            let bytes = self.data.get(start..start + count).ok_or_else(malformed)?;
+            start += count;
+            bytes
+                .iter()
+                .fold(0, |len, byte| (len << 8) | *byte as usize)
+        };
+        let end = start.checked_add(len).ok_or_else(malformed)?;
+        let content = self.data.get(start..end).ok_or_else(malformed)?;
+        self.position = end;
+        Ok((tag, content, self.offset + start))
+    }
+
+    fn next(&mut self) -> Result<(u8, &'a [u8]), String> {
+        self.next_at().map(|(tag, content, _)| (tag, content))
+    }
+
+    fn expect(&mut self, tag: u8) -> Result<&'a [u8], String> {
+        match self.next()? {
+            (found, content) if found == tag => Ok(content),
+            _ => Err(malformed()),
+        }
+    }
+
+    // The content of a constructed element, to read its elements from.
+    fn enter(&mut self, tag: u8) -> Result<Reader<'a>, String> {
+        match self.next_at()? {
+            (found, content, offset) if found == tag => Ok(Reader {
+                data: content,
+                position: 0,
+                offset,
+            }),
+            _ => Err(malformed()),
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64, String> {
+        decode_integer(self.expect(0x02)?)
+    }
+}
+
+// A GetRequest for the OID, or for nothing during engine discovery.
+fn encode_get(request_id: i64, oid: Option<&[u8]>) -> Vec<u8> {
+    let varbinds = match oid {
+        Some(oid) => sequence(&[&sequence(&[&tlv(0x06, oid), &tlv(0x05, &[])])]),
+        None => sequence(&[]),
+    };
+    tlv(
+        0xa0,
+        &[integer(request_id), integer(0), integer(0), varbinds].concat(),
+    )
+}
+
+fn encode_v2c(community: &str, request_id: i64, oid: &[u8]) -> Vec<u8> {
+    sequence(&[
+        &integer(1),
+        &octets(community.as_bytes()),
+        &encode_get(request_id, Some(oid)),
+    ])
+}
+
+// None for an answer to another request.
+fn parse_v2c(request_id: i64, message: &[u8]) -> Result<Option<SnmpValue>, String> {
+    let mut message = Reader::new(message).enter(0x30)?;
+    if message.integer()? != 1 {
+        return Err(malformed());
+    }
+    message.expect(0x04)?;
+    let (tag, pdu) = message.next()?;
+    if pdu_request_id(pdu)? != request_id {
+        return Ok(None);
+    }
+    parse_pdu(request_id, &tlv(tag, pdu)).map(Some)
+}
+
+fn pdu_request_id(pdu: &[u8]) -> Result<i64, String> {
+    Reader::new(pdu).integer()
+}
+
+// The value of the first varbind of a Response, or what a Report says went
+// wrong.
+fn parse_pdu(request_id: i64, pdu: &[u8]) -> Result<SnmpValue, String> {
+    let mut reader = Reader::new(pdu);
+    let (tag, content) = reader.next()?;
+    let mut pdu = Reader::new(content);
+    if pdu.integer()? != request_id {
+        return Err(malformed());
+    }
+    let error_status = pdu.integer()?;
+    pdu.integer()?;
+    let mut varbinds = pdu.enter(0x30)?;
+    let mut varbind = varbinds.enter(0x30)?;
+    let oid = decode_oid(varbind.expect(0x06)?)?;
+    let (value_tag, value) = varbind.next()?;
+
+    if tag == 0xa8 {
+        return Err(report_reason(&oid));
+    }
+    if tag != 0xa2 {
+        return Err(malformed());
+    }
+    if error_status != 0 {
+        return Err(format!("The agent answered {}", error_name(error_status)));
+    }
+    match value_tag {
+        0x02 => Ok(SnmpValue::Integer(decode_integer(value)?)),
+        0x04 => Ok(SnmpValue::Text(String::from_utf8_lossy(value).into_owned())),
+        0x06 => Ok(SnmpValue::Oid(decode_oid(value)?)),
+        0x40 => <[u8; 4]>::try_from(value)
+            .map(|octets| SnmpValue::IpAddress(Ipv4Addr::from(octets)))
+            .map_err(|_| malformed()),
+        0x41 | 0x42 | 0x46 => Ok(SnmpValue::Unsigned(decode_unsigned(value)?)),
+        0x43 => Ok(SnmpValue::TimeTicks(decode_unsigned(value)?)),
+        0x80 | 0x81 => Err(format!("The agent has no {}", oid)),
+        _ => Err(format!(
+            "Unsupported value type 0x{:02x} for {}",
+            value_tag, oid
+        )),
+    }
+}
+
+fn error_name(status: i64) -> String {
+    match status {
+        1 => "tooBig".to_string(),
+        2 => "noSuchName".to_string(),
+        5 => "genErr".to_string(),
+        6 => "noAccess".to_string(),
+        status => format!("error status {}", status),
+    }
+}
+
+fn report_reason(oid: &str) -> String {
+    let reason = match oid.strip_prefix(USM_STATS) {
+        Some("1.0") => "the security level isn't supported for this user",
+        Some("2.0") => "the request wasn't in the agent's time window",
+        Some("3.0") => "the username is unknown",
+        Some("4.0") => "the engine id is unknown",
+        Some("5.0") => "the auth password or protocol is wrong",
+        Some("6.0") => "the privacy password is wrong",
+        _ => return format!("The agent refused the request ({})", oid),
+    };
+    format!("The agent refused the request: {}", reason)
+}
+
+// SNMPv3 (RFC 3414, 3826 and 7860).
+
+// What engine discovery learns about the agent.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct EngineParameters {
+    id: Vec<u8>,
+    boots: i64,
+    time: i64,
+}
+
+struct V3Request<'a> {
+    message_id: i64,
+    engine: &'a EngineParameters,
+    user: &'a str,
+    auth: Option<(SnmpAuthProtocol, &'a [u8])>,
+    privacy: Option<&'a [u8]>,
+    pdu: &'a [u8],
+}
+
+fn digest_len(protocol: SnmpAuthProtocol) -> usize {
+    match protocol {
+        SnmpAuthProtocol::Sha => 12,
+        SnmpAuthProtocol::Sha256 => 24,
+    }
+}
+
+fn encode_v3(request: &V3Request) -> Vec<u8> {
+    let engine = request.engine;
+    // Reportable, plus auth and privacy when used.
+    let flags = 0x04 | request.auth.map_or(0, |_| 0x01) | request.privacy.map_or(0, |_| 0x02);
+    let global = sequence(&[
+        &integer(request.message_id),
+        &integer(MAX_RESPONSE as i64),
+        &octets(&[flags]),
+        &integer(3),
+    ]);
+
+    let scoped = sequence(&[&octets(&engine.id), &octets(&[]), request.pdu]);
+    let (scoped, salt) = match request.privacy {
+        Some(key) => {
+            let salt = rand::random::<u64>().to_be_bytes();
+            let encrypted = aes_cfb(key, iv(engine.boots, engine.time, &salt), &scoped, false);
+            (octets(&encrypted), salt.to_vec())
+        }
+        None => (scoped, Vec::new()),
+    };
+
+    let before_digest = [
+        octets(&engine.id),
+        integer(engine.boots),
+        integer(engine.time),
+        octets(request.user.as_bytes()),
+    ]
+    .concat();
+    let digest_len = request.auth.map_or(0, |(protocol, _)| digest_len(protocol));
+    let security = sequence(&[
+        &before_digest,
+        &octets(&vec![0; digest_len]),
+        &octets(&salt),
+    ]);
+    let mut message = sequence(&[&integer(3), &global, &octets(&security), &scoped]);
+
+    // The digest covers the whole message with its own place zeroed.
+    if let Some((protocol, key)) = request.auth {
+        let marker = [before_digest.as_slice(), &[0x04, digest_len as u8]].concat();
+        let start = message
+            .windows(marker.len())
+            .position(|window| window == marker)
+            .expect("the security parameters are in the message")
+            + marker.len();
+        let digest = sign(protocol, key, &message);
+        message[start..start + digest_len].copy_from_slice(&digest);
+    }
+    message
+}
+
+// A v3 message as received, before its digest is checked.
+struct V3Message<'a> {
+    raw: &'a [u8],
+    flags: u8,
+    engine: EngineParameters,
+    digest: &'a [u8],
+    digest_offset: usize,
+    salt: &'a [u8],
+    // Encrypted when the privacy flag is set.
+    scoped: &'a [u8],
+}
+
+impl V3Message<'_> {
+    fn verify(&self, protocol: SnmpAuthProtocol, key: &[u8]) -> Result<(), String> {
+        if self.flags & 0x01 == 0 {
+            // Reports about a failed login come unauthenticated. Any other
+            // answer has to be, or a forged one could lower the security
+            // level (RFC 3414 3.2).
+            return match self.scoped_pdu(None)?.first() {
+                Some(0xa8) => Ok(()),
+                _ => Err("The agent's answer wasn't authenticated".to_string()),
+            };
+        }
+        let mut zeroed = self.raw.to_vec();
+        zeroed[self.digest_offset..self.digest_offset + self.digest.len()].fill(0);
+        if bool::from(sign(protocol, key, &zeroed).ct_eq(self.digest)) {
+            Ok(())
+        } else {
+            Err("The agent's answer failed authentication".to_string())
+        }
+    }
+
+    // The PDU, decrypted with the privacy key when the message is encrypted.
+    fn scoped_pdu(&self, privacy: Option<&[u8]>) -> Result<Vec<u8>, String> {
+        let decrypted;
+        let scoped = if self.flags & 0x02 != 0 {
+            let key = privacy.ok_or("The agent encrypted its answer unasked")?;
+            let encrypted = Reader::new(self.scoped).expect(0x04)?;
+            decrypted = aes_cfb(
+                key,
+                iv(self.engine.boots, self.engine.time, self.salt),
+                encrypted,
+                true,
+            );
+            &decrypted
+        } else {
+            self.scoped
+        };
+        let mut scoped = Reader::new(scoped).enter(0x30)?;
+        scoped.expect(0x04)?;
+        scoped.expect(0x04)?;
+        let (tag, pdu) = scoped.next()?;
+        Ok(tlv(tag, pdu))
+    }
+}
+
+// None for an answer to another message.
+fn parse_v3(message_id: i64, raw: &[u8]) -> Result<Option<V3Message<'_>>, String> {
+    let mut message = Reader::new(raw).enter(0x30)?;
+    if message.integer()? != 3 {
+        return Err(malformed());
+    }
+    let mut global = message.enter(0x30)?;
+    if global.integer()? != message_id {
+        return Ok(None);
+    }
+    global.integer()?;
+    let flags = *global.expect(0x04)?.first().ok_or_else(malformed)?;
+
+    let (tag, security, security_offset) = message.next_at()?;
+    if tag != 0x04 {
+        return Err(malformed());
+    }
+    let mut security = Reader {
+        data: security,
+        position: 0,
+        offset: security_offset,
+    }
+    .enter(0x30)?;
+    let engine = EngineParameters {
+        id: security.expect(0x04)?.to_vec(),
+        boots: security.integer()?,
+        time: security.integer()?,
+    };
+    security.expect(0x04)?;
+    let (tag, digest, digest_offset) = security.next_at()?;
+    if tag != 0x04 {
+        return Err(malformed());
+    }
+    let salt = security.expect(0x04)?;
+
+    let scoped_start = message.position;
+    message.next()?;
+    if !message.is_done() {
+        return Err(malformed());
+    }
+    Ok(Some(V3Message {
+        raw,
+        flags,
+        engine,
+        digest,
+        digest_offset,
+        salt,
+        scoped: &message.data[scoped_start..],
+    }))
+}
+
+// The password stretched to a key (hashing a megabyte of it over and over)
+// and localized to the engine, so one key can't be used with another agent.
+// Deriving a key hashes a megabyte, so it's done once per agent engine and
+// password, off the runtime's threads.
+async fn cached_key(
+    protocol: SnmpAuthProtocol,
+    password: &str,
+    engine_id: &[u8],
+) -> Result<Vec<u8>, String> {
+    type Keys = Mutex<HashMap<(SnmpAuthProtocol, String, Vec<u8>), Vec<u8>>>;
+    static KEYS: OnceLock<Keys> = OnceLock::new();
+    let keys = KEYS.get_or_init(Default::default);
+
+    let entry = (protocol, password.to_string(), engine_id.to_vec());
+    if let Some(key) = keys.lock().unwrap().get(&entry) {
+        return Ok(key.clone());
+    }
+    let (password, engine_id) = (entry.1.clone(), entry.2.clone());
+    let key = tokio::task::spawn_blocking(move || localized_key(protocol, &password, &engine_id))
+        .await
+        .map_err(|e| e.to_string())??;
+    keys.lock().unwrap().insert(entry, key.clone());
+    Ok(key)
+}
+
+fn localized_key(
+    protocol: SnmpAuthProtocol,
+    password: &str,
+    engine_id: &[u8],
+) -> Result<Vec<u8>, String> {
+    if password.len() < 8 {
+        return Err("SNMPv3 passwords need at least 8 characters".to_string());
+    }
+    Ok(match protocol {
+        SnmpAuthProtocol::Sha => localize::<Sha1>(password, engine_id),
+        SnmpAuthProtocol::Sha256 => localize::<Sha256>(password, engine_id),
+    })
+}
+
+fn localize<D: Digest>(password: &str, engine_id: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    let mut stretched = password.bytes().cycle();
+    for _ in 0..(1 << 20) / 64 {
+        let chunk: Vec<u8> = stretched.by_ref().take(64).collect();
+        hasher.update(&chunk);
+    }
+    let key = hasher.finalize();
+    let mut hasher = D::new();
+    hasher.update(&key);
+    hasher.update(engine_id);
+    hasher.update(&key);
+    hasher.finalize().to_vec()
+}
+
+fn sign(protocol: SnmpAuthProtocol, key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut digest = match protocol {
+        SnmpAuthProtocol::Sha => {
+            let mut mac =
+                <Hmac<Sha1> as Mac>::new_from_slice(key).expect("HMAC takes any key length");
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+        SnmpAuthProtocol::Sha256 => {
+            let mut mac =
+                <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC takes any key length");
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+    };
+    digest.truncate(digest_len(protocol));
+    digest
+}
+
+fn iv(boots: i64, time: i64, salt: &[u8]) -> [u8; 16] {
+    let mut iv = [0; 16];
+    iv[..4].copy_from_slice(&(boots as u32).to_be_bytes());
+    iv[4..8].copy_from_slice(&(time as u32).to_be_bytes());
+    let salt_len = salt.len().min(8);
+    iv[8..8 + salt_len].copy_from_slice(&salt[..salt_len]);
+    iv
+}
+
+// AES-128 in CFB mode with 128 bit segments, which needs no padding.
+fn aes_cfb(key: &[u8], iv: [u8; 16], data: &[u8], decrypt: bool) -> Vec<u8> {
+    let cipher = Aes128::new(GenericArray::from_slice(key));
+    let mut feedback = iv;
+    let mut output = Vec::with_capacity(data.len());
+    for chunk in data.chunks(16) {
+        let mut keystream = GenericArray::from(feedback);
+        cipher.encrypt_block(&mut keystream);
+        let start = output.len();
+        output.extend(chunk.iter().zip(keystream.iter()).map(|(b, k)| b ^ k));
+        let ciphertext = if decrypt { chunk } else { &output[start..] };
+        feedback[..ciphertext.len()].copy_from_slice(ciphertext);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn speaks_snmp() {
+        let query = SnmpQuery::parse("10.0.0.1:1161/.1.3.6.1.2.1.2.2.1.8.1?min=1&max=1").unwrap();
+        assert_eq!(query.oid, "1.3.6.1.2.1.2.2.1.8.1");
+        assert_eq!(query.address(), "10.0.0.1:1161");
+        let query = SnmpQuery::parse("switch.lan").unwrap();
+        assert_eq!(query.oid, SYS_UP_TIME);
+        assert_eq!(query.address(), "switch.lan:161");
+        assert_eq!(
+            SnmpQuery::parse("fe80::1").unwrap().address(),
+            "[fe80::1]:161"
+        );
+        assert_eq!(SnmpQuery::parse("switch.lan/1.3.x"), None);
+        assert_eq!(SnmpQuery::parse("switch.lan?min=5&max=1"), None);
+
+        // A response to request 42 for sysUpTime.0, 2 days 3 hours 4 minutes.
+        let ticks = (((2 * 24 + 3) * 60 + 4) * 6000) as i64;
+        let oid = encode_oid(SYS_UP_TIME).unwrap();
+        assert_eq!(hex(&oid), "2b06010201010300");
+        assert_eq!(decode_oid(&oid).unwrap(), SYS_UP_TIME);
+        assert!(decode_oid(&[0x2b, 0x86]).is_err());
+        assert!(
+            decode_oid(&[
+                0x2b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f
+            ])
+            .is_err()
+        );
+        let mut ticks_content = integer(ticks);
+        ticks_content[0] = 0x43;
+        let response = sequence(&[
+            &integer(1),
+            &octets(b"public"),
+            &tlv(
+                0xa2,
+                &[
+                    integer(42),
+                    integer(0),
+                    integer(0),
+                    sequence(&[&sequence(&[&tlv(0x06, &oid), &ticks_content])]),
+                ]
+                .concat(),
+            ),
+        ]);
+        assert_eq!(parse_v2c(7, &response), Ok(None));
+        let value = parse_v2c(42, &response).unwrap().unwrap();
+        assert_eq!(value.to_string(), "up 2d 3h 4m");
+        let recently_rebooted = SnmpQuery::parse("switch.lan?min=259200").unwrap();
+        assert_eq!(judge(&recently_rebooted, &value).0, DomainStatus::Down);
+        assert_eq!(judge(&query, &value), (DomainStatus::Up, None));
+
+        // RFC 3414 A.3.2: "maplesyrup" localized to engine 00..02 with SHA.
+        let engine_id = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2];
+        assert_eq!(
+            hex(&localized_key(SnmpAuthProtocol::Sha, "maplesyrup", &engine_id).unwrap()),
+            "6695febc9288e36282235fc7151f128497b38f3f"
+        );
+
+        // A v3 request reads back as sent, digest and encryption included.
+        let key = localized_key(SnmpAuthProtocol::Sha256, "maplesyrup", &engine_id).unwrap();
+        let engine = EngineParameters {
+            id: engine_id.to_vec(),
+            boots: 3,
+            time: 1234,
+        };
+        let pdu = encode_get(42, Some(&oid));
+        let message = encode_v3(&V3Request {
+            message_id: 42,
+            engine: &engine,
+            user: "monitor",
+            auth: Some((SnmpAuthProtocol::Sha256, &key)),
+            privacy: Some(&key[..16]),
+            pdu: &pdu,
+        });
+        let parsed = parse_v3(42, &message).unwrap().unwrap();
+        assert_eq!(parsed.engine, engine);
+        assert_eq!(parsed.digest.len(), 24);
+        assert_eq!(parsed.verify(SnmpAuthProtocol::Sha256, &key), Ok(()));
+        assert_eq!(parsed.scoped_pdu(Some(&key[..16])).unwrap(), pdu);
+        assert!(parsed.verify(SnmpAuthProtocol::Sha256, &key[1..]).is_err());
+
+        assert_eq!(
+            credentials("switch.lan", &[]),
+            Ok(Credentials::Community("public"))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        for data in [
+            &[][..],
+            // Cut off before the length, or before the end of the content.
+            &[0x02],
+            &[0x02, 0x03, 0x01],
+            // A long form length cut off, longer than 4 bytes, or past the end.
+            &[0x04, 0x82, 0x01],
+            &[0x04, 0x85, 0x00, 0x00, 0x00, 0x00, 0x01, 0xff],
+            &[0x04, 0x84, 0xff, 0xff, 0xff, 0xff, 0x00],
+        ] {
+            assert_eq!(Reader::new(data).next(), Err(malformed()), "{:02x?}", data);
+        }
+        let mut reader = Reader::new(&[0x02, 0x01, 0x2a, 0x04, 0x00]);
+        assert_eq!(reader.integer(), Ok(42));
+        assert!(reader.enter(0x30).is_err());
+
+        let oid = encode_oid(SYS_UP_TIME).unwrap();
+        let pdu = |tag: u8, request_id: i64| {
+            tlv(
+                tag,
+                &[
+                    integer(request_id),
+                    integer(0),
+                    integer(0),
+                    sequence(&[&sequence(&[&tlv(0x06, &oid), &integer(5)])]),
+                ]
+                .concat(),
+            )
+        };
+        assert_eq!(parse_pdu(42, &pdu(0xa2, 42)), Ok(SnmpValue::Integer(5)));
+        assert_eq!(parse_pdu(42, &pdu(0xa2, 43)), Err(malformed()));
+        // A GetRequest echoed back isn't an answer.
+        assert_eq!(parse_pdu(42, &pdu(0xa0, 42)), Err(malformed()));
+        let mut truncated = pdu(0xa2, 42);
+        truncated.pop();
+        assert_eq!(parse_pdu(42, &truncated), Err(malformed()));
+    }
+
+    // RFC 3826 encrypts with AES in CFB128 mode, NIST SP 800-38A F.3.13/14.
+    #[test]
+    fn aes_cfb_matches_the_nist_vectors() {
+        let key = [
+            0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf,
+            0x4f, 0x3c,
+        ];
+        let nist_iv: [u8; 16] = std::array::from_fn(|i| i as u8);
+        let plaintext = [
+            "6bc1bee22e409f96e93d7e117393172a",
+            "ae2d8a571e03ac9c9eb76fac45af8e51",
+            "30c81c46a35ce411e5fbc1191a0a52ef",
+            "f69f2445df4f9b17ad2b417be66c3710",
+        ]
+        .concat();
+        let ciphertext = [
+            "3b3fd92eb72dad20333449f8e83cfb4a",
+            "c8a64537a0b3a93fcde3cdad9f1ce58b",
+            "26751f67a3cbb140b1808cf187a4f4df",
+            "c04b05357c5d1c0eeac4c66f9ff7f2e6",
+        ]
+        .concat();
+        let bytes = |hex: &str| -> Vec<u8> {
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect()
+        };
+
+        let encrypted = aes_cfb(&key, nist_iv, &bytes(&plaintext), false);
+        assert_eq!(hex(&encrypted), ciphertext);
+        assert_eq!(hex(&aes_cfb(&key, nist_iv, &encrypted, true)), plaintext);
+        // Scoped PDUs rarely fill the last block.
+        let partial = aes_cfb(&key, nist_iv, &bytes(&plaintext)[..20], false);
+        assert_eq!(hex(&partial), ciphertext[..40]);
+
+        // The IV is the engine's boots and time, then the salt.
+        let salt = [0xa1, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7, 0xa8];
+        assert_eq!(hex(&iv(3, 4, &salt)), "0000000300000004a1a2a3a4a5a6a7a8");
+    }
+
+    #[test]
+    fn only_reports_may_come_unauthenticated() {
+        let engine = EngineParameters {
+            id: vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2],
+            boots: 3,
+            time: 1234,
+        };
+        let key = localized_key(SnmpAuthProtocol::Sha, "maplesyrup", &engine.id).unwrap();
+        let unauthenticated = |tag: u8| {
+            let pdu = tlv(
+                tag,
+                &[
+                    integer(42),
+                    integer(0),
+                    integer(0),
+                    sequence(&[&sequence(&[
+                        &tlv(0x06, &encode_oid(SYS_UP_TIME).unwrap()),
+                        &[0x05, 0x00],
+                    ])]),
+                ]
+                .concat(),
+            );
+            encode_v3(&V3Request {
+                message_id: 42,
+                engine: &engine,
+                user: "monitor",
+                auth: None,
+                privacy: None,
+                pdu: &pdu,
+            })
+        };
+
+        let report = unauthenticated(0xa8);
+        let report = parse_v3(42, &report).unwrap().unwrap();
+        assert_eq!(report.verify(SnmpAuthProtocol::Sha, &key), Ok(()));
+
+        let response = unauthenticated(0xa2);
+        let response = parse_v3(42, &response).unwrap().unwrap();
+        assert_eq!(
+            response.verify(SnmpAuthProtocol::Sha, &key),
+            Err("The agent's answer wasn't authenticated".to_string())
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use super::response_time;
+use super::target::host_and_port;
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::Utc;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::net::TcpStream;
+
+// Lines a server may send before its version, RFC 4253 allows some.
+const MAX_PRELUDE_LINES: usize = 20;
+
+// ssh://<host>[:port], on port 22 unless the URL names one, e.g.
+// ssh://bastion.example.com or ssh://10.0.0.1:2222.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshQuery {
+    pub host: String,
+    pub port: u16,
+}
+
+impl SshQuery {
+    pub fn parse(rest: &str) -> Option<SshQuery> {
+        let (host, port) = host_and_port(rest, 22)?;
+        Some(SshQuery { host, port })
+    }
+}
+
+// Connects and reads the server's version banner, without logging in. Up
+// when it identifies itself, Down when it can't be reached or doesn't in
+// time, and Error when what it sends isn't SSH.
+#[tracing::instrument(name = "ssh check", skip(domain), fields(host = %query.host, port = query.port))]
+pub async fn check(query: &SshQuery, domain: &MonitoredDomain) -> CheckStatus {
+    let limit = domain.timeout();
+    let start_time = Utc::now();
+    let banner = tokio::time::timeout(limit, read_banner(query)).await;
+    let end_time = Utc::now();
+
+    let (status, error_message, detail) = match banner {
+        Ok(Ok(banner)) => (DomainStatus::Up, None, Some(banner)),
+        Ok(Err((status, message))) => {
+            if let DomainStatus::Error(_) = status {
+                tracing::error!("Error checking {}: {}", domain.url, message);
+            }
+            (status, Some(message), None)
+        }
+        Err(_) => (
+            DomainStatus::Down,
+            Some(format!(
+                "{}:{} sent no banner within {}s",
+                query.host,
+                query.port,
+                limit.as_secs()
+            )),
+            None,
+        ),
+    };
+    let response_time_ms = response_time(status == DomainStatus::Up, start_time, end_time);
+    CheckStatus {
+        response_time_ms,
+        error_message: error_message.map(Arc::from),
+        detail: detail.map(Arc::from),
+        ..CheckStatus::new(end_time, status)
+    }
+}
+
+// Why there's no banner, with the status it leaves the monitor in.
+type Failure = (DomainStatus, String);
+
+async fn read_banner(query: &SshQuery) -> Result<String, Failure> {
+    let stream = TcpStream::connect((query.host.trim_matches(['[', ']']), query.port))
+        .await
+        .map_err(|e| {
+            (
+                DomainStatus::Down,
+                format!("Failed to connect to {}:{}: {}", query.host, query.port, e),
+            )
+        })?;
+    banner(BufReader::new(stream)).await
+}
+
+// The first line starting with SSH-, e.g. SSH-2.0-OpenSSH_9.6.
+async fn banner<S: AsyncRead + Unpin>(mut stream: BufReader<S>) -> Result<String, Failure> {
+    let down = |message: String| (DomainStatus::Down, message);
+    for _ in 0..MAX_PRELUDE_LINES {
+        let mut line = String::new();
+        let read = stream
+            .read_line(&mut line)
+            .await
+            .map_err(|e| down(format!("Connection lost: {}", e)))?;
+        if read == 0 {
+            return Err(down("Connection closed before the banner".to_string()));
+        }
+        let line = line.trim_end();
+        if line.starts_with("SSH-") {
+            return Ok(line.to_string());
+        }
+    }
+    let message = "No SSH banner, is this an SSH server?".to_string();
+    Err((DomainStatus::Error(message.clone().into()), message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+
+    // Sends the lines to whoever connects, then hangs up.
+    async fn server(lines: &'static str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let _ = stream.write_all(lines.as_bytes()).await;
+            }
+        });
+        address
+    }
+
+    #[tokio::test]
+    async fn reads_the_version_banner() {
+        let address = server("Authorized access only\r\nSSH-2.0-OpenSSH_9.6\r\n").await;
+        let query = SshQuery::parse(&address).unwrap();
+        let domain = MonitoredDomain::new(&format!("ssh://{}", address));
+        let status = check(&query, &domain).await;
+        assert_eq!(status.status, DomainStatus::Up);
+        assert_eq!(status.detail.as_deref(), Some("SSH-2.0-OpenSSH_9.6"));
+        assert!(status.response_time_ms.is_some());
+
+        let web = server("HTTP/1.1 400 Bad Request\r\n").await;
+        let status = check(&SshQuery::parse(&web).unwrap(), &domain).await;
+        assert_eq!(status.status, DomainStatus::Down);
+        assert_eq!(
+            status.error_message.as_deref(),
+            Some("Connection closed before the banner")
+        );
+
+        assert_eq!(
+            SshQuery::parse("bastion.example.com"),
+            Some(SshQuery {
+                host: "bastion.example.com".to_string(),
+                port: 22
+            })
+        );
+        assert_eq!(SshQuery::parse("[::1]:2222").map(|q| q.port), Some(2222));
+        assert!(SshQuery::parse("ops@bastion").is_none());
+    }
+}
@@ -0,0 +1,282 @@
+use super::response_time;
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use chrono::Utc;
+use reqwest::Client;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+// The hosted status page services a monitor can follow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Provider {
+    // Atlassian Statuspage, e.g. www.githubstatus.com.
+    Statuspage,
+    Instatus,
+}
+
+impl Provider {
+    fn name(self) -> &'static str {
+        match self {
+            Provider::Statuspage => "Statuspage",
+            Provider::Instatus => "Instatus",
+        }
+    }
+
+    // The public JSON listing every component and its status.
+    fn components_path(self) -> &'static str {
+        match self {
+            Provider::Statuspage => "/api/v2/summary.json",
+            Provider::Instatus => "/v2/components.json",
+        }
+    }
+}
+
+// statuspage://<page host>[?component=<name>] or the same with instatus://,
+// e.g. statuspage://www.githubstatus.com?component=Actions. Without a
+// component the page's worst component counts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusPageQuery {
+    pub provider: Provider,
+    pub host: String,
+    pub component: Option<String>,
+}
+
+impl StatusPageQuery {
+    pub fn parse(provider: Provider, rest: &str) -> Option<StatusPageQuery> {
+        let (host, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let host = host.trim_end_matches('/');
+        let valid_host = !host.is_empty()
+            && host
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':'));
+        if !valid_host {
+            return None;
+        }
+
+        let mut component = None;
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            match &*key {
+                "component" if !value.trim().is_empty() => {
+                    component = Some(value.trim().to_string())
+                }
+                _ => return None,
+            }
+        }
+        Some(StatusPageQuery {
+            provider,
+            host: host.to_string(),
+            component,
+        })
+    }
+
+    fn components_url(&self) -> String {
+        format!("https://{}{}", self.host, self.provider.components_path())
+    }
+}
+
+// What a provider reports for a component, worst last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ComponentStatus {
+    Operational,
+    Maintenance,
+    Degraded,
+    PartialOutage,
+    MajorOutage,
+}
+
+impl ComponentStatus {
+    // Statuspage's snake_case and Instatus' upper case names alike.
+    fn parse(status: &str) -> Option<ComponentStatus> {
+        Some(
+            match status.to_ascii_lowercase().replace('_', "").as_str() {
+                "operational" => ComponentStatus::Operational,
+                "undermaintenance" => ComponentStatus::Maintenance,
+                "degradedperformance" => ComponentStatus::Degraded,
+                "partialoutage" => ComponentStatus::PartialOutage,
+                "majoroutage" => ComponentStatus::MajorOutage,
+                _ => return None,
+            },
+        )
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ComponentStatus::Operational => "operational",
+            ComponentStatus::Maintenance => "under maintenance",
+            ComponentStatus::Degraded => "degraded performance",
+            ComponentStatus::PartialOutage => "partial outage",
+            ComponentStatus::MajorOutage => "major outage",
+        }
+    }
+
+    // A provider's own outage only takes the monitor down when it's a major
+    // one, anything short of that is Degraded.
+    fn domain_status(self) -> DomainStatus {
+        match self {
+            ComponentStatus::Operational => DomainStatus::Up,
+            ComponentStatus::MajorOutage => DomainStatus::Down,
+            _ => DomainStatus::Degraded,
+        }
+    }
+}
+
+// Both providers list components as {"components": [{"name", "status"}]}.
+#[derive(Debug, Deserialize)]
+struct Components {
+    components: Vec<Component>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Component {
+    name: String,
+    status: String,
+}
+
+// Fetches the provider's component list and reports the status it gives the
+// component, or the page's worst one. Error when the page can't be read or
+// has no such component.
+#[tracing::instrument(name = "status page check", skip(client, domain), fields(host = %query.host))]
+pub async fn check(
+    client: &Client,
+    query: &StatusPageQuery,
+    domain: &MonitoredDomain,
+) -> CheckStatus {
+    let limit = domain.timeout();
+    let start_time = Utc::now();
+    let components = fetch(client, &query.components_url(), limit).await;
+    let end_time = Utc::now();
+
+    let (status, error_message, detail) = match components
+        .and_then(|body| parse_components(&body))
+        .and_then(|c| judge(query, &c))
+    {
+        Ok(judged) => judged,
+        Err(e) => {
+            tracing::error!("Error checking {}: {}", domain.url, e);
+            (DomainStatus::Error(e.clone().into()), Some(e), None)
+        }
+    };
+    let response_time_ms = response_time(
+        !matches!(status, DomainStatus::Error(_)),
+        start_time,
+        end_time,
+    );
+    CheckStatus {
+        response_time_ms,
+        error_message: error_message.map(Arc::from),
+        detail: detail.map(Arc::from),
+        ..CheckStatus::new(end_time, status)
+    }
+}
+
+async fn fetch(client: &Client, url: &str, limit: Duration) -> Result<String, String> {
+    let response = client
+        .get(url)
+        .timeout(limit)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to read {}: {}", url, e))?;
+    response.text().await.map_err(|e| e.to_string())
+}
+
+fn parse_components(body: &str) -> Result<Vec<(String, ComponentStatus)>, String> {
+    let components: Components =
+        serde_json::from_str(body).map_err(|e| format!("Invalid status page response: {}", e))?;
+    Ok(components
+        .components
+        .into_iter()
+        // Statuses a provider adds later are skipped rather than guessed at.
+        .filter_map(|c| Some((c.name, ComponentStatus::parse(&c.status)?)))
+        .collect())
+}
+
+// The status, the failure message and the Result column text, e.g.
+// "Actions: partial outage".
+type Judged = (DomainStatus, Option<String>, Option<String>);
+
+fn judge(
+    query: &StatusPageQuery,
+    components: &[(String, ComponentStatus)],
+) -> Result<Judged, String> {
+    let (name, status) = match &query.component {
+        Some(wanted) => components
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(wanted))
+            .ok_or_else(|| format!("{} has no component named {:?}", query.host, wanted))?,
+        None => components
+            .iter()
+            .max_by_key(|(_, status)| *status)
+            .ok_or_else(|| format!("{} lists no components", query.host))?,
+    };
+    let detail = format!("{}: {}", name, status.label());
+    let error_message = (*status != ComponentStatus::Operational).then(|| {
+        format!(
+            "{} reports {} for {}",
+            query.provider.name(),
+            status.label(),
+            name
+        )
+    });
+    Ok((status.domain_status(), error_message, Some(detail)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_what_the_provider_says() {
+        let github = StatusPageQuery::parse(
+            Provider::Statuspage,
+            "www.githubstatus.com?component=Git+Operations",
+        )
+        .unwrap();
+        assert_eq!(github.component.as_deref(), Some("Git Operations"));
+        assert_eq!(
+            github.components_url(),
+            "https://www.githubstatus.com/api/v2/summary.json"
+        );
+        assert!(StatusPageQuery::parse(Provider::Statuspage, "").is_none());
+        assert!(StatusPageQuery::parse(Provider::Instatus, "acme.instatus.com?page=2").is_none());
+
+        let summary = r#"{
+            "page": {"name": "GitHub"},
+            "status": {"indicator": "minor"},
+            "components": [
+                {"name": "Git Operations", "status": "operational"},
+                {"name": "Actions", "status": "partial_outage"},
+                {"name": "Pages", "status": "something_new"}
+            ]
+        }"#;
+        let components = parse_components(summary).unwrap();
+        assert_eq!(components.len(), 2);
+        assert_eq!(
+            judge(&github, &components),
+            Ok((
+                DomainStatus::Up,
+                None,
+                Some("Git Operations: operational".to_string())
+            ))
+        );
+        let page = StatusPageQuery::parse(Provider::Statuspage, "www.githubstatus.com").unwrap();
+        assert_eq!(
+            judge(&page, &components),
+            Ok((
+                DomainStatus::Degraded,
+                Some("Statuspage reports partial outage for Actions".to_string()),
+                Some("Actions: partial outage".to_string())
+            ))
+        );
+
+        let instatus =
+            StatusPageQuery::parse(Provider::Instatus, "acme.instatus.com?component=api").unwrap();
+        let components =
+            parse_components(r#"{"components": [{"name": "API", "status": "MAJOROUTAGE"}]}"#)
+                .unwrap();
+        assert_eq!(judge(&instatus, &components).unwrap().0, DomainStatus::Down);
+        let missing =
+            StatusPageQuery::parse(Provider::Instatus, "acme.instatus.com?component=CDN").unwrap();
+        assert!(judge(&missing, &components).is_err());
+    }
+}
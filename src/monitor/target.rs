@@ -0,0 +1,485 @@
+use super::dns::DnsQuery;
+use super::feed::FeedQuery;
+use super::file_transfer::{FileTransferQuery, Protocol};
+use super::grpc::GrpcQuery;
+use super::host::HostQuery;
+use super::local_file::LocalFileQuery;
+use super::ntp::NtpQuery;
+use super::ping::is_ping_host;
+use super::redfish::is_bmc_host;
+use super::smtp::SmtpQuery;
+use super::snmp::SnmpQuery;
+use super::ssh::SshQuery;
+use super::status_page::{Provider, StatusPageQuery};
+use crate::utils::is_valid_url;
+use url::Url;
+
+// What a monitor checks. The URL's scheme picks the kind of check, anything
+// without one of these schemes is an HTTP(S) check.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckTarget {
+    Http,
+    // docker://<container name or id>, see docker.rs.
+    Docker {
+        container: String,
+    },
+    // k8s://<namespace>/<label selector>, see kubernetes.rs.
+    Kubernetes {
+        namespace: String,
+        selector: String,
+    },
+    // ping://<host>, see ping.rs.
+    Ping {
+        host: String,
+    },
+    // dns://<name>[/<type>][?resolver=<ip>], see dns.rs.
+    Dns(DnsQuery),
+    // redfish://<bmc host>[/<system id>], see redfish.rs.
+    Redfish {
+        host: String,
+        system: Option<String>,
+    },
+    // snmp://<host>[/<oid>][?min=<n>&max=<n>], see snmp.rs.
+    Snmp(SnmpQuery),
+    // ftp://<host>[/<path>] or sftp://[<user>@]<host>[/<path>], see
+    // file_transfer.rs.
+    FileTransfer(FileTransferQuery),
+    // ntp://<host>[?max_offset_ms=<n>], see ntp.rs.
+    Ntp(NtpQuery),
+    // statuspage://<page host>[?component=<name>] or instatus://..., see
+    // status_page.rs.
+    StatusPage(StatusPageQuery),
+    // feed://<host>/<path>[?max_age_hours=<n>] or feed+http://..., see
+    // feed.rs.
+    Feed(FeedQuery),
+    // file://<absolute path>[?max_age_minutes=<n>], see local_file.rs.
+    LocalFile(LocalFileQuery),
+    // grpc://<host>:<port>[/<service>] or grpcs://..., see grpc.rs.
+    Grpc(GrpcQuery),
+    // host://[<path>][?max_disk_percent=<n>...], the machine upquack runs
+    // on, see host.rs.
+    Host(HostQuery),
+    // smtp://<host>[:port][?ehlo=<name>&starttls=true] or smtps://..., see
+    // smtp.rs.
+    Smtp(SmtpQuery),
+    // ssh://<host>[:port], see ssh.rs.
+    Ssh(SshQuery),
+}
+
+impl CheckTarget {
+    pub fn parse(url: &str) -> Result<CheckTarget, String> {
+        if let Some(container) = url.strip_prefix("docker://") {
+            return if is_container_name(container) {
+                Ok(CheckTarget::Docker {
+                    container: container.to_string(),
+                })
+            } else {
+                Err("Invalid container! (e.g., docker://my-app)".to_string())
+            };
+        }
+        if let Some(rest) = url.strip_prefix("k8s://") {
+            return match rest.split_once('/') {
+                Some((namespace, selector)) if is_namespace(namespace) && is_selector(selector) => {
+                    Ok(CheckTarget::Kubernetes {
+                        namespace: namespace.to_string(),
+                        selector: selector.to_string(),
+                    })
+                }
+                _ => Err("Invalid pod selector! (e.g., k8s://shop/app=web)".to_string()),
+            };
+        }
+        if let Some(host) = url.strip_prefix("ping://") {
+            return if is_ping_host(host) {
+                Ok(CheckTarget::Ping {
+                    host: host.to_string(),
+                })
+            } else {
+                Err("Invalid host! (e.g., ping://10.0.0.1 or ping://router.lan)".to_string())
+            };
+        }
+        if let Some(rest) = url.strip_prefix("dns://") {
+            return DnsQuery::parse(rest).map(CheckTarget::Dns).ok_or_else(|| {
+                "Invalid DNS query! (e.g., dns://example.com/MX?resolver=1.1.1.1, dns://example.com?expect=93.184.216.34)"
+                    .to_string()
+            });
+        }
+        if let Some(rest) = url.strip_prefix("redfish://") {
+            let (host, system) = match rest.split_once('/') {
+                Some((host, system)) => (host, Some(system)),
+                None => (rest, None),
+            };
+            let valid_system = system.is_none_or(|system| {
+                !system.is_empty()
+                    && system
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+            });
+            return if is_bmc_host(host) && valid_system {
+                Ok(CheckTarget::Redfish {
+                    host: host.to_string(),
+                    system: system.map(str::to_string),
+                })
+            } else {
+                Err("Invalid BMC! (e.g., redfish://10.0.0.50 or redfish://bmc.lan/1)".to_string())
+            };
+        }
+        if let Some(rest) = url.strip_prefix("snmp://") {
+            return SnmpQuery::parse(rest).map(CheckTarget::Snmp).ok_or_else(|| {
+                "Invalid SNMP query! (e.g., snmp://switch.lan or snmp://10.0.0.1/1.3.6.1.2.1.1.3.0?min=600)"
+                    .to_string()
+            });
+        }
+        if let Some(rest) = url.strip_prefix("ntp://") {
+            return NtpQuery::parse(rest).map(CheckTarget::Ntp).ok_or_else(|| {
+                "Invalid NTP server! (e.g., ntp://pool.ntp.org or ntp://10.0.0.1?max_offset_ms=50)"
+                    .to_string()
+            });
+        }
+        if let Some(rest) = url.strip_prefix("file://") {
+            return LocalFileQuery::parse(rest)
+                .map(CheckTarget::LocalFile)
+                .ok_or_else(|| {
+                    "Invalid file! (e.g., file:///var/backups/nightly.done?max_age_minutes=1500)"
+                        .to_string()
+                });
+        }
+        if let Some(rest) = url.strip_prefix("ssh://") {
+            return SshQuery::parse(rest).map(CheckTarget::Ssh).ok_or_else(|| {
+                "Invalid SSH server! (e.g., ssh://bastion.example.com or ssh://10.0.0.1:2222)"
+                    .to_string()
+            });
+        }
+        for (scheme, tls) in [("smtp://", false), ("smtps://", true)] {
+            if let Some(rest) = url.strip_prefix(scheme) {
+                return SmtpQuery::parse(rest, tls)
+                    .map(CheckTarget::Smtp)
+                    .ok_or_else(|| {
+                        "Invalid mail server! (e.g., smtp://mx.example.com?starttls=true or smtps://mail.example.com)"
+                            .to_string()
+                    });
+            }
+        }
+        if let Some(rest) = url.strip_prefix("host://") {
+            return HostQuery::parse(rest)
+                .map(CheckTarget::Host)
+                .ok_or_else(|| {
+                    "Invalid host metrics! (e.g., host:// or host:///var/lib?max_disk_percent=80)"
+                        .to_string()
+                });
+        }
+        for (scheme, tls) in [("grpc://", false), ("grpcs://", true)] {
+            if let Some(rest) = url.strip_prefix(scheme) {
+                return GrpcQuery::parse(rest, tls)
+                    .map(CheckTarget::Grpc)
+                    .ok_or_else(|| {
+                        "Invalid gRPC server! (e.g., grpc://10.0.0.5:50051 or grpcs://api.example.com:443/shop.Checkout)"
+                            .to_string()
+                    });
+            }
+        }
+        // feed+http:// first, it starts like feed:// otherwise would.
+        for (scheme, fetched_over) in [("feed+http://", "http"), ("feed://", "https")] {
+            if let Some(rest) = url.strip_prefix(scheme) {
+                return FeedQuery::parse(fetched_over, rest)
+                    .map(CheckTarget::Feed)
+                    .ok_or_else(|| {
+                        "Invalid feed! (e.g., feed://example.com/podcast.xml?max_age_hours=48&warn_age_hours=24)"
+                            .to_string()
+                    });
+            }
+        }
+        for (scheme, provider) in [
+            ("statuspage://", Provider::Statuspage),
+            ("instatus://", Provider::Instatus),
+        ] {
+            if let Some(rest) = url.strip_prefix(scheme) {
+                return StatusPageQuery::parse(provider, rest)
+                    .map(CheckTarget::StatusPage)
+                    .ok_or_else(|| {
+                        "Invalid status page! (e.g., statuspage://www.githubstatus.com?component=Actions or instatus://acme.instatus.com)"
+                            .to_string()
+                    });
+            }
+        }
+        for (scheme, protocol) in [("ftp://", Protocol::Ftp), ("sftp://", Protocol::Sftp)] {
+            if let Some(rest) = url.strip_prefix(scheme) {
+                return FileTransferQuery::parse(protocol, rest)
+                    .map(CheckTarget::FileTransfer)
+                    .ok_or_else(|| {
+                        "Invalid file server! (e.g., ftp://files.lan/incoming/ or sftp://backup@nas.lan/done?max_age_minutes=1440)"
+                            .to_string()
+                    });
+            }
+        }
+        // Addresses too, as service discovery lists targets.
+        if is_valid_url(url) || is_address_url(url) {
+            Ok(CheckTarget::Http)
+        } else {
+            Err(http_problem(url))
+        }
+    }
+
+    // What the add and edit forms call the kind of check, e.g. "Ping".
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CheckTarget::Http => "HTTP",
+            CheckTarget::Docker { .. } => "Docker",
+            CheckTarget::Kubernetes { .. } => "Kubernetes",
+            CheckTarget::Ping { .. } => "Ping",
+            CheckTarget::Dns(_) => "DNS",
+            CheckTarget::Redfish { .. } => "Redfish",
+            CheckTarget::Snmp(_) => "SNMP",
+            CheckTarget::FileTransfer(query) => match query.protocol {
+                Protocol::Ftp => "FTP",
+                Protocol::Sftp => "SFTP",
+            },
+            CheckTarget::Ntp(_) => "NTP",
+            CheckTarget::StatusPage(_) => "Status page",
+            CheckTarget::Feed(_) => "Feed",
+            CheckTarget::LocalFile(_) => "Local file",
+            CheckTarget::Grpc(_) => "gRPC",
+            CheckTarget::Host(_) => "Host metrics",
+            CheckTarget::Smtp(_) => "SMTP",
+            CheckTarget::Ssh(_) => "SSH",
+        }
+    }
+
+    pub fn is_http(&self) -> bool {
+        matches!(self, CheckTarget::Http)
+    }
+}
+
+// An http(s) URL of an IP address, e.g. https://10.0.0.1:8080/health.
+fn is_address_url(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| {
+        matches!(url.scheme(), "http" | "https")
+            && matches!(url.host(), Some(url::Host::Ipv4(_) | url::Host::Ipv6(_)))
+            && url.port() != Some(0)
+    })
+}
+
+// Why a URL with none of the other schemes isn't an HTTP(S) check either.
+fn http_problem(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return "Missing scheme, start with https:// (or docker://, ping://, dns://, ...)"
+            .to_string();
+    };
+    if !matches!(scheme, "http" | "https") {
+        return format!(
+            "Unsupported scheme {}://, use https:// or one of docker, k8s, ping, dns, ...",
+            scheme
+        );
+    }
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    if host.is_empty() || host.starts_with(':') {
+        return "Missing host, e.g. https://example.com".to_string();
+    }
+    match Url::parse(url) {
+        Err(url::ParseError::InvalidPort) => "Unsupported port, use 1 to 65535".to_string(),
+        Ok(url) if url.port() == Some(0) => "Unsupported port, use 1 to 65535".to_string(),
+        Err(e) => format!("Invalid URL: {}", e),
+        Ok(url) => format!(
+            "Unsupported domain {}, it has to end in .com, .org, .net, .io, .dev or the like",
+            url.host_str().unwrap_or_default()
+        ),
+    }
+}
+
+// <host>[:port] of the checks that connect to a host, with IPv6 addresses
+// in brackets, on default_port unless it names one. Never starting with '-',
+// so the commands some checks run can't take it for an option.
+pub fn host_and_port(address: &str, default_port: u16) -> Option<(String, u16)> {
+    let (host, port) = match address.rsplit_once(':') {
+        // One colon is a port, more are an IPv6 address.
+        Some((host, port)) if !host.contains(':') || host.ends_with(']') => {
+            (host, port.parse().ok().filter(|p| *p > 0)?)
+        }
+        _ => (address, default_port),
+    };
+    let valid_host = !host.is_empty()
+        && !host.starts_with('-')
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'));
+    valid_host.then(|| (host.to_string(), port))
+}
+
+// Docker's own rule for names, which ids pass too.
+fn is_container_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+// Namespaces are DNS labels: lowercase alphanumerics and '-', at most 63.
+fn is_namespace(namespace: &str) -> bool {
+    (1..=63).contains(&namespace.len())
+        && namespace
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !namespace.starts_with('-')
+        && !namespace.ends_with('-')
+}
+
+// The API server checks the selector itself, this only keeps out the empty
+// one, which would match every pod in the namespace.
+fn is_selector(selector: &str) -> bool {
+    !selector.is_empty() && !selector.chars().any(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheme_picks_the_kind_of_check() {
+        assert_eq!(
+            CheckTarget::parse("https://example.com"),
+            Ok(CheckTarget::Http)
+        );
+        assert_eq!(
+            CheckTarget::parse("https://[fd00::2]:8080/health"),
+            Ok(CheckTarget::Http)
+        );
+        assert!(CheckTarget::parse("https://10.0.0.1:0/").is_err());
+        assert_eq!(
+            CheckTarget::parse("docker://my-app_1.web"),
+            Ok(CheckTarget::Docker {
+                container: "my-app_1.web".to_string()
+            })
+        );
+        assert!(CheckTarget::parse("docker://").is_err());
+        assert!(CheckTarget::parse("docker://../containers").is_err());
+        assert_eq!(
+            CheckTarget::parse("k8s://shop/app=web,tier!=cache"),
+            Ok(CheckTarget::Kubernetes {
+                namespace: "shop".to_string(),
+                selector: "app=web,tier!=cache".to_string()
+            })
+        );
+        assert!(CheckTarget::parse("k8s://shop").is_err());
+        assert!(CheckTarget::parse("k8s://shop/").is_err());
+        assert!(CheckTarget::parse("k8s://Shop/app=web").is_err());
+        assert_eq!(
+            CheckTarget::parse("ping://10.0.0.1"),
+            Ok(CheckTarget::Ping {
+                host: "10.0.0.1".to_string()
+            })
+        );
+        assert!(CheckTarget::parse("ping://-f").is_err());
+        assert!(matches!(
+            CheckTarget::parse("dns://example.com/MX"),
+            Ok(CheckTarget::Dns(_))
+        ));
+        assert!(CheckTarget::parse("dns://example.com/SPF").is_err());
+        assert_eq!(
+            CheckTarget::parse("redfish://10.0.0.50/System.Embedded.1"),
+            Ok(CheckTarget::Redfish {
+                host: "10.0.0.50".to_string(),
+                system: Some("System.Embedded.1".to_string())
+            })
+        );
+        assert!(CheckTarget::parse("redfish://bmc.lan/").is_err());
+        assert!(matches!(
+            CheckTarget::parse("snmp://switch.lan?min=600"),
+            Ok(CheckTarget::Snmp(SnmpQuery { min: Some(600), .. }))
+        ));
+        assert!(CheckTarget::parse("snmp://switch.lan/sysUpTime").is_err());
+        assert!(matches!(
+            CheckTarget::parse("ftp://files.lan/incoming/"),
+            Ok(CheckTarget::FileTransfer(FileTransferQuery {
+                protocol: Protocol::Ftp,
+                ..
+            }))
+        ));
+        assert!(CheckTarget::parse("ftp://user@example.com").is_err());
+        assert_eq!(
+            CheckTarget::parse("ntp://time.lan?max_offset_ms=50"),
+            Ok(CheckTarget::Ntp(NtpQuery {
+                host: "time.lan".to_string(),
+                max_offset_ms: 50
+            }))
+        );
+        assert!(matches!(
+            CheckTarget::parse("instatus://acme.instatus.com"),
+            Ok(CheckTarget::StatusPage(StatusPageQuery {
+                provider: Provider::Instatus,
+                component: None,
+                ..
+            }))
+        ));
+        assert!(CheckTarget::parse("statuspage://status page").is_err());
+        assert!(matches!(
+            CheckTarget::parse("feed+http://blog.lan/rss"),
+            Ok(CheckTarget::Feed(FeedQuery { url, .. })) if url == "http://blog.lan/rss"
+        ));
+        assert_eq!(
+            CheckTarget::parse("file:///var/backups/nightly.done?max_age_minutes=1500"),
+            Ok(CheckTarget::LocalFile(LocalFileQuery {
+                path: "/var/backups/nightly.done".into(),
+                max_age_minutes: Some(1500)
+            }))
+        );
+        assert!(CheckTarget::parse("file://backups/done").is_err());
+        assert_eq!(
+            CheckTarget::parse("grpcs://api.example.com:443/shop.Checkout"),
+            Ok(CheckTarget::Grpc(GrpcQuery {
+                host: "api.example.com:443".to_string(),
+                service: Some("shop.Checkout".to_string()),
+                tls: true
+            }))
+        );
+        assert!(matches!(
+            CheckTarget::parse("host://?max_memory_percent=80"),
+            Ok(CheckTarget::Host(HostQuery {
+                max_memory_percent: 80,
+                ..
+            }))
+        ));
+        assert_eq!(
+            CheckTarget::parse("smtp://mail.example.com:587?ehlo=monitor.lan&starttls=true"),
+            Ok(CheckTarget::Smtp(SmtpQuery {
+                host: "mail.example.com".to_string(),
+                port: 587,
+                tls: false,
+                ehlo: Some("monitor.lan".to_string()),
+                starttls: true
+            }))
+        );
+        assert_eq!(
+            CheckTarget::parse("ssh://10.0.0.1:2222"),
+            Ok(CheckTarget::Ssh(SshQuery {
+                host: "10.0.0.1".to_string(),
+                port: 2222
+            }))
+        );
+        assert!(CheckTarget::parse("ssh://ops@bastion.example.com").is_err());
+        assert_eq!(
+            CheckTarget::parse("gopher://example.com"),
+            Err(
+                "Unsupported scheme gopher://, use https:// or one of docker, k8s, ping, dns, ..."
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            CheckTarget::parse("https://"),
+            Err("Missing host, e.g. https://example.com".to_string())
+        );
+        assert_eq!(
+            CheckTarget::parse("https://example.com:99999/health"),
+            Err("Unsupported port, use 1 to 65535".to_string())
+        );
+        assert!(
+            CheckTarget::parse("example.com")
+                .unwrap_err()
+                .starts_with("Missing scheme")
+        );
+        assert_eq!(
+            CheckTarget::parse("sftp://nas.lan/backups").map(|t| t.kind()),
+            Ok("SFTP")
+        );
+    }
+}
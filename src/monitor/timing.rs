@@ -0,0 +1,218 @@
+use crate::ui::domains::{CheckStatus, DomainStatus};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, lookup_host};
+use tokio::time::timeout;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, crypto::ring, pki_types::ServerName};
+use tracing::Instrument;
+use url::Url;
+use utoipa::ToSchema;
+
+// Recent response times an outlier is compared against.
+pub const OUTLIER_WINDOW: usize = 20;
+// How many times the median a check has to take to count as an outlier.
+const OUTLIER_FACTOR: u64 = 3;
+// Below this nothing is slow enough to be worth a probe.
+const OUTLIER_MIN_MS: u64 = 200;
+
+// Where the time of a request went. The monitor's own requests reuse pooled
+// connections, so this is measured on a fresh one made right after.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CheckTimings {
+    pub dns_ms: u64,
+    pub connect_ms: u64,
+    // Unset for plain http.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_ms: Option<u64>,
+    // From sending the request until the first byte of the response.
+    pub ttfb_ms: u64,
+}
+
+impl CheckTimings {
+    pub fn total_ms(&self) -> u64 {
+        self.dns_ms + self.connect_ms + self.tls_ms.unwrap_or(0) + self.ttfb_ms
+    }
+
+    // The phase that took more than half of the time, if one did.
+    pub fn hint(&self) -> Option<&'static str> {
+        let phases = [
+            ("slow DNS", self.dns_ms),
+            ("slow connect", self.connect_ms),
+            ("slow TLS", self.tls_ms.unwrap_or(0)),
+            ("slow server", self.ttfb_ms),
+        ];
+        let (hint, ms) = phases.into_iter().max_by_key(|(_, ms)| *ms)?;
+        (ms * 2 > self.total_ms()).then_some(hint)
+    }
+}
+
+// Whether a response time stands out from the domain's recent Up checks.
+pub fn is_latency_outlier(check_history: &[CheckStatus], response_time_ms: u64) -> bool {
+    let mut recent: Vec<u64> = check_history
+        .iter()
+        .rev()
+        .filter(|check| matches!(check.status, DomainStatus::Up))
+        .filter_map(|check| check.response_time_ms)
+        .take(OUTLIER_WINDOW)
+        .collect();
+    // Too little history to tell what's normal.
+    if recent.len() < 5 || response_time_ms < OUTLIER_MIN_MS {
+        return false;
+    }
+    recent.sort_unstable();
+    let median = recent[recent.len() / 2];
+    response_time_ms > median * OUTLIER_FACTOR
+}
+
+// Times each phase of a HEAD request to `url` on a new connection.
+pub async fn measure(url: &str, limit: Duration) -> Result<CheckTimings, String> {
+    timeout(limit, measure_phases(url))
+        .await
+        .map_err(|_| "timed out".to_string())?
+}
+
+async fn measure_phases(url: &str) -> Result<CheckTimings, String> {
+    let url = Url::parse(url).map_err(|e| e.to_string())?;
+    let host = url.host_str().ok_or("URL has no host")?.to_string();
+    let port = url.port_or_known_default().ok_or("URL has no port")?;
+
+    let started = Instant::now();
+    let address = lookup_host((host.as_str(), port))
+        .instrument(tracing::info_span!("dns", host = %host))
+        .await
+        .map_err(|e| format!("DNS: {}", e))?
+        .next()
+        .ok_or("DNS: no addresses")?;
+    let dns_ms = elapsed_ms(started);
+
+    let started = Instant::now();
+    let stream = TcpStream::connect(address)
+        .instrument(tracing::info_span!("connect", address = %address))
+        .await
+        .map_err(|e| format!("connect: {}", e))?;
+    let connect_ms = elapsed_ms(started);
+
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path = format!("{}?{}", path, query);
+    }
+    let request = format!(
+        "HEAD {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nUser-Agent: upquack\r\n\r\n",
+        path, host
+    );
+
+    if url.scheme() != "https" {
+        let ttfb_ms = first_byte_ms(stream, &request).await?;
+        return Ok(CheckTimings {
+            dns_ms,
+            connect_ms,
+            tls_ms: None,
+            ttfb_ms,
+        });
+    }
+
+    let server_name = ServerName::try_from(host).map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    let stream = tls_connector()?
+        .connect(server_name, stream)
+        .instrument(tracing::info_span!("tls"))
+        .await
+        .map_err(|e| format!("TLS: {}", e))?;
+    let tls_ms = elapsed_ms(started);
+
+    Ok(CheckTimings {
+        dns_ms,
+        connect_ms,
+        tls_ms: Some(tls_ms),
+        ttfb_ms: first_byte_ms(stream, &request).await?,
+    })
+}
+
+// The system resolver, in a span, so lookups for new connections show up in
+// traces. Pooled connections skip it.
+pub struct TracedResolver;
+
+impl Resolve for TracedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let span = tracing::info_span!("dns", host = name.as_str());
+        Box::pin(
+            async move {
+                let addresses = lookup_host((name.as_str().to_string(), 0)).await?;
+                Ok(Box::new(addresses) as Addrs)
+            }
+            .instrument(span),
+        )
+    }
+}
+
+pub fn tls_connector() -> Result<TlsConnector, String> {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+#[tracing::instrument(name = "first byte", skip_all)]
+async fn first_byte_ms(
+    mut stream: impl AsyncRead + AsyncWrite + Unpin,
+    request: &str,
+) -> Result<u64, String> {
+    let started = Instant::now();
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| format!("request: {}", e))?;
+    let mut first_byte = [0u8; 1];
+    stream
+        .read_exact(&mut first_byte)
+        .await
+        .map_err(|e| format!("response: {}", e))?;
+    Ok(elapsed_ms(started))
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn hint_names_the_dominant_phase() {
+        let timings = |dns_ms, tls_ms, ttfb_ms| CheckTimings {
+            dns_ms,
+            connect_ms: 20,
+            tls_ms: Some(tls_ms),
+            ttfb_ms,
+        };
+        assert_eq!(timings(900, 50, 100).hint(), Some("slow DNS"));
+        assert_eq!(timings(10, 30, 2000).hint(), Some("slow server"));
+        // Evenly spread, nothing to blame.
+        assert_eq!(timings(300, 300, 300).hint(), None);
+    }
+
+    #[test]
+    fn outliers_are_judged_against_recent_checks() {
+        let history: Vec<CheckStatus> = (0..10)
+            .map(|_| CheckStatus {
+                response_time_ms: Some(100),
+                ..CheckStatus::new(Utc::now(), DomainStatus::Up)
+            })
+            .collect();
+
+        assert!(is_latency_outlier(&history, 400));
+        assert!(!is_latency_outlier(&history, 250));
+        assert!(!is_latency_outlier(&history[..3], 400));
+    }
+}
@@ -0,0 +1,166 @@
+use super::target::CheckTarget;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+use url::Url;
+use utoipa::ToSchema;
+
+// Hops probed before giving up, one probe each and a second to answer.
+const MAX_HOPS: u8 = 20;
+
+// How long the whole trace may take.
+pub const TRACE_LIMIT: Duration = Duration::from_secs(30);
+
+// One router on the way to the host, kept with the check that went down.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct TraceHop {
+    pub ttl: u8,
+    // Unset when the hop didn't answer in time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rtt_ms: Option<f64>,
+}
+
+// The host a monitor's packets go to, for the checks that go over the
+// network. Docker, Kubernetes, local and DNS checks have none to trace.
+pub fn trace_host(url: &str) -> Option<String> {
+    match CheckTarget::parse(url).ok()? {
+        CheckTarget::Docker { .. }
+        | CheckTarget::Kubernetes { .. }
+        | CheckTarget::LocalFile(_)
+        | CheckTarget::Host(_)
+        | CheckTarget::Dns(_) => None,
+        _ => Url::parse(url)
+            .ok()?
+            .host_str()
+            .map(|host| host.trim_matches(['[', ']']).to_string()),
+    }
+}
+
+// Runs the system's traceroute (tracert on Windows), which doesn't need the
+// raw socket privileges sending the probes ourselves would.
+#[tracing::instrument(name = "traceroute")]
+pub async fn trace(host: &str) -> Result<Vec<TraceHop>, String> {
+    if host.starts_with('-') {
+        return Err(format!("Invalid host {}", host));
+    }
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("tracert");
+        command.args(["-d", "-w", "1000", "-h", &MAX_HOPS.to_string()]);
+        command
+    } else {
+        let mut command = Command::new("traceroute");
+        command.args(["-n", "-q", "1", "-w", "1", "-m", &MAX_HOPS.to_string()]);
+        command
+    };
+    let mut child = command
+        .arg(host)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run traceroute: {}", e))?;
+
+    // Read line by line, so the hops found before the time limit are kept.
+    let mut stdout = String::new();
+    let mut lines = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+    let read = async {
+        while let Ok(Some(line)) = lines.next_line().await {
+            stdout.push_str(&line);
+            stdout.push('\n');
+        }
+    };
+    let finished = tokio::time::timeout(TRACE_LIMIT, read).await.is_ok();
+
+    let mut stderr = String::new();
+    if finished {
+        if let Some(mut pipe) = child.stderr.take() {
+            let _ = pipe.read_to_string(&mut stderr).await;
+        }
+        let _ = child.wait().await;
+    } else {
+        let _ = child.kill().await;
+    }
+
+    let hops = parse_hops(&stdout);
+    match (hops.is_empty(), finished) {
+        (false, _) => Ok(hops),
+        (true, true) => Err(stderr.trim().to_string()),
+        (true, false) => Err("traceroute didn't finish in time".to_string()),
+    }
+}
+
+// traceroute's " 2  10.0.0.1  3.512 ms" and tracert's
+// "  2    <1 ms    <1 ms    <1 ms  10.0.0.1", "*" for hops that didn't answer.
+fn parse_hops(output: &str) -> Vec<TraceHop> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace().peekable();
+            let ttl = words.next()?.parse().ok()?;
+            let mut address = None;
+            let mut rtt_ms = None;
+            while let Some(word) = words.next() {
+                if let Ok(ip) = word.parse::<IpAddr>() {
+                    address.get_or_insert(ip.to_string());
+                } else if rtt_ms.is_none() && words.peek() == Some(&"ms") {
+                    rtt_ms = word.trim_start_matches('<').parse().ok();
+                }
+            }
+            Some(TraceHop {
+                ttl,
+                address,
+                rtt_ms,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_hops() {
+        let traceroute = "traceroute to example.com (93.184.215.14), 20 hops max, 60 byte packets\n \
+                          1  192.168.1.1  0.512 ms\n \
+                          2  *\n \
+                          3  93.184.215.14  12.840 ms\n";
+        assert_eq!(
+            parse_hops(traceroute),
+            [
+                TraceHop {
+                    ttl: 1,
+                    address: Some("192.168.1.1".to_string()),
+                    rtt_ms: Some(0.512)
+                },
+                TraceHop {
+                    ttl: 2,
+                    address: None,
+                    rtt_ms: None
+                },
+                TraceHop {
+                    ttl: 3,
+                    address: Some("93.184.215.14".to_string()),
+                    rtt_ms: Some(12.84)
+                },
+            ]
+        );
+        let tracert = "Tracing route to 10.0.0.1 over a maximum of 20 hops\r\n\r\n  \
+                       1    <1 ms    <1 ms    <1 ms  10.0.0.1\r\n\r\nTrace complete.\r\n";
+        assert_eq!(parse_hops(tracert)[0].rtt_ms, Some(1.0));
+
+        assert_eq!(
+            trace_host("https://api.example.com:8443/health").as_deref(),
+            Some("api.example.com")
+        );
+        assert_eq!(trace_host("ping://10.0.0.1").as_deref(), Some("10.0.0.1"));
+        assert_eq!(trace_host("docker://postgres"), None);
+        assert_eq!(trace_host("dns://example.com/MX"), None);
+    }
+}
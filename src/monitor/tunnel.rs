@@ -0,0 +1,185 @@
+use reqwest::{Client, Proxy};
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+use tokio::time::{Instant, sleep};
+
+// How long ssh gets to log in and open its SOCKS port.
+const TUNNEL_STARTUP: Duration = Duration::from_secs(15);
+
+// One `ssh -N -D` process per jump host, started by the first check that
+// needs it and restarted by the next check after it dies. Dropping the
+// tunnels kills the processes.
+#[derive(Debug, Default)]
+pub struct SshTunnels {
+    // A slot per jump host, locked while its tunnel starts so checks through
+    // it don't each start their own. Checks through other jump hosts don't
+    // wait for it.
+    tunnels: std::sync::Mutex<HashMap<String, Arc<Mutex<Option<Tunnel>>>>>,
+}
+
+#[derive(Debug)]
+struct Tunnel {
+    process: Child,
+    // Sends requests through the tunnel's SOCKS5 port.
+    client: Client,
+}
+
+impl SshTunnels {
+    pub async fn client(&self, jump_host: &str) -> Result<Client, String> {
+        let slot = {
+            let mut tunnels = self.tunnels.lock().unwrap();
+            Arc::clone(tunnels.entry(jump_host.to_string()).or_default())
+        };
+        let mut slot = slot.lock().await;
+        if let Some(tunnel) = slot.as_mut() {
+            match tunnel.process.try_wait() {
+                Ok(None) => return Ok(tunnel.client.clone()),
+                Ok(Some(status)) => {
+                    tracing::debug!(
+                        "SSH tunnel via {} exited ({}), restarting",
+                        jump_host,
+                        status
+                    )
+                }
+                Err(e) => tracing::error!("Error checking the SSH tunnel via {}: {}", jump_host, e),
+            }
+            *slot = None;
+        }
+
+        let tunnel = Tunnel::open(jump_host).await?;
+        let client = tunnel.client.clone();
+        *slot = Some(tunnel);
+        Ok(client)
+    }
+}
+
+impl Tunnel {
+    async fn open(jump_host: &str) -> Result<Tunnel, String> {
+        let port = free_port().map_err(|e| format!("no free local port: {}", e))?;
+        let mut process = Command::new("ssh")
+            .arg("-N")
+            .args(["-D", &format!("127.0.0.1:{}", port)])
+            // Never prompt, there's no terminal to answer on while the TUI runs.
+            .args(["-o", "BatchMode=yes"])
+            .args(["-o", "ExitOnForwardFailure=yes"])
+            // Notice a dead connection within a minute, the next check restarts it.
+            .args(["-o", "ServerAliveInterval=15"])
+            .args(["-o", "ServerAliveCountMax=3"])
+            .arg("--")
+            .arg(jump_host)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("starting ssh: {}", e))?;
+
+        let started = Instant::now();
+        loop {
+            if let Some(status) = process.try_wait().map_err(|e| e.to_string())? {
+                let mut stderr = String::new();
+                if let Some(mut pipe) = process.stderr.take() {
+                    let _ = pipe.read_to_string(&mut stderr).await;
+                }
+                return Err(format!("ssh exited ({}): {}", status, stderr.trim()));
+            }
+            if TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+                break;
+            }
+            if started.elapsed() > TUNNEL_STARTUP {
+                return Err(format!(
+                    "ssh didn't open the tunnel within {}s",
+                    TUNNEL_STARTUP.as_secs()
+                ));
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        tracing::debug!("SSH tunnel via {} listening on port {}", jump_host, port);
+
+        // socks5h so the jump host resolves the names, internal ones included.
+        let proxy =
+            Proxy::all(format!("socks5h://127.0.0.1:{}", port)).map_err(|e| e.to_string())?;
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .proxy(proxy)
+            // Followed by hand, see follow_redirects.
+            .redirect(reqwest::redirect::Policy::none())
+            .http1_only()
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Tunnel { process, client })
+    }
+}
+
+// A port nothing listens on right now, for ssh to take.
+fn free_port() -> std::io::Result<u16> {
+    Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+// Jump hosts are passed to ssh as the destination, e.g. "bastion",
+// "ops@bastion.example.com" or "ssh://ops@bastion.example.com:2222".
+pub fn validate_jump_host(jump_host: &str) -> Result<(), String> {
+    if jump_host.is_empty() {
+        return Err("SSH jump host is empty".to_string());
+    }
+    if jump_host.starts_with('-') || jump_host.chars().any(char::is_whitespace) {
+        return Err(format!("Invalid SSH jump host \"{}\"", jump_host));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jump_hosts_cant_pass_options() {
+        assert!(validate_jump_host("bastion").is_ok());
+        assert!(validate_jump_host("ssh://ops@bastion.example.com:2222").is_ok());
+        assert!(validate_jump_host("").is_err());
+        assert!(validate_jump_host("-oProxyCommand=touch /tmp/x").is_err());
+        assert!(validate_jump_host("ops@bastion -p 2222").is_err());
+    }
+
+    #[tokio::test]
+    async fn failing_ssh_is_reported() {
+        // Port 1 is never an SSH server, and BatchMode keeps ssh from asking.
+        let error = SshTunnels::default()
+            .client("ssh://nobody@127.0.0.1:1")
+            .await
+            .expect_err("There's no SSH server on port 1");
+        assert!(
+            error.starts_with("ssh exited") || error.starts_with("starting ssh"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn a_starting_tunnel_doesnt_hold_up_other_jump_hosts() {
+        let tunnels = SshTunnels::default();
+        let bastion = Arc::clone(
+            tunnels
+                .tunnels
+                .lock()
+                .unwrap()
+                .entry("bastion".to_string())
+                .or_default(),
+        );
+        let _starting = bastion.lock().await;
+
+        let other = tokio::time::timeout(
+            Duration::from_secs(10),
+            tunnels.client("ssh://nobody@127.0.0.1:1"),
+        )
+        .await;
+        assert!(other.is_ok(), "Waited for the bastion's tunnel");
+    }
+}
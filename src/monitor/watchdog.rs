@@ -0,0 +1,153 @@
+use super::MonitorEvent;
+use crate::configuration::MonitorSettings;
+use chrono::{DateTime, Utc};
+use std::{collections::HashMap, io, sync::Mutex};
+use uuid::Uuid;
+
+// Watches the saves after every check. A failing disk (full, read-only, no
+// permission) would otherwise only show up in the error log, so after
+// failure_threshold failures in a row it raises an alert. The results that
+// weren't saved stay in the domains' history, and are saved with it once a
+// save works again unless the history's retention dropped them meanwhile.
+#[derive(Debug)]
+pub struct PersistenceWatchdog {
+    failure_threshold: usize,
+    state: Mutex<WatchdogState>,
+}
+
+#[derive(Debug, Default)]
+struct WatchdogState {
+    consecutive_failures: usize,
+    // Per domain, when its first unsaved check was made and how many there
+    // are since.
+    unsaved: HashMap<Uuid, (DateTime<Utc>, usize)>,
+}
+
+impl Default for PersistenceWatchdog {
+    fn default() -> Self {
+        PersistenceWatchdog::new(MonitorSettings::default().save_failure_threshold)
+    }
+}
+
+impl PersistenceWatchdog {
+    pub fn new(failure_threshold: usize) -> Self {
+        PersistenceWatchdog {
+            failure_threshold: failure_threshold.max(1),
+            state: Mutex::new(WatchdogState::default()),
+        }
+    }
+
+    // Takes the outcome of saving a check, returns the event to raise if
+    // saving just started or stopped failing. On recovery, held(domain, since)
+    // counts the domain's checks in the history from since on, other than
+    // the one just saved, which are on disk now.
+    pub fn record_save(
+        &self,
+        domain_id: Uuid,
+        checked_at: DateTime<Utc>,
+        result: &io::Result<()>,
+        held: impl Fn(Uuid, DateTime<Utc>) -> Option<usize>,
+    ) -> Option<MonitorEvent> {
+        let mut state = self.state.lock().unwrap();
+        match result {
+            Ok(()) => {
+                let was_failing = state.consecutive_failures >= self.failure_threshold;
+                let recovered = std::mem::take(&mut *state);
+                if !was_failing {
+                    return None;
+                }
+                let (mut saved, mut dropped) = (0, 0);
+                for (domain_id, (since, unsaved)) in recovered.unsaved {
+                    // Results of a domain removed meanwhile count as neither.
+                    let Some(held) = held(domain_id, since) else {
+                        continue;
+                    };
+                    saved += held.min(unsaved);
+                    dropped += unsaved.saturating_sub(held);
+                }
+                Some(MonitorEvent::PersistenceRecovered {
+                    failures: recovered.consecutive_failures,
+                    saved,
+                    dropped,
+                })
+            }
+            Err(e) => {
+                state.consecutive_failures += 1;
+                state.unsaved.entry(domain_id).or_insert((checked_at, 0)).1 += 1;
+                (state.consecutive_failures == self.failure_threshold).then(|| {
+                    MonitorEvent::PersistenceFailing {
+                        failures: state.consecutive_failures,
+                        error: e.to_string(),
+                    }
+                })
+            }
+        }
+    }
+
+    // How many results are waiting for a save that works, None while saving
+    // hasn't failed often enough to raise the alert.
+    pub fn unsaved_while_failing(&self) -> Option<usize> {
+        let state = self.state.lock().unwrap();
+        (state.consecutive_failures >= self.failure_threshold)
+            .then(|| state.unsaved.values().map(|(_, count)| count).sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn alerts_after_repeated_failures_and_on_recovery() {
+        let watchdog = PersistenceWatchdog::new(3);
+        let id = Uuid::new_v4();
+        let start = Utc::now();
+        let at = |seconds: i64| start + Duration::seconds(seconds);
+        let failed = Err(io::Error::other("No space left on device"));
+        let untouched = |_: Uuid, _: DateTime<Utc>| -> Option<usize> { unreachable!() };
+
+        assert!(
+            watchdog
+                .record_save(id, at(0), &failed, untouched)
+                .is_none()
+        );
+        assert!(
+            watchdog
+                .record_save(id, at(1), &failed, untouched)
+                .is_none()
+        );
+        assert_eq!(watchdog.unsaved_while_failing(), None);
+        assert!(matches!(
+            watchdog.record_save(id, at(2), &failed, untouched),
+            Some(MonitorEvent::PersistenceFailing { failures: 3, .. })
+        ));
+        // Raised once, not on every failure after.
+        assert!(
+            watchdog
+                .record_save(id, at(3), &failed, untouched)
+                .is_none()
+        );
+        assert_eq!(watchdog.unsaved_while_failing(), Some(4));
+
+        // The retention kept the last two of the four.
+        let held = |domain_id: Uuid, since: DateTime<Utc>| {
+            assert_eq!((domain_id, since), (id, start));
+            Some(2)
+        };
+        assert!(matches!(
+            watchdog.record_save(id, at(4), &Ok(()), held),
+            Some(MonitorEvent::PersistenceRecovered {
+                failures: 4,
+                saved: 2,
+                dropped: 2
+            })
+        ));
+        assert_eq!(watchdog.unsaved_while_failing(), None);
+        assert!(
+            watchdog
+                .record_save(id, at(5), &Ok(()), untouched)
+                .is_none()
+        );
+    }
+}
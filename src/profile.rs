@@ -0,0 +1,174 @@
+use crate::store::FILE_PATH;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+static DEFAULT_CONFIG_PATH: &str = "configuration/production.toml";
+static PROFILE_CONFIG_DIR: &str = "configuration/profiles";
+static STORE_DIR: &str = "db";
+static LOG_DIR: &str = "log";
+static PROFILE_STORE_DIR: &str = "profiles";
+
+// A separate set of monitors with its own configuration. The default profile
+// keeps using the original paths so existing installs don't have to move
+// anything; named ones live under configuration/profiles/<name>.toml and
+// db/profiles/<name>/, logs included.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    name: Option<String>,
+}
+
+impl Profile {
+    pub fn named(name: &str) -> io::Result<Self> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Invalid profile name \"{}\", use letters, digits, - and _",
+                    name
+                ),
+            ));
+        }
+
+        Ok(Profile {
+            name: Some(name.to_string()),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("default")
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        match &self.name {
+            Some(name) => Path::new(PROFILE_CONFIG_DIR).join(format!("{}.toml", name)),
+            None => PathBuf::from(DEFAULT_CONFIG_PATH),
+        }
+    }
+
+    pub fn store_path(&self) -> String {
+        match &self.name {
+            Some(name) => profile_store_dir()
+                .join(name)
+                .join("domains.json")
+                .to_string_lossy()
+                .into_owned(),
+            None => FILE_PATH.to_string(),
+        }
+    }
+
+    pub fn secrets_path(&self) -> PathBuf {
+        self.data_dir().join("secrets.json")
+    }
+
+    pub fn debug_log_path(&self) -> PathBuf {
+        self.log_dir().join("debug.log")
+    }
+
+    pub fn error_log_path(&self) -> PathBuf {
+        self.log_dir().join("error.log")
+    }
+
+    // Named profiles keep their logs next to their store, so two running at
+    // once don't overwrite each other's.
+    fn log_dir(&self) -> PathBuf {
+        match &self.name {
+            Some(_) => self.data_dir().join(LOG_DIR),
+            None => PathBuf::from(LOG_DIR),
+        }
+    }
+
+    // Where the profile's store, secrets and crash bundles go.
+    pub fn data_dir(&self) -> PathBuf {
+        match &self.name {
+            Some(name) => profile_store_dir().join(name),
+            None => PathBuf::from(STORE_DIR),
+        }
+    }
+}
+
+// Joined rather than written as "db/profiles" so paths shown to the user use
+// the platform's separator; Windows accepts "/" but the mix reads badly.
+fn profile_store_dir() -> PathBuf {
+    Path::new(STORE_DIR).join(PROFILE_STORE_DIR)
+}
+
+// The default profile followed by every named profile that has a config file
+// or a store, sorted by name.
+pub fn list_profiles() -> Vec<Profile> {
+    let mut names = Vec::new();
+
+    let config_names = read_dir_names(PROFILE_CONFIG_DIR)
+        .into_iter()
+        .filter_map(|file| file.strip_suffix(".toml").map(str::to_string));
+    names.extend(config_names);
+    names.extend(read_dir_names(profile_store_dir()));
+
+    names.sort();
+    names.dedup();
+
+    let mut profiles = vec![Profile::default()];
+    profiles.extend(names.iter().filter_map(|name| Profile::named(name).ok()));
+    profiles
+}
+
+fn read_dir_names(dir: impl AsRef<Path>) -> Vec<String> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_profile_keeps_original_paths() {
+        let profile = Profile::default();
+        assert_eq!(profile.name(), "default");
+        assert_eq!(profile.config_path(), Path::new(DEFAULT_CONFIG_PATH));
+        assert_eq!(profile.store_path(), FILE_PATH);
+        assert_eq!(profile.debug_log_path(), Path::new("log").join("debug.log"));
+        assert_eq!(profile.error_log_path(), Path::new("log").join("error.log"));
+    }
+
+    #[test]
+    fn named_profiles_get_their_own_paths() {
+        let profile = Profile::named("work").expect("Valid profile name");
+        assert_eq!(
+            profile.config_path(),
+            Path::new("configuration/profiles/work.toml")
+        );
+        let store_dir: PathBuf = ["db", "profiles", "work"].iter().collect();
+        assert_eq!(
+            Path::new(&profile.store_path()),
+            store_dir.join("domains.json")
+        );
+        assert_eq!(profile.secrets_path(), store_dir.join("secrets.json"));
+        assert_eq!(
+            profile.debug_log_path(),
+            store_dir.join("log").join("debug.log")
+        );
+        assert_eq!(
+            profile.error_log_path(),
+            store_dir.join("log").join("error.log")
+        );
+    }
+
+    #[test]
+    fn profile_names_cannot_escape_their_directory() {
+        assert!(Profile::named("../work").is_err());
+        assert!(Profile::named("").is_err());
+        assert!(Profile::named("side_project-2").is_ok());
+    }
+}
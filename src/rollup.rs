@@ -0,0 +1,177 @@
+use crate::ui::domains::{CheckStatus, DomainStatus, PeriodStats};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// How many days of rollups a domain keeps, a little over a year.
+pub const ROLLUP_DAYS: i64 = 400;
+
+// Lower bounds of the latency buckets in ms, the last one is open ended.
+pub const LATENCY_BUCKETS_MS: [u64; 6] = [0, 100, 300, 1000, 3000, 10000];
+
+// What one UTC day of a domain's checks came to. The check history only
+// keeps the last checks (100 by default), so anything looking back days or
+// weeks reads these instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DailyRollup {
+    pub day: NaiveDate,
+    // Up or Degraded.
+    pub up: u32,
+    // Down or Error.
+    pub down: u32,
+    pub rate_limited: u32,
+    pub response_time_total_ms: u64,
+    pub timed_checks: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_response_time_ms: Option<u64>,
+    // Response times counted per LATENCY_BUCKETS_MS bucket.
+    pub latency_buckets: [u32; LATENCY_BUCKETS_MS.len()],
+}
+
+impl DailyRollup {
+    pub fn new(day: NaiveDate) -> Self {
+        DailyRollup {
+            day,
+            up: 0,
+            down: 0,
+            rate_limited: 0,
+            response_time_total_ms: 0,
+            timed_checks: 0,
+            max_response_time_ms: None,
+            latency_buckets: [0; LATENCY_BUCKETS_MS.len()],
+        }
+    }
+
+    pub fn add(&mut self, check: &CheckStatus) {
+        match check.status {
+            DomainStatus::Up | DomainStatus::Degraded => self.up += 1,
+            DomainStatus::Down | DomainStatus::Error(_) => self.down += 1,
+            DomainStatus::RateLimited => self.rate_limited += 1,
+            DomainStatus::Unknown => {}
+        }
+        if let Some(response_time_ms) = check.response_time_ms {
+            self.response_time_total_ms += response_time_ms;
+            self.timed_checks += 1;
+            self.max_response_time_ms = self.max_response_time_ms.max(Some(response_time_ms));
+            self.latency_buckets[latency_bucket(response_time_ms)] += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &DailyRollup) {
+        self.up += other.up;
+        self.down += other.down;
+        self.rate_limited += other.rate_limited;
+        self.response_time_total_ms += other.response_time_total_ms;
+        self.timed_checks += other.timed_checks;
+        self.max_response_time_ms = self.max_response_time_ms.max(other.max_response_time_ms);
+        for (bucket, count) in self.latency_buckets.iter_mut().zip(other.latency_buckets) {
+            *bucket += count;
+        }
+    }
+
+    // Unknown results aren't counted.
+    pub fn checks(&self) -> u32 {
+        self.up + self.down + self.rate_limited
+    }
+
+    // Counts the same way as MonitoredDomain::uptime_percentage.
+    pub fn stats(&self, count_rate_limited_as_down: bool) -> PeriodStats {
+        let down = self.down
+            + if count_rate_limited_as_down {
+                self.rate_limited
+            } else {
+                0
+            };
+        let total = self.up + down;
+        PeriodStats {
+            uptime_percentage: (total > 0).then(|| self.up as f64 / total as f64 * 100.0),
+            average_response_time_ms: (self.timed_checks > 0)
+                .then(|| self.response_time_total_ms as f64 / self.timed_checks as f64),
+        }
+    }
+}
+
+pub fn latency_bucket(response_time_ms: u64) -> usize {
+    LATENCY_BUCKETS_MS.partition_point(|&from| from <= response_time_ms) - 1
+}
+
+// Adds the check to its day, rollups are kept oldest first.
+pub fn roll_up(rollups: &mut Vec<DailyRollup>, check: &CheckStatus) {
+    let day = check.timestamp.date_naive();
+    let at = match rollups.binary_search_by_key(&day, |rollup| rollup.day) {
+        Ok(at) => at,
+        Err(at) => {
+            rollups.insert(at, DailyRollup::new(day));
+            at
+        }
+    };
+    rollups[at].add(check);
+}
+
+// The days in [from, to] added up, under `from`'s date.
+pub fn total(rollups: &[DailyRollup], from: NaiveDate, to: NaiveDate) -> DailyRollup {
+    let mut total = DailyRollup::new(from);
+    for rollup in rollups.iter().filter(|r| r.day >= from && r.day <= to) {
+        total.merge(rollup);
+    }
+    total
+}
+
+// Drops the days older than ROLLUP_DAYS.
+pub fn compact(rollups: &mut Vec<DailyRollup>, now: DateTime<Utc>) {
+    let oldest = (now - Duration::days(ROLLUP_DAYS)).date_naive();
+    let expired = rollups.partition_point(|rollup| rollup.day < oldest);
+    rollups.drain(0..expired);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn check(day: u32, status: DomainStatus, response_time_ms: Option<u64>) -> CheckStatus {
+        let timestamp = Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap();
+        CheckStatus {
+            response_time_ms,
+            ..CheckStatus::new(timestamp, status)
+        }
+    }
+
+    #[test]
+    fn checks_are_summed_per_day() {
+        let mut rollups = Vec::new();
+        for check in [
+            check(2, DomainStatus::Up, Some(50)),
+            check(1, DomainStatus::Down, None),
+            check(2, DomainStatus::RateLimited, Some(150)),
+            check(2, DomainStatus::Degraded, Some(2000)),
+        ] {
+            roll_up(&mut rollups, &check);
+        }
+
+        let days: Vec<NaiveDate> = rollups.iter().map(|r| r.day).collect();
+        assert_eq!(
+            days,
+            [
+                NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 5, 2).unwrap()
+            ]
+        );
+        assert_eq!(rollups[1].latency_buckets, [1, 1, 0, 1, 0, 0]);
+        assert_eq!(rollups[1].max_response_time_ms, Some(2000));
+
+        let both = total(&rollups, rollups[0].day, rollups[1].day);
+        assert_eq!(both.stats(false).uptime_percentage, Some(2.0 / 3.0 * 100.0));
+        assert_eq!(both.stats(true).uptime_percentage, Some(50.0));
+        assert_eq!(
+            both.stats(false).average_response_time_ms,
+            Some(2200.0 / 3.0)
+        );
+
+        compact(
+            &mut rollups,
+            Utc.with_ymd_and_hms(2025, 6, 6, 0, 0, 0).unwrap(),
+        );
+        assert_eq!(rollups.len(), 1);
+    }
+}
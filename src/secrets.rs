@@ -0,0 +1,393 @@
+use crate::profile::Profile;
+use argon2::Argon2;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chacha20poly1305::{
+    ChaCha20Poly1305, Key, KeyInit, Nonce,
+    aead::{Aead, Payload},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, IsTerminal};
+use std::{fs, io, path::Path, path::PathBuf};
+
+#[cfg(feature = "keyring")]
+static KEYRING_SERVICE: &str = "upquack";
+static PASSPHRASE_ENV: &str = "UPQUACK_SECRETS_PASSPHRASE";
+
+// Config values written as "secret:<name>" are looked up in the secrets store.
+static SECRET_PREFIX: &str = "secret:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum KeySource {
+    // A random key kept in the OS keyring (Keychain, Credential Manager or
+    // the Secret Service on Linux).
+    Keyring,
+    // A key derived from UPQUACK_SECRETS_PASSPHRASE or a prompted passphrase,
+    // for machines without a keyring.
+    Passphrase,
+}
+
+// What ends up on disk. Only the names of the secrets are readable without
+// the key, so `secret list` doesn't need to unlock anything.
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedFile {
+    key_source: KeySource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<String>,
+    names: Vec<String>,
+    nonce: String,
+    ciphertext: String,
+}
+
+pub struct SecretStore {
+    path: PathBuf,
+    key: Key,
+    key_source: KeySource,
+    salt: Option<Vec<u8>>,
+    secrets: BTreeMap<String, String>,
+}
+
+impl SecretStore {
+    // Opens the profile's secrets, creating an empty store (and its key) the
+    // first time. New stores use the keyring unless a passphrase is set in the
+    // environment.
+    pub fn open(profile: &Profile) -> io::Result<Self> {
+        Self::open_prompting(profile, true)
+    }
+
+    // Like open, but a passphrase is only prompted for when `prompt` is set.
+    fn open_prompting(profile: &Profile, prompt: bool) -> io::Result<Self> {
+        let path = profile.secrets_path();
+        let key_source = match read_encrypted_file(&path)? {
+            Some(file) => file.key_source,
+            None if !cfg!(feature = "keyring") || std::env::var_os(PASSPHRASE_ENV).is_some() => {
+                KeySource::Passphrase
+            }
+            None => KeySource::Keyring,
+        };
+
+        match key_source {
+            KeySource::Keyring => {
+                let key = keyring_key(profile)?;
+                Self::open_with_key(&path, key, KeySource::Keyring)
+            }
+            KeySource::Passphrase => Self::open_with_passphrase(&path, &passphrase(prompt)?),
+        }
+    }
+
+    fn open_with_passphrase(path: &Path, passphrase: &str) -> io::Result<Self> {
+        let salt = match read_encrypted_file(path)?.and_then(|file| file.salt) {
+            Some(salt) => decode(&salt)?,
+            None => rand::random::<[u8; 16]>().to_vec(),
+        };
+
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+
+        let mut store = Self::open_with_key(path, key, KeySource::Passphrase)?;
+        store.salt = Some(salt);
+        Ok(store)
+    }
+
+    fn open_with_key(path: &Path, key: Key, key_source: KeySource) -> io::Result<Self> {
+        let secrets = match read_encrypted_file(path)? {
+            Some(file) => {
+                let nonce = decode(&file.nonce)?;
+                let plaintext = ChaCha20Poly1305::new(&key)
+                    .decrypt(
+                        Nonce::from_slice(&nonce),
+                        Payload {
+                            msg: &decode(&file.ciphertext)?,
+                            aad: &associated_data(&file.names),
+                        },
+                    )
+                    .map_err(|_| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "Could not decrypt secrets, wrong key or passphrase?",
+                        )
+                    })?;
+                serde_json::from_slice(&plaintext)?
+            }
+            None => BTreeMap::new(),
+        };
+
+        Ok(SecretStore {
+            path: path.to_path_buf(),
+            key,
+            key_source,
+            salt: None,
+            secrets,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.secrets.get(name).map(String::as_str)
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) {
+        self.secrets.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.secrets.remove(name).is_some()
+    }
+
+    // Encrypts with a fresh nonce on every save.
+    pub fn save(&self) -> io::Result<()> {
+        let names: Vec<String> = self.secrets.keys().cloned().collect();
+        let nonce = rand::random::<[u8; 12]>();
+        let ciphertext = ChaCha20Poly1305::new(&self.key)
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &serde_json::to_vec(&self.secrets)?,
+                    aad: &associated_data(&names),
+                },
+            )
+            .map_err(|e| io::Error::other(e.to_string()))?;
+
+        let file = EncryptedFile {
+            key_source: self.key_source,
+            salt: self.salt.as_ref().map(|salt| STANDARD.encode(salt)),
+            names,
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&file)?)
+    }
+}
+
+// Resolves "secret:<name>" config values, opening the store only when the
+// first reference shows up so configs without secrets never touch the keyring.
+// A store that failed to open isn't tried again.
+#[derive(Default)]
+pub struct SecretResolver {
+    profile: Profile,
+    // Off once the TUI owns the terminal.
+    prompt: bool,
+    store: Option<Result<SecretStore, String>>,
+}
+
+impl SecretResolver {
+    pub fn new(profile: &Profile) -> Self {
+        SecretResolver {
+            profile: profile.clone(),
+            prompt: true,
+            store: None,
+        }
+    }
+
+    pub fn with_prompt(mut self, prompt: bool) -> Self {
+        self.prompt = prompt;
+        self
+    }
+
+    pub fn is_secret(value: &str) -> bool {
+        value.starts_with(SECRET_PREFIX)
+    }
+
+    pub fn resolve(&mut self, value: &str) -> io::Result<String> {
+        let Some(name) = value.strip_prefix(SECRET_PREFIX) else {
+            return Ok(value.to_string());
+        };
+
+        let store = self.store.get_or_insert_with(|| {
+            SecretStore::open_prompting(&self.profile, self.prompt).map_err(|e| e.to_string())
+        });
+        let store = store.as_ref().map_err(|e| io::Error::other(e.clone()))?;
+        store.get(name).map(str::to_string).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "Unknown secret \"{}\", add it with `upquack secret set {}`",
+                    name, name
+                ),
+            )
+        })
+    }
+}
+
+pub fn run_set_secret(profile: &Profile, name: &str) -> io::Result<()> {
+    let value = if io::stdin().is_terminal() {
+        rpassword::prompt_password(format!("Value for {}: ", name))?
+    } else {
+        let mut value = String::new();
+        io::stdin().lock().read_line(&mut value)?;
+        value.trim_end_matches(['\r', '\n']).to_string()
+    };
+
+    let mut store = SecretStore::open(profile)?;
+    store.set(name, &value);
+    store.save()?;
+    println!(
+        "Saved secret {}, reference it as \"{}{}\".",
+        name, SECRET_PREFIX, name
+    );
+    Ok(())
+}
+
+pub fn run_list_secrets(profile: &Profile) -> io::Result<()> {
+    let names = read_encrypted_file(&profile.secrets_path())?
+        .map(|file| file.names)
+        .unwrap_or_default();
+
+    if names.is_empty() {
+        println!("No secrets stored for the {} profile.", profile.name());
+    }
+    for name in names {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+pub fn run_remove_secret(profile: &Profile, name: &str) -> io::Result<()> {
+    let mut store = SecretStore::open(profile)?;
+    if !store.remove(name) {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No secret named {}", name),
+        ));
+    }
+    store.save()?;
+    println!("Removed secret {}.", name);
+    Ok(())
+}
+
+fn read_encrypted_file(path: &Path) -> io::Result<Option<EncryptedFile>> {
+    match fs::read_to_string(path) {
+        Ok(raw) => Ok(Some(serde_json::from_str(&raw)?)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Binds the readable list of names to the ciphertext so it can't be edited.
+fn associated_data(names: &[String]) -> Vec<u8> {
+    names.join("\n").into_bytes()
+}
+
+fn decode(value: &str) -> io::Result<Vec<u8>> {
+    STANDARD
+        .decode(value)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn passphrase(prompt: bool) -> io::Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    if !prompt || !io::stdin().is_terminal() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Secrets are locked, set {} to unlock them", PASSPHRASE_ENV),
+        ));
+    }
+    rpassword::prompt_password("Secrets passphrase: ")
+}
+
+// Fetches the profile's key from the OS keyring, creating it on first use.
+#[cfg(feature = "keyring")]
+fn keyring_key(profile: &Profile) -> io::Result<Key> {
+    let keyring_error = |e: keyring::Error| {
+        io::Error::other(format!(
+            "OS keyring unavailable ({}), set {} to use a passphrase instead",
+            e, PASSPHRASE_ENV
+        ))
+    };
+    let entry = keyring::Entry::new(KEYRING_SERVICE, &format!("secrets-{}", profile.name()))
+        .map_err(keyring_error)?;
+
+    match entry.get_password() {
+        Ok(encoded) => {
+            let bytes = decode(&encoded)?;
+            if bytes.len() != 32 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Secrets key in the OS keyring is invalid",
+                ));
+            }
+            Ok(*Key::from_slice(&bytes))
+        }
+        Err(keyring::Error::NoEntry) => {
+            let key = rand::random::<[u8; 32]>();
+            entry
+                .set_password(&STANDARD.encode(key))
+                .map_err(keyring_error)?;
+            Ok(key.into())
+        }
+        Err(e) => Err(keyring_error(e)),
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_key(_profile: &Profile) -> io::Result<Key> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "These secrets are keyed in the OS keyring, which this build doesn't support",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_round_trip_encrypted() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("secrets.json");
+
+        let mut store = SecretStore::open_with_passphrase(&path, "hunter2").unwrap();
+        store.set("webhook", "https://hooks.example.com/very-secret");
+        store.save().expect("Failed to save secrets");
+
+        let on_disk = fs::read_to_string(&path).unwrap();
+        assert!(on_disk.contains("webhook"));
+        assert!(!on_disk.contains("very-secret"));
+
+        let reopened = SecretStore::open_with_passphrase(&path, "hunter2").unwrap();
+        assert_eq!(
+            reopened.get("webhook"),
+            Some("https://hooks.example.com/very-secret")
+        );
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("secrets.json");
+
+        let mut store = SecretStore::open_with_passphrase(&path, "hunter2").unwrap();
+        store.set("token", "abc");
+        store.save().expect("Failed to save secrets");
+
+        let err = SecretStore::open_with_passphrase(&path, "hunter3")
+            .err()
+            .expect("Wrong passphrase should not decrypt");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn plain_values_are_not_resolved() {
+        let profile = Profile::default();
+        let mut resolver = SecretResolver::new(&profile);
+        assert_eq!(resolver.resolve("plain-token").unwrap(), "plain-token");
+        assert!(resolver.store.is_none());
+    }
+
+    #[test]
+    fn a_store_that_failed_to_open_is_not_reopened() {
+        let mut resolver = SecretResolver::new(&Profile::default()).with_prompt(false);
+        resolver.store = Some(Err("Secrets are locked".to_string()));
+        let err = resolver.resolve("secret:token").unwrap_err();
+        assert_eq!(err.to_string(), "Secrets are locked");
+        assert!(matches!(resolver.store, Some(Err(_))));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::rollup;
+use crate::ui::domains::{CheckStatus, MonitoredDomain, PeriodStats};
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// Most points a series is asked for, more than any chart is wide.
+pub const MAX_SERIES_POINTS: usize = 1000;
+
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+
+// A domain's checks in [from, to) averaged into buckets of resolution_seconds,
+// for the dashboard's latency and uptime chart. Zooming in asks for a shorter
+// range and so gets finer buckets, down to one check interval. Buckets of a
+// day or more are whole UTC days read from the daily rollups, since the
+// history doesn't reach back that far.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LatencySeries {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub resolution_seconds: i64,
+    // Oldest first, buckets without checks included so gaps show.
+    pub points: Vec<SeriesPoint>,
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+pub struct SeriesPoint {
+    // Start of the bucket.
+    pub at: DateTime<Utc>,
+    pub checks: usize,
+    pub uptime_percentage: Option<f64>,
+    pub average_response_time_ms: Option<f64>,
+    pub max_response_time_ms: Option<u64>,
+}
+
+pub fn latency_series(
+    domain: &MonitoredDomain,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    max_points: usize,
+    count_rate_limited_as_down: bool,
+) -> LatencySeries {
+    let max_points = max_points.clamp(1, MAX_SERIES_POINTS) as i64;
+    let span = (to - from).num_seconds().max(1);
+    // Finer than the interval would only add empty buckets.
+    let resolution_seconds = (span + max_points - 1) / max_points;
+    let resolution_seconds = resolution_seconds
+        .max(domain.interval_seconds as i64)
+        .max(1);
+    if resolution_seconds >= DAY_SECONDS {
+        return daily_series(
+            domain,
+            from,
+            to,
+            resolution_seconds,
+            count_rate_limited_as_down,
+        );
+    }
+    let resolution = Duration::seconds(resolution_seconds);
+
+    // The history is stored oldest first, so each bucket is a slice of it.
+    let history = &domain.check_history;
+    let index_at = |at: DateTime<Utc>| history.partition_point(|c| c.timestamp < at);
+    let mut points = Vec::new();
+    let mut at = from;
+    while at < to {
+        let end = (at + resolution).min(to);
+        let checks: Vec<&CheckStatus> = history[index_at(at)..index_at(end)].iter().collect();
+        let stats = PeriodStats::of(&checks, count_rate_limited_as_down);
+        points.push(SeriesPoint {
+            at,
+            checks: checks.len(),
+            uptime_percentage: stats.uptime_percentage,
+            average_response_time_ms: stats.average_response_time_ms,
+            max_response_time_ms: checks.iter().filter_map(|c| c.response_time_ms).max(),
+        });
+        at = end;
+    }
+
+    LatencySeries {
+        from,
+        to,
+        resolution_seconds,
+        points,
+    }
+}
+
+// Starts at the UTC midnight on or before `from`.
+fn daily_series(
+    domain: &MonitoredDomain,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    resolution_seconds: i64,
+    count_rate_limited_as_down: bool,
+) -> LatencySeries {
+    let days = (resolution_seconds + DAY_SECONDS - 1) / DAY_SECONDS;
+    let from = from.date_naive().and_time(NaiveTime::MIN).and_utc();
+    let rollups = domain.rollups();
+
+    let mut points = Vec::new();
+    let mut at = from;
+    while at < to {
+        let end = at + Duration::days(days);
+        let total = rollup::total(
+            &rollups,
+            at.date_naive(),
+            (end - Duration::days(1)).date_naive(),
+        );
+        let stats = total.stats(count_rate_limited_as_down);
+        points.push(SeriesPoint {
+            at,
+            checks: total.checks() as usize,
+            uptime_percentage: stats.uptime_percentage,
+            average_response_time_ms: stats.average_response_time_ms,
+            max_response_time_ms: total.max_response_time_ms,
+        });
+        at = end;
+    }
+
+    LatencySeries {
+        from,
+        to,
+        resolution_seconds: days * DAY_SECONDS,
+        points,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::domains::DomainStatus;
+
+    #[test]
+    fn buckets_follow_the_zoom_level() {
+        let start = Utc::now() - Duration::hours(2);
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.check_history = (0..120)
+            .map(|minute| CheckStatus {
+                response_time_ms: Some(minute as u64),
+                ..CheckStatus::new(
+                    start + Duration::minutes(minute),
+                    if minute == 5 {
+                        DomainStatus::Down
+                    } else {
+                        DomainStatus::Up
+                    },
+                )
+            })
+            .collect();
+        let end = start + Duration::hours(2);
+
+        let all = latency_series(&domain, start, end, 12, false);
+        assert_eq!(all.resolution_seconds, 600);
+        assert_eq!(all.points.len(), 12);
+        assert_eq!(all.points[0].checks, 10);
+        assert_eq!(all.points[0].uptime_percentage, Some(90.0));
+        assert_eq!(all.points[0].average_response_time_ms, Some(4.5));
+        assert_eq!(all.points[0].max_response_time_ms, Some(9));
+
+        // Zoomed in to 10 minutes, buckets shrink to the check interval.
+        let zoomed = latency_series(&domain, start, start + Duration::minutes(10), 200, false);
+        assert_eq!(zoomed.resolution_seconds, 60);
+        assert_eq!(zoomed.points.len(), 10);
+        assert!(zoomed.points.iter().all(|p| p.checks == 1));
+
+        // Past the history the buckets are empty.
+        let later = latency_series(&domain, end, end + Duration::hours(1), 6, false);
+        assert!(later.points.iter().all(|p| p.checks == 0));
+        assert_eq!(later.points[0].uptime_percentage, None);
+    }
+
+    #[test]
+    fn long_ranges_are_read_from_the_rollups() {
+        let start: DateTime<Utc> = "2025-03-01T06:00:00Z".parse().unwrap();
+        let mut domain = MonitoredDomain::new("https://example.com");
+        for day in 0..10 {
+            let at = start + Duration::days(day);
+            domain.push_check(CheckStatus {
+                response_time_ms: Some(100 * (day as u64 + 1)),
+                ..CheckStatus::new(at, DomainStatus::Up)
+            });
+            domain.push_check(CheckStatus::new(
+                at + Duration::hours(1),
+                DomainStatus::Down,
+            ));
+        }
+        // Only the last day is left in the history.
+        domain.check_history.drain(0..18);
+
+        let end: DateTime<Utc> = "2025-03-11T00:00:00Z".parse().unwrap();
+        let series = latency_series(&domain, start, end, 5, false);
+        assert_eq!(series.resolution_seconds, 2 * DAY_SECONDS);
+        assert_eq!(
+            series.from,
+            "2025-03-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(series.points.len(), 5);
+        assert_eq!(series.points[0].checks, 4);
+        assert_eq!(series.points[0].uptime_percentage, Some(50.0));
+        assert_eq!(series.points[0].average_response_time_ms, Some(150.0));
+        assert_eq!(series.points[4].max_response_time_ms, Some(1000));
+    }
+}
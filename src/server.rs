@@ -0,0 +1,1463 @@
+use crate::annotation::{
+    Annotation, add_annotation, annotations_between, annotations_path, load_annotations,
+};
+use crate::auth::{Access, AuthError, authorize};
+use crate::badge::uptime_badge;
+use crate::blind_spot::{CheckAge, check_age};
+use crate::calendar::incident_calendar;
+use crate::configuration::{MonitorTemplate, Settings};
+use crate::grafana::{self, MetricOption, QueryRequest, TimeSeries, metric_options};
+#[cfg(feature = "graphql")]
+use crate::graphql::{self, UpquackSchema};
+use crate::monitor::{Criterion, HttpChallenge, MonitorEvent, MonitorHandle};
+use crate::series::{LatencySeries, latency_series};
+use crate::ui::domains::{
+    CheckStatus, DomainAuth, HistoryRetention, HttpMethod, MonitoredDomain, StatusRange,
+};
+use crate::ui::latency::LatencySettings;
+use axum::{
+    Json, Router,
+    extract::{Path, Query, Request, State},
+    http::{
+        StatusCode,
+        header::{CACHE_CONTROL, CONTENT_TYPE, WWW_AUTHENTICATE},
+    },
+    middleware::{self, Next},
+    response::{
+        Html, IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+    routing::{get, post, put},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, convert::Infallible, io, sync::Arc};
+use tokio::net::TcpListener;
+use tokio_stream::{
+    Stream, StreamExt,
+    wrappers::{BroadcastStream, errors::BroadcastStreamRecvError},
+};
+use utoipa::{
+    IntoParams, Modify, OpenApi, ToSchema,
+    openapi::security::{Http, HttpAuthScheme, SecurityScheme},
+};
+use utoipa_swagger_ui::SwaggerUi;
+use uuid::Uuid;
+
+static DASHBOARD_HTML: &str = include_str!("../static/dashboard.html");
+
+#[derive(Clone)]
+struct ServerState {
+    monitor: MonitorHandle,
+    settings: Arc<Settings>,
+    #[cfg(feature = "graphql")]
+    graphql: UpquackSchema,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "upquack", description = "Monitored domains and their check results"),
+    paths(
+        list_domains,
+        get_domain,
+        get_latency_series,
+        list_annotations,
+        create_annotation,
+        create_domain,
+        update_domain,
+        delete_domain,
+        events,
+        deploy_hook,
+        badge,
+        incident_feed,
+        grafana_health,
+        grafana_metrics,
+        grafana_query
+    ),
+    components(schemas(MonitorEvent)),
+    modifiers(&SecuritySchemes),
+    security(("bearer" = []), ("basic" = []))
+)]
+struct ApiDoc;
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+        );
+        // The token goes in the password, the username is ignored.
+        components.add_security_scheme(
+            "basic",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Basic)),
+        );
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DomainSummary {
+    id: Uuid,
+    url: String,
+    display_name: Option<String>,
+    environment: Option<String>,
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+    interval_seconds: u64,
+    timeout_seconds: Option<u64>,
+    uptime_percentage: Option<f64>,
+    last_check: Option<CheckStatus>,
+    // Whether the last check is overdue, for coloring it.
+    last_check_age: Option<CheckAge>,
+    // What's weak about the certificate chain, empty when nothing is.
+    certificate_warnings: Vec<String>,
+    // Unset while the domain isn't being monitored.
+    next_check_at: Option<DateTime<Utc>>,
+    // How to show and color response times, overrides applied.
+    latency: LatencySettings,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DomainDetail {
+    #[serde(flatten)]
+    domain: MonitoredDomain,
+    uptime_percentage: Option<f64>,
+    // The configured default with the domain's overrides applied.
+    history_retention: HistoryRetention,
+    latency: LatencySettings,
+}
+
+// Body of POST /domains and PUT /domains/{id}. Fields left out take their
+// defaults (or the template's), like in a monitors file.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct DomainRequest {
+    url: String,
+    display_name: Option<String>,
+    template: Option<String>,
+    interval_seconds: Option<u64>,
+    timeout_seconds: Option<u64>,
+    history_max_checks: Option<usize>,
+    history_max_age_days: Option<u64>,
+    latency_good_below_ms: Option<u64>,
+    latency_warn_below_ms: Option<u64>,
+    success_criteria: Option<Criterion>,
+    #[serde(default)]
+    #[schema(value_type = Vec<String>)]
+    expected_status_codes: Vec<StatusRange>,
+    expected_redirect: Option<String>,
+    max_redirects: Option<usize>,
+    keyword: Option<String>,
+    body_regex: Option<String>,
+    http_method: Option<HttpMethod>,
+    request_body: Option<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    auth: Option<DomainAuth>,
+    challenge: Option<HttpChallenge>,
+    ssh_jump_host: Option<String>,
+    kubeconfig: Option<String>,
+    owner: Option<String>,
+    environment: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+}
+
+impl DomainRequest {
+    fn to_domain(&self, templates: &[MonitorTemplate]) -> Result<MonitoredDomain, String> {
+        let mut domain = MonitoredDomain::new(&self.url);
+        domain.display_name = self.display_name.clone();
+        if let Some(name) = &self.template {
+            let template = templates
+                .iter()
+                .find(|t| &t.name == name)
+                .ok_or_else(|| format!("Unknown template \"{}\"", name))?;
+            domain.apply_template(template);
+        }
+        if let Some(interval_seconds) = self.interval_seconds {
+            domain.interval_seconds = interval_seconds;
+        }
+        if self.timeout_seconds.is_some() {
+            domain.timeout_seconds = self.timeout_seconds;
+        }
+        domain.history_max_checks = self.history_max_checks;
+        domain.history_max_age_days = self.history_max_age_days;
+        domain.latency_good_below_ms = self.latency_good_below_ms;
+        domain.latency_warn_below_ms = self.latency_warn_below_ms;
+        domain.success_criteria = self.success_criteria.clone();
+        domain.expected_status_codes = self.expected_status_codes.clone();
+        domain.expected_redirect = self.expected_redirect.clone();
+        domain.max_redirects = self.max_redirects;
+        domain.keyword = self.keyword.clone();
+        domain.body_regex = self.body_regex.clone();
+        domain.http_method = self.http_method;
+        domain.request_body = self.request_body.clone();
+        domain.headers = self.headers.clone();
+        domain.auth = self.auth.clone();
+        domain.challenge = self.challenge.clone();
+        domain.ssh_jump_host = self.ssh_jump_host.clone();
+        domain.kubeconfig = self.kubeconfig.clone();
+        domain.owner = self.owner.clone();
+        domain.environment = self.environment.clone();
+        domain.tags = self.tags.clone();
+        domain.metadata = self.metadata.clone();
+
+        domain.validate()?;
+        Ok(domain)
+    }
+}
+
+// Body of POST /hooks/deploy.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct DeployHook {
+    // The deployed service's URL, the monitor with this URL is updated if
+    // there is one.
+    url: String,
+    // The [[templates]] entry the monitor's settings come from.
+    template: String,
+    owner: Option<String>,
+    environment: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct SeriesQuery {
+    // RFC 3339. From the first check and up to now unless given.
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    // The most buckets to return, 200 unless given.
+    points: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct AnnotationQuery {
+    // RFC 3339, all annotations unless given.
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    // Leaves out the ones for other domains.
+    url: Option<String>,
+}
+
+// Body of POST /annotations.
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct AnnotationRequest {
+    text: String,
+    // Only shown on the chart of the monitor with this URL.
+    url: Option<String>,
+    // RFC 3339, now unless given.
+    timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+struct BadgeQuery {
+    // The left half of the badge, "uptime" unless given.
+    label: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ApiError {
+    error: String,
+}
+
+pub async fn serve(settings: Settings, monitor: MonitorHandle) -> io::Result<()> {
+    let listener = TcpListener::bind(&settings.server.address).await?;
+    tracing::debug!("API server listening on {}", listener.local_addr()?);
+
+    axum::serve(listener, router(settings, monitor))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+}
+
+fn router(settings: Settings, monitor: MonitorHandle) -> Router {
+    let state = ServerState {
+        #[cfg(feature = "graphql")]
+        graphql: graphql::schema(monitor.clone(), settings.monitor.count_rate_limited_as_down),
+        monitor,
+        settings: Arc::new(settings),
+    };
+
+    let require_admin = middleware::from_fn_with_state(state.clone(), require_admin);
+    let require_read = middleware::from_fn_with_state(state.clone(), require_read);
+    let public_badges = state.settings.server.public_badges;
+
+    let router = Router::new()
+        .route("/", get(dashboard))
+        .route(
+            "/domains",
+            get(list_domains).merge(post(create_domain).route_layer(require_admin.clone())),
+        )
+        .route(
+            "/domains/{id}",
+            get(get_domain).merge(
+                put(update_domain)
+                    .delete(delete_domain)
+                    .route_layer(require_admin),
+            ),
+        )
+        .route("/domains/{id}/latency", get(get_latency_series))
+        .route("/annotations", get(list_annotations))
+        .route("/events", get(events))
+        .route("/incidents.ics", get(incident_feed))
+        .route("/grafana", get(grafana_health))
+        .route("/grafana/metrics", post(grafana_metrics))
+        .route("/grafana/query", post(grafana_query))
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi()));
+    #[cfg(feature = "graphql")]
+    let router = router.route("/graphql", post(graphql_query));
+    // Routes added after the layer are left out of it.
+    let router = if public_badges {
+        router
+            .route_layer(require_read)
+            .route("/badge/{file}", get(badge))
+    } else {
+        router
+            .route("/badge/{file}", get(badge))
+            .route_layer(require_read)
+    };
+    // Pipelines get their own token, which the read check would refuse.
+    let require_deploy = middleware::from_fn_with_state(state.clone(), require_deploy);
+    router
+        .route(
+            "/hooks/deploy",
+            post(deploy_hook).route_layer(require_deploy.clone()),
+        )
+        // Joins the GET above, without its read check.
+        .route(
+            "/annotations",
+            post(create_annotation).route_layer(require_deploy),
+        )
+        .with_state(state)
+}
+
+async fn require_read(State(state): State<ServerState>, request: Request, next: Next) -> Response {
+    match authorize(&state.settings.server, request.headers(), Access::Read) {
+        Ok(()) => next.run(request).await,
+        Err(e) => auth_error_response(e),
+    }
+}
+
+async fn require_admin(State(state): State<ServerState>, request: Request, next: Next) -> Response {
+    match authorize(&state.settings.server, request.headers(), Access::Admin) {
+        Ok(()) => next.run(request).await,
+        Err(e) => auth_error_response(e),
+    }
+}
+
+async fn require_deploy(
+    State(state): State<ServerState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match authorize(&state.settings.server, request.headers(), Access::Deploy) {
+        Ok(()) => next.run(request).await,
+        Err(e) => auth_error_response(e),
+    }
+}
+
+fn auth_error_response(error: AuthError) -> Response {
+    match error {
+        // The Basic challenge makes browsers prompt for the token.
+        AuthError::Unauthorized => (
+            StatusCode::UNAUTHORIZED,
+            [(WWW_AUTHENTICATE, "Basic realm=\"upquack\"")],
+            Json(ApiError {
+                error: "Missing or invalid API token".to_string(),
+            }),
+        )
+            .into_response(),
+        AuthError::Forbidden => (
+            StatusCode::FORBIDDEN,
+            Json(ApiError {
+                error: "This endpoint needs the admin token".to_string(),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+async fn dashboard() -> Html<&'static str> {
+    Html(DASHBOARD_HTML)
+}
+
+#[utoipa::path(
+    get,
+    path = "/domains",
+    responses((status = 200, description = "All monitored domains", body = [DomainSummary]))
+)]
+async fn list_domains(State(state): State<ServerState>) -> Json<Vec<DomainSummary>> {
+    let count_rate_limited_as_down = state.settings.monitor.count_rate_limited_as_down;
+    let now = Utc::now();
+    let domains_guard = state.monitor.domains.lock().unwrap();
+
+    let summaries = domains_guard
+        .iter()
+        .map(|domain| DomainSummary {
+            id: domain.id,
+            url: domain.url.to_string(),
+            display_name: domain.display_name.clone(),
+            environment: domain.environment.clone(),
+            tags: domain.tags.clone(),
+            metadata: domain.metadata.clone(),
+            interval_seconds: domain.interval_seconds,
+            timeout_seconds: domain.timeout_seconds,
+            uptime_percentage: domain.uptime_percentage(count_rate_limited_as_down),
+            last_check: domain.check_history.last().cloned(),
+            last_check_age: domain
+                .check_history
+                .last()
+                .map(|check| check_age(check, domain.interval_seconds, now)),
+            certificate_warnings: domain
+                .certificate
+                .as_ref()
+                .map(|c| c.warnings.clone())
+                .unwrap_or_default(),
+            next_check_at: state.monitor.next_check_at(domain.id),
+            latency: domain.latency_settings(state.settings.latency),
+        })
+        .collect();
+
+    Json(summaries)
+}
+
+#[utoipa::path(
+    get,
+    path = "/domains/{id}",
+    params(("id" = Uuid, Path, description = "Domain id")),
+    responses(
+        (status = 200, description = "The domain with its check history", body = DomainDetail),
+        (status = 404, description = "No domain with this id", body = ApiError)
+    )
+)]
+async fn get_domain(State(state): State<ServerState>, Path(id): Path<Uuid>) -> Response {
+    let domain = {
+        let domains_guard = state.monitor.domains.lock().unwrap();
+        domains_guard.iter().find(|d| d.id == id).cloned()
+    };
+
+    match domain {
+        Some(domain) => Json(domain_detail(&state, domain)).into_response(),
+        None => not_found_response(id),
+    }
+}
+
+// The dashboard chart asks for the range it shows, so zooming in gets finer
+// buckets instead of the same ones drawn wider.
+#[utoipa::path(
+    get,
+    path = "/domains/{id}/latency",
+    params(("id" = Uuid, Path, description = "Domain id"), SeriesQuery),
+    responses(
+        (status = 200, description = "Response times and uptime per bucket", body = LatencySeries),
+        (status = 400, description = "from isn't before to", body = ApiError),
+        (status = 404, description = "No domain with this id", body = ApiError)
+    )
+)]
+async fn get_latency_series(
+    State(state): State<ServerState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<SeriesQuery>,
+) -> Response {
+    let now = Utc::now();
+    let series = {
+        let domains_guard = state.monitor.domains.lock().unwrap();
+        domains_guard.iter().find(|d| d.id == id).map(|domain| {
+            let to = query.to.unwrap_or(now);
+            // The last hour of a domain without checks yet.
+            let first_check = domain.check_history.first().map(|c| c.timestamp);
+            let from = query
+                .from
+                .or(first_check)
+                .unwrap_or(to - chrono::Duration::hours(1));
+            (from < to).then(|| {
+                latency_series(
+                    domain,
+                    from,
+                    to,
+                    query.points.unwrap_or(200),
+                    state.settings.monitor.count_rate_limited_as_down,
+                )
+            })
+        })
+    };
+
+    match series {
+        Some(Some(series)) => Json(series).into_response(),
+        Some(None) => error_response(
+            StatusCode::BAD_REQUEST,
+            "from has to be before to".to_string(),
+        ),
+        None => not_found_response(id),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/annotations",
+    params(AnnotationQuery),
+    responses((status = 200, description = "Annotations oldest first", body = [Annotation]))
+)]
+async fn list_annotations(
+    State(state): State<ServerState>,
+    Query(query): Query<AnnotationQuery>,
+) -> Response {
+    let path = annotations_path(state.monitor.store_path());
+    match load_annotations(&path) {
+        Ok(annotations) => Json(annotations_between(
+            &annotations,
+            query.from.unwrap_or(DateTime::<Utc>::MIN_UTC),
+            query.to.unwrap_or(DateTime::<Utc>::MAX_UTC),
+            query.url.as_deref(),
+        ))
+        .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to load annotations: {}", e);
+            error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to load annotations".to_string(),
+            )
+        }
+    }
+}
+
+// Takes the deploy token too, so a pipeline can mark its deploys.
+#[utoipa::path(
+    post,
+    path = "/annotations",
+    request_body = AnnotationRequest,
+    responses(
+        (status = 201, description = "The annotation was saved", body = Annotation),
+        (status = 403, description = "Needs the deploy or admin token", body = ApiError),
+        (status = 422, description = "No text", body = ApiError)
+    ),
+    security(("bearer" = []))
+)]
+async fn create_annotation(
+    State(state): State<ServerState>,
+    Json(request): Json<AnnotationRequest>,
+) -> Response {
+    let annotation = match Annotation::new(
+        &request.text,
+        request.url.as_deref(),
+        request.timestamp.unwrap_or_else(Utc::now),
+    ) {
+        Ok(annotation) => annotation,
+        Err(e) => return error_response(StatusCode::UNPROCESSABLE_ENTITY, e),
+    };
+
+    let path = annotations_path(state.monitor.store_path());
+    match add_annotation(&path, annotation.clone()) {
+        Ok(()) => (StatusCode::CREATED, Json(annotation)).into_response(),
+        Err(e) => {
+            tracing::error!("Failed to save annotation: {}", e);
+            error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save annotation".to_string(),
+            )
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/domains",
+    request_body = DomainRequest,
+    responses(
+        (status = 201, description = "The domain was added and is being checked", body = DomainDetail),
+        (status = 403, description = "Needs the admin token", body = ApiError),
+        (status = 422, description = "Invalid settings", body = ApiError)
+    )
+)]
+async fn create_domain(
+    State(state): State<ServerState>,
+    Json(request): Json<DomainRequest>,
+) -> Response {
+    let domain = match request
+        .to_domain(&state.settings.templates)
+        .and_then(|mut domain| domain.unmask(None).map(|()| domain))
+    {
+        Ok(domain) => domain,
+        Err(e) => return error_response(StatusCode::UNPROCESSABLE_ENTITY, e),
+    };
+
+    match state.monitor.add_domain(domain.clone()) {
+        Ok(()) => (StatusCode::CREATED, Json(domain_detail(&state, domain))).into_response(),
+        Err(e) => save_error_response(e),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/domains/{id}",
+    params(("id" = Uuid, Path, description = "Domain id")),
+    request_body = DomainRequest,
+    responses(
+        (status = 200, description = "The updated domain", body = DomainDetail),
+        (status = 403, description = "Needs the admin token", body = ApiError),
+        (status = 404, description = "No domain with this id", body = ApiError),
+        (status = 422, description = "Invalid settings", body = ApiError)
+    )
+)]
+async fn update_domain(
+    State(state): State<ServerState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<DomainRequest>,
+) -> Response {
+    let mut edited = match request.to_domain(&state.settings.templates) {
+        Ok(domain) => MonitoredDomain { id, ..domain },
+        Err(e) => return error_response(StatusCode::UNPROCESSABLE_ENTITY, e),
+    };
+    // What GET returned comes back with its credentials masked.
+    let stored = {
+        let domains_guard = state.monitor.domains.lock().unwrap();
+        domains_guard.iter().find(|d| d.id == id).cloned()
+    };
+    if let Err(e) = edited.unmask(stored.as_ref()) {
+        return error_response(StatusCode::UNPROCESSABLE_ENTITY, e);
+    }
+
+    match state.monitor.update_domain(&edited) {
+        Ok(Some(domain)) => Json(domain_detail(&state, domain)).into_response(),
+        Ok(None) => not_found_response(id),
+        Err(e) => save_error_response(e),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/domains/{id}",
+    params(("id" = Uuid, Path, description = "Domain id")),
+    responses(
+        (status = 204, description = "The domain was removed"),
+        (status = 403, description = "Needs the admin token", body = ApiError),
+        (status = 404, description = "No domain with this id", body = ApiError)
+    )
+)]
+async fn delete_domain(State(state): State<ServerState>, Path(id): Path<Uuid>) -> Response {
+    match state.monitor.remove_domain(id) {
+        Ok(Some(_)) => StatusCode::NO_CONTENT.into_response(),
+        Ok(None) => not_found_response(id),
+        Err(e) => save_error_response(e),
+    }
+}
+
+fn domain_detail(state: &ServerState, domain: MonitoredDomain) -> DomainDetail {
+    let domain = domain.redacted();
+    DomainDetail {
+        uptime_percentage: domain
+            .uptime_percentage(state.settings.monitor.count_rate_limited_as_down),
+        history_retention: domain.history_retention(state.settings.monitor.history_retention()),
+        latency: domain.latency_settings(state.settings.latency),
+        domain,
+    }
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    (status, Json(ApiError { error })).into_response()
+}
+
+fn not_found_response(id: Uuid) -> Response {
+    error_response(StatusCode::NOT_FOUND, format!("No domain with id {}", id))
+}
+
+fn save_error_response(e: io::Error) -> Response {
+    tracing::error!("Failed to save domains from API request: {}", e);
+    error_response(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to save domains".to_string(),
+    )
+}
+
+// Server-Sent Events feed of check results and status changes as they happen.
+#[utoipa::path(
+    get,
+    path = "/events",
+    responses((
+        status = 200,
+        description = "Server-Sent Events named check_completed, status_changed, domain_added, domain_updated, domain_removed, persistence_failing or persistence_recovered, each carrying a MonitorEvent",
+        content_type = "text/event-stream",
+        body = MonitorEvent
+    ))
+)]
+async fn events(
+    State(state): State<ServerState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream =
+        BroadcastStream::new(state.monitor.events.subscribe()).filter_map(|event| match event {
+            Ok(event) => Some(Ok(sse_event(&event))),
+            Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                tracing::debug!("Event subscriber fell behind, skipped {} events", missed);
+                None
+            }
+        });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+// Every domain's incidents as an iCalendar feed calendar apps can subscribe
+// to. Most of them can't send a bearer token, but take basic auth in the URL.
+#[utoipa::path(
+    get,
+    path = "/incidents.ics",
+    responses((
+        status = 200,
+        description = "One event per incident, ongoing ones ending now",
+        content_type = "text/calendar",
+        body = String
+    ))
+)]
+async fn incident_feed(State(state): State<ServerState>) -> Response {
+    let calendar = {
+        let domains_guard = state.monitor.domains.lock().unwrap();
+        incident_calendar(&domains_guard, Utc::now())
+    };
+    ([(CONTENT_TYPE, "text/calendar; charset=utf-8")], calendar).into_response()
+}
+
+// The Grafana JSON datasource tests the connection with this.
+#[utoipa::path(
+    get,
+    path = "/grafana",
+    responses((status = 200, description = "The datasource is reachable", body = String))
+)]
+async fn grafana_health() -> &'static str {
+    "OK"
+}
+
+// The metrics Grafana's query editor offers, each with a domain picker.
+#[utoipa::path(
+    post,
+    path = "/grafana/metrics",
+    responses((status = 200, description = "The metrics to chart", body = [MetricOption]))
+)]
+async fn grafana_metrics(State(state): State<ServerState>) -> Json<Vec<MetricOption>> {
+    let domains_guard = state.monitor.domains.lock().unwrap();
+    Json(metric_options(&domains_guard))
+}
+
+// Check history as time series in the Grafana JSON datasource format, so
+// existing dashboards can chart it without a time series database between.
+#[utoipa::path(
+    post,
+    path = "/grafana/query",
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "A series per target and domain", body = [TimeSeries]),
+        (status = 400, description = "An empty range or unknown domain", body = ApiError)
+    )
+)]
+async fn grafana_query(
+    State(state): State<ServerState>,
+    Json(request): Json<QueryRequest>,
+) -> Response {
+    let series = {
+        let domains_guard = state.monitor.domains.lock().unwrap();
+        grafana::query(
+            &domains_guard,
+            &request,
+            state.settings.monitor.count_rate_limited_as_down,
+        )
+    };
+    match series {
+        Ok(series) => Json(series).into_response(),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+// An SVG badge of the domain's status and 30-day uptime, for READMEs and
+// wikis. The path is the domain id with .svg appended.
+#[utoipa::path(
+    get,
+    path = "/badge/{id}.svg",
+    params(("id" = Uuid, Path, description = "Domain id"), BadgeQuery),
+    responses(
+        (status = 200, description = "The badge", content_type = "image/svg+xml", body = String),
+        (status = 404, description = "No domain with this id", body = ApiError)
+    )
+)]
+async fn badge(
+    State(state): State<ServerState>,
+    Path(file): Path<String>,
+    Query(query): Query<BadgeQuery>,
+) -> Response {
+    let Some(id) = file
+        .strip_suffix(".svg")
+        .and_then(|id| Uuid::parse_str(id).ok())
+    else {
+        return error_response(StatusCode::NOT_FOUND, format!("No badge {}", file));
+    };
+    let svg = {
+        let domains_guard = state.monitor.domains.lock().unwrap();
+        domains_guard.iter().find(|d| d.id == id).map(|domain| {
+            uptime_badge(
+                domain,
+                query.label.as_deref().unwrap_or("uptime"),
+                Utc::now(),
+                state.settings.monitor.count_rate_limited_as_down,
+            )
+        })
+    };
+
+    match svg {
+        // Image proxies like GitHub's would otherwise keep a stale status.
+        Some(svg) => (
+            [(CONTENT_TYPE, "image/svg+xml"), (CACHE_CONTROL, "no-cache")],
+            svg,
+        )
+            .into_response(),
+        None => not_found_response(id),
+    }
+}
+
+// For CI/CD pipelines: creates a monitor for a freshly deployed service from a
+// template, or re-applies the template to the monitor it already has, so
+// redeploys are safe to repeat. Other settings of an existing monitor stay.
+#[utoipa::path(
+    post,
+    path = "/hooks/deploy",
+    request_body = DeployHook,
+    responses(
+        (status = 201, description = "A monitor was created", body = DomainDetail),
+        (status = 200, description = "The existing monitor was updated", body = DomainDetail),
+        (status = 403, description = "Needs the deploy or admin token", body = ApiError),
+        (status = 422, description = "Unknown template or invalid URL", body = ApiError)
+    ),
+    security(("bearer" = []))
+)]
+async fn deploy_hook(State(state): State<ServerState>, Json(hook): Json<DeployHook>) -> Response {
+    let Some(template) = state
+        .settings
+        .templates
+        .iter()
+        .find(|t| t.name == hook.template)
+    else {
+        return error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Unknown template \"{}\"", hook.template),
+        );
+    };
+    let existing = {
+        let domains_guard = state.monitor.domains.lock().unwrap();
+        domains_guard
+            .iter()
+            .find(|d| *d.url == *hook.url.trim())
+            .cloned()
+    };
+    let created = existing.is_none();
+
+    let mut domain = existing.unwrap_or_else(|| MonitoredDomain::new(&hook.url));
+    domain.apply_template(template);
+    if hook.owner.is_some() {
+        domain.owner = hook.owner;
+    }
+    if hook.environment.is_some() {
+        domain.environment = hook.environment;
+    }
+    if let Err(e) = domain.validate() {
+        return error_response(StatusCode::UNPROCESSABLE_ENTITY, e);
+    }
+
+    if created {
+        tracing::debug!("Deploy hook added {}", domain.url);
+        match state.monitor.add_domain(domain.clone()) {
+            Ok(()) => (StatusCode::CREATED, Json(domain_detail(&state, domain))).into_response(),
+            Err(e) => save_error_response(e),
+        }
+    } else {
+        tracing::debug!("Deploy hook updated {}", domain.url);
+        match state.monitor.update_domain(&domain) {
+            Ok(Some(domain)) => Json(domain_detail(&state, domain)).into_response(),
+            // Removed in the meantime.
+            Ok(None) => not_found_response(domain.id),
+            Err(e) => save_error_response(e),
+        }
+    }
+}
+
+// Read-only queries over the same data, see graphql.rs.
+#[cfg(feature = "graphql")]
+async fn graphql_query(
+    State(state): State<ServerState>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(state.graphql.execute(request).await)
+}
+
+fn sse_event(event: &MonitorEvent) -> Event {
+    // Domain changes carry the whole domain, credentials included.
+    let redacted = match event {
+        MonitorEvent::DomainAdded { domain } | MonitorEvent::DomainUpdated { domain }
+            if domain.auth.is_some() || !domain.headers.is_empty() =>
+        {
+            let domain = domain.redacted();
+            Some(match event {
+                MonitorEvent::DomainAdded { .. } => MonitorEvent::DomainAdded { domain },
+                _ => MonitorEvent::DomainUpdated { domain },
+            })
+        }
+        _ => None,
+    };
+    Event::default()
+        .event(event.name())
+        .json_data(redacted.as_ref().unwrap_or(event))
+        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::RequestBudget;
+    use std::sync::Mutex;
+
+    async fn spawn_test_server(domains: Vec<MonitoredDomain>) -> (String, MonitorHandle) {
+        spawn_test_server_with(Settings::default(), domains, "db/domains.json").await
+    }
+
+    async fn spawn_test_server_with(
+        settings: Settings,
+        domains: Vec<MonitoredDomain>,
+        store_path: &str,
+    ) -> (String, MonitorHandle) {
+        let monitor = MonitorHandle::new(
+            Arc::new(Mutex::new(domains)),
+            store_path,
+            Arc::new(|_: &MonitoredDomain, _: &[CheckStatus]| Ok(())),
+            Arc::new(RequestBudget::default()),
+            tokio::sync::broadcast::channel(16).0,
+        );
+        let server_monitor = monitor.clone();
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind test listener");
+        let address = listener.local_addr().expect("Listener has no address");
+
+        tokio::spawn(async move {
+            axum::serve(listener, router(settings, server_monitor))
+                .await
+                .expect("Test server failed");
+        });
+
+        (format!("http://{}", address), monitor)
+    }
+
+    #[tokio::test]
+    async fn lists_and_fetches_domains() {
+        let domain = MonitoredDomain::new("https://example.com");
+        let domain_id = domain.id;
+        let (base_url, _) = spawn_test_server(vec![domain]).await;
+
+        let domains: serde_json::Value = reqwest::get(format!("{}/domains", base_url))
+            .await
+            .expect("Request failed")
+            .json()
+            .await
+            .expect("Response is not JSON");
+        assert_eq!(domains[0]["url"], "https://example.com");
+
+        let detail = reqwest::get(format!("{}/domains/{}", base_url, domain_id))
+            .await
+            .expect("Request failed");
+        assert_eq!(detail.status(), reqwest::StatusCode::OK);
+
+        let missing = reqwest::get(format!("{}/domains/{}", base_url, Uuid::new_v4()))
+            .await
+            .expect("Request failed");
+        assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn serves_latency_series() {
+        let mut domain = MonitoredDomain::new("https://example.com");
+        let now = Utc::now();
+        domain.check_history = (1..=10)
+            .rev()
+            .map(|minutes_ago| CheckStatus {
+                response_time_ms: Some(100),
+                ..CheckStatus::new(
+                    now - chrono::Duration::minutes(minutes_ago),
+                    crate::ui::domains::DomainStatus::Up,
+                )
+            })
+            .collect();
+        let series_url = format!("/domains/{}/latency", domain.id);
+        let (base_url, _) = spawn_test_server(vec![domain]).await;
+
+        let series: serde_json::Value = reqwest::Client::new()
+            .get(format!("{}{}", base_url, series_url))
+            .query(&[("points", "5".to_string()), ("to", now.to_rfc3339())])
+            .send()
+            .await
+            .expect("Request failed")
+            .json()
+            .await
+            .expect("Response is not JSON");
+        let points = series["points"].as_array().expect("No points");
+        assert_eq!(points.len(), 5);
+        assert_eq!(points[0]["average_response_time_ms"], 100.0);
+
+        let from = (now - chrono::Duration::minutes(3)).to_rfc3339();
+        let to = (now - chrono::Duration::minutes(4)).to_rfc3339();
+        let backwards = reqwest::Client::new()
+            .get(format!("{}{}", base_url, series_url))
+            .query(&[("from", from), ("to", to)])
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(backwards.status(), reqwest::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn serves_dashboard() {
+        let (base_url, _) = spawn_test_server(Vec::new()).await;
+
+        let page = reqwest::get(&base_url)
+            .await
+            .expect("Request failed")
+            .text()
+            .await
+            .expect("Response is not text");
+        assert!(page.contains("UpQuack"));
+    }
+
+    #[tokio::test]
+    async fn read_token_protects_endpoints() {
+        let mut settings = Settings::default();
+        settings.server.read_token = Some("reader".to_string());
+        let (base_url, _) = spawn_test_server_with(settings, Vec::new(), "db/domains.json").await;
+        let client = reqwest::Client::new();
+
+        let anonymous = client
+            .get(format!("{}/domains", base_url))
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(anonymous.status(), reqwest::StatusCode::UNAUTHORIZED);
+        assert!(anonymous.headers().contains_key(WWW_AUTHENTICATE));
+
+        let with_token = client
+            .get(format!("{}/domains", base_url))
+            .bearer_auth("reader")
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(with_token.status(), reqwest::StatusCode::OK);
+
+        let from_browser = client
+            .get(&base_url)
+            .basic_auth("me", Some("reader"))
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(from_browser.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn admin_token_manages_domains() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let store_path = temp_dir.path().join("domains.json");
+        let mut settings = Settings::default();
+        settings.server.read_token = Some("reader".to_string());
+        settings.server.admin_token = Some("admin".to_string());
+        let (base_url, monitor) =
+            spawn_test_server_with(settings, Vec::new(), store_path.to_str().unwrap()).await;
+        let client = reqwest::Client::new();
+        let body = serde_json::json!({ "url": "https://example.com", "interval_seconds": 30 });
+
+        let as_reader = client
+            .post(format!("{}/domains", base_url))
+            .bearer_auth("reader")
+            .json(&body)
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(as_reader.status(), reqwest::StatusCode::FORBIDDEN);
+
+        let invalid = client
+            .post(format!("{}/domains", base_url))
+            .bearer_auth("admin")
+            .json(&serde_json::json!({ "url": "not a url" }))
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(invalid.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+        let created: serde_json::Value = client
+            .post(format!("{}/domains", base_url))
+            .bearer_auth("admin")
+            .json(&body)
+            .send()
+            .await
+            .expect("Request failed")
+            .json()
+            .await
+            .expect("Response is not JSON");
+        let id = created["id"].as_str().expect("Created domain has no id");
+        assert_eq!(created["interval_seconds"], 30);
+        assert_eq!(monitor.domains.lock().unwrap().len(), 1);
+        assert!(store_path.exists());
+
+        let updated = client
+            .put(format!("{}/domains/{}", base_url, id))
+            .bearer_auth("admin")
+            .json(&serde_json::json!({ "url": "https://example.org" }))
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(updated.status(), reqwest::StatusCode::OK);
+        assert_eq!(
+            &*monitor.domains.lock().unwrap()[0].url,
+            "https://example.org"
+        );
+
+        // Credentials come back masked, and sending them back keeps them.
+        let with_credentials = serde_json::json!({
+            "url": "https://example.org",
+            "auth": { "type": "basic", "username": "ops", "password": "hunter2" },
+            "headers": { "X-Api-Key": "k3y" },
+        });
+        let put = |body: serde_json::Value| {
+            client
+                .put(format!("{}/domains/{}", base_url, id))
+                .bearer_auth("admin")
+                .json(&body)
+                .send()
+        };
+        put(with_credentials).await.expect("Request failed");
+        let fetched: serde_json::Value = client
+            .get(format!("{}/domains/{}", base_url, id))
+            .bearer_auth("reader")
+            .send()
+            .await
+            .expect("Request failed")
+            .json()
+            .await
+            .expect("Response is not JSON");
+        assert_eq!(fetched["auth"]["password"], "****");
+        assert_eq!(fetched["headers"]["X-Api-Key"], "****");
+        let round_trip = serde_json::json!({
+            "url": fetched["url"],
+            "auth": fetched["auth"],
+            "headers": fetched["headers"],
+        });
+        let saved = put(round_trip).await.expect("Request failed");
+        assert_eq!(saved.status(), reqwest::StatusCode::OK);
+        {
+            let domains_guard = monitor.domains.lock().unwrap();
+            assert_eq!(
+                domains_guard[0].auth.as_ref().unwrap().to_string(),
+                "basic ops:hunter2"
+            );
+            assert_eq!(domains_guard[0].headers["X-Api-Key"], "k3y");
+        }
+        let masked_new = client
+            .post(format!("{}/domains", base_url))
+            .bearer_auth("admin")
+            .json(&serde_json::json!({ "url": "https://example.net", "headers": { "X-Api-Key": "****" } }))
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(
+            masked_new.status(),
+            reqwest::StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        let deleted = client
+            .delete(format!("{}/domains/{}", base_url, id))
+            .bearer_auth("admin")
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(deleted.status(), reqwest::StatusCode::NO_CONTENT);
+        assert!(monitor.domains.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn serves_badges() {
+        let domain = MonitoredDomain::new("https://example.com");
+        let badge_url = format!("/badge/{}.svg", domain.id);
+        let (base_url, _) = spawn_test_server(vec![domain]).await;
+
+        let badge = reqwest::get(format!("{}{}?label=api", base_url, badge_url))
+            .await
+            .expect("Request failed");
+        assert_eq!(badge.status(), reqwest::StatusCode::OK);
+        assert_eq!(badge.headers()[CONTENT_TYPE], "image/svg+xml");
+        let svg = badge.text().await.expect("Response is not text");
+        assert!(
+            svg.contains(">api<") && svg.contains(">no data<"),
+            "{}",
+            svg
+        );
+
+        for missing in [
+            format!("/badge/{}.svg", Uuid::new_v4()),
+            "/badge/x.png".to_string(),
+        ] {
+            let response = reqwest::get(format!("{}{}", base_url, missing))
+                .await
+                .expect("Request failed");
+            assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+        }
+    }
+
+    #[tokio::test]
+    async fn public_badges_skip_the_read_token() {
+        let mut settings = Settings::default();
+        settings.server.read_token = Some("reader".to_string());
+        let domain = MonitoredDomain::new("https://example.com");
+        let badge_url = format!("/badge/{}.svg", domain.id);
+        let (private_url, _) =
+            spawn_test_server_with(settings.clone(), vec![domain.clone()], "db/domains.json").await;
+        settings.server.public_badges = true;
+        let (public_url, _) =
+            spawn_test_server_with(settings, vec![domain], "db/domains.json").await;
+
+        let private = reqwest::get(format!("{}{}", private_url, badge_url))
+            .await
+            .expect("Request failed");
+        assert_eq!(private.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let public = reqwest::get(format!("{}{}", public_url, badge_url))
+            .await
+            .expect("Request failed");
+        assert_eq!(public.status(), reqwest::StatusCode::OK);
+        // Everything else still needs the token.
+        let domains = reqwest::get(format!("{}/domains", public_url))
+            .await
+            .expect("Request failed");
+        assert_eq!(domains.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn serves_incident_calendar() {
+        let (base_url, _) =
+            spawn_test_server(vec![MonitoredDomain::new("https://example.com")]).await;
+
+        let feed = reqwest::get(format!("{}/incidents.ics", base_url))
+            .await
+            .expect("Request failed");
+        assert_eq!(feed.status(), reqwest::StatusCode::OK);
+        assert!(
+            feed.headers()[CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/calendar")
+        );
+        let calendar = feed.text().await.expect("Response is not text");
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"), "{}", calendar);
+    }
+
+    #[cfg(feature = "graphql")]
+    #[tokio::test]
+    async fn answers_graphql_queries() {
+        let (base_url, _) =
+            spawn_test_server(vec![MonitoredDomain::new("https://example.com")]).await;
+
+        let response: serde_json::Value = reqwest::Client::new()
+            .post(format!("{}/graphql", base_url))
+            .json(&serde_json::json!({ "query": "{ domains { total items { url } } }" }))
+            .send()
+            .await
+            .expect("Request failed")
+            .json()
+            .await
+            .expect("Response is not JSON");
+        assert_eq!(
+            response["data"]["domains"]["items"][0]["url"],
+            "https://example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn deploy_hook_creates_then_updates_monitors() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let store_path = temp_dir.path().join("domains.json");
+        let mut settings = Settings::default();
+        settings.server.read_token = Some("reader".to_string());
+        settings.server.deploy_token = Some("ci".to_string());
+        settings.templates = vec![MonitorTemplate {
+            name: "api".to_string(),
+            interval_seconds: Some(15),
+            timeout_seconds: Some(3),
+            ..Default::default()
+        }];
+        let (base_url, monitor) =
+            spawn_test_server_with(settings, Vec::new(), store_path.to_str().unwrap()).await;
+        let client = reqwest::Client::new();
+        let deploy = |body: serde_json::Value| {
+            client
+                .post(format!("{}/hooks/deploy", base_url))
+                .bearer_auth("ci")
+                .json(&body)
+                .send()
+        };
+
+        let created = deploy(serde_json::json!({ "url": "https://api.example.com", "template": "api", "owner": "payments" }))
+            .await
+            .expect("Request failed");
+        assert_eq!(created.status(), reqwest::StatusCode::CREATED);
+
+        let redeployed =
+            deploy(serde_json::json!({ "url": "https://api.example.com", "template": "api" }))
+                .await
+                .expect("Request failed");
+        assert_eq!(redeployed.status(), reqwest::StatusCode::OK);
+        {
+            let domains = monitor.domains.lock().unwrap();
+            assert_eq!(domains.len(), 1);
+            assert_eq!(domains[0].interval_seconds, 15);
+            assert_eq!(domains[0].timeout_seconds, Some(3));
+            assert_eq!(domains[0].owner.as_deref(), Some("payments"));
+        }
+
+        let unknown =
+            deploy(serde_json::json!({ "url": "https://www.example.com", "template": "web" }))
+                .await
+                .expect("Request failed");
+        assert_eq!(unknown.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+
+        // The deploy token is only good for the hook.
+        let listing = client
+            .get(format!("{}/domains", base_url))
+            .bearer_auth("ci")
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(listing.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn stores_and_lists_annotations() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let store_path = temp_dir.path().join("domains.json");
+        let mut settings = Settings::default();
+        settings.server.read_token = Some("reader".to_string());
+        settings.server.deploy_token = Some("ci".to_string());
+        let (base_url, _) =
+            spawn_test_server_with(settings, Vec::new(), store_path.to_str().unwrap()).await;
+        let client = reqwest::Client::new();
+        let annotate = |token: &str, body: serde_json::Value| {
+            client
+                .post(format!("{}/annotations", base_url))
+                .bearer_auth(token)
+                .json(&body)
+                .send()
+        };
+
+        let created = annotate("ci", serde_json::json!({ "text": "deployed v1.2" }))
+            .await
+            .expect("Request failed");
+        assert_eq!(created.status(), reqwest::StatusCode::CREATED);
+        let scoped = annotate(
+            "ci",
+            serde_json::json!({ "text": "migration", "url": "https://api.example.com", "timestamp": "2020-01-01T00:00:00Z" }),
+        )
+        .await
+        .expect("Request failed");
+        assert_eq!(scoped.status(), reqwest::StatusCode::CREATED);
+        let empty = annotate("ci", serde_json::json!({ "text": " " }))
+            .await
+            .expect("Request failed");
+        assert_eq!(empty.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+        let forbidden = annotate("reader", serde_json::json!({ "text": "deployed v1.3" }))
+            .await
+            .expect("Request failed");
+        assert_eq!(forbidden.status(), reqwest::StatusCode::FORBIDDEN);
+
+        let list = |query: &'static str| {
+            client
+                .get(format!("{}/annotations?{}", base_url, query))
+                .bearer_auth("reader")
+                .send()
+        };
+        let all: serde_json::Value = list("")
+            .await
+            .expect("Request failed")
+            .json()
+            .await
+            .expect("Response is not JSON");
+        assert_eq!(all[0]["text"], "migration");
+        assert_eq!(all[1]["text"], "deployed v1.2");
+        let other: serde_json::Value = list("url=https://www.example.com")
+            .await
+            .expect("Request failed")
+            .json()
+            .await
+            .expect("Response is not JSON");
+        assert_eq!(other.as_array().unwrap().len(), 1);
+        let recent: serde_json::Value =
+            list("from=2024-01-01T00:00:00Z&url=https://api.example.com")
+                .await
+                .expect("Request failed")
+                .json()
+                .await
+                .expect("Response is not JSON");
+        assert_eq!(recent[0]["text"], "deployed v1.2");
+        assert_eq!(recent.as_array().unwrap().len(), 1);
+
+        // Listing needs the read token, the deploy token isn't one.
+        let unauthorized = client
+            .get(format!("{}/annotations", base_url))
+            .bearer_auth("ci")
+            .send()
+            .await
+            .expect("Request failed");
+        assert_eq!(unauthorized.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn serves_openapi_document() {
+        let (base_url, _) = spawn_test_server(Vec::new()).await;
+
+        let document: serde_json::Value = reqwest::get(format!("{}/openapi.json", base_url))
+            .await
+            .expect("Request failed")
+            .json()
+            .await
+            .expect("Response is not JSON");
+        assert!(document["paths"]["/domains/{id}"]["put"].is_object());
+        assert!(document["components"]["schemas"]["DomainDetail"].is_object());
+
+        let docs = reqwest::get(format!("{}/docs/", base_url))
+            .await
+            .expect("Request failed");
+        assert_eq!(docs.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn streams_monitor_events() {
+        let (base_url, monitor) = spawn_test_server(Vec::new()).await;
+
+        let mut response = reqwest::get(format!("{}/events", base_url))
+            .await
+            .expect("Request failed");
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        monitor
+            .events
+            .send(MonitorEvent::CheckCompleted {
+                domain_id: Uuid::new_v4(),
+                url: "https://example.com".into(),
+                check: CheckStatus {
+                    response_time_ms: Some(42),
+                    ..CheckStatus::new(chrono::Utc::now(), crate::ui::domains::DomainStatus::Up)
+                },
+            })
+            .expect("Nobody subscribed to events");
+
+        let chunk = response
+            .chunk()
+            .await
+            .expect("Failed to read event")
+            .expect("Stream ended");
+        let chunk = String::from_utf8_lossy(&chunk);
+        assert!(chunk.contains("event: check_completed"), "{}", chunk);
+        assert!(chunk.contains("\"response_time_ms\":42"), "{}", chunk);
+    }
+}
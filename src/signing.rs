@@ -0,0 +1,261 @@
+use crate::store::load_domains;
+use crate::ui::domains::{CheckSignature, CheckStatus, MonitoredDomain};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{fs, io, path::Path};
+use uuid::Uuid;
+
+// Signs every check record with an ed25519 key. Each signature also covers the
+// hash of the record before it, so edited, removed or reordered records are
+// caught by `upquack verify-history` without needing the private key.
+#[derive(Debug)]
+pub struct CheckSigner {
+    key: SigningKey,
+}
+
+// What a signature covers: the record without its signature, the domain it
+// belongs to and the hash of the record before it.
+#[derive(Serialize)]
+struct SignedMessage<'a> {
+    domain_id: Uuid,
+    previous_hash: &'a Option<String>,
+    check: &'a CheckStatus,
+}
+
+impl CheckSigner {
+    // Creates the key (and a .pub file with the public half to hand to
+    // auditors) the first time signing is enabled.
+    pub fn load_or_create(key_path: &Path) -> io::Result<Self> {
+        if key_path.exists() {
+            let bytes = decode_key(&fs::read_to_string(key_path)?)?;
+            return Ok(CheckSigner {
+                key: SigningKey::from_bytes(&bytes),
+            });
+        }
+
+        let key = SigningKey::from_bytes(&rand::random::<[u8; 32]>());
+        if let Some(parent) = key_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(key_path, STANDARD.encode(key.to_bytes()))?;
+        fs::write(
+            public_key_path(key_path),
+            STANDARD.encode(key.verifying_key().to_bytes()),
+        )?;
+        tracing::debug!("Created check signing key at {}", key_path.display());
+
+        Ok(CheckSigner { key })
+    }
+
+    pub fn sign(&self, domain_id: Uuid, previous: Option<&CheckStatus>, check: &mut CheckStatus) {
+        check.signature = None;
+        let previous_hash = previous.map(record_hash);
+        let signature = self
+            .key
+            .sign(&signed_message(domain_id, &previous_hash, check));
+
+        check.signature = Some(CheckSignature {
+            previous_hash,
+            signature: STANDARD.encode(signature.to_bytes()),
+        });
+    }
+}
+
+fn public_key_path(key_path: &Path) -> std::path::PathBuf {
+    let mut path = key_path.as_os_str().to_owned();
+    path.push(".pub");
+    path.into()
+}
+
+fn decode_key(encoded: &str) -> io::Result<[u8; 32]> {
+    STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid signing key"))
+}
+
+fn signed_message(domain_id: Uuid, previous_hash: &Option<String>, check: &CheckStatus) -> Vec<u8> {
+    serde_json::to_vec(&SignedMessage {
+        domain_id,
+        previous_hash,
+        check,
+    })
+    .expect("Check records always serialize")
+}
+
+// Hash of the full stored record, signature included.
+fn record_hash(check: &CheckStatus) -> String {
+    let json = serde_json::to_vec(check).expect("Check records always serialize");
+    STANDARD.encode(Sha256::digest(json))
+}
+
+// Problems found in one domain's history, as human readable lines.
+fn verify_domain(domain: &MonitoredDomain, key: &VerifyingKey) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut previous: Option<&CheckStatus> = None;
+    let mut chain_started = false;
+
+    for check in &domain.check_history {
+        let Some(signature) = &check.signature else {
+            // Records from before signing was enabled are fine, but once the
+            // chain starts every record has to be part of it.
+            if chain_started {
+                problems.push(format!("{}: record is not signed", check.timestamp));
+            }
+            previous = Some(check);
+            continue;
+        };
+        chain_started = true;
+
+        let unsigned = CheckStatus {
+            signature: None,
+            ..check.clone()
+        };
+        let valid_signature = STANDARD
+            .decode(&signature.signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .is_some_and(|sig| {
+                key.verify(
+                    &signed_message(domain.id, &signature.previous_hash, &unsigned),
+                    &sig,
+                )
+                .is_ok()
+            });
+        if !valid_signature {
+            problems.push(format!("{}: signature does not match", check.timestamp));
+        }
+
+        // The oldest kept record may point at one that was trimmed from the
+        // history, so only links between kept records can be checked.
+        if let Some(previous) = previous
+            && signature.previous_hash.as_deref() != Some(record_hash(previous).as_str())
+        {
+            problems.push(format!(
+                "{}: previous record was changed, removed or reordered",
+                check.timestamp
+            ));
+        }
+        previous = Some(check);
+    }
+
+    problems
+}
+
+pub fn run_verify_history(
+    history_path: &Path,
+    key_path: &Path,
+    public_key: Option<&str>,
+) -> io::Result<()> {
+    let encoded_key = match public_key {
+        Some(public_key) => public_key.to_string(),
+        None => fs::read_to_string(public_key_path(key_path))?,
+    };
+    let key = VerifyingKey::from_bytes(&decode_key(&encoded_key)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let domains = load_domains(&history_path.to_string_lossy())?;
+    let mut failed = 0;
+
+    for domain in &domains {
+        let signed = domain
+            .check_history
+            .iter()
+            .filter(|c| c.signature.is_some())
+            .count();
+        let problems = verify_domain(domain, &key);
+
+        if problems.is_empty() {
+            println!("ok {} ({} signed checks)", domain.url, signed);
+        } else {
+            failed += 1;
+            println!("FAILED {}", domain.url);
+            for problem in problems {
+                println!("    {}", problem);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("History of {} domains failed verification", failed),
+        ));
+    }
+    println!("All {} domains verified.", domains.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::domains::DomainStatus;
+    use chrono::Utc;
+
+    fn signed_domain(signer: &CheckSigner, checks: usize) -> MonitoredDomain {
+        let mut domain = MonitoredDomain::new("https://example.com");
+        for i in 0..checks {
+            let mut check = CheckStatus {
+                response_time_ms: Some(i as u64),
+                ..CheckStatus::new(Utc::now(), DomainStatus::Up)
+            };
+            signer.sign(domain.id, domain.check_history.last(), &mut check);
+            domain.check_history.push(check);
+        }
+        domain
+    }
+
+    fn signer() -> CheckSigner {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        CheckSigner::load_or_create(&temp_dir.path().join("signing.key"))
+            .expect("Failed to create signing key")
+    }
+
+    #[test]
+    fn untouched_history_verifies() {
+        let signer = signer();
+        let domain = signed_domain(&signer, 5);
+        assert!(verify_domain(&domain, &signer.key.verifying_key()).is_empty());
+    }
+
+    #[test]
+    fn trimmed_history_still_verifies() {
+        let signer = signer();
+        let mut domain = signed_domain(&signer, 5);
+        domain.check_history.drain(0..2);
+        assert!(verify_domain(&domain, &signer.key.verifying_key()).is_empty());
+    }
+
+    #[test]
+    fn edited_record_is_detected() {
+        let signer = signer();
+        let mut domain = signed_domain(&signer, 5);
+        domain.check_history[2].status = DomainStatus::Down;
+
+        let problems = verify_domain(&domain, &signer.key.verifying_key());
+        // The edited record's signature and the next record's link both break.
+        assert_eq!(problems.len(), 2);
+    }
+
+    #[test]
+    fn removed_record_is_detected() {
+        let signer = signer();
+        let mut domain = signed_domain(&signer, 5);
+        domain.check_history.remove(2);
+        assert_eq!(verify_domain(&domain, &signer.key.verifying_key()).len(), 1);
+    }
+
+    #[test]
+    fn key_is_reused_once_created() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let key_path = temp_dir.path().join("keys/signing.key");
+
+        let first = CheckSigner::load_or_create(&key_path).expect("Failed to create key");
+        let second = CheckSigner::load_or_create(&key_path).expect("Failed to load key");
+        assert_eq!(first.key.to_bytes(), second.key.to_bytes());
+        assert!(public_key_path(&key_path).exists());
+    }
+}
@@ -0,0 +1,296 @@
+use crate::ui::domains::MonitoredDomain;
+use std::{fs, io, path::Path};
+
+pub static FILE_PATH: &str = "db/domains.json";
+
+// The store's JSON, kept apart from the file handling so it can be benchmarked.
+pub fn domains_to_json(domains: &[MonitoredDomain]) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(domains)
+}
+
+pub fn domains_from_json(json: &str) -> serde_json::Result<Vec<MonitoredDomain>> {
+    serde_json::from_str(json)
+}
+
+#[tracing::instrument(skip(domains), fields(domains = domains.len()))]
+pub fn save_domains(domains: &[MonitoredDomain], file_path: &str) -> io::Result<()> {
+    let domain_data = domains_to_json(domains)?;
+
+    if let Some(parent) = Path::new(file_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(file_path, domain_data)?;
+    Ok(())
+}
+
+#[tracing::instrument]
+pub fn load_domains(file_path: &str) -> io::Result<Vec<MonitoredDomain>> {
+    let domain_data = fs::read_to_string(file_path)?;
+    Ok(domains_from_json(&domain_data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::incident::IncidentNote;
+    use crate::monitor::CheckTimings;
+    use crate::ui::domains::{CheckStatus, DomainStatus, HttpCode};
+    use chrono::{DateTime, Utc};
+    use proptest::{option, prelude::*};
+    use serde_json::Value;
+
+    // Fields added after the first release, which stores written by older
+    // versions don't have.
+    const NEWER_DOMAIN_FIELDS: [&str; 29] = [
+        "display_name",
+        "timeout_seconds",
+        "history_max_checks",
+        "history_max_age_days",
+        "latency_good_below_ms",
+        "latency_warn_below_ms",
+        "incident_notes",
+        "muted_until",
+        "muted_from",
+        "expected_status_codes",
+        "expected_redirect",
+        "max_redirects",
+        "keyword",
+        "body_regex",
+        "http_method",
+        "request_body",
+        "headers",
+        "auth",
+        "challenge",
+        "ssh_jump_host",
+        "kubeconfig",
+        "owner",
+        "environment",
+        "tags",
+        "metadata",
+        "group",
+        "certificate",
+        "daily_rollups",
+        "incident_log",
+    ];
+    const NEWER_CHECK_FIELDS: [&str; 7] = [
+        "signature",
+        "timings",
+        "detail",
+        "packet_loss_percent",
+        "redirects",
+        "response_bytes",
+        "traceroute",
+    ];
+
+    fn timestamp() -> impl Strategy<Value = DateTime<Utc>> {
+        (0i64..4_000_000_000, 0u32..1_000_000_000).prop_map(|(secs, nanos)| {
+            DateTime::from_timestamp(secs, nanos).expect("Timestamp in range")
+        })
+    }
+
+    fn check_status() -> impl Strategy<Value = CheckStatus> {
+        let status = prop_oneof![
+            Just(DomainStatus::Up),
+            Just(DomainStatus::Degraded),
+            Just(DomainStatus::Down),
+            Just(DomainStatus::Unknown),
+            Just(DomainStatus::RateLimited),
+            ".{0,40}".prop_map(|e| DomainStatus::Error(e.into())),
+        ];
+        let http_code = prop_oneof![
+            Just(HttpCode::Ok),
+            Just(HttpCode::Err),
+            (100u16..600).prop_map(HttpCode::Other),
+            Just(HttpCode::Timeout),
+            Just(HttpCode::NetworkError),
+        ];
+        let timings = (0u64..5000, 0u64..5000, option::of(0u64..5000), 0u64..5000).prop_map(
+            |(dns_ms, connect_ms, tls_ms, ttfb_ms)| CheckTimings {
+                dns_ms,
+                connect_ms,
+                tls_ms,
+                ttfb_ms,
+            },
+        );
+        (
+            timestamp(),
+            status,
+            option::of(http_code),
+            option::of(0u64..120_000),
+            option::of(".{0,40}"),
+            option::of(timings),
+            option::of(".{0,20}"),
+            option::of((0u8..=100).prop_map(f64::from)),
+            option::of(0u64..10_000_000),
+        )
+            .prop_map(
+                |(
+                    timestamp,
+                    status,
+                    http_code,
+                    response_time_ms,
+                    error_message,
+                    timings,
+                    detail,
+                    packet_loss_percent,
+                    response_bytes,
+                )| {
+                    CheckStatus {
+                        http_code,
+                        response_time_ms,
+                        error_message: error_message.map(Into::into),
+                        timings,
+                        detail: detail.map(Into::into),
+                        packet_loss_percent,
+                        response_bytes,
+                        ..CheckStatus::new(timestamp, status)
+                    }
+                },
+            )
+    }
+
+    fn monitored_domain() -> impl Strategy<Value = MonitoredDomain> {
+        let note = (timestamp(), ".{0,80}", timestamp()).prop_map(
+            |(incident_started_at, note, updated_at)| IncidentNote {
+                incident_started_at,
+                note,
+                updated_at,
+            },
+        );
+        (
+            "https?://[a-z]{1,12}\\.(com|org|io)(/[a-z0-9]{0,8}){0,2}",
+            1u64..86_400,
+            option::of(1u64..120),
+            (option::of(1usize..100_000), option::of(1u64..365)),
+            (option::of(1u64..1000), option::of(1000u64..5000)),
+            prop::collection::vec(note, 0..3),
+            option::of(timestamp()),
+            prop::collection::vec(check_status(), 0..20),
+        )
+            .prop_map(
+                |(
+                    url,
+                    interval_seconds,
+                    timeout_seconds,
+                    (history_max_checks, history_max_age_days),
+                    (latency_good_below_ms, latency_warn_below_ms),
+                    incident_notes,
+                    muted_until,
+                    check_history,
+                )| {
+                    let mut domain = MonitoredDomain::new(&url);
+                    domain.interval_seconds = interval_seconds;
+                    domain.timeout_seconds = timeout_seconds;
+                    domain.history_max_checks = history_max_checks;
+                    domain.history_max_age_days = history_max_age_days;
+                    domain.latency_good_below_ms = latency_good_below_ms;
+                    domain.latency_warn_below_ms = latency_warn_below_ms;
+                    domain.incident_notes = incident_notes;
+                    domain.muted_until = muted_until;
+                    domain.check_history = check_history;
+                    domain
+                },
+            )
+    }
+
+    // Domains have no PartialEq, so they're compared by what they serialize to.
+    fn to_value(domains: &[MonitoredDomain]) -> Value {
+        serde_json::to_value(domains).expect("Domains serialize")
+    }
+
+    // What a store from before the newer fields looks like.
+    fn strip_newer_fields(mut store: Value) -> Value {
+        for domain in store.as_array_mut().expect("Store is an array") {
+            let domain = domain.as_object_mut().expect("Domain is an object");
+            for field in NEWER_DOMAIN_FIELDS {
+                domain.remove(field);
+            }
+            for check in domain["check_history"]
+                .as_array_mut()
+                .expect("History is an array")
+            {
+                let check = check.as_object_mut().expect("Check is an object");
+                for field in NEWER_CHECK_FIELDS {
+                    check.remove(field);
+                }
+            }
+        }
+        store
+    }
+
+    proptest! {
+        #[test]
+        fn domains_round_trip_through_json(
+            domains in prop::collection::vec(monitored_domain(), 0..5)
+        ) {
+            let json = domains_to_json(&domains).expect("Domains serialize");
+            let loaded = domains_from_json(&json).expect("Domains deserialize");
+            prop_assert_eq!(to_value(&loaded), to_value(&domains));
+        }
+
+        #[test]
+        fn checks_round_trip_through_json(check in check_status()) {
+            let json = serde_json::to_string(&check).expect("Check serializes");
+            let loaded: CheckStatus = serde_json::from_str(&json).expect("Check deserializes");
+            prop_assert_eq!(loaded.timestamp, check.timestamp);
+            prop_assert_eq!(&loaded.status, &check.status);
+            prop_assert_eq!(&loaded.http_code, &check.http_code);
+            prop_assert_eq!(loaded.response_time_ms, check.response_time_ms);
+            prop_assert_eq!(&loaded.error_message, &check.error_message);
+            prop_assert_eq!(loaded.timings, check.timings);
+            prop_assert_eq!(&loaded.detail, &check.detail);
+        }
+
+        #[test]
+        fn stores_without_newer_fields_still_load(
+            domains in prop::collection::vec(monitored_domain(), 1..5)
+        ) {
+            let old_store = strip_newer_fields(to_value(&domains));
+            let loaded = domains_from_json(&old_store.to_string()).expect("Old store loads");
+
+            for domain in &loaded {
+                prop_assert!(domain.timeout_seconds.is_none());
+                prop_assert!(domain.incident_notes.is_empty());
+                prop_assert!(domain.muted_until.is_none());
+                prop_assert!(domain.check_history.iter().all(|c| c.timings.is_none()));
+                prop_assert!(domain.check_history.iter().all(|c| c.detail.is_none()));
+            }
+            // Saving it again only adds the newer fields, timeout_seconds as
+            // null since it's always written.
+            prop_assert_eq!(strip_newer_fields(to_value(&loaded)), old_store);
+        }
+    }
+
+    proptest! {
+        // Each case writes a file, so fewer of them.
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn domains_round_trip_through_the_store_file(
+            domains in prop::collection::vec(monitored_domain(), 0..5)
+        ) {
+            let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+            let store_path = temp_dir.path().join("domains.json");
+            let store_path = store_path.to_str().expect("Temp dir path is UTF-8");
+
+            save_domains(&domains, store_path).expect("Failed to save domains");
+            let loaded = load_domains(store_path).expect("Failed to load domains");
+            prop_assert_eq!(to_value(&loaded), to_value(&domains));
+        }
+    }
+
+    #[test]
+    fn saving_creates_missing_directories() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        // Built with join so the test uses "\" on Windows like real paths do.
+        let store_path = temp_dir.path().join("db").join("work").join("domains.json");
+        let store_path = store_path.to_str().expect("Temp dir path is UTF-8");
+
+        let domains = vec![MonitoredDomain::new("https://example.com")];
+        save_domains(&domains, store_path).expect("Failed to save domains");
+
+        let loaded = load_domains(store_path).expect("Failed to load domains");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(&*loaded[0].url, "https://example.com");
+    }
+}
@@ -0,0 +1,70 @@
+use crate::configuration::TelemetrySettings;
+use tracing::Subscriber;
+use tracing_subscriber::{Layer, registry::LookupSpan};
+
+// Exports the check and storage spans over OTLP/HTTP until dropped, which
+// flushes the ones still queued.
+pub struct Telemetry {
+    #[cfg(feature = "otel")]
+    provider: opentelemetry_sdk::trace::SdkTracerProvider,
+}
+
+// The layer that exports spans, when [telemetry] has an endpoint. Without one
+// the spans cost next to nothing, there's nothing listening for them. Errors
+// are returned rather than logged, logging isn't set up yet.
+#[cfg(feature = "otel")]
+pub fn init_telemetry<S>(
+    settings: &TelemetrySettings,
+) -> Result<Option<(Telemetry, impl Layer<S>)>, String>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
+
+    let Some(endpoint) = settings.otlp_endpoint.as_deref() else {
+        return Ok(None);
+    };
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| format!("Can't export traces to {}: {}", endpoint, e))?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(settings.service_name.clone())
+                .build(),
+        )
+        .build();
+
+    let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("upquack"));
+    Ok(Some((Telemetry { provider }, layer)))
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn init_telemetry<S>(
+    settings: &TelemetrySettings,
+) -> Result<Option<(Telemetry, impl Layer<S>)>, String>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    if settings.otlp_endpoint.is_some() {
+        return Err(
+            "[telemetry] has an otlp_endpoint, but this build has no OpenTelemetry export"
+                .to_string(),
+        );
+    }
+    Ok(None::<(Telemetry, tracing_subscriber::layer::Identity)>)
+}
+
+#[cfg(feature = "otel")]
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            tracing::error!("Flushing traces failed: {}", e);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use chrono::{DateTime, Utc};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Widget},
+};
+
+// How far ahead the timeline looks.
+const WINDOW_SECS: i64 = 60;
+
+// More checks than this starting in the same second counts as a cluster.
+const CLUSTER_THRESHOLD: usize = 3;
+
+static BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// One column per second of the next minute, showing how many checks are due
+// in it, so checks bunching up at the same moment stand out.
+#[derive(Debug)]
+pub struct CheckTimeline {
+    upcoming: Vec<DateTime<Utc>>,
+    now: DateTime<Utc>,
+}
+
+impl CheckTimeline {
+    pub fn new(upcoming: Vec<DateTime<Utc>>, now: DateTime<Utc>) -> Self {
+        Self { upcoming, now }
+    }
+
+    pub fn height() -> u16 {
+        4
+    }
+
+    // Checks due in each second of the window. Overdue ones count as due now.
+    fn buckets(&self) -> Vec<usize> {
+        let mut buckets = vec![0; WINDOW_SECS as usize];
+        for next_check in &self.upcoming {
+            let secs = (*next_check - self.now).num_seconds().max(0);
+            if secs < WINDOW_SECS {
+                buckets[secs as usize] += 1;
+            }
+        }
+        buckets
+    }
+}
+
+impl Widget for CheckTimeline {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let buckets = self.buckets();
+        let busiest = buckets.iter().copied().max().unwrap_or(0);
+        let clustered = busiest > CLUSTER_THRESHOLD;
+
+        let title = format!(
+            " Upcoming checks: {} in the next {}s, busiest second {} ",
+            buckets.iter().sum::<usize>(),
+            WINDOW_SECS,
+            busiest
+        );
+        let block = Block::bordered()
+            .title(if clustered {
+                Span::from(title).yellow().bold()
+            } else {
+                Span::from(title)
+            })
+            .border_style(Style::default().fg(Color::DarkGray));
+
+        // Seconds are spread over the available width, or squeezed together
+        // when the terminal is narrower than the window.
+        let width = block.inner(area).width.max(1) as usize;
+        let columns: Vec<usize> = (0..width)
+            .map(|column| {
+                let start = column * buckets.len() / width;
+                let end = ((column + 1) * buckets.len() / width).max(start + 1);
+                buckets[start..end.min(buckets.len())]
+                    .iter()
+                    .copied()
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let bars: Vec<Span> = columns
+            .iter()
+            .map(|&count| {
+                if count == 0 {
+                    return Span::raw(" ");
+                }
+                let bar = BARS[(count * BARS.len() / busiest.max(1)).clamp(1, BARS.len()) - 1];
+                let color = if count > CLUSTER_THRESHOLD {
+                    Color::Red
+                } else {
+                    Color::Green
+                };
+                Span::styled(bar.to_string(), Style::default().fg(color))
+            })
+            .collect();
+
+        let end_label = format!("+{}s", WINDOW_SECS);
+        let axis = format!("now{:>width$}", end_label, width = width.saturating_sub(3));
+
+        Paragraph::new(vec![Line::from(bars), Line::from(axis).dark_gray()])
+            .block(block)
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn checks_are_counted_per_second_ahead() {
+        let now = Utc::now();
+        let timeline = CheckTimeline::new(
+            vec![
+                // Overdue, so due now.
+                now - Duration::seconds(5),
+                now,
+                now + Duration::seconds(10),
+                now + Duration::milliseconds(10_500),
+                // Past the window.
+                now + Duration::seconds(WINDOW_SECS),
+            ],
+            now,
+        );
+        let buckets = timeline.buckets();
+
+        assert_eq!(buckets.len(), WINDOW_SECS as usize);
+        assert_eq!(buckets[0], 2);
+        assert_eq!(buckets[10], 2);
+        assert_eq!(buckets.iter().sum::<usize>(), 4);
+    }
+
+    #[test]
+    fn clusters_are_flagged() {
+        let now = Utc::now();
+        let area = Rect::new(0, 0, 62, CheckTimeline::height());
+        let mut buf = Buffer::empty(area);
+        let upcoming = vec![now + Duration::seconds(30); CLUSTER_THRESHOLD + 1];
+        CheckTimeline::new(upcoming, now).render(area, &mut buf);
+
+        let rendered: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(rendered.contains("Upcoming checks: 4 in the next 60s, busiest second 4"));
+        let bar = buf
+            .content()
+            .iter()
+            .find(|cell| cell.symbol() == "█")
+            .expect("The busy second has a full bar");
+        assert_eq!(bar.fg, Color::Red);
+    }
+}
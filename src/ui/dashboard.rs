@@ -0,0 +1,243 @@
+use crate::configuration::Settings;
+use crate::incident::format_duration;
+use crate::monitor::{DomainSnapshot, MonitorHandle};
+use crate::ui::domains::DomainStatus;
+use crate::ui::table_view::in_environment;
+use chrono::{DateTime, Duration, Utc};
+use crossterm::event::KeyEvent;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Paragraph, Widget},
+};
+use std::{cmp::Reverse, sync::Arc};
+
+// The state of every domain at a glance.
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub domains: usize,
+    pub up: usize,
+    pub down: usize,
+    pub errors: usize,
+    // Unknown, rate limited or not checked yet.
+    pub other: usize,
+    pub muted: usize,
+    // Averaged over the domains with checks.
+    pub average_uptime: Option<f64>,
+    // The failing domains and how long they've been failing, longest first.
+    pub failing: Vec<(Arc<str>, Duration)>,
+}
+
+pub fn summarize(
+    domains: &[Arc<DomainSnapshot>],
+    now: DateTime<Utc>,
+    count_rate_limited_as_down: bool,
+) -> Summary {
+    let mut summary = Summary {
+        domains: domains.len(),
+        ..Summary::default()
+    };
+    let mut uptimes = Vec::new();
+    for domain in domains {
+        match domain.last_check.as_ref().map(|c| &c.status) {
+            Some(DomainStatus::Up) => summary.up += 1,
+            Some(DomainStatus::Down) => summary.down += 1,
+            Some(DomainStatus::Error(_)) => summary.errors += 1,
+            _ => summary.other += 1,
+        }
+        if domain.is_muted(now) {
+            summary.muted += 1;
+        }
+        uptimes.extend(domain.uptime_percentage(count_rate_limited_as_down));
+        if let Some(since) = domain.failing_since {
+            summary.failing.push((domain.name().into(), now - since));
+        }
+    }
+    summary.average_uptime =
+        (!uptimes.is_empty()).then(|| uptimes.iter().sum::<f64>() / uptimes.len() as f64);
+    summary
+        .failing
+        .sort_by_key(|(_, duration)| Reverse(*duration));
+    summary
+}
+
+#[derive(Debug)]
+pub struct DashboardScreen {
+    summary: Summary,
+    // Of the snapshot the summary was worked out from.
+    version: Option<u64>,
+    settings: Settings,
+    monitor: MonitorHandle,
+    // Only this environment's domains are counted when set.
+    environment: Option<String>,
+}
+
+impl DashboardScreen {
+    pub fn new(settings: Settings, monitor: &MonitorHandle, environment: Option<String>) -> Self {
+        let mut screen = DashboardScreen {
+            summary: Summary::default(),
+            version: None,
+            settings,
+            monitor: monitor.clone(),
+            environment,
+        };
+        screen.refresh();
+        screen
+    }
+
+    // Only the summary is kept, so it's worked out again when the snapshot
+    // changed rather than every frame.
+    fn refresh(&mut self) {
+        let snapshot = self.monitor.snapshot();
+        if self.version == Some(snapshot.version) {
+            return;
+        }
+        self.version = Some(snapshot.version);
+        let domains: Vec<Arc<DomainSnapshot>> = snapshot
+            .domains
+            .iter()
+            .filter(|d| in_environment(self.environment.as_deref(), d.environment.as_deref()))
+            .cloned()
+            .collect();
+        self.summary = summarize(
+            &domains,
+            Utc::now(),
+            self.settings.monitor.count_rate_limited_as_down,
+        );
+    }
+
+    pub fn handle_key_event(&mut self, _key_event: KeyEvent) -> bool {
+        false
+    }
+}
+
+impl Widget for &mut DashboardScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        self.refresh();
+        let locale = &self.settings.display.locale;
+        let summary = &self.summary;
+
+        let block = Block::bordered()
+            .title(
+                Line::from(match &self.environment {
+                    Some(environment) => format!(" Dashboard ({}) ", environment),
+                    None => " Dashboard ".to_string(),
+                })
+                .centered(),
+            )
+            .title_bottom(Line::from(" Esc: Go Back | Q: Quit ").centered());
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        let [totals_area, failing_area] =
+            Layout::vertical([Constraint::Length(7), Constraint::Min(0)]).areas(inner_area);
+
+        let web_dashboard = if self.settings.server.enabled {
+            Line::from(format!(
+                " Web dashboard: http://{}/",
+                self.settings.server.address
+            ))
+        } else {
+            Line::from(
+                " Web dashboard: off, set enabled = true under [server] or run `upquack daemon`",
+            )
+            .dark_gray()
+        };
+        let totals = vec![
+            Line::from(format!(
+                " Domains: {}",
+                locale.integer(summary.domains as u64)
+            ))
+            .bold(),
+            Line::from(vec![
+                format!(" UP: {}", locale.integer(summary.up as u64)).green(),
+                format!("   DOWN: {}", locale.integer(summary.down as u64)).red(),
+                format!("   ERROR: {}", locale.integer(summary.errors as u64)).red(),
+                format!("   Other: {}", locale.integer(summary.other as u64)).dark_gray(),
+                format!("   Muted: {}", locale.integer(summary.muted as u64)).dark_gray(),
+            ]),
+            Line::from(format!(
+                " Average uptime: {}",
+                summary
+                    .average_uptime
+                    .map_or("-".to_string(), |uptime| locale.percentage(uptime))
+            )),
+            Line::from(""),
+            web_dashboard,
+        ];
+        Paragraph::new(totals).render(totals_area, buf);
+
+        let mut failing = vec![Line::from(" Failing now").bold()];
+        if summary.failing.is_empty() {
+            failing.push(Line::from(" Nothing, every checked domain is up.").green());
+        }
+        failing.extend(summary.failing.iter().map(|(url, duration)| {
+            Line::from(format!(" {} for {}", url, format_duration(*duration))).red()
+        }));
+        Paragraph::new(failing).render(failing_area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::domains::{CheckStatus, MonitoredDomain};
+
+    fn checked(url: &str, statuses: &[DomainStatus], now: DateTime<Utc>) -> MonitoredDomain {
+        let mut domain = MonitoredDomain::new(url);
+        domain.check_history = statuses
+            .iter()
+            .enumerate()
+            .map(|(i, status)| {
+                CheckStatus::new(
+                    now - Duration::minutes((statuses.len() - i) as i64),
+                    status.clone(),
+                )
+            })
+            .collect();
+        domain
+    }
+
+    #[test]
+    fn summary_counts_latest_statuses() {
+        let now = Utc::now();
+        let domains = [
+            checked("https://up.com", &[DomainStatus::Up, DomainStatus::Up], now),
+            checked(
+                "https://down.com",
+                &[DomainStatus::Up, DomainStatus::Down, DomainStatus::Down],
+                now,
+            ),
+            checked(
+                "https://error.com",
+                &[DomainStatus::Up, DomainStatus::Error("timeout".into())],
+                now,
+            ),
+            MonitoredDomain::new("https://new.com"),
+        ];
+
+        let domains: Vec<_> = domains
+            .iter()
+            .map(|d| Arc::new(DomainSnapshot::new(d)))
+            .collect();
+        let summary = summarize(&domains, now, false);
+
+        assert_eq!(
+            (
+                summary.domains,
+                summary.up,
+                summary.down,
+                summary.errors,
+                summary.other
+            ),
+            (4, 1, 1, 1, 1)
+        );
+        // 100%, 33.3% and 50%.
+        let average = summary.average_uptime.unwrap();
+        assert!((average - 61.11).abs() < 0.01, "{}", average);
+        let failing: Vec<&str> = summary.failing.iter().map(|(url, _)| &**url).collect();
+        assert_eq!(failing, ["https://down.com", "https://error.com"]);
+    }
+}
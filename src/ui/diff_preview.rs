@@ -0,0 +1,60 @@
+use crate::ui::domains::FieldChange;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Block, Clear, Paragraph, Widget, Wrap},
+};
+
+// Confirmation popup listing what an edit is about to change.
+#[derive(Debug)]
+pub struct DiffPreview<'a> {
+    title: &'a str,
+    changes: &'a [FieldChange],
+}
+
+impl<'a> DiffPreview<'a> {
+    pub fn new(title: &'a str, changes: &'a [FieldChange]) -> Self {
+        Self { title, changes }
+    }
+
+    pub fn height(&self) -> u16 {
+        self.changes.len() as u16 * 2 + 2
+    }
+}
+
+impl Widget for DiffPreview<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+
+        let block = Block::bordered()
+            .title(self.title)
+            .title_style(Style::default().fg(Color::White).bold())
+            .title_bottom(Line::from(" Enter/Y: Save - Esc/N: Back ").centered())
+            .border_style(Style::default().fg(Color::Yellow))
+            .style(Style::default().bg(Color::DarkGray));
+
+        let lines = self
+            .changes
+            .iter()
+            .flat_map(|change| {
+                [
+                    Line::from(vec![
+                        format!("{}: ", change.field).bold(),
+                        format!("- {}", change.old).red(),
+                    ]),
+                    Line::from(vec![
+                        " ".repeat(change.field.len() + 2).into(),
+                        format!("+ {}", change.new).green(),
+                    ]),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        Paragraph::new(Text::from(lines))
+            .wrap(Wrap { trim: false })
+            .block(block)
+            .render(area, buf);
+    }
+}
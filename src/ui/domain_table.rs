@@ -1,14 +1,18 @@
+use crate::blind_spot::{CheckAge, check_age};
+use crate::configuration::Settings;
+use crate::incident::format_duration;
+use crate::monitor::CheckTarget;
 use crate::ui::domains::{DomainStatus, HttpCode, MonitoredDomain};
+use crate::ui::table_view::{Column, SortColumn, TableView};
+use chrono::Utc;
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Rect},
     style::{Color, Modifier, Style, Stylize},
-    text::Span,
+    text::{Line, Span},
     widgets::{Cell, Row, StatefulWidget, Table, TableState},
 };
 
-use chrono::prelude::*;
-
 #[derive(Debug, Default)]
 pub struct DomainTableState {
     pub table_state: TableState,
@@ -16,12 +20,85 @@ pub struct DomainTableState {
 
 #[derive(Debug)]
 pub struct DomainTable<'a> {
-    domains: &'a [MonitoredDomain],
+    domains: Vec<&'a MonitoredDomain>,
+    settings: &'a Settings,
+    // For the hidden columns and which one is sorted by.
+    view: Option<&'a TableView>,
 }
 
+// Every column with its header, what hides it and what sorts by it. The name
+// can't be hidden.
+const COLUMNS: [(&str, Option<Column>, Option<SortColumn>, Constraint); 10] = [
+    (
+        "Name",
+        None,
+        Some(SortColumn::Name),
+        Constraint::Percentage(30),
+    ),
+    (
+        "Owner",
+        Some(Column::Owner),
+        Some(SortColumn::Owner),
+        Constraint::Length(12),
+    ),
+    (
+        "Status",
+        Some(Column::Status),
+        Some(SortColumn::Status),
+        Constraint::Length(12),
+    ),
+    // STALE included.
+    (
+        "Last Check",
+        Some(Column::LastCheck),
+        Some(SortColumn::LastCheck),
+        Constraint::Length(24),
+    ),
+    (
+        "Response Time",
+        Some(Column::ResponseTime),
+        Some(SortColumn::ResponseTime),
+        Constraint::Length(15),
+    ),
+    ("Loss", Some(Column::Loss), None, Constraint::Length(8)),
+    ("Result", Some(Column::Result), None, Constraint::Length(20)),
+    (
+        "Uptime",
+        Some(Column::Uptime),
+        Some(SortColumn::Uptime),
+        Constraint::Length(8),
+    ),
+    (
+        "Interval",
+        Some(Column::Interval),
+        Some(SortColumn::Interval),
+        Constraint::Length(8),
+    ),
+    ("Muted", Some(Column::Muted), None, Constraint::Length(8)),
+];
+
 impl<'a> DomainTable<'a> {
-    pub fn new(domains: &'a [MonitoredDomain]) -> Self {
-        Self { domains }
+    pub fn new(
+        domains: impl IntoIterator<Item = &'a MonitoredDomain>,
+        settings: &'a Settings,
+    ) -> Self {
+        Self {
+            domains: domains.into_iter().collect(),
+            settings,
+            view: None,
+        }
+    }
+
+    pub fn with_view(mut self, view: &'a TableView) -> Self {
+        self.view = Some(view);
+        self
+    }
+
+    fn shows(&self, column: Option<Column>) -> bool {
+        if column == Some(Column::Loss) && !self.domains.iter().any(|d| is_ping(d)) {
+            return false;
+        }
+        column.is_none_or(|column| self.view.is_none_or(|view| view.shows(column)))
     }
 }
 
@@ -29,22 +106,32 @@ impl<'a> StatefulWidget for DomainTable<'a> {
     type State = DomainTableState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        let header_cells = [
-            "URL",
-            "Status",
-            "Last Check",
-            "Response Time",
-            "HTTP Code",
-            "Interval",
-        ]
-        .iter()
-        .map(|h| Cell::from(*h).style(Style::default().bold()));
+        let shown: Vec<bool> = COLUMNS
+            .iter()
+            .map(|(_, column, _, _)| self.shows(*column))
+            .collect();
+        let sort = self.view.and_then(|view| view.sort);
+        let header_cells = COLUMNS
+            .iter()
+            .zip(&shown)
+            .filter(|(_, shown)| **shown)
+            .map(|(column, _)| column)
+            .map(|(header, _, sort_column, _)| {
+                let header = match sort {
+                    Some(sort) if Some(sort.column) == *sort_column => {
+                        format!("{} {}", header, if sort.descending { "▼" } else { "▲" })
+                    }
+                    _ => header.to_string(),
+                };
+                Cell::from(header).style(Style::default().bold())
+            });
 
         let header = Row::new(header_cells)
             .height(1)
             .bottom_margin(1)
             .style(Style::default().bg(Color::Yellow).fg(Color::Black));
 
+        let now = Utc::now();
         let rows: Vec<Row> = self
             .domains
             .iter()
@@ -57,8 +144,29 @@ impl<'a> StatefulWidget for DomainTable<'a> {
                     Color::Reset
                 };
 
-                let url_display = domain.url.clone();
-                let interval_display = format!("{}s", domain.interval_seconds);
+                let locale = &self.settings.display.locale;
+                let interval_display = format!("{}s", locale.integer(domain.interval_seconds));
+                let uptime_display = domain
+                    .uptime_percentage(self.settings.monitor.count_rate_limited_as_down)
+                    .map(|pct| locale.percentage(pct))
+                    .unwrap_or_else(|| "N/A".to_string());
+                // Only ping checks measure loss.
+                let loss_display = match domain.check_history.last() {
+                    Some(check) if is_ping(domain) => match check.packet_loss_percent {
+                        Some(loss) if loss > 0.0 => {
+                            Span::styled(locale.percentage(loss), Style::default().yellow())
+                        }
+                        Some(loss) => Span::raw(locale.percentage(loss)),
+                        None => Span::raw("N/A"),
+                    },
+                    _ => Span::raw("-"),
+                };
+                let muted_display = domain
+                    .mute_remaining(now)
+                    .map(|remaining| {
+                        Span::styled(format_duration(remaining), Style::default().magenta())
+                    })
+                    .unwrap_or_else(|| Span::raw("-"));
 
                 // --- Extract the latest check result ---
                 let (status_display, last_check_display, response_time_display, http_code_display) =
@@ -66,25 +174,35 @@ impl<'a> StatefulWidget for DomainTable<'a> {
                         // Get the last element
                         let status = match &latest_check.status {
                             DomainStatus::Up => Span::styled("UP", Style::default().green().bold()),
+                            DomainStatus::Degraded => {
+                                Span::styled("DEGRADED", Style::default().yellow().bold())
+                            }
                             DomainStatus::Down => {
                                 Span::styled("DOWN", Style::default().red().bold())
                             }
                             DomainStatus::Unknown => {
                                 Span::styled("UNKNOWN", Style::default().yellow().bold())
                             }
+                            DomainStatus::RateLimited => {
+                                Span::styled("RATE LIMITED", Style::default().yellow().bold())
+                            }
                             DomainStatus::Error(e) => {
                                 Span::styled(format!("Error: {}", e), Style::default().red())
                             }
                         };
-                        let last_check = latest_check
-                            .timestamp
-                            .with_timezone(&Local)
-                            .format("%Y-%m-%d %H:%M:%S")
-                            .to_string();
-                        let response_time = latest_check
-                            .response_time_ms
-                            .map(|ms| format!("{}ms", ms))
-                            .unwrap_or_else(|| "N/A".to_string());
+                        let last_check = locale.timestamp(latest_check.timestamp);
+                        let last_check = match check_age(latest_check, domain.interval_seconds, now)
+                        {
+                            CheckAge::Fresh => Span::raw(last_check),
+                            CheckAge::Late => Span::styled(last_check, Style::default().yellow()),
+                            CheckAge::Stale => Span::styled(
+                                format!("{} STALE", last_check),
+                                Style::default().red().bold(),
+                            ),
+                        };
+                        let response_time = domain
+                            .latency_settings(self.settings.latency)
+                            .span(latest_check.response_time_ms, locale);
                         let http_code = match &latest_check.http_code {
                             Some(HttpCode::Ok) => Span::styled("200 OK", Style::default().green()),
                             Some(HttpCode::Err) => Span::styled("500 ERR", Style::default().red()),
@@ -97,47 +215,77 @@ impl<'a> StatefulWidget for DomainTable<'a> {
                             Some(HttpCode::NetworkError) => {
                                 Span::styled("Net Err", Style::default().red())
                             }
-                            None => Span::styled("N/A", Style::default().gray()),
+                            // Checks other than HTTP describe what they found instead.
+                            None => match &latest_check.detail {
+                                Some(detail) => Span::raw(&**detail),
+                                None => Span::styled("N/A", Style::default().gray()),
+                            },
                         };
                         (status, last_check, response_time, http_code)
                     } else {
                         // If no check history yet
                         (
                             Span::styled("N/A", Style::default().gray()), // Status
-                            "N/A".to_string(),                            // Last Check
-                            "N/A".to_string(),                            // Response Time
-                            Span::styled("N/A", Style::default().gray()), // HTTP Code
+                            Span::raw("N/A"),                             // Last Check
+                            Span::styled("N/A", Style::default().gray()), // Response Time
+                            Span::styled("N/A", Style::default().gray()), // Result
                         )
                     };
 
-                let cells = vec![
+                // What's weak is listed on the history screen.
+                let mut name_spans = vec![Span::raw(domain.name())];
+                if let Some(inspection) = &domain.certificate
+                    && !inspection.warnings.is_empty()
+                {
+                    name_spans.push(Span::styled(" WEAK TLS", Style::default().yellow().bold()));
+                }
+                for tag in &domain.tags {
+                    name_spans.push(Span::styled(
+                        format!(" #{}", tag),
+                        Style::default().dark_gray(),
+                    ));
+                }
+                let url_display = Line::from(name_spans);
+
+                let cells = [
                     Cell::from(url_display),
+                    Cell::from(domain.owner.as_deref().unwrap_or("-")),
                     Cell::from(status_display),
                     Cell::from(last_check_display),
                     Cell::from(response_time_display),
+                    Cell::from(loss_display),
                     Cell::from(http_code_display),
+                    Cell::from(uptime_display),
                     Cell::from(interval_display),
+                    Cell::from(muted_display),
                 ];
+                let cells = cells
+                    .into_iter()
+                    .zip(&shown)
+                    .filter(|(_, shown)| **shown)
+                    .map(|(cell, _)| cell);
                 Row::new(cells).style(Style::default().bg(row_color))
             })
             .collect();
 
-        let table = Table::new(
-            rows,
-            [
-                Constraint::Percentage(30), // For URL
-                Constraint::Length(10),     // For Status
-                Constraint::Length(18),     // For Last Check
-                Constraint::Length(15),     // For Response Time
-                Constraint::Length(10),     // For HTTP Code
-                Constraint::Length(8),      // For Interval
-            ],
-        )
-        .column_spacing(2)
-        .header(header)
-        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
-        .highlight_symbol("-> ");
+        let widths = COLUMNS
+            .iter()
+            .zip(&shown)
+            .filter(|(_, shown)| **shown)
+            .map(|((_, _, _, width), _)| *width);
+        let table = Table::new(rows, widths)
+            .column_spacing(2)
+            .header(header)
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("-> ");
 
         table.render(area, buf, &mut state.table_state);
     }
 }
+
+fn is_ping(domain: &MonitoredDomain) -> bool {
+    matches!(
+        CheckTarget::parse(&domain.url),
+        Ok(CheckTarget::Ping { .. })
+    )
+}
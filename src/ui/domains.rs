@@ -1,58 +1,1416 @@
-use log::error;
-use std::sync::{Arc, Mutex};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::error;
 
-use std::{fs, io};
-
-use crate::monitor::start_monitoring_task;
+use crate::app::AppEvent;
+use crate::budget::scheduled_load;
+use crate::configuration::{MonitorTemplate, Settings};
+use crate::incident::{self, Incident, IncidentNote};
+use crate::monitor::{
+    CertificateInspection, CheckTarget, CheckTimings, Criterion, HttpChallenge, MAX_REDIRECTS,
+    MonitorHandle, RedirectHop, TraceHop, validate_jump_host,
+};
+use crate::rollup::{self, DailyRollup};
 use crate::ui::domain_table::{DomainTable, DomainTableState};
 
-use crate::ui::history_table::{HistoryTable, HistoryTableState};
+use crate::ui::check_timeline::CheckTimeline;
+use crate::ui::diff_preview::DiffPreview;
+use crate::ui::form::Form;
+use crate::ui::input::textarea_input;
+use crate::ui::latency::{LatencySettings, validate_thresholds};
 use crate::ui::popup::Popup;
-use crate::utils::is_valid_url;
-use chrono::{DateTime, Utc};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::ui::table_view::{
+    Column, UiState, known_environments, load_ui_state, save_ui_state, ui_state_path,
+};
+use crate::ui::tags::{MAX_SUGGESTIONS, complete, known_tags, parse_tags, suggestions};
+use crate::utils::{is_link, open_link};
+use crate::wildcard::{WildcardUrl, expand_input};
+use chrono::{DateTime, NaiveDate, Utc};
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::prelude::*;
 use ratatui::widgets::Clear;
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
     text::Line,
-    widgets::{Block, Widget},
+    widgets::{Block, Paragraph, Widget},
 };
+use regex::Regex;
 use reqwest::StatusCode;
+use reqwest::header::{HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
-use tui_textarea::{Input, Key};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-static FILE_PATH: &str = "db/domains.json";
+// How long a check may take when its monitor doesn't say.
+pub const DEFAULT_TIMEOUT_SECONDS: u64 = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MonitoredDomain {
+    pub id: Uuid,
+    #[schema(value_type = String)]
+    pub url: Arc<str>,
+    // Shown in the table instead of the URL, e.g. for long health check URLs
+    // with tokens in them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    pub interval_seconds: u64,
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    // Override the [monitor] history retention for this monitor only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_max_checks: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_max_age_days: Option<u64>,
+    // Override the [latency] coloring thresholds for this monitor only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_good_below_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_warn_below_ms: Option<u64>,
+    // When unset any 2xx response is Up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub success_criteria: Option<Criterion>,
+    // The status codes that count as Up instead of any 2xx, e.g. 401 for a
+    // login wall or 301 for a redirector. Redirects with an expected status
+    // aren't followed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    pub expected_status_codes: Vec<StatusRange>,
+    // Where the request has to end up after its redirects, the URL or a
+    // pattern with * for any text, e.g. https://www.example.com/* to catch a
+    // site that stops canonicalizing to https and www.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_redirect: Option<String>,
+    // How many redirects to follow, 0 to judge the redirect itself. Unset
+    // follows MAX_REDIRECTS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_redirects: Option<usize>,
+    // Text the response body has to contain for the check to be Up, even
+    // with a 2xx status. Turns the check into a GET. Same as a body_contains
+    // criterion ANDed onto success_criteria.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyword: Option<String>,
+    // A regex the response body has to match. When the check fails anyway,
+    // its first capture group goes into the error message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_regex: Option<String>,
+    // HEAD, or GET when the body is needed, when unset. Some health
+    // endpoints only answer POST.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_method: Option<HttpMethod>,
+    // Sent with POST and PUT, as JSON when it parses as JSON.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    // Sent with every request of the check, e.g. X-Api-Key or a Host for
+    // a server reached by IP.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    // Basic or bearer credentials for services behind a login, sent as the
+    // Authorization header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<DomainAuth>,
+    // A token the site must serve at a path, checked once the URL is Up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub challenge: Option<HttpChallenge>,
+    // Checked through an SSH tunnel to this host, for services only reachable
+    // from behind a bastion. See monitor/tunnel.rs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_jump_host: Option<String>,
+    // The kubeconfig k8s:// checks use instead of KUBECONFIG or
+    // ~/.kube/config. See monitor/kubernetes.rs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kubeconfig: Option<String>,
+    // The person or team responsible, named in status change notifications
+    // and used to filter the domain table.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    // The environment the monitor belongs to, e.g. prod, staging or dev.
+    // Unlike tags there's one at most, and the domain table's environment
+    // switcher (N) scopes the TUI to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    // Free-form labels to classify monitors by, e.g. prod or payments. Set
+    // with the tag editor (T) on the domain table.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // Free-form key/value pairs like a cost center, runbook or dashboard
+    // link. The history screen lists them and opens the links.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    // The wildcard URL this monitor was expanded from, e.g.
+    // https://*.example.com/health. Edits to one monitor's settings apply to
+    // the whole group. See wildcard.rs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    // Postmortem notes on this domain's incidents, see incident.rs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub incident_notes: Vec<IncidentNote>,
+    // Status change notifications are held back until then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub muted_until: Option<DateTime<Utc>>,
+    // When the last mute started, so the window can go in the calendar.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub muted_from: Option<DateTime<Utc>>,
+    // The last look at an https URL's certificate chain, see
+    // monitor/certificate.rs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<CertificateInspection>,
+    // One summary per UTC day of the checks, kept far longer than the
+    // history. See rollup.rs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub daily_rollups: Vec<DailyRollup>,
+    // The domain's incidents, oldest first, the last one open until an Up
+    // check ends it. Kept for ROLLUP_DAYS after they end.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub incident_log: Vec<Incident>,
+    pub check_history: Vec<CheckStatus>,
+}
+
+impl MonitoredDomain {
+    pub fn new(url: &str) -> Self {
+        MonitoredDomain {
+            id: Uuid::new_v4(),
+            url: url.trim().into(),
+            display_name: None,
+            interval_seconds: 60,
+            timeout_seconds: None,
+            history_max_checks: None,
+            history_max_age_days: None,
+            latency_good_below_ms: None,
+            latency_warn_below_ms: None,
+            success_criteria: None,
+            expected_status_codes: Vec::new(),
+            expected_redirect: None,
+            max_redirects: None,
+            keyword: None,
+            body_regex: None,
+            http_method: None,
+            request_body: None,
+            headers: BTreeMap::new(),
+            auth: None,
+            challenge: None,
+            ssh_jump_host: None,
+            kubeconfig: None,
+            owner: None,
+            environment: None,
+            tags: Vec::new(),
+            metadata: BTreeMap::new(),
+            group: None,
+            incident_notes: Vec::new(),
+            muted_until: None,
+            muted_from: None,
+            certificate: None,
+            daily_rollups: Vec::new(),
+            incident_log: Vec::new(),
+            check_history: Vec::new(),
+        }
+    }
+
+    // How long a check of this domain may take.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECONDS))
+    }
+
+    // A copy with only the last `checks` of the history, for the monitoring
+    // tasks that re-read their domain every round.
+    pub fn with_recent_history(&self, checks: usize) -> Self {
+        let recent = self.check_history.len().saturating_sub(checks);
+        MonitoredDomain {
+            // The tasks only look at the last checks.
+            daily_rollups: Vec::new(),
+            incident_log: Vec::new(),
+            check_history: self.check_history[recent..].to_vec(),
+            ..self.clone()
+        }
+    }
+
+    // Field-by-field differences between two versions of a monitor's settings.
+    pub fn field_changes(&self, other: &MonitoredDomain) -> Vec<FieldChange> {
+        let mut changes = Vec::new();
+        let mut compare = |field: &'static str, old: String, new: String| {
+            if old != new {
+                changes.push(FieldChange { field, old, new });
+            }
+        };
+
+        compare("url", self.url.to_string(), other.url.to_string());
+        compare(
+            "display name",
+            self.display_name.as_deref().unwrap_or("none").to_string(),
+            other.display_name.as_deref().unwrap_or("none").to_string(),
+        );
+        compare(
+            "interval",
+            format!("{}s", self.interval_seconds),
+            format!("{}s", other.interval_seconds),
+        );
+        compare(
+            "timeout",
+            describe_timeout(self.timeout_seconds),
+            describe_timeout(other.timeout_seconds),
+        );
+        compare(
+            "history checks",
+            describe_override(self.history_max_checks, "checks"),
+            describe_override(other.history_max_checks, "checks"),
+        );
+        compare(
+            "history age",
+            describe_override(self.history_max_age_days, "days"),
+            describe_override(other.history_max_age_days, "days"),
+        );
+        compare(
+            "latency good below",
+            describe_override(self.latency_good_below_ms, "ms"),
+            describe_override(other.latency_good_below_ms, "ms"),
+        );
+        compare(
+            "latency warn below",
+            describe_override(self.latency_warn_below_ms, "ms"),
+            describe_override(other.latency_warn_below_ms, "ms"),
+        );
+        compare(
+            "success criteria",
+            describe_criteria(&self.success_criteria),
+            describe_criteria(&other.success_criteria),
+        );
+        compare(
+            "expected status codes",
+            describe_status_codes(&self.expected_status_codes),
+            describe_status_codes(&other.expected_status_codes),
+        );
+        compare(
+            "expected redirect",
+            self.expected_redirect
+                .as_deref()
+                .unwrap_or("none")
+                .to_string(),
+            other
+                .expected_redirect
+                .as_deref()
+                .unwrap_or("none")
+                .to_string(),
+        );
+        compare(
+            "redirects followed",
+            describe_override(self.max_redirects, "hops"),
+            describe_override(other.max_redirects, "hops"),
+        );
+        compare(
+            "keyword",
+            self.keyword.as_deref().unwrap_or("none").to_string(),
+            other.keyword.as_deref().unwrap_or("none").to_string(),
+        );
+        compare(
+            "body regex",
+            self.body_regex.as_deref().unwrap_or("none").to_string(),
+            other.body_regex.as_deref().unwrap_or("none").to_string(),
+        );
+        compare(
+            "method",
+            self.http_method
+                .map_or("automatic", HttpMethod::label)
+                .to_string(),
+            other
+                .http_method
+                .map_or("automatic", HttpMethod::label)
+                .to_string(),
+        );
+        compare(
+            "request body",
+            self.request_body.as_deref().unwrap_or("none").to_string(),
+            other.request_body.as_deref().unwrap_or("none").to_string(),
+        );
+        compare(
+            "headers",
+            describe_headers(&self.headers),
+            describe_headers(&other.headers),
+        );
+        compare(
+            "auth",
+            self.auth
+                .as_ref()
+                .map_or("none".to_string(), DomainAuth::describe),
+            other
+                .auth
+                .as_ref()
+                .map_or("none".to_string(), DomainAuth::describe),
+        );
+        compare(
+            "challenge",
+            describe_challenge(&self.challenge),
+            describe_challenge(&other.challenge),
+        );
+        compare(
+            "SSH jump host",
+            self.ssh_jump_host.as_deref().unwrap_or("none").to_string(),
+            other.ssh_jump_host.as_deref().unwrap_or("none").to_string(),
+        );
+        compare(
+            "kubeconfig",
+            self.kubeconfig.as_deref().unwrap_or("default").to_string(),
+            other.kubeconfig.as_deref().unwrap_or("default").to_string(),
+        );
+        compare(
+            "owner",
+            self.owner.as_deref().unwrap_or("none").to_string(),
+            other.owner.as_deref().unwrap_or("none").to_string(),
+        );
+        compare(
+            "environment",
+            self.environment.as_deref().unwrap_or("none").to_string(),
+            other.environment.as_deref().unwrap_or("none").to_string(),
+        );
+        compare(
+            "tags",
+            describe_tags(&self.tags),
+            describe_tags(&other.tags),
+        );
+        compare(
+            "metadata",
+            describe_metadata(&self.metadata),
+            describe_metadata(&other.metadata),
+        );
+        compare(
+            "group",
+            self.group.as_deref().unwrap_or("none").to_string(),
+            other.group.as_deref().unwrap_or("none").to_string(),
+        );
+
+        changes
+    }
+
+    // The checks every new or edited monitor has to pass, wherever it comes from.
+    pub fn validate(&self) -> Result<(), String> {
+        let target = CheckTarget::parse(&self.url)?;
+        if self.interval_seconds == 0 {
+            return Err("Interval must be a positive number".to_string());
+        }
+        if self.timeout_seconds == Some(0) {
+            return Err("Timeout must be a positive number".to_string());
+        }
+        if self.history_max_checks == Some(0) || self.history_max_age_days == Some(0) {
+            return Err("History retention must be a positive number".to_string());
+        }
+        if let (Some(good), Some(warn)) = (self.latency_good_below_ms, self.latency_warn_below_ms) {
+            validate_thresholds(good, warn)?;
+        }
+        if let Some(criteria) = &self.success_criteria {
+            criteria.validate()?;
+        }
+        if let Some(challenge) = &self.challenge {
+            challenge.validate()?;
+        }
+        if let Some(jump_host) = &self.ssh_jump_host {
+            validate_jump_host(jump_host)?;
+        }
+        for range in &self.expected_status_codes {
+            range.validate()?;
+        }
+        if self.max_redirects.is_some_and(|max| max > REDIRECT_LIMIT) {
+            return Err(format!(
+                "Redirects to follow can be at most {}",
+                REDIRECT_LIMIT
+            ));
+        }
+        if let Some(expected) = &self.expected_redirect
+            && !is_link(expected)
+        {
+            return Err(format!(
+                "Expected redirect {:?} has to be an http(s) URL or pattern",
+                expected
+            ));
+        }
+        if self.keyword.as_deref() == Some("") {
+            return Err("Keyword is empty".to_string());
+        }
+        if let Some(pattern) = &self.body_regex {
+            if pattern.is_empty() {
+                return Err("Body regex is empty".to_string());
+            }
+            Regex::new(pattern).map_err(|e| format!("Invalid body regex: {}", e))?;
+        }
+        if self.request_body.as_deref() == Some("") {
+            return Err("Request body is empty".to_string());
+        }
+        if self.request_body.is_some()
+            && !matches!(self.http_method, Some(HttpMethod::Post | HttpMethod::Put))
+        {
+            return Err("A request body needs the POST or PUT method".to_string());
+        }
+        if self.http_method == Some(HttpMethod::Head) && self.needs_body() {
+            return Err(
+                "HEAD gets no body for the keyword, body regex or success criteria to look at"
+                    .to_string(),
+            );
+        }
+        for (name, value) in &self.headers {
+            HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| format!("Invalid header name {:?}", name))?;
+            HeaderValue::from_str(value)
+                .map_err(|_| format!("Invalid value for header {}", name))?;
+        }
+        for (key, value) in &self.metadata {
+            if key.is_empty() || key.contains(['=', ';']) || value.contains(';') {
+                return Err(format!(
+                    "Invalid metadata {:?}, keys can't be empty or hold = or ; and values can't hold ;",
+                    key
+                ));
+            }
+        }
+        if let Some(auth) = &self.auth {
+            auth.validate()?;
+            if self
+                .headers
+                .keys()
+                .any(|name| name.eq_ignore_ascii_case("authorization"))
+            {
+                return Err("Set either auth or an Authorization header, not both".to_string());
+            }
+        }
+        if !target.is_http()
+            && (self.http_method.is_some()
+                || self.request_body.is_some()
+                || !self.headers.is_empty()
+                || self.auth.is_some())
+        {
+            return Err(
+                "Methods, request bodies, headers and auth only apply to HTTP(S) checks"
+                    .to_string(),
+            );
+        }
+        if !target.is_http()
+            && (self.success_criteria.is_some()
+                || !self.expected_status_codes.is_empty()
+                || self.expected_redirect.is_some()
+                || self.max_redirects.is_some()
+                || self.keyword.is_some()
+                || self.body_regex.is_some()
+                || self.challenge.is_some()
+                || self.ssh_jump_host.is_some())
+        {
+            return Err(
+                "Success criteria, expected status codes and redirects, keywords, body regexes, challenges and SSH jump hosts only apply to HTTP(S) checks"
+                    .to_string(),
+            );
+        }
+        if self.kubeconfig.as_deref() == Some("") {
+            return Err("Kubeconfig path is empty".to_string());
+        }
+        if self.kubeconfig.is_some() && !matches!(target, CheckTarget::Kubernetes { .. }) {
+            return Err("A kubeconfig only applies to k8s:// checks".to_string());
+        }
+        if let Some(owner) = &self.owner
+            && (owner.is_empty() || owner.trim() != owner)
+        {
+            return Err("Owner must not be empty or start or end with spaces".to_string());
+        }
+        if let Some(environment) = &self.environment
+            && (environment.is_empty() || environment.contains(char::is_whitespace))
+        {
+            return Err("Environment must not be empty or contain spaces".to_string());
+        }
+        if let Some(name) = &self.display_name
+            && (name.is_empty() || name.trim() != name)
+        {
+            return Err("Display name must not be empty or start or end with spaces".to_string());
+        }
+        if self
+            .tags
+            .iter()
+            .any(|tag| tag.is_empty() || tag.contains(|c: char| c.is_whitespace() || c == ','))
+        {
+            return Err("Tags must not be empty or contain spaces or commas".to_string());
+        }
+        Ok(())
+    }
+
+    // Whether checks have to GET the body to judge it, rather than HEAD.
+    pub fn needs_body(&self) -> bool {
+        self.keyword.is_some()
+            || self.body_regex.is_some()
+            || self
+                .success_criteria
+                .as_ref()
+                .is_some_and(Criterion::needs_body)
+    }
+
+    // The method checks use: the configured one, or else HEAD unless the
+    // body is needed.
+    pub fn method(&self) -> HttpMethod {
+        match self.http_method {
+            Some(method) => method,
+            None if self.needs_body() => HttpMethod::Get,
+            None => HttpMethod::Head,
+        }
+    }
+
+    // Whether a response with the status counts as Up, as far as the status
+    // goes: one of the expected codes, or any 2xx when there are none.
+    pub fn expects_status(&self, status: u16) -> bool {
+        if self.expected_status_codes.is_empty() {
+            (200..300).contains(&status)
+        } else {
+            self.expected_status_codes
+                .iter()
+                .any(|range| range.contains(status))
+        }
+    }
+
+    pub fn max_redirects(&self) -> usize {
+        self.max_redirects.unwrap_or(MAX_REDIRECTS)
+    }
+
+    // Whether the URL the request ended up at is the expected redirect
+    // target. Without one any is fine.
+    pub fn expects_redirect_to(&self, url: &str) -> bool {
+        self.expected_redirect.as_deref().is_none_or(|expected| {
+            matches_pattern(expected, url) || matches_pattern(expected, url.trim_end_matches('/'))
+        })
+    }
+
+    // The link under the runbook metadata key (in any case), for whoever
+    // responds to it going down.
+    pub fn runbook(&self) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(key, value)| key.eq_ignore_ascii_case("runbook") && is_link(value))
+            .map(|(_, value)| value.as_str())
+    }
+
+    // With auth and header values masked, for API responses and events.
+    // Headers carry API keys as often as not.
+    pub fn redacted(&self) -> MonitoredDomain {
+        MonitoredDomain {
+            auth: self.auth.as_ref().map(DomainAuth::redacted),
+            headers: self
+                .headers
+                .iter()
+                .map(|(name, value)| (name.clone(), mask(value)))
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    // Puts the stored credentials back where an edit sent a masked one, as
+    // an edit of what the API returned does. Err when there's nothing stored
+    // to keep.
+    pub fn unmask(&mut self, stored: Option<&MonitoredDomain>) -> Result<(), String> {
+        let keep = |value: &mut String, stored: Option<&String>| match stored {
+            _ if value != MASKED => Ok(()),
+            Some(stored) => {
+                value.clone_from(stored);
+                Ok(())
+            }
+            None => Err(format!(
+                "{} is a masked value, send the credential itself",
+                MASKED
+            )),
+        };
+        let stored_auth = stored.and_then(|d| d.auth.as_ref());
+        match (&mut self.auth, stored_auth) {
+            (
+                Some(DomainAuth::Basic { password, .. }),
+                Some(DomainAuth::Basic {
+                    password: stored, ..
+                }),
+            ) => keep(password, Some(stored))?,
+            (Some(DomainAuth::Bearer { token }), Some(DomainAuth::Bearer { token: stored })) => {
+                keep(token, Some(stored))?
+            }
+            (Some(DomainAuth::Basic { password, .. }), _) => keep(password, None)?,
+            (Some(DomainAuth::Bearer { token }), _) => keep(token, None)?,
+            (None, _) => {}
+        }
+        for (name, value) in &mut self.headers {
+            keep(value, stored.and_then(|d| d.headers.get(name)))?;
+        }
+        Ok(())
+    }
+
+    // What the domain is listed as: its display name, or else its URL.
+    pub fn name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.url)
+    }
+
+    // Takes over the other monitor's settings, everything but what makes it a
+    // monitor of its own: id, URL, group, history and state.
+    pub fn copy_settings_from(&mut self, other: &MonitoredDomain) {
+        self.interval_seconds = other.interval_seconds;
+        self.timeout_seconds = other.timeout_seconds;
+        self.history_max_checks = other.history_max_checks;
+        self.history_max_age_days = other.history_max_age_days;
+        self.latency_good_below_ms = other.latency_good_below_ms;
+        self.latency_warn_below_ms = other.latency_warn_below_ms;
+        self.success_criteria = other.success_criteria.clone();
+        self.expected_status_codes = other.expected_status_codes.clone();
+        self.expected_redirect = other.expected_redirect.clone();
+        self.max_redirects = other.max_redirects;
+        self.keyword = other.keyword.clone();
+        self.body_regex = other.body_regex.clone();
+        self.http_method = other.http_method;
+        self.request_body = other.request_body.clone();
+        self.headers = other.headers.clone();
+        self.auth = other.auth.clone();
+        self.challenge = other.challenge.clone();
+        self.ssh_jump_host = other.ssh_jump_host.clone();
+        self.kubeconfig = other.kubeconfig.clone();
+        self.owner = other.owner.clone();
+        self.environment = other.environment.clone();
+        self.metadata = other.metadata.clone();
+    }
+
+    pub fn apply_template(&mut self, template: &MonitorTemplate) {
+        if let Some(interval_seconds) = template.interval_seconds {
+            self.interval_seconds = interval_seconds;
+        }
+        if template.timeout_seconds.is_some() {
+            self.timeout_seconds = template.timeout_seconds;
+        }
+        if template.method.is_some() {
+            self.http_method = template.method;
+        }
+        if !template.expected_status.is_empty() {
+            self.expected_status_codes = template.expected_status.clone();
+        }
+        if template.keyword.is_some() {
+            self.keyword = template.keyword.clone();
+        }
+        // Headers set on the monitor itself are kept unless the template
+        // names the same header.
+        self.headers.extend(template.headers.clone());
+    }
+
+    pub fn incident_note(&self, incident_started_at: DateTime<Utc>) -> Option<&IncidentNote> {
+        self.incident_notes
+            .iter()
+            .find(|note| note.incident_started_at == incident_started_at)
+    }
+
+    // Replaces the incident's note, an empty one removes it.
+    pub fn set_incident_note(
+        &mut self,
+        incident_started_at: DateTime<Utc>,
+        note: &str,
+        now: DateTime<Utc>,
+    ) {
+        self.incident_notes
+            .retain(|n| n.incident_started_at != incident_started_at);
+        if !note.trim().is_empty() {
+            self.incident_notes.push(IncidentNote {
+                incident_started_at,
+                note: note.to_string(),
+                updated_at: now,
+            });
+        }
+    }
+
+    // How much longer notifications stay muted, None when they aren't.
+    pub fn mute_remaining(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        self.muted_until
+            .map(|until| until - now)
+            .filter(|remaining| *remaining > chrono::Duration::zero())
+    }
+
+    // Shares repeated error messages across the history, see
+    // CheckStatus::share_strings_with. Histories loaded from disk start out
+    // with a copy per check.
+    pub fn share_history_strings(&mut self) {
+        for i in 1..self.check_history.len() {
+            let (before, after) = self.check_history.split_at_mut(i);
+            after[0].share_strings_with(&before[i - 1]);
+        }
+    }
+
+    // The retention in effect for this monitor, its overrides on top of the
+    // configured defaults.
+    pub fn history_retention(&self, defaults: HistoryRetention) -> HistoryRetention {
+        HistoryRetention {
+            max_checks: self.history_max_checks.unwrap_or(defaults.max_checks),
+            max_age_days: self.history_max_age_days.or(defaults.max_age_days),
+        }
+    }
+
+    // The configured latency display with this monitor's thresholds applied.
+    pub fn latency_settings(&self, defaults: LatencySettings) -> LatencySettings {
+        LatencySettings {
+            good_below_ms: self.latency_good_below_ms.unwrap_or(defaults.good_below_ms),
+            warn_below_ms: self.latency_warn_below_ms.unwrap_or(defaults.warn_below_ms),
+            ..defaults
+        }
+    }
+
+    pub fn overrides_history_retention(&self) -> bool {
+        self.history_max_checks.is_some() || self.history_max_age_days.is_some()
+    }
+
+    // Adds a new check to the history, its day's rollup and the incident log.
+    // Domains saved before there were rollups get them and their log from the
+    // history they have.
+    pub fn push_check(&mut self, check: CheckStatus) {
+        if self.daily_rollups.is_empty() {
+            for earlier in &self.check_history {
+                rollup::roll_up(&mut self.daily_rollups, earlier);
+            }
+            self.incident_log = incident::incidents(&self.check_history);
+        }
+        rollup::roll_up(&mut self.daily_rollups, &check);
+        incident::log_check(&mut self.incident_log, &check);
+        self.check_history.push(check);
+    }
+
+    // The incident log, or the incidents in the history for a domain that
+    // hasn't been checked since there was one.
+    pub fn incidents(&self) -> Vec<Incident> {
+        if self.daily_rollups.is_empty() {
+            incident::incidents(&self.check_history)
+        } else {
+            self.incident_log.clone()
+        }
+    }
+
+    // Drops the oldest checks the retention no longer covers and returns how
+    // many were dropped. Checks are stored oldest first. Rollups are kept for
+    // ROLLUP_DAYS whatever the retention.
+    pub fn compact_history(&mut self, retention: HistoryRetention, now: DateTime<Utc>) -> usize {
+        rollup::compact(&mut self.daily_rollups, now);
+        let oldest = now - chrono::Duration::days(rollup::ROLLUP_DAYS);
+        self.incident_log
+            .retain(|incident| incident.ended_at.is_none_or(|ended_at| ended_at >= oldest));
+        let before = self.check_history.len();
+
+        if let Some(max_age_days) = retention.max_age_days {
+            let cutoff = now - chrono::Duration::days(max_age_days as i64);
+            self.check_history.retain(|check| check.timestamp >= cutoff);
+        }
+        let excess = self
+            .check_history
+            .len()
+            .saturating_sub(retention.max_checks);
+        self.check_history.drain(0..excess);
+
+        before - self.check_history.len()
+    }
+
+    // Share of checks that came back Up. Unknown results (e.g. clock-jump gap
+    // markers) never count; rate limited ones only count when configured to.
+    pub fn uptime_percentage(&self, count_rate_limited_as_down: bool) -> Option<f64> {
+        uptime_of(&self.check_history, count_rate_limited_as_down)
+    }
+
+    // Uptime and average response time of the checks made in [from, to).
+    pub fn period_stats(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        count_rate_limited_as_down: bool,
+    ) -> PeriodStats {
+        let checks: Vec<&CheckStatus> = self
+            .check_history
+            .iter()
+            .filter(|check| check.timestamp >= from && check.timestamp < to)
+            .collect();
+        PeriodStats::of(&checks, count_rate_limited_as_down)
+    }
+
+    // Uptime and average response time of the UTC days in [from, to], from
+    // the daily rollups rather than the history.
+    pub fn daily_stats(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        count_rate_limited_as_down: bool,
+    ) -> PeriodStats {
+        rollup::total(&self.rollups(), from, to).stats(count_rate_limited_as_down)
+    }
+
+    // The daily rollups, or what the history adds up to for a domain that
+    // hasn't been checked since there were rollups.
+    pub fn rollups(&self) -> Cow<'_, [DailyRollup]> {
+        if !self.daily_rollups.is_empty() {
+            return Cow::Borrowed(&self.daily_rollups);
+        }
+        let mut rollups = Vec::new();
+        for check in &self.check_history {
+            rollup::roll_up(&mut rollups, check);
+        }
+        Cow::Owned(rollups)
+    }
+}
+
+// Whether the text matches the pattern, each * in it standing for any run of
+// characters.
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = text.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+// Opens the domain's runbook for the R key of the domain and incident
+// screens, or says why it can't.
+pub fn open_runbook(domain: &MonitoredDomain, event_sender: &mpsc::UnboundedSender<AppEvent>) {
+    let event = match domain.runbook().map(|link| (link, open_link(link))) {
+        Some((_, Ok(()))) => return,
+        Some((link, Err(e))) => AppEvent::Error(format!("Failed to open {}: {}", link, e)),
+        None => AppEvent::NotificationSent(format!(
+            "{} has no runbook link, add one as runbook in its metadata",
+            domain.name()
+        )),
+    };
+    if let Err(e) = event_sender.send(event) {
+        error!("Error sending event: {}", e);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    Head,
+    Get,
+    Post,
+    Put,
+}
+
+impl HttpMethod {
+    pub fn parse(method: &str) -> Option<HttpMethod> {
+        match method.trim().to_ascii_uppercase().as_str() {
+            "HEAD" => Some(HttpMethod::Head),
+            "GET" => Some(HttpMethod::Get),
+            "POST" => Some(HttpMethod::Post),
+            "PUT" => Some(HttpMethod::Put),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+        }
+    }
+}
+
+// The password and token can be "secret:<name>" references to the secrets
+// store, resolved when the check runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DomainAuth {
+    Basic { username: String, password: String },
+    Bearer { token: String },
+}
+
+impl DomainAuth {
+    // The form's "basic <user>:<password>" or "bearer <token>", empty for none.
+    pub fn parse(input: &str) -> Result<Option<DomainAuth>, String> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(None);
+        }
+        let (scheme, credentials) = input.split_once(' ').unwrap_or((input, ""));
+        let credentials = credentials.trim();
+        match scheme.to_ascii_lowercase().as_str() {
+            "basic" => {
+                let (username, password) = credentials
+                    .split_once(':')
+                    .ok_or("Basic auth needs a username and password, e.g. basic ops:hunter2")?;
+                Ok(Some(DomainAuth::Basic {
+                    username: username.to_string(),
+                    password: password.to_string(),
+                }))
+            }
+            "bearer" => Ok(Some(DomainAuth::Bearer {
+                token: credentials.to_string(),
+            })),
+            _ => Err(format!(
+                "Unknown auth {:?}, use basic <user>:<password> or bearer <token>",
+                scheme
+            )),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            DomainAuth::Basic { username, password } => {
+                if username.is_empty() || username.contains(':') {
+                    return Err("Basic auth username must not be empty or contain :".to_string());
+                }
+                if password.is_empty() {
+                    return Err("Basic auth password is empty".to_string());
+                }
+            }
+            DomainAuth::Bearer { token } => {
+                if token.is_empty() || token.contains(char::is_whitespace) {
+                    return Err("Bearer token must not be empty or contain spaces".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // With plaintext passwords and tokens masked, for change previews and API
+    // responses. Secret references are kept, they give nothing away.
+    pub fn redacted(&self) -> DomainAuth {
+        match self {
+            DomainAuth::Basic { username, password } => DomainAuth::Basic {
+                username: username.clone(),
+                password: mask(password),
+            },
+            DomainAuth::Bearer { token } => DomainAuth::Bearer { token: mask(token) },
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        self.redacted().to_string()
+    }
+}
+
+impl fmt::Display for DomainAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DomainAuth::Basic { username, password } => {
+                write!(f, "basic {}:{}", username, password)
+            }
+            DomainAuth::Bearer { token } => write!(f, "bearer {}", token),
+        }
+    }
+}
+
+// What API responses show instead of a plaintext credential.
+pub const MASKED: &str = "****";
+
+// The value masked unless it's a "secret:<name>" reference.
+fn mask(value: &str) -> String {
+    if value.starts_with("secret:") {
+        value.to_string()
+    } else {
+        MASKED.to_string()
+    }
+}
+
+// One of a domain's expected status codes: a single code, or a range like
+// 200-299. Written that way in forms, files and the API.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct StatusRange {
+    pub min: u16,
+    pub max: u16,
+}
+
+impl StatusRange {
+    pub fn contains(&self, status: u16) -> bool {
+        (self.min..=self.max).contains(&status)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(100..=599).contains(&self.min) || !(100..=599).contains(&self.max) {
+            return Err(format!("Status code {} isn't between 100 and 599", self));
+        }
+        if self.min > self.max {
+            return Err(format!("Status range {} is empty", self));
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for StatusRange {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let code = |code: &str| {
+            code.trim()
+                .parse()
+                .map_err(|_| format!("Invalid status code {:?}", value))
+        };
+        match value.split_once('-') {
+            Some((min, max)) => Ok(StatusRange {
+                min: code(min)?,
+                max: code(max)?,
+            }),
+            None => {
+                let code = code(&value)?;
+                Ok(StatusRange {
+                    min: code,
+                    max: code,
+                })
+            }
+        }
+    }
+}
+
+impl From<StatusRange> for String {
+    fn from(range: StatusRange) -> String {
+        range.to_string()
+    }
+}
+
+impl fmt::Display for StatusRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.min == self.max {
+            write!(f, "{}", self.min)
+        } else {
+            write!(f, "{}-{}", self.min, self.max)
+        }
+    }
+}
+
+// Expected status codes the way the edit form shows them, e.g. "200-299, 401".
+pub fn format_status_codes(codes: &[StatusRange]) -> String {
+    codes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// The inverse of format_status_codes, spaces work as separators too.
+pub fn parse_status_codes(input: &str) -> Result<Vec<StatusRange>, String> {
+    input
+        .split([',', ' '])
+        .filter(|code| !code.is_empty())
+        .map(|code| StatusRange::try_from(code.to_string()))
+        .collect()
+}
+
+fn describe_status_codes(codes: &[StatusRange]) -> String {
+    if codes.is_empty() {
+        "any 2xx".to_string()
+    } else {
+        format_status_codes(codes)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeriodStats {
+    pub uptime_percentage: Option<f64>,
+    pub average_response_time_ms: Option<f64>,
+}
+
+impl PeriodStats {
+    pub fn of(checks: &[&CheckStatus], count_rate_limited_as_down: bool) -> Self {
+        let response_times: Vec<u64> = checks.iter().filter_map(|c| c.response_time_ms).collect();
+
+        PeriodStats {
+            uptime_percentage: uptime_of(checks.iter().copied(), count_rate_limited_as_down),
+            average_response_time_ms: (!response_times.is_empty())
+                .then(|| response_times.iter().sum::<u64>() as f64 / response_times.len() as f64),
+        }
+    }
+}
+
+fn uptime_of<'a>(
+    checks: impl IntoIterator<Item = &'a CheckStatus>,
+    count_rate_limited_as_down: bool,
+) -> Option<f64> {
+    let (mut up, mut total) = (0usize, 0usize);
+
+    for check in checks {
+        match check.status {
+            DomainStatus::Up | DomainStatus::Degraded => {
+                up += 1;
+                total += 1;
+            }
+            DomainStatus::Down | DomainStatus::Error(_) => total += 1,
+            DomainStatus::RateLimited if count_rate_limited_as_down => total += 1,
+            DomainStatus::RateLimited | DomainStatus::Unknown => {}
+        }
+    }
+
+    if total == 0 {
+        None
+    } else {
+        Some(up as f64 / total as f64 * 100.0)
+    }
+}
+
+// How much check history a monitor keeps: at most `max_checks` records, and
+// none older than `max_age_days` when that is set.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct HistoryRetention {
+    pub max_checks: usize,
+    pub max_age_days: Option<u64>,
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        HistoryRetention {
+            max_checks: 100,
+            max_age_days: None,
+        }
+    }
+}
+
+impl fmt::Display for HistoryRetention {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "last {} checks", self.max_checks)?;
+        if let Some(max_age_days) = self.max_age_days {
+            write!(f, ", up to {} days", max_age_days)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+pub fn status_label(status: &DomainStatus) -> &'static str {
+    match status {
+        DomainStatus::Up => "UP",
+        DomainStatus::Degraded => "DEGRADED",
+        DomainStatus::Down => "DOWN",
+        DomainStatus::Unknown => "UNKNOWN",
+        DomainStatus::RateLimited => "RATE LIMITED",
+        DomainStatus::Error(_) => "ERROR",
+    }
+}
+
+pub fn describe_timeout(timeout_seconds: Option<u64>) -> String {
+    timeout_seconds
+        .map(|secs| format!("{}s", secs))
+        .unwrap_or_else(|| "default".to_string())
+}
+
+fn describe_criteria(criteria: &Option<Criterion>) -> String {
+    criteria
+        .as_ref()
+        .map(Criterion::to_string)
+        .unwrap_or_else(|| "any 2xx status".to_string())
+}
+
+fn describe_challenge(challenge: &Option<HttpChallenge>) -> String {
+    challenge
+        .as_ref()
+        .map(HttpChallenge::to_string)
+        .unwrap_or_else(|| "none".to_string())
+}
+
+fn describe_tags(tags: &[String]) -> String {
+    if tags.is_empty() {
+        "none".to_string()
+    } else {
+        tags.join(", ")
+    }
+}
+
+fn describe_headers(headers: &BTreeMap<String, String>) -> String {
+    if headers.is_empty() {
+        "none".to_string()
+    } else {
+        format_headers(headers)
+    }
+}
+
+fn describe_metadata(metadata: &BTreeMap<String, String>) -> String {
+    if metadata.is_empty() {
+        "none".to_string()
+    } else {
+        format_metadata(metadata)
+    }
+}
+
+// Metadata the way the edit form shows it, e.g. "cost_center=4711; runbook=https://wiki/pay".
+pub fn format_metadata(metadata: &BTreeMap<String, String>) -> String {
+    metadata
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// The inverse of format_metadata. Values may hold =, but not semicolons.
+pub fn parse_metadata(input: &str) -> Result<BTreeMap<String, String>, String> {
+    input
+        .split(';')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (key, value) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Metadata {:?} needs a value, e.g. team=payments", entry))?;
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+// Headers the way the forms show them, e.g. "Host: api.internal; X-Api-Key: abc".
+pub fn format_headers(headers: &BTreeMap<String, String>) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// The inverse of format_headers. Values may hold colons, but not semicolons.
+pub fn parse_headers(input: &str) -> Result<BTreeMap<String, String>, String> {
+    input
+        .split(';')
+        .map(str::trim)
+        .filter(|header| !header.is_empty())
+        .map(|header| {
+            let (name, value) = header
+                .split_once(':')
+                .ok_or_else(|| format!("Header {:?} needs a value, e.g. X-Api-Key: abc", header))?;
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+// The edit form's live verdict on its URL: the kind of check it makes, or
+// why it makes none.
+fn url_feedback(url: &str) -> Result<String, String> {
+    CheckTarget::parse(url).map(|target| format!("{} check", target.kind()))
+}
+
+// The add form's, which also takes a method before the URL and names after
+// a wildcard URL.
+fn add_url_feedback(input: &str) -> Result<String, String> {
+    let input = match input.split_once(' ') {
+        Some((word, rest)) if HttpMethod::parse(word).is_some() => rest.trim(),
+        _ => input,
+    };
+    let url = input.split_whitespace().next().unwrap_or_default();
+    if WildcardUrl::parse(url).is_some() {
+        return Ok("Wildcard, one HTTP check per name after it".to_string());
+    }
+    url_feedback(url)
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MonitoredDomain {
-    pub id: Uuid,
-    pub url: String,
-    pub interval_seconds: u64,
-    pub check_history: Vec<CheckStatus>,
+fn describe_override<T: fmt::Display>(value: Option<T>, unit: &str) -> String {
+    value
+        .map(|value| format!("{} {}", value, unit))
+        .unwrap_or_else(|| "default".to_string())
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CheckStatus {
     pub timestamp: DateTime<Utc>,
     pub status: DomainStatus,
     pub http_code: Option<HttpCode>,
     pub response_time_ms: Option<u64>,
-    pub error_message: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub error_message: Option<Arc<str>>,
+    // Only set when check signing is enabled, see signing.rs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<CheckSignature>,
+    // Only measured for unusually slow checks, see monitor/timing.rs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<CheckTimings>,
+    // What a check other than HTTP found, e.g. "healthy, 2 restarts" for a
+    // container, shown next to its status.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub detail: Option<Arc<str>>,
+    // Of the echo requests a ping check sent, see monitor/ping.rs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packet_loss_percent: Option<f64>,
+    // The redirects the request went through, see monitor::follow_redirect_chain.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<RedirectHop>,
+    // Of the response body, from Content-Length or counted when it was read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_bytes: Option<u64>,
+    // The route to the host when the check went down, see
+    // monitor/traceroute.rs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub traceroute: Vec<TraceHop>,
+}
+
+impl CheckStatus {
+    // A check with only its time and status, the rest filled in with
+    // `..CheckStatus::new(timestamp, status)` by what each checker measures.
+    pub fn new(timestamp: DateTime<Utc>, status: DomainStatus) -> Self {
+        CheckStatus {
+            timestamp,
+            status,
+            http_code: None,
+            response_time_ms: None,
+            error_message: None,
+            signature: None,
+            timings: None,
+            detail: None,
+            packet_loss_percent: None,
+            redirects: Vec::new(),
+            response_bytes: None,
+            traceroute: Vec::new(),
+        }
+    }
+
+    // A check that got no answer, the error being its status and message.
+    pub fn failed(timestamp: DateTime<Utc>, message: impl Into<Arc<str>>) -> Self {
+        let message = message.into();
+        CheckStatus {
+            error_message: Some(message.clone()),
+            ..CheckStatus::new(timestamp, DomainStatus::Error(message))
+        }
+    }
+
+    // Points repeated error messages at the previous check's copy, so a long
+    // outage keeps one string instead of one per check.
+    pub fn share_strings_with(&mut self, previous: &CheckStatus) {
+        if let (Some(message), Some(previous_message)) =
+            (&mut self.error_message, &previous.error_message)
+            && message == previous_message
+        {
+            *message = Arc::clone(previous_message);
+        }
+        if let (DomainStatus::Error(message), DomainStatus::Error(previous_message)) =
+            (&mut self.status, &previous.status)
+            && message == previous_message
+        {
+            *message = Arc::clone(previous_message);
+        }
+        if let (Some(detail), Some(previous_detail)) = (&mut self.detail, &previous.detail)
+            && detail == previous_detail
+        {
+            *detail = Arc::clone(previous_detail);
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CheckSignature {
+    // Hash of the record before this one, chaining the history together.
+    pub previous_hash: Option<String>,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub enum DomainStatus {
     Up,
+    // Answering, but off in a way that needs a look, e.g. an NTP server
+    // drifting past its threshold. Counts as up for uptime.
+    Degraded,
     Down,
     Unknown,
-    Error(String),
+    RateLimited,
+    #[schema(value_type = String)]
+    Error(Arc<str>),
 }
 
 #[repr(u16)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub enum HttpCode {
     Ok = 200,
     Err = 500,
@@ -75,97 +1433,217 @@ impl HttpCode {
 #[derive(Debug)]
 enum DomainScreenMode {
     DomainTable,
-    AddDomain(Popup<'static>),
-    HistoryTable,
+    // The popup and the index of the template the new domain starts from.
+    AddDomain(Form<'static>, Option<usize>),
+    EditDomain(Form<'static>, Uuid),
+    // The edit form to go back to, the edited domain and what changed.
+    ConfirmEdit(Form<'static>, MonitoredDomain, Vec<FieldChange>),
+    // Picking how long to mute the domain's notifications for.
+    Mute(Uuid),
+    // Naming the current view to save it.
+    SaveView(Popup<'static>),
+    // Picking the columns to show.
+    Columns,
+    // Editing the tags of a domain, with completion from the ones in use.
+    EditTags(Popup<'static>, Uuid),
 }
 
+// The keys of the mute popup and how many hours each mutes for.
+// The most redirects a domain may ask to follow.
+const REDIRECT_LIMIT: usize = 20;
+
+const MUTE_HOURS: [(char, i64); 3] = [('1', 1), ('4', 4), ('d', 24)];
+
+const URL_FIELD: &str = "URL";
+const ADD_URL_FIELD: &str = "URL, optionally after a method like POST";
+const DISPLAY_NAME_FIELD: &str = "Display name (empty to show the URL)";
+const INTERVAL_FIELD: &str = "Interval (seconds)";
+const TIMEOUT_FIELD: &str = "Timeout (seconds, empty for default)";
+const EXPECTED_STATUS_FIELD: &str = "Expected status codes (e.g. 200-299, 401, empty for any 2xx)";
+const EXPECTED_REDIRECT_FIELD: &str =
+    "Expected redirect target (URL, * for any text, empty for any)";
+const MAX_REDIRECTS_FIELD: &str = "Redirects to follow (0 for none, empty for 10)";
+const KEYWORD_FIELD: &str = "Keyword the body must contain (empty for none)";
+const METHOD_FIELD: &str = "Method (HEAD, GET, POST or PUT, empty for automatic)";
+const REQUEST_BODY_FIELD: &str = "Request body (POST/PUT only, empty for none)";
+const HEADERS_FIELD: &str = "Headers (e.g. X-Api-Key: abc; Host: api.internal)";
+const AUTH_FIELD: &str = "Auth (basic user:password or bearer token, empty for none)";
+const CHALLENGE_PATH_FIELD: &str = "Challenge path (e.g. /.well-known/health-token)";
+const CHALLENGE_TOKEN_FIELD: &str = "Challenge token (empty for no challenge)";
+const SSH_JUMP_HOST_FIELD: &str = "SSH jump host (e.g. ops@bastion, empty for direct)";
+const KUBECONFIG_FIELD: &str = "Kubeconfig (k8s:// only, empty for default)";
+const OWNER_FIELD: &str = "Owner (person or team, empty for none)";
+const ENVIRONMENT_FIELD: &str = "Environment (e.g. prod, staging or dev, empty for none)";
+const TAGS_FIELD: &str = "Tags (separated by spaces or commas)";
+const METADATA_FIELD: &str = "Metadata (e.g. runbook=https://wiki/pay; cost_center=4711)";
+
 #[derive(Debug)]
 pub struct DomainScreen {
     pub domain_table_state: DomainTableState,
-    pub history_table_state: HistoryTableState,
-    domains: Arc<Mutex<Vec<MonitoredDomain>>>,
+    // A copy of the monitored domains, kept current by the AppEvents the app
+    // passes to handle_app_event. Changes go through the monitor.
+    domains: Vec<MonitoredDomain>,
+    // The filter, sort and columns of the table and the saved views, kept
+    // across restarts. See table_view.rs.
+    ui_state: UiState,
     mode: DomainScreenMode,
+    settings: Settings,
+    monitor: MonitorHandle,
+    event_sender: mpsc::UnboundedSender<AppEvent>,
 }
 
 impl DomainScreen {
-    pub async fn init() -> Self {
-        let domains = Self::load_domains(FILE_PATH).unwrap_or_default();
-        let domains_arc = Arc::new(Mutex::new(domains));
-
-        let update_domains_callback = {
-            let domains_arc_for_callback = Arc::clone(&domains_arc);
-            Arc::new(
-                move |updated_domain: &MonitoredDomain, check_history: &[CheckStatus]| {
-                    let mut domains_guard = domains_arc_for_callback.lock().unwrap();
-                    if let Some(d) = domains_guard.iter_mut().find(|d| d.id == updated_domain.id) {
-                        d.check_history = check_history.to_vec();
-
-                        if let Err(e) = Self::save_domains(&domains_guard, FILE_PATH) {
-                            error!("Failed to save domains after check: {}", e);
-                            return Err(e); // Propagate the error
-                        }
-                    }
-                    Ok(())
-                },
-            )
-        };
-
-        start_monitoring_task(Arc::clone(&domains_arc), update_domains_callback).await;
-
+    pub fn new(
+        settings: Settings,
+        monitor: &MonitorHandle,
+        event_sender: mpsc::UnboundedSender<AppEvent>,
+    ) -> Self {
+        let ui_state = load_ui_state(&ui_state_path(&settings.profile.store_path()))
+            .unwrap_or_else(|e| {
+                error!("Failed to load the UI state, using the defaults: {}", e);
+                UiState::default()
+            });
         DomainScreen {
             domain_table_state: DomainTableState::default(),
-            history_table_state: HistoryTableState::default(),
             mode: DomainScreenMode::DomainTable,
-            domains: domains_arc,
+            domains: monitor.domains.lock().unwrap().clone(),
+            ui_state,
+            settings,
+            monitor: monitor.clone(),
+            event_sender,
+        }
+    }
+
+    pub fn handle_app_event(&mut self, event: &AppEvent) {
+        match event {
+            AppEvent::CheckCompleted { domain_id, check } => {
+                let retention = self.settings.monitor.history_retention();
+                if let Some(d) = self.domains.iter_mut().find(|d| d.id == *domain_id)
+                    // Already in the copy when the screen opened after the check.
+                    && !d.check_history.iter().any(|c| c.timestamp == check.timestamp)
+                {
+                    d.push_check(check.clone());
+                    d.compact_history(d.history_retention(retention), Utc::now());
+                }
+            }
+            AppEvent::DomainAdded(domain) | AppEvent::DomainUpdated(domain) => {
+                match self.domains.iter_mut().find(|d| d.id == domain.id) {
+                    Some(d) => *d = domain.clone(),
+                    None => self.domains.push(domain.clone()),
+                }
+            }
+            AppEvent::DomainRemoved(domain_id) => {
+                self.domains.retain(|d| d.id != *domain_id);
+                self.clamp_selection();
+            }
+            AppEvent::DomainsReloaded(domains) => {
+                self.domains = domains.clone();
+                self.clamp_selection();
+            }
+            _ => {}
         }
     }
 
-    fn save_domains(domains: &[MonitoredDomain], file_path: &str) -> io::Result<()> {
-        let domain_data = serde_json::to_string_pretty(domains)?;
+    fn clamp_selection(&mut self) {
+        let selected = self.domain_table_state.table_state.selected();
+        let visible = self.visible_domains().len();
+        if visible == 0 {
+            self.domain_table_state.table_state.select(None);
+        } else if selected.is_some_and(|i| i >= visible) {
+            self.domain_table_state
+                .table_state
+                .select(Some(visible - 1));
+        }
+    }
 
-        fs::write(file_path, domain_data)?;
-        Ok(())
+    // The domains listed in the table, in table order.
+    fn visible_domains(&self) -> Vec<&MonitoredDomain> {
+        self.ui_state.current.apply(
+            &self.domains,
+            self.settings.monitor.count_rate_limited_as_down,
+        )
+    }
+
+    fn selected_domain(&self) -> Option<&MonitoredDomain> {
+        let selected = self.domain_table_state.table_state.selected()?;
+        self.visible_domains().get(selected).copied()
+    }
+
+    // Steps through all domains, then each owner's in turn.
+    fn cycle_owner_filter(&mut self) {
+        let mut owners: Vec<&String> = self
+            .domains
+            .iter()
+            .filter_map(|d| d.owner.as_ref())
+            .collect();
+        owners.sort();
+        owners.dedup();
+        let next = match &self.ui_state.current.owner {
+            None => owners.first(),
+            Some(current) => owners.iter().find(|owner| **owner > current),
+        };
+        self.ui_state.current.owner = next.map(|owner| owner.to_string());
+        self.view_changed();
+    }
+
+    // Steps through all environments, then each one in turn.
+    fn cycle_environment(&mut self) {
+        let environments = known_environments(&self.domains);
+        let next = match &self.ui_state.current.environment {
+            None => environments.first(),
+            Some(current) => environments.iter().find(|e| **e > current.as_str()),
+        };
+        self.ui_state.current.environment = next.map(|e| e.to_string());
+        self.view_changed();
     }
 
-    fn load_domains(file_path: &str) -> io::Result<Vec<MonitoredDomain>> {
-        let domain_data = fs::read_to_string(file_path)?;
-        let domains: Vec<MonitoredDomain> = serde_json::from_str(&domain_data)?;
-        Ok(domains)
+    // Saves the view and goes back to the top of the table, whose rows may
+    // be different ones now. The other screens follow the environment.
+    fn view_changed(&mut self) {
+        self.domain_table_state
+            .table_state
+            .select((!self.visible_domains().is_empty()).then_some(0));
+        let path = ui_state_path(&self.settings.profile.store_path());
+        if let Err(e) = save_ui_state(&path, &self.ui_state) {
+            self.report_error(format!("Error saving the UI state: {}", e));
+        }
+        let environment = self.ui_state.current.environment.clone();
+        if let Err(e) = self
+            .event_sender
+            .send(AppEvent::EnvironmentSelected(environment))
+        {
+            error!("Error sending event: {}", e);
+        }
+    }
+
+    fn report_error(&self, message: String) {
+        error!("{}", message);
+        if let Err(e) = self.event_sender.send(AppEvent::Error(message)) {
+            error!("Error sending event: {}", e);
+        }
     }
 
     fn delete_entry(&mut self) {
-        let mut domain_guard = self.domains.lock().unwrap().clone();
-
-        if let Some(selected_index) = self.domain_table_state.table_state.selected() {
-            if selected_index < domain_guard.len() {
-                let entry_id = domain_guard[selected_index].id;
-                domain_guard.retain(|domain| domain.id != entry_id);
-
-                if domain_guard.is_empty() {
-                    self.domain_table_state.table_state.select(None);
-                } else if selected_index >= domain_guard.len() {
-                    self.domain_table_state
-                        .table_state
-                        .select(Some(domain_guard.len() - 1))
-                } else {
-                    self.domain_table_state
-                        .table_state
-                        .select(Some(selected_index));
-                }
+        let Some(entry_id) = self.selected_domain().map(|d| d.id) else {
+            return;
+        };
 
-                if let Err(e) = Self::save_domains(&domain_guard, FILE_PATH) {
-                    eprintln!("Error updating domains after deletion: {}", e);
-                }
-            }
+        match self.monitor.remove_domain(entry_id) {
+            // Don't wait for the event so the selection moves right away.
+            Ok(_) => self.handle_app_event(&AppEvent::DomainRemoved(entry_id)),
+            Err(e) => self.report_error(format!("Error updating domains after deletion: {}", e)),
         }
     }
 
     fn next_row(&mut self) {
-        let domain_guard = self.domains.lock().unwrap().clone();
+        let visible = self.visible_domains().len();
+        if visible == 0 {
+            return;
+        }
 
         let i = match self.domain_table_state.table_state.selected() {
             Some(i) => {
-                if i >= domain_guard.len() - 1 {
+                if i >= visible - 1 {
                     0
                 } else {
                     i + 1
@@ -178,11 +1656,15 @@ impl DomainScreen {
     }
 
     fn previous_row(&mut self) {
-        let domain_guard = self.domains.lock().unwrap().clone();
+        let visible = self.visible_domains().len();
+        if visible == 0 {
+            return;
+        }
+
         let i = match self.domain_table_state.table_state.selected() {
             Some(i) => {
                 if i == 0 {
-                    domain_guard.len() - 1
+                    visible - 1
                 } else {
                     i - 1
                 }
@@ -193,100 +1675,579 @@ impl DomainScreen {
         self.domain_table_state.table_state.select(Some(i));
     }
 
+    // Names the template the add form starts from, when there are any.
+    fn add_title(templates: &[MonitorTemplate], selected: Option<usize>) -> Line<'static> {
+        if templates.is_empty() {
+            return Line::from("Add New Domain");
+        }
+        let name = selected
+            .and_then(|i| templates.get(i))
+            .map_or("None", |template| template.name.as_str());
+        Line::from(format!(
+            "Add New Domain - Template: {} (Up/Down to change)",
+            name
+        ))
+    }
+
+    fn edit_form(domain: &MonitoredDomain) -> Form<'static> {
+        let timeout = domain
+            .timeout_seconds
+            .map(|secs| secs.to_string())
+            .unwrap_or_default();
+        let (challenge_path, challenge_token) = domain
+            .challenge
+            .as_ref()
+            .map(|c| (c.path.as_str(), c.token.as_str()))
+            .unwrap_or_default();
+
+        Form::new(Line::from("Edit Domain"))
+            .with_field(URL_FIELD, &domain.url)
+            .with_validator(URL_FIELD, url_feedback)
+            .with_field(
+                DISPLAY_NAME_FIELD,
+                domain.display_name.as_deref().unwrap_or_default(),
+            )
+            .with_field(INTERVAL_FIELD, &domain.interval_seconds.to_string())
+            .with_field(TIMEOUT_FIELD, &timeout)
+            .with_field(
+                EXPECTED_STATUS_FIELD,
+                &format_status_codes(&domain.expected_status_codes),
+            )
+            .with_field(
+                EXPECTED_REDIRECT_FIELD,
+                domain.expected_redirect.as_deref().unwrap_or_default(),
+            )
+            .with_field(
+                MAX_REDIRECTS_FIELD,
+                &domain
+                    .max_redirects
+                    .map(|max| max.to_string())
+                    .unwrap_or_default(),
+            )
+            .with_field(KEYWORD_FIELD, domain.keyword.as_deref().unwrap_or_default())
+            .with_field(
+                METHOD_FIELD,
+                domain.http_method.map_or("", HttpMethod::label),
+            )
+            .with_field(
+                REQUEST_BODY_FIELD,
+                domain.request_body.as_deref().unwrap_or_default(),
+            )
+            .with_field(HEADERS_FIELD, &format_headers(&domain.headers))
+            .with_field(
+                AUTH_FIELD,
+                &domain
+                    .auth
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            )
+            .with_field(CHALLENGE_PATH_FIELD, challenge_path)
+            .with_field(CHALLENGE_TOKEN_FIELD, challenge_token)
+            .with_field(
+                SSH_JUMP_HOST_FIELD,
+                domain.ssh_jump_host.as_deref().unwrap_or_default(),
+            )
+            .with_field(
+                KUBECONFIG_FIELD,
+                domain.kubeconfig.as_deref().unwrap_or_default(),
+            )
+            .with_field(OWNER_FIELD, domain.owner.as_deref().unwrap_or_default())
+            .with_field(
+                ENVIRONMENT_FIELD,
+                domain.environment.as_deref().unwrap_or_default(),
+            )
+            .with_field(TAGS_FIELD, &domain.tags.join(" "))
+            .with_field(METADATA_FIELD, &format_metadata(&domain.metadata))
+    }
+
+    // The tag editor for the domain with the given input, listing what Tab
+    // would complete it with.
+    fn tag_popup(domain: &MonitoredDomain, input: &str, known: &[String]) -> Popup<'static> {
+        let mut popup = Popup::new(
+            Line::from(format!("Tags of {}", domain.name())),
+            Some(input.to_string()),
+        );
+        popup.set_input_title("Tags (separated by spaces)");
+        popup.set_footer(Self::tag_footer(input, known));
+        popup
+    }
+
+    // What Tab would complete the input with.
+    fn tag_footer(input: &str, known: &[String]) -> Line<'static> {
+        let matches = suggestions(input, known);
+        if matches.is_empty() {
+            return Line::from(" Enter: Save | Esc: Cancel ");
+        }
+        let shown: Vec<&str> = matches.into_iter().take(MAX_SUGGESTIONS).collect();
+        Line::from(format!(
+            " Tab: {} | Enter: Save | Esc: Cancel ",
+            shown.join(", ")
+        ))
+    }
+
+    fn edited_domain(original: &MonitoredDomain, form: &Form) -> Result<MonitoredDomain, String> {
+        let parse_seconds = |label: &str| {
+            form.value(label)
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .ok_or_else(|| format!("{} must be a positive number", label))
+        };
+        let interval_seconds = parse_seconds(INTERVAL_FIELD)?;
+        let timeout_seconds = if form.value(TIMEOUT_FIELD).is_empty() {
+            None
+        } else {
+            Some(parse_seconds(TIMEOUT_FIELD)?)
+        };
+
+        // Clearing the token turns the challenge off, the path may stay.
+        let challenge_token = form.value(CHALLENGE_TOKEN_FIELD);
+        let challenge = if challenge_token.is_empty() {
+            None
+        } else {
+            Some(HttpChallenge {
+                path: form.value(CHALLENGE_PATH_FIELD),
+                token: challenge_token,
+            })
+        };
+
+        let method = form.value(METHOD_FIELD);
+        let http_method = if method.trim().is_empty() {
+            None
+        } else {
+            Some(HttpMethod::parse(&method).ok_or_else(|| format!("Unknown method {}", method))?)
+        };
+
+        // Spaces may be part of the keyword and body, so they're taken as typed.
+        let keyword = form.value(KEYWORD_FIELD);
+        let request_body = form.value(REQUEST_BODY_FIELD);
+        let headers = parse_headers(&form.value(HEADERS_FIELD))?;
+        let auth = DomainAuth::parse(&form.value(AUTH_FIELD))?;
+        let jump_host = form.value(SSH_JUMP_HOST_FIELD);
+        let kubeconfig = form.value(KUBECONFIG_FIELD);
+        let owner = form.value(OWNER_FIELD).trim().to_string();
+        let environment = form.value(ENVIRONMENT_FIELD).trim().to_string();
+        let display_name = form.value(DISPLAY_NAME_FIELD).trim().to_string();
+        let expected_redirect = form.value(EXPECTED_REDIRECT_FIELD).trim().to_string();
+        let max_redirects = match form.value(MAX_REDIRECTS_FIELD).trim() {
+            "" => None,
+            max => Some(
+                max.parse::<usize>()
+                    .map_err(|_| format!("{} must be a number", MAX_REDIRECTS_FIELD))?,
+            ),
+        };
+        let edited = MonitoredDomain {
+            url: form.value(URL_FIELD).into(),
+            display_name: (!display_name.is_empty()).then_some(display_name),
+            interval_seconds,
+            timeout_seconds,
+            expected_status_codes: parse_status_codes(&form.value(EXPECTED_STATUS_FIELD))?,
+            expected_redirect: (!expected_redirect.is_empty()).then_some(expected_redirect),
+            max_redirects,
+            keyword: (!keyword.is_empty()).then_some(keyword),
+            http_method,
+            request_body: (!request_body.is_empty()).then_some(request_body),
+            headers,
+            auth,
+            challenge,
+            ssh_jump_host: (!jump_host.is_empty()).then_some(jump_host),
+            kubeconfig: (!kubeconfig.is_empty()).then_some(kubeconfig),
+            owner: (!owner.is_empty()).then_some(owner),
+            environment: (!environment.is_empty()).then_some(environment),
+            tags: parse_tags(&form.value(TAGS_FIELD)),
+            metadata: parse_metadata(&form.value(METADATA_FIELD))?,
+            ..original.clone()
+        };
+        edited.validate()?;
+        Ok(edited)
+    }
+
+    fn save_edit(&mut self, edited: &MonitoredDomain) {
+        if let Err(e) = self
+            .monitor
+            .update_domain(edited)
+            .and_then(|_| self.monitor.update_group(edited))
+        {
+            self.report_error(format!("Error saving domains after edit: {}", e));
+        }
+    }
+
+    fn save_tags(&mut self, domain_id: Uuid, tags: Vec<String>) {
+        if let Err(e) = self.monitor.set_tags(domain_id, tags) {
+            self.report_error(format!("Error saving domains after tagging: {}", e));
+        }
+    }
+
+    fn mute(&mut self, domain_id: Uuid, until: Option<DateTime<Utc>>) {
+        if let Err(e) = self.monitor.mute_domain(domain_id, until) {
+            self.report_error(format!("Error saving domains after muting: {}", e));
+        }
+    }
+
     pub fn handle_key_event(&mut self, key_event: KeyEvent) -> bool {
         match &mut self.mode {
-            DomainScreenMode::AddDomain(popup) => match key_event.code {
+            DomainScreenMode::Mute(domain_id) => {
+                let domain_id = *domain_id;
+                match key_event.code {
+                    KeyCode::Esc => self.mode = DomainScreenMode::DomainTable,
+                    KeyCode::Char('u') | KeyCode::Char('U') => {
+                        self.mute(domain_id, None);
+                        self.mode = DomainScreenMode::DomainTable;
+                    }
+                    KeyCode::Char(key) => {
+                        if let Some((_, hours)) = MUTE_HOURS
+                            .iter()
+                            .find(|(k, _)| *k == key.to_ascii_lowercase())
+                        {
+                            self.mute(
+                                domain_id,
+                                Some(Utc::now() + chrono::Duration::hours(*hours)),
+                            );
+                            self.mode = DomainScreenMode::DomainTable;
+                        }
+                    }
+                    _ => {}
+                }
+                // Keep keys from leaking to the table while the popup is open.
+                true
+            }
+            DomainScreenMode::EditDomain(form, domain_id) => match key_event.code {
                 KeyCode::Esc => {
                     self.mode = DomainScreenMode::DomainTable;
                     true
                 }
+                KeyCode::Tab | KeyCode::Down => {
+                    form.focus_next();
+                    true
+                }
+                KeyCode::BackTab | KeyCode::Up => {
+                    form.focus_previous();
+                    true
+                }
                 KeyCode::Enter => {
-                    let input_url = popup.get_input_text().join("\n");
-
-                    if !is_valid_url(&input_url) {
-                        popup
-                            .set_title(Line::from("Invalid URL! (e.g., http://example.com)".red()));
+                    let original = self.domains.iter().find(|d| d.id == *domain_id).cloned();
+                    let Some(original) = original else {
+                        // Deleted while the form was open.
+                        self.mode = DomainScreenMode::DomainTable;
                         return true;
+                    };
+
+                    match Self::edited_domain(&original, form) {
+                        Err(e) => form.set_error(e),
+                        Ok(edited) => {
+                            let changes = original.field_changes(&edited);
+                            self.mode = if changes.is_empty() {
+                                DomainScreenMode::DomainTable
+                            } else {
+                                DomainScreenMode::ConfirmEdit(form.clone(), edited, changes)
+                            };
+                        }
                     }
+                    true
+                }
+                _ => form.input(key_event),
+            },
+            DomainScreenMode::ConfirmEdit(form, edited, _) => match key_event.code {
+                KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    let edited = edited.clone();
+                    self.save_edit(&edited);
+                    self.mode = DomainScreenMode::DomainTable;
+                    true
+                }
+                KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
+                    self.mode = DomainScreenMode::EditDomain(form.clone(), edited.id);
+                    true
+                }
+                // Keep keys from leaking to the table while the preview is open.
+                _ => true,
+            },
+            DomainScreenMode::AddDomain(form, template) => match key_event.code {
+                KeyCode::Esc => {
+                    self.mode = DomainScreenMode::DomainTable;
+                    true
+                }
+                KeyCode::Tab => {
+                    form.focus_next();
+                    true
+                }
+                KeyCode::BackTab => {
+                    form.focus_previous();
+                    true
+                }
+                KeyCode::Enter => {
+                    let input = form.value(ADD_URL_FIELD);
+                    // A leading method word, like POST https://..., picks
+                    // the method the checks use.
+                    let (http_method, input) = match input.split_once(' ') {
+                        Some((word, rest)) if HttpMethod::parse(word).is_some() => {
+                            (HttpMethod::parse(word), rest.trim().to_string())
+                        }
+                        _ => (None, input),
+                    };
+                    let headers = match parse_headers(&form.value(HEADERS_FIELD)) {
+                        Ok(headers) => headers,
+                        Err(e) => {
+                            form.set_error(e);
+                            return true;
+                        }
+                    };
+                    let mut words = input.split_whitespace();
+                    let input_url = words.next().unwrap_or_default();
 
-                    let new_domain = MonitoredDomain {
-                        id: Uuid::new_v4(),
-                        url: input_url.trim().to_string(),
-                        interval_seconds: 60,
-                        check_history: Vec::new(),
+                    // A wildcard URL is followed by the names to fill in, and
+                    // adds one monitor per name as a group.
+                    let (urls, group) = match WildcardUrl::parse(input_url) {
+                        Some(wildcard) => {
+                            match expand_input(&wildcard, &words.collect::<Vec<_>>()) {
+                                Ok(urls) => (urls, Some(wildcard.pattern())),
+                                Err(e) => {
+                                    form.set_error(e);
+                                    return true;
+                                }
+                            }
+                        }
+                        None => (vec![input.clone()], None),
                     };
 
-                    {
-                        let mut domain_guard = self.domains.lock().unwrap();
-                        domain_guard.push(new_domain);
-                        if let Err(e) = Self::save_domains(&domain_guard, "db/domains.json") {
-                            eprintln!("Error saving domains: {}", e);
+                    let new_domains: Vec<MonitoredDomain> = urls
+                        .iter()
+                        .map(|url| {
+                            let mut new_domain = MonitoredDomain::new(url);
+                            if let Some(template) =
+                                template.and_then(|i| self.settings.templates.get(i))
+                            {
+                                new_domain.apply_template(template);
+                            }
+                            new_domain.group = group.clone();
+                            if http_method.is_some() {
+                                new_domain.http_method = http_method;
+                            }
+                            new_domain.headers.extend(headers.clone());
+                            new_domain
+                        })
+                        .collect();
+                    if let Some(e) = new_domains.iter().find_map(|d| d.validate().err()) {
+                        form.set_error(e);
+                        return true;
+                    }
+
+                    for new_domain in new_domains {
+                        if let Err(e) = self.monitor.add_domain(new_domain) {
+                            self.report_error(format!("Error saving domains: {}", e));
+                            break;
                         }
                     }
 
                     self.mode = DomainScreenMode::DomainTable;
                     true
                 }
-                _ => {
-                    let tui_input = match key_event.code {
-                        KeyCode::Char(c) => Input {
-                            key: Key::Char(c),
-                            ctrl: key_event.modifiers.contains(KeyModifiers::CONTROL),
-                            alt: key_event.modifiers.contains(KeyModifiers::ALT),
-                            shift: key_event.modifiers.contains(KeyModifiers::SHIFT),
-                        },
-                        KeyCode::Backspace => Input {
-                            key: Key::Backspace,
-                            ctrl: false,
-                            alt: false,
-                            shift: false,
-                        },
-                        KeyCode::Delete => Input {
-                            key: Key::Delete,
-                            ctrl: false,
-                            alt: false,
-                            shift: false,
-                        },
-                        KeyCode::Left => Input {
-                            key: Key::Left,
-                            ctrl: false,
-                            alt: false,
-                            shift: false,
-                        },
-                        KeyCode::Right => Input {
-                            key: Key::Right,
-                            ctrl: false,
-                            alt: false,
-                            shift: false,
-                        },
-                        KeyCode::Tab => Input {
-                            key: Key::Tab,
-                            ctrl: false,
-                            alt: false,
-                            shift: false,
-                        },
-
-                        _ => return false,
+                KeyCode::Up | KeyCode::Down if !self.settings.templates.is_empty() => {
+                    let template_count = self.settings.templates.len();
+                    // Cycle through "no template" followed by each configured template.
+                    *template = match (*template, key_event.code) {
+                        (None, KeyCode::Down) => Some(0),
+                        (None, _) => Some(template_count - 1),
+                        (Some(i), KeyCode::Down) if i + 1 < template_count => Some(i + 1),
+                        (Some(i), KeyCode::Up) if i > 0 => Some(i - 1),
+                        (Some(_), _) => None,
                     };
-                    popup.textarea_mut().input(tui_input);
+                    form.set_title(Self::add_title(&self.settings.templates, *template));
                     true
                 }
+                KeyCode::Up => {
+                    form.focus_previous();
+                    true
+                }
+                KeyCode::Down => {
+                    form.focus_next();
+                    true
+                }
+                _ => form.input(key_event),
+            },
+            DomainScreenMode::SaveView(popup) => match key_event.code {
+                KeyCode::Esc => {
+                    self.mode = DomainScreenMode::DomainTable;
+                    true
+                }
+                KeyCode::Enter => {
+                    let name = popup.get_input_text().join(" ");
+                    if let Err(e) = self.ui_state.save_view(&name) {
+                        popup.set_title(Line::from(e.red()));
+                        return true;
+                    }
+                    self.mode = DomainScreenMode::DomainTable;
+                    self.view_changed();
+                    true
+                }
+                _ => match textarea_input(key_event) {
+                    Some(tui_input) => {
+                        popup.textarea_mut().input(tui_input);
+                        true
+                    }
+                    None => false,
+                },
             },
+            DomainScreenMode::EditTags(popup, domain_id) => {
+                let domain_id = *domain_id;
+                let Some(domain) = self.domains.iter().find(|d| d.id == domain_id) else {
+                    // Deleted while the editor was open.
+                    self.mode = DomainScreenMode::DomainTable;
+                    return true;
+                };
+                let input = popup.get_input_text().join(" ");
+                match key_event.code {
+                    KeyCode::Esc => self.mode = DomainScreenMode::DomainTable,
+                    KeyCode::Enter => {
+                        self.save_tags(domain_id, parse_tags(&input));
+                        self.mode = DomainScreenMode::DomainTable;
+                    }
+                    KeyCode::Tab => {
+                        let known = known_tags(&self.domains);
+                        if let Some(completed) = complete(&input, &known) {
+                            *popup = Self::tag_popup(domain, &completed, &known);
+                        }
+                    }
+                    _ => {
+                        let Some(tui_input) = textarea_input(key_event) else {
+                            return false;
+                        };
+                        // The textarea keeps its cursor, only the
+                        // suggestions change.
+                        popup.textarea_mut().input(tui_input);
+                        let input = popup.get_input_text().join(" ");
+                        popup.set_footer(Self::tag_footer(&input, &known_tags(&self.domains)));
+                    }
+                }
+                true
+            }
+            DomainScreenMode::Columns => {
+                match key_event.code {
+                    KeyCode::Esc | KeyCode::Enter => self.mode = DomainScreenMode::DomainTable,
+                    KeyCode::Char(key) => {
+                        let column = key
+                            .to_digit(10)
+                            .and_then(|n| Column::ALL.get((n as usize).checked_sub(1)?));
+                        if let Some(column) = column {
+                            self.ui_state.current.toggle_column(*column);
+                            self.view_changed();
+                        }
+                    }
+                    _ => {}
+                }
+                // Keep keys from leaking to the table while the popup is open.
+                true
+            }
             DomainScreenMode::DomainTable => {
                 match key_event.code {
                     KeyCode::Char('A') | KeyCode::Char('a') => {
-                        self.mode = DomainScreenMode::AddDomain(Popup::new(
-                            Line::from("Add New Domain"),
-                            Some("https://".to_string()),
-                        ));
+                        let form = Form::new(Self::add_title(&self.settings.templates, None))
+                            .with_field(ADD_URL_FIELD, "https://")
+                            .with_validator(ADD_URL_FIELD, add_url_feedback)
+                            .with_field(HEADERS_FIELD, "");
+                        self.mode = DomainScreenMode::AddDomain(form, None);
                         true
                     }
                     KeyCode::Char('D') | KeyCode::Char('d') => {
                         self.delete_entry();
                         true
                     }
+                    KeyCode::Char('E') | KeyCode::Char('e') => {
+                        if let Some(domain) = self.selected_domain().cloned() {
+                            self.mode =
+                                DomainScreenMode::EditDomain(Self::edit_form(&domain), domain.id);
+                        }
+                        true
+                    }
                     KeyCode::Char('H') | KeyCode::Char('h') => {
-                        self.mode = DomainScreenMode::HistoryTable;
+                        if let Some(domain) = self.selected_domain()
+                            && let Err(e) = self
+                                .event_sender
+                                .send(AppEvent::SwitchToHistoryScreen(domain.clone()))
+                        {
+                            error!("Error sending event: {}", e);
+                        }
+                        true
+                    }
+                    KeyCode::Char('I') | KeyCode::Char('i') => {
+                        if let Some(domain) = self.selected_domain()
+                            && let Err(e) = self
+                                .event_sender
+                                .send(AppEvent::SwitchToIncidentsScreen(domain.clone()))
+                        {
+                            error!("Error sending event: {}", e);
+                        }
+                        true
+                    }
+                    KeyCode::Char('M') | KeyCode::Char('m') => {
+                        if let Some(domain) = self.selected_domain() {
+                            self.mode = DomainScreenMode::Mute(domain.id);
+                        }
+                        true
+                    }
+                    KeyCode::Char('O') | KeyCode::Char('o') => {
+                        self.cycle_owner_filter();
+                        true
+                    }
+                    KeyCode::Char('N') | KeyCode::Char('n') => {
+                        self.cycle_environment();
+                        true
+                    }
+                    KeyCode::Char('T') | KeyCode::Char('t') => {
+                        if let Some(domain) = self.selected_domain() {
+                            let input = match domain.tags.is_empty() {
+                                true => String::new(),
+                                false => format!("{} ", domain.tags.join(" ")),
+                            };
+                            let popup = Self::tag_popup(domain, &input, &known_tags(&self.domains));
+                            self.mode = DomainScreenMode::EditTags(popup, domain.id);
+                        }
+                        true
+                    }
+                    KeyCode::Char('B') | KeyCode::Char('b') => {
+                        self.ui_state.current.next_sort();
+                        self.view_changed();
+                        true
+                    }
+                    KeyCode::Char('C') | KeyCode::Char('c') => {
+                        self.mode = DomainScreenMode::Columns;
+                        true
+                    }
+                    KeyCode::Char('V') | KeyCode::Char('v') => {
+                        let mut popup = Popup::new(Line::from("Save View As"), None);
+                        popup.set_input_title("View name");
+                        self.mode = DomainScreenMode::SaveView(popup);
+                        true
+                    }
+                    // 0 shows every domain again, 1 to 9 the saved views.
+                    KeyCode::Char(key @ '0'..='9') => {
+                        let switched = match key.to_digit(10) {
+                            Some(0) => {
+                                self.ui_state.current = Default::default();
+                                true
+                            }
+                            Some(n) => self.ui_state.switch_to(n as usize).is_some(),
+                            None => false,
+                        };
+                        if switched {
+                            self.view_changed();
+                        }
+                        true
+                    }
+                    KeyCode::Char('R') | KeyCode::Char('r') => {
+                        if let Some(domain) = self.selected_domain() {
+                            open_runbook(domain, &self.event_sender);
+                        }
+                        true
+                    }
+                    KeyCode::Char('S') | KeyCode::Char('s') => {
+                        if let Some(domain) = self.selected_domain()
+                            && let Err(e) = self
+                                .event_sender
+                                .send(AppEvent::StartSoakTest(domain.clone()))
+                        {
+                            error!("Error sending event: {}", e);
+                        }
                         true
                     }
 
@@ -303,36 +2264,6 @@ impl DomainScreen {
                     _ => false,            // Event not consumed by DomainScreen (in Table mode)
                 }
             }
-            DomainScreenMode::HistoryTable => {
-                if let Some(selected_domain) = self.domain_table_state.table_state.selected() {
-                    let domains_guard = self.domains.lock().unwrap().clone();
-                    let domain_history = domains_guard[selected_domain].check_history.clone();
-                    match key_event.code {
-                        KeyCode::Esc => {
-                            self.mode = DomainScreenMode::DomainTable;
-                            true
-                        }
-                        KeyCode::Up | KeyCode::Char('k') => {
-                            HistoryTable::previous_row(
-                                &mut self.history_table_state,
-                                domain_history.len(),
-                            );
-                            true
-                        }
-                        KeyCode::Down | KeyCode::Char('j') => {
-                            HistoryTable::next_row(
-                                &mut self.history_table_state,
-                                domain_history.len(),
-                            );
-                            true
-                        }
-
-                        _ => false,
-                    }
-                } else {
-                    false
-                }
-            }
         }
     }
 }
@@ -342,44 +2273,334 @@ impl Widget for &mut DomainScreen {
         let instructions = Line::from(vec![
             " Esc: Return to Menu - ".into(),
             "A: Add - ".into(),
+            "E: Edit - ".into(),
             "H: History - ".into(),
+            "I: Incidents - ".into(),
+            "M: Mute - ".into(),
+            "O: Owner Filter - ".into(),
+            "N: Environment - ".into(),
+            "T: Tags - ".into(),
+            "B: Sort - ".into(),
+            "C: Columns - ".into(),
+            "V: Save View - ".into(),
+            "0-9: Views - ".into(),
+            "S: Soak Test - ".into(),
             "D: Delete - ".into(),
-            "R: Refresh - ".into(),
+            "R: Runbook - ".into(),
             "Q: Quit - ".into(),
             "Up/Down: Navigation ".into(),
         ]);
-        let header = Line::from("URL Monitoring").left_aligned();
+        let current = &self.ui_state.current;
+        let mut header = format!("URL Monitoring ({} profile", self.settings.profile.name());
+        if let Some(view) = self.ui_state.views.iter().find(|v| v.view == *current) {
+            header.push_str(&format!(", view: {}", view.name));
+        }
+        if let Some(owner) = &current.owner {
+            header.push_str(&format!(", owner: {}", owner));
+        }
+        if let Some(sort) = current.sort {
+            header.push_str(&format!(", sorted by {}", sort.column.label()));
+        }
+        header.push(')');
+        let header = Line::from(header).left_aligned();
+
+        // The environment switcher, the pick highlighted.
+        let environments = known_environments(&self.domains);
+        let selected = current.environment.as_deref();
+        let mut switcher = vec![Span::raw(" Env: ")];
+        for (i, environment) in std::iter::once(None)
+            .chain(environments.iter().copied().map(Some))
+            .enumerate()
+        {
+            if i > 0 {
+                switcher.push(Span::raw(" | "));
+            }
+            let label = Span::raw(environment.unwrap_or("all"));
+            switcher.push(if environment == selected {
+                label.reversed().bold()
+            } else {
+                label
+            });
+        }
+        switcher.push(Span::raw(" "));
 
-        let main_block = Block::bordered()
+        let mut main_block = Block::bordered()
             .title_top(header)
             .title_bottom(instructions.centered())
             .magenta();
+        if !environments.is_empty() {
+            main_block = main_block.title_top(Line::from(switcher).right_aligned());
+        }
 
-        let inner_area = main_block.inner(area);
+        let mut inner_area = main_block.inner(area);
 
-        let domains_guard = self.domains.lock().unwrap().clone();
-        let domain_table_widget = DomainTable::new(&domains_guard);
+        // Not visible_domains, which would borrow all of self.
+        let visible_domains = self.ui_state.current.apply(
+            &self.domains,
+            self.settings.monitor.count_rate_limited_as_down,
+        );
+        let domain_table_widget =
+            DomainTable::new(visible_domains, &self.settings).with_view(&self.ui_state.current);
 
         main_block.render(area, buf);
 
+        let queued_checks = self.monitor.budget.queued_checks();
+        if queued_checks > 0 {
+            let [banner_area, table_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner_area);
+            Line::from(format!(
+                " Request budget reached: {} check(s) queued ",
+                queued_checks
+            ))
+            .black()
+            .on_yellow()
+            .bold()
+            .centered()
+            .render(banner_area, buf);
+            inner_area = table_area;
+        }
+
+        // Intervals that schedule more checks than the budget, before any
+        // get queued. The first is shown, the others counted.
+        let monitors = self
+            .domains
+            .iter()
+            .map(|domain| (&*domain.url, domain.interval_seconds));
+        let warnings = scheduled_load(monitors, &self.settings.budget);
+        if let Some(warning) = warnings.first() {
+            let [banner_area, table_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner_area);
+            let more = match warnings.len() {
+                1 => String::new(),
+                n => format!(" (+{} more)", n - 1),
+            };
+            Line::from(format!(" {}{} ", warning, more))
+                .black()
+                .on_yellow()
+                .centered()
+                .render(banner_area, buf);
+            inner_area = table_area;
+        }
+
+        // Leave the table the whole screen when it is too small for both.
+        if inner_area.height >= CheckTimeline::height() + 6
+            && !self.settings.performance.low_resource
+        {
+            let [table_area, timeline_area] = Layout::vertical([
+                Constraint::Min(0),
+                Constraint::Length(CheckTimeline::height()),
+            ])
+            .areas(inner_area);
+            CheckTimeline::new(self.monitor.snapshot().upcoming_checks(), Utc::now())
+                .render(timeline_area, buf);
+            inner_area = table_area;
+        }
+
         domain_table_widget.render(inner_area, buf, &mut self.domain_table_state);
-        drop(domains_guard);
 
-        if let DomainScreenMode::AddDomain(popup) = &self.mode {
+        if let DomainScreenMode::SaveView(popup) | DomainScreenMode::EditTags(popup, _) = &self.mode
+        {
             let popup_area = Popup::centered_rect(60, 20, area);
             Clear.render(popup_area, buf);
             popup.clone().render(popup_area, buf);
         }
 
-        if let DomainScreenMode::HistoryTable = &self.mode {
-            Clear.render(area, buf);
+        match &self.mode {
+            DomainScreenMode::AddDomain(form, _) | DomainScreenMode::EditDomain(form, _) => {
+                form.render(
+                    Popup::centered_rect_with_height(60, form.height(), area),
+                    buf,
+                );
+            }
+            DomainScreenMode::ConfirmEdit(form, edited, changes) => {
+                form.render(
+                    Popup::centered_rect_with_height(60, form.height(), area),
+                    buf,
+                );
+
+                let title = match &edited.group {
+                    Some(group) => format!(" Save changes to every monitor in {}? ", group),
+                    None => format!(" Save changes to {}? ", edited.url),
+                };
+                let preview = DiffPreview::new(&title, changes);
+                let preview_area = Popup::centered_rect_with_height(50, preview.height(), area);
+                preview.render(preview_area, buf);
+            }
+            DomainScreenMode::Mute(domain_id) => {
+                let url = self
+                    .domains
+                    .iter()
+                    .find(|d| d.id == *domain_id)
+                    .map_or("", |d| &*d.url);
+                let mut lines: Vec<Line> = MUTE_HOURS
+                    .iter()
+                    .map(|(key, hours)| Line::from(format!(" {}: Mute for {}h", key, hours)))
+                    .collect();
+                lines.push(Line::from(" U: Unmute"));
+                let popup_area = Popup::centered_rect_with_height(40, lines.len() as u16 + 2, area);
+                Clear.render(popup_area, buf);
+                Paragraph::new(lines)
+                    .block(
+                        Block::bordered()
+                            .title(format!(" Mute notifications of {} ", url))
+                            .title_bottom(Line::from(" Esc: Cancel ").centered())
+                            .style(Style::default().fg(Color::LightCyan)),
+                    )
+                    .render(popup_area, buf);
+            }
+            DomainScreenMode::Columns => {
+                let lines: Vec<Line> = Column::ALL
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        let shown = if self.ui_state.current.shows(*column) {
+                            "x"
+                        } else {
+                            " "
+                        };
+                        Line::from(format!(" {}: [{}] {}", i + 1, shown, column.label()))
+                    })
+                    .collect();
+                let popup_area = Popup::centered_rect_with_height(40, lines.len() as u16 + 2, area);
+                Clear.render(popup_area, buf);
+                Paragraph::new(lines)
+                    .block(
+                        Block::bordered()
+                            .title(" Columns to show ")
+                            .title_bottom(Line::from(" 1-9: Toggle | Esc: Done ").centered())
+                            .style(Style::default().fg(Color::LightCyan)),
+                    )
+                    .render(popup_area, buf);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
 
-            let selected_domain_index = self.domain_table_state.table_state.selected().unwrap();
-            let domains = self.domains.lock().unwrap().clone();
+    #[test]
+    fn a_request_body_needs_post_or_put() {
+        let mut domain = MonitoredDomain::new("https://example.com/health");
+        domain.request_body = Some(r#"{"probe": true}"#.to_string());
+        assert_eq!(
+            domain.validate(),
+            Err("A request body needs the POST or PUT method".to_string())
+        );
+        domain.http_method = HttpMethod::parse("post");
+        assert_eq!(domain.validate(), Ok(()));
+        domain.request_body = Some(String::new());
+        assert!(domain.validate().is_err());
 
-            let history_table_widget = HistoryTable::new(domains[selected_domain_index].clone());
+        domain.request_body = None;
+        domain.http_method = HttpMethod::parse("head");
+        domain.keyword = Some("ok".to_string());
+        assert!(domain.validate().is_err());
+    }
 
-            history_table_widget.render(area, buf, &mut self.history_table_state);
+    #[test]
+    fn recent_history_copies_keep_the_settings() {
+        let mut domain = MonitoredDomain::new("https://example.com/health");
+        domain.display_name = Some("Checkout".to_string());
+        domain.keyword = Some("ok".to_string());
+        domain.headers = parse_headers("X-Api-Key: abc").unwrap();
+        domain.tags = vec!["payments".to_string()];
+        domain.group = Some("web".to_string());
+        for minutes in 0..5 {
+            domain.push_check(CheckStatus::new(
+                Utc::now() + Duration::minutes(minutes),
+                DomainStatus::Up,
+            ));
         }
+
+        let copy = domain.with_recent_history(2);
+        let timestamps = |checks: &[CheckStatus]| -> Vec<DateTime<Utc>> {
+            checks.iter().map(|check| check.timestamp).collect()
+        };
+        assert_eq!(
+            timestamps(&copy.check_history),
+            timestamps(&domain.check_history[3..])
+        );
+        assert!(copy.daily_rollups.is_empty());
+        assert!(copy.incident_log.is_empty());
+
+        let settings = |domain: &MonitoredDomain| {
+            let mut domain = domain.clone();
+            domain.check_history.clear();
+            domain.daily_rollups.clear();
+            domain.incident_log.clear();
+            serde_json::to_value(domain).unwrap()
+        };
+        assert_eq!(settings(&copy), settings(&domain));
+    }
+
+    #[test]
+    fn headers_are_parsed_and_validated() {
+        assert_eq!(
+            parse_headers("X-Api-Key: a:b; ; Host: web").unwrap(),
+            BTreeMap::from([
+                ("Host".to_string(), "web".to_string()),
+                ("X-Api-Key".to_string(), "a:b".to_string()),
+            ])
+        );
+        assert!(parse_headers("X-Api-Key").is_err());
+
+        let mut domain = MonitoredDomain::new("https://example.com/health");
+        domain.headers = parse_headers("X-Api-Key: abc; Host: api.internal").unwrap();
+        assert_eq!(domain.validate(), Ok(()));
+        domain
+            .headers
+            .insert("Bad Name".to_string(), "x".to_string());
+        assert_eq!(
+            domain.validate(),
+            Err("Invalid header name \"Bad Name\"".to_string())
+        );
+    }
+
+    #[test]
+    fn auth_is_parsed_and_masked() {
+        let basic = DomainAuth::parse("basic ops:hunter2").unwrap().unwrap();
+        assert_eq!(basic.describe(), "basic ops:****");
+        let bearer = DomainAuth::parse("bearer secret:api-token").unwrap();
+        assert_eq!(
+            bearer.as_ref().unwrap().describe(),
+            "bearer secret:api-token"
+        );
+        assert!(DomainAuth::parse("basic ops").is_err());
+        assert!(DomainAuth::parse("digest ops:x").is_err());
+
+        let mut domain = MonitoredDomain::new("https://example.com/health");
+        domain.auth = Some(basic);
+        assert_eq!(domain.validate(), Ok(()));
+        domain
+            .headers
+            .insert("Authorization".to_string(), "Bearer abc".to_string());
+        assert_eq!(
+            domain.validate(),
+            Err("Set either auth or an Authorization header, not both".to_string())
+        );
+    }
+
+    #[test]
+    fn status_codes_are_parsed() {
+        let codes = parse_status_codes("200-299 418").unwrap();
+        assert_eq!(format_status_codes(&codes), "200-299, 418");
+        assert!(parse_status_codes("2xx").is_err());
+
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.expected_status_codes = parse_status_codes("299-200").unwrap();
+        assert!(domain.validate().is_err());
+    }
+
+    #[test]
+    fn an_expected_redirect_has_to_be_a_url() {
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.expected_redirect = Some("https://*.example.com/*".to_string());
+        assert_eq!(domain.validate(), Ok(()));
+        domain.expected_redirect = Some("www.example.com".to_string());
+        assert!(domain.validate().is_err());
     }
 }
@@ -0,0 +1,271 @@
+use crate::ui::input::textarea_input;
+use crossterm::event::KeyEvent;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Widget},
+};
+use tui_textarea::TextArea;
+
+// Checks a field's value as it's typed: Ok with a note on what it is, or Err
+// with what's wrong with it.
+pub type Validator = fn(&str) -> Result<String, String>;
+
+// A popup with several labelled single-line inputs, Tab/BackTab move between them.
+#[derive(Debug, Clone)]
+pub struct Form<'a> {
+    title: Line<'a>,
+    fields: Vec<(&'static str, TextArea<'a>)>,
+    validators: Vec<(&'static str, Validator)>,
+    focused: usize,
+    error: Option<String>,
+}
+
+impl<'a> Form<'a> {
+    pub fn new(title: Line<'a>) -> Self {
+        Self {
+            title,
+            fields: Vec::new(),
+            validators: Vec::new(),
+            focused: 0,
+            error: None,
+        }
+    }
+
+    pub fn with_field(mut self, label: &'static str, value: &str) -> Self {
+        let mut textarea = TextArea::default();
+        textarea.insert_str(value);
+        self.fields.push((label, textarea));
+        self.refresh_focus();
+        self
+    }
+
+    // Shows the validator's verdict on the field's value under it.
+    pub fn with_validator(mut self, label: &'static str, validator: Validator) -> Self {
+        self.validators.push((label, validator));
+        self
+    }
+
+    pub fn value(&self, label: &str) -> String {
+        self.fields
+            .iter()
+            .find(|(field_label, _)| *field_label == label)
+            .map(|(_, textarea)| textarea.lines().join("").trim().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn set_title(&mut self, title: Line<'a>) {
+        self.title = title;
+    }
+
+    pub fn set_error(&mut self, error: impl Into<String>) {
+        self.error = Some(error.into());
+    }
+
+    pub fn focus_next(&mut self) {
+        self.focused = (self.focused + 1) % self.fields.len().max(1);
+        self.refresh_focus();
+    }
+
+    pub fn focus_previous(&mut self) {
+        let field_count = self.fields.len().max(1);
+        self.focused = (self.focused + field_count - 1) % field_count;
+        self.refresh_focus();
+    }
+
+    // Returns false when the key isn't something a text field understands.
+    pub fn input(&mut self, key_event: KeyEvent) -> bool {
+        let Some(input) = textarea_input(key_event) else {
+            return false;
+        };
+        if let Some((_, textarea)) = self.fields.get_mut(self.focused) {
+            textarea.input(input);
+            self.error = None;
+        }
+        true
+    }
+
+    // Height needed to show every field and verdict plus the error line and
+    // borders.
+    pub fn height(&self) -> u16 {
+        self.fields.len() as u16 * 3 + self.validators.len() as u16 + 3
+    }
+
+    // Lines a field takes: its box, and its verdict if it has a validator.
+    fn field_height(&self, label: &str) -> u16 {
+        3 + self.validator(label).is_some() as u16
+    }
+
+    // The first field shown when only `lines` fit: the top one if the focused
+    // field fits from there, else the one that brings it in at the bottom.
+    fn scroll_offset(&self, lines: u16) -> usize {
+        let mut used = 0;
+        let mut offset = self.focused.min(self.fields.len().saturating_sub(1));
+        for (i, (label, _)) in self.fields.iter().enumerate().take(offset + 1).rev() {
+            used += self.field_height(label);
+            if used > lines {
+                break;
+            }
+            offset = i;
+        }
+        offset
+    }
+
+    fn validator(&self, label: &str) -> Option<Validator> {
+        self.validators
+            .iter()
+            .find(|(validated, _)| *validated == label)
+            .map(|(_, validator)| *validator)
+    }
+
+    fn refresh_focus(&mut self) {
+        for (i, (label, textarea)) in self.fields.iter_mut().enumerate() {
+            let (border_color, cursor_style) = if i == self.focused {
+                (
+                    Color::LightCyan,
+                    Style::default().add_modifier(Modifier::REVERSED),
+                )
+            } else {
+                (Color::Gray, Style::default())
+            };
+            textarea.set_block(
+                Block::bordered()
+                    .title(*label)
+                    .style(Style::default().fg(border_color)),
+            );
+            textarea.set_cursor_style(cursor_style);
+            textarea.set_cursor_line_style(Style::default());
+        }
+    }
+}
+
+impl Widget for &Form<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+
+        let mut block = Block::bordered()
+            .title(self.title.clone())
+            .title_style(Style::default().fg(Color::White).bold())
+            .title_bottom(Line::from(" Tab: Next Field - Enter: Save - Esc: Cancel ").centered())
+            .border_style(Style::default().fg(Color::Gray))
+            .style(Style::default().bg(Color::DarkGray));
+
+        // Forms taller than the screen scroll to keep the focused field in
+        // view, the error line stays at the bottom.
+        let lines = area.height.saturating_sub(3);
+        let offset = self.scroll_offset(lines);
+        let mut used = 0;
+        let shown = self.fields[offset..]
+            .iter()
+            .take_while(|(label, _)| {
+                used += self.field_height(label);
+                used <= lines
+            })
+            .count();
+        if offset > 0 {
+            block = block.title(Line::from(format!(" ↑ {} more ", offset)).right_aligned());
+        }
+        let below = self.fields.len() - offset - shown;
+        if below > 0 {
+            block = block.title_bottom(Line::from(format!(" ↓ {} more ", below)).right_aligned());
+        }
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        let visible = &self.fields[offset..offset + shown];
+        let mut constraints = Vec::new();
+        for (label, _) in visible {
+            constraints.push(Constraint::Length(3));
+            if self.validator(label).is_some() {
+                constraints.push(Constraint::Length(1));
+            }
+        }
+        constraints.push(Constraint::Length(1));
+        let rows = Layout::vertical(constraints).split(inner_area);
+
+        let mut rows = rows.iter();
+        for (label, textarea) in visible {
+            let Some(row) = rows.next() else { break };
+            textarea.render(*row, buf);
+            // Only fields with a validator have a row for its verdict.
+            if let Some(validator) = self.validator(label)
+                && let Some(row) = rows.next()
+            {
+                let value = textarea.lines().join("");
+                let verdict = match validator(value.trim()) {
+                    Ok(note) => format!(" ✓ {}", note).green(),
+                    Err(problem) => format!(" ✗ {}", problem).light_red(),
+                };
+                Line::from(verdict).render(*row, buf);
+            }
+        }
+
+        if let (Some(error), Some(row)) = (&self.error, rows.next()) {
+            Line::from(error.as_str().red().bold()).render(*row, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    fn rendered(form: &Form) -> String {
+        let area = Rect::new(0, 0, 50, form.height());
+        let mut buf = Buffer::empty(area);
+        form.render(area, &mut buf);
+        buf.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn validates_as_you_type() {
+        let port = |value: &str| match value.parse::<u16>() {
+            Ok(0) | Err(_) => Err("Not a port".to_string()),
+            Ok(_) => Ok("Looks good".to_string()),
+        };
+        let mut form = Form::new(Line::from("Port"))
+            .with_field("Port", "8")
+            .with_validator("Port", port)
+            .with_field("Name", "");
+        assert_eq!(form.height(), 10);
+        assert!(rendered(&form).contains("✓ Looks good"));
+
+        form.input(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE));
+        assert!(rendered(&form).contains("✗ Not a port"));
+    }
+
+    #[test]
+    fn scrolls_to_the_focused_field() {
+        const LABELS: [&str; 8] = [
+            "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight",
+        ];
+        let mut form = LABELS
+            .iter()
+            .fold(Form::new(Line::from("Long")), |form, label| {
+                form.with_field(label, "")
+            });
+        // Room for three fields.
+        let area = Rect::new(0, 0, 80, 12);
+        let rendered = |form: &Form| {
+            let mut buf = Buffer::empty(area);
+            form.render(area, &mut buf);
+            buf.content()
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+        let screen = rendered(&form);
+        assert!(screen.contains("One") && screen.contains("Three") && !screen.contains("Four"));
+        assert!(screen.contains("↓ 5 more"));
+
+        for _ in 0..6 {
+            form.focus_next();
+        }
+        let screen = rendered(&form);
+        assert!(screen.contains("Five") && screen.contains("Seven") && !screen.contains("Four"));
+        assert!(screen.contains("↑ 4 more") && screen.contains("↓ 1 more"));
+    }
+}
@@ -0,0 +1,120 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Paragraph, Widget, Wrap},
+};
+
+// The keys of every screen, a section per screen.
+const SECTIONS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Everywhere",
+        &[
+            ("Esc", "Go back one screen"),
+            ("Q", "Quit"),
+            ("Ctrl+P", "Pause or resume all checks"),
+        ],
+    ),
+    (
+        "Main menu",
+        &[
+            ("Up/Down, Enter", "Pick an entry and open it"),
+            ("E D I L S P ?", "Open an entry directly"),
+        ],
+    ),
+    (
+        "Monitored URLs",
+        &[
+            ("A / E / D", "Add, edit or delete a domain"),
+            ("H / I", "History or incidents of the selected domain"),
+            ("M", "Mute its notifications"),
+            ("O", "Show only one owner's domains"),
+            ("T", "Edit the tags of the selected domain, Tab completes"),
+            ("B", "Sort by the next column, then descending"),
+            ("C", "Pick the columns to show"),
+            (
+                "V / 1-9 / 0",
+                "Save the view, switch to a saved one, show all",
+            ),
+            ("S", "Soak test the selected domain"),
+            ("R", "Open the runbook from its metadata"),
+        ],
+    ),
+    (
+        "History",
+        &[
+            (
+                "Enter",
+                "Show the redirects and traceroute of the selected check",
+            ),
+            ("C", "Collapse runs of identical results"),
+            ("F", "Follow the newest check as checks come in"),
+            ("W", "Change the latency histogram's window"),
+            ("1-9", "Open the numbered metadata link"),
+        ],
+    ),
+    (
+        "Incidents",
+        &[
+            ("Enter", "Open the incidents of the domain (overview)"),
+            ("N", "Write a postmortem note (per domain)"),
+            ("R", "Open the domain's runbook"),
+        ],
+    ),
+    (
+        "Logs",
+        &[
+            ("Up/Down, PgUp/PgDn", "Scroll"),
+            ("End", "Follow new lines"),
+        ],
+    ),
+    ("Settings", &[("Left/Right", "Change the selected setting")]),
+];
+
+#[derive(Debug, Default)]
+pub struct HelpScreen {
+    scroll: u16,
+}
+
+impl HelpScreen {
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Widget for &mut HelpScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered()
+            .title(Line::from(" Help ").centered())
+            .title_bottom(Line::from(" Esc: Go Back | Up/Down: Scroll ").centered());
+
+        let mut lines = Vec::new();
+        for (screen, keys) in SECTIONS {
+            lines.push(Line::from(format!(" {}", screen)).yellow().bold());
+            for (key, action) in *keys {
+                lines.push(Line::from(vec![
+                    format!("   {:<20}", key).blue().bold(),
+                    action.to_string().into(),
+                ]));
+            }
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(" The README has the details, e.g. the config options.").dark_gray());
+
+        // Never scroll past the last line.
+        let max_scroll = (lines.len() as u16).saturating_sub(1);
+        self.scroll = self.scroll.min(max_scroll);
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0))
+            .block(block)
+            .render(area, buf);
+    }
+}
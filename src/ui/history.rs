@@ -0,0 +1,327 @@
+use crate::app::AppEvent;
+use crate::configuration::Settings;
+use crate::ui::domains::MonitoredDomain;
+use crate::ui::history_table::{HistoryRow, HistoryTable, HistoryTableState, history_rows};
+use crate::ui::latency_histogram::{HistogramWindow, LatencyHistogram};
+use crate::ui::week_comparison::WeekComparison;
+use crate::utils::{is_link, open_link};
+use chrono::{DateTime, Utc};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Paragraph, StatefulWidget, Widget},
+};
+
+// The check history of one domain, opened from the domains screen.
+#[derive(Debug)]
+pub struct HistoryScreen {
+    domain: MonitoredDomain,
+    history_table_state: HistoryTableState,
+    histogram_window: HistogramWindow,
+    // Runs of identical results as single rows.
+    collapsed: bool,
+    settings: Settings,
+}
+
+impl HistoryScreen {
+    pub fn new(domain: MonitoredDomain, settings: Settings) -> Self {
+        HistoryScreen {
+            domain,
+            history_table_state: HistoryTableState::default(),
+            histogram_window: HistogramWindow::default(),
+            collapsed: false,
+            settings,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.domain.url
+    }
+
+    // The metadata values 1-9 open, in the order they're listed.
+    fn links(&self) -> impl Iterator<Item = &str> {
+        self.domain
+            .metadata
+            .values()
+            .map(String::as_str)
+            .filter(|value| is_link(value))
+    }
+
+    // When the selected row starts: its first check, or the first missed one
+    // for a gap.
+    fn selected_time(&self) -> Option<DateTime<Utc>> {
+        let selected = self.history_table_state.table_state.selected()?;
+        let rows = history_rows(
+            &self.domain.check_history,
+            self.domain.interval_seconds,
+            Utc::now(),
+            self.collapsed,
+        );
+        rows.get(selected).map(row_time)
+    }
+
+    // Selects the row starting at the time, or the one it's now part of.
+    fn select_time(&mut self, time: DateTime<Utc>) {
+        let rows = history_rows(
+            &self.domain.check_history,
+            self.domain.interval_seconds,
+            Utc::now(),
+            self.collapsed,
+        );
+        let row = rows.iter().rposition(|row| row_time(row) <= time);
+        self.history_table_state
+            .table_state
+            .select(row.or((!rows.is_empty()).then_some(0)));
+    }
+
+    // Returns false once the domain is gone, so the app can close the screen.
+    pub fn handle_app_event(&mut self, event: &AppEvent) -> bool {
+        // Compaction drops the oldest checks and collapsed runs grow, so the
+        // same row number would soon be another check. Only looked up for
+        // events that change this domain, the others leave the table alone.
+        let selected = match event {
+            AppEvent::CheckCompleted { domain_id, check }
+                if *domain_id == self.domain.id
+                    && !self
+                        .domain
+                        .check_history
+                        .iter()
+                        .any(|c| c.timestamp == check.timestamp) =>
+            {
+                let selected = self.selected_time();
+                let retention = self
+                    .domain
+                    .history_retention(self.settings.monitor.history_retention());
+                self.domain.push_check(check.clone());
+                self.domain.compact_history(retention, Utc::now());
+                selected
+            }
+            AppEvent::DomainUpdated(domain) if domain.id == self.domain.id => {
+                let selected = self.selected_time();
+                self.domain = domain.clone();
+                selected
+            }
+            AppEvent::DomainRemoved(domain_id) if *domain_id == self.domain.id => return false,
+            AppEvent::DomainsReloaded(domains) => {
+                let Some(domain) = domains.iter().find(|d| d.id == self.domain.id) else {
+                    return false;
+                };
+                let selected = self.selected_time();
+                self.domain = domain.clone();
+                selected
+            }
+            _ => return true,
+        };
+        if let Some(time) = selected
+            && !self.history_table_state.following
+        {
+            self.select_time(time);
+        }
+        true
+    }
+
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> bool {
+        if let KeyCode::Char(digit @ '1'..='9') = key_event.code
+            && let Some(link) = self.links().nth(digit as usize - '1' as usize)
+        {
+            if let Err(e) = open_link(link) {
+                tracing::error!("Failed to open {}: {}", link, e);
+            }
+            return true;
+        }
+
+        if let KeyCode::Char('w') | KeyCode::Char('W') = key_event.code {
+            self.histogram_window = self.histogram_window.next();
+            return true;
+        }
+
+        if key_event.code == KeyCode::Enter {
+            self.history_table_state.expanded = !self.history_table_state.expanded;
+            return true;
+        }
+
+        if let KeyCode::Char('f') | KeyCode::Char('F') = key_event.code {
+            self.history_table_state.following = !self.history_table_state.following;
+            return true;
+        }
+
+        if let KeyCode::Char('c') | KeyCode::Char('C') = key_event.code {
+            self.collapsed = !self.collapsed;
+            // Row numbers mean something else now.
+            self.history_table_state.table_state.select(None);
+            return true;
+        }
+
+        let history_len = history_rows(
+            &self.domain.check_history,
+            self.domain.interval_seconds,
+            Utc::now(),
+            self.collapsed,
+        )
+        .len();
+        if history_len == 0 {
+            return false;
+        }
+
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                // Looking back at older checks, which following would undo.
+                self.history_table_state.following = false;
+                HistoryTable::previous_row(&mut self.history_table_state, history_len);
+                true
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                HistoryTable::next_row(&mut self.history_table_state, history_len);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn row_time(row: &HistoryRow) -> DateTime<Utc> {
+    match row {
+        HistoryRow::Checks(run) => run.first.timestamp,
+        HistoryRow::Gap(gap) => gap.from,
+    }
+}
+
+impl Widget for &mut HistoryScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut area = area;
+        let latency = self.domain.latency_settings(self.settings.latency);
+        let now = Utc::now();
+
+        let metadata = &self.domain.metadata;
+        if !metadata.is_empty() && area.height > metadata.len() as u16 + 10 {
+            let [metadata_area, rest] = Layout::vertical([
+                Constraint::Length(metadata.len() as u16),
+                Constraint::Min(0),
+            ])
+            .areas(area);
+            let mut link_number = 0;
+            let lines: Vec<Line> = metadata
+                .iter()
+                .map(|(key, value)| {
+                    if !is_link(value) || link_number >= 9 {
+                        return Line::from(format!("    {}: {}", key, value));
+                    }
+                    link_number += 1;
+                    Line::from(vec![
+                        Span::from(format!("[{}] ", link_number)).bold(),
+                        Span::from(format!("{}: ", key)),
+                        Span::from(value.as_str()).blue().underlined(),
+                    ])
+                })
+                .collect();
+            Paragraph::new(lines).render(metadata_area, buf);
+            area = rest;
+        }
+
+        let warnings = self
+            .domain
+            .certificate
+            .as_ref()
+            .map(|c| c.warnings.as_slice())
+            .unwrap_or_default();
+        if !warnings.is_empty() && area.height > warnings.len() as u16 + 10 {
+            let [warnings_area, rest] = Layout::vertical([
+                Constraint::Length(warnings.len() as u16),
+                Constraint::Min(0),
+            ])
+            .areas(area);
+            let lines: Vec<Line> = warnings
+                .iter()
+                .map(|w| {
+                    Line::styled(
+                        format!("Weak certificate: {}", w),
+                        Style::default().yellow(),
+                    )
+                })
+                .collect();
+            Paragraph::new(lines).render(warnings_area, buf);
+            area = rest;
+        }
+        if area.height >= WeekComparison::height() + 10 {
+            let [comparison_area, rest] = Layout::vertical([
+                Constraint::Length(WeekComparison::height()),
+                Constraint::Min(0),
+            ])
+            .areas(area);
+            WeekComparison::new(
+                &self.domain,
+                now,
+                self.settings.monitor.count_rate_limited_as_down,
+                latency,
+                self.settings.display.locale,
+            )
+            .render(comparison_area, buf);
+            area = rest;
+        }
+        // Leave the table the whole screen when it is too small for both.
+        if area.height >= LatencyHistogram::height() + 10 && !self.settings.performance.low_resource
+        {
+            let [table_area, histogram_area] = Layout::vertical([
+                Constraint::Min(0),
+                Constraint::Length(LatencyHistogram::height()),
+            ])
+            .areas(area);
+            LatencyHistogram::new(
+                &self.domain,
+                self.histogram_window,
+                now,
+                latency,
+                self.settings.display.locale,
+            )
+            .render(histogram_area, buf);
+            area = table_area;
+        }
+
+        HistoryTable::new(
+            &self.domain,
+            self.settings.monitor.history_retention(),
+            self.settings.latency,
+            self.settings.display.locale,
+        )
+        .with_collapsed(self.collapsed)
+        .with_now(now)
+        .render(area, buf, &mut self.history_table_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::domains::{CheckStatus, DomainStatus, HttpCode};
+    use chrono::Duration;
+
+    #[test]
+    fn new_checks_keep_the_selected_one_selected() {
+        let now = Utc::now();
+        let check = |minute: i64| CheckStatus {
+            http_code: Some(HttpCode::Ok),
+            response_time_ms: Some(100),
+            ..CheckStatus::new(now + Duration::minutes(minute), DomainStatus::Up)
+        };
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.interval_seconds = 60;
+        domain.history_max_checks = Some(3);
+        domain.check_history = vec![check(-3), check(-2), check(-1)];
+        let id = domain.id;
+        let mut screen = HistoryScreen::new(domain, Settings::default());
+        screen.history_table_state.table_state.select(Some(1));
+
+        let event = AppEvent::CheckCompleted {
+            domain_id: id,
+            check: check(0),
+        };
+        assert!(screen.handle_app_event(&event));
+        // The oldest check was compacted away, the one at -2 is now first.
+        assert_eq!(screen.domain.check_history.len(), 3);
+        assert_eq!(screen.history_table_state.table_state.selected(), Some(0));
+        assert_eq!(screen.selected_time(), Some(now - Duration::minutes(2)));
+    }
+}
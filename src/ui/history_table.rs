@@ -1,27 +1,126 @@
-use crate::ui::domains::{DomainStatus, HttpCode, MonitoredDomain};
-use chrono::prelude::*;
+use crate::blind_spot::{HistoryGap, history_gaps};
+use crate::incident::format_duration;
+use crate::monitor::CheckTimings;
+use crate::ui::domains::{CheckStatus, DomainStatus, HistoryRetention, HttpCode, MonitoredDomain};
+use crate::ui::latency::LatencySettings;
+use crate::ui::locale::Locale;
+use chrono::{DateTime, Utc};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Rect},
     prelude::Modifier,
     style::{Color, Style, Stylize},
-    text::{Line, Span},
+    text::{Line, Span, Text},
     widgets::{Block, Cell, Row, StatefulWidget, Table, TableState, Widget},
 };
 
 #[derive(Debug, Default, Clone)]
 pub struct HistoryTableState {
     pub table_state: TableState,
+    // Whether the selected row lists the redirects and traceroute of its
+    // check.
+    pub expanded: bool,
+    // Keeps the newest row selected as checks come in, like tail -f.
+    pub following: bool,
 }
 
 #[derive(Debug, Clone)]
-pub struct HistoryTable {
-    domain: MonitoredDomain,
+pub struct HistoryTable<'a> {
+    domain: &'a MonitoredDomain,
+    // The configured default, the domain may override it.
+    default_retention: HistoryRetention,
+    default_latency: LatencySettings,
+    locale: Locale,
+    // One row per run of identical results instead of one per check.
+    collapsed: bool,
+    // Where a gap at the end of the history stops.
+    now: DateTime<Utc>,
 }
 
-impl HistoryTable {
-    pub fn new(domain: MonitoredDomain) -> Self {
-        Self { domain }
+// Consecutive checks with the same status and HTTP code, shown as one row
+// when the history is collapsed.
+#[derive(Debug)]
+pub struct HistoryRun<'a> {
+    pub first: &'a CheckStatus,
+    pub last: &'a CheckStatus,
+    pub count: usize,
+    // Average of the checks that have one.
+    pub response_time_ms: Option<u64>,
+}
+
+// The rows of the history table, every check its own run unless collapsed.
+pub fn history_runs(checks: &[CheckStatus], collapsed: bool) -> Vec<HistoryRun<'_>> {
+    let same_result = |a: &CheckStatus, b: &CheckStatus| {
+        collapsed && a.status == b.status && a.http_code == b.http_code
+    };
+    checks
+        .chunk_by(same_result)
+        .map(|run| {
+            let times: Vec<u64> = run.iter().filter_map(|c| c.response_time_ms).collect();
+            HistoryRun {
+                first: &run[0],
+                last: &run[run.len() - 1],
+                count: run.len(),
+                response_time_ms: (!times.is_empty())
+                    .then(|| times.iter().sum::<u64>() / times.len() as u64),
+            }
+        })
+        .collect()
+}
+
+// A row of the history table: a run of checks or the time between two of
+// them that went without any.
+#[derive(Debug)]
+pub enum HistoryRow<'a> {
+    Checks(HistoryRun<'a>),
+    Gap(HistoryGap),
+}
+
+// The runs of the history with a row for every gap, runs never spanning one.
+pub fn history_rows(
+    checks: &[CheckStatus],
+    interval_seconds: u64,
+    now: DateTime<Utc>,
+    collapsed: bool,
+) -> Vec<HistoryRow<'_>> {
+    let mut rows = Vec::new();
+    let mut start = 0;
+    for gap in history_gaps(checks, interval_seconds, now) {
+        let runs = history_runs(&checks[start..gap.before], collapsed);
+        rows.extend(runs.into_iter().map(HistoryRow::Checks));
+        rows.push(HistoryRow::Gap(gap));
+        start = gap.before;
+    }
+    let runs = history_runs(&checks[start..], collapsed);
+    rows.extend(runs.into_iter().map(HistoryRow::Checks));
+    rows
+}
+
+impl<'a> HistoryTable<'a> {
+    pub fn new(
+        domain: &'a MonitoredDomain,
+        default_retention: HistoryRetention,
+        default_latency: LatencySettings,
+        locale: Locale,
+    ) -> Self {
+        Self {
+            domain,
+            default_retention,
+            default_latency,
+            locale,
+            collapsed: false,
+            now: Utc::now(),
+        }
+    }
+
+    pub fn with_now(mut self, now: DateTime<Utc>) -> Self {
+        self.now = now;
+        self
+    }
+
+    pub fn with_collapsed(mut self, collapsed: bool) -> Self {
+        self.collapsed = collapsed;
+        self
     }
 
     pub fn next_row(history_table_state: &mut HistoryTableState, check_history_len: usize) {
@@ -35,7 +134,7 @@ impl HistoryTable {
             }
             None => 0,
         };
-        log::debug!("{i:?}");
+        tracing::debug!("{i:?}");
 
         history_table_state.table_state.select(Some(i));
     }
@@ -56,58 +155,189 @@ impl HistoryTable {
     }
 }
 
-impl StatefulWidget for HistoryTable {
+impl HistoryTable<'_> {
+    // "slow DNS: DNS 900ms, connect 20ms, TLS 40ms, first byte 120ms"
+    fn timings_hint(&self, timings: &CheckTimings, latency: &LatencySettings) -> String {
+        let format = |ms: u64| latency.format(ms, &self.locale);
+        let mut phases = vec![
+            format!("DNS {}", format(timings.dns_ms)),
+            format!("connect {}", format(timings.connect_ms)),
+        ];
+        if let Some(tls_ms) = timings.tls_ms {
+            phases.push(format!("TLS {}", format(tls_ms)));
+        }
+        phases.push(format!("first byte {}", format(timings.ttfb_ms)));
+        format!(
+            "{}: {}",
+            timings.hint().unwrap_or("slow"),
+            phases.join(", ")
+        )
+    }
+
+    // The response size, yellow when it shrank to less than half of the
+    // check before, like a page swapped for a small error stub.
+    fn size_span(&self, check: &CheckStatus) -> Span<'static> {
+        let Some(bytes) = check.response_bytes else {
+            return Span::raw("");
+        };
+        let previous = self
+            .domain
+            .check_history
+            .iter()
+            .rposition(|c| std::ptr::eq(c, check))
+            .and_then(|at| {
+                self.domain.check_history[..at]
+                    .iter()
+                    .rev()
+                    .find_map(|c| c.response_bytes)
+            });
+        let size = self.locale.bytes(bytes);
+        if previous.is_some_and(|previous| bytes < previous / 2) {
+            Span::styled(size, Style::default().yellow())
+        } else {
+            Span::raw(size)
+        }
+    }
+
+    fn gap_cells(&self, gap: &HistoryGap) -> Vec<Cell<'static>> {
+        let ongoing = gap.before == self.domain.check_history.len();
+        let mut cells = vec![Cell::from(self.locale.timestamp(gap.from))];
+        if self.collapsed {
+            let to = if ongoing {
+                String::new()
+            } else {
+                self.locale.timestamp(gap.to)
+            };
+            cells.extend([Cell::from(to), Cell::from("")]);
+        }
+        let message = format!(
+            "No checks for {}{}",
+            format_duration(gap.to - gap.from),
+            if ongoing { " so far" } else { "" }
+        );
+        cells.extend([
+            Cell::from(Span::styled("GAP", Style::default().magenta().bold())),
+            Cell::from(""),
+            Cell::from(""),
+            Cell::from(""),
+            Cell::from(Span::styled(message, Style::default().magenta())),
+        ]);
+        cells
+    }
+}
+
+impl StatefulWidget for HistoryTable<'_> {
     type State = HistoryTableState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         let title = format!("History for: {}", self.domain.url);
-        let instructions = Line::from("Esc: Go Back | Up/Down: Navigate History");
+        let instructions = Line::from(if self.collapsed {
+            "Esc: Go Back | Up/Down: Navigate History | Enter: Redirects | C: Show Every Check | F: Follow"
+        } else {
+            "Esc: Go Back | Up/Down: Navigate History | Enter: Redirects | C: Collapse Repeats | F: Follow"
+        });
+        let retention = format!(
+            " Keeping {} ({}) ",
+            self.domain.history_retention(self.default_retention),
+            if self.domain.overrides_history_retention() {
+                "override"
+            } else {
+                "default"
+            }
+        );
 
-        let main_block = Block::bordered()
+        let mut main_block = Block::bordered()
             .title_top(Line::from(title).centered())
+            .title_top(Line::from(retention).right_aligned())
             .title_bottom(instructions.centered());
+        if state.following {
+            main_block = main_block.title_top(Line::from(" Following ").green().left_aligned());
+        }
 
         Widget::render(&main_block, area, buf);
 
         let inner_area = main_block.inner(area);
 
-        let header_cells = [
-            "Timestamp",
-            "Status",
-            "HTTP Code",
-            "Response Time",
-            "Error Message",
-        ]
-        .iter()
-        .map(|h| Cell::from(*h).style(Style::default().bold()));
+        let mut header_cells = if self.collapsed {
+            vec!["From", "To", "Checks"]
+        } else {
+            vec!["Timestamp"]
+        };
+        header_cells.extend(["Status", "HTTP Code", "Response Time", "Size", "Message"]);
+        let header_cells = header_cells
+            .into_iter()
+            .map(|h| Cell::from(h).style(Style::default().bold()));
         let header = Row::new(header_cells)
             .height(1)
             .bottom_margin(1)
             .style(Style::default().bg(Color::Yellow).fg(Color::Black));
 
-        let rows: Vec<Row> = self
-            .domain
-            .check_history
+        let latency = self.domain.latency_settings(self.default_latency);
+        let history = history_rows(
+            &self.domain.check_history,
+            self.domain.interval_seconds,
+            self.now,
+            self.collapsed,
+        );
+        if state.following && !history.is_empty() {
+            state.table_state.select(Some(history.len() - 1));
+        }
+        // Long histories have far more rows than fit, so only the ones on
+        // screen are built. The header and its margin take two lines.
+        let selected = state.table_state.selected();
+        // The lines the expanded row takes on top of its own.
+        let expansion = match selected.and_then(|i| history.get(i)) {
+            Some(HistoryRow::Checks(run)) if state.expanded => {
+                run.last.redirects.len() + run.last.traceroute.len()
+            }
+            _ => 0,
+        };
+        let visible = (inner_area.height.saturating_sub(2) as usize)
+            .saturating_sub(expansion)
+            .max(1);
+        let offset = visible_offset(&state.table_state, visible, history.len());
+        let rows: Vec<Row> = history
             .iter()
             .enumerate()
-            .map(|(i, check)| {
+            .skip(offset)
+            .take(visible)
+            .map(|(i, row)| {
                 let row_color = if i % 2 == 0 {
                     Color::DarkGray
                 } else {
                     Color::Reset
                 };
+                let run = match row {
+                    HistoryRow::Checks(run) => run,
+                    HistoryRow::Gap(gap) => {
+                        return Row::new(self.gap_cells(gap)).style(Style::default().bg(row_color));
+                    }
+                };
+                let check = run.last;
 
-                let timestamp_display = check
-                    .timestamp
-                    .with_timezone(&Local)
-                    .format("%Y-%m-%d %H:%M:%S")
-                    .to_string();
+                let timestamp_display = self.locale.timestamp(run.first.timestamp);
+                let to_display = if run.count > 1 {
+                    self.locale.timestamp(run.last.timestamp)
+                } else {
+                    String::new()
+                };
+                let count_display = if run.count > 1 {
+                    format!("×{}", self.locale.integer(run.count as u64))
+                } else {
+                    String::new()
+                };
                 let status_display = match &check.status {
                     DomainStatus::Up => Span::styled("UP", Style::default().green().bold()),
+                    DomainStatus::Degraded => {
+                        Span::styled("DEGRADED", Style::default().yellow().bold())
+                    }
                     DomainStatus::Down => Span::styled("DOWN", Style::default().red().bold()),
                     DomainStatus::Unknown => {
                         Span::styled("UNKNOWN", Style::default().yellow().bold())
                     }
+                    DomainStatus::RateLimited => {
+                        Span::styled("RATE LIMITED", Style::default().yellow().bold())
+                    }
                     DomainStatus::Error(_) => Span::styled("ERROR", Style::default().red()),
                 };
                 let http_code_display = match &check.http_code {
@@ -120,39 +350,246 @@ impl StatefulWidget for HistoryTable {
                     Some(HttpCode::NetworkError) => Span::styled("Net Err", Style::default().red()),
                     None => Span::styled("N/A", Style::default().gray()),
                 };
-                let response_time_display = check
-                    .response_time_ms
-                    .map(|ms| format!("{}ms", ms))
-                    .unwrap_or_else(|| "N/A".to_string());
-                let error_message_display =
-                    check.error_message.as_deref().unwrap_or("").to_string();
-
-                let cells = vec![
-                    Cell::from(timestamp_display),
+                let response_time_display = latency.span(run.response_time_ms, &self.locale);
+                let error_message_display = match (&check.error_message, &check.timings) {
+                    (Some(error), _) => Span::raw(&**error),
+                    (None, _) if !check.redirects.is_empty() => Span::styled(
+                        match check.redirects.len() {
+                            1 => "via 1 redirect".to_string(),
+                            hops => format!("via {} redirects", hops),
+                        },
+                        Style::default().gray(),
+                    ),
+                    (None, _) if check.detail.is_some() => Span::styled(
+                        check.detail.as_deref().unwrap_or_default(),
+                        Style::default().gray(),
+                    ),
+                    // A run's average hides which check the breakdown was for.
+                    (None, Some(timings)) if run.count == 1 => Span::styled(
+                        self.timings_hint(timings, &latency),
+                        Style::default().yellow(),
+                    ),
+                    _ => Span::raw(""),
+                };
+
+                let mut cells = vec![Cell::from(timestamp_display)];
+                if self.collapsed {
+                    cells.extend([Cell::from(to_display), Cell::from(count_display)]);
+                }
+                // The expanded row lists each redirect and traceroute hop
+                // under its message.
+                let mut message = vec![Line::from(error_message_display)];
+                if state.expanded && selected == Some(i) {
+                    message.extend(check.redirects.iter().map(|hop| {
+                        Line::styled(
+                            format!("  {} → {}", hop.status, hop.location),
+                            Style::default().gray(),
+                        )
+                    }));
+                    message.extend(check.traceroute.iter().map(|hop| {
+                        let rtt = hop
+                            .rtt_ms
+                            .map(|ms| format!("  {}ms", self.locale.decimal(ms, 1)))
+                            .unwrap_or_default();
+                        Line::styled(
+                            format!(
+                                "  {:>2}  {}{}",
+                                hop.ttl,
+                                hop.address.as_deref().unwrap_or("*"),
+                                rtt
+                            ),
+                            Style::default().gray(),
+                        )
+                    }));
+                }
+                let height = message.len() as u16;
+                cells.extend([
                     Cell::from(status_display),
                     Cell::from(http_code_display),
                     Cell::from(response_time_display),
-                    Cell::from(error_message_display),
-                ];
-                Row::new(cells).style(Style::default().bg(row_color))
+                    Cell::from(self.size_span(check)),
+                    Cell::from(Text::from(message)),
+                ]);
+                Row::new(cells)
+                    .height(height)
+                    .style(Style::default().bg(row_color))
             })
             .collect();
 
-        let table = Table::new(
-            rows,
-            [
-                Constraint::Length(20), // Timestamp
-                Constraint::Length(10), // Status
-                Constraint::Length(12), // HTTP Code
-                Constraint::Length(15), // Response Time
-                Constraint::Min(0),     // Error Message (takes remaining space)
-            ],
-        )
-        .header(header)
-        .column_spacing(2)
-        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
-        .highlight_symbol("-> ");
+        let mut widths = vec![Constraint::Length(20)]; // Timestamp, or the start of a run
+        if self.collapsed {
+            widths.extend([
+                Constraint::Length(20), // End of the run
+                Constraint::Length(8),  // Checks in the run
+            ]);
+        }
+        widths.extend([
+            Constraint::Length(12), // Status
+            Constraint::Length(12), // HTTP Code
+            Constraint::Length(15), // Response Time
+            Constraint::Length(10), // Size
+            Constraint::Min(0),     // Error or detail message (takes remaining space)
+        ]);
+
+        let table = Table::new(rows, widths)
+            .header(header)
+            .column_spacing(2)
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("-> ");
+
+        let mut window_state = TableState::default().with_selected(
+            state
+                .table_state
+                .selected()
+                .map(|selected| selected - offset),
+        );
+        StatefulWidget::render(table, inner_area, buf, &mut window_state);
+        *state.table_state.offset_mut() = offset;
+    }
+}
+
+// The first row to show so the selection stays on screen, scrolling as
+// little as possible from the last frame.
+fn visible_offset(table_state: &TableState, visible: usize, rows: usize) -> usize {
+    let mut offset = table_state.offset().min(rows.saturating_sub(visible));
+    if let Some(selected) = table_state.selected() {
+        if selected < offset {
+            offset = selected;
+        } else if selected >= offset + visible {
+            offset = selected + 1 - visible;
+        }
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn offset_follows_the_selection() {
+        let state = |selected, offset| {
+            TableState::default()
+                .with_selected(selected)
+                .with_offset(offset)
+        };
+        assert_eq!(visible_offset(&state(None, 0), 10, 100), 0);
+        assert_eq!(visible_offset(&state(Some(25), 0), 10, 100), 16);
+        assert_eq!(visible_offset(&state(Some(20), 16), 10, 100), 16);
+        assert_eq!(visible_offset(&state(Some(3), 16), 10, 100), 3);
+        // Shrunk history, the old offset would show nothing.
+        assert_eq!(visible_offset(&state(None, 90), 10, 20), 10);
+    }
+
+    #[test]
+    fn collapsing_groups_identical_results() {
+        let now = Utc::now();
+        let check = |minute: i64, status: DomainStatus, response_time_ms: u64| CheckStatus {
+            http_code: Some(HttpCode::Ok),
+            response_time_ms: Some(response_time_ms),
+            ..CheckStatus::new(now + Duration::minutes(minute), status)
+        };
+        let checks = vec![
+            check(0, DomainStatus::Up, 100),
+            check(1, DomainStatus::Up, 200),
+            check(2, DomainStatus::Up, 300),
+            check(3, DomainStatus::Down, 50),
+            check(4, DomainStatus::Up, 100),
+        ];
+
+        assert_eq!(history_runs(&checks, false).len(), 5);
+
+        let runs = history_runs(&checks, true);
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[0].count, 3);
+        assert_eq!(runs[0].first.timestamp, checks[0].timestamp);
+        assert_eq!(runs[0].last.timestamp, checks[2].timestamp);
+        assert_eq!(runs[0].response_time_ms, Some(200));
+        assert_eq!(runs[1].count, 1);
+    }
+
+    #[test]
+    fn runs_stop_at_gaps() {
+        let now = Utc::now();
+        let check = |minute: i64| CheckStatus {
+            http_code: Some(HttpCode::Ok),
+            response_time_ms: Some(100),
+            ..CheckStatus::new(now + Duration::minutes(minute), DomainStatus::Up)
+        };
+        let checks = vec![check(0), check(1), check(10), check(11)];
+
+        let rows = history_rows(&checks, 60, now + Duration::minutes(12), true);
+
+        assert_eq!(rows.len(), 3);
+        assert!(matches!(&rows[0], HistoryRow::Checks(run) if run.count == 2));
+        assert!(matches!(&rows[1], HistoryRow::Gap(gap) if gap.before == 2));
+        assert!(matches!(&rows[2], HistoryRow::Checks(run) if run.count == 2));
+    }
+
+    #[test]
+    fn shrunken_responses_stand_out() {
+        let now = Utc::now();
+        let check = |minute: i64, response_bytes: Option<u64>| CheckStatus {
+            http_code: Some(HttpCode::Ok),
+            response_time_ms: Some(100),
+            response_bytes,
+            ..CheckStatus::new(now + Duration::minutes(minute), DomainStatus::Up)
+        };
+        let mut domain = MonitoredDomain::new("https://example.com");
+        domain.check_history = vec![
+            check(0, Some(48_000)),
+            check(1, None),
+            check(2, Some(500)),
+            check(3, Some(47_000)),
+        ];
+        let table = HistoryTable::new(
+            &domain,
+            HistoryRetention::default(),
+            LatencySettings::default(),
+            Locale::default(),
+        );
+        let history = &domain.check_history;
+
+        assert_eq!(table.size_span(&history[0]), Span::raw("48.0 KB"));
+        assert_eq!(table.size_span(&history[1]), Span::raw(""));
+        assert_eq!(
+            table.size_span(&history[2]),
+            Span::styled("500 B", Style::default().yellow())
+        );
+        assert_eq!(table.size_span(&history[3]), Span::raw("47.0 KB"));
+    }
+
+    #[test]
+    fn following_selects_the_newest_check() {
+        let now = Utc::now();
+        let mut domain = MonitoredDomain::new("https://example.com");
+        let check = |minute: i64| CheckStatus {
+            http_code: Some(HttpCode::Ok),
+            response_time_ms: Some(100),
+            ..CheckStatus::new(now + Duration::minutes(minute), DomainStatus::Up)
+        };
+        domain.check_history = vec![check(0), check(1)];
+        let mut state = HistoryTableState {
+            following: true,
+            ..HistoryTableState::default()
+        };
+        let area = Rect::new(0, 0, 80, 12);
+        let render = |domain: &MonitoredDomain, state: &mut HistoryTableState| {
+            HistoryTable::new(
+                domain,
+                HistoryRetention::default(),
+                LatencySettings::default(),
+                Locale::default(),
+            )
+            .with_now(now + Duration::minutes(2))
+            .render(area, &mut Buffer::empty(area), state);
+        };
 
-        StatefulWidget::render(table, inner_area, buf, &mut state.table_state);
+        render(&domain, &mut state);
+        assert_eq!(state.table_state.selected(), Some(1));
+        domain.check_history.push(check(2));
+        render(&domain, &mut state);
+        assert_eq!(state.table_state.selected(), Some(2));
     }
 }
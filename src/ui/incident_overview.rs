@@ -0,0 +1,180 @@
+use crate::app::AppEvent;
+use crate::configuration::Settings;
+use crate::incident::{Incident, format_duration};
+use crate::monitor::MonitorHandle;
+use crate::ui::domains::{MonitoredDomain, open_runbook};
+use crate::ui::table_view::in_environment;
+use chrono::Utc;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, Cell, Row, StatefulWidget, Table, TableState, Widget},
+};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+// The incidents of every domain, ongoing ones first and then newest first.
+// Enter opens the domain's own incidents, where notes are written.
+#[derive(Debug)]
+pub struct IncidentOverviewScreen {
+    incidents: Vec<(Uuid, Arc<str>, Incident)>,
+    table_state: TableState,
+    settings: Settings,
+    monitor: MonitorHandle,
+    // Only this environment's incidents are listed when set.
+    environment: Option<String>,
+    event_sender: mpsc::UnboundedSender<AppEvent>,
+}
+
+impl IncidentOverviewScreen {
+    pub fn new(
+        settings: Settings,
+        monitor: &MonitorHandle,
+        environment: Option<String>,
+        event_sender: mpsc::UnboundedSender<AppEvent>,
+    ) -> Self {
+        let mut screen = IncidentOverviewScreen {
+            incidents: Vec::new(),
+            table_state: TableState::default(),
+            settings,
+            monitor: monitor.clone(),
+            environment,
+            event_sender,
+        };
+        screen.refresh();
+        screen
+    }
+
+    fn refresh(&mut self) {
+        let domains = self.monitor.domains.lock().unwrap();
+        self.incidents = domains
+            .iter()
+            .filter(|d| in_environment(self.environment.as_deref(), d.environment.as_deref()))
+            .flat_map(|d| {
+                d.incidents()
+                    .into_iter()
+                    .map(|i| (d.id, d.name().into(), i))
+            })
+            .collect();
+        self.incidents.sort_by(|(_, _, a), (_, _, b)| {
+            a.is_closed()
+                .cmp(&b.is_closed())
+                .then(b.started_at.cmp(&a.started_at))
+        });
+        if self.incidents.is_empty() {
+            self.table_state.select(None);
+        } else if self
+            .table_state
+            .selected()
+            .is_none_or(|i| i >= self.incidents.len())
+        {
+            self.table_state.select(Some(0));
+        }
+    }
+
+    pub fn handle_app_event(&mut self, event: &AppEvent) {
+        if matches!(
+            event,
+            AppEvent::CheckCompleted { .. }
+                | AppEvent::DomainAdded(_)
+                | AppEvent::DomainUpdated(_)
+                | AppEvent::DomainRemoved(_)
+                | AppEvent::DomainsReloaded(_)
+        ) {
+            self.refresh();
+        }
+    }
+
+    // The domain of the selected incident, as it is now.
+    fn selected_domain(&self) -> Option<MonitoredDomain> {
+        let (domain_id, _, _) = self.incidents.get(self.table_state.selected()?)?;
+        let domains = self.monitor.domains.lock().unwrap();
+        domains.iter().find(|d| d.id == *domain_id).cloned()
+    }
+
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => self.table_state.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.table_state.select_next(),
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                if let Some(domain) = self.selected_domain() {
+                    open_runbook(&domain, &self.event_sender);
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(domain) = self.selected_domain()
+                    && let Err(e) = self
+                        .event_sender
+                        .send(AppEvent::SwitchToIncidentsScreen(domain))
+                {
+                    tracing::error!("Error sending event: {}", e);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Widget for &mut IncidentOverviewScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let now = Utc::now();
+        let locale = &self.settings.display.locale;
+
+        let block = Block::bordered()
+            .title(Line::from(" Incidents ").centered())
+            .title_bottom(
+                Line::from(" Esc: Go Back | Up/Down: Navigate | Enter: Open Domain | R: Runbook ")
+                    .centered(),
+            );
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        if self.incidents.is_empty() {
+            Line::from(" No incidents in the stored history.").render(inner_area, buf);
+            return;
+        }
+
+        let header = Row::new(["Domain", "Started", "Duration", "Status", "First Error"])
+            .style(Style::default().bg(Color::Yellow).fg(Color::Black).bold())
+            .bottom_margin(1);
+        let rows: Vec<Row> = self
+            .incidents
+            .iter()
+            .map(|(_, url, incident)| {
+                let status = if incident.is_closed() {
+                    Cell::from("RESOLVED".green())
+                } else {
+                    Cell::from("ONGOING".red().bold())
+                };
+                Row::new(vec![
+                    Cell::from(url.to_string()),
+                    Cell::from(locale.timestamp(incident.started_at)),
+                    Cell::from(format_duration(incident.duration(now))),
+                    status,
+                    Cell::from(incident.first_error.as_deref().unwrap_or("-").to_string()),
+                ])
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Percentage(30),
+                Constraint::Length(20),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Min(10),
+            ],
+        )
+        .header(header)
+        .column_spacing(2)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("-> ");
+        StatefulWidget::render(table, inner_area, buf, &mut self.table_state);
+    }
+}
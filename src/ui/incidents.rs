@@ -0,0 +1,270 @@
+use crate::app::AppEvent;
+use crate::configuration::Settings;
+use crate::incident::{Incident, format_duration};
+use crate::monitor::MonitorHandle;
+use crate::ui::domains::{MonitoredDomain, open_runbook};
+use crate::ui::input::textarea_input;
+use crate::ui::popup::Popup;
+use chrono::{DateTime, Utc};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::Line,
+    widgets::{
+        Block, Cell, Clear, Paragraph, Row, StatefulWidget, Table, TableState, Widget, Wrap,
+    },
+};
+use tokio::sync::mpsc;
+
+// The incidents of one domain, newest first, where closed ones can be given a
+// postmortem note.
+#[derive(Debug)]
+pub struct IncidentsScreen {
+    domain: MonitoredDomain,
+    incidents: Vec<Incident>,
+    table_state: TableState,
+    // The note being written and the incident it's for.
+    editing: Option<(Popup<'static>, DateTime<Utc>)>,
+    settings: Settings,
+    monitor: MonitorHandle,
+    event_sender: mpsc::UnboundedSender<AppEvent>,
+}
+
+impl IncidentsScreen {
+    pub fn new(
+        domain: MonitoredDomain,
+        settings: Settings,
+        monitor: &MonitorHandle,
+        event_sender: mpsc::UnboundedSender<AppEvent>,
+    ) -> Self {
+        let mut screen = IncidentsScreen {
+            domain,
+            incidents: Vec::new(),
+            table_state: TableState::default(),
+            editing: None,
+            settings,
+            monitor: monitor.clone(),
+            event_sender,
+        };
+        screen.refresh_incidents();
+        screen
+    }
+
+    pub fn url(&self) -> &str {
+        &self.domain.url
+    }
+
+    fn refresh_incidents(&mut self) {
+        self.incidents = self.domain.incidents();
+        self.incidents.reverse();
+        let selected = self.table_state.selected();
+        if self.incidents.is_empty() {
+            self.table_state.select(None);
+        } else if selected.is_none_or(|i| i >= self.incidents.len()) {
+            self.table_state.select(Some(0));
+        }
+    }
+
+    // Returns false once the domain is gone, so the app can close the screen.
+    pub fn handle_app_event(&mut self, event: &AppEvent) -> bool {
+        match event {
+            AppEvent::CheckCompleted { domain_id, check }
+                if *domain_id == self.domain.id
+                    && !self
+                        .domain
+                        .check_history
+                        .iter()
+                        .any(|c| c.timestamp == check.timestamp) =>
+            {
+                let retention = self
+                    .domain
+                    .history_retention(self.settings.monitor.history_retention());
+                self.domain.push_check(check.clone());
+                self.domain.compact_history(retention, Utc::now());
+            }
+            AppEvent::DomainUpdated(domain) if domain.id == self.domain.id => {
+                self.domain = domain.clone();
+            }
+            AppEvent::DomainRemoved(domain_id) if *domain_id == self.domain.id => return false,
+            AppEvent::DomainsReloaded(domains) => {
+                match domains.iter().find(|d| d.id == self.domain.id) {
+                    Some(domain) => self.domain = domain.clone(),
+                    None => return false,
+                }
+            }
+            _ => return true,
+        }
+        self.refresh_incidents();
+        true
+    }
+
+    fn selected(&self) -> Option<&Incident> {
+        self.table_state
+            .selected()
+            .and_then(|i| self.incidents.get(i))
+    }
+
+    fn save_note(&mut self, incident_started_at: DateTime<Utc>, note: &str) {
+        if let Err(e) = self
+            .monitor
+            .set_incident_note(self.domain.id, incident_started_at, note)
+        {
+            tracing::error!("Error saving incident note: {}", e);
+            if let Err(e) = self.event_sender.send(AppEvent::Error(format!(
+                "Error saving incident note: {}",
+                e
+            ))) {
+                tracing::error!("Error sending event: {}", e);
+            }
+        }
+    }
+
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> bool {
+        if let Some((popup, incident_started_at)) = &mut self.editing {
+            match key_event.code {
+                KeyCode::Esc => self.editing = None,
+                KeyCode::Char('s') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let (note, started_at) =
+                        (popup.get_input_text().join("\n"), *incident_started_at);
+                    self.editing = None;
+                    self.save_note(started_at, &note);
+                }
+                KeyCode::Enter => {
+                    popup.textarea_mut().insert_newline();
+                }
+                _ => {
+                    if let Some(input) = textarea_input(key_event) {
+                        popup.textarea_mut().input(input);
+                    }
+                }
+            }
+            // Keep keys from leaking to the table while the note is open.
+            return true;
+        }
+
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.table_state.select_previous();
+                true
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.table_state.select_next();
+                true
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') => {
+                // Only closed incidents get a postmortem.
+                if let Some(incident) = self.selected().filter(|i| i.is_closed()) {
+                    let started_at = incident.started_at;
+                    let note = self
+                        .domain
+                        .incident_note(started_at)
+                        .map(|n| n.note.clone());
+                    let mut popup = Popup::new(Line::from(" Postmortem note "), note);
+                    popup.set_input_title("Note");
+                    popup.set_footer(Line::from(" Enter: New Line - Ctrl+S: Save - Esc: Cancel "));
+                    self.editing = Some((popup, started_at));
+                }
+                true
+            }
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                open_runbook(&self.domain, &self.event_sender);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Widget for &mut IncidentsScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let now = Utc::now();
+        let locale = &self.settings.display.locale;
+
+        let block = Block::bordered()
+            .title(Line::from(format!(" Incidents: {} ", self.domain.url)).centered())
+            .title_bottom(
+                Line::from(" Esc: Go Back | Up/Down: Navigate | N: Postmortem Note | R: Runbook ")
+                    .centered(),
+            );
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        let [table_area, note_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(6)]).areas(inner_area);
+
+        let header = Row::new(["Started", "Duration", "Status", "Failed Checks", "Note"])
+            .style(Style::default().bg(Color::Yellow).fg(Color::Black).bold())
+            .bottom_margin(1);
+        let rows: Vec<Row> = self
+            .incidents
+            .iter()
+            .map(|incident| {
+                let status = if incident.is_closed() {
+                    Cell::from("RESOLVED".green())
+                } else {
+                    Cell::from("ONGOING".red().bold())
+                };
+                let has_note = self.domain.incident_note(incident.started_at).is_some();
+                Row::new(vec![
+                    Cell::from(locale.timestamp(incident.started_at)),
+                    Cell::from(format_duration(incident.duration(now))),
+                    status,
+                    Cell::from(locale.integer(incident.failed_checks as u64)),
+                    Cell::from(if has_note { "yes" } else { "" }),
+                ])
+            })
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(20),
+                Constraint::Length(10),
+                Constraint::Length(10),
+                Constraint::Length(14),
+                Constraint::Min(4),
+            ],
+        )
+        .header(header)
+        .column_spacing(2)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("-> ");
+        StatefulWidget::render(table, table_area, buf, &mut self.table_state);
+
+        let details = match self.selected() {
+            None => vec![Line::from(" No incidents in the stored history.")],
+            Some(incident) => {
+                let mut lines = vec![Line::from(format!(
+                    " First error: {}",
+                    incident.first_error.as_deref().unwrap_or("-")
+                ))];
+                match self.domain.incident_note(incident.started_at) {
+                    Some(note) => {
+                        lines.extend(note.note.lines().map(|l| Line::from(format!(" {}", l))))
+                    }
+                    None if incident.is_closed() => lines.push(
+                        Line::from(" No postmortem note yet, press N to add one.").dark_gray(),
+                    ),
+                    None => {}
+                }
+                lines
+            }
+        };
+        Paragraph::new(details)
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::bordered()
+                    .title(" Details ")
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            )
+            .render(note_area, buf);
+
+        if let Some((popup, _)) = &self.editing {
+            let popup_area = Popup::centered_rect(70, 40, area);
+            Clear.render(popup_area, buf);
+            popup.clone().render(popup_area, buf);
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui_textarea::{Input, Key};
+
+// tui-textarea is built against an older crossterm than we use, so its own
+// `From<KeyEvent>` impl can't be used and keys are mapped by hand instead.
+pub fn textarea_input(key_event: KeyEvent) -> Option<Input> {
+    let key = match key_event.code {
+        KeyCode::Char(c) => {
+            return Some(Input {
+                key: Key::Char(c),
+                ctrl: key_event.modifiers.contains(KeyModifiers::CONTROL),
+                alt: key_event.modifiers.contains(KeyModifiers::ALT),
+                shift: key_event.modifiers.contains(KeyModifiers::SHIFT),
+            });
+        }
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::Tab => Key::Tab,
+        _ => return None,
+    };
+
+    Some(Input {
+        key,
+        ctrl: false,
+        alt: false,
+        shift: false,
+    })
+}
@@ -0,0 +1,127 @@
+use crate::ui::locale::Locale;
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::Span,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub enum LatencyUnit {
+    #[default]
+    #[serde(rename = "ms")]
+    Milliseconds,
+    #[serde(rename = "s")]
+    Seconds,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LatencyLevel {
+    Good,
+    Warn,
+    Slow,
+}
+
+// How response times are shown and colored. Response times below
+// good_below_ms are green, below warn_below_ms yellow and anything slower red.
+// Monitors can override both thresholds, see MonitoredDomain::latency_settings.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct LatencySettings {
+    pub unit: LatencyUnit,
+    pub good_below_ms: u64,
+    pub warn_below_ms: u64,
+    // Points averaged by the smoothed line of the web dashboard's latency
+    // chart, below 2 there's no smoothed line.
+    pub smoothing_window: usize,
+}
+
+impl Default for LatencySettings {
+    fn default() -> Self {
+        LatencySettings {
+            unit: LatencyUnit::Milliseconds,
+            good_below_ms: 200,
+            warn_below_ms: 1000,
+            smoothing_window: 5,
+        }
+    }
+}
+
+impl LatencySettings {
+    // A response time can't be good past the point where it's already slow.
+    pub fn validate(&self) -> Result<(), String> {
+        validate_thresholds(self.good_below_ms, self.warn_below_ms)
+    }
+
+    pub fn level(&self, response_time_ms: u64) -> LatencyLevel {
+        if response_time_ms < self.good_below_ms {
+            LatencyLevel::Good
+        } else if response_time_ms < self.warn_below_ms {
+            LatencyLevel::Warn
+        } else {
+            LatencyLevel::Slow
+        }
+    }
+
+    pub fn format(&self, response_time_ms: u64, locale: &Locale) -> String {
+        match self.unit {
+            LatencyUnit::Milliseconds => format!("{}ms", locale.integer(response_time_ms)),
+            LatencyUnit::Seconds => {
+                format!("{}s", locale.decimal(response_time_ms as f64 / 1000.0, 2))
+            }
+        }
+    }
+
+    pub fn color(&self, response_time_ms: u64) -> Color {
+        match self.level(response_time_ms) {
+            LatencyLevel::Good => Color::Green,
+            LatencyLevel::Warn => Color::Yellow,
+            LatencyLevel::Slow => Color::Red,
+        }
+    }
+
+    // A response time cell for the domain and history tables.
+    pub fn span(&self, response_time_ms: Option<u64>, locale: &Locale) -> Span<'static> {
+        match response_time_ms {
+            Some(ms) => Span::styled(self.format(ms, locale), Style::default().fg(self.color(ms))),
+            None => Span::styled("N/A", Style::default().gray()),
+        }
+    }
+}
+
+// Shared with the per-monitor overrides, see MonitoredDomain::validate.
+pub fn validate_thresholds(good_below_ms: u64, warn_below_ms: u64) -> Result<(), String> {
+    if good_below_ms >= warn_below_ms {
+        return Err(format!(
+            "Latency good threshold ({}ms) must be below the warn threshold ({}ms)",
+            good_below_ms, warn_below_ms
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_color_and_validate() {
+        let latency = LatencySettings::default();
+        assert!(latency.validate().is_ok());
+        assert_eq!(latency.level(199), LatencyLevel::Good);
+        assert_eq!(latency.level(200), LatencyLevel::Warn);
+        assert_eq!(latency.level(1000), LatencyLevel::Slow);
+
+        let equal = LatencySettings {
+            good_below_ms: 500,
+            warn_below_ms: 500,
+            ..latency
+        };
+        assert!(equal.validate().is_err());
+        let inverted = LatencySettings {
+            good_below_ms: 2000,
+            ..latency
+        };
+        assert!(inverted.validate().is_err());
+    }
+}
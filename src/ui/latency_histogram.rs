@@ -0,0 +1,204 @@
+use crate::rollup::{self, LATENCY_BUCKETS_MS, latency_bucket};
+use crate::ui::domains::MonitoredDomain;
+use crate::ui::latency::LatencySettings;
+use crate::ui::locale::Locale;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, Block, Widget},
+};
+
+// How far back the histogram looks. The hour and the day come from the check
+// history, the longer windows from the daily rollups.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum HistogramWindow {
+    Hour,
+    #[default]
+    Day,
+    Week,
+    All,
+}
+
+impl HistogramWindow {
+    pub fn next(self) -> Self {
+        match self {
+            HistogramWindow::Hour => HistogramWindow::Day,
+            HistogramWindow::Day => HistogramWindow::Week,
+            HistogramWindow::Week => HistogramWindow::All,
+            HistogramWindow::All => HistogramWindow::Hour,
+        }
+    }
+}
+
+// Response times of a domain's checks in the window, counted per bucket.
+#[derive(Debug)]
+pub struct LatencyHistogram<'a> {
+    domain: &'a MonitoredDomain,
+    window: HistogramWindow,
+    now: DateTime<Utc>,
+    latency: LatencySettings,
+    locale: Locale,
+}
+
+impl<'a> LatencyHistogram<'a> {
+    pub fn new(
+        domain: &'a MonitoredDomain,
+        window: HistogramWindow,
+        now: DateTime<Utc>,
+        latency: LatencySettings,
+        locale: Locale,
+    ) -> Self {
+        LatencyHistogram {
+            domain,
+            window,
+            now,
+            latency,
+            locale,
+        }
+    }
+
+    pub fn height() -> u16 {
+        9
+    }
+
+    fn label(&self) -> String {
+        match self.window {
+            HistogramWindow::Hour => "last hour".to_string(),
+            HistogramWindow::Day => "last 24h".to_string(),
+            HistogramWindow::Week => "last 7 days".to_string(),
+            HistogramWindow::All => match self.domain.rollups().first() {
+                Some(first) => format!("since {}", first.day),
+                None => "all history".to_string(),
+            },
+        }
+    }
+
+    fn counts(&self) -> [u64; LATENCY_BUCKETS_MS.len()] {
+        match self.window {
+            HistogramWindow::Hour => self.history_counts(self.now - Duration::hours(1)),
+            HistogramWindow::Day => self.history_counts(self.now - Duration::days(1)),
+            // Today and the six UTC days before it.
+            HistogramWindow::Week => {
+                self.rollup_counts((self.now - Duration::days(6)).date_naive())
+            }
+            HistogramWindow::All => self.rollup_counts(NaiveDate::MIN),
+        }
+    }
+
+    fn history_counts(&self, since: DateTime<Utc>) -> [u64; LATENCY_BUCKETS_MS.len()] {
+        let mut counts = [0; LATENCY_BUCKETS_MS.len()];
+        let in_window = self
+            .domain
+            .check_history
+            .iter()
+            .filter(|check| check.timestamp >= since);
+        for response_time_ms in in_window.filter_map(|check| check.response_time_ms) {
+            counts[latency_bucket(response_time_ms)] += 1;
+        }
+        counts
+    }
+
+    fn rollup_counts(&self, first_day: NaiveDate) -> [u64; LATENCY_BUCKETS_MS.len()] {
+        let total = rollup::total(&self.domain.rollups(), first_day, NaiveDate::MAX);
+        total.latency_buckets.map(u64::from)
+    }
+
+    fn bucket_label(&self, bucket: usize) -> String {
+        let format = |ms: u64| self.latency.format(ms, &self.locale);
+        match LATENCY_BUCKETS_MS.get(bucket + 1) {
+            Some(&to) => format!("{}–{}", format(LATENCY_BUCKETS_MS[bucket]), format(to)),
+            None => format!("≥{}", format(LATENCY_BUCKETS_MS[bucket])),
+        }
+    }
+}
+
+impl Widget for LatencyHistogram<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let counts = self.counts();
+        let total: u64 = counts.iter().sum();
+
+        let bars: Vec<Bar> = counts
+            .iter()
+            .enumerate()
+            .map(|(bucket, &count)| {
+                Bar::default()
+                    .value(count)
+                    .label(Line::from(self.bucket_label(bucket)))
+                    .style(Style::default().fg(self.latency.color(LATENCY_BUCKETS_MS[bucket])))
+            })
+            .collect();
+
+        let title = format!(
+            " Latency, {} ({} checks) - W: change window ",
+            self.label(),
+            total
+        );
+        // Wide enough for the labels, but never wider than the screen allows.
+        let inner_width = area.width.saturating_sub(2) as usize;
+        let bar_width = (inner_width / LATENCY_BUCKETS_MS.len())
+            .saturating_sub(1)
+            .clamp(1, 14) as u16;
+
+        BarChart::default()
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            )
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(bar_width)
+            .bar_gap(1)
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::domains::{CheckStatus, DomainStatus};
+    use chrono::TimeZone;
+
+    fn check(minutes_ago: i64, response_time_ms: Option<u64>, now: DateTime<Utc>) -> CheckStatus {
+        CheckStatus {
+            response_time_ms,
+            ..CheckStatus::new(now - Duration::minutes(minutes_ago), DomainStatus::Up)
+        }
+    }
+
+    #[test]
+    fn checks_are_bucketed_within_the_window() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 20, 12, 0, 0).unwrap();
+        let mut domain = MonitoredDomain::new("https://example.com");
+        for check in [
+            check(20 * 24 * 60, Some(15000), now),
+            check(90, Some(20000), now),
+            check(30, None, now),
+            check(20, Some(2500), now),
+            check(10, Some(100), now),
+            check(5, Some(50), now),
+        ] {
+            domain.push_check(check);
+        }
+        // Long gone from the history, but still in its day's rollup.
+        domain.check_history.remove(0);
+
+        let histogram = |window| {
+            LatencyHistogram::new(
+                &domain,
+                window,
+                now,
+                LatencySettings::default(),
+                Locale::default(),
+            )
+            .counts()
+        };
+
+        assert_eq!(histogram(HistogramWindow::Hour), [1, 1, 0, 1, 0, 0]);
+        assert_eq!(histogram(HistogramWindow::Day), [1, 1, 0, 1, 0, 1]);
+        assert_eq!(histogram(HistogramWindow::Week), [1, 1, 0, 1, 0, 1]);
+        assert_eq!(histogram(HistogramWindow::All), [1, 1, 0, 1, 0, 2]);
+    }
+}
@@ -0,0 +1,174 @@
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Deserializer};
+
+// How numbers and timestamps are written, picked from a locale name like
+// "de-DE" or "fr_FR.UTF-8". Only the language (and a few regions) matter;
+// anything unknown gets the ISO-style defaults upquack always used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    decimal_separator: char,
+    group_separator: Option<char>,
+    date_time_format: &'static str,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            decimal_separator: '.',
+            group_separator: None,
+            date_time_format: "%Y-%m-%d %H:%M:%S",
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Locale {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(if name.eq_ignore_ascii_case("system") {
+            Locale::system()
+        } else {
+            Locale::parse(&name)
+        })
+    }
+}
+
+impl Locale {
+    pub fn parse(name: &str) -> Self {
+        // "de_DE.UTF-8@euro" -> ("de", "DE")
+        let name = name.split(['.', '@']).next().unwrap_or_default();
+        let mut parts = name.split(['-', '_']);
+        let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let region = parts.next().unwrap_or_default().to_ascii_uppercase();
+
+        let (decimal_separator, group_separator, date_time_format) =
+            match (language.as_str(), region.as_str()) {
+                ("en", "US") => ('.', Some(','), "%m/%d/%Y %H:%M:%S"),
+                ("en", "GB" | "IE" | "AU" | "NZ" | "IN") => ('.', Some(','), "%d/%m/%Y %H:%M:%S"),
+                ("de", "CH") => ('.', Some('\''), "%d.%m.%Y %H:%M:%S"),
+                ("de", _) => (',', Some('.'), "%d.%m.%Y %H:%M:%S"),
+                ("fr", _) => (',', Some(' '), "%d/%m/%Y %H:%M:%S"),
+                ("es" | "it" | "pt" | "id" | "tr" | "el", _) => {
+                    (',', Some('.'), "%d/%m/%Y %H:%M:%S")
+                }
+                ("nl" | "da", _) => (',', Some('.'), "%d-%m-%Y %H:%M:%S"),
+                ("ru" | "uk" | "pl" | "cs" | "sk" | "fi" | "nb" | "no", _) => {
+                    (',', Some(' '), "%d.%m.%Y %H:%M:%S")
+                }
+                ("sv" | "lt", _) => (',', Some(' '), "%Y-%m-%d %H:%M:%S"),
+                ("ja" | "zh" | "ko", _) => ('.', Some(','), "%Y/%m/%d %H:%M:%S"),
+                _ => return Locale::default(),
+            };
+
+        Locale {
+            decimal_separator,
+            group_separator,
+            date_time_format,
+        }
+    }
+
+    // The locale numbers are formatted in by the environment, as set by
+    // LC_ALL, LC_NUMERIC or LANG (first one set wins).
+    pub fn system() -> Self {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .map_or_else(Locale::default, |value| Locale::parse(&value))
+    }
+
+    pub fn integer(&self, value: u64) -> String {
+        self.group(&value.to_string())
+    }
+
+    pub fn decimal(&self, value: f64, decimals: usize) -> String {
+        let formatted = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+        let sign = if value < 0.0 && formatted.chars().any(|c| ('1'..='9').contains(&c)) {
+            "-"
+        } else {
+            ""
+        };
+
+        if fraction.is_empty() {
+            format!("{}{}", sign, self.group(integer))
+        } else {
+            format!(
+                "{}{}{}{}",
+                sign,
+                self.group(integer),
+                self.decimal_separator,
+                fraction
+            )
+        }
+    }
+
+    // In B up to a KB, then KB or MB with one decimal (1 KB is 1,000 B).
+    pub fn bytes(&self, value: u64) -> String {
+        match value {
+            0..1_000 => format!("{} B", value),
+            1_000..1_000_000 => format!("{} KB", self.decimal(value as f64 / 1e3, 1)),
+            _ => format!("{} MB", self.decimal(value as f64 / 1e6, 1)),
+        }
+    }
+
+    pub fn percentage(&self, value: f64) -> String {
+        format!("{}%", self.decimal(value, 1))
+    }
+
+    // In the local time zone.
+    pub fn timestamp(&self, timestamp: DateTime<Utc>) -> String {
+        timestamp
+            .with_timezone(&Local)
+            .format(self.date_time_format)
+            .to_string()
+    }
+
+    fn group(&self, digits: &str) -> String {
+        let Some(separator) = self.group_separator else {
+            return digits.to_string();
+        };
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_follow_the_locale() {
+        let english = Locale::parse("en-US");
+        let german = Locale::parse("de_DE.UTF-8");
+        assert_eq!(english.decimal(1234.5, 1), "1,234.5");
+        assert_eq!(german.decimal(1234.5, 1), "1.234,5");
+        assert_eq!(Locale::parse("fr-FR").integer(1234567), "1 234 567");
+        assert_eq!(german.percentage(99.5), "99,5%");
+        assert_eq!(english.decimal(-0.01, 1), "0.0");
+        assert_eq!(english.bytes(512), "512 B");
+        assert_eq!(german.bytes(14_250), "14,2 KB");
+        assert_eq!(english.bytes(3_100_000), "3.1 MB");
+    }
+
+    #[test]
+    fn unknown_locales_keep_the_defaults() {
+        assert_eq!(Locale::parse("C"), Locale::default());
+        assert_eq!(Locale::parse("xx-YY"), Locale::default());
+        assert_eq!(Locale::default().integer(1234), "1234");
+    }
+
+    #[test]
+    fn timestamps_follow_the_locale() {
+        let timestamp = "2025-03-01T12:00:00Z".parse().expect("Valid timestamp");
+        let formatted = Locale::parse("de-DE").timestamp(timestamp);
+        // The day and hour depend on the machine's time zone.
+        assert_eq!(&formatted[2..3], ".");
+        assert_eq!(&formatted[5..11], ".2025 ");
+    }
+}
@@ -0,0 +1,100 @@
+use crate::logging::{LogHandle, LogLine};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Widget},
+};
+use tracing::Level;
+
+// How far PageUp/PageDown scroll.
+const PAGE_LINES: usize = 20;
+
+// The recent log lines, newest at the bottom. Follows new lines until
+// scrolled up.
+#[derive(Debug)]
+pub struct LogScreen {
+    logs: LogHandle,
+    // Lines hidden below the bottom of the screen, 0 while following.
+    scroll: usize,
+}
+
+impl LogScreen {
+    pub fn new(logs: LogHandle) -> Self {
+        LogScreen { logs, scroll: 0 }
+    }
+
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> bool {
+        let max_scroll = self.logs.len().saturating_sub(1);
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = (self.scroll + 1).min(max_scroll),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::PageUp => self.scroll = (self.scroll + PAGE_LINES).min(max_scroll),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(PAGE_LINES),
+            KeyCode::Home => self.scroll = max_scroll,
+            KeyCode::End => self.scroll = 0,
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn log_line(line: &LogLine) -> Line<'static> {
+    let level_color = match line.level {
+        Level::ERROR => Color::Red,
+        Level::WARN => Color::Yellow,
+        Level::INFO => Color::Green,
+        Level::DEBUG => Color::Blue,
+        Level::TRACE => Color::DarkGray,
+    };
+    Line::from(vec![
+        Span::from(line.timestamp.format("%H:%M:%S ").to_string()).dark_gray(),
+        Span::from(format!("{:<5} ", line.level)).fg(level_color),
+        Span::from(format!("{}: ", line.target)).dark_gray(),
+        Span::from(line.message.clone()),
+    ])
+}
+
+impl Widget for &mut LogScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let instructions = Line::from(vec![
+            " Esc: Return to Menu - ".into(),
+            "Up/Down/PgUp/PgDn: Scroll - ".into(),
+            "End: Follow - ".into(),
+            "Q: Quit ".into(),
+        ]);
+        let following = if self.scroll == 0 {
+            "following".to_string()
+        } else {
+            format!("{} newer lines below", self.scroll)
+        };
+        let title = format!(
+            "Logs (level: {}, {}) ",
+            self.logs.level().label(),
+            following
+        );
+        let block = Block::bordered()
+            .title_top(Line::from(title).left_aligned())
+            .title_bottom(instructions.centered())
+            .magenta();
+
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        let lines: Vec<Line> = self
+            .logs
+            .tail(inner_area.height as usize, self.scroll)
+            .iter()
+            .map(log_line)
+            .collect();
+        if lines.is_empty() {
+            Line::from(" Nothing logged yet ")
+                .dark_gray()
+                .render(inner_area, buf);
+            return;
+        }
+        Paragraph::new(lines).white().render(inner_area, buf);
+    }
+}
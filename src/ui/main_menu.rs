@@ -0,0 +1,157 @@
+use crate::app::AppEvent;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::widgets::ListState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuEntry {
+    Domains,
+    Dashboard,
+    Incidents,
+    Logs,
+    Settings,
+    Profiles,
+    Help,
+    Quit,
+}
+
+impl MenuEntry {
+    pub const ALL: [MenuEntry; 8] = [
+        MenuEntry::Domains,
+        MenuEntry::Dashboard,
+        MenuEntry::Incidents,
+        MenuEntry::Logs,
+        MenuEntry::Settings,
+        MenuEntry::Profiles,
+        MenuEntry::Help,
+        MenuEntry::Quit,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MenuEntry::Domains => "Monitored URLs",
+            MenuEntry::Dashboard => "Dashboard",
+            MenuEntry::Incidents => "Incidents",
+            MenuEntry::Logs => "Logs",
+            MenuEntry::Settings => "Settings",
+            MenuEntry::Profiles => "Profiles",
+            MenuEntry::Help => "Help",
+            MenuEntry::Quit => "Quit",
+        }
+    }
+
+    // Opens the entry straight from the menu, without selecting it first.
+    pub fn shortcut(self) -> char {
+        match self {
+            MenuEntry::Domains => 'E',
+            MenuEntry::Dashboard => 'D',
+            MenuEntry::Incidents => 'I',
+            MenuEntry::Logs => 'L',
+            MenuEntry::Settings => 'S',
+            MenuEntry::Profiles => 'P',
+            MenuEntry::Help => '?',
+            MenuEntry::Quit => 'Q',
+        }
+    }
+
+    fn from_shortcut(c: char) -> Option<MenuEntry> {
+        MenuEntry::ALL
+            .into_iter()
+            .find(|entry| entry.shortcut() == c.to_ascii_uppercase())
+    }
+
+    // The screen the entry opens, none for Quit.
+    pub fn event(self) -> Option<AppEvent> {
+        Some(match self {
+            MenuEntry::Domains => AppEvent::SwitchToDomainsScreen,
+            MenuEntry::Dashboard => AppEvent::SwitchToDashboardScreen,
+            MenuEntry::Incidents => AppEvent::SwitchToIncidentOverviewScreen,
+            MenuEntry::Logs => AppEvent::SwitchToLogsScreen,
+            MenuEntry::Settings => AppEvent::SwitchToSettingsScreen,
+            MenuEntry::Profiles => AppEvent::SwitchToProfilesScreen,
+            MenuEntry::Help => AppEvent::SwitchToHelpScreen,
+            MenuEntry::Quit => return None,
+        })
+    }
+}
+
+// Which main menu entry is selected. The app draws the menu around it.
+#[derive(Debug)]
+pub struct MainMenu {
+    pub list_state: ListState,
+}
+
+impl Default for MainMenu {
+    fn default() -> Self {
+        MainMenu {
+            list_state: ListState::default().with_selected(Some(0)),
+        }
+    }
+}
+
+impl MainMenu {
+    pub fn selected(&self) -> MenuEntry {
+        let index = self.list_state.selected().unwrap_or(0);
+        MenuEntry::ALL[index.min(MenuEntry::ALL.len() - 1)]
+    }
+
+    // The entry to open, if the key picked one. Up/Down wrap around.
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> Option<MenuEntry> {
+        let last = MenuEntry::ALL.len() - 1;
+        let selected = self.list_state.selected().unwrap_or(0);
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                let previous = if selected == 0 { last } else { selected - 1 };
+                self.list_state.select(Some(previous));
+                None
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let next = if selected >= last { 0 } else { selected + 1 };
+                self.list_state.select(Some(next));
+                None
+            }
+            KeyCode::Enter => Some(self.selected()),
+            // Ctrl+P pauses, it doesn't open the profiles.
+            KeyCode::Char(c) if !key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                let entry = MenuEntry::from_shortcut(c)?;
+                self.list_state
+                    .select(MenuEntry::ALL.iter().position(|e| *e == entry));
+                Some(entry)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn navigates_and_opens_entries() {
+        let mut menu = MainMenu::default();
+        assert_eq!(menu.selected(), MenuEntry::Domains);
+
+        // Up from the top wraps to Quit.
+        assert_eq!(menu.handle_key_event(key(KeyCode::Up)), None);
+        assert_eq!(menu.selected(), MenuEntry::Quit);
+        menu.handle_key_event(key(KeyCode::Down));
+        menu.handle_key_event(key(KeyCode::Down));
+        assert_eq!(
+            menu.handle_key_event(key(KeyCode::Enter)),
+            Some(MenuEntry::Dashboard)
+        );
+
+        assert_eq!(
+            menu.handle_key_event(key(KeyCode::Char('l'))),
+            Some(MenuEntry::Logs)
+        );
+        assert_eq!(menu.selected(), MenuEntry::Logs);
+        assert_eq!(menu.handle_key_event(key(KeyCode::Char('x'))), None);
+        let ctrl_p = KeyEvent::new(KeyCode::Char('p'), KeyModifiers::CONTROL);
+        assert_eq!(menu.handle_key_event(ctrl_p), None);
+    }
+}
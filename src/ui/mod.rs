@@ -1,4 +1,25 @@
-mod domain_table;
+mod check_timeline;
+pub mod dashboard;
+mod diff_preview;
+pub mod domain_table;
 pub mod domains;
-mod history_table;
+mod form;
+pub mod help;
+pub mod history;
+pub mod history_table;
+pub mod incident_overview;
+pub mod incidents;
+mod input;
+pub mod latency;
+mod latency_histogram;
+pub mod locale;
+pub mod logs;
+pub mod main_menu;
 mod popup;
+pub mod profiles;
+pub mod progress;
+pub mod settings;
+pub mod soak;
+pub mod table_view;
+mod tags;
+mod week_comparison;
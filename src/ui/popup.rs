@@ -1,3 +1,4 @@
+use ratatui::layout::Flex;
 use ratatui::prelude::{Constraint, Direction, Layout, Rect};
 use ratatui::{
     style::{Color, Style},
@@ -9,6 +10,7 @@ use tui_textarea::TextArea;
 #[derive(Debug)]
 pub struct Popup<'a> {
     title: Line<'a>,
+    footer: Option<Line<'a>>,
     textarea: TextArea<'a>,
     border_style: Style,
     style: Style,
@@ -18,6 +20,7 @@ pub struct Popup<'a> {
 impl<'a> Clone for Popup<'a> {
     fn clone(&self) -> Self {
         let title_clone = self.title.clone();
+        let footer_clone = self.footer.clone();
         let border_style_clone = self.border_style;
         let style_clone = self.style;
         let title_style_clone = self.title_style;
@@ -34,6 +37,7 @@ impl<'a> Clone for Popup<'a> {
 
         Self {
             title: title_clone,
+            footer: footer_clone,
             textarea: cloned_textarea,
             border_style: border_style_clone,
             style: style_clone,
@@ -57,6 +61,7 @@ impl<'a> Popup<'a> {
 
         Self {
             title,
+            footer: None,
             textarea,
             border_style: Style::default().fg(Color::Gray),
             style: Style::default().bg(Color::DarkGray),
@@ -74,6 +79,20 @@ impl<'a> Popup<'a> {
         self.title = title;
     }
 
+    // The label above the input, "Enter URL" unless changed.
+    pub fn set_input_title(&mut self, title: &'a str) {
+        self.textarea.set_block(
+            Block::bordered()
+                .borders(Borders::ALL)
+                .title(title)
+                .style(Style::default().fg(Color::LightCyan)),
+        );
+    }
+
+    pub fn set_footer(&mut self, footer: Line<'a>) {
+        self.footer = Some(footer);
+    }
+
     pub fn get_input_text(&self) -> Vec<String> {
         self.textarea
             .lines()
@@ -101,6 +120,15 @@ impl<'a> Popup<'a> {
             ])
             .split(popup_layout[1])[1]
     }
+
+    // Like `centered_rect`, but with a fixed height for content that needs it.
+    pub fn centered_rect_with_height(percent_x: u16, height: u16, r: Rect) -> Rect {
+        let [popup_row] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(r);
+
+        Self::centered_rect(percent_x, 100, popup_row)
+    }
 }
 
 impl<'a> Widget for Popup<'a> {
@@ -110,13 +138,17 @@ impl<'a> Widget for Popup<'a> {
     {
         Clear.render(area, buf);
 
-        let block = Block::bordered()
+        let mut block = Block::bordered()
             .title(self.title)
             .title_style(self.title_style)
             .borders(Borders::ALL)
             .border_style(self.border_style)
             .style(self.style);
 
+        if let Some(footer) = self.footer {
+            block = block.title_bottom(footer.centered());
+        }
+
         let inner_area = block.inner(area);
 
         block.render(area, buf);
@@ -0,0 +1,109 @@
+use crate::app::AppEvent;
+use crate::profile::{Profile, list_profiles};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListItem, ListState, StatefulWidget, Widget},
+};
+use tokio::sync::mpsc;
+
+#[derive(Debug)]
+pub struct ProfileScreen {
+    profiles: Vec<Profile>,
+    current: Profile,
+    list_state: ListState,
+    event_sender: mpsc::UnboundedSender<AppEvent>,
+}
+
+impl ProfileScreen {
+    pub fn new(current: &Profile, event_sender: mpsc::UnboundedSender<AppEvent>) -> Self {
+        let mut profiles = list_profiles();
+        // A profile given with --profile that hasn't saved anything yet.
+        if !profiles.contains(current) {
+            profiles.push(current.clone());
+        }
+
+        let mut list_state = ListState::default();
+        list_state.select(profiles.iter().position(|p| p == current));
+
+        ProfileScreen {
+            profiles,
+            current: current.clone(),
+            list_state,
+            event_sender,
+        }
+    }
+
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.list_state.select_previous();
+                true
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.list_state.select_next();
+                true
+            }
+            KeyCode::Enter => {
+                let selected = self
+                    .list_state
+                    .selected()
+                    .and_then(|i| self.profiles.get(i));
+                if let Some(profile) = selected.filter(|p| **p != self.current)
+                    && let Err(e) = self
+                        .event_sender
+                        .send(AppEvent::ProfileSelected(profile.clone()))
+                {
+                    tracing::error!("Error sending event: {}", e);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Widget for &mut ProfileScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let instructions = Line::from(vec![
+            " Esc: Return to Menu - ".into(),
+            "Enter: Switch - ".into(),
+            "Q: Quit - ".into(),
+            "Up/Down: Navigation ".into(),
+        ]);
+        let main_block = Block::bordered()
+            .title_top(Line::from("Profiles").left_aligned())
+            .title_bottom(instructions.centered())
+            .magenta();
+
+        let inner_area = main_block.inner(area);
+        main_block.render(area, buf);
+
+        let [list_area, hint_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner_area);
+
+        let items: Vec<ListItem> = self
+            .profiles
+            .iter()
+            .map(|profile| {
+                if *profile == self.current {
+                    ListItem::new(format!("● {} (active)", profile.name())).green()
+                } else {
+                    ListItem::new(format!("  {}", profile.name())).white()
+                }
+            })
+            .collect();
+
+        let list = List::new(items)
+            .highlight_style(Style::default().bg(Color::DarkGray))
+            .highlight_symbol("> ");
+        StatefulWidget::render(list, list_area, buf, &mut self.list_state);
+
+        Line::from(" Start upquack with --profile <name> to create a new profile ")
+            .dark_gray()
+            .render(hint_area, buf);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::monitor::MonitorEvent;
+use crate::ui::domains::MonitoredDomain;
+use chrono::{DateTime, Utc};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Gauge, Widget},
+};
+use std::collections::HashSet;
+use std::time::Duration;
+use uuid::Uuid;
+
+static SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+// How far along a slow operation is. Sent over the app's event channel by
+// whatever runs the operation; the popup closes once done reaches total.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub operation: String,
+    pub done: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    pub fn is_finished(&self) -> bool {
+        self.done >= self.total
+    }
+}
+
+// Follows the first round of checks after the monitor (re)starts, so the UI
+// can tell when every domain has a fresh result.
+#[derive(Debug)]
+pub struct InitialChecks {
+    pending: HashSet<Uuid>,
+    total: usize,
+}
+
+impl InitialChecks {
+    // Domains checked since the monitor started may have finished before we
+    // started listening, those count as done already.
+    pub fn new(domains: &[MonitoredDomain], started_at: DateTime<Utc>) -> Self {
+        let pending = domains
+            .iter()
+            .filter(|d| {
+                d.check_history
+                    .last()
+                    .is_none_or(|c| c.timestamp < started_at)
+            })
+            .map(|d| d.id)
+            .collect();
+        InitialChecks {
+            pending,
+            total: domains.len(),
+        }
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            operation: "Running initial checks".to_string(),
+            done: self.total - self.pending.len(),
+            total: self.total,
+        }
+    }
+
+    // The new progress when the event moved it along.
+    pub fn update(&mut self, event: &MonitorEvent) -> Option<Progress> {
+        match event {
+            MonitorEvent::CheckCompleted { domain_id, .. } if self.pending.remove(domain_id) => {}
+            // A domain deleted before its first check won't get one.
+            MonitorEvent::DomainRemoved { domain_id, .. } if self.pending.remove(domain_id) => {
+                self.total -= 1;
+            }
+            _ => return None,
+        }
+        Some(self.progress())
+    }
+}
+
+// A small box with a spinner, the counts and a bar. It is drawn over the
+// current screen without taking its keys, so the UI stays usable meanwhile.
+#[derive(Debug)]
+pub struct ProgressPopup<'a> {
+    progress: &'a Progress,
+    elapsed: Duration,
+}
+
+impl<'a> ProgressPopup<'a> {
+    pub fn new(progress: &'a Progress, elapsed: Duration) -> Self {
+        ProgressPopup { progress, elapsed }
+    }
+}
+
+impl Widget for ProgressPopup<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [area] = Layout::horizontal([Constraint::Length(50)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(3)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let spinner = SPINNER[(self.elapsed.as_millis() / 100) as usize % SPINNER.len()];
+        let block = Block::bordered()
+            .title(Line::from(format!(" {} {} ", spinner, self.progress.operation)).bold())
+            .border_style(Style::default().fg(Color::Yellow));
+        let ratio = if self.progress.total == 0 {
+            1.0
+        } else {
+            (self.progress.done as f64 / self.progress.total as f64).min(1.0)
+        };
+
+        Clear.render(area, buf);
+        Gauge::default()
+            .block(block)
+            .gauge_style(Style::default().fg(Color::Yellow).bg(Color::DarkGray))
+            .ratio(ratio)
+            .label(format!("{}/{}", self.progress.done, self.progress.total))
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::domains::{CheckStatus, DomainStatus};
+
+    fn domain(url: &str, last_check: Option<DateTime<Utc>>) -> MonitoredDomain {
+        let mut domain = MonitoredDomain::new(url);
+        if let Some(timestamp) = last_check {
+            domain.check_history.push(check(timestamp));
+        }
+        domain
+    }
+
+    fn check(timestamp: DateTime<Utc>) -> CheckStatus {
+        CheckStatus {
+            response_time_ms: Some(10),
+            ..CheckStatus::new(timestamp, DomainStatus::Up)
+        }
+    }
+
+    #[test]
+    fn initial_checks_count_each_domain_once() {
+        let started_at = Utc::now();
+        let domains = vec![
+            domain(
+                "https://old.example.com",
+                Some(started_at - chrono::Duration::hours(1)),
+            ),
+            domain("https://fresh.example.com", Some(started_at)),
+            domain("https://new.example.com", None),
+        ];
+        let mut initial_checks = InitialChecks::new(&domains, started_at);
+        assert_eq!(initial_checks.progress().done, 1);
+
+        let completed = |domain: &MonitoredDomain| MonitorEvent::CheckCompleted {
+            domain_id: domain.id,
+            url: domain.url.clone(),
+            check: check(Utc::now()),
+        };
+        let progress = initial_checks.update(&completed(&domains[0]));
+        assert_eq!(progress.map(|p| (p.done, p.total)), Some((2, 3)));
+        // A second check of the same domain doesn't move it along.
+        assert_eq!(initial_checks.update(&completed(&domains[0])), None);
+
+        let progress = initial_checks.update(&MonitorEvent::DomainRemoved {
+            domain_id: domains[2].id,
+            url: domains[2].url.clone(),
+        });
+        assert!(progress.is_some_and(|p| p.is_finished() && p.total == 2));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::logging::{LogHandle, LogLevel};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListItem, ListState, StatefulWidget, Widget},
+};
+
+// Settings that can be changed while upquack runs. They last until it's
+// restarted, the config file has the ones it starts with.
+#[derive(Debug)]
+pub struct SettingsScreen {
+    logs: LogHandle,
+    list_state: ListState,
+}
+
+impl SettingsScreen {
+    pub fn new(logs: LogHandle) -> Self {
+        SettingsScreen {
+            logs,
+            list_state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    pub fn handle_key_event(&mut self, key_event: KeyEvent) -> bool {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => self.list_state.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.list_state.select_next(),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter => self.change_level(true),
+            KeyCode::Left | KeyCode::Char('h') => self.change_level(false),
+            _ => return false,
+        }
+        true
+    }
+
+    fn change_level(&mut self, more_verbose: bool) {
+        let level = self.logs.level();
+        let changed = level.step(more_verbose);
+        if changed != level {
+            self.logs.set_level(changed);
+        }
+    }
+}
+
+impl Widget for &mut SettingsScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let instructions = Line::from(vec![
+            " Esc: Return to Menu - ".into(),
+            "Left/Right: Change - ".into(),
+            "Q: Quit - ".into(),
+            "Up/Down: Navigation ".into(),
+        ]);
+        let main_block = Block::bordered()
+            .title_top(Line::from("Settings").left_aligned())
+            .title_bottom(instructions.centered())
+            .magenta();
+
+        let inner_area = main_block.inner(area);
+        main_block.render(area, buf);
+
+        let [list_area, hint_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner_area);
+
+        let current = self.logs.level();
+        let levels: Vec<String> = LogLevel::ALL
+            .iter()
+            .map(|level| {
+                if *level == current {
+                    format!("[{}]", level.label())
+                } else {
+                    level.label().to_string()
+                }
+            })
+            .collect();
+        let items = vec![ListItem::new(format!("Log level   {}", levels.join(" "))).white()];
+
+        let list = List::new(items)
+            .highlight_style(Style::default().bg(Color::DarkGray))
+            .highlight_symbol("> ");
+        StatefulWidget::render(list, list_area, buf, &mut self.list_state);
+
+        Line::from(
+            " Changes last until upquack restarts, [logging] in the config sets the defaults ",
+        )
+        .dark_gray()
+        .render(hint_area, buf);
+    }
+}
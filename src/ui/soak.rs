@@ -0,0 +1,240 @@
+use crate::app::AppEvent;
+use crate::configuration::Settings;
+use crate::monitor::MonitorHandle;
+use crate::ui::domains::{CheckStatus, DomainStatus, MonitoredDomain};
+use crossterm::event::KeyEvent;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, Block, Paragraph, Sparkline, Widget},
+};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+// Columns in the latency distribution.
+const DISTRIBUTION_BINS: usize = 6;
+
+// A burst of back-to-back checks of one domain, e.g. right after a deploy, to
+// see whether it answers steadily. The results aren't recorded in the history
+// and the monitor's own schedule carries on as usual.
+#[derive(Debug)]
+pub struct SoakScreen {
+    domain: MonitoredDomain,
+    checks: Vec<CheckStatus>,
+    total: usize,
+    spacing: Duration,
+    settings: Settings,
+    task: JoinHandle<()>,
+}
+
+impl SoakScreen {
+    pub fn start(
+        domain: MonitoredDomain,
+        settings: Settings,
+        monitor: &MonitorHandle,
+        event_sender: mpsc::UnboundedSender<AppEvent>,
+    ) -> Self {
+        let total = settings.monitor.soak_checks;
+        let spacing = Duration::from_secs(settings.monitor.soak_spacing_seconds);
+
+        let (monitor, task_domain) = (monitor.clone(), domain.clone());
+        let task = tokio::spawn(async move {
+            for i in 0..total {
+                if i > 0 {
+                    sleep(spacing).await;
+                }
+                let check = monitor.check_now(&task_domain).await;
+                let event = AppEvent::SoakCheckCompleted {
+                    domain_id: task_domain.id,
+                    check,
+                };
+                if event_sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        SoakScreen {
+            domain,
+            checks: Vec::with_capacity(total),
+            total,
+            spacing,
+            settings,
+            task,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.domain.url
+    }
+
+    // Returns false once the domain is gone, so the app can close the screen.
+    pub fn handle_app_event(&mut self, event: &AppEvent) -> bool {
+        match event {
+            AppEvent::SoakCheckCompleted { domain_id, check } if *domain_id == self.domain.id => {
+                self.checks.push(check.clone());
+            }
+            AppEvent::DomainRemoved(domain_id) if *domain_id == self.domain.id => return false,
+            AppEvent::DomainsReloaded(domains) => {
+                return domains.iter().any(|d| d.id == self.domain.id);
+            }
+            _ => {}
+        }
+        true
+    }
+
+    pub fn handle_key_event(&mut self, _key_event: KeyEvent) -> bool {
+        false
+    }
+
+    fn latencies(&self) -> Vec<u64> {
+        self.checks
+            .iter()
+            .filter_map(|check| check.response_time_ms)
+            .collect()
+    }
+}
+
+// Leaving the screen cancels the rest of the burst.
+impl Drop for SoakScreen {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// The value below which `percent` of the sorted latencies fall.
+fn percentile(sorted: &[u64], percent: usize) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    Some(sorted[rank - 1])
+}
+
+// Splits min..=max into equal ranges and counts the latencies in each,
+// returning each range's lower bound with its count.
+fn distribution(sorted: &[u64], bins: usize) -> Vec<(u64, u64)> {
+    let (Some(&min), Some(&max)) = (sorted.first(), sorted.last()) else {
+        return Vec::new();
+    };
+    let width = ((max - min) / bins as u64).max(1);
+    let bins = bins.min((max - min) as usize / width as usize + 1);
+
+    let mut counts = vec![0; bins];
+    for &latency in sorted {
+        let bin = (((latency - min) / width) as usize).min(bins - 1);
+        counts[bin] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| (min + i as u64 * width, count))
+        .collect()
+}
+
+impl Widget for &mut SoakScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let latency = self.domain.latency_settings(self.settings.latency);
+        let locale = &self.settings.display.locale;
+        let format = |ms: Option<u64>| ms.map_or("-".to_string(), |ms| latency.format(ms, locale));
+
+        let mut sorted = self.latencies();
+        sorted.sort_unstable();
+        let up = self
+            .checks
+            .iter()
+            .filter(|check| matches!(check.status, DomainStatus::Up))
+            .count();
+        let running = self.checks.len() < self.total;
+
+        let block = Block::bordered()
+            .title(Line::from(format!(" Soak test: {} ", self.domain.url)).bold())
+            .title_bottom(Line::from(" Esc: Stop and go back ").centered())
+            .magenta();
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        let [summary_area, sparkline_area, distribution_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(4),
+            Constraint::Min(0),
+        ])
+        .areas(inner_area);
+
+        let summary = vec![
+            Line::from(format!(
+                " {} {}/{} checks, {}s apart - {} up, {} not up",
+                if running { "Running" } else { "Finished" },
+                self.checks.len(),
+                self.total,
+                self.spacing.as_secs(),
+                up,
+                self.checks.len() - up
+            )),
+            Line::from(format!(
+                " Latency min {} - p50 {} - p95 {} - max {}",
+                format(sorted.first().copied()),
+                format(percentile(&sorted, 50)),
+                format(percentile(&sorted, 95)),
+                format(sorted.last().copied())
+            )),
+            match self
+                .checks
+                .iter()
+                .rev()
+                .find_map(|c| c.error_message.as_ref())
+            {
+                Some(error) => Line::from(format!(" Last error: {}", error)).red(),
+                None => Line::default(),
+            },
+        ];
+        Paragraph::new(summary).render(summary_area, buf);
+
+        // In the order the checks ran.
+        Sparkline::default()
+            .block(Block::bordered().title(" Response times "))
+            .data(self.latencies())
+            .style(Style::default().fg(Color::Magenta))
+            .render(sparkline_area, buf);
+
+        let bars: Vec<Bar> = distribution(&sorted, DISTRIBUTION_BINS)
+            .into_iter()
+            .map(|(from_ms, count)| {
+                Bar::default()
+                    .value(count)
+                    .label(Line::from(format!("≥{}", latency.format(from_ms, locale))))
+                    .style(Style::default().fg(latency.color(from_ms)))
+            })
+            .collect();
+        BarChart::default()
+            .block(Block::bordered().title(" Latency distribution "))
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(8)
+            .bar_gap(2)
+            .render(distribution_area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_and_distribution() {
+        let sorted = [100, 110, 120, 130, 140, 150, 160, 170, 180, 700];
+        assert_eq!(percentile(&sorted, 50), Some(140));
+        assert_eq!(percentile(&sorted, 95), Some(700));
+        assert_eq!(percentile(&[], 50), None);
+
+        let bins = distribution(&sorted, 6);
+        assert_eq!(bins.len(), 6);
+        assert_eq!(bins[0], (100, 9));
+        assert_eq!(bins[5], (600, 1));
+        // All checks equally fast still make one bar.
+        assert_eq!(distribution(&[80, 80], 6), vec![(80, 2)]);
+    }
+}
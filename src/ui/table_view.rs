@@ -0,0 +1,392 @@
+use crate::ui::domains::{DomainStatus, MonitoredDomain};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// Saved views are switched to with the keys 1 to 9.
+pub const MAX_SAVED_VIEWS: usize = 9;
+
+// The columns of the domain table that can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortColumn {
+    Name,
+    Owner,
+    Status,
+    LastCheck,
+    ResponseTime,
+    Uptime,
+    Interval,
+}
+
+impl SortColumn {
+    const ALL: [SortColumn; 7] = [
+        SortColumn::Name,
+        SortColumn::Owner,
+        SortColumn::Status,
+        SortColumn::LastCheck,
+        SortColumn::ResponseTime,
+        SortColumn::Uptime,
+        SortColumn::Interval,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortColumn::Name => "Name",
+            SortColumn::Owner => "Owner",
+            SortColumn::Status => "Status",
+            SortColumn::LastCheck => "Last Check",
+            SortColumn::ResponseTime => "Response Time",
+            SortColumn::Uptime => "Uptime",
+            SortColumn::Interval => "Interval",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Sort {
+    pub column: SortColumn,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+// The columns of the domain table that can be hidden. The name always shows.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Owner,
+    Status,
+    LastCheck,
+    ResponseTime,
+    // Only shown while a ping check is listed.
+    Loss,
+    Result,
+    Uptime,
+    Interval,
+    Muted,
+}
+
+impl Column {
+    pub const ALL: [Column; 9] = [
+        Column::Owner,
+        Column::Status,
+        Column::LastCheck,
+        Column::ResponseTime,
+        Column::Loss,
+        Column::Result,
+        Column::Uptime,
+        Column::Interval,
+        Column::Muted,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Column::Owner => "Owner",
+            Column::Status => "Status",
+            Column::LastCheck => "Last Check",
+            Column::ResponseTime => "Response Time",
+            Column::Loss => "Loss",
+            Column::Result => "Result",
+            Column::Uptime => "Uptime",
+            Column::Interval => "Interval",
+            Column::Muted => "Muted",
+        }
+    }
+}
+
+// Which domains the table lists, in what order and with which columns.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TableView {
+    // Only this owner's domains are listed when set.
+    pub owner: Option<String>,
+    // The environment switcher's pick, which also scopes the dashboard,
+    // incident overview and notifications. Every environment when unset.
+    pub environment: Option<String>,
+    // The order the domains were added in when unset.
+    pub sort: Option<Sort>,
+    pub hidden_columns: Vec<Column>,
+}
+
+impl TableView {
+    pub fn apply<'a>(
+        &self,
+        domains: &'a [MonitoredDomain],
+        count_rate_limited_as_down: bool,
+    ) -> Vec<&'a MonitoredDomain> {
+        let mut listed: Vec<&MonitoredDomain> = domains
+            .iter()
+            .filter(|d| {
+                self.owner
+                    .as_deref()
+                    .is_none_or(|owner| d.owner.as_deref() == Some(owner))
+                    && in_environment(self.environment.as_deref(), d.environment.as_deref())
+            })
+            .collect();
+        if let Some(sort) = self.sort {
+            listed.sort_by(|a, b| {
+                let ordering = compare(a, b, sort.column, count_rate_limited_as_down);
+                if sort.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+        listed
+    }
+
+    // Steps through the columns ascending, then the same column descending,
+    // and back to the order the domains were added in.
+    pub fn next_sort(&mut self) {
+        self.sort = match self.sort {
+            None => Some(Sort {
+                column: SortColumn::ALL[0],
+                descending: false,
+            }),
+            Some(Sort {
+                column,
+                descending: false,
+            }) => Some(Sort {
+                column,
+                descending: true,
+            }),
+            Some(Sort {
+                column,
+                descending: true,
+            }) => SortColumn::ALL
+                .iter()
+                .skip_while(|c| **c != column)
+                .nth(1)
+                .map(|&column| Sort {
+                    column,
+                    descending: false,
+                }),
+        };
+    }
+
+    pub fn toggle_column(&mut self, column: Column) {
+        match self.hidden_columns.iter().position(|c| *c == column) {
+            Some(index) => {
+                self.hidden_columns.remove(index);
+            }
+            None => self.hidden_columns.push(column),
+        }
+    }
+
+    pub fn shows(&self, column: Column) -> bool {
+        !self.hidden_columns.contains(&column)
+    }
+}
+
+// Whether a monitor in `environment` is in scope of the switcher's pick.
+pub fn in_environment(selected: Option<&str>, environment: Option<&str>) -> bool {
+    selected.is_none_or(|selected| environment == Some(selected))
+}
+
+// The environments in use, for the switcher to step through.
+pub fn known_environments(domains: &[MonitoredDomain]) -> Vec<&str> {
+    let mut environments: Vec<&str> = domains
+        .iter()
+        .filter_map(|d| d.environment.as_deref())
+        .collect();
+    environments.sort();
+    environments.dedup();
+    environments
+}
+
+// Worst first, so sorting by status ascending puts what needs attention on top.
+fn status_rank(domain: &MonitoredDomain) -> u8 {
+    match domain.check_history.last().map(|c| &c.status) {
+        Some(DomainStatus::Error(_)) => 0,
+        Some(DomainStatus::Down) => 1,
+        Some(DomainStatus::Degraded) => 2,
+        Some(DomainStatus::RateLimited) => 3,
+        Some(DomainStatus::Unknown) => 4,
+        Some(DomainStatus::Up) => 5,
+        None => 6,
+    }
+}
+
+fn compare(
+    a: &MonitoredDomain,
+    b: &MonitoredDomain,
+    column: SortColumn,
+    count_rate_limited_as_down: bool,
+) -> Ordering {
+    let last = |d: &MonitoredDomain| d.check_history.last().cloned();
+    match column {
+        SortColumn::Name => a.name().to_lowercase().cmp(&b.name().to_lowercase()),
+        SortColumn::Owner => a.owner.cmp(&b.owner),
+        SortColumn::Status => status_rank(a).cmp(&status_rank(b)),
+        SortColumn::LastCheck => last(a)
+            .map(|c| c.timestamp)
+            .cmp(&last(b).map(|c| c.timestamp)),
+        SortColumn::ResponseTime => last(a)
+            .and_then(|c| c.response_time_ms)
+            .cmp(&last(b).and_then(|c| c.response_time_ms)),
+        SortColumn::Uptime => a
+            .uptime_percentage(count_rate_limited_as_down)
+            .partial_cmp(&b.uptime_percentage(count_rate_limited_as_down))
+            .unwrap_or(Ordering::Equal),
+        SortColumn::Interval => a.interval_seconds.cmp(&b.interval_seconds),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedView {
+    pub name: String,
+    #[serde(flatten)]
+    pub view: TableView,
+}
+
+// What the domain table looked like when upquack was last closed, and the
+// views saved to switch to.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    pub current: TableView,
+    pub views: Vec<SavedView>,
+}
+
+impl UiState {
+    // Replaces the view with the same name, or adds one while there's a key
+    // left for it.
+    pub fn save_view(&mut self, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("A view needs a name".to_string());
+        }
+        let saved = SavedView {
+            name: name.to_string(),
+            view: self.current.clone(),
+        };
+        match self.views.iter().position(|v| v.name == name) {
+            Some(index) => self.views[index] = saved,
+            None if self.views.len() < MAX_SAVED_VIEWS => self.views.push(saved),
+            None => {
+                return Err(format!(
+                    "All {} views are taken, save over one by name",
+                    MAX_SAVED_VIEWS
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Switches to the view saved under key (1 for the first), returning its
+    // name.
+    pub fn switch_to(&mut self, key: usize) -> Option<&str> {
+        let saved = self.views.get(key.checked_sub(1)?)?;
+        self.current = saved.view.clone();
+        Some(&self.views[key - 1].name)
+    }
+}
+
+// Kept next to the store, so every profile has its own.
+pub fn ui_state_path(store_path: &str) -> PathBuf {
+    Path::new(store_path).with_file_name("ui_state.json")
+}
+
+// No file yet means the defaults.
+pub fn load_ui_state(path: &Path) -> io::Result<UiState> {
+    match fs::read_to_string(path) {
+        Ok(json) => Ok(serde_json::from_str(&json)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(UiState::default()),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn save_ui_state(path: &Path, state: &UiState) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(state)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::domains::CheckStatus;
+    use chrono::Utc;
+
+    fn domain(url: &str, owner: Option<&str>, status: DomainStatus) -> MonitoredDomain {
+        let mut domain = MonitoredDomain::new(url);
+        domain.owner = owner.map(str::to_string);
+        domain.check_history.push(CheckStatus {
+            response_time_ms: Some(100),
+            ..CheckStatus::new(Utc::now(), status)
+        });
+        domain
+    }
+
+    #[test]
+    fn views_filter_sort_and_persist() {
+        let mut domains = vec![
+            domain("https://b.example.com", Some("ops"), DomainStatus::Up),
+            domain("https://a.example.com", Some("web"), DomainStatus::Down),
+            domain("https://c.example.com", Some("ops"), DomainStatus::Down),
+        ];
+        domains[2].environment = Some("prod".to_string());
+        assert_eq!(known_environments(&domains), ["prod"]);
+        let urls = |view: &TableView| -> Vec<String> {
+            view.apply(&domains, false)
+                .iter()
+                .map(|d| d.url.to_string())
+                .collect()
+        };
+
+        let mut state = UiState::default();
+        assert_eq!(urls(&state.current).len(), 3);
+        state.current.next_sort();
+        assert_eq!(
+            urls(&state.current),
+            [
+                "https://a.example.com",
+                "https://b.example.com",
+                "https://c.example.com"
+            ]
+        );
+        state.current.next_sort();
+        assert_eq!(urls(&state.current)[0], "https://c.example.com");
+        state.current.next_sort();
+        assert_eq!(state.current.sort.unwrap().column, SortColumn::Owner);
+
+        state.current = TableView {
+            owner: Some("ops".to_string()),
+            environment: None,
+            sort: Some(Sort {
+                column: SortColumn::Status,
+                descending: false,
+            }),
+            hidden_columns: Vec::new(),
+        };
+        state.current.toggle_column(Column::Muted);
+        assert!(!state.current.shows(Column::Muted));
+        assert_eq!(
+            urls(&state.current),
+            ["https://c.example.com", "https://b.example.com"]
+        );
+        state.current.environment = Some("prod".to_string());
+        assert_eq!(urls(&state.current), ["https://c.example.com"]);
+        state.current.environment = None;
+        state.save_view(" ops critical ").unwrap();
+        assert!(state.save_view("").is_err());
+
+        state.current = TableView::default();
+        assert_eq!(state.switch_to(1), Some("ops critical"));
+        assert_eq!(state.current.owner.as_deref(), Some("ops"));
+        assert_eq!(state.switch_to(2), None);
+        assert_eq!(state.switch_to(0), None);
+
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let store_path = temp_dir.path().join("domains.json");
+        let path = ui_state_path(store_path.to_str().unwrap());
+        assert_eq!(load_ui_state(&path).unwrap(), UiState::default());
+        save_ui_state(&path, &state).unwrap();
+        assert_eq!(load_ui_state(&path).unwrap(), state);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::ui::domains::MonitoredDomain;
+
+// How many suggestions the tag editor lists under the input.
+pub const MAX_SUGGESTIONS: usize = 5;
+
+// The tags typed into the editor, separated by spaces or commas, in the
+// order typed with repeats dropped.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split([' ', ',']).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+// Every tag in use, sorted, to complete from.
+pub fn known_tags(domains: &[MonitoredDomain]) -> Vec<String> {
+    let mut tags: Vec<String> = domains.iter().flat_map(|d| d.tags.clone()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+// The word being typed at the end of the input, empty after a separator.
+fn partial(input: &str) -> &str {
+    input.rsplit([' ', ',']).next().unwrap_or_default()
+}
+
+// The known tags starting with the word being typed (ignoring case) that
+// aren't entered yet.
+pub fn suggestions<'a>(input: &str, known: &'a [String]) -> Vec<&'a str> {
+    let typed = partial(input);
+    let entered = parse_tags(&input[..input.len() - typed.len()]);
+    // Lowercasing can change the length, e.g. İ, so it's only compared.
+    let typed = typed.to_lowercase();
+    known
+        .iter()
+        .filter(|tag| tag.to_lowercase().starts_with(&typed) && !entered.contains(tag))
+        .map(String::as_str)
+        .collect()
+}
+
+// The input with the word being typed replaced by the first suggestion,
+// ready for the next tag. None when nothing matches.
+pub fn complete(input: &str, known: &[String]) -> Option<String> {
+    let tag = *suggestions(input, known).first()?;
+    let typed = &input[..input.len() - partial(input).len()];
+    Some(format!("{}{} ", typed, tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_completes_tags() {
+        assert_eq!(parse_tags(" prod,eu  api prod,"), ["prod", "eu", "api"]);
+
+        let mut api = MonitoredDomain::new("https://api.example.com");
+        api.tags = vec!["prod".to_string(), "payments".to_string()];
+        let mut web = MonitoredDomain::new("https://www.example.com");
+        web.tags = vec!["prod".to_string(), "eu".to_string()];
+        let known = known_tags(&[api, web]);
+        assert_eq!(known, ["eu", "payments", "prod"]);
+
+        assert_eq!(suggestions("P", &known), ["payments", "prod"]);
+        assert_eq!(suggestions("prod p", &known), ["payments"]);
+        assert_eq!(suggestions("eu ", &known), ["payments", "prod"]);
+        assert_eq!(complete("eu, pa", &known).as_deref(), Some("eu, payments "));
+        assert_eq!(complete("staging", &known), None);
+        assert!(suggestions("İ", &known).is_empty());
+        assert_eq!(suggestions("İ ", &known), ["eu", "payments", "prod"]);
+        assert_eq!(suggestions("prod İ p", &known), ["payments"]);
+    }
+}
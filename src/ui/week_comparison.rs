@@ -0,0 +1,187 @@
+use crate::ui::domains::{MonitoredDomain, PeriodStats};
+use crate::ui::latency::LatencySettings;
+use crate::ui::locale::Locale;
+use chrono::{DateTime, Duration, Utc};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Widget},
+};
+
+// The last 7 UTC days (today included) next to the 7 before, so a release
+// that made a domain flakier or slower stands out. Read from the daily
+// rollups, the history doesn't reach that far back.
+#[derive(Debug)]
+pub struct WeekComparison {
+    this_week: PeriodStats,
+    previous_week: PeriodStats,
+    latency: LatencySettings,
+    locale: Locale,
+}
+
+impl WeekComparison {
+    pub fn new(
+        domain: &MonitoredDomain,
+        now: DateTime<Utc>,
+        count_rate_limited_as_down: bool,
+        latency: LatencySettings,
+        locale: Locale,
+    ) -> Self {
+        let today = now.date_naive();
+        let week_ago = today - Duration::days(7);
+        WeekComparison {
+            this_week: domain.daily_stats(
+                week_ago + Duration::days(1),
+                today,
+                count_rate_limited_as_down,
+            ),
+            previous_week: domain.daily_stats(
+                week_ago - Duration::days(6),
+                week_ago,
+                count_rate_limited_as_down,
+            ),
+            latency,
+            locale,
+        }
+    }
+
+    pub fn height() -> u16 {
+        3
+    }
+
+    fn uptime(&self, stats: PeriodStats) -> String {
+        stats
+            .uptime_percentage
+            .map_or("N/A".to_string(), |pct| self.locale.percentage(pct))
+    }
+
+    fn average_latency(&self, stats: PeriodStats) -> String {
+        stats
+            .average_response_time_ms
+            .map_or("N/A".to_string(), |ms| {
+                self.latency.format(ms.round() as u64, &self.locale)
+            })
+    }
+
+    // Uptime going down or latency going up is bad, so red.
+    fn deltas(&self) -> Vec<Span<'static>> {
+        let mut spans = Vec::new();
+        let color = |worse: bool| if worse { Color::Red } else { Color::Green };
+
+        match (
+            self.this_week.uptime_percentage,
+            self.previous_week.uptime_percentage,
+        ) {
+            (Some(now), Some(before)) => {
+                let delta = now - before;
+                spans.push(Span::styled(
+                    format!("{}{}pp", sign(delta), self.locale.decimal(delta.abs(), 1)),
+                    Style::default().fg(color(delta < -0.05)),
+                ));
+            }
+            _ => spans.push("N/A".into()),
+        }
+        spans.push(" uptime, ".into());
+
+        match (
+            self.this_week.average_response_time_ms,
+            self.previous_week.average_response_time_ms,
+        ) {
+            (Some(now), Some(before)) => {
+                let delta = now - before;
+                spans.push(Span::styled(
+                    format!(
+                        "{}{}",
+                        sign(delta),
+                        self.latency
+                            .format(delta.abs().round() as u64, &self.locale)
+                    ),
+                    Style::default().fg(color(delta > 0.5)),
+                ));
+            }
+            _ => spans.push("N/A".into()),
+        }
+        spans.push(" latency".into());
+        spans
+    }
+}
+
+fn sign(delta: f64) -> &'static str {
+    if delta < 0.0 { "-" } else { "+" }
+}
+
+impl Widget for WeekComparison {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut spans = vec![
+            " This week ".bold(),
+            format!(
+                "{} up, {} avg",
+                self.uptime(self.this_week),
+                self.average_latency(self.this_week)
+            )
+            .into(),
+            "  Previous week ".bold(),
+            format!(
+                "{} up, {} avg",
+                self.uptime(self.previous_week),
+                self.average_latency(self.previous_week)
+            )
+            .into(),
+            "  Change ".bold(),
+        ];
+        spans.extend(self.deltas());
+
+        Paragraph::new(Line::from(spans))
+            .block(
+                Block::bordered()
+                    .title(" Week over week ")
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            )
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::domains::{CheckStatus, DomainStatus};
+    use chrono::TimeZone;
+
+    #[test]
+    fn weeks_are_compared_separately() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 20, 12, 0, 0).unwrap();
+        let mut domain = MonitoredDomain::new("https://example.com");
+        let mut check = |days_ago: i64, status: DomainStatus, response_time_ms: u64| {
+            domain.push_check(CheckStatus {
+                response_time_ms: Some(response_time_ms),
+                ..CheckStatus::new(now - Duration::days(days_ago), status)
+            });
+        };
+        check(10, DomainStatus::Up, 100);
+        check(9, DomainStatus::Up, 100);
+        check(2, DomainStatus::Up, 300);
+        check(0, DomainStatus::Down, 500);
+        // Only the last checks stay in the history.
+        domain.check_history.drain(0..3);
+
+        let comparison = WeekComparison::new(
+            &domain,
+            now,
+            false,
+            LatencySettings::default(),
+            Locale::default(),
+        );
+        assert_eq!(comparison.previous_week.uptime_percentage, Some(100.0));
+        assert_eq!(comparison.this_week.uptime_percentage, Some(50.0));
+        assert_eq!(comparison.this_week.average_response_time_ms, Some(400.0));
+
+        let deltas: String = comparison
+            .deltas()
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(deltas, "-50.0pp uptime, +300ms latency");
+    }
+}
@@ -1,3 +1,5 @@
+use std::io;
+use std::process::{Command, Stdio};
 use url::Url;
 
 pub fn is_valid_url(url_str: &str) -> bool {
@@ -21,9 +23,50 @@ pub fn is_valid_url(url_str: &str) -> bool {
     }
 }
 
+// Whether the value is something open_link can hand to a browser. Links
+// come from the API and imports, so anything a shell could read as more
+// than one argument or another command (& or | for cmd) is refused.
+pub fn is_link(value: &str) -> bool {
+    (value.starts_with("https://") || value.starts_with("http://"))
+        && !value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || "&|<>^\"%`".contains(c))
+}
+
+// Opens the link with the desktop's default handler, a browser for http(s),
+// without waiting for it.
+pub fn open_link(link: &str) -> io::Result<()> {
+    if !is_link(link) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Not a link that can be opened: {}", link),
+        ));
+    }
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        // Hands the link to the default browser without cmd parsing it.
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    let mut child = command
+        .arg(link)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reaped in the background so it doesn't linger as a zombie.
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn parse_invalid_url() {
@@ -37,7 +80,7 @@ mod tests {
         ];
 
         for url in invalid_url {
-            assert_eq!(is_valid_url(url), false, "Expected false for: {}", url);
+            assert!(!is_valid_url(url), "Expected false for: {}", url);
         }
     }
 
@@ -53,7 +96,55 @@ mod tests {
         ];
 
         for url in valid_url {
-            assert_eq!(is_valid_url(url), true, "Expected true for: {}", url);
+            assert!(is_valid_url(url), "Expected true for: {}", url);
+        }
+    }
+
+    #[test]
+    fn links_cant_smuggle_commands() {
+        assert!(is_link("https://grafana.example.com/d/abc?orgId=1"));
+        assert!(!is_link("https://x/?a=1&calc"));
+        assert!(!is_link("https://x/ | calc"));
+        assert!(!is_link("file:///etc/passwd"));
+        assert!(open_link("https://x/?a=1&calc").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn any_input_is_answered(input in "\\PC*") {
+            is_valid_url(&input);
+        }
+
+        #[test]
+        fn http_urls_with_a_known_tld_are_valid(
+            scheme in "https?",
+            subdomain in "([a-z0-9]{1,10}\\.)?",
+            // "xn--" labels are punycode and have to decode.
+            name in "[a-z0-9]([a-z0-9-]{0,20}[a-z0-9])?".prop_filter("not punycode", |n| !n.starts_with("xn--")),
+            tld in prop::sample::select(vec!["com", "org", "net", "io", "co", "gov", "edu", "dev"]),
+            path in "(/[a-zA-Z0-9_-]{0,10}){0,3}",
+        ) {
+            let url = format!("{}://{}{}.{}{}", scheme, subdomain, name, tld, path);
+            prop_assert!(is_valid_url(&url), "Expected true for: {}", url);
+        }
+
+        #[test]
+        fn other_schemes_are_invalid(
+            scheme in "[a-z]{2,8}".prop_filter("not http", |s| s != "http" && s != "https"),
+            name in "[a-z0-9]{1,20}",
+        ) {
+            let url = format!("{}://{}.com", scheme, name);
+            prop_assert!(!is_valid_url(&url), "Expected false for: {}", url);
+        }
+
+        #[test]
+        fn unknown_tlds_are_invalid(
+            scheme in "https?",
+            name in "[a-z0-9]{1,20}",
+            tld in prop::sample::select(vec!["xyz", "local", "internal", "test"]),
+        ) {
+            let url = format!("{}://{}.{}", scheme, name, tld);
+            prop_assert!(!is_valid_url(&url), "Expected false for: {}", url);
         }
     }
 }
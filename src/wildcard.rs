@@ -0,0 +1,189 @@
+use std::{fs, io, path::Path};
+
+// Record types whose names are hosts worth monitoring.
+const HOST_RECORD_TYPES: [&str; 3] = ["A", "AAAA", "CNAME"];
+
+// A URL with a "*" as the first label of its host, e.g.
+// https://*.example.com/health, standing for one monitor per host name
+// filled in. The monitors it expands into share a template and are managed
+// as a group, named after the pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WildcardUrl {
+    // Everything up to the "*".
+    prefix: String,
+    // The domain the names go under, e.g. example.com.
+    base: String,
+    // The rest of the URL after the base domain, e.g. /health.
+    suffix: String,
+}
+
+impl WildcardUrl {
+    // None for URLs without the "*.", which are added as they are.
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = url.trim();
+        let host_start = url.find("://")? + 3;
+        let rest = url[host_start..].strip_prefix("*.")?;
+        let host_end = rest.find(['/', ':', '?', '#']).unwrap_or(rest.len());
+        let base = &rest[..host_end];
+        if base.is_empty() || base.contains('*') {
+            return None;
+        }
+
+        Some(WildcardUrl {
+            prefix: url[..host_start].to_string(),
+            base: base.to_lowercase(),
+            suffix: rest[host_end..].to_string(),
+        })
+    }
+
+    pub fn pattern(&self) -> String {
+        format!("{}*.{}{}", self.prefix, self.base, self.suffix)
+    }
+
+    // One URL per name, in the order given with duplicates dropped. Names
+    // are single labels like "api" or full host names under the base domain.
+    pub fn expand<S: AsRef<str>>(&self, names: &[S]) -> Result<Vec<String>, String> {
+        let mut urls: Vec<String> = Vec::new();
+        for name in names {
+            let name = name.as_ref().trim().trim_end_matches('.').to_lowercase();
+            let label = name
+                .strip_suffix(&format!(".{}", self.base))
+                .unwrap_or(&name);
+            let valid = !label.is_empty()
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                && !label.starts_with('-')
+                && !label.ends_with('-');
+            if !valid {
+                return Err(format!(
+                    "\"{}\" isn't a host name under {}",
+                    name, self.base
+                ));
+            }
+
+            let url = format!("{}{}.{}{}", self.prefix, label, self.base, self.suffix);
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        if urls.is_empty() {
+            return Err(format!("No host names to fill into {}", self.pattern()));
+        }
+        Ok(urls)
+    }
+
+    // The names of the host records directly under the base domain in a zone
+    // file export (BIND format), e.g. www for "www 300 IN A 192.0.2.1".
+    pub fn zone_names(&self, zone: &str) -> Vec<String> {
+        let mut origin: Option<String> = None;
+        let mut owner: Option<String> = None;
+        let mut names: Vec<String> = Vec::new();
+
+        for line in zone.lines() {
+            let line = line.split(';').next().unwrap_or_default();
+            let mut tokens = line.split_whitespace();
+            if line.starts_with('$') {
+                if tokens.next() == Some("$ORIGIN") {
+                    origin = tokens
+                        .next()
+                        .map(|o| o.trim_end_matches('.').to_lowercase());
+                }
+                continue;
+            }
+            // A record without a name belongs to the previous one's.
+            if !line.starts_with(char::is_whitespace) {
+                owner = tokens.next().map(|name| match (name, &origin) {
+                    ("@", Some(origin)) => origin.clone(),
+                    (name, _) if name.ends_with('.') => name.trim_end_matches('.').to_lowercase(),
+                    (name, Some(origin)) => format!("{}.{}", name, origin).to_lowercase(),
+                    (name, None) => name.to_lowercase(),
+                });
+            }
+            // The TTL and class may come before the type.
+            let is_host_record = tokens
+                .take(3)
+                .any(|token| HOST_RECORD_TYPES.contains(&token.to_uppercase().as_str()));
+            let label = owner
+                .as_deref()
+                .and_then(|owner| owner.strip_suffix(&format!(".{}", self.base)));
+            if let Some(label) = label
+                && is_host_record
+                && !label.contains(['.', '*'])
+                && !names.iter().any(|name| name == label)
+            {
+                names.push(label.to_string());
+            }
+        }
+        names
+    }
+
+    pub fn zone_file_names(&self, path: &Path) -> io::Result<Vec<String>> {
+        Ok(self.zone_names(&fs::read_to_string(path)?))
+    }
+}
+
+// What the add popup takes for a wildcard URL: the names after it, or
+// zone:<file> to take them from a zone file export, e.g.
+// "https://*.example.com/health api www" or
+// "https://*.example.com/health zone:example.com.zone".
+pub fn expand_input(wildcard: &WildcardUrl, sources: &[&str]) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    for source in sources {
+        match source.strip_prefix("zone:") {
+            Some(path) => names.extend(
+                wildcard
+                    .zone_file_names(Path::new(path))
+                    .map_err(|e| format!("Failed to read {}: {}", path, e))?,
+            ),
+            None => names.extend(source.split(',').map(str::to_string)),
+        }
+    }
+    wildcard.expand(&names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_names_from_a_list_or_zone_file() {
+        assert_eq!(WildcardUrl::parse("https://example.com/health"), None);
+        let wildcard = WildcardUrl::parse("https://*.Example.com:8443/health").unwrap();
+        assert_eq!(wildcard.pattern(), "https://*.example.com:8443/health");
+
+        assert_eq!(
+            wildcard
+                .expand(&["api", "WWW.example.com.", "api"])
+                .unwrap(),
+            vec![
+                "https://api.example.com:8443/health",
+                "https://www.example.com:8443/health",
+            ]
+        );
+        assert!(wildcard.expand(&["a b"]).is_err());
+        assert!(wildcard.expand::<&str>(&[]).is_err());
+
+        let zone = "\
+$ORIGIN example.com.
+$TTL 3600
+@       IN SOA ns1.example.com. admin.example.com. (
+            2025010101 ; serial
+            3600 )
+@           IN A     192.0.2.1
+api     300 IN A     192.0.2.2
+            IN AAAA  2001:db8::2
+www         CNAME    example.com.
+shop.example.com. IN CNAME shops.example.net.
+mail        IN MX    10 mx.example.com.
+*           IN A     192.0.2.3
+a.b         IN A     192.0.2.4
+; old IN A 192.0.2.5
+";
+        assert_eq!(wildcard.zone_names(zone), vec!["api", "www", "shop"]);
+
+        assert_eq!(
+            expand_input(&wildcard, &["api,www", "shop"]).unwrap().len(),
+            3
+        );
+        assert!(expand_input(&wildcard, &["zone:/nonexistent.zone"]).is_err());
+    }
+}